@@ -1,21 +1,34 @@
 use std::{
     fs::{self, File},
-    collections::{HashMap, HashSet},
+    io::BufWriter,
+    collections::{BTreeMap, HashMap, HashSet},
     path::PathBuf,
+    thread,
     time::Duration,
 };
 use benchmark::{
-    fmt_open_err, path_str, get_algorithms,
-    schema::*, datafile,
+    fmt_open_err, path_str, get_algorithms, resolve_algorithm_sets,
+    schema::{self, *}, datafile::{self, DatafileSet}, registry, migrate,
+    environment::{self, FrequencyMonitor}, export, compare, verify, roofline, process,
+    placement::{self, Arena},
     timer::{
-        Timer,
-        harness::Harness,
+        self, Timer,
+        harness::{Harness, PreparedCache},
+        latency::Histogram,
         perf::PerfCounters,
+        concurrent::ConcurrentConfig,
     },
 };
-use clap::Parser;
+use setops::intersect::{
+    self, prefetch::DEFAULT_PREFETCH_DISTANCE, fesia::FesiaStats, DEFAULT_GALLOP_FACTOR,
+};
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
 
+#[cfg(all(feature = "placement", target_os = "linux"))]
+#[global_allocator]
+static ALLOCATOR: placement::PlacementAllocator = placement::PlacementAllocator;
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
@@ -25,12 +38,99 @@ struct Cli {
     datasets: PathBuf,
     #[arg(default_value = "results.json", long)]
     out: PathBuf,
+    /// Also write the results as tidy CSV (see `export`).
+    #[arg(long)]
+    csv: Option<PathBuf>,
     // Ignore --bench provided by cargo.
     #[arg(long, action)]
     bench: bool,
     #[arg(long, action)]
     count_only: bool,
+    /// Visitor receiving the result of experiments not setting their own
+    /// `output`.
+    #[arg(default_value = "unsafe", long, value_enum)]
+    output: OutputMode,
+    /// Record the distribution of per-iteration times for each result.
+    #[arg(long, value_enum)]
+    latency: Option<LatencyOutput>,
+    /// Number of individually timed iterations per datafile with --latency.
+    #[arg(default_value_t = 100, long)]
+    latency_iterations: usize,
+    /// Measure the throughput of this many threads running queries at once
+    /// on shared prepared sets, rather than timing each datafile alone.
+    #[arg(long)]
+    query_threads: Option<usize>,
+    /// With --query-threads, probe the larger set of a point's first datafile
+    /// with the smaller set of every datafile, this many queries at a time,
+    /// for algorithms with batch probes.
+    #[arg(long, requires = "query_threads")]
+    query_batch: Option<usize>,
+    /// Duration of each concurrent pass with --query-threads.
+    #[arg(default_value_t = 1000, long)]
+    query_duration_ms: u64,
+    /// Record the segment sizes and bitmap density of the sets each FESIA
+    /// algorithm builds, per point.
+    #[arg(long, action)]
+    fesia_stats: bool,
+    /// Build the FESIA, BSR, Roaring and other prepared representations of
+    /// each set once per point, sharing them between the algorithms and
+    /// passes which prepare sets alike. Holds them for the whole point.
+    #[arg(long, action)]
+    share_prepared: bool,
+    /// Record a hash of each algorithm's result per datafile, to check with
+    /// `verify` that another machine computed the same results.
+    #[arg(long, action)]
+    hash_outputs: bool,
+    /// Choose algorithm lists for this microarchitecture rather than the
+    /// detected one.
+    #[arg(long)]
+    microarch: Option<String>,
     experiments: Vec<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compare the times of two results files cell by cell, e.g. before and
+    /// after a kernel change, reporting speedups with 95% confidence
+    /// intervals.
+    Compare {
+        baseline: PathBuf,
+        candidate: PathBuf,
+        /// Smallest change flagged as faster or slower (out of 1000).
+        #[arg(default_value_t = 20, long)]
+        threshold: u32,
+        /// Fail if any cell is significantly slower.
+        #[arg(long, action)]
+        fail_on_regression: bool,
+    },
+    /// Check that two results files run with --hash-outputs, e.g. from
+    /// machines with different ISAs, agree on the result of each cell.
+    Verify {
+        baseline: PathBuf,
+        candidate: PathBuf,
+    },
+    /// Summarise a results file for plotting: for each experiment, write
+    /// `<out>/<experiment>/<algorithm>.csv` with the mean and 95% confidence
+    /// interval of each point's time and counters.
+    Process {
+        results: PathBuf,
+        #[arg(default_value = "processed", long)]
+        out: PathBuf,
+        /// Also write `<algorithm>_strata.csv`, summarising the queries of
+        /// real datasets per size ratio and selectivity.
+        #[arg(long, action)]
+        stratify: bool,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LatencyOutput {
+    /// Store percentiles and a histogram of iteration times.
+    Histogram,
+    /// Additionally store every iteration time.
+    Samples,
 }
 
 fn main() {
@@ -40,9 +140,17 @@ fn main() {
         println!("{}", "warning: running in debug mode".yellow().bold());
     }
 
-    if let Err(e) = bench_from_files(&cli) {
+    let result = match &cli.command {
+        Some(Command::Compare { baseline, candidate, threshold, fail_on_regression }) =>
+            compare_files(baseline, candidate, *threshold, *fail_on_regression),
+        Some(Command::Verify { baseline, candidate }) => verify_files(baseline, candidate),
+        Some(Command::Process { results, out, stratify }) => process_file(results, out, *stratify),
+        None => bench_from_files(&cli),
+    };
+    if let Err(e) = result {
         let msg = format!("error: {}", e);
         println!("{}", msg.red().bold());
+        std::process::exit(1);
     }
 }
 
@@ -50,41 +158,73 @@ fn bench_from_files(cli: &Cli) -> Result<(), String> {
     let experiment_toml = fs::read_to_string(&cli.experiment)
         .map_err(|e| fmt_open_err(e, &cli.experiment))?;
 
-    let experiment: Experiment = toml::from_str(&experiment_toml)
+    let experiment: Experiment = migrate::parse_experiment(&experiment_toml)
         .map_err(|e| format!(
             "invalid toml file {}: {}",
             path_str(&cli.experiment), e
         ))?;
 
-    let dataset_algos = gen_dataset_to_algos_map(cli, &experiment)?;
+    let warnings =
+        registry::validate_experiment(&experiment, &cli.experiments, default_output(cli))?;
+    for warning in warnings {
+        println!("{}", format!("warning: {warning}").yellow());
+    }
+
+    let microarch = cli.microarch.clone()
+        .or_else(|| environment::microarch().map(String::from));
+    println!("microarchitecture: {}", microarch.as_deref().unwrap_or("unknown"));
+
+    let algorithm_sets = select_algorithm_sets(cli, &experiment, microarch.as_deref())?;
+
+    let mut dataset_algos = gen_dataset_to_algos_map(cli, &experiment, &algorithm_sets)?;
+
+    let unsupported = remove_unsupported(&mut dataset_algos, experiment.on_unsupported)?;
         
     if dataset_algos.len() == 0 {
         return Err("no algorithm matches found".to_string());
     }
 
-    let results = run_experiments(cli, experiment, dataset_algos)?;
+    let results = run_experiments(
+        cli, experiment, algorithm_sets, microarch, dataset_algos, unsupported)?;
     
-    write_results(results, &cli.out)?;
+    write_results(&results, &cli.out)?;
+    if let Some(csv) = &cli.csv {
+        write_csv(&results, csv)?;
+    }
 
     Ok(())
 }
 
 type AlgorithmSet = HashSet<String>;
-/// Map each dataset to algorithms which need to be run on it.
+/// A dataset, and the output of experiments setting their own.
+type RunKey = (DatasetId, Option<OutputMode>);
+
+/// Output of experiments not setting their own.
+fn default_output(cli: &Cli) -> OutputMode {
+    if cli.count_only { OutputMode::Count } else { cli.output }
+}
+
+/// Map each dataset (and output) to algorithms which need to be run on it.
 /// This saves us from running multiple dataset/algorithm pairs twice
 /// if present in multiple experiments.
-fn gen_dataset_to_algos_map(cli: &Cli, experiment: &Experiment)
-    -> Result<HashMap<DatasetId, AlgorithmSet>, String>
+fn gen_dataset_to_algos_map(
+    cli: &Cli,
+    experiment: &Experiment,
+    algorithm_sets: &HashMap<String, AlgorithmVec>)
+    -> Result<HashMap<RunKey, AlgorithmSet>, String>
 {
-    let mut dataset_algos: HashMap<String, AlgorithmSet> = HashMap::new();
+    let mut dataset_algos: HashMap<RunKey, AlgorithmSet> = HashMap::new();
     for e in &experiment.experiment {
+        // Skipped with a warning when validating.
+        if e.output.is_some_and(|o| !o.built()) {
+            continue;
+        }
         if cli.experiments.len() == 0 || cli.experiments.contains(&e.name) {
 
-            let algorithms =
-                get_algorithms(&experiment.algorithm_sets, &e.algorithms)?;
+            let algorithms = get_algorithms(algorithm_sets, &e.algorithms)?;
 
             dataset_algos
-                .entry(e.dataset.clone())
+                .entry((e.dataset.clone(), e.output))
                 .or_default()
                 .extend(algorithms.clone());
         }
@@ -92,10 +232,77 @@ fn gen_dataset_to_algos_map(cli: &Cli, experiment: &Experiment)
     Ok(dataset_algos)
 }
 
+/// Choose the list of each algorithm set used by the selected experiments for
+/// `microarch`. Unused sets are left out so that they need not have a list for
+/// this machine.
+fn select_algorithm_sets(cli: &Cli, experiment: &Experiment, microarch: Option<&str>)
+    -> Result<HashMap<String, AlgorithmVec>, String>
+{
+    let used: HashMap<String, schema::AlgorithmSet> = experiment.experiment.iter()
+        .filter(|e| cli.experiments.is_empty() || cli.experiments.contains(&e.name))
+        .filter_map(|e| match &e.algorithms {
+            Algorithms::AlgorithmSet(id) => experiment.algorithm_sets.get(id)
+                .map(|set| (id.clone(), set.clone())),
+            Algorithms::Algorithms(_) | Algorithms::Pipeline(_) => None,
+        })
+        .collect();
+
+    resolve_algorithm_sets(&used, microarch)
+}
+
+/// Find algorithms requiring CPU features this machine or build lacks. These
+/// are either removed from `dataset_algos` and returned, or reported as an
+/// error, depending on `policy`.
+fn remove_unsupported(
+    dataset_algos: &mut HashMap<RunKey, AlgorithmSet>,
+    policy: OnUnsupported)
+    -> Result<HashMap<AlgorithmId, Vec<String>>, String>
+{
+    let mut unsupported: HashMap<AlgorithmId, Vec<String>> = HashMap::new();
+    for algorithm in dataset_algos.values().flatten() {
+        let Some(caps) = registry::lookup(algorithm) else {
+            continue;
+        };
+        let missing = registry::missing_features(caps.features);
+        if !missing.is_empty() {
+            unsupported.insert(
+                algorithm.clone(),
+                missing.iter().map(|f| f.to_string()).collect());
+        }
+    }
+
+    if unsupported.is_empty() {
+        return Ok(unsupported);
+    }
+
+    let mut names: Vec<_> = unsupported.iter()
+        .map(|(name, missing)| format!("{name} (requires {})", missing.join(", ")))
+        .collect();
+    names.sort();
+
+    match policy {
+        OnUnsupported::Fail => Err(format!(
+            "algorithms unsupported on this machine:\n  {}", names.join("\n  "))),
+        OnUnsupported::Skip => {
+            for name in names {
+                println!("{}", format!("warning: skipping {name}").yellow());
+            }
+            for algos in dataset_algos.values_mut() {
+                algos.retain(|a| !unsupported.contains_key(a));
+            }
+            dataset_algos.retain(|_, algos| !algos.is_empty());
+            Ok(unsupported)
+        },
+    }
+}
+
 fn run_experiments(
     cli: &Cli,
     experiment: Experiment,
-    dataset_algos: HashMap<DatasetId, AlgorithmSet>)
+    algorithm_sets: HashMap<String, AlgorithmVec>,
+    microarch: Option<String>,
+    dataset_algos: HashMap<RunKey, AlgorithmSet>,
+    unsupported: HashMap<AlgorithmId, Vec<String>>)
     -> Result<Results, String>
 {
     let mut results =
@@ -104,15 +311,92 @@ fn run_experiments(
     let mut counters = PerfCounters::new();
     counters.summarise();
 
+    let config = &experiment.environment;
+    if let Some(cpu) = config.pin_cpu {
+        environment::pin_to_cpu(cpu)?;
+    }
+    let cpu = config.pin_cpu.unwrap_or_else(environment::current_cpu);
+    if cli.query_threads.is_some() {
+        if config.pin_cpu.is_some() {
+            println!("{}", "warning: query threads all run on the pinned cpu".yellow());
+        }
+        if config.places_sets() {
+            println!("{}", "warning: sets are not placed with --query-threads".yellow());
+        }
+    }
+
+    let mut env_results = EnvironmentResults {
+        config: config.clone(),
+        microarch,
+        start: environment::cpu_state(cpu),
+        ..Default::default()
+    };
+    print_cpu_state(&env_results.start);
+    if config.places_sets() {
+        // Check placement works before starting the sweep.
+        let backing = Arena::new(placement::HUGE_PAGE, config.huge_pages, config.numa_node)?
+            .backing();
+        if backing == placement::Backing::Transparent {
+            println!("{}", "warning: no huge pages reserved, using transparent huge pages"
+                .yellow());
+        }
+        env_results.backing = Some(backing);
+    }
+    if config.spin_baseline {
+        record_spin_baseline(&mut env_results);
+    }
+    let peak_bandwidth = config.roofline.then(|| match config.peak_bandwidth {
+        Some(peak) => (peak, false),
+        None => {
+            let peak = roofline::probe_bandwidth();
+            println!("peak read bandwidth: {peak} MB/s (measured)");
+            (peak, true)
+        },
+    });
+
+    // Highest mean frequency of a cell so far, with `frequency_monitor`.
+    let mut frequency_peak = 0;
+
     for dataset in &experiment.dataset {
-        if let Some(algos) = dataset_algos.get(&dataset.name) {
-            let dataset_results = DatasetResults{
-                info: dataset.clone(),
-                algos: run_dataset_benchmarks(cli, &dataset, algos, &mut counters)?,
-            };
-            results.insert(dataset.name.clone(), dataset_results);
+        let mut outputs: Vec<Option<OutputMode>> = dataset_algos.keys()
+            .filter(|(name, _)| *name == dataset.name)
+            .map(|&(_, output)| output)
+            .collect();
+        if outputs.is_empty() {
+            continue;
+        }
+        outputs.sort();
+
+        let mut dataset_results = DatasetResults {
+            info: dataset.clone(),
+            algos: AlgorithmResults::new(),
+            outputs: HashMap::new(),
+            fesia_stats: HashMap::new(),
+        };
+        for output in outputs {
+            let algos = &dataset_algos[&(dataset.name.clone(), output)];
+            let (runs, fesia_stats) = run_dataset_benchmarks(
+                cli, config, &dataset, algos, output, &mut counters, &mut frequency_peak)?;
+            // Stats do not depend on the output.
+            dataset_results.fesia_stats.extend(fesia_stats);
+            match output {
+                Some(output) => { dataset_results.outputs.insert(output, runs); },
+                None => dataset_results.algos = runs,
+            }
+        }
+        results.insert(dataset.name.clone(), dataset_results);
+
+        if config.spin_baseline {
+            record_spin_baseline(&mut env_results);
         }
     }
+    env_results.end = environment::cpu_state(cpu);
+
+    let roofline = peak_bandwidth.map(|(peak, measured)| {
+        let summary = roofline::summarise(&results, peak, measured);
+        print_roofline(&summary);
+        summary
+    });
 
     let experiments = if cli.experiments.len() > 0 {
         experiment.experiment
@@ -124,49 +408,163 @@ fn run_experiments(
     };
 
     Ok(Results{
+        version: SCHEMA_VERSION,
         experiments: experiments,
         datasets: results,
-        algorithm_sets: experiment.algorithm_sets,
+        algorithm_sets,
+        unsupported,
+        environment: env_results,
+        output: Some(default_output(cli)),
+        roofline,
     })
 }
 
+fn print_roofline(summary: &roofline::Roofline) {
+    println!("{}", format!("roofline (peak {} MB/s)", summary.peak_bandwidth).bold());
+    for algorithm in &summary.algorithms {
+        let memory_bound = algorithm.points.iter()
+            .filter(|p| p.bound == roofline::Bound::Memory)
+            .count();
+        println!("  {:<16} {:<24} max {:5.1}% of peak, {}/{} points memory bound",
+            algorithm.dataset, algorithm.algorithm,
+            algorithm.max_of_peak as f64 / PERCENT_F * 100.0,
+            memory_bound, algorithm.points.len());
+    }
+}
+
+fn print_cpu_state(state: &CpuState) {
+    let unknown = || "unknown".to_string();
+    println!("cpu {}: governor {}, frequency {} kHz, turbo {}",
+        state.cpu,
+        state.governor.clone().unwrap_or_else(unknown),
+        state.frequency_khz.map_or_else(unknown, |f| f.to_string()),
+        state.turbo.map_or_else(unknown, |t| if t { "on" } else { "off" }.to_string()));
+
+    if state.turbo == Some(true) {
+        println!("{}", "warning: turbo is enabled; timings may vary with frequency".yellow());
+    }
+}
+
+/// Time the spin loop, warning if it has drifted from the first measurement.
+fn record_spin_baseline(env_results: &mut EnvironmentResults) {
+    let baseline = environment::spin_baseline().as_nanos() as u64;
+    let baselines = &mut env_results.spin_baseline;
+
+    if let Some(&reference) = baselines.first() {
+        let drift = environment::baseline_drift(reference, baseline);
+        let max_drift = env_results.config.max_baseline_drift
+            .unwrap_or(environment::DEFAULT_MAX_BASELINE_DRIFT);
+        if drift > max_drift {
+            println!("{}", format!(
+                "warning: spin loop baseline drifted {:.1}% ({reference}ns to {baseline}ns)",
+                drift as f64 / PERCENT_F * 100.0).yellow());
+        }
+    }
+    baselines.push(baseline);
+}
+
 fn run_dataset_benchmarks(
     cli: &Cli,
+    config: &EnvironmentConfig,
     info: &DatasetInfo,
     algos: &HashSet<String>,
-    counters: &mut PerfCounters) -> Result<AlgorithmResults, String>
+    output: Option<OutputMode>,
+    counters: &mut PerfCounters,
+    frequency_peak: &mut u64)
+    -> Result<(AlgorithmResults, HashMap<AlgorithmId, Vec<FesiaStatsRun>>), String>
 {
-    println!("{}", &info.name.green().bold());
+    match output {
+        Some(output) => println!("{} {}",
+            &info.name.green().bold(), format!("(output {})", output.name()).green()),
+        None => println!("{}", &info.name.green().bold()),
+    }
+    let output = output.unwrap_or_else(|| default_output(cli));
 
     let dataset_dir = PathBuf::from(&cli.datasets)
         .join(&info.name);
 
     let mut algorithm_results: AlgorithmResults =
         algos.iter().map(|a| (a.clone(), Vec::new())).collect();
+    let mut fesia_stats = HashMap::new();
+
+    let prefetch_distance = config.prefetch_distance.unwrap_or(DEFAULT_PREFETCH_DISTANCE);
+    let cpu = config.pin_cpu.unwrap_or_else(environment::current_cpu);
+    let sample_interval = Duration::from_millis(config.frequency_sample_ms
+        .unwrap_or(environment::DEFAULT_FREQUENCY_SAMPLE_MS));
 
-    for x in benchmark::xvalues(info) {
-        let xlabel = format!("[x: {:4}]", x);
+    // Chosen on the dataset's first point.
+    let mut gallop_factor = None;
+
+    let yvalues = benchmark::yvalues(info);
+    let points = benchmark::xvalues(info)
+        .flat_map(|x| yvalues.iter().map(move |&y| (x, y)));
+
+    for (x, y) in points {
+        let xlabel = match y {
+            Some(y) => format!("[x: {x:4}, y: {y:4}]"),
+            None => format!("[x: {:4}]", x),
+        };
         println!("{}", xlabel.bold());
-        let xdir = dataset_dir.join(x.to_string());
+        let xdir = benchmark::point_dir(&dataset_dir, x, y);
+
+        // Instances are only recorded for datasets with data_replications.
+        let mut paths = Vec::new();
+        for (instance, dir) in benchmark::instance_dirs(info, &xdir).iter().enumerate() {
+            let instance = info.data_replications.map(|_| instance as u32);
+            paths.extend(benchmark::datafile_paths(dir)?.into_iter()
+                .map(|path| (instance, path)));
+        }
+        let real = matches!(info.dataset_type, DatasetType::Real(_));
+        let point = PointData::load(config, paths, real)?;
 
         for (name, runs) in &mut algorithm_results {
             println!("  {}", name);
 
-            let pairs: Result<Vec<PathBuf>, String> = fs::read_dir(&xdir)
-                .map_err(|e| fmt_open_err(e, &xdir))?
-                .map(|s| s
-                    .map_err(|e| format!(
-                        "unable to open directory entry in {}: {}",
-                        path_str(&xdir), e.to_string()
-                    ))
-                    .map(|s| s.path())
-                )
-                .collect();
-
-            let pairs = pairs?;
-
-            if let Some(timer) = Timer::new(name, cli.count_only) {
-                let run = time_algorithm_on_x(x, timer, pairs, counters)?;
+            let tuned = (name == "galloping_auto").then(|| *gallop_factor
+                .get_or_insert_with(|| tune_gallop_factor(config, &point)));
+            let timer_name = match tuned {
+                Some(factor) => {
+                    println!("    growth factor {factor}");
+                    format!("galloping_factor{factor}")
+                },
+                None => name.clone(),
+            };
+
+            if let Some(timer) = Timer::new(&timer_name, output, prefetch_distance) {
+                if cli.fesia_stats {
+                    if let Some(stats) = point_fesia_stats(&timer, &point) {
+                        println!("    segments min {} avg {:.2} max {}, {} empty, bitmap density {:.3}",
+                            stats.min_segment_size(), stats.avg_segment_size(),
+                            stats.max_segment_size(), stats.empty_segments(),
+                            stats.bitmap_density());
+                        fesia_stats.entry(name.clone())
+                            .or_insert_with(Vec::new)
+                            .push(fesia_stats_run(x, y, &stats));
+                    }
+                }
+
+                let monitor = config.frequency_monitor
+                    .map(|source| FrequencyMonitor::start(cpu, source, sample_interval))
+                    .transpose()?;
+
+                let mut run = if cli.query_threads.is_some() {
+                    time_concurrent_on_point(cli, config, x, y, timer, &point, counters)?
+                }
+                else {
+                    time_algorithm_on_point(cli, config, x, y, timer, &point, counters)?
+                };
+                run.gallop_factor = tuned;
+
+                if let Some(monitor) = monitor {
+                    record_frequency(config, &mut run, monitor.stop(), frequency_peak);
+                }
+                if let Some(cooldown) = config.avx512_cooldown_ms {
+                    let avx512 = registry::lookup(name)
+                        .is_some_and(|caps| caps.features.contains(&"avx512f"));
+                    if avx512 {
+                        thread::sleep(Duration::from_millis(cooldown));
+                    }
+                }
                 runs.push(run);
             }
             else {
@@ -174,81 +572,399 @@ fn run_dataset_benchmarks(
             }
         }
     }
-    Ok(algorithm_results)
+    Ok((algorithm_results, fesia_stats))
 }
 
-fn time_algorithm_on_x(
+const DEFAULT_GALLOP_FACTORS: &[usize] = &[2, 4, 8, 16];
+
+/// Growth factor of `galloping_auto`, the one of `gallop_factors` comparing
+/// the fewest elements over the first two sets of each of the point's
+/// datafiles.
+fn tune_gallop_factor(config: &EnvironmentConfig, point: &PointData) -> usize {
+    let factors: Vec<usize> = config.gallop_factors.as_deref()
+        .unwrap_or(DEFAULT_GALLOP_FACTORS)
+        .iter()
+        .copied()
+        .filter(|factor| (2..=timer::MAX_GALLOP_FACTOR).contains(factor))
+        .collect();
+    let pairs: Vec<(&[i32], &[i32])> = point.datafiles.iter()
+        .filter(|datafile| datafile.sets.len() >= 2)
+        .map(|datafile| (datafile.sets[0].as_slice(), datafile.sets[1].as_slice()))
+        .collect();
+    intersect::tune_gallop_factor(&pairs, &factors).unwrap_or(DEFAULT_GALLOP_FACTOR)
+}
+
+/// Records the mean frequency of a cell, noting and warning if it dropped
+/// below the highest mean of the cells before.
+fn record_frequency(config: &EnvironmentConfig, run: &mut ResultRun, mean: Option<u64>, peak: &mut u64) {
+    let Some(mean) = mean else {
+        println!("{}", "    warning: unable to read the frequency".yellow());
+        return;
+    };
+    run.frequency_khz = Some(mean);
+
+    let drop = environment::frequency_drop(*peak, mean);
+    let max_drop = config.max_frequency_drop.unwrap_or(environment::DEFAULT_MAX_FREQUENCY_DROP);
+    if drop > max_drop {
+        run.frequency_drop = Some(drop);
+        println!("{}", format!("    warning: mean frequency {mean} kHz is {:.1}% below the peak of {peak} kHz",
+            drop as f64 / PERCENT_F * 100.0).yellow());
+    }
+    *peak = (*peak).max(mean);
+}
+
+/// Stats over the sets of every datafile of the point, if `timer` builds
+/// FESIA sets and accepts them all.
+fn point_fesia_stats(timer: &Timer, point: &PointData) -> Option<FesiaStats> {
+    let mut stats = FesiaStats::default();
+    for datafile in &point.datafiles {
+        stats.merge(&timer.fesia_stats(&datafile.sets)?);
+    }
+    Some(stats)
+}
+
+fn fesia_stats_run(x: u32, y: Option<u32>, stats: &FesiaStats) -> FesiaStatsRun {
+    FesiaStatsRun {
+        x,
+        y,
+        sets: stats.sets,
+        elements: stats.elements,
+        segments: stats.segments,
+        empty_segments: stats.empty_segments(),
+        min_segment_size: stats.min_segment_size(),
+        max_segment_size: stats.max_segment_size(),
+        avg_segment_size: stats.avg_segment_size(),
+        bitmap_density: stats.bitmap_density(),
+        histogram: stats.histogram.clone(),
+    }
+}
+
+const MIN_REPLICATIONS: usize = 3;
+
+/// Sets of a point's datafiles, read before any algorithm is timed on them so
+/// that no file I/O or allocation of inputs happens between timed runs. With
+/// placement, the sets share one arena. Fields drop in order, so the sets are
+/// freed before the arena holding them.
+struct PointData {
+    datafiles: Vec<Datafile>,
+    arena: Option<Arena>,
+}
+
+struct Datafile {
+    instance: Option<u32>,
+    sets: Vec<DatafileSet>,
+    // Recorded with each run for real datasets.
+    lens: Option<process::QueryLens>,
+    // Filled with --share-prepared, and dropped with the point.
+    prepared: PreparedCache,
+}
+
+impl PointData {
+    fn load(config: &EnvironmentConfig, paths: Vec<(Option<u32>, PathBuf)>, query_lens: bool)
+        -> Result<Self, String>
+    {
+        let files = paths.iter()
+            .map(|(_, path)| File::open(path).map_err(|e| fmt_open_err(e, path)))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let arena = if config.places_sets() {
+            let len: usize = files.iter()
+                .map(|f| f.metadata().map_or(0, |m| m.len() as usize))
+                .sum();
+            Some(Arena::new(len + placement::HUGE_PAGE, config.huge_pages, config.numa_node)?)
+        }
+        else {
+            None
+        };
+
+        let mut datafiles = Vec::with_capacity(files.len());
+        for ((instance, path), file) in paths.into_iter().zip(files) {
+            let read = || datafile::from_reader(file);
+            let sets = match &arena {
+                Some(arena) => arena.scope(read),
+                None => read(),
+            }
+                .map_err(|e| format!("invalid datafile {}: {}", path_str(&path), e.to_string()))?;
+
+            if config.pretouch {
+                pretouch_sets(&sets);
+            }
+            let lens = query_lens.then(|| process::QueryLens::of(&sets));
+            datafiles.push(Datafile { instance, sets, lens, prepared: PreparedCache::default() });
+        }
+        Ok(Self { datafiles, arena })
+    }
+
+    /// Copies the sets of `datafile` into fresh allocations, placed like the
+    /// originals in an arena of their own. The arena is returned after the
+    /// sets, so it is dropped after them.
+    fn fresh_copy(&self, config: &EnvironmentConfig, datafile: &Datafile)
+        -> Result<(Vec<DatafileSet>, Option<Arena>), String>
+    {
+        let copy = || datafile.sets.clone();
+        let (sets, arena) = match &self.arena {
+            Some(_) => {
+                let len = datafile.sets.iter().map(|s| std::mem::size_of_val(s.as_slice())).sum::<usize>();
+                let arena = Arena::new(len + placement::HUGE_PAGE, config.huge_pages, config.numa_node)?;
+                (arena.scope(copy), Some(arena))
+            },
+            None => (copy(), None),
+        };
+        if config.pretouch {
+            pretouch_sets(&sets);
+        }
+        Ok((sets, arena))
+    }
+}
+
+/// Reads one element per page of each set, so no page of the inputs faults in
+/// a timed run.
+fn pretouch_sets(sets: &[DatafileSet]) {
+    const PAGE_ELEMENTS: usize = 4096 / std::mem::size_of::<i32>();
+    let mut sum = 0i32;
+    for set in sets {
+        for &item in set.iter().step_by(PAGE_ELEMENTS) {
+            sum = sum.wrapping_add(item);
+        }
+    }
+    std::hint::black_box(sum);
+}
+
+fn time_algorithm_on_point(
+    cli: &Cli,
+    config: &EnvironmentConfig,
+    x: u32,
+    y: Option<u32>,
+    timer: Timer,
+    point: &PointData,
+    counters: &mut PerfCounters)
+    -> Result<ResultRun, String>
+{
+    let mut result = counters.new_result_run(x, y);
+
+    let latency_iterations = cli.latency.map_or(0, |_| cli.latency_iterations);
+    let mut histogram = Histogram::new();
+    let mut samples = Vec::new();
+
+    let max_replications = config.max_replications.unwrap_or(1);
+    let mut pass_times = Vec::new();
+
+    'passes: for _ in 0..max_replications {
+        let pass_start = result.times.len();
+
+        for datafile in &point.datafiles {
+            let instance = &datafile.instance;
+
+            let fresh = if config.fresh_inputs {
+                Some(point.fresh_copy(config, datafile)?)
+            }
+            else {
+                None
+            };
+            let sets = fresh.as_ref().map_or(&datafile.sets, |(sets, _arena)| sets);
+            // Fresh inputs are prepared afresh too.
+            let prepared = (cli.share_prepared && fresh.is_none()).then_some(&datafile.prepared);
+
+            const TARGET_WARMUP: Duration = Duration::from_millis(1000);
+            let warmup = TARGET_WARMUP.div_f32(point.datafiles.len() as f32);
+
+            let mut harness = Harness::new(warmup, counters)
+                .with_latency(latency_iterations)
+                .with_pretouch(config.pretouch)
+                .with_output_hashes(cli.hash_outputs)
+                .with_prepared(prepared);
+            let run_result = timer.run(&mut harness, sets);
+
+            match run_result {
+                Ok(run) => {
+                    let perf = &run.perf;
+
+                    for &sample in &run.samples {
+                        histogram.record(sample);
+                    }
+                    if cli.latency == Some(LatencyOutput::Samples) {
+                        samples.extend_from_slice(&run.samples);
+                    }
+                    if let Some(output) = run.output {
+                        result.output_len.get_or_insert_with(Vec::new).push(output.len as u64);
+                        result.output_capacity.get_or_insert_with(Vec::new)
+                            .push(output.capacity as u64);
+                    }
+                    if let Some(flush) = run.flush {
+                        result.flush_times.get_or_insert_with(Vec::new)
+                            .push(flush.as_nanos() as u64);
+                    }
+                    if let Some(work) = run.work {
+                        if timer.counts_comparisons() {
+                            result.comparisons.get_or_insert_with(Vec::new).push(work.comparisons);
+                        }
+                        result.vector_loads.get_or_insert_with(Vec::new).push(work.vector_loads);
+                        result.emitted_vectors.get_or_insert_with(Vec::new)
+                            .push(work.emitted_vectors);
+                        if work.gallops > 0 {
+                            result.gallops.get_or_insert_with(Vec::new).push(work.gallops);
+                            result.gallop_overshoot.get_or_insert_with(Vec::new)
+                                .push(work.gallop_overshoot);
+                        }
+                    }
+                    if let Some(hash) = run.output_hash {
+                        result.output_hashes.get_or_insert_with(Vec::new).push(hash);
+                    }
+
+                    result.times.push(run.time.as_nanos() as u64);
+                    result.input_bytes.get_or_insert_with(Vec::new).push(
+                        sets.iter().map(|s| std::mem::size_of_val(s.as_slice()) as u64).sum());
+                    if let Some(lens) = &datafile.lens {
+                        result.len_a.get_or_insert_with(Vec::new).push(lens.len_a);
+                        result.len_b.get_or_insert_with(Vec::new).push(lens.len_b);
+                        result.intersection_len.get_or_insert_with(Vec::new)
+                            .push(lens.intersection_len);
+                    }
+                    if let Some(instance) = instance {
+                        result.instances.get_or_insert_with(Vec::new).push(*instance);
+                    }
+                    if let Some(v) = &mut result.l1d.rd_access { v.push(perf.l1d.rd_access.unwrap()); }
+                    if let Some(v) = &mut result.l1d.rd_miss { v.push(perf.l1d.rd_miss.unwrap()); }
+                    if let Some(v) = &mut result.l1d.wr_access { v.push(perf.l1d.wr_access.unwrap()); }
+                    if let Some(v) = &mut result.l1d.wr_miss { v.push(perf.l1d.wr_miss.unwrap()); }
+
+                    if let Some(v) = &mut result.l1i.rd_access { v.push(perf.l1i.rd_access.unwrap()); }
+                    if let Some(v) = &mut result.l1i.rd_miss { v.push(perf.l1i.rd_miss.unwrap()); }
+                    if let Some(v) = &mut result.l1i.wr_access { v.push(perf.l1i.wr_access.unwrap()); }
+                    if let Some(v) = &mut result.l1i.wr_miss { v.push(perf.l1i.wr_miss.unwrap()); }
+
+                    if let Some(v) = &mut result.ll.rd_access { v.push(perf.ll.rd_access.unwrap()); }
+                    if let Some(v) = &mut result.ll.rd_miss { v.push(perf.ll.rd_miss.unwrap()); }
+                    if let Some(v) = &mut result.ll.wr_access { v.push(perf.ll.wr_access.unwrap()); }
+                    if let Some(v) = &mut result.ll.wr_miss { v.push(perf.ll.wr_miss.unwrap()); }
+
+                    if let Some(v) = &mut result.branches { v.push(perf.branches.unwrap()); }
+                    if let Some(v) = &mut result.branch_misses { v.push(perf.branch_misses.unwrap()); }
+
+                    if let Some(v) = &mut result.cpu_stalled_front { v.push(perf.cpu_stalled_front.unwrap()); }
+                    if let Some(v) = &mut result.cpu_stalled_back { v.push(perf.cpu_stalled_back.unwrap()); }
+                    if let Some(v) = &mut result.instructions { v.push(perf.instructions.unwrap()); }
+                    if let Some(v) = &mut result.cpu_cycles { v.push(perf.cpu_cycles.unwrap()); }
+                    if let Some(v) = &mut result.cpu_cycles_ref { v.push(perf.cpu_cycles_ref.unwrap()); }
+                },
+                Err(e) => {
+                    println!("warn: {}", e);
+                    break 'passes;
+                },
+            }
+        }
+
+        let pass = &result.times[pass_start..];
+        pass_times.push(pass.iter().sum::<u64>() / pass.len().max(1) as u64);
+
+        // Two passes may agree by chance, so estimate the error from at least
+        // MIN_REPLICATIONS before stopping early.
+        let rse = environment::relative_standard_error(&pass_times);
+        if pass_times.len() >= MIN_REPLICATIONS &&
+            config.target_rse.is_some_and(|target| rse.is_some_and(|rse| rse <= target))
+        {
+            break;
+        }
+    }
+
+    if config.max_replications.is_some() {
+        result.replications = Some(pass_times.len() as u32);
+        result.rse = environment::relative_standard_error(&pass_times);
+        if let Some(rse) = result.rse {
+            println!("    {} replications, rse {:.1}%",
+                pass_times.len(), rse as f64 / PERCENT_F * 100.0);
+        }
+    }
+
+    if let Some(instances) = &result.instances {
+        let means = instance_means(&result.times, instances);
+        result.data_rse = environment::relative_standard_error(&means);
+        if let Some(rse) = result.data_rse {
+            println!("    {} instances, data rse {:.1}%",
+                means.len(), rse as f64 / PERCENT_F * 100.0);
+        }
+    }
+
+    if !histogram.is_empty() {
+        result.latency = Some(histogram.to_latency(samples));
+    }
+
+    Ok(result)
+}
+
+/// Run the datafiles of a point as queries from `cli.query_threads` threads
+/// at once, with one sample of throughput per pass.
+fn time_concurrent_on_point(
+    cli: &Cli,
+    config: &EnvironmentConfig,
     x: u32,
+    y: Option<u32>,
     timer: Timer,
-    datafile_paths: Vec<PathBuf>,
+    point: &PointData,
     counters: &mut PerfCounters)
     -> Result<ResultRun, String>
 {
-    let mut result = counters.new_result_run(x);
-
-    for datafile_path in &datafile_paths {
-        let datafile = File::open(datafile_path)
-            .map_err(|e| fmt_open_err(e, datafile_path))?;
-
-        let sets = datafile::from_reader(datafile)
-            .map_err(|e| format!(
-                "invalid datafile {}: {}",
-                path_str(datafile_path),
-                e.to_string())
-            )?;
-
-        const TARGET_WARMUP: Duration = Duration::from_millis(1000);
-        let warmup = TARGET_WARMUP.div_f32(datafile_paths.len() as f32);
-
-        let mut harness = Harness::new(warmup, counters);
-        let run_result = timer.run(&mut harness, &sets);
-
-        match run_result {
-            Ok(run) => {
-                let perf = &run.perf;
-
-                result.times.push(run.time.as_nanos() as u64);
-                if let Some(v) = &mut result.l1d.rd_access { v.push(perf.l1d.rd_access.unwrap()); }
-                if let Some(v) = &mut result.l1d.rd_miss { v.push(perf.l1d.rd_miss.unwrap()); }
-                if let Some(v) = &mut result.l1d.wr_access { v.push(perf.l1d.wr_access.unwrap()); }
-                if let Some(v) = &mut result.l1d.wr_miss { v.push(perf.l1d.wr_miss.unwrap()); }
-
-                if let Some(v) = &mut result.l1i.rd_access { v.push(perf.l1i.rd_access.unwrap()); }
-                if let Some(v) = &mut result.l1i.rd_miss { v.push(perf.l1i.rd_miss.unwrap()); }
-                if let Some(v) = &mut result.l1i.wr_access { v.push(perf.l1i.wr_access.unwrap()); }
-                if let Some(v) = &mut result.l1i.wr_miss { v.push(perf.l1i.wr_miss.unwrap()); }
-
-                if let Some(v) = &mut result.ll.rd_access { v.push(perf.ll.rd_access.unwrap()); }
-                if let Some(v) = &mut result.ll.rd_miss { v.push(perf.ll.rd_miss.unwrap()); }
-                if let Some(v) = &mut result.ll.wr_access { v.push(perf.ll.wr_access.unwrap()); }
-                if let Some(v) = &mut result.ll.wr_miss { v.push(perf.ll.wr_miss.unwrap()); }
-
-                if let Some(v) = &mut result.branches { v.push(perf.branches.unwrap()); }
-                if let Some(v) = &mut result.branch_misses { v.push(perf.branch_misses.unwrap()); }
-
-                if let Some(v) = &mut result.cpu_stalled_front { v.push(perf.cpu_stalled_front.unwrap()); }
-                if let Some(v) = &mut result.cpu_stalled_back { v.push(perf.cpu_stalled_back.unwrap()); }
-                if let Some(v) = &mut result.instructions { v.push(perf.instructions.unwrap()); }
-                if let Some(v) = &mut result.cpu_cycles { v.push(perf.cpu_cycles.unwrap()); }
-                if let Some(v) = &mut result.cpu_cycles_ref { v.push(perf.cpu_cycles_ref.unwrap()); }
+    let mut result = counters.new_result_run(x, y);
+
+    let queries: Vec<Vec<DatafileSet>> = point.datafiles.iter()
+        .map(|d| d.sets.clone())
+        .collect();
+
+    let threads = cli.query_threads.unwrap_or(1);
+    let concurrent = ConcurrentConfig {
+        threads,
+        duration: Duration::from_millis(cli.query_duration_ms),
+    };
+    result.query_threads = Some(threads as u32);
+    result.query_batch = cli.query_batch.map(|batch| batch as u32);
+    let throughput = result.throughput.insert(Vec::new());
+
+    for _ in 0..config.max_replications.unwrap_or(1) {
+        let pass = match cli.query_batch {
+            Some(batch) => timer.run_batch(&queries, &concurrent, batch),
+            None => timer.run_concurrent(&queries, &concurrent),
+        };
+        match pass {
+            Ok(pass) => {
+                result.times.push(pass.mean_query_time(threads).as_nanos() as u64);
+                throughput.push(pass.queries_per_second() as u64);
             },
             Err(e) => {
-                println!("warn: {}", e);
+                println!("warn: {e}");
                 break;
             },
         }
     }
 
+    if let Some(&last) = throughput.last() {
+        println!("    {threads} threads, {last} queries/s");
+    }
+    if config.max_replications.is_some() {
+        result.replications = Some(result.times.len() as u32);
+        result.rse = environment::relative_standard_error(&result.times);
+    }
+
     Ok(result)
 }
 
-fn write_results(results: Results, path: &PathBuf) -> Result<(), String> {
+// Mean time of each instance, over its datafiles and replications.
+fn instance_means(times: &[u64], instances: &[u32]) -> Vec<u64> {
+    let mut sums: BTreeMap<u32, (u64, u64)> = BTreeMap::new();
+    for (&time, &instance) in times.iter().zip(instances) {
+        let (sum, count) = sums.entry(instance).or_default();
+        *sum += time;
+        *count += 1;
+    }
+    sums.values().map(|(sum, count)| sum / count).collect()
+}
+
+fn write_results(results: &Results, path: &PathBuf) -> Result<(), String> {
     let results_file = File::options()
         .write(true).create(true).truncate(true)
         .open(path)
         .map_err(|e| fmt_open_err(e, path))?;
 
-    serde_json::to_writer(results_file, &results)
+    serde_json::to_writer(results_file, results)
         .map_err(|e| format!(
             "failed to write {}: {}",
             path_str(path), e.to_string()
@@ -256,3 +972,133 @@ fn write_results(results: Results, path: &PathBuf) -> Result<(), String> {
 
     Ok(())
 }
+
+fn compare_files(
+    baseline: &PathBuf,
+    candidate: &PathBuf,
+    threshold: u32,
+    fail_on_regression: bool) -> Result<(), String>
+{
+    let cells = compare::compare(&read_results(baseline)?, &read_results(candidate)?, threshold);
+    if cells.is_empty() {
+        return Err("no cells in common".to_string());
+    }
+
+    println!("{:<16} {:<24} {:>8} {:>14} {:>14} {:>8}  95% interval",
+        "dataset", "algorithm", "x[,y]", "baseline ns", "candidate ns", "speedup");
+    for cell in &cells {
+        let point = match cell.y {
+            Some(y) => format!("{},{y}", cell.x),
+            None => cell.x.to_string(),
+        };
+        let interval = cell.interval
+            .map(|(lo, hi)| format!("[{lo:.3}, {hi:.3}]"))
+            .unwrap_or_else(|| "n/a".to_string());
+        let line = format!("{:<16} {:<24} {:>8} {:>14.0} {:>14.0} {:>8.3}  {interval}",
+            cell.dataset, cell.algorithm, point, cell.baseline, cell.candidate, cell.speedup);
+
+        match cell.change {
+            compare::Change::Faster => println!("{}", line.green()),
+            compare::Change::Slower => println!("{}", line.red()),
+            compare::Change::Same => println!("{line}"),
+        }
+    }
+
+    let count = |change| cells.iter().filter(|c| c.change == change).count();
+    let slower = count(compare::Change::Slower);
+    println!("{} cells: {} faster, {} slower, geometric mean speedup {:.3}",
+        cells.len(), count(compare::Change::Faster), slower,
+        compare::geometric_mean_speedup(&cells).unwrap_or(1.0));
+
+    if fail_on_regression && slower > 0 {
+        return Err(format!("{slower} cells are significantly slower"));
+    }
+    Ok(())
+}
+
+fn verify_files(baseline: &PathBuf, candidate: &PathBuf) -> Result<(), String> {
+    let cells = verify::verify(&read_results(baseline)?, &read_results(candidate)?);
+    if cells.is_empty() {
+        return Err("no hashed cells in common (run both with --hash-outputs)".to_string());
+    }
+
+    println!("{:<16} {:<8} {:<24} {:<24} {:>8}  result",
+        "dataset", "output", "algorithm", "reference", "x[,y]");
+    for cell in &cells {
+        let point = match cell.y {
+            Some(y) => format!("{},{y}", cell.x),
+            None => cell.x.to_string(),
+        };
+        let output = cell.output.map_or("-", OutputMode::name);
+        let line = format!("{:<16} {:<8} {:<24} {:<24} {:>8}",
+            cell.dataset, output, cell.algorithm, cell.reference, point);
+
+        if cell.mismatches.is_empty() {
+            println!("{line}  {} datafiles match", cell.compared);
+        }
+        else {
+            println!("{}", format!("{line}  datafiles {:?} of {} differ",
+                cell.mismatches, cell.compared).red());
+        }
+    }
+
+    let mismatched = cells.iter().filter(|c| !c.mismatches.is_empty()).count();
+    println!("{} cells: {} differ", cells.len(), mismatched);
+
+    if mismatched > 0 {
+        return Err(format!("{mismatched} cells computed different results"));
+    }
+    Ok(())
+}
+
+fn read_results(path: &PathBuf) -> Result<Results, String> {
+    let json = fs::read_to_string(path).map_err(|e| fmt_open_err(e, path))?;
+    migrate::parse_results(&json)
+        .map_err(|e| format!("invalid results file {}: {}", path_str(path), e))
+}
+
+fn process_file(results_path: &PathBuf, out: &PathBuf, stratify: bool) -> Result<(), String> {
+    let json = fs::read_to_string(results_path).map_err(|e| fmt_open_err(e, results_path))?;
+    let results = migrate::parse_results(&json)
+        .map_err(|e| format!("invalid results file {}: {}", path_str(results_path), e))?;
+
+    for experiment in &results.experiments {
+        let experiment_dir = out.join(&experiment.name);
+        fs::create_dir_all(&experiment_dir).map_err(|e| fmt_open_err(e, &experiment_dir))?;
+
+        for summary in process::summarise_experiment(&results, experiment)? {
+            let path = experiment_dir.join(format!("{}.csv", summary.algorithm));
+            let csv_file = File::create(&path).map_err(|e| fmt_open_err(e, &path))?;
+
+            process::write_summary_csv(&summary, &mut BufWriter::new(csv_file))
+                .map_err(|e| format!("failed to write {}: {}", path_str(&path), e))?;
+            println!("{}", path_str(&path));
+        }
+
+        if !stratify {
+            continue;
+        }
+        for summary in process::stratify(&results, experiment)? {
+            if summary.strata.is_empty() {
+                continue;
+            }
+            let path = experiment_dir.join(format!("{}_strata.csv", summary.algorithm));
+            let csv_file = File::create(&path).map_err(|e| fmt_open_err(e, &path))?;
+
+            process::write_strata_csv(&summary, &mut BufWriter::new(csv_file))
+                .map_err(|e| format!("failed to write {}: {}", path_str(&path), e))?;
+            println!("{}", path_str(&path));
+        }
+    }
+    Ok(())
+}
+
+fn write_csv(results: &Results, path: &PathBuf) -> Result<(), String> {
+    let csv_file = File::options()
+        .write(true).create(true).truncate(true)
+        .open(path)
+        .map_err(|e| fmt_open_err(e, path))?;
+
+    export::write_csv(results, &mut BufWriter::new(csv_file))
+        .map_err(|e| format!("failed to write {}: {}", path_str(path), e))
+}
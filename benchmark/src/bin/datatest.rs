@@ -1,9 +1,10 @@
 use std::{path::PathBuf, fs::{self, File}, io::Write};
 
-use benchmark::{fmt_open_err, path_str, schema::*, datafile};
-use clap::Parser;
+use benchmark::{fmt_open_err, path_str, schema::*, datafile, realdata, stats::{self, PointStats, DatasetStats}};
+use clap::{Parser, Subcommand};
 use colored::Colorize;
-use setops::intersect::{run_svs, self};
+use rand::{SeedableRng, rngs::StdRng};
+use setops::{intersect::{run_svs, self}, estimate};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -11,14 +12,45 @@ struct Cli {
     #[arg(default_value = "datasets/", long)]
     datasets: PathBuf,
     tests: Vec<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Summarise the cardinality, density, run lengths and selectivity of the
+    /// sets of each point of a generated dataset, or of the sets of a real
+    /// source, as JSON.
+    Stats {
+        /// Name of a generated dataset, or of a real source with --source.
+        dataset: String,
+        /// Analyse all sets of a real source (e.g. webdocs) rather than the
+        /// datafiles of a generated dataset.
+        #[arg(long, action)]
+        source: bool,
+        /// Most pairs of sets per point to compute pairwise selectivity of,
+        /// sampled at random if there are more.
+        #[arg(default_value_t = 10000, long)]
+        max_pairs: usize,
+        #[arg(long)]
+        seed: Option<u64>,
+        #[arg(default_value = "stats.json", long)]
+        out: PathBuf,
+    },
 }
 
 fn main() {
     let cli = Cli::parse();
 
-    if let Err(e) = run_datatest(&cli) {
+    let result = match &cli.command {
+        Some(Command::Stats { dataset, source, max_pairs, seed, out }) =>
+            dataset_stats(&cli, dataset, *source, *max_pairs, *seed, out),
+        None => run_datatest(&cli),
+    };
+    if let Err(e) = result {
         let msg = format!("error: {}", e);
         println!("{}", msg.red().bold());
+        std::process::exit(1);
     }
 }
 
@@ -59,35 +91,101 @@ fn dataset_info(cli: &Cli, dataset_name: &str) -> Result<DatasetInfo, String> {
     Ok(dataset_info)
 }
 
+fn dataset_stats(
+    cli: &Cli,
+    dataset_name: &str,
+    source: bool,
+    max_pairs: usize,
+    seed: Option<u64>,
+    out: &PathBuf) -> Result<(), String>
+{
+    let rng = &mut match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let points = if source {
+        let sets = realdata::load_sets(&cli.datasets, dataset_name)?;
+        vec![PointStats {
+            x: None,
+            y: None,
+            stats: stats::sets_stats(&[sets], max_pairs, rng),
+        }]
+    }
+    else {
+        let info = dataset_info(cli, dataset_name)?;
+        let dir = cli.datasets.join(dataset_name);
+
+        let yvalues = benchmark::yvalues(&info);
+        let mut points = Vec::new();
+        for x in benchmark::xvalues(&info) {
+            for &y in &yvalues {
+                let point_dir = benchmark::point_dir(&dir, x, y);
+                let mut groups = Vec::new();
+                for instance_dir in benchmark::instance_dirs(&info, &point_dir) {
+                    groups.extend(read_instance(&instance_dir)?);
+                }
+                points.push(PointStats {
+                    x: Some(x),
+                    y,
+                    stats: stats::sets_stats(&groups, max_pairs, rng),
+                });
+            }
+        }
+        points
+    };
+
+    let summary = DatasetStats { dataset: dataset_name.to_string(), points };
+    let json = serde_json::to_string_pretty(&summary)
+        .map_err(|e| format!("failed to serialize stats: {e}"))?;
+
+    fs::write(out, json)
+        .map_err(|e| format!("failed to write {}: {}", path_str(out), e))?;
+    println!("wrote {}", path_str(out));
+    Ok(())
+}
+
+// Sets of each datafile of an instance of a point.
+fn read_instance(dir: &PathBuf) -> Result<Vec<Vec<Vec<i32>>>, String> {
+    benchmark::datafile_paths(dir)?.iter()
+        .map(|path| {
+            let datafile = File::open(path).map_err(|e| fmt_open_err(e, path))?;
+            datafile::from_reader(datafile)
+                .map_err(|e| format!("invalid datafile {}: {}", path_str(path), e.to_string()))
+        })
+        .collect()
+}
+
 fn verify_dataset(info: &DatasetInfo, dir: &PathBuf) -> Result<(), String> {
 
     dbg!(info);
     
-    for x in benchmark::xvalues(info) {
+    let yvalues = benchmark::yvalues(info);
+    let points = benchmark::xvalues(info)
+        .flat_map(|x| yvalues.iter().map(move |&y| (x, y)));
+
+    for (x, y) in points {
         // later: look at throughput?
-        let xlabel = format!("[x: {:4}]", x);
+        let xlabel = match y {
+            Some(y) => format!("[x: {x:4}, y: {y:4}]"),
+            None => format!("[x: {:4}]", x),
+        };
         println!("{}", xlabel.bold());
 
-        let xdir = dir.join(x.to_string());
-        let pairs = fs::read_dir(&xdir)
-            .map_err(|e| fmt_open_err(e, &xdir))?;
-
-        for (i, pair_path) in pairs.enumerate() {
-
-            let pair_path = pair_path
-                .map_err(|e| format!(
-                    "unable to open directory entry in {}: {}",
-                    path_str(&xdir), e.to_string()
-                ))?;
+        let xdir = benchmark::point_dir(dir, x, y);
+        let mut datafile_paths = Vec::new();
+        for instance_dir in benchmark::instance_dirs(info, &xdir) {
+            datafile_paths.extend(benchmark::datafile_paths(&instance_dir)?);
+        }
 
-            let datafile_path = pair_path.path();
-            let datafile = File::open(&datafile_path)
-                .map_err(|e| fmt_open_err(e, &datafile_path))?;
+        for (i, datafile_path) in datafile_paths.iter().enumerate() {
+            let datafile = File::open(datafile_path)
+                .map_err(|e| fmt_open_err(e, datafile_path))?;
 
             let sets = datafile::from_reader(datafile)
                 .map_err(|e| format!(
                     "invalid datafile {}: {}",
-                    path_str(&datafile_path),
+                    path_str(datafile_path),
                     e.to_string())
                 )?;
 
@@ -96,9 +194,9 @@ fn verify_dataset(info: &DatasetInfo, dir: &PathBuf) -> Result<(), String> {
 
             match &info.dataset_type {
                 DatasetType::Synthetic(s) =>
-                    verify_synthetic(&sets, &benchmark::props_at_x(s, x)),
-                DatasetType::Real(_) => 
-                    verify_real(&sets, x),
+                    verify_synthetic(&sets, &benchmark::props_at(s, x, y)),
+                DatasetType::Real(r) =>
+                    verify_real(&sets, r.set_count_at(x)),
             }
         }
         println!();
@@ -117,8 +215,9 @@ fn verify_synthetic(sets: &[Vec<i32>], info: &IntersectionInfo) {
     verify_set_count(sets, info.set_count as usize);
     verify_sizes(sets, info);
     verify_density(sets, info);
-    verify_selectivity(sets, info.selectivity);
+    verify_selectivity(sets, info.selectivity, info.exact || info.seed.is_some());
     verify_sorted(sets);
+    trace_estimate(sets);
 }
 
 fn verify_set_count(sets: &[Vec<i32>], set_count: usize) {
@@ -198,7 +297,7 @@ fn verify_density(sets: &[Vec<i32>], info: &IntersectionInfo) {
 }
 
 // The closer the density is to 1.0, the lower the error should be
-fn verify_selectivity(sets: &[Vec<i32>], selectivity: u32) {
+fn verify_selectivity(sets: &[Vec<i32>], selectivity: u32, exact: bool) {
     let smallest_len = sets.iter()
         .map(|s| s.len())
         .min().unwrap();
@@ -209,7 +308,11 @@ fn verify_selectivity(sets: &[Vec<i32>], selectivity: u32) {
         run_svs(&sets, intersect::branchless_merge).len();
 
     let selectivity = selectivity as f64 / PERCENT_F;
-    let target_len = (smallest_len as f64 * selectivity) as usize;
+    let target_len = if exact {
+        (smallest_len as f64 * selectivity).round() as usize
+    } else {
+        (smallest_len as f64 * selectivity) as usize
+    };
 
     let message = format!(
         "expected result len {}, got {} (sel {:.06})",
@@ -219,7 +322,7 @@ fn verify_selectivity(sets: &[Vec<i32>], selectivity: u32) {
         error(&message);
     }
     else if result_len > target_len {
-        if sets.len() == 2 {
+        if sets.len() == 2 || exact {
             error(&message);
         }
         else {
@@ -250,6 +353,7 @@ fn verify_real(sets: &[Vec<i32>], set_count: u32) {
 
     trace_selectivity(sets);
     trace_density(sets);
+    trace_estimate(sets);
 }
 
 fn trace_selectivity(sets: &[Vec<i32>]) {
@@ -279,6 +383,32 @@ fn trace_density(sets: &[Vec<i32>]) {
     println!("max density: {:.4}", density);
 }
 
+// Reports the relative error of the cardinality estimators for pairwise
+// intersections.
+fn trace_estimate(sets: &[Vec<i32>]) {
+    const HLL_PRECISION: u8 = 14;
+    const SAMPLES: usize = 1024;
+
+    if sets.len() != 2 {
+        return;
+    }
+
+    let exact =
+        run_svs(sets, intersect::branchless_merge).len() as f64;
+
+    let hll = estimate::intersection_card(&sets[0], &sets[1], HLL_PRECISION);
+    let sampled = estimate::sample_intersection_card(&sets[0], &sets[1], SAMPLES);
+
+    let relative = |estimate: f64| if exact == 0.0 {
+        estimate
+    } else {
+        (estimate - exact).abs() / exact
+    };
+
+    println!("estimate: exact {}, hll {:.0} (err {:.4}), sampled {:.0} (err {:.4})",
+        exact, hll, relative(hll), sampled, relative(sampled));
+}
+
 fn error(text: &str) {
     println!("{}", text.red().bold());
 }
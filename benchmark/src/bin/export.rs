@@ -0,0 +1,41 @@
+use std::{fs::{self, File}, io::BufWriter, path::PathBuf};
+
+use benchmark::{fmt_open_err, path_str, migrate, export};
+use clap::Parser;
+use colored::*;
+
+/// Convert a results file to tidy CSV, with one row per dataset, x, y,
+/// algorithm and sample.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[arg(default_value = "results.json")]
+    results: PathBuf,
+    #[arg(default_value = "results.csv", long)]
+    out: PathBuf,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    if let Err(e) = export_results(&cli) {
+        let msg = format!("error: {e}");
+        println!("{}", msg.red().bold());
+    }
+}
+
+fn export_results(cli: &Cli) -> Result<(), String> {
+    let results_json = fs::read_to_string(&cli.results)
+        .map_err(|e| fmt_open_err(e, &cli.results))?;
+
+    let results = migrate::parse_results(&results_json)
+        .map_err(|e| format!("invalid results file {}: {}", path_str(&cli.results), e))?;
+
+    let csv_file = File::options()
+        .write(true).create(true).truncate(true)
+        .open(&cli.out)
+        .map_err(|e| fmt_open_err(e, &cli.out))?;
+
+    export::write_csv(&results, &mut BufWriter::new(csv_file))
+        .map_err(|e| format!("failed to write {}: {}", path_str(&cli.out), e))
+}
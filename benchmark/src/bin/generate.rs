@@ -3,8 +3,9 @@ use benchmark::{
     datafile::{self, DatafileSet},
     path_str, fmt_open_err,
     generators,
-    format::{format_xlabel, format_x},
-    realdata::generate_real_dataset
+    format::{format_xlabel, format_x, format_y},
+    realdata::generate_real_dataset,
+    migrate,
 };
 use clap::Parser;
 use colored::*;
@@ -53,7 +54,7 @@ impl Cli {
         let experiment_toml = fs::read_to_string(&self.experiment)
             .map_err(|e| fmt_open_err(e, &self.experiment))?;
 
-        let experiments: Experiment = toml::from_str(&experiment_toml)
+        let experiments: Experiment = migrate::parse_experiment(&experiment_toml)
             .map_err(|e| format!(
                 "invalid toml file {}: {}",
                 path_str(&self.experiment), e
@@ -94,8 +95,8 @@ fn maybe_generate_dataset(datasets: &PathBuf, info: &DatasetInfo)
     }
 
     match &info.dataset_type {
-        DatasetType::Synthetic(s) => generate_synthetic_dataset(s, &dataset_path)?,
-        DatasetType::Real(r) => generate_real_dataset(r, datasets, &dataset_path)?,
+        DatasetType::Synthetic(s) => generate_synthetic_dataset(info, s, &dataset_path)?,
+        DatasetType::Real(r) => generate_real_dataset(info, r, datasets, &dataset_path)?,
     }
 
     // Write new info file
@@ -112,27 +113,34 @@ fn maybe_generate_dataset(datasets: &PathBuf, info: &DatasetInfo)
     Ok(())
 }
 
-fn generate_synthetic_dataset(info: &SyntheticDataset, path: &PathBuf)
-    -> Result<(), String>
+fn generate_synthetic_dataset(
+    dataset: &DatasetInfo,
+    info: &SyntheticDataset,
+    path: &PathBuf) -> Result<(), String>
 {
+    benchmark::validate_synthetic(info)?;
+
     let _ = fs::remove_dir_all(&path);
-    let xvalues: Vec<u32> = benchmark::xvalues_synthetic(info).collect();
+    let yvalues = benchmark::yvalues_synthetic(info);
+    let points: Vec<(u32, Option<u32>)> = benchmark::xvalues_synthetic(info)
+        .flat_map(|x| yvalues.iter().map(move |&y| (x, y)))
+        .collect();
 
     let multi_progress = MultiProgress::new();
 
     let main_style =
-        ProgressStyle::with_template("  Dispatched for {pos}/{len} x-values")
+        ProgressStyle::with_template("  Dispatched for {pos}/{len} points")
             .map_err(|e| e.to_string())?;
 
-    let main_bar = ProgressBar::new(xvalues.len() as u64)
+    let main_bar = ProgressBar::new(points.len() as u64)
         .with_style(main_style);
 
     let main_bar = multi_progress.add(main_bar);
 
-    let gen_errors: Vec<String> = xvalues
+    let gen_errors: Vec<String> = points
         .into_par_iter()
         .progress_with(main_bar)
-        .map(move |x| generate_synthetic_for_x(x, &multi_progress, &path, &info))
+        .map(move |(x, y)| generate_synthetic_for_point(x, y, &multi_progress, &path, dataset, &info))
         .map(|r| r.err())
         .flatten()
         .collect();
@@ -149,39 +157,49 @@ fn generate_synthetic_dataset(info: &SyntheticDataset, path: &PathBuf)
     }
 }
 
-fn generate_synthetic_for_x(
+fn generate_synthetic_for_point(
     x: u32,
+    y: Option<u32>,
     multi_progress: &MultiProgress,
     path: &PathBuf,
+    dataset: &DatasetInfo,
     info: &SyntheticDataset) -> Result<(), String>
 {
-    let xdir = path.join(x.to_string());
-    fs::create_dir_all(&xdir)
-        .map_err(|e| format!(
-            "failed to create directory {}:\n{}",
-            xdir.to_str().unwrap_or("<unknown>"),
-            e.to_string()
-        ))?;
+    let instance_dirs = benchmark::instance_dirs(dataset, &benchmark::point_dir(path, x, y));
+    for dir in &instance_dirs {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!(
+                "failed to create directory {}:\n{}",
+                dir.to_str().unwrap_or("<unknown>"),
+                e.to_string()
+            ))?;
+    }
+    let datafiles: Vec<(&PathBuf, usize)> = instance_dirs.iter()
+        .flat_map(|dir| (0..info.gen_count).map(move |i| (dir, i)))
+        .collect();
 
-    let label = format!(
+    let mut label = format!(
         "    {}: {:10} ",
         format_xlabel(info.vary),
         format_x(x, &info)
     );
+    if let (Some(sweep), Some(y)) = (&info.vary_y, y) {
+        label += &format!("{}: {:10} ", format_xlabel(sweep.vary), format_y(y, info));
+    }
     let style = ProgressStyle::with_template(&(label + "[{bar}] {pos}/{len}"))
         .map_err(|e| e.to_string())?
         .progress_chars("##-");
 
-    let bar = ProgressBar::new(info.gen_count as u64)
+    let bar = ProgressBar::new(datafiles.len() as u64)
         .with_style(style);
     let bar = multi_progress.add(bar);
 
-    let props = benchmark::props_at_x(info, x);
+    let props = benchmark::props_at(info, x, y);
 
-    let errors: Vec<String> = (0..info.gen_count)
+    let errors: Vec<String> = datafiles
         .into_par_iter()
         .progress_with(bar)
-        .map(|i| generate_synthetic_datafile(&props, &xdir, i))
+        .map(|(dir, i)| generate_synthetic_datafile(&props, dir, i))
         .map(|r| r.err())
         .flatten()
         .collect();
@@ -203,7 +221,7 @@ fn generate_synthetic_datafile(
     xdir: &PathBuf,
     i: usize) -> Result<(), String>
 {
-    let sets = generate_synthetic_intersection(&props);
+    let sets = generate_synthetic_intersection(&props, i)?;
 
     let pair_path = xdir.join(i.to_string());
 
@@ -220,14 +238,20 @@ fn generate_synthetic_datafile(
     Ok(())
 }
 
-fn generate_synthetic_intersection(props: &IntersectionInfo)
-    -> Vec<DatafileSet>
+fn generate_synthetic_intersection(props: &IntersectionInfo, index: usize)
+    -> Result<Vec<DatafileSet>, String>
 {
-    if props.set_count == 2 {
+    if let Some(seed) = props.seed {
+        generators::gen_hashed(props, seed, index)
+    }
+    else if props.exact {
+        generators::gen_exact(props)
+    }
+    else if props.set_count == 2 {
         let (set_a, set_b) = generators::gen_twoset(props);
-        vec![set_a, set_b]
+        Ok(vec![set_a, set_b])
     }
     else {
-        generators::gen_kset(props)
+        Ok(generators::gen_kset(props))
     }
 }
@@ -0,0 +1,596 @@
+//! Microbenchmarks the FESIA segment kernels and set construction, the
+//! shuffling algorithms and the 16-bit element algorithms in isolation on
+//! synthetic inputs, reporting cycles per input element so kernel-level
+//! regressions show up without running full experiments. The merging and
+//! all-pairs algorithms are also swept over tiny inputs of up to
+//! `SMALL_LENS` elements, where fixed overheads dominate. Bloom-filtered
+//! galloping is reported separately, with the false positive rate measured
+//! for each filter size.
+//!
+//! Cycles are read from the time-stamp counter, which ticks at a constant
+//! reference frequency. Disable frequency scaling for stable results.
+//!
+//! The `kernel_*` functions are never inlined so their code can be inspected
+//! with cargo-show-asm, e.g.,
+//! `cargo asm -p benchmark --bin kernel-bench kernel_fesia_avx2`.
+
+use clap::Parser;
+use colored::*;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::index::sample};
+use setops::{visitor::Counter, bloom::BlockedBloom};
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+use setops::intersect::{
+    self,
+    fesia::{SegmentIntersect, SegmentIntersectSse},
+};
+#[cfg(all(feature = "simd", target_feature = "avx2"))]
+use setops::intersect::fesia::SegmentIntersectAvx2;
+#[cfg(all(feature = "simd", target_feature = "avx512f"))]
+use setops::intersect::fesia::SegmentIntersectAvx512;
+#[cfg(feature = "simd")]
+use setops::intersect::fesia::{Fesia, IntegerHash, MixHash, SetWithHashScale};
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Only run kernels whose name contains this string, e.g. avx2_3x or
+    /// shuffling.
+    #[arg(long)]
+    filter: Option<String>,
+    /// Number of synthetic segment pairs each kernel cycles through.
+    #[arg(default_value_t = 1024, long)]
+    pool: usize,
+    /// Passes over the pool per trial.
+    #[arg(default_value_t = 64, long)]
+    reps: usize,
+    /// The fastest of this many trials is reported.
+    #[arg(default_value_t = 5, long)]
+    trials: usize,
+    /// Length of each input of the shuffling algorithms.
+    #[arg(default_value_t = 1024, long)]
+    shuffling_len: usize,
+    /// Elements of each set built by the FESIA build rows.
+    #[arg(default_value_t = 1 << 16, long)]
+    build_len: usize,
+    /// Length of the small set of the Bloom-filtered galloping rows.
+    #[arg(default_value_t = 256, long)]
+    bloom_small_len: usize,
+    /// Ratio of the large set's length to the small set's in those rows.
+    #[arg(default_value_t = 1024, long)]
+    bloom_skew: usize,
+    #[arg(default_value_t = 0, long)]
+    seed: u64,
+}
+
+struct Row {
+    kernel: String,
+    sizes: (usize, usize),
+    cycles_per_call: f64,
+}
+
+impl Row {
+    fn cycles_per_element(&self) -> f64 {
+        self.cycles_per_call / (self.sizes.0 + self.sizes.1) as f64
+    }
+}
+
+struct BloomRow {
+    // None for plain galloping.
+    bits_per_element: Option<usize>,
+    false_positive_rate: f64,
+    cycles_per_call: f64,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    if cfg!(debug_assertions) {
+        println!("{}", "warning: running in debug mode".yellow().bold());
+    }
+    if !cfg!(target_arch = "x86_64") {
+        println!("{}", "error: kernel-bench requires x86-64".red().bold());
+        return;
+    }
+
+    let mut rng = StdRng::seed_from_u64(cli.seed);
+    let mut rows = Vec::new();
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    rows.extend(bench_fesia(&cli, &mut rng, "sse", 4, kernel_fesia_sse));
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    rows.extend(bench_fesia(&cli, &mut rng, "avx2", 8, kernel_fesia_avx2));
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    rows.extend(bench_fesia(&cli, &mut rng, "avx512", 16, kernel_fesia_avx512));
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    rows.extend(bench_shuffling(&cli, &mut rng, "shuffling_sse", kernel_shuffling_sse));
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    rows.extend(bench_shuffling(&cli, &mut rng, "shuffling_avx2", kernel_shuffling_avx2));
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    rows.extend(bench_shuffling(&cli, &mut rng, "shuffling_avx512", kernel_shuffling_avx512));
+
+    rows.extend(bench_small(&cli, &mut rng, "branchless_merge", kernel_merge));
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    {
+        rows.extend(bench_small(&cli, &mut rng, "shuffling_sse", kernel_shuffling_sse));
+        rows.extend(bench_small(&cli, &mut rng, "tiny_sse", kernel_tiny_sse));
+    }
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    {
+        rows.extend(bench_small(&cli, &mut rng, "shuffling_avx2", kernel_shuffling_avx2));
+        rows.extend(bench_small(&cli, &mut rng, "tiny_avx2", kernel_tiny_avx2));
+    }
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    {
+        rows.extend(bench_small(&cli, &mut rng, "shuffling_avx512", kernel_shuffling_avx512));
+        rows.extend(bench_small(&cli, &mut rng, "tiny_avx512", kernel_tiny_avx512));
+    }
+
+    #[cfg(feature = "simd")]
+    {
+        rows.extend(bench_fesia_build::<MixHash>(&cli, &mut rng, "fesia_build_mix"));
+        rows.extend(bench_fesia_build::<ScalarMixHash>(&cli, &mut rng, "fesia_build_mix_scalar"));
+    }
+
+    rows.extend(bench_u16(&cli, &mut rng, "merge_u16", kernel_merge_u16));
+    #[cfg(all(feature = "simd", target_feature = "sse4.2"))]
+    rows.extend(bench_u16(&cli, &mut rng, "sttni_u16", kernel_sttni_u16));
+
+    let bloom_rows = bench_bloom(&cli, &mut rng);
+
+    if rows.is_empty() && bloom_rows.is_empty() {
+        println!("{}", "warning: no kernels enabled by this build and filter".yellow());
+        return;
+    }
+
+    if !rows.is_empty() {
+        println!("{:<20} {:>9} {:>12} {:>12}", "kernel", "sizes", "cycles/call", "cycles/elem");
+        for row in &rows {
+            println!("{:<20} {:>9} {:>12.1} {:>12.3}",
+                row.kernel,
+                format!("{}x{}", row.sizes.0, row.sizes.1),
+                row.cycles_per_call,
+                row.cycles_per_element());
+        }
+    }
+
+    if let Some(galloping) = bloom_rows.first() {
+        println!();
+        println!("{:<20} {:>9} {:>12} {:>12}", "kernel", "fp rate", "cycles/call", "speedup");
+        for row in &bloom_rows {
+            let kernel = row.bits_per_element
+                .map_or("galloping".to_string(), |bits| format!("bloom{bits}_galloping"));
+            println!("{:<20} {:>9.4} {:>12.1} {:>12.2}",
+                kernel,
+                row.false_positive_rate,
+                row.cycles_per_call,
+                galloping.cycles_per_call / row.cycles_per_call);
+        }
+    }
+}
+
+fn selected(cli: &Cli, kernel: &str) -> bool {
+    cli.filter.as_ref().is_none_or(|f| kernel.contains(f.as_str()))
+}
+
+type SegmentKernel = fn(&[i32], &[i32], usize, usize, &mut Counter);
+
+// Each FESIA kernel `m x n` intersects up to m elements against up to n,
+// where n is one or two vectors of `lanes` elements. The segment intersection
+// dispatches segment sizes (m, lanes) to the former and (m, 2*lanes - 1) to
+// the latter.
+fn bench_fesia(
+    cli: &Cli,
+    rng: &mut impl Rng,
+    simd: &str,
+    lanes: usize,
+    kernel: SegmentKernel) -> Vec<Row>
+{
+    let mut shapes: Vec<(usize, usize, usize)> = (1..=lanes)
+        .map(|m| (m, lanes, lanes))
+        .collect();
+    shapes.extend((1..2 * lanes).map(|m| (m, 2 * lanes, 2 * lanes - 1)));
+
+    let mut rows = Vec::new();
+    for (m, n, size_b) in shapes {
+        let name = format!("fesia_{simd}_{m}x{n}");
+        if !selected(cli, &name) {
+            continue;
+        }
+        // Kernels may read a full 2*lanes elements beyond a segment's start.
+        let stride = 2 * lanes;
+        let (set_a, set_b) = segment_pool(rng, cli.pool, stride, m, size_b);
+
+        let cycles = min_cycles(cli.trials, || {
+            let mut counter = Counter::new();
+            for _ in 0..cli.reps {
+                for i in 0..cli.pool {
+                    let range = i * stride..(i + 1) * stride;
+                    kernel(&set_a[range.clone()], &set_b[range], m, size_b, &mut counter);
+                }
+            }
+            std::hint::black_box(counter.count());
+        });
+
+        rows.push(Row {
+            kernel: name,
+            sizes: (m, size_b),
+            cycles_per_call: cycles as f64 / (cli.reps * cli.pool) as f64,
+        });
+    }
+
+    // Random sizes on both sides so the kernel dispatch itself is measured,
+    // i.e., its indirect branch is no longer perfectly predicted.
+    let name = format!("fesia_{simd}_mixed");
+    if selected(cli, &name) {
+        let stride = 2 * lanes;
+        let mut sizes = Vec::with_capacity(cli.pool);
+        let (mut set_a, mut set_b) = (Vec::new(), Vec::new());
+        for _ in 0..cli.pool {
+            let size_a = rng.gen_range(1..stride);
+            let size_b = rng.gen_range(1..stride);
+            let (a, b) = segment_pool(rng, 1, stride, size_a, size_b);
+            set_a.extend(a);
+            set_b.extend(b);
+            sizes.push((size_a, size_b));
+        }
+
+        let cycles = min_cycles(cli.trials, || {
+            let mut counter = Counter::new();
+            for _ in 0..cli.reps {
+                for (i, &(size_a, size_b)) in sizes.iter().enumerate() {
+                    let range = i * stride..(i + 1) * stride;
+                    kernel(&set_a[range.clone()], &set_b[range], size_a, size_b, &mut counter);
+                }
+            }
+            std::hint::black_box(counter.count());
+        });
+
+        rows.push(Row {
+            kernel: name,
+            // Mean sizes.
+            sizes: (stride / 2, stride / 2),
+            cycles_per_call: cycles as f64 / (cli.reps * cli.pool) as f64,
+        });
+    }
+    rows
+}
+
+// Builds `count` segment pairs laid out every `stride` elements. Segments draw
+// from a shared range so about half of the smaller segment matches, and the
+// padding after each segment never matches the other side, as with elements of
+// successive FESIA segments.
+fn segment_pool(
+    rng: &mut impl Rng,
+    count: usize,
+    stride: usize,
+    size_a: usize,
+    size_b: usize) -> (Vec<i32>, Vec<i32>)
+{
+    let mut set_a = Vec::with_capacity(count * stride);
+    let mut set_b = Vec::with_capacity(count * stride);
+    let universe = size_a.max(size_b) * 2;
+
+    for _ in 0..count {
+        set_a.extend(sample(rng, universe, size_a).into_iter().map(|v| v as i32));
+        set_a.extend((size_a..stride).map(|i| -1 - i as i32));
+        set_b.extend(sample(rng, universe, size_b).into_iter().map(|v| v as i32));
+        set_b.extend((size_b..stride).map(|i| i32::MIN + i as i32));
+    }
+    (set_a, set_b)
+}
+
+// `MixHash` without its vectorised `hash_simd`, i.e., hashing each lane on its
+// own, to show what vectorised hashing gains when building sets.
+#[cfg(feature = "simd")]
+struct ScalarMixHash;
+
+#[cfg(feature = "simd")]
+impl IntegerHash for ScalarMixHash {
+    fn hash(item: i32) -> i32 {
+        MixHash::hash(item)
+    }
+}
+
+// Builds a FESIA set of `build_len` elements, as `FesiaAlgorithm` does before
+// intersecting. Cycles per element are per element of the built set.
+#[cfg(feature = "simd")]
+fn bench_fesia_build<H: IntegerHash>(
+    cli: &Cli,
+    rng: &mut impl Rng,
+    name: &str) -> Vec<Row>
+{
+    if !selected(cli, name) {
+        return Vec::new();
+    }
+
+    let len = cli.build_len;
+    let set = sorted_sample(rng, len * 4, len);
+    let reps = (cli.reps * cli.pool / len).max(1);
+
+    let cycles = min_cycles(cli.trials, || {
+        for _ in 0..reps {
+            let fesia = Fesia::<H, i32, 8>::from_sorted(&set, 2.0);
+            std::hint::black_box(&fesia);
+        }
+    });
+
+    vec![Row {
+        kernel: name.to_string(),
+        sizes: (len, 0),
+        cycles_per_call: cycles as f64 / reps as f64,
+    }]
+}
+
+type Intersect = fn(&[i32], &[i32], &mut Counter);
+
+fn bench_shuffling(
+    cli: &Cli,
+    rng: &mut impl Rng,
+    name: &str,
+    kernel: Intersect) -> Vec<Row>
+{
+    if !selected(cli, name) {
+        return Vec::new();
+    }
+
+    const PAIRS: usize = 16;
+    let len = cli.shuffling_len;
+    let pairs: Vec<(Vec<i32>, Vec<i32>)> = (0..PAIRS)
+        .map(|_| (sorted_sample(rng, len * 2, len), sorted_sample(rng, len * 2, len)))
+        .collect();
+    let reps = (cli.reps * cli.pool / len).max(1);
+
+    let cycles = min_cycles(cli.trials, || {
+        let mut counter = Counter::new();
+        for _ in 0..reps {
+            for (set_a, set_b) in &pairs {
+                kernel(set_a, set_b, &mut counter);
+            }
+        }
+        std::hint::black_box(counter.count());
+    });
+
+    vec![Row {
+        kernel: name.to_string(),
+        sizes: (len, len),
+        cycles_per_call: cycles as f64 / (reps * PAIRS) as f64,
+    }]
+}
+
+// Lengths of both inputs in the sweep over tiny inputs.
+const SMALL_LENS: [usize; 8] = [4, 8, 16, 24, 32, 48, 64, 128];
+
+// Equal-length inputs drawn from twice their length, as in `bench_shuffling`,
+// for each of `SMALL_LENS`. Many pairs are cycled through so that branches on
+// the lengths and the results are not perfectly predicted.
+fn bench_small(
+    cli: &Cli,
+    rng: &mut impl Rng,
+    name: &str,
+    kernel: Intersect) -> Vec<Row>
+{
+    if !selected(cli, name) && !selected(cli, "small") {
+        return Vec::new();
+    }
+
+    let mut rows = Vec::new();
+    for len in SMALL_LENS {
+        let pairs: Vec<(Vec<i32>, Vec<i32>)> = (0..cli.pool)
+            .map(|_| (sorted_sample(rng, len * 2, len), sorted_sample(rng, len * 2, len)))
+            .collect();
+
+        let cycles = min_cycles(cli.trials, || {
+            let mut counter = Counter::new();
+            for _ in 0..cli.reps {
+                for (set_a, set_b) in &pairs {
+                    kernel(set_a, set_b, &mut counter);
+                }
+            }
+            std::hint::black_box(counter.count());
+        });
+
+        rows.push(Row {
+            kernel: name.to_string(),
+            sizes: (len, len),
+            cycles_per_call: cycles as f64 / (cli.reps * cli.pool) as f64,
+        });
+    }
+    rows
+}
+
+// Galloping a small set into one `bloom_skew` times larger, unfiltered and
+// filtered with a range of filter sizes. Both sets are drawn from the same
+// universe, so about a quarter of the small set is in the large set, and the
+// false positive rate is measured over a separate sample of absent elements.
+fn bench_bloom(cli: &Cli, rng: &mut impl Rng) -> Vec<BloomRow> {
+    if !selected(cli, "bloom") && !selected(cli, "galloping") {
+        return Vec::new();
+    }
+
+    let small_len = cli.bloom_small_len;
+    let large_len = small_len * cli.bloom_skew;
+    let large = sorted_sample(rng, large_len * 4, large_len);
+    let small = sorted_sample(rng, large_len * 4, small_len);
+    let absent: Vec<i32> = (0..1 << 16)
+        .map(|_| rng.gen_range(0..(large_len * 4) as i32))
+        .filter(|item| large.binary_search(item).is_err())
+        .collect();
+
+    let reps = (cli.reps * cli.pool / small_len).max(1);
+    let time = |filter: Option<&BlockedBloom>| min_cycles(cli.trials, || {
+        let mut counter = Counter::new();
+        for _ in 0..reps {
+            match filter {
+                Some(filter) => setops::intersect::bloom_galloping(&small, &large, filter, &mut counter),
+                None => setops::intersect::galloping(&small, &large, &mut counter),
+            }
+        }
+        std::hint::black_box(counter.count());
+    }) as f64 / reps as f64;
+
+    let mut rows = vec![BloomRow {
+        bits_per_element: None,
+        false_positive_rate: 1.0,
+        cycles_per_call: time(None),
+    }];
+    for bits_per_element in [2, 4, 8, 16, 32] {
+        let filter = BlockedBloom::with_bits_per_element(&large, bits_per_element);
+        let false_positives = absent.iter().filter(|&&item| filter.contains(item)).count();
+        rows.push(BloomRow {
+            bits_per_element: Some(bits_per_element),
+            false_positive_rate: false_positives as f64 / absent.len() as f64,
+            cycles_per_call: time(Some(&filter)),
+        });
+    }
+    rows
+}
+
+type IntersectU16 = fn(&[u16], &[u16], &mut Counter);
+
+// 16-bit elements, e.g., the low halves of one chunk of a Roaring-style set.
+// Inputs are capped at the 2^16 values of a chunk.
+fn bench_u16(
+    cli: &Cli,
+    rng: &mut impl Rng,
+    name: &str,
+    kernel: IntersectU16) -> Vec<Row>
+{
+    if !selected(cli, name) {
+        return Vec::new();
+    }
+
+    const PAIRS: usize = 16;
+    let len = cli.shuffling_len.min(1 << 15);
+    let pairs: Vec<(Vec<u16>, Vec<u16>)> = (0..PAIRS)
+        .map(|_| (sorted_sample_u16(rng, len * 2, len), sorted_sample_u16(rng, len * 2, len)))
+        .collect();
+    let reps = (cli.reps * cli.pool / len).max(1);
+
+    let cycles = min_cycles(cli.trials, || {
+        let mut counter = Counter::new();
+        for _ in 0..reps {
+            for (set_a, set_b) in &pairs {
+                kernel(set_a, set_b, &mut counter);
+            }
+        }
+        std::hint::black_box(counter.count());
+    });
+
+    vec![Row {
+        kernel: name.to_string(),
+        sizes: (len, len),
+        cycles_per_call: cycles as f64 / (reps * PAIRS) as f64,
+    }]
+}
+
+fn sorted_sample_u16(rng: &mut impl Rng, universe: usize, len: usize) -> Vec<u16> {
+    let mut set: Vec<u16> = sample(rng, universe, len).into_iter().map(|v| v as u16).collect();
+    set.sort_unstable();
+    set
+}
+
+fn sorted_sample(rng: &mut impl Rng, universe: usize, len: usize) -> Vec<i32> {
+    let mut set: Vec<i32> = sample(rng, universe, len).into_iter().map(|v| v as i32).collect();
+    set.sort_unstable();
+    set
+}
+
+fn min_cycles(trials: usize, mut run: impl FnMut()) -> u64 {
+    // Warm up caches and branch predictors.
+    run();
+    (0..trials.max(1))
+        .map(|_| {
+            let start = rdtsc();
+            run();
+            rdtsc() - start
+        })
+        .min()
+        .unwrap()
+}
+
+#[cfg(target_arch = "x86_64")]
+fn rdtsc() -> u64 {
+    unsafe { std::arch::x86_64::_rdtsc() }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn rdtsc() -> u64 {
+    0
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+#[inline(never)]
+pub fn kernel_fesia_sse(
+    set_a: &[i32], set_b: &[i32], size_a: usize, size_b: usize, visitor: &mut Counter)
+{
+    SegmentIntersectSse::intersect(set_a, set_b, size_a, size_b, visitor);
+}
+
+#[cfg(all(feature = "simd", target_feature = "avx2"))]
+#[inline(never)]
+pub fn kernel_fesia_avx2(
+    set_a: &[i32], set_b: &[i32], size_a: usize, size_b: usize, visitor: &mut Counter)
+{
+    SegmentIntersectAvx2::intersect(set_a, set_b, size_a, size_b, visitor);
+}
+
+#[cfg(all(feature = "simd", target_feature = "avx512f"))]
+#[inline(never)]
+pub fn kernel_fesia_avx512(
+    set_a: &[i32], set_b: &[i32], size_a: usize, size_b: usize, visitor: &mut Counter)
+{
+    SegmentIntersectAvx512::intersect(set_a, set_b, size_a, size_b, visitor);
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+#[inline(never)]
+pub fn kernel_shuffling_sse(set_a: &[i32], set_b: &[i32], visitor: &mut Counter) {
+    intersect::shuffling_sse(set_a, set_b, visitor);
+}
+
+#[cfg(all(feature = "simd", target_feature = "avx2"))]
+#[inline(never)]
+pub fn kernel_shuffling_avx2(set_a: &[i32], set_b: &[i32], visitor: &mut Counter) {
+    intersect::shuffling_avx2(set_a, set_b, visitor);
+}
+
+#[cfg(all(feature = "simd", target_feature = "avx512f"))]
+#[inline(never)]
+pub fn kernel_shuffling_avx512(set_a: &[i32], set_b: &[i32], visitor: &mut Counter) {
+    intersect::shuffling_avx512(set_a, set_b, visitor);
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+#[inline(never)]
+pub fn kernel_tiny_sse(set_a: &[i32], set_b: &[i32], visitor: &mut Counter) {
+    intersect::tiny_sse(set_a, set_b, visitor);
+}
+
+#[cfg(all(feature = "simd", target_feature = "avx2"))]
+#[inline(never)]
+pub fn kernel_tiny_avx2(set_a: &[i32], set_b: &[i32], visitor: &mut Counter) {
+    intersect::tiny_avx2(set_a, set_b, visitor);
+}
+
+#[cfg(all(feature = "simd", target_feature = "avx512f"))]
+#[inline(never)]
+pub fn kernel_tiny_avx512(set_a: &[i32], set_b: &[i32], visitor: &mut Counter) {
+    intersect::tiny_avx512(set_a, set_b, visitor);
+}
+
+#[inline(never)]
+pub fn kernel_merge(set_a: &[i32], set_b: &[i32], visitor: &mut Counter) {
+    setops::intersect::branchless_merge(set_a, set_b, visitor);
+}
+
+#[inline(never)]
+pub fn kernel_merge_u16(set_a: &[u16], set_b: &[u16], visitor: &mut Counter) {
+    setops::intersect::branchless_merge(set_a, set_b, visitor);
+}
+
+#[cfg(all(feature = "simd", target_feature = "sse4.2"))]
+#[inline(never)]
+pub fn kernel_sttni_u16(set_a: &[u16], set_b: &[u16], visitor: &mut Counter) {
+    intersect::sttni_u16(set_a, set_b, visitor);
+}
@@ -72,11 +72,14 @@ fn test_on_dataset(cli: &Cli, real_dataset: &str) -> Result<(), String> {
     run_twoset_tests(&all_sets, cli.test_count, &twoset_array_algorithms, test_twoset_array);
     run_twoset_tests(&all_sets, cli.test_count, &twoset_bsr_algorithms,   test_twoset_bsr);
 
+    #[cfg(feature = "roaring")]
     run_twoset_test(&all_sets, cli.test_count, "croaring",  |a, b| test_croaring_2set(a, b));
     // run_twoset_test(&all_sets, cli.test_count, "roaringrs", |a, b| test_roaringrs_2set(a, b));
 
     println!("k-set:");
     run_kset_tests(&all_sets, cli.test_count, &twoset_array_algorithms, |sets, f| test_svs(sets, f));
+    run_kset_test(&all_sets, cli.test_count,
+        "svs", |sets| test_kset(sets, intersect::svs));
     run_kset_test(&all_sets, cli.test_count,
         "baezayates_k", |sets| test_kset(sets, intersect::baezayates_k));
     run_kset_test(&all_sets, cli.test_count,
@@ -84,6 +87,7 @@ fn test_on_dataset(cli: &Cli, real_dataset: &str) -> Result<(), String> {
     run_kset_test(&all_sets, cli.test_count,
         "small_adaptive_sorted", |sets| test_kset(sets, intersect::small_adaptive_sorted));
 
+    #[cfg(feature = "roaring")]
     run_kset_test(&all_sets, cli.test_count, "croaring_svs", |sets| test_croaring_svs(sets));
     // run_kset_test(&all_sets, cli.test_count, "roaringrs_svs", |sets| test_roaringrs_svs(sets));
 
@@ -284,6 +288,7 @@ fn test_svs<S: AsRef<[i32]>>(
     actual == expected
 }
 
+#[cfg(feature = "roaring")]
 fn test_croaring_2set(set_a: &[i32], set_b: &[i32]) -> bool {
     use croaring::Bitmap;
 
@@ -300,6 +305,7 @@ fn test_croaring_2set(set_a: &[i32], set_b: &[i32]) -> bool {
     util::slice_u32_to_i32(&actual) == expected
 }
 
+#[cfg(feature = "roaring")]
 fn test_croaring_svs<S: AsRef<[i32]>>(sets: &[S]) -> bool {
     use croaring::Bitmap;
     assert!(sets.len() >= 2);
@@ -397,12 +403,13 @@ const TWOSET: [TwoSetAlgorithm; 6] = [
     (intersect::baezayates, "baezayates"),
 ];
 
-const TWOSET_SSE: [TwoSetAlgorithm; 6] = [
+const TWOSET_SSE: [TwoSetAlgorithm; 7] = [
     (intersect::shuffling_sse, "shuffling_sse"),
     (intersect::broadcast_sse, "broadcast_sse"),
     (intersect::galloping_sse, "galloping_sse"),
     (intersect::bmiss, "bmiss"),
     (intersect::bmiss_sttni, "bmiss_sttni"),
+    (intersect::bmiss_galloping, "bmiss_galloping"),
     (intersect::qfilter, "qfilter"),
 ];
 
@@ -0,0 +1,241 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use benchmark::{
+    fmt_open_err, path_str, realdata,
+    datafile::DatafileSet,
+    timer::latency::Histogram,
+    trace::{self, Trace, ReplayResults, ReplayRun},
+};
+use clap::{Parser, Subcommand};
+use colored::*;
+use rand::{SeedableRng, rngs::StdRng};
+use setops::{
+    intersect::{self, Intersect2},
+    visitor::{
+        Visitor, SimdVisitor4, SimdVisitor8, SimdVisitor16,
+        UnsafeWriter, Counter, Clearable,
+    },
+};
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[arg(default_value = "datasets/", long)]
+    datasets: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Synthesise a trace over the sets of a real dataset, with Zipfian set
+    /// popularity and Poisson arrivals.
+    Synth {
+        /// Real dataset source, e.g. webdocs.
+        source: String,
+        #[arg(long)]
+        out: PathBuf,
+        #[arg(default_value_t = 100_000, long)]
+        queries: usize,
+        /// Zipf exponent of set popularity. 0 chooses sets uniformly.
+        #[arg(default_value_t = 1.0, long)]
+        exponent: f64,
+        /// Mean arrival rate in queries per second.
+        #[arg(default_value_t = 10_000.0, long)]
+        rate: f64,
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Replay a trace with each algorithm, measuring throughput and latency.
+    Replay {
+        /// Real dataset source the trace was made for.
+        source: String,
+        #[arg(long)]
+        trace: PathBuf,
+        /// 2-set algorithms on sorted arrays, e.g. shuffling_avx2.
+        #[arg(long, num_args = 1.., required = true)]
+        algorithms: Vec<String>,
+        /// Issue queries at their trace times rather than back to back.
+        #[arg(long, action)]
+        paced: bool,
+        /// Replay the trace once without measuring before each algorithm.
+        #[arg(long, action)]
+        warmup: bool,
+        #[arg(long, action)]
+        count_only: bool,
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    if cfg!(debug_assertions) {
+        println!("{}", "warning: running in debug mode".yellow().bold());
+    }
+
+    let result = match &cli.command {
+        Command::Synth { source, out, queries, exponent, rate, seed } =>
+            synth(&cli, source, out, *queries, *exponent, *rate, *seed),
+        Command::Replay { source, trace, algorithms, paced, warmup, count_only, out } =>
+            replay(&cli, source, trace, algorithms, *paced, *warmup, *count_only, out.as_ref()),
+    };
+
+    if let Err(e) = result {
+        let msg = format!("error: {e}");
+        println!("{}", msg.red().bold());
+    }
+}
+
+fn synth(
+    cli: &Cli,
+    source: &str,
+    out: &PathBuf,
+    queries: usize,
+    exponent: f64,
+    rate: f64,
+    seed: Option<u64>) -> Result<(), String>
+{
+    if exponent < 0.0 || rate <= 0.0 {
+        return Err("exponent must not be negative and rate must be positive".to_string());
+    }
+    let sets = realdata::load_sets(&cli.datasets, source)?;
+    if sets.len() < 2 {
+        return Err(format!("{source} has fewer than two sets"));
+    }
+
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let trace = trace::zipf_trace(sets.len(), queries, exponent, rate, &mut rng);
+
+    let file = File::create(out).map_err(|e| fmt_open_err(e, out))?;
+    trace.write(BufWriter::new(file))
+        .map_err(|e| format!("failed to write {}: {}", path_str(out), e))?;
+
+    println!("wrote {} queries over {:.1}s to {}",
+        trace.len(), trace.duration_us() as f64 / 1e6, path_str(out));
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn replay(
+    cli: &Cli,
+    source: &str,
+    trace_path: &PathBuf,
+    algorithms: &[String],
+    paced: bool,
+    warmup: bool,
+    count_only: bool,
+    out: Option<&PathBuf>) -> Result<(), String>
+{
+    let sets = realdata::load_sets(&cli.datasets, source)?;
+
+    let file = File::open(trace_path).map_err(|e| fmt_open_err(e, trace_path))?;
+    let trace = Trace::read(BufReader::new(file))
+        .map_err(|e| format!("invalid trace {}: {}", path_str(trace_path), e))?;
+    trace.validate(sets.len())
+        .map_err(|e| format!("trace {} does not match {}: {}", path_str(trace_path), source, e))?;
+
+    let available = intersect::dynamic::names();
+    if let Some(unknown) = algorithms.iter().find(|a| !available.contains(&a.as_str())) {
+        return Err(format!(
+            "unknown algorithm {unknown} (replay supports 2-set algorithms on sorted arrays)"));
+    }
+
+    let mut runs = Vec::new();
+    for name in algorithms {
+        let run = if count_only {
+            let intersect = lookup::<Counter>(name)?;
+            replay_algorithm(&sets, &trace, paced, warmup, Counter::new(), intersect,
+                |counter| counter.count())
+        }
+        else {
+            let capacity = sets.iter().map(|s| s.len()).max().unwrap_or(0);
+            let intersect = lookup::<UnsafeWriter<i32>>(name)?;
+            replay_algorithm(&sets, &trace, paced, warmup,
+                UnsafeWriter::with_capacity(capacity), intersect,
+                |writer| writer.as_ref().len())
+        };
+
+        println!("{}: {:.0} queries/s, latency p50 {}ns p99 {}ns max {}ns",
+            name.green(), run.queries_per_second,
+            run.latency.p50, run.latency.p99, run.latency.max);
+
+        runs.push(ReplayRun { algorithm: name.clone(), ..run });
+    }
+
+    if let Some(out) = out {
+        let results = ReplayResults {
+            trace: path_str(trace_path).to_string(),
+            source: source.to_string(),
+            paced,
+            runs,
+        };
+        let file = File::create(out).map_err(|e| fmt_open_err(e, out))?;
+        serde_json::to_writer(file, &results)
+            .map_err(|e| format!("failed to write {}: {}", path_str(out), e))?;
+    }
+    Ok(())
+}
+
+fn lookup<V>(name: &str) -> Result<Intersect2<[i32], V>, String>
+where
+    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16,
+{
+    intersect::dynamic::lookup(name).ok_or_else(|| format!("unknown algorithm {name}"))
+}
+
+// Runs every query of `trace` through one visitor, cleared between queries.
+fn replay_algorithm<V: Clearable>(
+    sets: &[DatafileSet],
+    trace: &Trace,
+    paced: bool,
+    warmup: bool,
+    mut visitor: V,
+    intersect: Intersect2<[i32], V>,
+    result_len: impl Fn(&V) -> usize) -> ReplayRun
+{
+    if warmup {
+        for q in &trace.queries {
+            visitor.clear();
+            intersect(&sets[q.set_a as usize], &sets[q.set_b as usize], &mut visitor);
+        }
+    }
+
+    let mut histogram = Histogram::new();
+    let mut total_len = 0;
+
+    let start = Instant::now();
+    for q in &trace.queries {
+        let scheduled = start + Duration::from_micros(q.time_us);
+        if paced {
+            while Instant::now() < scheduled {
+                std::hint::spin_loop();
+            }
+        }
+
+        let query_start = if paced { scheduled } else { Instant::now() };
+        visitor.clear();
+        intersect(&sets[q.set_a as usize], &sets[q.set_b as usize], &mut visitor);
+        histogram.record(query_start.elapsed().as_nanos() as u64);
+
+        total_len += result_len(&visitor) as u64;
+    }
+    let elapsed = start.elapsed();
+
+    ReplayRun {
+        algorithm: String::new(),
+        queries: trace.len(),
+        elapsed_ns: elapsed.as_nanos() as u64,
+        queries_per_second: trace.len() as f64 / elapsed.as_secs_f64(),
+        result_len: total_len,
+        latency: histogram.to_latency(Vec::new()),
+    }
+}
@@ -0,0 +1,204 @@
+//! Compares two results files (e.g., before and after a kernel change) cell by
+//! cell, where a cell is the runs of one algorithm at one (x, y) point of a
+//! dataset. The speedup of a cell is the ratio of mean times, baseline over
+//! candidate, with a confidence interval from the normal approximation of the
+//! log of the ratio (i.e., the delta method on each mean). A cell is flagged
+//! when the interval excludes 1 and the speedup differs from 1 by at least a
+//! threshold, so that tiny but consistent differences are not reported.
+
+use crate::schema::{AlgorithmId, DatasetId, Results, PERCENT_F};
+
+/// Two-sided 95% quantile of the standard normal distribution.
+pub const Z_95: f64 = 1.959964;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellComparison {
+    pub dataset: DatasetId,
+    pub algorithm: AlgorithmId,
+    pub x: u32,
+    pub y: Option<u32>,
+    /// Mean nanoseconds in each file.
+    pub baseline: f64,
+    pub candidate: f64,
+    /// Baseline time over candidate time, so above 1 is faster.
+    pub speedup: f64,
+    /// 95% confidence interval of the speedup, if both cells have at least two
+    /// samples.
+    pub interval: Option<(f64, f64)>,
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Faster,
+    Slower,
+    /// Not significant, or smaller than the threshold.
+    Same,
+}
+
+/// Compares each cell present in both `baseline` and `candidate`, in order of
+/// dataset, algorithm and point. `threshold` is the smallest relative change
+/// flagged (out of PERCENT).
+pub fn compare(baseline: &Results, candidate: &Results, threshold: u32) -> Vec<CellComparison> {
+    let threshold = threshold as f64 / PERCENT_F;
+    let mut cells = Vec::new();
+
+    let mut datasets: Vec<&DatasetId> = baseline.datasets.keys()
+        .filter(|d| candidate.datasets.contains_key(*d))
+        .collect();
+    datasets.sort();
+
+    for dataset in datasets {
+        let (base_algos, cand_algos) =
+            (&baseline.datasets[dataset].algos, &candidate.datasets[dataset].algos);
+
+        let mut algorithms: Vec<&AlgorithmId> = base_algos.keys()
+            .filter(|a| cand_algos.contains_key(*a))
+            .collect();
+        algorithms.sort();
+
+        for algorithm in algorithms {
+            for base_run in &base_algos[algorithm] {
+                let Some(cand_run) = cand_algos[algorithm].iter()
+                    .find(|r| r.x == base_run.x && r.y == base_run.y)
+                else {
+                    continue;
+                };
+                let (Some(base), Some(cand)) =
+                    (Summary::of(&base_run.times), Summary::of(&cand_run.times))
+                else {
+                    continue;
+                };
+
+                let speedup = base.mean / cand.mean;
+                let interval = base.log_variance()
+                    .zip(cand.log_variance())
+                    .map(|(v_b, v_c)| {
+                        let margin = Z_95 * (v_b + v_c).sqrt();
+                        (speedup * (-margin).exp(), speedup * margin.exp())
+                    });
+
+                let change = match interval {
+                    Some((lo, _)) if lo > 1.0 && speedup >= 1.0 + threshold => Change::Faster,
+                    Some((_, hi)) if hi < 1.0 && speedup <= 1.0 - threshold => Change::Slower,
+                    _ => Change::Same,
+                };
+
+                cells.push(CellComparison {
+                    dataset: dataset.clone(),
+                    algorithm: algorithm.clone(),
+                    x: base_run.x,
+                    y: base_run.y,
+                    baseline: base.mean,
+                    candidate: cand.mean,
+                    speedup,
+                    interval,
+                    change,
+                });
+            }
+        }
+    }
+    cells
+}
+
+/// Geometric mean of the speedups of `cells`.
+pub fn geometric_mean_speedup<'a>(cells: impl IntoIterator<Item = &'a CellComparison>) -> Option<f64> {
+    let (sum, count) = cells.into_iter()
+        .fold((0.0, 0), |(sum, count), c| (sum + c.speedup.ln(), count + 1));
+    (count > 0).then(|| (sum / count as f64).exp())
+}
+
+struct Summary {
+    mean: f64,
+    variance: Option<f64>,
+    count: usize,
+}
+
+impl Summary {
+    fn of(times: &[u64]) -> Option<Self> {
+        if times.is_empty() {
+            return None;
+        }
+        let count = times.len();
+        let mean = times.iter().sum::<u64>() as f64 / count as f64;
+        if mean == 0.0 {
+            return None;
+        }
+        let variance = (count > 1).then(|| {
+            times.iter().map(|&t| (t as f64 - mean).powi(2)).sum::<f64>() / (count - 1) as f64
+        });
+        Some(Self { mean, variance, count })
+    }
+
+    // Variance of the log of the mean.
+    fn log_variance(&self) -> Option<f64> {
+        self.variance.map(|v| v / (self.count as f64 * self.mean * self.mean))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(times: &[(&str, u32, &[u64])]) -> Results {
+        let algos: Vec<String> = times.iter()
+            .map(|(algorithm, x, times)| format!(
+                r#""{algorithm}": [{{"x": {x}, "times": {times:?},
+                    "l1d": {{}}, "l1i": {{}}, "ll": {{}}}}]"#))
+            .collect();
+
+        crate::migrate::parse_results(&format!(r#"{{
+            "experiments": [],
+            "algorithm_sets": {{}},
+            "datasets": {{"d": {{
+                "info": {{"name": "d", "type": "real", "source": "webdocs",
+                    "gen_count": 2, "set_count_start": 2, "set_count_end": 2}},
+                "algos": {{{}}}
+            }}}}
+        }}"#, algos.join(","))).unwrap()
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = results(&[
+            ("a", 2, &[100, 102, 98, 100]),
+            ("b", 2, &[1000, 1001, 999, 1000]),
+            ("c", 2, &[100, 190, 10, 100]),
+            ("only_baseline", 2, &[1]),
+        ]);
+        let candidate = results(&[
+            ("a", 2, &[50, 51, 49, 50]),
+            ("b", 2, &[1010, 1011, 1009, 1010]),
+            ("c", 2, &[200, 380, 20, 200]),
+        ]);
+
+        let cells = compare(&baseline, &candidate, 20);
+        let names: Vec<&str> = cells.iter().map(|c| c.algorithm.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+
+        assert_eq!(cells[0].speedup, 2.0);
+        let (lo, hi) = cells[0].interval.unwrap();
+        assert!(lo < 2.0 && 2.0 < hi && lo > 1.9 && hi < 2.1);
+        assert_eq!(cells[0].change, Change::Faster);
+
+        // Significant, but below the threshold of 2%.
+        assert_eq!(cells[1].speedup, 1000.0 / 1010.0);
+        assert!(cells[1].interval.unwrap().1 < 1.0);
+        assert_eq!(cells[1].change, Change::Same);
+
+        // Half the speed, but too noisy to be significant.
+        assert_eq!(cells[2].speedup, 0.5);
+        assert_eq!(cells[2].change, Change::Same);
+        assert_eq!(compare(&baseline, &candidate, 0)[1].change, Change::Slower);
+
+        let geomean = geometric_mean_speedup(&cells[..1]).unwrap();
+        assert!((geomean - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_single_sample() {
+        let cells = compare(&results(&[("a", 1, &[10])]), &results(&[("a", 1, &[5])]), 0);
+        assert_eq!(cells[0].interval, None);
+        assert_eq!(cells[0].change, Change::Same);
+    }
+}
@@ -0,0 +1,432 @@
+//! Controls for the machine state during a run: pinning the measurement
+//! thread, reading frequency scaling settings, timing a spin loop to detect
+//! frequency changes over long sweeps and monitoring the frequency during each
+//! cell to detect throttling, e.g., by AVX-512 kernels. Also detects the CPU
+//! microarchitecture used to choose between per-machine algorithm lists.
+
+use std::{
+    fs,
+    hint,
+    sync::{Arc, atomic::{AtomicBool, Ordering}},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use crate::schema::{CpuState, FrequencySource, PERCENT_F};
+
+/// Default tolerated spin loop drift, out of PERCENT.
+pub const DEFAULT_MAX_BASELINE_DRIFT: u32 = 50;
+
+/// Default tolerated drop in a cell's mean frequency, out of PERCENT.
+pub const DEFAULT_MAX_FREQUENCY_DROP: u32 = 50;
+
+/// Default milliseconds between frequency samples from sysfs.
+pub const DEFAULT_FREQUENCY_SAMPLE_MS: u64 = 10;
+
+const SPIN_ITERATIONS: u64 = 10_000_000;
+const SPIN_REPEATS: usize = 5;
+
+/// Restricts the calling thread to run only on logical CPU `cpu`.
+#[cfg(target_os = "linux")]
+pub fn pin_to_cpu(cpu: usize) -> Result<(), String> {
+    if cpu >= libc::CPU_SETSIZE as usize {
+        return Err(format!("cannot pin to cpu {cpu}: cpu out of range"));
+    }
+    set_affinity([cpu]).map_err(|e| format!("cannot pin to cpu {cpu}: {e}"))
+}
+
+/// Lets the calling thread run on any online CPU but `cpu`, so that it does
+/// not disturb a thread pinned there. Does nothing with a single CPU.
+#[cfg(target_os = "linux")]
+fn avoid_cpu(cpu: usize) -> Result<(), String> {
+    // SAFETY: sysconf has no preconditions.
+    let online = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) }.max(1) as usize;
+    if online == 1 {
+        return Ok(());
+    }
+    let cpus = (0..online.min(libc::CPU_SETSIZE as usize)).filter(|&c| c != cpu);
+    set_affinity(cpus).map_err(|e| format!("cannot move off cpu {cpu}: {e}"))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn avoid_cpu(_cpu: usize) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn set_affinity(cpus: impl IntoIterator<Item = usize>) -> Result<(), std::io::Error> {
+    // SAFETY: cpu_set_t is a plain bitmask, and callers keep cpus within its
+    // bounds.
+    let result = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for cpu in cpus {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+    if result == 0 {
+        Ok(())
+    }
+    else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_to_cpu(cpu: usize) -> Result<(), String> {
+    Err(format!("cannot pin to cpu {cpu}: thread pinning is only supported on linux"))
+}
+
+/// Returns the CPU the calling thread is currently running on.
+#[cfg(target_os = "linux")]
+pub fn current_cpu() -> usize {
+    // SAFETY: sched_getcpu has no preconditions.
+    let cpu = unsafe { libc::sched_getcpu() };
+    cpu.max(0) as usize
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn current_cpu() -> usize {
+    0
+}
+
+/// Reads the frequency governor, current frequency and turbo state of `cpu`.
+/// Settings which cannot be read (e.g., not on linux, or in a VM without
+/// cpufreq) are left as `None`.
+pub fn cpu_state(cpu: usize) -> CpuState {
+    let cpufreq = format!("/sys/devices/system/cpu/cpu{cpu}/cpufreq");
+    CpuState {
+        cpu,
+        governor: read_sysfs(&format!("{cpufreq}/scaling_governor")),
+        frequency_khz: read_sysfs(&format!("{cpufreq}/scaling_cur_freq"))
+            .and_then(|f| f.parse().ok()),
+        turbo: turbo_enabled(),
+    }
+}
+
+fn turbo_enabled() -> Option<bool> {
+    // intel_pstate reports whether turbo is disabled, acpi-cpufreq whether
+    // boost is enabled.
+    if let Some(no_turbo) = read_sysfs("/sys/devices/system/cpu/intel_pstate/no_turbo") {
+        return Some(no_turbo == "0");
+    }
+    read_sysfs("/sys/devices/system/cpu/cpufreq/boost").map(|boost| boost == "1")
+}
+
+fn read_sysfs(path: &str) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Current frequency of `cpu` in kHz, from cpufreq or else from the `cpu MHz`
+/// of /proc/cpuinfo (e.g., in a VM without cpufreq).
+pub fn current_frequency_khz(cpu: usize) -> Option<u64> {
+    read_sysfs(&format!("/sys/devices/system/cpu/cpu{cpu}/cpufreq/scaling_cur_freq"))
+        .and_then(|f| f.parse().ok())
+        .or_else(|| cpuinfo_frequency_khz(&fs::read_to_string("/proc/cpuinfo").ok()?, cpu))
+}
+
+fn cpuinfo_frequency_khz(cpuinfo: &str, cpu: usize) -> Option<u64> {
+    let field = |block: &str, name: &str| block.lines()
+        .find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (key.trim() == name).then(|| value.trim().to_string())
+        });
+
+    let block = cpuinfo.split("\n\n")
+        .find(|block| field(block, "processor") == Some(cpu.to_string()))?;
+    let mhz: f64 = field(block, "cpu MHz")?.parse().ok()?;
+    Some((mhz * 1000.0).round() as u64)
+}
+
+/// Measures the mean frequency of one CPU from `start` to `stop`, e.g., over
+/// the timed runs of a cell.
+pub enum FrequencyMonitor {
+    /// A thread off the CPU, reading its frequency every interval.
+    Sysfs { stop: Arc<AtomicBool>, sampler: JoinHandle<Vec<u64>> },
+    /// The APERF and MPERF counters at the start. Their ratio is that of the
+    /// mean frequency to the base frequency, at which MPERF counts.
+    Msr { cpu: usize, base_khz: u64, aperf: u64, mperf: u64 },
+}
+
+impl FrequencyMonitor {
+    pub fn start(cpu: usize, source: FrequencySource, interval: Duration) -> Result<Self, String> {
+        match source {
+            FrequencySource::Sysfs => {
+                current_frequency_khz(cpu)
+                    .ok_or_else(|| format!("cannot read the frequency of cpu {cpu}"))?;
+
+                let stop = Arc::new(AtomicBool::new(false));
+                let stopped = stop.clone();
+                let sampler = thread::spawn(move || {
+                    // Monitoring goes on without samples rather than stopping
+                    // the run if the thread cannot move.
+                    let _ = avoid_cpu(cpu);
+                    let mut samples = Vec::new();
+                    loop {
+                        samples.extend(current_frequency_khz(cpu));
+                        if stopped.load(Ordering::Relaxed) {
+                            return samples;
+                        }
+                        thread::sleep(interval);
+                    }
+                });
+                Ok(Self::Sysfs { stop, sampler })
+            },
+            FrequencySource::Msr => {
+                let base_khz = read_sysfs(
+                    &format!("/sys/devices/system/cpu/cpu{cpu}/cpufreq/base_frequency"))
+                    .and_then(|f| f.parse().ok())
+                    .ok_or_else(|| format!("cannot read the base frequency of cpu {cpu}"))?;
+                let (aperf, mperf) = msr::read_aperf_mperf(cpu)?;
+                Ok(Self::Msr { cpu, base_khz, aperf, mperf })
+            },
+        }
+    }
+
+    /// Mean frequency in kHz since `start`, or `None` if it could not be read.
+    pub fn stop(self) -> Option<u64> {
+        match self {
+            Self::Sysfs { stop, sampler } => {
+                stop.store(true, Ordering::Relaxed);
+                let samples = sampler.join().ok()?;
+                (!samples.is_empty())
+                    .then(|| samples.iter().sum::<u64>() / samples.len() as u64)
+            },
+            Self::Msr { cpu, base_khz, aperf, mperf } => {
+                let (aperf_end, mperf_end) = msr::read_aperf_mperf(cpu).ok()?;
+                let mperf_delta = mperf_end.wrapping_sub(mperf);
+                (mperf_delta > 0).then(|| {
+                    (base_khz as f64 * aperf_end.wrapping_sub(aperf) as f64 / mperf_delta as f64)
+                        .round() as u64
+                })
+            },
+        }
+    }
+}
+
+#[cfg(all(feature = "msr", target_os = "linux"))]
+mod msr {
+    use std::{fs::File, os::unix::fs::FileExt};
+
+    const IA32_MPERF: u64 = 0xe7;
+    const IA32_APERF: u64 = 0xe8;
+
+    /// Reads APERF and MPERF of `cpu` through the msr driver, which needs
+    /// root (or CAP_SYS_RAWIO) and `modprobe msr`.
+    pub fn read_aperf_mperf(cpu: usize) -> Result<(u64, u64), String> {
+        let path = format!("/dev/cpu/{cpu}/msr");
+        let file = File::open(&path).map_err(|e| format!("unable to open {path}: {e}"))?;
+        let read = |register| {
+            let mut value = [0u8; 8];
+            file.read_exact_at(&mut value, register)
+                .map_err(|e| format!("unable to read msr {register:#x} of cpu {cpu}: {e}"))?;
+            Ok::<_, String>(u64::from_ne_bytes(value))
+        };
+        Ok((read(IA32_APERF)?, read(IA32_MPERF)?))
+    }
+}
+
+#[cfg(not(all(feature = "msr", target_os = "linux")))]
+mod msr {
+    pub fn read_aperf_mperf(_cpu: usize) -> Result<(u64, u64), String> {
+        Err("frequency_monitor = \"msr\" requires the benchmark to be built with \
+            --features msr on linux".to_string())
+    }
+}
+
+/// How far `mean` dropped below `peak`, out of PERCENT.
+pub fn frequency_drop(peak: u64, mean: u64) -> u32 {
+    if peak == 0 {
+        return 0;
+    }
+    let drop = peak.saturating_sub(mean) as f64 / peak as f64;
+    (drop * PERCENT_F).round() as u32
+}
+
+/// Microarchitectures which can be detected, usable as keys of algorithm sets.
+pub const MICROARCHS: &[&str] = &[
+    "haswell", "broadwell", "skylake", "skylakex", "icelake", "tigerlake",
+    "alderlake", "sapphirerapids", "zen", "zen2", "zen3", "zen4", "zen5",
+];
+
+/// Detects the microarchitecture of the running CPU from its CPUID vendor,
+/// family and model. Returns `None` for unrecognised CPUs.
+#[cfg(target_arch = "x86_64")]
+pub fn microarch() -> Option<&'static str> {
+    use std::arch::x86_64::__cpuid;
+
+    // SAFETY: CPUID is available on every x86_64 CPU.
+    let (vendor, signature) = unsafe {
+        let leaf0 = __cpuid(0);
+        let mut vendor = [0u8; 12];
+        vendor[0..4].copy_from_slice(&leaf0.ebx.to_le_bytes());
+        vendor[4..8].copy_from_slice(&leaf0.edx.to_le_bytes());
+        vendor[8..12].copy_from_slice(&leaf0.ecx.to_le_bytes());
+        (vendor, __cpuid(1).eax)
+    };
+
+    let base_family = (signature >> 8) & 0xf;
+    let base_model = (signature >> 4) & 0xf;
+    let (family, model) = if base_family == 0xf || base_family == 0x6 {
+        let family = if base_family == 0xf {
+            base_family + ((signature >> 20) & 0xff)
+        }
+        else {
+            base_family
+        };
+        (family, base_model | ((signature >> 12) & 0xf0))
+    }
+    else {
+        (base_family, base_model)
+    };
+
+    match &vendor {
+        b"GenuineIntel" => intel_microarch(family, model),
+        b"AuthenticAMD" => amd_microarch(family, model),
+        _ => None,
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn microarch() -> Option<&'static str> {
+    None
+}
+
+fn intel_microarch(family: u32, model: u32) -> Option<&'static str> {
+    if family != 6 {
+        return None;
+    }
+    match model {
+        0x3c | 0x3f | 0x45 | 0x46 => Some("haswell"),
+        0x3d | 0x47 | 0x4f | 0x56 => Some("broadwell"),
+        // Includes Kaby Lake, Coffee Lake and Comet Lake.
+        0x4e | 0x5e | 0x8e | 0x9e | 0xa5 | 0xa6 => Some("skylake"),
+        // Includes Cascade Lake and Cooper Lake.
+        0x55 => Some("skylakex"),
+        0x6a | 0x6c | 0x7d | 0x7e => Some("icelake"),
+        0x8c | 0x8d => Some("tigerlake"),
+        // Includes Raptor Lake.
+        0x97 | 0x9a | 0xb7 | 0xba | 0xbf => Some("alderlake"),
+        // Includes Emerald Rapids.
+        0x8f | 0xcf => Some("sapphirerapids"),
+        _ => None,
+    }
+}
+
+fn amd_microarch(family: u32, model: u32) -> Option<&'static str> {
+    match (family, model) {
+        (0x17, 0x30..=0xff) => Some("zen2"),
+        (0x17, _) => Some("zen"),
+        (0x19, 0x10..=0x1f | 0x60..=0x7f | 0xa0..=0xaf) => Some("zen4"),
+        (0x19, _) => Some("zen3"),
+        (0x1a, _) => Some("zen5"),
+        _ => None,
+    }
+}
+
+/// Times a fixed chain of dependent additions, returning the fastest of
+/// several repeats. On a pinned thread this changes only with core frequency.
+pub fn spin_baseline() -> Duration {
+    (0..SPIN_REPEATS)
+        .map(|_| {
+            let start = Instant::now();
+            let mut acc = 0u64;
+            for i in 0..SPIN_ITERATIONS {
+                acc = hint::black_box(acc.wrapping_add(i));
+            }
+            hint::black_box(acc);
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+/// Relative difference between `baseline` and `reference`, out of PERCENT.
+pub fn baseline_drift(reference: u64, baseline: u64) -> u32 {
+    if reference == 0 {
+        return 0;
+    }
+    let drift = (baseline as f64 - reference as f64).abs() / reference as f64;
+    (drift * PERCENT_F).round() as u32
+}
+
+/// Relative standard error of the mean of `values`, out of PERCENT, or `None`
+/// for fewer than two values.
+pub fn relative_standard_error(values: &[u64]) -> Option<u32> {
+    if values.len() < 2 {
+        return None;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<u64>() as f64 / n;
+    if mean == 0.0 {
+        return Some(0);
+    }
+    let variance = values.iter()
+        .map(|&v| (v as f64 - mean).powi(2))
+        .sum::<f64>() / (n - 1.0);
+    let rse = (variance / n).sqrt() / mean;
+    Some((rse * PERCENT_F).round() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_baseline_drift() {
+        assert_eq!(baseline_drift(1000, 1000), 0);
+        assert_eq!(baseline_drift(1000, 1050), 50);
+        assert_eq!(baseline_drift(1000, 900), 100);
+        assert_eq!(baseline_drift(0, 900), 0);
+    }
+
+    #[test]
+    fn test_frequency_drop() {
+        assert_eq!(frequency_drop(3_000_000, 3_000_000), 0);
+        assert_eq!(frequency_drop(3_000_000, 2_700_000), 100);
+        assert_eq!(frequency_drop(3_000_000, 3_300_000), 0);
+        assert_eq!(frequency_drop(0, 2_000_000), 0);
+    }
+
+    #[test]
+    fn test_cpuinfo_frequency() {
+        let cpuinfo = "processor\t: 0\ncpu MHz\t\t: 2400.000\n\n\
+            processor\t: 1\nmodel name\t: Some CPU\ncpu MHz\t\t: 3100.512\n";
+        assert_eq!(cpuinfo_frequency_khz(cpuinfo, 0), Some(2_400_000));
+        assert_eq!(cpuinfo_frequency_khz(cpuinfo, 1), Some(3_100_512));
+        assert_eq!(cpuinfo_frequency_khz(cpuinfo, 2), None);
+    }
+
+    #[test]
+    fn test_relative_standard_error() {
+        assert_eq!(relative_standard_error(&[]), None);
+        assert_eq!(relative_standard_error(&[100]), None);
+        assert_eq!(relative_standard_error(&[100, 100, 100]), Some(0));
+        assert_eq!(relative_standard_error(&[0, 0]), Some(0));
+        // Sample standard deviation 11.5, standard error 5.8, mean 100.
+        assert_eq!(relative_standard_error(&[90, 110, 90, 110]), Some(58));
+    }
+
+    #[test]
+    fn test_microarch() {
+        assert_eq!(intel_microarch(6, 0x55), Some("skylakex"));
+        assert_eq!(intel_microarch(6, 0x01), None);
+        assert_eq!(amd_microarch(0x19, 0x61), Some("zen4"));
+        assert_eq!(amd_microarch(0x19, 0x21), Some("zen3"));
+        assert_eq!(amd_microarch(0x17, 0x71), Some("zen2"));
+        if let Some(name) = microarch() {
+            assert!(MICROARCHS.contains(&name));
+        }
+    }
+
+    #[test]
+    fn test_pin_to_cpu() {
+        // Pinning to the current CPU is always permitted.
+        let cpu = current_cpu();
+        if cfg!(target_os = "linux") {
+            pin_to_cpu(cpu).unwrap();
+            assert_eq!(current_cpu(), cpu);
+        }
+        assert!(pin_to_cpu(usize::MAX).is_err());
+        assert_eq!(cpu_state(cpu).cpu, cpu);
+    }
+}
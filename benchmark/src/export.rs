@@ -0,0 +1,221 @@
+//! Exports results as tidy CSV, with one row per (dataset, x, y, algorithm,
+//! sample), for analysis with e.g. pandas without parsing the nested JSON.
+//! A sample is one timed datafile (and replication) of a point, and the
+//! `instance` column records which instance of the point it belongs to for
+//! datasets with `data_replications`, and the `output` column the visitor used
+//! when some experiment chose its own. Counter columns are only written if some
+//! run recorded them, and are empty for runs which did not.
+
+use std::io::{self, Write};
+
+use crate::schema::{Results, ResultRun, CacheRun, AlgorithmResults, DatasetResults};
+
+// Samples of a counter in a run, if recorded.
+type RunSamples = fn(&ResultRun) -> Option<&Vec<u64>>;
+type CacheSamples = fn(&CacheRun) -> Option<&Vec<u64>>;
+pub(crate) type Samples = Box<dyn Fn(&ResultRun) -> Option<&Vec<u64>>>;
+type Cache = fn(&ResultRun) -> &CacheRun;
+
+const CACHES: [(&str, Cache); 3] = [
+    ("l1d", |r| &r.l1d),
+    ("l1i", |r| &r.l1i),
+    ("ll", |r| &r.ll),
+];
+
+const COLUMNS: [(&str, RunSamples); 18] = [
+    ("branches", |r| r.branches.as_ref()),
+    ("branch_misses", |r| r.branch_misses.as_ref()),
+    ("cpu_stalled_front", |r| r.cpu_stalled_front.as_ref()),
+    ("cpu_stalled_back", |r| r.cpu_stalled_back.as_ref()),
+    ("instructions", |r| r.instructions.as_ref()),
+    ("cpu_cycles", |r| r.cpu_cycles.as_ref()),
+    ("cpu_cycles_ref", |r| r.cpu_cycles_ref.as_ref()),
+    ("output_len", |r| r.output_len.as_ref()),
+    ("output_capacity", |r| r.output_capacity.as_ref()),
+    ("flush_times", |r| r.flush_times.as_ref()),
+    ("comparisons", |r| r.comparisons.as_ref()),
+    ("vector_loads", |r| r.vector_loads.as_ref()),
+    ("emitted_vectors", |r| r.emitted_vectors.as_ref()),
+    ("gallops", |r| r.gallops.as_ref()),
+    ("gallop_overshoot", |r| r.gallop_overshoot.as_ref()),
+    ("len_a", |r| r.len_a.as_ref()),
+    ("len_b", |r| r.len_b.as_ref()),
+    ("intersection_len", |r| r.intersection_len.as_ref()),
+];
+
+const CACHE_STATS: [(&str, CacheSamples); 4] = [
+    ("rd_access", |c| c.rd_access.as_ref()),
+    ("rd_miss", |c| c.rd_miss.as_ref()),
+    ("wr_access", |c| c.wr_access.as_ref()),
+    ("wr_miss", |c| c.wr_miss.as_ref()),
+];
+
+pub fn write_csv<W: Write>(results: &Results, out: &mut W) -> io::Result<()> {
+    let runs = || results.datasets.values()
+        .flat_map(|d| d.algos.values().chain(d.outputs.values().flat_map(|o| o.values())))
+        .flatten();
+
+    let columns = counter_columns(runs);
+
+    let instances = runs().any(|r| r.instances.is_some());
+    let outputs = results.datasets.values().any(|d| !d.outputs.is_empty());
+
+    write!(out, "dataset,x,y,algorithm,sample,time_ns")?;
+    if outputs {
+        write!(out, ",output")?;
+    }
+    if instances {
+        write!(out, ",instance")?;
+    }
+    for (name, _) in &columns {
+        write!(out, ",{name}")?;
+    }
+    writeln!(out)?;
+
+    let mut datasets: Vec<_> = results.datasets.iter().collect();
+    datasets.sort_by_key(|(name, _)| *name);
+
+    for (dataset, dataset_results) in datasets {
+        for (output, algos) in output_groups(results, dataset_results) {
+            let mut algos: Vec<_> = algos.iter().collect();
+            algos.sort_by_key(|(name, _)| *name);
+
+            for (algorithm, runs) in algos {
+                for run in runs {
+                    let y = run.y.map(|y| y.to_string()).unwrap_or_default();
+
+                    for (sample, time) in run.times.iter().enumerate() {
+                        write!(out, "{},{},{y},{},{sample},{time}",
+                            escape(dataset), run.x, escape(algorithm))?;
+
+                        if outputs {
+                            write!(out, ",{output}")?;
+                        }
+
+                        if instances {
+                            match run.instances.as_ref().and_then(|i| i.get(sample)) {
+                                Some(instance) => write!(out, ",{instance}")?,
+                                None => write!(out, ",")?,
+                            }
+                        }
+
+                        for (_, get) in &columns {
+                            match get(run).and_then(|values| values.get(sample)) {
+                                Some(value) => write!(out, ",{value}")?,
+                                None => write!(out, ",")?,
+                            }
+                        }
+                        writeln!(out)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Name and sample getter of each counter recorded by any of `runs`.
+pub(crate) fn counter_columns<'a, I>(runs: impl Fn() -> I) -> Vec<(String, Samples)>
+where
+    I: Iterator<Item = &'a ResultRun>,
+{
+    let mut columns: Vec<(String, Samples)> = Vec::new();
+    for (cache, get_cache) in CACHES {
+        for (stat, get_stat) in CACHE_STATS {
+            if runs().any(|r| get_stat(get_cache(r)).is_some()) {
+                columns.push((
+                    format!("{cache}_{stat}"),
+                    Box::new(move |r| get_stat(get_cache(r)))));
+            }
+        }
+    }
+    for (name, get) in COLUMNS {
+        if runs().any(|r| get(r).is_some()) {
+            columns.push((name.to_string(), Box::new(get)));
+        }
+    }
+    columns
+}
+
+// Runs of each output of a dataset, labelled by output: those of experiments
+// not setting their own (under `results.output`) first, then the others.
+fn output_groups<'a>(results: &Results, dataset: &'a DatasetResults)
+    -> Vec<(&'static str, &'a AlgorithmResults)>
+{
+    let mut groups = vec![(results.output.map_or("", |o| o.name()), &dataset.algos)];
+
+    let mut outputs: Vec<_> = dataset.outputs.iter().collect();
+    outputs.sort_by_key(|(output, _)| **output);
+    groups.extend(outputs.into_iter().map(|(output, algos)| (output.name(), algos)));
+    groups
+}
+
+// Quotes a field containing a separator, quote or line break.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+    else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_csv() {
+        let results: Results = crate::migrate::parse_results(r#"{
+            "experiments": [],
+            "algorithm_sets": {},
+            "datasets": {"d": {
+                "info": {"name": "d", "type": "real", "source": "webdocs",
+                    "gen_count": 2, "set_count_start": 2, "set_count_end": 2},
+                "algos": {
+                    "b,c": [{"x": 2, "times": [5, 6],
+                        "l1d": {}, "l1i": {}, "ll": {}, "branches": [7],
+                        "instances": [0, 1]}],
+                    "a": [{"x": 2, "times": [3],
+                        "l1d": {}, "l1i": {}, "ll": {"rd_miss": [4]}}]
+                }
+            }}
+        }"#).unwrap();
+
+        let mut csv = Vec::new();
+        write_csv(&results, &mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "\
+            dataset,x,y,algorithm,sample,time_ns,instance,ll_rd_miss,branches\n\
+            d,2,,a,0,3,,4,\n\
+            d,2,,\"b,c\",0,5,0,,7\n\
+            d,2,,\"b,c\",1,6,1,,\n");
+    }
+
+    #[test]
+    fn test_write_csv_outputs() {
+        let results: Results = crate::migrate::parse_results(r#"{
+            "experiments": [],
+            "algorithm_sets": {},
+            "output": "unsafe",
+            "datasets": {"d": {
+                "info": {"name": "d", "type": "real", "source": "webdocs",
+                    "gen_count": 2, "set_count_start": 2, "set_count_end": 2},
+                "algos": {
+                    "a": [{"x": 2, "times": [3], "l1d": {}, "l1i": {}, "ll": {}}]
+                },
+                "outputs": {
+                    "vec": {"a": [{"x": 2, "times": [5], "l1d": {}, "l1i": {}, "ll": {}}]},
+                    "count": {"a": [{"x": 2, "times": [2], "l1d": {}, "l1i": {}, "ll": {}}]}
+                }
+            }}
+        }"#).unwrap();
+
+        let mut csv = Vec::new();
+        write_csv(&results, &mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "\
+            dataset,x,y,algorithm,sample,time_ns,output\n\
+            d,2,,a,0,3,unsafe\n\
+            d,2,,a,0,2,count\n\
+            d,2,,a,0,5,vec\n");
+    }
+}
@@ -1,7 +1,18 @@
 use crate::schema::*;
 
 pub fn format_x(x: u32, info: &SyntheticDataset) -> String {
-    match info.vary {
+    format_parameter(info.vary, x, info)
+}
+
+pub fn format_y(y: u32, info: &SyntheticDataset) -> String {
+    match &info.vary_y {
+        Some(sweep) => format_parameter(sweep.vary, y, info),
+        None => y.to_string(),
+    }
+}
+
+pub fn format_parameter(parameter: Parameter, x: u32, info: &SyntheticDataset) -> String {
+    match parameter {
         Parameter::Density | Parameter::Selectivity =>
             format!("{:.2}", x as f64 / PERCENT_F),
         Parameter::Size => format_size(x),
@@ -11,7 +22,8 @@ pub fn format_x(x: u32, info: &SyntheticDataset) -> String {
         } else {
             format!("f={}", x as f64 / PERCENT_F)
         },
-        Parameter::SetCount => x.to_string()
+        Parameter::SetCount => x.to_string(),
+        Parameter::LenOffset => format!("+{}", x),
     }
 }
 
@@ -47,5 +59,6 @@ pub fn format_xlabel(parameter: Parameter) -> &'static str {
         Parameter::Size => "size",
         Parameter::Skew => "skew",
         Parameter::SetCount => "set count",
+        Parameter::LenOffset => "length offset",
     }
 }
@@ -13,6 +13,8 @@ struct GenContext {
     pub max_len: usize,
     pub skewness_factor: u32,
     pub set_count: usize,
+    pub len_offset: usize,
+    pub universe: Option<u64>,
 }
 
 impl From<&IntersectionInfo> for GenContext {
@@ -23,6 +25,24 @@ impl From<&IntersectionInfo> for GenContext {
             max_len: 1 << props.max_len,
             skewness_factor: props.skewness_factor,
             set_count: props.set_count as usize,
+            len_offset: props.len_offset as usize,
+            universe: props.universe,
+        }
+    }
+}
+
+impl GenContext {
+    /// Length of the set at `set_index`, where set 0 is the largest.
+    fn set_len(&self, set_index: usize) -> usize {
+        self.max_len / get_skew(set_index, self.skewness_factor) + self.len_offset
+    }
+
+    /// Exclusive upper bound of the elements of sets whose largest is of
+    /// length `large_len`.
+    fn max_value(&self, large_len: usize) -> i32 {
+        match self.universe {
+            Some(universe) => universe as i32,
+            None => (large_len as f64 / self.density) as i32,
         }
     }
 }
@@ -30,14 +50,14 @@ impl From<&IntersectionInfo> for GenContext {
 pub fn gen_twoset(props: &IntersectionInfo) -> (DatafileSet, DatafileSet) {
     let gen: GenContext = props.into();
 
-    let large_len = gen.max_len;
-    let small_len = large_len / get_skew(1, gen.skewness_factor);
+    let large_len = gen.set_len(0);
+    let small_len = gen.set_len(1);
 
     if small_len < MIN_SET_LENGTH {
         warn_set_len(small_len);
     }
 
-    let max_value = (large_len as f64 / gen.density) as i32;
+    let max_value = gen.max_value(large_len);
 
     let (target_shared_count, target_gen_count) =
         get_gen_counts(gen.selectivity, small_len, large_len);
@@ -112,9 +132,9 @@ fn shuffled_set(
 pub fn gen_kset(props: &IntersectionInfo) -> Vec<DatafileSet> {
     let gen: GenContext = props.into();
 
-    let max_value = (gen.max_len as f64 / gen.density) as i32;
+    let max_value = gen.max_value(gen.max_len);
 
-    let min_len = gen.max_len / get_skew(gen.set_count - 1, gen.skewness_factor);
+    let min_len = gen.set_len(gen.set_count - 1);
     if min_len < MIN_SET_LENGTH {
         warn_set_len(min_len);
     }
@@ -125,7 +145,7 @@ pub fn gen_kset(props: &IntersectionInfo) -> Vec<DatafileSet> {
     let mut sets = Vec::with_capacity(gen.set_count);
 
     for set_index in (0..gen.set_count).rev() {
-        let set_len = gen.max_len / get_skew(set_index, gen.skewness_factor);
+        let set_len = gen.set_len(set_index);
         let set = sorted_set_containing(&shared, set_len, max_value);
         sets.push(set);
     }
@@ -134,6 +154,117 @@ pub fn gen_kset(props: &IntersectionInfo) -> Vec<DatafileSet> {
     sets
 }
 
+/// Generates sets with an intersection of exactly `selectivity` times the
+/// smallest length (rounded) elements. The shared elements are sampled first,
+/// then each set's remaining elements are sampled disjoint from those of every
+/// other set, so the intersection of any two of the sets is also exact. Fails
+/// if the element space is too small to fit the disjoint remainders.
+pub fn gen_exact(props: &IntersectionInfo) -> Result<Vec<DatafileSet>, String> {
+    gen_disjoint_remainders(props, shuffled_set)
+}
+
+/// Generates sets as `gen_exact` does, but with the values derived from
+/// hashing their indices under `seed` and the datafile's `index` rather than
+/// sampled. There is no RNG state, so the same seed and parameters give the
+/// same sets on any machine and no datafiles need to be shipped.
+pub fn gen_hashed(props: &IntersectionInfo, seed: u64, index: usize)
+    -> Result<Vec<DatafileSet>, String>
+{
+    let key = fmix64(seed ^ fmix64(index as u64));
+    gen_disjoint_remainders(props, |len, max_value| hashed_set(len, max_value, key))
+}
+
+fn gen_disjoint_remainders(
+    props: &IntersectionInfo,
+    values: impl FnOnce(usize, i32) -> Vec<i32>) -> Result<Vec<DatafileSet>, String>
+{
+    let gen: GenContext = props.into();
+
+    let max_value = gen.max_value(gen.max_len);
+
+    let lengths: Vec<usize> = (0..gen.set_count).rev()
+        .map(|set_index| gen.set_len(set_index))
+        .collect();
+
+    let min_len = lengths[0];
+    if min_len < MIN_SET_LENGTH {
+        warn_set_len(min_len);
+    }
+
+    let shared_count = (gen.selectivity * min_len as f64).round() as usize;
+    let gen_count = shared_count +
+        lengths.iter().map(|len| len - shared_count).sum::<usize>();
+
+    if gen_count > max_value as usize {
+        return Err(format!(
+            "selectivity {:.3} is unachievable exactly with density {:.3}: \
+            needs {} distinct elements but only {} are available",
+            gen.selectivity, gen.density, gen_count, max_value));
+    }
+
+    let values = values(gen_count, max_value);
+    let (shared, mut remainders) = values.split_at(shared_count);
+
+    let sets: Vec<DatafileSet> = lengths.iter()
+        .map(|&len| {
+            let (only_set, rest) = remainders.split_at(len - shared_count);
+            remainders = rest;
+
+            let mut set = [shared, only_set].concat();
+            set.sort_unstable();
+            set
+        })
+        .collect();
+
+    assert!(sets.len() == gen.set_count);
+    Ok(sets)
+}
+
+/// Returns the first `result_len` values of a permutation of `0..max_value`
+/// keyed by `key`. The permutation cycle-walks a bijection of the smallest
+/// power-of-two domain covering `max_value`, so each value depends only on its
+/// index.
+fn hashed_set(result_len: usize, max_value: i32, key: u64) -> Vec<i32> {
+    let bits = (max_value as u64).next_power_of_two().trailing_zeros().max(2);
+    (0..result_len as u64)
+        .map(|index| {
+            let mut value = permute(index, bits, key);
+            while value >= max_value as u64 {
+                value = permute(value, bits, key);
+            }
+            value as i32
+        })
+        .collect()
+}
+
+// Two keyed rounds of the MurmurHash3 finalizer reduced to `bits` bits. Each
+// step (xor with a key, xor-shift, multiply by an odd constant) is invertible
+// modulo 2^bits, so the whole is a bijection of `0..1 << bits`.
+fn permute(value: u64, bits: u32, key: u64) -> u64 {
+    let mask = (1u64 << bits) - 1;
+    let shift = bits / 2;
+    let mut x = value;
+    for round_key in [key, key >> 32] {
+        x = (x ^ round_key) & mask;
+        x ^= x >> shift;
+        x = x.wrapping_mul(0xff51afd7ed558ccd) & mask;
+        x ^= x >> shift;
+        x = x.wrapping_mul(0xc4ceb9fe1a85ec53) & mask;
+        x ^= x >> shift;
+    }
+    x
+}
+
+// The 64-bit MurmurHash3 finalizer.
+pub(crate) fn fmix64(mut x: u64) -> u64 {
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xff51afd7ed558ccd);
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xc4ceb9fe1a85ec53);
+    x ^= x >> 33;
+    x
+}
+
 /// Same as `shuffed_set` but result is sorted and all elements from `include`
 /// must be present.
 fn sorted_set_containing(
@@ -214,9 +345,35 @@ fn sorted_set_high_density_containing(
     result
 }
 
+/// Checks that `universe`, if set, holds the largest set of `props` and fits
+/// the elements' `i32` values.
+pub fn check_universe(props: &IntersectionInfo) -> Result<(), String> {
+    let Some(universe) = props.universe else {
+        return Ok(());
+    };
+    let large_len = set_lengths(props)[0];
+    if universe > i32::MAX as u64 {
+        Err(format!("universe {} exceeds the largest element value {}", universe, i32::MAX))
+    }
+    else if (universe as usize) < large_len {
+        Err(format!("universe {} cannot hold a set of {} elements", universe, large_len))
+    }
+    else {
+        Ok(())
+    }
+}
+
 /// The skew WRT the largest set is k^f where f is the skewness factor.
 /// The size of the kth set is S_1/(k^f)
 /// `set_index` is 0-based.
+/// Lengths of the sets generated for `props`, largest first.
+pub fn set_lengths(props: &IntersectionInfo) -> Vec<usize> {
+    let gen: GenContext = props.into();
+    (0..gen.set_count)
+        .map(|set_index| gen.set_len(set_index))
+        .collect()
+}
+
 fn get_skew(set_index: usize, skew_factor: u32) -> usize {
     let skewness_f = skew_factor as f64 / PERCENT_F;
     let index_f = (set_index + 1) as f64;
@@ -257,3 +414,127 @@ fn warn_set_len(len: usize) {
 }
 
 // TODO: also return "real" selectivity for plotting
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn props(set_count: u32, density: u32, selectivity: u32) -> IntersectionInfo {
+        IntersectionInfo {
+            set_count,
+            density,
+            selectivity,
+            max_len: 10,
+            skewness_factor: 1000,
+            exact: true,
+            seed: None,
+            len_offset: 0,
+            universe: None,
+        }
+    }
+
+    #[test]
+    fn test_gen_exact() {
+        for set_count in 2..=4 {
+            let sets = gen_exact(&props(set_count, 100, 300)).unwrap();
+
+            let lengths: Vec<usize> = sets.iter().map(Vec::len).collect();
+            let expected: Vec<usize> = (1..=set_count as usize).rev()
+                .map(|k| 1024 / k)
+                .collect();
+            assert_eq!(lengths, expected);
+            assert!(sets.iter().all(|s| s.windows(2).all(|w| w[0] < w[1])));
+
+            // 30% of the smallest set, rounded.
+            let shared = (0.3 * expected[0] as f64).round() as usize;
+            let result = setops::intersect::run_svs(&sets, setops::intersect::branchless_merge);
+            assert_eq!(result.len(), shared);
+
+            for (i, a) in sets.iter().enumerate() {
+                for b in &sets[i+1..] {
+                    assert_eq!(setops::simple::count(a, b), shared);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_gen_exact_unachievable() {
+        // Two disjoint sets of 1024 elements do not fit in 1024 values.
+        let props = IntersectionInfo { skewness_factor: 0, ..props(2, 1000, 0) };
+        assert!(gen_exact(&props).is_err());
+        assert_eq!(gen_exact(&IntersectionInfo { selectivity: 1000, ..props }).unwrap()[0].len(), 1024);
+    }
+
+    #[test]
+    fn test_len_offset() {
+        let props = IntersectionInfo { len_offset: 7, ..props(3, 100, 300) };
+        assert_eq!(set_lengths(&props), vec![1031, 519, 348]);
+
+        let sets = gen_exact(&props).unwrap();
+        let lengths: Vec<usize> = sets.iter().map(Vec::len).collect();
+        assert_eq!(lengths, vec![348, 519, 1031]);
+    }
+
+    #[test]
+    fn test_universe() {
+        for set_count in 2..=3 {
+            for exact in [false, true] {
+                let props = IntersectionInfo {
+                    universe: Some(5000), exact, ..props(set_count, 1000, 300)
+                };
+                assert!(check_universe(&props).is_ok());
+
+                let sets = if exact {
+                    gen_exact(&props).unwrap()
+                }
+                else if set_count == 2 {
+                    let (small, large) = gen_twoset(&props);
+                    vec![small, large]
+                }
+                else {
+                    gen_kset(&props)
+                };
+                // Density 100% would bound the elements by 1024.
+                assert!(sets.iter().flatten().all(|&v| (0..5000).contains(&v)));
+                assert!(sets.iter().flatten().any(|&v| v >= 1024));
+            }
+        }
+
+        let props = props(2, 100, 300);
+        assert!(check_universe(&IntersectionInfo { universe: Some(1000), ..props.clone() }).is_err());
+        assert!(check_universe(&IntersectionInfo { universe: Some(1 << 31), ..props }).is_err());
+    }
+
+    #[test]
+    fn test_gen_hashed() {
+        for set_count in 2..=4 {
+            let props = props(set_count, 300, 300);
+            let sets = gen_hashed(&props, 42, 0).unwrap();
+            assert_eq!(sets, gen_hashed(&props, 42, 0).unwrap());
+            assert_ne!(sets, gen_hashed(&props, 42, 1).unwrap());
+            assert_ne!(sets, gen_hashed(&props, 43, 0).unwrap());
+
+            let lengths: Vec<usize> = sets.iter().map(Vec::len).collect();
+            assert_eq!(lengths, set_lengths(&props).into_iter().rev().collect::<Vec<_>>());
+            // 1024 elements at 30% density.
+            let max_value = (1024.0 / 0.3) as i32;
+            assert!(sets.iter().all(|s| s.windows(2).all(|w| w[0] < w[1])));
+            assert!(sets.iter().flatten().all(|&v| (0..max_value).contains(&v)));
+
+            let shared = (0.3 * lengths[0] as f64).round() as usize;
+            let result = setops::intersect::run_svs(&sets, setops::intersect::branchless_merge);
+            assert_eq!(result.len(), shared);
+        }
+    }
+
+    #[test]
+    fn test_hashed_set_permutes() {
+        // Cycle-walking covers the whole domain, including non-powers of two.
+        for max_value in [1, 5, 1000, 1024] {
+            let mut values = hashed_set(max_value as usize, max_value, 7);
+            values.sort_unstable();
+            assert_eq!(values, (0..max_value).collect::<Vec<_>>());
+        }
+    }
+}
@@ -7,14 +7,27 @@ pub mod format;
 pub mod timer;
 pub mod util;
 pub mod realdata;
+pub mod registry;
+pub mod environment;
+pub mod trace;
+pub mod placement;
+pub mod migrate;
+pub mod export;
+pub mod compare;
+pub mod stats;
+pub mod roofline;
+pub mod process;
+pub mod pipeline;
+pub mod verify;
 
 use std::{
     ops::RangeInclusive,
-    path::PathBuf,
+    path::{Path, PathBuf},
     iter::StepBy,
     collections::HashMap
 };
-use schema::{SyntheticDataset, Parameter, IntersectionInfo, AlgorithmVec, DatasetInfo, Algorithms};
+use schema::{SyntheticDataset, Parameter, IntersectionInfo, AlgorithmVec, AlgorithmSet, DatasetInfo, Algorithms};
+use format::format_xlabel;
 
 pub fn fmt_open_err(e: impl ToString, path: &PathBuf) -> String {
     format!("unable to open {}: {}", path_str(path), e.to_string())
@@ -27,34 +40,145 @@ pub fn path_str(path: &PathBuf) -> &str {
 pub fn xvalues(info: &DatasetInfo) -> StepBy<RangeInclusive<u32>> {
     match &info.dataset_type {
         schema::DatasetType::Synthetic(s) => xvalues_synthetic(s),
-        schema::DatasetType::Real(r) => (r.set_count_start..=r.set_count_end).step_by(1),
+        schema::DatasetType::Real(r) => match &r.subsample {
+            Some(s) => (s.from..=s.to).step_by(s.step as usize),
+            None => (r.set_count_start..=r.set_count_end).step_by(1),
+        },
     }
 }
 
 pub fn xvalues_synthetic(info: &SyntheticDataset) -> StepBy<RangeInclusive<u32>> {
-    let begin = match info.vary {
-        Parameter::Selectivity => info.intersection.selectivity,
-        Parameter::Density     => info.intersection.density,
-        Parameter::Size        => info.intersection.max_len,
-        Parameter::Skew        => info.intersection.skewness_factor,
-        Parameter::SetCount    => info.intersection.set_count,
-    };
-
+    let begin = *parameter(&info.intersection, info.vary);
     (begin..=info.to).step_by(info.step as usize)
 }
 
+/// Values of the second parameter of a 2D sweep, or a single `None` if the
+/// dataset only varies one parameter. Iterating over the product of `xvalues`
+/// and `yvalues` therefore visits every cell of the dataset.
+pub fn yvalues(info: &DatasetInfo) -> Vec<Option<u32>> {
+    match &info.dataset_type {
+        schema::DatasetType::Synthetic(s) => yvalues_synthetic(s),
+        schema::DatasetType::Real(_) => vec![None],
+    }
+}
+
+pub fn yvalues_synthetic(info: &SyntheticDataset) -> Vec<Option<u32>> {
+    match &info.vary_y {
+        Some(sweep) => {
+            let begin = *parameter(&info.intersection, sweep.vary);
+            (begin..=sweep.to).step_by(sweep.step as usize).map(Some).collect()
+        },
+        None => vec![None],
+    }
+}
+
+pub fn validate_synthetic(info: &SyntheticDataset) -> Result<(), String> {
+    match &info.vary_y {
+        Some(sweep) if sweep.vary == info.vary => return Err(format!(
+            "vary and vary_y must be different parameters (both are {})",
+            format_xlabel(info.vary))),
+        _ => (),
+    }
+
+    if info.intersection.universe.is_some() {
+        let varies_density = info.vary == Parameter::Density ||
+            info.vary_y.as_ref().is_some_and(|sweep| sweep.vary == Parameter::Density);
+        if varies_density {
+            return Err("density cannot be varied with a fixed universe".to_string());
+        }
+        for x in xvalues_synthetic(info) {
+            for y in yvalues_synthetic(info) {
+                generators::check_universe(&props_at(info, x, y))?;
+            }
+        }
+    }
+    Ok(())
+}
+
 pub fn props_at_x(info: &SyntheticDataset, x: u32) -> IntersectionInfo {
+    props_at(info, x, None)
+}
+
+pub fn props_at(info: &SyntheticDataset, x: u32, y: Option<u32>) -> IntersectionInfo {
     let mut props = info.intersection.clone();
-    let prop = match info.vary {
+    *parameter_mut(&mut props, info.vary) = x;
+
+    if let (Some(sweep), Some(y)) = (&info.vary_y, y) {
+        *parameter_mut(&mut props, sweep.vary) = y;
+    }
+    props
+}
+
+/// Directory containing the datafiles of a cell: `<dataset>/<x>` or
+/// `<dataset>/<x>/<y>` for 2D sweeps.
+pub fn point_dir(dataset_dir: &Path, x: u32, y: Option<u32>) -> PathBuf {
+    let xdir = dataset_dir.join(x.to_string());
+    match y {
+        Some(y) => xdir.join(y.to_string()),
+        None => xdir,
+    }
+}
+
+/// Directories of the instances of a cell: the cell's directory itself, or
+/// `<cell>/<instance>` for each instance of a dataset with `data_replications`.
+pub fn instance_dirs(info: &DatasetInfo, point_dir: &Path) -> Vec<PathBuf> {
+    match info.data_replications {
+        Some(count) => (0..count).map(|i| point_dir.join(i.to_string())).collect(),
+        None => vec![point_dir.to_path_buf()],
+    }
+}
+
+/// Paths of the datafiles of an instance, in order of generation.
+pub fn datafile_paths(instance_dir: &PathBuf) -> Result<Vec<PathBuf>, String> {
+    let mut paths = std::fs::read_dir(instance_dir)
+        .map_err(|e| fmt_open_err(e, instance_dir))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<PathBuf>, _>>()
+        .map_err(|e| format!(
+            "unable to open directory entry in {}: {}", path_str(instance_dir), e))?;
+
+    paths.sort_by_key(|p| p.file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.parse::<usize>().ok()));
+    Ok(paths)
+}
+
+fn parameter(props: &IntersectionInfo, parameter: Parameter) -> &u32 {
+    match parameter {
+        Parameter::Selectivity => &props.selectivity,
+        Parameter::Density     => &props.density,
+        Parameter::Size        => &props.max_len,
+        Parameter::Skew        => &props.skewness_factor,
+        Parameter::SetCount    => &props.set_count,
+        Parameter::LenOffset   => &props.len_offset,
+    }
+}
+
+fn parameter_mut(props: &mut IntersectionInfo, parameter: Parameter) -> &mut u32 {
+    match parameter {
         Parameter::Selectivity => &mut props.selectivity,
         Parameter::Density     => &mut props.density,
         Parameter::Size        => &mut props.max_len,
         Parameter::Skew        => &mut props.skewness_factor,
         Parameter::SetCount    => &mut props.set_count,
-    };
-    *prop = x;
+        Parameter::LenOffset   => &mut props.len_offset,
+    }
+}
 
-    props
+/// Chooses the list of each algorithm set for `microarch`, failing if a set
+/// has no list for it and no default list.
+pub fn resolve_algorithm_sets(
+    algorithm_sets: &HashMap<String, AlgorithmSet>,
+    microarch: Option<&str>) -> Result<HashMap<String, AlgorithmVec>, String>
+{
+    algorithm_sets.iter()
+        .map(|(name, set)| {
+            let algorithms = set.select(microarch).ok_or_else(|| format!(
+                "algorithm set {} has no list for microarchitecture {} and no {} list",
+                name, microarch.unwrap_or("unknown"), schema::DEFAULT_MICROARCH))?;
+            Ok((name.clone(), algorithms.clone()))
+        })
+        .collect()
 }
 
 pub fn get_algorithms<'a>(
@@ -65,5 +189,111 @@ pub fn get_algorithms<'a>(
         Algorithms::Algorithms(v) => Ok(v),
         Algorithms::AlgorithmSet(id) => algorithm_sets.get(id)
                 .ok_or_else(|| format!("algorithm set {} not found", id)),
+        Algorithms::Pipeline(_) => Err("pipeline not expanded".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::Experiment;
+
+    const GRID_TOML: &str = r#"
+        algorithm_sets = {}
+        experiment = []
+
+        [[dataset]]
+        name = "grid"
+        type = "synthetic"
+        set_count = 2
+        gen_count = 1
+        vary = "selectivity"
+        selectivity = 0
+        to = 1000
+        step = 500
+        vary_y = { vary = "skew", to = 2000, step = 1000 }
+        skewness_factor = 0
+        density = 1
+        max_len = 10
+    "#;
+
+    #[test]
+    fn test_2d_sweep() {
+        let experiment: Experiment = toml::from_str(GRID_TOML).unwrap();
+        let info = &experiment.dataset[0];
+
+        let xvalues: Vec<u32> = xvalues(info).collect();
+        assert_eq!(xvalues, [0, 500, 1000]);
+        assert_eq!(yvalues(info), [Some(0), Some(1000), Some(2000)]);
+
+        let schema::DatasetType::Synthetic(s) = &info.dataset_type else {
+            panic!("expected synthetic dataset");
+        };
+        let props = props_at(s, 500, Some(2000));
+        assert_eq!(props.selectivity, 500);
+        assert_eq!(props.skewness_factor, 2000);
+        assert_eq!(props.density, 1);
+
+        assert_eq!(point_dir(Path::new("d"), 500, Some(2000)), Path::new("d/500/2000"));
+        assert!(validate_synthetic(s).is_ok());
+    }
+
+    #[test]
+    fn test_1d_sweep_unchanged() {
+        let experiment: Experiment =
+            toml::from_str(&GRID_TOML.replace(
+                r#"vary_y = { vary = "skew", to = 2000, step = 1000 }"#, "")).unwrap();
+        let info = &experiment.dataset[0];
+
+        assert_eq!(yvalues(info), [None]);
+        assert_eq!(point_dir(Path::new("d"), 500, None), Path::new("d/500"));
+
+        // Dataset info files written before 2D sweeps must compare equal.
+        let json = serde_json::to_string(info).unwrap();
+        assert!(!json.contains("vary_y"));
+    }
+
+    #[test]
+    fn test_instance_dirs() {
+        let toml = GRID_TOML.replace("type = \"synthetic\"",
+            "type = \"synthetic\"\ndata_replications = 2");
+        let experiment: Experiment = toml::from_str(&toml).unwrap();
+        let point = Path::new("d/500");
+
+        assert_eq!(instance_dirs(&experiment.dataset[0], point),
+            [Path::new("d/500/0"), Path::new("d/500/1")]);
+
+        let experiment: Experiment = toml::from_str(GRID_TOML).unwrap();
+        assert_eq!(instance_dirs(&experiment.dataset[0], point), [point]);
+    }
+
+    #[test]
+    fn test_same_parameter_rejected() {
+        let experiment: Experiment =
+            toml::from_str(&GRID_TOML.replace(r#"vary = "skew""#, r#"vary = "selectivity""#))
+            .unwrap();
+        let schema::DatasetType::Synthetic(s) = &experiment.dataset[0].dataset_type else {
+            panic!("expected synthetic dataset");
+        };
+        assert!(validate_synthetic(s).is_err());
+    }
+
+    #[test]
+    fn test_universe_validated() {
+        let validate = |toml: &str| {
+            let experiment: Experiment = toml::from_str(toml).unwrap();
+            let schema::DatasetType::Synthetic(s) = &experiment.dataset[0].dataset_type else {
+                panic!("expected synthetic dataset");
+            };
+            validate_synthetic(s)
+        };
+        let with_universe = |universe: u64|
+            GRID_TOML.replace("max_len = 10", &format!("max_len = 10\nuniverse = {universe}"));
+
+        assert!(validate(&with_universe(1 << 20)).is_ok());
+        // Sets of 1024 elements.
+        assert!(validate(&with_universe(1000)).is_err());
+        assert!(validate(&with_universe(1 << 20).replace(r#"vary = "skew""#, r#"vary = "density""#))
+            .is_err());
     }
 }
@@ -0,0 +1,177 @@
+//! Reading experiment and result files written by older versions of the
+//! benchmark. Each file records the `version` of the schema it was written
+//! with (files from before versioning have none and count as version 0). Files
+//! are parsed into a JSON value, upgraded one version at a time up to
+//! `SCHEMA_VERSION`, then deserialized, and any fields not used by the schema
+//! are reported rather than silently ignored.
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+
+use crate::schema::{Experiment, Results, SCHEMA_VERSION};
+
+/// Upgrades a file from the version at its index to the next version.
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+// Version 1 added the `version` field itself, so unversioned files have the
+// same layout.
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [
+    |_| Ok(()),
+];
+
+/// Parses an experiment TOML file of any version, expanding its pipelines.
+pub fn parse_experiment(toml: &str) -> Result<Experiment, String> {
+    let value: Value = toml::from_str(toml).map_err(|e| e.to_string())?;
+    let mut experiment: Experiment = parse(value)?;
+    experiment.version = SCHEMA_VERSION;
+    crate::pipeline::expand(&mut experiment)?;
+    Ok(experiment)
+}
+
+/// Parses a results JSON file of any version.
+pub fn parse_results(json: &str) -> Result<Results, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let mut results: Results = parse(value)?;
+    results.version = SCHEMA_VERSION;
+    Ok(results)
+}
+
+fn parse<T: Serialize + DeserializeOwned>(mut value: Value) -> Result<T, String> {
+    let Value::Object(fields) = &mut value else {
+        return Err("expected a table at the top level".to_string());
+    };
+    migrate(fields)?;
+
+    let parsed: T = serde_json::from_value(value.clone()).map_err(|e| e.to_string())?;
+
+    let known = serde_json::to_value(&parsed).map_err(|e| e.to_string())?;
+    let mut unknown = Vec::new();
+    unknown_fields(&value, &known, String::new(), &mut unknown);
+    if !unknown.is_empty() {
+        return Err(format!("unknown fields: {}", unknown.join(", ")));
+    }
+    Ok(parsed)
+}
+
+/// Upgrades `fields` in place to `SCHEMA_VERSION`.
+pub fn migrate(fields: &mut Map<String, Value>) -> Result<(), String> {
+    let version = match fields.get("version") {
+        None => 0,
+        Some(v) => v.as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| format!("invalid version {v}"))?,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "version {version} is newer than the latest supported ({SCHEMA_VERSION})"));
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(fields)
+            .map_err(|e| format!("upgrading from version {from}: {e}"))?;
+    }
+    fields.insert("version".to_string(), SCHEMA_VERSION.into());
+    Ok(())
+}
+
+// Appends the paths of fields in `given` missing from `known`, i.e., the
+// parsed value serialized again. Empty fields may be absent from `known`
+// because they are skipped when serializing, so they are not reported.
+fn unknown_fields(given: &Value, known: &Value, path: String, unknown: &mut Vec<String>) {
+    match (given, known) {
+        (Value::Object(given), Value::Object(known)) => {
+            for (key, value) in given {
+                let field_path = if path.is_empty() { key.clone() } else { format!("{path}.{key}") };
+                match known.get(key) {
+                    Some(known_value) =>
+                        unknown_fields(value, known_value, field_path, unknown),
+                    None if !is_empty(value) => unknown.push(field_path),
+                    None => (),
+                }
+            }
+        },
+        (Value::Array(given), Value::Array(known)) => {
+            for (i, (value, known_value)) in given.iter().zip(known).enumerate() {
+                unknown_fields(value, known_value, format!("{path}[{i}]"), unknown);
+            }
+        },
+        _ => (),
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(a) => a.is_empty(),
+        Value::Object(o) => o.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPERIMENT_TOML: &str = r#"
+        [[experiment]]
+        name = "e"
+        title = "E"
+        dataset = "d"
+        algorithms = ["naive_merge"]
+
+        [[dataset]]
+        name = "d"
+        type = "synthetic"
+        vary = "selectivity"
+        to = 1000
+        step = 500
+        gen_count = 1
+        set_count = 2
+        density = 0
+        selectivity = 0
+        max_len = 10
+        skewness_factor = 0
+
+        [algorithm_sets]
+
+        [environment]
+        pin_cpu = 0
+    "#;
+
+    #[test]
+    fn test_unversioned_experiment() {
+        let experiment = parse_experiment(EXPERIMENT_TOML).unwrap();
+        assert_eq!(experiment.version, SCHEMA_VERSION);
+        assert_eq!(experiment.environment.pin_cpu, Some(0));
+
+        let versioned = format!("version = {SCHEMA_VERSION}\n{EXPERIMENT_TOML}");
+        assert!(parse_experiment(&versioned).is_ok());
+    }
+
+    #[test]
+    fn test_newer_version() {
+        let newer = format!("version = {}\n{EXPERIMENT_TOML}", SCHEMA_VERSION + 1);
+        let err = parse_experiment(&newer).unwrap_err();
+        assert!(err.contains("newer than the latest supported"), "{err}");
+    }
+
+    #[test]
+    fn test_unknown_fields() {
+        let toml = EXPERIMENT_TOML
+            .replace("pin_cpu = 0", "pin_cpus = 0")
+            .replace("gen_count = 1", "gen_count = 1\ngen_cuont = 2");
+        assert_eq!(
+            parse_experiment(&toml).unwrap_err(),
+            "unknown fields: dataset[0].gen_cuont, environment.pin_cpus");
+    }
+
+    #[test]
+    fn test_unversioned_results() {
+        let json = r#"{"experiments": [], "datasets": {}, "algorithm_sets": {}}"#;
+        assert_eq!(parse_results(json).unwrap().version, SCHEMA_VERSION);
+
+        let json = json.replace("{}}", r#"{}, "environment":
+            {"config": {}, "start": {"cpu": 0, "mhz": 1}, "end": {"cpu": 0}}}"#);
+        assert_eq!(parse_results(&json).unwrap_err(), "unknown fields: environment.start.mhz");
+    }
+}
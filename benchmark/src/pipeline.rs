@@ -0,0 +1,140 @@
+//! Experiments defined as a pipeline of stages rather than a list of algorithm
+//! names: a set representation, a kernel intersecting it and an output, where
+//! the experiment runs every combination, e.g., {sorted, BSR} × {shuffling,
+//! merge} × {vec, count}. The algorithm of a representation and kernel is
+//! named as the timer parses it (see `Representation::algorithm`). Pipelines
+//! are expanded into plain experiments as the experiment file is parsed, one
+//! per output, so the runner, results and scripts only see algorithm lists.
+
+use crate::schema::{AlgorithmId, Algorithms, Experiment, ExperimentEntry, Pipeline};
+
+/// Replaces each pipeline experiment with an experiment per output, named
+/// `<name>_<output>` if the pipeline has more than one.
+pub fn expand(experiment: &mut Experiment) -> Result<(), String> {
+    let mut expanded = Vec::with_capacity(experiment.experiment.len());
+    for entry in experiment.experiment.drain(..) {
+        match &entry.algorithms {
+            Algorithms::Pipeline(pipeline) => expand_entry(&entry, pipeline, &mut expanded)?,
+            _ => expanded.push(entry),
+        }
+    }
+    experiment.experiment = expanded;
+    Ok(())
+}
+
+fn expand_entry(entry: &ExperimentEntry, pipeline: &Pipeline, expanded: &mut Vec<ExperimentEntry>)
+    -> Result<(), String>
+{
+    if pipeline.representations.is_empty() || pipeline.algorithms.is_empty() {
+        return Err(format!(
+            "experiment {}: pipeline needs at least one representation and algorithm",
+            entry.name));
+    }
+    if entry.output.is_some() && !pipeline.outputs.is_empty() {
+        return Err(format!(
+            "experiment {}: set either output or the pipeline's outputs", entry.name));
+    }
+
+    let algorithms: Vec<AlgorithmId> = pipeline.representations.iter()
+        .flat_map(|r| pipeline.algorithms.iter().map(|kernel| r.algorithm(kernel)))
+        .collect();
+
+    match pipeline.outputs.as_slice() {
+        [] | [_] => expanded.push(ExperimentEntry {
+            algorithms: Algorithms::Algorithms(algorithms),
+            output: pipeline.outputs.first().copied().or(entry.output),
+            ..entry.clone()
+        }),
+        outputs => expanded.extend(outputs.iter().map(|&output| ExperimentEntry {
+            name: format!("{}_{}", entry.name, output.name()),
+            title: format!("{} -- {} output", entry.title, output.name()),
+            algorithms: Algorithms::Algorithms(algorithms.clone()),
+            output: Some(output),
+            ..entry.clone()
+        })),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::OutputMode;
+
+    const PIPELINE_TOML: &str = r#"
+        algorithm_sets = {}
+        dataset = []
+
+        [[experiment]]
+        name = "plain"
+        title = "Plain"
+        dataset = "d"
+        algorithms = ["naive_merge"]
+
+        [[experiment]]
+        name = "bsr"
+        title = "BSR"
+        dataset = "d"
+        relative_to = "branchless_merge"
+
+        [experiment.pipeline]
+        representations = ["sorted", "bsr"]
+        algorithms = ["branchless_merge", "shuffling_sse"]
+        outputs = ["vec", "count"]
+    "#;
+
+    fn parse(toml: &str) -> Result<Experiment, String> {
+        crate::migrate::parse_experiment(toml)
+    }
+
+    fn algorithms(entry: &ExperimentEntry) -> &[AlgorithmId] {
+        match &entry.algorithms {
+            Algorithms::Algorithms(algorithms) => algorithms,
+            other => panic!("not expanded: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        let experiment = parse(PIPELINE_TOML).unwrap();
+        let entries = &experiment.experiment;
+
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["plain", "bsr_vec", "bsr_count"]);
+        assert_eq!(algorithms(&entries[0]), ["naive_merge"]);
+
+        for (entry, output) in entries[1..].iter().zip([OutputMode::Vec, OutputMode::Count]) {
+            assert_eq!(algorithms(entry), [
+                "branchless_merge", "shuffling_sse",
+                "branchless_merge_bsr", "shuffling_sse_bsr",
+            ]);
+            assert_eq!(entry.output, Some(output));
+            assert_eq!(entry.relative_to.as_deref(), Some("branchless_merge"));
+        }
+        assert_eq!(entries[2].title, "BSR -- count output");
+    }
+
+    #[test]
+    fn test_expand_single_output() {
+        // Without outputs the experiment keeps its own, and its name.
+        let experiment = parse(&PIPELINE_TOML
+            .replace(r#"outputs = ["vec", "count"]"#, "")
+            .replace(r#"relative_to = "branchless_merge""#, r#"output = "slice""#))
+            .unwrap();
+        let entry = &experiment.experiment[1];
+        assert_eq!(entry.name, "bsr");
+        assert_eq!(entry.output, Some(OutputMode::Slice));
+        assert_eq!(algorithms(entry).len(), 4);
+    }
+
+    #[test]
+    fn test_expand_rejects() {
+        let both = PIPELINE_TOML.replace(
+            r#"relative_to = "branchless_merge""#, r#"output = "slice""#);
+        assert!(parse(&both).unwrap_err().contains("either output or"));
+
+        let empty = PIPELINE_TOML.replace(r#"representations = ["sorted", "bsr"]"#,
+            "representations = []");
+        assert!(parse(&empty).unwrap_err().contains("at least one representation"));
+    }
+}
@@ -0,0 +1,289 @@
+//! Placement of benchmark sets in memory: in 2MB huge pages and/or on a given
+//! NUMA node, to study memory-system effects on intersection performance.
+//!
+//! Sets stay plain `Vec<i32>`s so every timer can use them unchanged. Instead,
+//! binaries install `PlacementAllocator` as their global allocator, and any
+//! allocation made inside `Arena::scope` is served from the arena's mapping
+//! (requires the `placement` feature, linux only). Memory freed back to an
+//! arena is only reclaimed when the arena is dropped, so arenas should be
+//! short-lived, e.g., one per loaded datafile.
+
+use serde::{Serialize, Deserialize};
+
+/// Size of the huge pages arenas are aligned to.
+pub const HUGE_PAGE: usize = 2 << 20;
+
+/// How an arena's memory is backed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Backing {
+    /// Ordinary pages.
+    Normal,
+    /// Reserved huge pages (`MAP_HUGETLB`).
+    HugeTlb,
+    /// Transparent huge pages (`MADV_HUGEPAGE`), used when no huge pages are
+    /// reserved. The kernel may still back parts with ordinary pages.
+    Transparent,
+}
+
+#[cfg(all(feature = "placement", target_os = "linux"))]
+pub use imp::{Arena, PlacementAllocator};
+
+#[cfg(not(all(feature = "placement", target_os = "linux")))]
+pub struct Arena;
+
+#[cfg(not(all(feature = "placement", target_os = "linux")))]
+impl Arena {
+    pub fn new(_len: usize, _huge_pages: bool, _numa_node: Option<usize>)
+        -> Result<Self, String>
+    {
+        Err("huge_pages and numa_node require the benchmark to be built with \
+            --features placement on linux".to_string())
+    }
+
+    pub fn backing(&self) -> Backing {
+        Backing::Normal
+    }
+
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+}
+
+#[cfg(all(feature = "placement", target_os = "linux"))]
+mod imp {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+    use super::{Backing, HUGE_PAGE};
+
+    // Flags of mbind(2), not exported by libc.
+    const MPOL_MF_STRICT: libc::c_uint = 1 << 0;
+    const MPOL_MF_MOVE: libc::c_uint = 1 << 1;
+
+    const MAX_ARENAS: usize = 16;
+    const NO_ARENA: usize = usize::MAX;
+
+    // Arenas are registered in a fixed table so the allocator can recognise
+    // their memory without allocating. A slot is free while `start` is 0.
+    struct Slot {
+        start: AtomicUsize,
+        end: AtomicUsize,
+        next: AtomicUsize,
+        live: AtomicUsize,
+    }
+
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY_SLOT: Slot = Slot {
+        start: AtomicUsize::new(0),
+        end: AtomicUsize::new(0),
+        next: AtomicUsize::new(0),
+        live: AtomicUsize::new(0),
+    };
+
+    static SLOTS: [Slot; MAX_ARENAS] = [EMPTY_SLOT; MAX_ARENAS];
+
+    thread_local! {
+        static ACTIVE: Cell<usize> = const { Cell::new(NO_ARENA) };
+    }
+
+    /// Global allocator serving allocations made inside `Arena::scope` from
+    /// the arena, and all others from the system allocator.
+    pub struct PlacementAllocator;
+
+    unsafe impl GlobalAlloc for PlacementAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let active = ACTIVE.with(|a| a.get());
+            if active != NO_ARENA {
+                if let Some(ptr) = bump(&SLOTS[active], layout) {
+                    return ptr;
+                }
+            }
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            let addr = ptr as usize;
+            for slot in &SLOTS {
+                let start = slot.start.load(Ordering::Acquire);
+                if start != 0 && start <= addr && addr < slot.end.load(Ordering::Acquire) {
+                    slot.live.fetch_sub(1, Ordering::AcqRel);
+                    return;
+                }
+            }
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    // Allocates from the arena in `slot`, or returns `None` if it is full.
+    fn bump(slot: &Slot, layout: Layout) -> Option<*mut u8> {
+        let end = slot.end.load(Ordering::Acquire);
+        let mut result = 0;
+        slot.next.fetch_update(Ordering::AcqRel, Ordering::Acquire, |next| {
+            let aligned = next.checked_next_multiple_of(layout.align())?;
+            let new_next = aligned.checked_add(layout.size())?;
+            result = aligned;
+            (new_next <= end).then_some(new_next)
+        }).ok()?;
+        slot.live.fetch_add(1, Ordering::AcqRel);
+        Some(result as *mut u8)
+    }
+
+    /// A region of memory placed according to the benchmark's environment.
+    pub struct Arena {
+        slot: usize,
+        map: *mut libc::c_void,
+        map_len: usize,
+        backing: Backing,
+    }
+
+    impl Arena {
+        /// Maps an arena of at least `len` bytes. With `huge_pages`, reserved
+        /// huge pages are used if available, otherwise transparent huge pages.
+        /// With `numa_node`, pages are bound to that node.
+        pub fn new(len: usize, huge_pages: bool, numa_node: Option<usize>)
+            -> Result<Self, String>
+        {
+            let len = len.max(1).next_multiple_of(HUGE_PAGE);
+            let (map, map_len, start, backing) = map(len, huge_pages)?;
+
+            if let Some(node) = numa_node {
+                if let Err(e) = bind(start, len, node) {
+                    unsafe { libc::munmap(map, map_len) };
+                    return Err(e);
+                }
+            }
+
+            let slot = SLOTS.iter()
+                .position(|s| s.start
+                    .compare_exchange(0, start, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok());
+            let Some(slot) = slot else {
+                unsafe { libc::munmap(map, map_len) };
+                return Err(format!("more than {MAX_ARENAS} arenas in use"));
+            };
+            SLOTS[slot].next.store(start, Ordering::Release);
+            SLOTS[slot].live.store(0, Ordering::Release);
+            SLOTS[slot].end.store(start + len, Ordering::Release);
+
+            Ok(Self { slot, map, map_len, backing })
+        }
+
+        pub fn backing(&self) -> Backing {
+            self.backing
+        }
+
+        /// Runs `f`, serving its allocations on this thread from the arena.
+        /// Once the arena is full, allocations fall back to the system.
+        pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+            let previous = ACTIVE.with(|a| a.replace(self.slot));
+            let result = f();
+            ACTIVE.with(|a| a.set(previous));
+            result
+        }
+    }
+
+    impl Drop for Arena {
+        fn drop(&mut self) {
+            let slot = &SLOTS[self.slot];
+            if slot.live.load(Ordering::Acquire) != 0 {
+                // Something allocated in the arena outlives it. Leak the
+                // mapping (and its slot) rather than leave it dangling.
+                return;
+            }
+            slot.end.store(0, Ordering::Release);
+            slot.start.store(0, Ordering::Release);
+            unsafe { libc::munmap(self.map, self.map_len) };
+        }
+    }
+
+    // Returns the mapping, its length, the start of the usable (2MB aligned)
+    // region of `len` bytes and its backing.
+    fn map(len: usize, huge_pages: bool)
+        -> Result<(*mut libc::c_void, usize, usize, Backing), String>
+    {
+        let prot = libc::PROT_READ | libc::PROT_WRITE;
+        let flags = libc::MAP_PRIVATE | libc::MAP_ANONYMOUS;
+
+        if huge_pages {
+            let map = unsafe {
+                libc::mmap(std::ptr::null_mut(), len, prot,
+                    flags | libc::MAP_HUGETLB | libc::MAP_HUGE_2MB, -1, 0)
+            };
+            if map != libc::MAP_FAILED {
+                return Ok((map, len, map as usize, Backing::HugeTlb));
+            }
+        }
+
+        // Over-allocate so the region can start on a huge page boundary.
+        let map_len = len + HUGE_PAGE;
+        let map = unsafe { libc::mmap(std::ptr::null_mut(), map_len, prot, flags, -1, 0) };
+        if map == libc::MAP_FAILED {
+            return Err(format!("unable to map {} bytes: {}",
+                map_len, std::io::Error::last_os_error()));
+        }
+        let start = (map as usize).next_multiple_of(HUGE_PAGE);
+
+        if !huge_pages {
+            return Ok((map, map_len, start, Backing::Normal));
+        }
+        let advised = unsafe {
+            libc::madvise(start as *mut libc::c_void, len, libc::MADV_HUGEPAGE)
+        };
+        if advised != 0 {
+            let e = std::io::Error::last_os_error();
+            unsafe { libc::munmap(map, map_len) };
+            return Err(format!("no huge pages reserved and transparent huge pages \
+                unavailable: {e}"));
+        }
+        Ok((map, map_len, start, Backing::Transparent))
+    }
+
+    fn bind(start: usize, len: usize, node: usize) -> Result<(), String> {
+        const MASK_BITS: usize = 1024;
+        if node >= MASK_BITS {
+            return Err(format!("numa node {node} out of range"));
+        }
+        let mut mask = [0u64; MASK_BITS / 64];
+        mask[node / 64] |= 1 << (node % 64);
+
+        let result = unsafe {
+            libc::syscall(libc::SYS_mbind, start, len, libc::MPOL_BIND,
+                mask.as_ptr(), MASK_BITS + 1, MPOL_MF_STRICT | MPOL_MF_MOVE)
+        };
+        if result == 0 {
+            Ok(())
+        }
+        else {
+            Err(format!("unable to bind memory to numa node {}: {}",
+                node, std::io::Error::last_os_error()))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_arena_scope() {
+            let arena = Arena::new(1 << 20, false, None).unwrap();
+            let start = SLOTS[arena.slot].start.load(Ordering::Acquire);
+            let end = SLOTS[arena.slot].end.load(Ordering::Acquire);
+            assert_eq!(start % HUGE_PAGE, 0);
+            assert_eq!(end - start, HUGE_PAGE);
+
+            // Without the global allocator installed, only bump is exercised.
+            let layout = Layout::from_size_align(100, 64).unwrap();
+            let ptr = bump(&SLOTS[arena.slot], layout).unwrap() as usize;
+            assert!(start <= ptr && ptr + 100 <= end && ptr % 64 == 0);
+
+            let too_big = Layout::from_size_align(HUGE_PAGE, 4).unwrap();
+            assert!(bump(&SLOTS[arena.slot], too_big).is_none());
+
+            unsafe { PlacementAllocator.dealloc(ptr as *mut u8, layout) };
+            assert_eq!(SLOTS[arena.slot].live.load(Ordering::Acquire), 0);
+        }
+    }
+}
@@ -0,0 +1,566 @@
+//! Aggregates raw results into plot-ready summaries, in place of
+//! `scripts/results/process.py`. For each experiment, the runs of each
+//! algorithm are reduced to one row per point (x, and y for 2D sweeps) with
+//! the mean, standard deviation and 95% confidence interval of the time and of
+//! every recorded counter, the number of input elements, the throughput, and
+//! the throughput relative to each algorithm of the experiment. Counters are
+//! read with the same getters as the CSV export, so both follow the results
+//! schema.
+//!
+//! The queries of a real dataset range from tiny to huge sets at a single
+//! point, so a mean over them mostly reflects the largest. `stratify` instead
+//! reduces the runs of each point per regime of its queries: the ratio of the
+//! largest set to the smallest and the selectivity of the intersection, from
+//! the cardinalities recorded per query (see `QueryLens`).
+
+use std::{collections::{BTreeMap, HashMap}, io::{self, Write}};
+use setops::intersect;
+use crate::{
+    compare::Z_95, datafile::DatafileSet, export, generators, get_algorithms, props_at,
+    schema::{
+        AlgorithmId, AlgorithmResults, DatasetId, DatasetInfo, DatasetResults, DatasetType,
+        ExperimentEntry, Results, ResultRun, PERCENT, PERCENT_F,
+    },
+};
+
+/// Bytes per element of the benchmark datasets.
+const ELEMENT_BYTES: f64 = 4.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimate {
+    pub mean: f64,
+    /// Sample standard deviation, 0 for a single sample.
+    pub std: f64,
+    /// Half-width of the 95% confidence interval of the mean by the normal
+    /// approximation, if there are at least two samples.
+    pub ci: Option<f64>,
+    pub samples: usize,
+}
+
+impl Estimate {
+    pub fn of(samples: &[u64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let count = samples.len();
+        let mean = samples.iter().sum::<u64>() as f64 / count as f64;
+        let variance = if count > 1 {
+            samples.iter().map(|&s| (s as f64 - mean).powi(2)).sum::<f64>() / (count - 1) as f64
+        }
+        else {
+            0.0
+        };
+        let std = variance.sqrt();
+        let ci = (count > 1).then(|| Z_95 * std / (count as f64).sqrt());
+
+        Some(Self { mean, std, ci, samples: count })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointSummary {
+    pub x: u32,
+    pub y: Option<u32>,
+    /// Nanoseconds.
+    pub time: Estimate,
+    /// Elements of the input sets, from the recorded input sizes or else the
+    /// generator's set lengths. Unknown for real datasets without input sizes.
+    pub element_count: Option<f64>,
+    /// Each counter recorded for the point, named as in the CSV export.
+    pub counters: Vec<(String, Estimate)>,
+    /// Throughput relative to each algorithm of the experiment at the same
+    /// point, i.e., that algorithm's mean time over this one.
+    pub relative: BTreeMap<AlgorithmId, f64>,
+}
+
+impl PointSummary {
+    /// Elements per second.
+    pub fn throughput(&self) -> Option<f64> {
+        self.element_count.map(|count| count / (self.time.mean / 1e9))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlgorithmSummary {
+    pub dataset: DatasetId,
+    pub algorithm: AlgorithmId,
+    pub points: Vec<PointSummary>,
+}
+
+type AlgorithmRuns<'a> = Vec<(&'a AlgorithmId, &'a Vec<ResultRun>)>;
+
+// The dataset of `experiment` and the runs of each of its algorithms, in the
+// order they are listed, leaving out algorithms skipped as unsupported.
+fn experiment_runs<'a>(results: &'a Results, experiment: &ExperimentEntry)
+    -> Result<(&'a DatasetResults, AlgorithmRuns<'a>), String>
+{
+    let missing = |what: String| format!("experiment {}: no results for {what}", experiment.name);
+
+    let dataset = results.datasets.get(&experiment.dataset)
+        .ok_or_else(|| missing(format!("dataset {}", experiment.dataset)))?;
+    let algos: &AlgorithmResults = match experiment.output {
+        Some(output) => dataset.outputs.get(&output)
+            .ok_or_else(|| missing(format!("output {}", output.name())))?,
+        None => &dataset.algos,
+    };
+    let algorithms = get_algorithms(&results.algorithm_sets, &experiment.algorithms)
+        .map_err(|e| format!("experiment {}: {}", experiment.name, e))?;
+
+    let mut runs: AlgorithmRuns = Vec::new();
+    for algorithm in algorithms {
+        if results.unsupported.contains_key(algorithm) {
+            continue;
+        }
+        let (algorithm, algorithm_runs) = algos.get_key_value(algorithm)
+            .ok_or_else(|| missing(format!("algorithm {algorithm}")))?;
+        runs.push((algorithm, algorithm_runs));
+    }
+    Ok((dataset, runs))
+}
+
+/// Summarises the runs of each algorithm of `experiment`, in the order they
+/// are listed, leaving out algorithms skipped as unsupported.
+pub fn summarise_experiment(results: &Results, experiment: &ExperimentEntry)
+    -> Result<Vec<AlgorithmSummary>, String>
+{
+    let (dataset, algorithm_runs) = experiment_runs(results, experiment)?;
+
+    let mut summaries = Vec::new();
+    for (algorithm, runs) in algorithm_runs {
+        let columns = export::counter_columns(|| runs.iter());
+        let points = runs.iter()
+            .filter_map(|run| Some(PointSummary {
+                x: run.x,
+                y: run.y,
+                time: Estimate::of(&run.times)?,
+                element_count: element_count(&dataset.info, run),
+                counters: columns.iter()
+                    .filter_map(|(name, get)| Some((name.clone(), Estimate::of(get(run)?)?)))
+                    .collect(),
+                relative: BTreeMap::new(),
+            }))
+            .collect();
+
+        summaries.push(AlgorithmSummary {
+            dataset: experiment.dataset.clone(),
+            algorithm: algorithm.clone(),
+            points,
+        });
+    }
+
+    let means: Vec<(AlgorithmId, HashMap<(u32, Option<u32>), f64>)> = summaries.iter()
+        .map(|s| (
+            s.algorithm.clone(),
+            s.points.iter().map(|p| ((p.x, p.y), p.time.mean)).collect()))
+        .collect();
+
+    for point in summaries.iter_mut().flat_map(|s| &mut s.points) {
+        for (other, times) in &means {
+            if let Some(time) = times.get(&(point.x, point.y)) {
+                point.relative.insert(other.clone(), time / point.time.mean);
+            }
+        }
+    }
+    Ok(summaries)
+}
+
+/// Width of the selectivity strata, out of PERCENT.
+pub const SELECTIVITY_STEP: u32 = 100;
+
+/// Cardinalities of a query, recorded with each run on a real dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryLens {
+    /// Smallest set.
+    pub len_a: u64,
+    /// Largest set.
+    pub len_b: u64,
+    /// Intersection of all the sets.
+    pub intersection_len: u64,
+}
+
+impl QueryLens {
+    pub fn of(sets: &[DatafileSet]) -> Self {
+        let mut by_len: Vec<&DatafileSet> = sets.iter().collect();
+        by_len.sort_by_key(|s| s.len());
+
+        let intersection_len = match by_len.split_first() {
+            Some((first, rest)) => rest.iter()
+                .fold(first.to_vec(), |result, set|
+                    intersect::run_2set(&result, set, intersect::branchless_merge))
+                .len(),
+            None => 0,
+        };
+        Self {
+            len_a: by_len.first().map_or(0, |s| s.len() as u64),
+            len_b: by_len.last().map_or(0, |s| s.len() as u64),
+            intersection_len: intersection_len as u64,
+        }
+    }
+
+    pub fn stratum(&self) -> Stratum {
+        let ratio = self.len_b / self.len_a.max(1);
+        let mut ratio_bound = 1;
+        while ratio_bound * 10 <= ratio {
+            ratio_bound *= 10;
+        }
+        let selectivity = (self.intersection_len * PERCENT as u64 / self.len_a.max(1)) as u32;
+        Stratum {
+            ratio: ratio_bound,
+            selectivity: selectivity / SELECTIVITY_STEP * SELECTIVITY_STEP,
+        }
+    }
+}
+
+/// A regime of queries: those whose largest set is `ratio` to 10 × `ratio`
+/// times their smallest, and whose intersection holds `selectivity` up to
+/// `selectivity + SELECTIVITY_STEP` of the smallest set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Stratum {
+    /// Lower bound of |B| / |A|, a power of 10.
+    pub ratio: u64,
+    /// Lower bound of |A ∩ B| / |A| out of PERCENT, a multiple of
+    /// `SELECTIVITY_STEP`.
+    pub selectivity: u32,
+}
+
+// x, y and stratum.
+type StratumPoint = (u32, Option<u32>, Stratum);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StratumSummary {
+    pub x: u32,
+    pub y: Option<u32>,
+    pub stratum: Stratum,
+    /// Nanoseconds, over the queries of the stratum.
+    pub time: Estimate,
+    /// Throughput relative to each algorithm of the experiment over the
+    /// queries of the same point and stratum.
+    pub relative: BTreeMap<AlgorithmId, f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StratifiedSummary {
+    pub dataset: DatasetId,
+    pub algorithm: AlgorithmId,
+    /// In order of point, then stratum.
+    pub strata: Vec<StratumSummary>,
+}
+
+/// Summarises the runs of each algorithm of `experiment` per point and
+/// stratum of its queries, as `summarise_experiment` does per point. Runs
+/// without recorded cardinalities, e.g., on synthetic datasets, are left out.
+pub fn stratify(results: &Results, experiment: &ExperimentEntry)
+    -> Result<Vec<StratifiedSummary>, String>
+{
+    let (_, algorithm_runs) = experiment_runs(results, experiment)?;
+
+    let mut summaries = Vec::new();
+    for (algorithm, runs) in algorithm_runs {
+        let mut groups: BTreeMap<StratumPoint, Vec<u64>> = BTreeMap::new();
+        for run in runs {
+            let (Some(len_a), Some(len_b), Some(intersection_len)) =
+                (&run.len_a, &run.len_b, &run.intersection_len)
+            else {
+                continue;
+            };
+            let samples = run.times.iter().zip(len_a).zip(len_b).zip(intersection_len);
+            for (((&time, &len_a), &len_b), &intersection_len) in samples {
+                let stratum = QueryLens { len_a, len_b, intersection_len }.stratum();
+                groups.entry((run.x, run.y, stratum)).or_default().push(time);
+            }
+        }
+
+        summaries.push(StratifiedSummary {
+            dataset: experiment.dataset.clone(),
+            algorithm: algorithm.clone(),
+            strata: groups.into_iter()
+                .filter_map(|((x, y, stratum), times)| Some(StratumSummary {
+                    x,
+                    y,
+                    stratum,
+                    time: Estimate::of(&times)?,
+                    relative: BTreeMap::new(),
+                }))
+                .collect(),
+        });
+    }
+
+    let means: Vec<(AlgorithmId, HashMap<StratumPoint, f64>)> = summaries.iter()
+        .map(|s| (
+            s.algorithm.clone(),
+            s.strata.iter().map(|p| ((p.x, p.y, p.stratum), p.time.mean)).collect()))
+        .collect();
+
+    for point in summaries.iter_mut().flat_map(|s| &mut s.strata) {
+        for (other, times) in &means {
+            if let Some(time) = times.get(&(point.x, point.y, point.stratum)) {
+                point.relative.insert(other.clone(), time / point.time.mean);
+            }
+        }
+    }
+    Ok(summaries)
+}
+
+fn element_count(info: &DatasetInfo, run: &ResultRun) -> Option<f64> {
+    if let Some(bytes) = run.input_bytes.as_ref().filter(|b| !b.is_empty()) {
+        let mean = bytes.iter().sum::<u64>() as f64 / bytes.len() as f64;
+        return Some(mean / ELEMENT_BYTES);
+    }
+    match &info.dataset_type {
+        DatasetType::Synthetic(s) => {
+            let lengths = generators::set_lengths(&props_at(s, run.x, run.y));
+            Some(lengths.iter().sum::<usize>() as f64)
+        },
+        DatasetType::Real(_) => None,
+    }
+}
+
+/// Writes the points of `summary` as CSV, one row per point. Columns of
+/// values missing for every point are left out, and cells of values missing
+/// for some points are empty.
+pub fn write_summary_csv<W: Write>(summary: &AlgorithmSummary, out: &mut W) -> io::Result<()> {
+    let points = &summary.points;
+    let has_y = points.iter().any(|p| p.y.is_some());
+    let has_elements = points.iter().any(|p| p.element_count.is_some());
+
+    // In order of first appearance, which follows the export's order.
+    let mut counters: Vec<&str> = Vec::new();
+    for (name, _) in points.iter().flat_map(|p| &p.counters) {
+        if !counters.contains(&name.as_str()) {
+            counters.push(name);
+        }
+    }
+    let mut relative: Vec<&AlgorithmId> = points.iter().flat_map(|p| p.relative.keys()).collect();
+    relative.sort();
+    relative.dedup();
+
+    write!(out, "x")?;
+    if has_y {
+        write!(out, ",y")?;
+    }
+    write!(out, ",samples,time_ns,time_ns_std,time_ns_ci")?;
+    if has_elements {
+        write!(out, ",element_count,throughput_eps,time_ns/element")?;
+    }
+    for name in &counters {
+        write!(out, ",{name},{name}_std,{name}_ci")?;
+    }
+    for other in &relative {
+        write!(out, ",throughput_vs_{other}")?;
+    }
+    writeln!(out)?;
+
+    for point in points {
+        write!(out, "{}", point.x)?;
+        if has_y {
+            write!(out, ",{}", optional(point.y))?;
+        }
+        write_estimate(out, Some(&point.time), true)?;
+        if has_elements {
+            write!(out, ",{},{},{}",
+                optional(point.element_count),
+                optional(point.throughput()),
+                optional(point.element_count.map(|c| point.time.mean / c)))?;
+        }
+        for name in &counters {
+            let estimate = point.counters.iter().find(|(n, _)| n == name).map(|(_, e)| e);
+            write_estimate(out, estimate, false)?;
+        }
+        for other in &relative {
+            write!(out, ",{}", optional(point.relative.get(*other)))?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Writes the strata of `summary` as CSV, one row per point and stratum, with
+/// the selectivity as a fraction.
+pub fn write_strata_csv<W: Write>(summary: &StratifiedSummary, out: &mut W) -> io::Result<()> {
+    let strata = &summary.strata;
+    let has_y = strata.iter().any(|p| p.y.is_some());
+
+    let mut relative: Vec<&AlgorithmId> = strata.iter().flat_map(|p| p.relative.keys()).collect();
+    relative.sort();
+    relative.dedup();
+
+    write!(out, "x")?;
+    if has_y {
+        write!(out, ",y")?;
+    }
+    write!(out, ",ratio,selectivity,samples,time_ns,time_ns_std,time_ns_ci")?;
+    for other in &relative {
+        write!(out, ",throughput_vs_{other}")?;
+    }
+    writeln!(out)?;
+
+    for point in strata {
+        write!(out, "{}", point.x)?;
+        if has_y {
+            write!(out, ",{}", optional(point.y))?;
+        }
+        write!(out, ",{},{}",
+            point.stratum.ratio, point.stratum.selectivity as f64 / PERCENT_F)?;
+        write_estimate(out, Some(&point.time), true)?;
+        for other in &relative {
+            write!(out, ",{}", optional(point.relative.get(*other)))?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn write_estimate<W: Write>(out: &mut W, estimate: Option<&Estimate>, samples: bool)
+    -> io::Result<()>
+{
+    if samples {
+        write!(out, ",{}", optional(estimate.map(|e| e.samples)))?;
+    }
+    write!(out, ",{},{},{}",
+        optional(estimate.map(|e| e.mean)),
+        optional(estimate.map(|e| e.std)),
+        optional(estimate.and_then(|e| e.ci)))
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Results {
+        crate::migrate::parse_results(r#"{
+            "experiments": [{"name": "e", "title": "e", "dataset": "d",
+                "algorithms": ["a", "b", "c"]}],
+            "algorithm_sets": {},
+            "unsupported": {"c": ["avx512f"]},
+            "datasets": {"d": {
+                "info": {"name": "d", "type": "synthetic", "vary": "selectivity",
+                    "to": 1000, "step": 500, "gen_count": 2, "set_count": 2,
+                    "density": 1, "selectivity": 0, "max_len": 10, "skewness_factor": 0},
+                "algos": {
+                    "a": [
+                        {"x": 0, "times": [100, 300], "branches": [10, 10],
+                            "l1d": {}, "l1i": {}, "ll": {}},
+                        {"x": 500, "times": [400], "input_bytes": [8000],
+                            "l1d": {}, "l1i": {}, "ll": {}}],
+                    "b": [{"x": 0, "times": [400, 400], "l1d": {}, "l1i": {}, "ll": {}}]
+                }
+            }}
+        }"#).unwrap()
+    }
+
+    #[test]
+    fn test_estimate() {
+        let estimate = Estimate::of(&[100, 300]).unwrap();
+        assert_eq!(estimate.mean, 200.0);
+        assert!((estimate.std - 141.421).abs() < 0.001);
+        assert!((estimate.ci.unwrap() - Z_95 * 100.0).abs() < 0.001);
+
+        assert_eq!(Estimate::of(&[5]).unwrap().ci, None);
+        assert_eq!(Estimate::of(&[]), None);
+    }
+
+    #[test]
+    fn test_summarise_experiment() {
+        let results = results();
+        let summaries = summarise_experiment(&results, &results.experiments[0]).unwrap();
+        let names: Vec<&str> = summaries.iter().map(|s| s.algorithm.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+
+        let a = &summaries[0].points;
+        assert_eq!(a[0].time.mean, 200.0);
+        // Two sets of 2^10 elements.
+        assert_eq!(a[0].element_count, Some(2048.0));
+        assert_eq!(a[0].counters[0].0, "branches");
+        assert_eq!(a[0].relative["b"], 2.0);
+        assert_eq!(a[0].relative["a"], 1.0);
+        // Recorded input sizes take precedence.
+        assert_eq!(a[1].element_count, Some(2000.0));
+        assert!(a[1].counters.is_empty());
+        assert!(!a[1].relative.contains_key("b"));
+
+        let mut csv = Vec::new();
+        write_summary_csv(&summaries[1], &mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "\
+            x,samples,time_ns,time_ns_std,time_ns_ci,element_count,throughput_eps,\
+            time_ns/element,throughput_vs_a,throughput_vs_b\n\
+            0,2,400,0,0,2048,5120000000,0.1953125,0.5,1\n");
+    }
+
+    #[test]
+    fn test_query_lens() {
+        let sets: Vec<DatafileSet> = vec![vec![1, 2, 3, 4, 5, 6], vec![2, 4], vec![2, 3, 4]];
+        let lens = QueryLens::of(&sets);
+        assert_eq!(lens, QueryLens { len_a: 2, len_b: 6, intersection_len: 2 });
+        assert_eq!(lens.stratum(), Stratum { ratio: 1, selectivity: 1000 });
+
+        let lens = QueryLens { len_a: 10, len_b: 2500, intersection_len: 3 };
+        assert_eq!(lens.stratum(), Stratum { ratio: 100, selectivity: 300 });
+        let lens = QueryLens { len_a: 0, len_b: 5, intersection_len: 0 };
+        assert_eq!(lens.stratum(), Stratum { ratio: 1, selectivity: 0 });
+    }
+
+    #[test]
+    fn test_stratify() {
+        let results = crate::migrate::parse_results(r#"{
+            "experiments": [{"name": "e", "title": "e", "dataset": "d",
+                "algorithms": ["a", "b"]}],
+            "algorithm_sets": {},
+            "datasets": {"d": {
+                "info": {"name": "d", "type": "real", "source": "webdocs",
+                    "gen_count": 3, "set_count_start": 2, "set_count_end": 2},
+                "algos": {
+                    "a": [{"x": 2, "times": [100, 300, 1000],
+                        "len_a": [10, 10, 10], "len_b": [20, 50, 5000],
+                        "intersection_len": [1, 1, 9],
+                        "l1d": {}, "l1i": {}, "ll": {}}],
+                    "b": [{"x": 2, "times": [200, 200, 500],
+                        "len_a": [10, 10, 10], "len_b": [20, 50, 5000],
+                        "intersection_len": [1, 1, 9],
+                        "l1d": {}, "l1i": {}, "ll": {}}]
+                }
+            }}
+        }"#).unwrap();
+
+        let summaries = stratify(&results, &results.experiments[0]).unwrap();
+        let a = &summaries[0].strata;
+        let strata: Vec<Stratum> = a.iter().map(|p| p.stratum).collect();
+        assert_eq!(strata, [
+            Stratum { ratio: 1, selectivity: 100 },
+            Stratum { ratio: 100, selectivity: 900 },
+        ]);
+        assert_eq!(a[0].time.mean, 200.0);
+        assert_eq!(a[0].time.samples, 2);
+        assert_eq!(a[0].relative["b"], 1.0);
+        assert_eq!(a[1].relative["b"], 0.5);
+
+        let mut csv = Vec::new();
+        write_strata_csv(&summaries[1], &mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "\
+            x,ratio,selectivity,samples,time_ns,time_ns_std,time_ns_ci,\
+            throughput_vs_a,throughput_vs_b\n\
+            2,1,0.1,2,200,0,0,1,1\n\
+            2,100,0.9,1,500,0,,2,1\n");
+
+        // Synthetic runs record no cardinalities.
+        let results = self::results();
+        let summaries = stratify(&results, &results.experiments[0]).unwrap();
+        assert!(summaries.iter().all(|s| s.strata.is_empty()));
+    }
+
+    #[test]
+    fn test_missing_results() {
+        let mut results = results();
+        let mut experiment = results.experiments[0].clone();
+        experiment.dataset = "missing".to_string();
+        let err = summarise_experiment(&results, &experiment).unwrap_err();
+        assert!(err.contains("no results for dataset missing"));
+
+        results.unsupported.clear();
+        let err = summarise_experiment(&results, &results.experiments[0]).unwrap_err();
+        assert!(err.contains("no results for algorithm c"));
+    }
+}
@@ -1,41 +1,66 @@
+pub mod query;
+pub mod subsample;
+
 use std::{
     path::PathBuf,
     fs::{File, self},
     io::{BufReader, BufRead}
 };
-use rand::{thread_rng, seq::SliceRandom};
+use rand::{SeedableRng, rngs::StdRng, thread_rng};
 use crate::{
     schema::*,
     datafile::{DatafileSet, self},
     fmt_open_err, path_str
 };
+use query::QuerySampler;
 
 const TEXT_FILE_EXT: &str = ".dat";
 const CACHE_EXT: &str = ".cache";
 
 pub fn generate_real_dataset(
+    dataset: &DatasetInfo,
     info: &RealDataset,
     root: &PathBuf,
     dataset_path: &PathBuf) -> Result<(), String>
 {
+    if let Some(subsample) = &info.subsample {
+        subsample::validate(info, subsample)?;
+    }
     let sets = load_sets(root, &info.source)?;
+    let sampler = QuerySampler::new(&sets, info)?;
 
     println!("Building intersections...");
 
     let _ = fs::remove_dir_all(&dataset_path);
-    for count in info.set_count_start..=info.set_count_end {
-        println!("  set count: {}", count);
-
-        let xdir = dataset_path.join(count.to_string());
-        fs::create_dir_all(&xdir)
-            .map_err(|e| format!(
-                "failed to create directory {}:\n{}",
-                xdir.to_str().unwrap_or("<unknown>"),
-                e.to_string()
-            ))?;
-
-        for i in 0..info.gen_count {
-            generate_real_intersection(&sets, &xdir, count as usize, i)?;
+    for x in crate::xvalues(dataset) {
+        match &info.subsample {
+            Some(_) => println!("  cardinality: {}", x),
+            None => println!("  set count: {}", x),
+        }
+        let count = info.set_count_at(x) as usize;
+
+        let xdir = crate::point_dir(dataset_path, x, None);
+        for (instance, dir) in crate::instance_dirs(dataset, &xdir).iter().enumerate() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!(
+                    "failed to create directory {}:\n{}",
+                    dir.to_str().unwrap_or("<unknown>"),
+                    e.to_string()
+                ))?;
+
+            for i in 0..info.gen_count {
+                match &info.subsample {
+                    Some(s) => {
+                        let rng = &mut StdRng::seed_from_u64(subsample::query_seed(s, instance, i));
+                        let sets: Vec<DatafileSet> = sampler.sample(count, rng)?.iter()
+                            .map(|set| subsample::subsample(set, x as usize, s.seed))
+                            .collect();
+                        write_real_intersection(sets.iter().collect(), dir, i)?;
+                    },
+                    None => write_real_intersection(
+                        sampler.sample(count, &mut thread_rng())?, dir, i)?,
+                }
+            }
         }
     }
 
@@ -109,20 +134,11 @@ fn parse_line(line: String) -> Result<DatafileSet, String> {
         .collect()
 }
 
-fn generate_real_intersection(
-    all_sets: &Vec<DatafileSet>,
-    xdir: &PathBuf,
-    set_count: usize,
-    i: usize) -> Result<(), String>
+fn write_real_intersection(mut sets: Vec<&DatafileSet>, xdir: &PathBuf, i: usize)
+    -> Result<(), String>
 {
-    let rng = &mut thread_rng();
-
-    let mut sets: Vec<&DatafileSet> = all_sets
-        .choose_multiple(rng, set_count)
-        .collect();
-
     sets.sort_by_key(|&s| s.len());
-    
+
     let pair_path = xdir.join(i.to_string());
 
     let dataset_file = File::create(&pair_path)
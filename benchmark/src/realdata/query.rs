@@ -0,0 +1,272 @@
+//! Strategies for choosing which sets of a real dataset form each query.
+
+use rand::{Rng, seq::SliceRandom};
+use crate::{
+    schema::{RealDataset, RealQuery, PERCENT},
+    datafile::DatafileSet,
+};
+
+// Attempts to find a clique or snowball sample before giving up on a query.
+const ATTEMPTS: usize = 10_000;
+const DEFAULT_STRATUM: u32 = 100;
+
+pub struct QuerySampler<'a> {
+    sets: &'a [DatafileSet],
+    query: RealQuery,
+    source: &'a str,
+    // Indices of non-empty sets from smallest to largest, used by stratified
+    // and top_k queries.
+    by_size: Vec<usize>,
+    // Number of sets in each stratum.
+    stratum_len: usize,
+}
+
+impl<'a> QuerySampler<'a> {
+    pub fn new(sets: &'a [DatafileSet], info: &'a RealDataset) -> Result<Self, String> {
+        let stratum = info.stratum.unwrap_or(DEFAULT_STRATUM);
+        if stratum == 0 || stratum > PERCENT {
+            return Err(format!(
+                "dataset {}: stratum must be between 1 and {}", info.source, PERCENT));
+        }
+
+        let by_size = match info.query {
+            RealQuery::Stratified | RealQuery::TopK => {
+                let mut by_size: Vec<usize> = (0..sets.len())
+                    .filter(|&i| !sets[i].is_empty())
+                    .collect();
+                by_size.sort_by_key(|&i| sets[i].len());
+                by_size
+            },
+            _ => Vec::new(),
+        };
+        if by_size.is_empty() && matches!(info.query, RealQuery::Stratified | RealQuery::TopK) {
+            return Err(format!(
+                "dataset {}: {:?} queries need a non-empty set", info.source, info.query));
+        }
+        let stratum_len =
+            ((by_size.len() as u64 * stratum as u64) / PERCENT as u64).max(1) as usize;
+
+        Ok(Self {
+            sets,
+            query: info.query,
+            source: &info.source,
+            by_size,
+            stratum_len,
+        })
+    }
+
+    /// Chooses the `k` sets of a query.
+    pub fn sample(&self, k: usize, rng: &mut impl Rng) -> Result<Vec<&'a DatafileSet>, String> {
+        let indices = match self.query {
+            RealQuery::Random => (k <= self.sets.len())
+                .then(|| rand::seq::index::sample(rng, self.sets.len(), k).into_vec()),
+            RealQuery::Stratified => self.sample_stratified(k, rng),
+            RealQuery::TopK => self.sample_top(k, rng),
+            RealQuery::Clique => sample_clique(self.sets, k, rng),
+            RealQuery::Snowball => sample_snowball(self.sets, k, rng),
+        };
+
+        let indices = indices.ok_or_else(|| format!(
+            "unable to sample a {:?} query of {} sets from {}",
+            self.query, k, self.source
+        ))?;
+        Ok(indices.into_iter().map(|i| &self.sets[i]).collect())
+    }
+
+    fn sample_stratified(&self, k: usize, rng: &mut impl Rng) -> Option<Vec<usize>> {
+        if k == 0 || self.stratum_len < k - 1 {
+            return None;
+        }
+        let smallest = &self.by_size[..self.stratum_len];
+        let largest = &self.by_size[self.by_size.len() - self.stratum_len..];
+
+        let small = *smallest.choose(rng)?;
+        let mut query = vec![small];
+        // The strata overlap if stratum is over half of the sets.
+        let large: Vec<usize> = largest.iter().copied().filter(|&i| i != small).collect();
+        query.extend(large.choose_multiple(rng, k - 1));
+
+        (query.len() == k).then_some(query)
+    }
+
+    fn sample_top(&self, k: usize, rng: &mut impl Rng) -> Option<Vec<usize>> {
+        let largest = &self.by_size[self.by_size.len() - self.stratum_len..];
+        let query: Vec<usize> = largest.choose_multiple(rng, k).copied().collect();
+        (query.len() == k).then_some(query)
+    }
+}
+
+/// Finds the vertices of a random `k`-clique, where `adjacency[v]` holds the
+/// sorted neighbours of vertex `v`. Each vertex is grown from a random
+/// neighbour of all previously chosen vertices, so for directed graphs every
+/// vertex is an out-neighbour of the vertices before it.
+fn sample_clique(adjacency: &[DatafileSet], k: usize, rng: &mut impl Rng)
+    -> Option<Vec<usize>>
+{
+    let starts: Vec<usize> = (0..adjacency.len())
+        .filter(|&v| adjacency[v].len() + 1 >= k)
+        .collect();
+
+    'attempt: for _ in 0..ATTEMPTS {
+        let start = *starts.choose(rng)?;
+        let mut clique = vec![start];
+        let mut candidates: Vec<i32> = adjacency[start].iter()
+            .copied()
+            .filter(|&v| is_vertex(adjacency, v) && v as usize != start)
+            .collect();
+
+        while clique.len() < k {
+            let Some(&next) = candidates.choose(rng) else {
+                continue 'attempt;
+            };
+            let next = next as usize;
+            clique.push(next);
+
+            let neighbours = &adjacency[next];
+            candidates.retain(|v| *v as usize != next && neighbours.binary_search(v).is_ok());
+        }
+        return Some(clique);
+    }
+    None
+}
+
+/// Finds `k` distinct vertices by snowball sampling: starting from a random
+/// vertex, each following vertex is a random neighbour of a random vertex
+/// already chosen.
+fn sample_snowball(adjacency: &[DatafileSet], k: usize, rng: &mut impl Rng)
+    -> Option<Vec<usize>>
+{
+    let starts: Vec<usize> = (0..adjacency.len())
+        .filter(|&v| !adjacency[v].is_empty() || k <= 1)
+        .collect();
+
+    'attempt: for _ in 0..ATTEMPTS {
+        let mut sample = vec![*starts.choose(rng)?];
+        let mut misses = 0;
+
+        while sample.len() < k {
+            let from = *sample.choose(rng).unwrap();
+            let next = adjacency[from].choose(rng).copied();
+
+            match next {
+                Some(v) if is_vertex(adjacency, v) && !sample.contains(&(v as usize)) => {
+                    sample.push(v as usize);
+                    misses = 0;
+                },
+                _ => {
+                    // The neighbourhood found so far may be too small.
+                    misses += 1;
+                    if misses > 8 * k {
+                        continue 'attempt;
+                    }
+                },
+            }
+        }
+        return Some(sample);
+    }
+    None
+}
+
+fn is_vertex(adjacency: &[DatafileSet], v: i32) -> bool {
+    v >= 0 && (v as usize) < adjacency.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    // Vertices 0-3 form a 4-clique, 4 is connected only to 0, and 5 only to 4.
+    fn graph() -> Vec<DatafileSet> {
+        vec![
+            vec![1, 2, 3, 4],
+            vec![0, 2, 3],
+            vec![0, 1, 3],
+            vec![0, 1, 2],
+            vec![0, 5],
+            vec![4],
+        ]
+    }
+
+    fn info(query: RealQuery, stratum: Option<u32>) -> RealDataset {
+        RealDataset {
+            source: "test".to_string(),
+            gen_count: 1,
+            set_count_start: 2,
+            set_count_end: 2,
+            query,
+            stratum,
+            subsample: None,
+        }
+    }
+
+    #[test]
+    fn test_sample_clique() {
+        let adjacency = graph();
+        let rng = &mut thread_rng();
+
+        for k in 1..=4 {
+            for _ in 0..100 {
+                let clique = sample_clique(&adjacency, k, rng).unwrap();
+                assert_eq!(clique.len(), k);
+
+                for (i, &u) in clique.iter().enumerate() {
+                    for &v in &clique[i + 1..] {
+                        assert!(adjacency[u].contains(&(v as i32)));
+                    }
+                }
+            }
+        }
+        assert!(sample_clique(&adjacency, 5, rng).is_none());
+    }
+
+    #[test]
+    fn test_sample_snowball() {
+        let adjacency = graph();
+        let rng = &mut thread_rng();
+
+        for k in 1..=6 {
+            for _ in 0..100 {
+                let sample = sample_snowball(&adjacency, k, rng).unwrap();
+                assert_eq!(sample.len(), k);
+
+                for (i, &v) in sample.iter().enumerate().skip(1) {
+                    assert!(sample[..i].iter().any(|&u| adjacency[u].contains(&(v as i32))));
+                }
+            }
+        }
+        assert!(sample_snowball(&adjacency, 7, rng).is_none());
+    }
+
+    #[test]
+    fn test_stratified_and_top_k() {
+        // Set i has i + 1 elements.
+        let sets: Vec<DatafileSet> = (0..100).map(|i| (0..=i).collect()).collect();
+        let rng = &mut thread_rng();
+
+        let info = info(RealQuery::Stratified, Some(100));
+        let sampler = QuerySampler::new(&sets, &info).unwrap();
+        for _ in 0..100 {
+            let query = sampler.sample(3, rng).unwrap();
+            assert!(query[0].len() <= 10);
+            assert!(query[1..].iter().all(|s| s.len() > 90));
+            assert_ne!(query[1], query[2]);
+        }
+
+        let info = self::info(RealQuery::TopK, Some(50));
+        let sampler = QuerySampler::new(&sets, &info).unwrap();
+        for _ in 0..100 {
+            let query = sampler.sample(4, rng).unwrap();
+            assert!(query.iter().all(|s| s.len() > 95));
+        }
+        assert!(sampler.sample(6, rng).is_err());
+
+        let info = self::info(RealQuery::TopK, Some(0));
+        assert!(QuerySampler::new(&sets, &info).is_err());
+
+        let empty: Vec<DatafileSet> = vec![Vec::new(); 4];
+        for query in [RealQuery::Stratified, RealQuery::TopK] {
+            assert!(QuerySampler::new(&empty, &self::info(query, None)).is_err());
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Deterministic subsampling of real sets to a target cardinality, so that a
+//! single large real dataset can provide a sweep of set sizes.
+//!
+//! Each element is ranked by a hash of itself under the seed, and a set keeps
+//! its `target` lowest-ranked elements (a bottom-k sample). As the rank of an
+//! element is the same in every set, an element shared by two sets tends to
+//! be kept or dropped by both, so intersections shrink with their sets rather
+//! than vanishing as with independent samples. The samples of a set are also
+//! nested: a smaller target keeps a subset of a larger one.
+
+use crate::{datafile::DatafileSet, generators::fmix64, schema::{RealDataset, Subsample}};
+
+/// The `target` elements of the sorted `set` ranked lowest under `seed`, in
+/// ascending order. A set with at most `target` elements is kept whole.
+pub fn subsample(set: &[i32], target: usize, seed: u64) -> DatafileSet {
+    if set.len() <= target {
+        return set.to_vec();
+    }
+    if target == 0 {
+        return Vec::new();
+    }
+
+    let mut ranked: Vec<(u64, i32)> = set.iter()
+        .map(|&element| (rank(element, seed), element))
+        .collect();
+    // fmix64 is a bijection, so distinct elements never tie.
+    ranked.select_nth_unstable(target - 1);
+
+    let mut sample: DatafileSet = ranked[..target].iter()
+        .map(|&(_, element)| element)
+        .collect();
+    sample.sort_unstable();
+    sample
+}
+
+fn rank(element: i32, seed: u64) -> u64 {
+    fmix64(seed ^ fmix64(element as u32 as u64))
+}
+
+/// Seed of the query sampled for datafile `index` of `instance`, the same at
+/// every point of the dataset.
+pub fn query_seed(subsample: &Subsample, instance: usize, index: usize) -> u64 {
+    fmix64(fmix64(subsample.seed ^ fmix64(instance as u64)) ^ index as u64)
+}
+
+/// Checks the sweep of a subsampled dataset.
+pub fn validate(info: &RealDataset, subsample: &Subsample) -> Result<(), String> {
+    if info.set_count_start != info.set_count_end {
+        return Err(format!(
+            "dataset {}: set_count_start and set_count_end must be equal with subsample",
+            info.source));
+    }
+    if subsample.step == 0 || subsample.from > subsample.to {
+        return Err(format!(
+            "dataset {}: subsample needs from <= to and a non-zero step", info.source));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subsample() {
+        let set: DatafileSet = (0..1000).map(|i| i * 3).collect();
+
+        let sample = subsample(&set, 100, 7);
+        assert_eq!(sample.len(), 100);
+        assert!(sample.windows(2).all(|w| w[0] < w[1]));
+        assert!(sample.iter().all(|e| set.binary_search(e).is_ok()));
+
+        assert_eq!(subsample(&set, 100, 7), sample);
+        assert_ne!(subsample(&set, 100, 8), sample);
+
+        let smaller = subsample(&set, 10, 7);
+        assert!(smaller.iter().all(|e| sample.binary_search(e).is_ok()));
+
+        assert_eq!(subsample(&set, 2000, 7), set);
+        assert!(subsample(&set, 0, 7).is_empty());
+    }
+
+    #[test]
+    fn test_subsample_keeps_intersections() {
+        // Both sets keep the same elements of their intersection.
+        let a: DatafileSet = (0..2000).collect();
+        let b: DatafileSet = (1000..3000).collect();
+        let (sample_a, sample_b) = (subsample(&a, 500, 1), subsample(&b, 500, 1));
+
+        let shared_in = |sample: &[i32], other: &[i32]| -> Vec<i32> {
+            sample.iter().copied().filter(|e| other.binary_search(e).is_ok()).collect()
+        };
+        let kept = shared_in(&sample_a, &sample_b);
+        assert!(!kept.is_empty());
+
+        // Every shared element ranked below both thresholds is in both samples.
+        let threshold = |sample: &[i32]| sample.iter().map(|&e| rank(e, 1)).max().unwrap();
+        let limit = threshold(&sample_a).min(threshold(&sample_b));
+        let expected: Vec<i32> = (1000..2000).filter(|&e| rank(e, 1) <= limit).collect();
+        assert_eq!(kept, expected);
+    }
+}
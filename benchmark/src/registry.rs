@@ -0,0 +1,782 @@
+//! Capabilities of each algorithm accepted by the benchmark. Experiment files
+//! are validated against this registry when loaded so that misspelled names or
+//! algorithms unable to run on a dataset are reported before any benchmarking
+//! starts, rather than part way through a run.
+
+use std::collections::HashMap;
+use crate::{
+    get_algorithms, timer, environment,
+    schema::{
+        Experiment, ExperimentEntry, DatasetInfo, DatasetType, Parameter, AlgorithmSet,
+        AlgorithmVec, OutputMode, DEFAULT_MICROARCH,
+    },
+};
+
+/// Element widths (in bits) of sets an algorithm can intersect.
+const ANY_WIDTH: &[u32] = &[8, 16, 32, 64];
+const WIDTH_32: &[u32] = &[32];
+
+/// Width of elements in benchmark datasets.
+pub const DATASET_ELEMENT_BITS: u32 = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KSet {
+    Unsupported,
+    /// Intersects any number of sets directly.
+    Native,
+    /// 2-set algorithm extended to k sets with svs, which requires the
+    /// output of each step so is unavailable with `--count-only` or
+    /// `--output streaming`.
+    Svs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Has a dedicated 2-set implementation. Algorithms with native k-set
+    /// support can also run on 2-set datasets.
+    pub twoset: bool,
+    pub kset: KSet,
+    /// Operates on the BSR representation rather than sorted arrays.
+    pub bsr: bool,
+    pub element_bits: &'static [u32],
+    /// Target features which must be enabled for the algorithm to be built.
+    pub features: &'static [&'static str],
+}
+
+impl Capabilities {
+    pub fn supports_set_count(&self, set_count: u32, count_only: bool) -> bool {
+        let kset = match self.kset {
+            KSet::Unsupported => false,
+            KSet::Native => true,
+            KSet::Svs => !count_only,
+        };
+        if set_count == 2 {
+            self.twoset || kset
+        }
+        else {
+            kset
+        }
+    }
+}
+
+const fn scalar() -> Capabilities {
+    Capabilities {
+        twoset: true,
+        kset: KSet::Svs,
+        bsr: false,
+        element_bits: ANY_WIDTH,
+        features: &[],
+    }
+}
+
+const fn vector(features: &'static [&'static str]) -> Capabilities {
+    Capabilities {
+        twoset: true,
+        kset: KSet::Svs,
+        bsr: false,
+        element_bits: WIDTH_32,
+        features,
+    }
+}
+
+const fn bsr(features: &'static [&'static str]) -> Capabilities {
+    Capabilities {
+        twoset: true,
+        kset: KSet::Unsupported,
+        bsr: true,
+        element_bits: WIDTH_32,
+        features,
+    }
+}
+
+const fn kset() -> Capabilities {
+    Capabilities {
+        twoset: false,
+        kset: KSet::Native,
+        bsr: false,
+        element_bits: ANY_WIDTH,
+        features: &[],
+    }
+}
+
+const SSE: &[&str] = &["ssse3"];
+const AVX2: &[&str] = &["avx2"];
+const AVX512: &[&str] = &["avx512f"];
+const AVX512CD: &[&str] = &["avx512f", "avx512cd"];
+
+/// Algorithms with fixed names. Parameterised families (FESIA and compressed
+/// variants) are handled by `lookup`.
+pub const ALGORITHMS: &[(&str, Capabilities)] = &[
+    ("naive_merge",      scalar()),
+    ("branchless_merge", scalar()),
+    ("branchless_merge_unroll2", scalar()),
+    ("branchless_merge_unroll4", scalar()),
+    ("branchless_merge_cmov",    scalar()),
+    ("bmiss_scalar_3x",  scalar()),
+    ("bmiss_scalar_4x",  scalar()),
+    ("galloping",        scalar()),
+    ("binary_search",    scalar()),
+    ("baezayates",       scalar()),
+    // SSE
+    ("shuffling_sse",    vector(SSE)),
+    ("broadcast_sse",    vector(SSE)),
+    ("bmiss",            vector(SSE)),
+    ("bmiss_sttni",      vector(SSE)),
+    ("bmiss_galloping",  vector(SSE)),
+    ("qfilter",          vector(SSE)),
+    ("qfilter_v1",       vector(SSE)),
+    ("lbk_v1x4_sse",     vector(SSE)),
+    ("lbk_v1x8_sse",     vector(SSE)),
+    ("lbk_v3_sse",       vector(SSE)),
+    ("galloping_sse",    vector(SSE)),
+    ("simd_merge_sse",   vector(SSE)),
+    // AVX2
+    ("shuffling_avx2",   vector(AVX2)),
+    ("broadcast_avx2",   vector(AVX2)),
+    ("lbk_v1x8_avx2",    vector(AVX2)),
+    ("lbk_v1x16_avx2",   vector(AVX2)),
+    ("lbk_v3_avx2",      vector(AVX2)),
+    ("galloping_avx2",   vector(AVX2)),
+    ("simd_merge_avx2",  vector(AVX2)),
+    // AVX-512
+    ("shuffling_avx512",       vector(AVX512)),
+    ("broadcast_avx512",       vector(AVX512)),
+    ("vp2intersect_emulation", vector(AVX512)),
+    ("conflict_intersect",     vector(AVX512CD)),
+    ("lbk_v1x16_avx512",       vector(AVX512)),
+    ("lbk_v1x32_avx512",       vector(AVX512)),
+    ("lbk_v3_avx512",          vector(AVX512)),
+    ("galloping_avx512",       vector(AVX512)),
+    // Branch
+    ("shuffling_sse_branch",          vector(SSE)),
+    ("broadcast_sse_branch",          vector(SSE)),
+    ("bmiss_branch",                  vector(SSE)),
+    ("bmiss_sttni_branch",            vector(SSE)),
+    ("qfilter_branch",                vector(SSE)),
+    ("qfilter_v1_branch",             vector(SSE)),
+    ("shuffling_avx2_branch",         vector(AVX2)),
+    ("broadcast_avx2_branch",         vector(AVX2)),
+    ("shuffling_avx512_branch",       vector(AVX512)),
+    ("broadcast_avx512_branch",       vector(AVX512)),
+    ("vp2intersect_emulation_branch", vector(AVX512)),
+    ("conflict_intersect_branch",     vector(AVX512CD)),
+    // Aligned loads after a scalar prologue
+    ("shuffling_sse_aligned",    vector(SSE)),
+    ("shuffling_avx2_aligned",   vector(AVX2)),
+    ("shuffling_avx512_aligned", vector(AVX512)),
+    // Masked-load tails instead of a scalar merge
+    ("shuffling_sse_tail",    vector(SSE)),
+    ("shuffling_avx2_tail",   vector(AVX2)),
+    ("shuffling_avx512_tail", vector(AVX512)),
+    ("simd_merge_sse_tail",   vector(SSE)),
+    ("simd_merge_avx2_tail",  vector(AVX2)),
+    // All-pairs comparison for tiny sets
+    ("tiny_sse",    vector(SSE)),
+    ("tiny_avx2",   vector(AVX2)),
+    ("tiny_avx512", vector(AVX512)),
+    // Writes to a slice, so svs does not need a visitor.
+    ("qfilter_c", Capabilities { kset: KSet::Native, ..vector(SSE) }),
+    // BSR
+    ("branchless_merge_bsr",        bsr(&[])),
+    ("galloping_bsr",               bsr(&[])),
+    ("shuffling_sse_bsr",           bsr(SSE)),
+    ("broadcast_sse_bsr",           bsr(SSE)),
+    ("qfilter_bsr",                 bsr(SSE)),
+    ("galloping_sse_bsr",           bsr(SSE)),
+    ("shuffling_avx2_bsr",          bsr(AVX2)),
+    ("broadcast_avx2_bsr",          bsr(AVX2)),
+    ("galloping_avx2_bsr",          bsr(AVX2)),
+    ("shuffling_avx512_bsr",        bsr(AVX512)),
+    ("broadcast_avx512_bsr",        bsr(AVX512)),
+    ("galloping_avx512_bsr",        bsr(AVX512)),
+    ("shuffling_sse_bsr_branch",    bsr(SSE)),
+    ("broadcast_sse_bsr_branch",    bsr(SSE)),
+    ("qfilter_bsr_branch",          bsr(SSE)),
+    ("shuffling_avx2_bsr_branch",   bsr(AVX2)),
+    ("broadcast_avx2_bsr_branch",   bsr(AVX2)),
+    ("shuffling_avx512_bsr_branch", bsr(AVX512)),
+    ("broadcast_avx512_bsr_branch", bsr(AVX512)),
+    // k-set
+    ("svs",                   Capabilities { element_bits: &[32, 64], ..kset() }),
+    ("baezayates_k",          kset()),
+    ("small_adaptive",        kset()),
+    ("small_adaptive_sorted", kset()),
+    // Roaring
+    #[cfg(feature = "roaring")]
+    ("croaring",     Capabilities { element_bits: WIDTH_32, ..scalar() }),
+    #[cfg(feature = "roaring")]
+    ("croaring_opt", Capabilities { element_bits: WIDTH_32, ..scalar() }),
+    // Bitmaps over the largest element of the sets
+    ("bitmap_and", Capabilities { kset: KSet::Native, element_bits: WIDTH_32, ..scalar() }),
+    // EWAH compressed bitmaps
+    ("ewah_and", Capabilities { kset: KSet::Native, element_bits: WIDTH_32, ..scalar() }),
+    // Compressed
+    ("svb_probe", Capabilities { kset: KSet::Unsupported, element_bits: WIDTH_32, ..scalar() }),
+    // Galloping through a sampled rank index of the larger set
+    ("galloping_cached", Capabilities { kset: KSet::Unsupported, ..scalar() }),
+    // Galloping through skip pointers to every 256th element of the larger set
+    ("galloping_skip", Capabilities { kset: KSet::Unsupported, ..scalar() }),
+    // Prefetching `prefetch_distance` elements ahead in both sets
+    ("merge_prefetch",     Capabilities { kset: KSet::Unsupported, ..scalar() }),
+    ("galloping_prefetch", Capabilities { kset: KSet::Unsupported, ..scalar() }),
+    // Galloping with the growth factor tuned per dataset
+    ("galloping_auto", Capabilities { kset: KSet::Unsupported, ..scalar() }),
+    // Probing the smaller set into an Eytzinger layout of the larger set
+    ("eytzinger_probe",        Capabilities { kset: KSet::Unsupported, ..scalar() }),
+    ("eytzinger_probe_avx2",   Capabilities { kset: KSet::Unsupported, ..vector(AVX2) }),
+    ("eytzinger_probe_avx512", Capabilities { kset: KSet::Unsupported, ..vector(AVX512) }),
+];
+
+// Target features the benchmark was compiled with.
+const BUILD_FEATURES: &[(&str, bool)] = &[
+    ("ssse3", cfg!(target_feature = "ssse3")),
+    ("avx2", cfg!(target_feature = "avx2")),
+    ("avx512f", cfg!(target_feature = "avx512f")),
+    ("avx512cd", cfg!(target_feature = "avx512cd")),
+];
+
+/// Returns true if the benchmark was compiled with all of `features`, i.e.,
+/// algorithms requiring them are available.
+pub fn built_with(features: &[&str]) -> bool {
+    features.iter()
+        .all(|f| BUILD_FEATURES.iter().any(|(name, enabled)| name == f && *enabled))
+}
+
+/// Returns the features in `features` which are unavailable, either because
+/// the benchmark was not compiled with them or because the CPU running it
+/// does not support them. Running an algorithm built with a feature the CPU
+/// lacks would fault with an illegal instruction.
+pub fn missing_features(features: &[&'static str]) -> Vec<&'static str> {
+    features.iter()
+        .copied()
+        .filter(|f| !built_with(&[f]) || !cpu_supports(f))
+        .collect()
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn cpu_supports(feature: &str) -> bool {
+    match feature {
+        "ssse3" => is_x86_feature_detected!("ssse3"),
+        "avx2" => is_x86_feature_detected!("avx2"),
+        "avx512f" => is_x86_feature_detected!("avx512f"),
+        "avx512cd" => is_x86_feature_detected!("avx512cd"),
+        _ => false,
+    }
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+fn cpu_supports(_feature: &str) -> bool {
+    false
+}
+
+/// Returns the capabilities of the algorithm `name`, or `None` if the
+/// benchmark does not recognise it.
+pub fn lookup(name: &str) -> Option<Capabilities> {
+    if let Some((_, caps)) = ALGORITHMS.iter().find(|(n, _)| *n == name) {
+        return Some(*caps);
+    }
+    lookup_fesia(name)
+        .or_else(|| lookup_with_kernel(name))
+        .or_else(|| lookup_bsr_sorted(name))
+        .or_else(|| lookup_bloom(name))
+        .or_else(|| lookup_galloping_factor(name))
+}
+
+// galloping_factor<factor>
+fn lookup_galloping_factor(name: &str) -> Option<Capabilities> {
+    timer::parse_gallop_factor(name)?;
+    Some(Capabilities { kset: KSet::Unsupported, ..scalar() })
+}
+
+// bloom[<bits per element>]_galloping
+fn lookup_bloom(name: &str) -> Option<Capabilities> {
+    timer::parse_bloom_bits(name.strip_suffix("_galloping")?)?;
+    Some(Capabilities { kset: KSet::Unsupported, element_bits: WIDTH_32, ..scalar() })
+}
+
+// <BSR algorithm>_sorted
+fn lookup_bsr_sorted(name: &str) -> Option<Capabilities> {
+    let bsr = name.strip_suffix("_sorted")?;
+    let (_, caps) = ALGORITHMS.iter().find(|(n, _)| *n == bsr)?;
+    caps.bsr.then_some(*caps)
+}
+
+// fesia<8|16|32>_<sse|avx2|avx512>[_<hash>][_prefilter]_<hash scale> and
+// fesia_hash<8|16|32>[_<hash>]_<hash scale>
+fn lookup_fesia(name: &str) -> Option<Capabilities> {
+    let (prefix, hash_scale) = name.rsplit_once('_')?;
+    let hash_scale: f64 = hash_scale.parse().ok()?;
+    if hash_scale <= 0.0 {
+        return None;
+    }
+
+    let fesia = Capabilities {
+        kset: KSet::Native,
+        ..vector(&[])
+    };
+
+    if let Some(width) = prefix.strip_prefix("fesia_hash") {
+        let (width, _) = timer::split_hash_type(width);
+        return matches!(width, "8" | "16" | "32").then_some(fesia);
+    }
+
+    let prefix = prefix.strip_suffix("_prefilter").unwrap_or(prefix);
+    let (prefix, _) = timer::split_hash_type(prefix);
+    let (width, simd) = prefix.strip_prefix("fesia")?.split_once('_')?;
+    if !matches!(width, "8" | "16" | "32") {
+        return None;
+    }
+    let features = match simd {
+        "sse" => SSE,
+        "avx2" => AVX2,
+        "avx512" => AVX512,
+        _ => return None,
+    };
+    Some(Capabilities { features, ..fesia })
+}
+
+// <bp128|svb|partition|hybrid[<threshold>]|range[<window>]|union>_<2-set
+// algorithm on sorted arrays>
+fn lookup_with_kernel(name: &str) -> Option<Capabilities> {
+    let (encoding, kernel) = name.split_once('_')?;
+    let hybrid = timer::parse_hybrid_threshold(encoding).is_some();
+    let range = timer::parse_range_window(encoding).is_some();
+    if !hybrid && !range && !matches!(encoding, "bp128" | "svb" | "partition" | "union") {
+        return None;
+    }
+
+    let (_, kernel_caps) = ALGORITHMS.iter().find(|(n, _)| *n == kernel)?;
+    if !kernel_caps.twoset || kernel_caps.bsr {
+        return None;
+    }
+
+    // The first set against the union of any number of others.
+    if encoding == "union" {
+        return Some(Capabilities {
+            twoset: false,
+            kset: KSet::Native,
+            element_bits: WIDTH_32,
+            ..*kernel_caps
+        });
+    }
+
+    Some(Capabilities {
+        kset: KSet::Unsupported,
+        element_bits: WIDTH_32,
+        ..*kernel_caps
+    })
+}
+
+/// Checks that every algorithm of each experiment exists and can run on the
+/// experiment's dataset. If `names` is non-empty, only experiments with those
+/// names are checked.
+///
+/// Unknown algorithms and algorithms unable to run on any part of their
+/// dataset are errors, and all such errors are reported together. Algorithms
+/// which can only run on part of a dataset (e.g., a 2-set algorithm on a
+/// dataset varying the set count) are returned as warnings, as the runner
+/// skips the unsupported x-values.
+///
+/// Algorithm sets keyed by microarchitecture are checked in full, whichever
+/// list this machine would use.
+pub fn validate_experiment(
+    experiment: &Experiment,
+    names: &[String],
+    output: OutputMode) -> Result<Vec<String>, String>
+{
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    let mut set_names: Vec<&String> = experiment.algorithm_sets.keys().collect();
+    set_names.sort();
+    for name in set_names {
+        if let AlgorithmSet::ByMicroarch(lists) = &experiment.algorithm_sets[name] {
+            validate_microarch_keys(name, lists, &mut warnings);
+        }
+    }
+
+    if !output.built() {
+        errors.push(format!("output {} requires a build with avx512f", output.name()));
+    }
+
+    let environment = &experiment.environment;
+    if environment.max_replications == Some(0) {
+        errors.push("environment: max_replications must be at least 1".to_string());
+    }
+    if environment.target_rse.is_some() && environment.max_replications.is_none() {
+        errors.push("environment: target_rse requires max_replications".to_string());
+    }
+
+    let algorithm_sets: HashMap<String, AlgorithmVec> = experiment.algorithm_sets.iter()
+        .map(|(name, set)| (name.clone(), set.all()))
+        .collect();
+
+    for entry in &experiment.experiment {
+        if names.is_empty() || names.contains(&entry.name) {
+            validate_entry(
+                experiment, &algorithm_sets, entry, output, &mut errors, &mut warnings);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(warnings)
+    }
+    else {
+        Err(format!("invalid experiment file:\n  {}", errors.join("\n  ")))
+    }
+}
+
+fn validate_microarch_keys(
+    name: &str,
+    lists: &HashMap<String, AlgorithmVec>,
+    warnings: &mut Vec<String>)
+{
+    let mut keys: Vec<&String> = lists.keys().collect();
+    keys.sort();
+    for key in keys {
+        if key != DEFAULT_MICROARCH && !environment::MICROARCHS.contains(&key.as_str()) {
+            warnings.push(format!(
+                "algorithm set {}: unknown microarchitecture {} (expected one of {} or {})",
+                name, key, environment::MICROARCHS.join(", "), DEFAULT_MICROARCH));
+        }
+    }
+    if !lists.contains_key(DEFAULT_MICROARCH) {
+        warnings.push(format!(
+            "algorithm set {name}: no {DEFAULT_MICROARCH} list, so experiments using it \
+            fail on other microarchitectures"));
+    }
+}
+
+fn validate_entry(
+    experiment: &Experiment,
+    algorithm_sets: &HashMap<String, AlgorithmVec>,
+    entry: &ExperimentEntry,
+    output: OutputMode,
+    errors: &mut Vec<String>,
+    warnings: &mut Vec<String>)
+{
+    if let Some(own) = entry.output.filter(|o| !o.built()) {
+        warnings.push(format!(
+            "experiment {}: output {} requires a build with avx512f, skipping",
+            entry.name, own.name()));
+        return;
+    }
+    let output = entry.output.unwrap_or(output);
+    // Neither counting nor streaming keeps the output svs needs for each step.
+    let no_output = !output.readable();
+
+    let algorithms = match get_algorithms(algorithm_sets, &entry.algorithms) {
+        Ok(algorithms) => algorithms,
+        Err(e) => {
+            errors.push(format!("experiment {}: {}", entry.name, e));
+            return;
+        },
+    };
+
+    let dataset = experiment.dataset.iter().find(|d| d.name == entry.dataset);
+    if dataset.is_none() {
+        errors.push(format!(
+            "experiment {}: dataset {} not found", entry.name, entry.dataset));
+    }
+    let (min_sets, max_sets) = dataset.map(set_count_range).unwrap_or((2, 2));
+
+    for name in algorithms {
+        let Some(caps) = lookup(name) else {
+            let suggestion = closest_name(name)
+                .map(|s| format!(" (did you mean {s}?)"))
+                .unwrap_or_default();
+            errors.push(format!(
+                "experiment {}: unknown algorithm {}{}", entry.name, name, suggestion));
+            continue;
+        };
+
+        let unsupported: Vec<u32> = [min_sets, max_sets].into_iter()
+            .filter(|&set_count| !caps.supports_set_count(set_count, no_output))
+            .collect();
+
+        if let Some(&set_count) = unsupported.last() {
+            let mode = if set_count == 2 {
+                "2-set intersection"
+            }
+            else if no_output && caps.kset == KSet::Svs {
+                "k-set intersection with output count or streaming"
+            }
+            else {
+                "k-set intersection"
+            };
+            let message = format!(
+                "experiment {}: algorithm {} does not support {} (dataset {} has {} sets)",
+                entry.name, name, mode, entry.dataset, set_count);
+
+            if unsupported.len() == 2 || min_sets == max_sets {
+                errors.push(message);
+            }
+            else {
+                warnings.push(message);
+            }
+        }
+
+        if !caps.element_bits.contains(&DATASET_ELEMENT_BITS) {
+            errors.push(format!(
+                "experiment {}: algorithm {} does not support {}-bit elements",
+                entry.name, name, DATASET_ELEMENT_BITS));
+        }
+    }
+}
+
+/// Smallest and largest number of sets intersected in a dataset.
+fn set_count_range(info: &DatasetInfo) -> (u32, u32) {
+    match &info.dataset_type {
+        DatasetType::Synthetic(s) => {
+            let mut range = (s.intersection.set_count, s.intersection.set_count);
+            if s.vary == Parameter::SetCount {
+                range = (range.0.min(s.to), range.1.max(s.to));
+            }
+            if let Some(sweep) = s.vary_y.as_ref().filter(|y| y.vary == Parameter::SetCount) {
+                range = (range.0.min(sweep.to), range.1.max(sweep.to));
+            }
+            range
+        },
+        DatasetType::Real(r) => (r.set_count_start, r.set_count_end),
+    }
+}
+
+fn closest_name(name: &str) -> Option<&'static str> {
+    ALGORITHMS.iter()
+        .map(|(n, _)| (*n, edit_distance(name, n)))
+        .filter(|&(_, distance)| distance <= 3)
+        .min_by_key(|&(_, distance)| distance)
+        .map(|(n, _)| n)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitute = previous[j] + (ca != cb) as usize;
+            current[j + 1] = substitute.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use setops::intersect::prefetch::DEFAULT_PREFETCH_DISTANCE;
+    use crate::timer::Timer;
+
+    // The registry must agree with the algorithms the timer can construct.
+    #[test]
+    fn test_registry_matches_timer() {
+        let names = ALGORITHMS.iter()
+            .map(|(name, _)| name.to_string())
+            .chain(["fesia32_sse_16.0", "fesia8_avx512_2", "fesia_hash16_64.0",
+                "fesia16_avx2_prefilter_4.0", "fesia32_sse_crc32_16.0", "fesia8_avx2_xxhash_prefilter_2",
+                "fesia_hash32_mulshift_8.0",
+                "bp128_shuffling_sse", "svb_galloping", "partition_shuffling_avx2",
+                "hybrid_galloping", "hybrid0_shuffling_sse", "range_galloping", "range500_shuffling_sse",
+                "union_galloping", "union_shuffling_avx2", "bloom_galloping", "bloom16_galloping",
+                "galloping_factor4", "galloping_bsr_sorted",
+                "shuffling_avx512_bsr_sorted", "broadcast_sse_bsr_branch_sorted"]
+                .map(String::from));
+
+        for name in names {
+            let caps = lookup(&name).unwrap();
+            if built_with(caps.features) {
+                assert!(Timer::new(&name, OutputMode::default(), DEFAULT_PREFETCH_DISTANCE).is_some(), "{name} not recognised by timer");
+            }
+        }
+    }
+
+    #[test]
+    fn test_lookup_families() {
+        assert_eq!(lookup("fesia16_avx2_4.0").unwrap().features, AVX2);
+        assert_eq!(lookup("fesia16_avx2_prefilter_4.0").unwrap().features, AVX2);
+        assert_eq!(lookup("fesia16_avx2_identity_4.0").unwrap().features, AVX2);
+        assert!(lookup("fesia16_avx2_md5_4.0").is_none());
+        assert!(lookup("fesia_hash16_md5_4.0").is_none());
+        assert!(lookup("fesia16_avx1_4.0").is_none());
+        assert!(lookup("fesia64_sse_4.0").is_none());
+        assert!(lookup("fesia_hash8_0").is_none());
+        assert_eq!(lookup("bp128_galloping_avx512").unwrap().kset, KSet::Unsupported);
+        assert!(lookup("bp128_shuffling_sse_bsr").is_none());
+        assert_eq!(lookup("partition_bmiss").unwrap().features, SSE);
+        assert!(lookup("hybrid4096_naive_merge").is_some());
+        assert!(lookup("hybridx_naive_merge").is_none());
+        assert_eq!(lookup("range_shuffling_avx2").unwrap().features, AVX2);
+        assert!(lookup("range250_galloping").is_some());
+        assert!(lookup("range2000_galloping").is_none());
+        assert!(lookup("lz4_shuffling_sse").is_none());
+        let union = lookup("union_shuffling_avx2").unwrap();
+        assert_eq!((union.twoset, union.kset, union.features), (false, KSet::Native, AVX2));
+        assert!(union.supports_set_count(2, true));
+        assert!(lookup("union_galloping_bsr").is_none());
+        assert_eq!(lookup("bloom4_galloping").unwrap().kset, KSet::Unsupported);
+        assert!(lookup("bloom0_galloping").is_none());
+        assert!(lookup("bloom_shuffling_sse").is_none());
+        assert_eq!(lookup("galloping_factor8").unwrap().kset, KSet::Unsupported);
+        assert!(lookup("galloping_factor1").is_none());
+        assert!(lookup("galloping_factor").is_none());
+        assert!(lookup("qfilter_bsr_sorted").unwrap().bsr);
+        assert!(lookup("qfilter_sorted").is_none());
+    }
+
+    #[test]
+    fn test_validate() {
+        let experiment: Experiment = toml::from_str(r#"
+            [algorithm_sets]
+            twoset = ["galloping", "shufling_sse", "shuffling_sse_bsr"]
+
+            [[experiment]]
+            name = "k"
+            title = "k"
+            dataset = "kset"
+            algorithm_set = "twoset"
+
+            [[experiment]]
+            name = "vary_k"
+            title = "vary k"
+            dataset = "vary_kset"
+            algorithms = ["galloping_bsr", "small_adaptive"]
+
+            [[dataset]]
+            name = "kset"
+            type = "synthetic"
+            vary = "density"
+            to = 100
+            step = 10
+            gen_count = 1
+            set_count = 4
+            density = 1
+            selectivity = 1
+            max_len = 10
+            skewness_factor = 0
+
+            [[dataset]]
+            name = "vary_kset"
+            type = "synthetic"
+            vary = "set_count"
+            to = 8
+            step = 1
+            gen_count = 1
+            set_count = 2
+            density = 1
+            selectivity = 1
+            max_len = 10
+            skewness_factor = 0
+        "#).unwrap();
+
+        let err = validate_experiment(&experiment, &[], OutputMode::Unsafe).unwrap_err();
+        assert!(err.contains("unknown algorithm shufling_sse (did you mean shuffling_sse?)"));
+        assert!(err.contains("shuffling_sse_bsr does not support k-set intersection"));
+        assert!(!err.contains("algorithm galloping"));
+
+        let err = validate_experiment(&experiment, &[], OutputMode::Count).unwrap_err();
+        assert!(err.contains("galloping does not support k-set intersection with output count"));
+
+        let warnings = validate_experiment(&experiment, &["vary_k".to_string()], OutputMode::Unsafe).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("galloping_bsr does not support k-set intersection"));
+
+        let mut experiment = experiment;
+        experiment.environment.target_rse = Some(10);
+        let err = validate_experiment(&experiment, &["vary_k".to_string()], OutputMode::Unsafe).unwrap_err();
+        assert!(err.contains("target_rse requires max_replications"));
+
+        experiment.environment.max_replications = Some(0);
+        let err = validate_experiment(&experiment, &["vary_k".to_string()], OutputMode::Unsafe).unwrap_err();
+        assert!(err.contains("max_replications must be at least 1"));
+
+        experiment.environment.max_replications = Some(5);
+        validate_experiment(&experiment, &["vary_k".to_string()], OutputMode::Unsafe).unwrap();
+
+        // An experiment's own output overrides the default.
+        experiment.experiment[0].output = Some(OutputMode::Count);
+        let err = validate_experiment(&experiment, &["k".to_string()], OutputMode::Unsafe).unwrap_err();
+        assert!(err.contains("galloping does not support k-set intersection with output count"));
+    }
+
+    #[test]
+    fn test_validate_microarch_sets() {
+        let experiment: Experiment = toml::from_str(r#"
+            [algorithm_sets.vector]
+            default = ["shuffling_sse"]
+            zen4 = ["shuffling_avx512", "shuffling_sse"]
+            icelak = ["shufling_avx512"]
+
+            [[experiment]]
+            name = "vector"
+            title = "vector"
+            dataset = "twoset"
+            algorithm_set = "vector"
+
+            [[dataset]]
+            name = "twoset"
+            type = "synthetic"
+            vary = "density"
+            to = 100
+            step = 10
+            gen_count = 1
+            set_count = 2
+            density = 1
+            selectivity = 1
+            max_len = 10
+            skewness_factor = 0
+        "#).unwrap();
+
+        let err = validate_experiment(&experiment, &[], OutputMode::Unsafe).unwrap_err();
+        assert!(err.contains("unknown algorithm shufling_avx512"));
+
+        let mut experiment = experiment;
+        let AlgorithmSet::ByMicroarch(lists) =
+            experiment.algorithm_sets.get_mut("vector").unwrap() else { unreachable!() };
+        lists.remove("icelak");
+        lists.insert("icelake".to_string(), vec!["shuffling_avx512".to_string()]);
+        lists.remove(DEFAULT_MICROARCH);
+
+        let warnings = validate_experiment(&experiment, &[], OutputMode::Unsafe).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("no default list"));
+
+        let resolved = crate::resolve_algorithm_sets(&experiment.algorithm_sets, Some("zen4"));
+        assert_eq!(resolved.unwrap()["vector"], ["shuffling_avx512", "shuffling_sse"]);
+        assert!(crate::resolve_algorithm_sets(&experiment.algorithm_sets, None).is_err());
+    }
+
+    // The repository's experiments use every algorithm family.
+    #[cfg(feature = "roaring")]
+    #[test]
+    fn test_repo_experiment_file_valid() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../experiment.toml");
+        let experiment =
+            crate::migrate::parse_experiment(&std::fs::read_to_string(path).unwrap()).unwrap();
+        validate_experiment(&experiment, &[], OutputMode::Unsafe).unwrap();
+    }
+
+    #[test]
+    fn test_missing_features() {
+        assert!(missing_features(&[]).is_empty());
+        assert_eq!(missing_features(&["not_a_feature"]), vec!["not_a_feature"]);
+
+        let sse = missing_features(SSE);
+        assert_eq!(sse.is_empty(), built_with(SSE) && cpu_supports("ssse3"));
+
+        let avx512cd = missing_features(AVX512CD);
+        assert!(avx512cd.iter().all(|f| AVX512CD.contains(f)));
+        if !built_with(&["avx512f"]) {
+            assert!(avx512cd.contains(&"avx512f"));
+        }
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("galloping", "galloping"), 0);
+    }
+}
@@ -0,0 +1,209 @@
+//! Throughput model of intersection as a streaming read of its inputs. For
+//! each run the bytes of the input sets, the elements produced and the cycles
+//! taken (where counted) give the effective bandwidth, which is compared with
+//! the machine's peak read bandwidth, either configured or measured by a
+//! STREAM-like probe. A run at a large fraction of peak is bound by memory, so
+//! a faster kernel cannot improve it much; the rest are bound by compute.
+
+use std::{
+    collections::HashMap,
+    hint,
+    time::Instant,
+};
+use serde::{Deserialize, Serialize};
+use crate::schema::{AlgorithmId, DatasetId, DatasetResults, ResultRun, PERCENT_F};
+
+/// Fraction of peak bandwidth (out of PERCENT) from which a run is considered
+/// bound by memory.
+pub const MEMORY_BOUND: u32 = 800;
+
+// 128MiB, well beyond the last level cache of current machines.
+const PROBE_BYTES: usize = 128 << 20;
+const PROBE_REPEATS: usize = 5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Roofline {
+    /// Peak read bandwidth in MB/s.
+    pub peak_bandwidth: u64,
+    /// Whether `peak_bandwidth` was measured rather than configured.
+    pub measured: bool,
+    pub algorithms: Vec<AlgorithmRoofline>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AlgorithmRoofline {
+    pub dataset: DatasetId,
+    pub algorithm: AlgorithmId,
+    /// Largest fraction of peak (out of PERCENT) over the points.
+    pub max_of_peak: u32,
+    pub points: Vec<RooflinePoint>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RooflinePoint {
+    pub x: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<u32>,
+    /// Effective bandwidth in MB/s, i.e., input bytes over time.
+    pub bandwidth: u64,
+    /// Effective bandwidth as a fraction of peak (out of PERCENT).
+    pub of_peak: u32,
+    pub bound: Bound,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_per_cycle: Option<f64>,
+    /// Present when the result cardinality was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elements_per_cycle: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Bound {
+    Memory,
+    Compute,
+}
+
+/// Summarises every run recording its input size, in order of dataset and
+/// algorithm. `peak_bandwidth` is in MB/s.
+pub fn summarise(
+    datasets: &HashMap<DatasetId, DatasetResults>,
+    peak_bandwidth: u64,
+    measured: bool) -> Roofline
+{
+    let mut algorithms = Vec::new();
+
+    let mut dataset_ids: Vec<&DatasetId> = datasets.keys().collect();
+    dataset_ids.sort();
+
+    for dataset in dataset_ids {
+        let algos = &datasets[dataset].algos;
+        let mut names: Vec<&AlgorithmId> = algos.keys().collect();
+        names.sort();
+
+        for algorithm in names {
+            let points: Vec<RooflinePoint> = algos[algorithm].iter()
+                .filter_map(|run| point(run, peak_bandwidth))
+                .collect();
+            if points.is_empty() {
+                continue;
+            }
+            algorithms.push(AlgorithmRoofline {
+                dataset: dataset.clone(),
+                algorithm: algorithm.clone(),
+                max_of_peak: points.iter().map(|p| p.of_peak).max().unwrap_or(0),
+                points,
+            });
+        }
+    }
+
+    Roofline { peak_bandwidth, measured, algorithms }
+}
+
+/// Roofline point of a run, or `None` if it recorded no input sizes or took
+/// no time.
+pub fn point(run: &ResultRun, peak_bandwidth: u64) -> Option<RooflinePoint> {
+    let bytes: u64 = run.input_bytes.as_ref()?.iter().sum();
+    let nanos: u64 = run.times.iter().sum();
+    if nanos == 0 {
+        return None;
+    }
+
+    // Bytes per nanosecond is GB/s.
+    let bandwidth = (bytes as f64 / nanos as f64 * 1000.0).round() as u64;
+    let of_peak = if peak_bandwidth == 0 {
+        0
+    } else {
+        (bandwidth as f64 / peak_bandwidth as f64 * PERCENT_F).round() as u32
+    };
+
+    let cycles: Option<u64> = run.cpu_cycles.as_ref()
+        .map(|c| c.iter().sum())
+        .filter(|&c| c > 0);
+    let elements: Option<u64> = run.output_len.as_ref().map(|l| l.iter().sum());
+
+    Some(RooflinePoint {
+        x: run.x,
+        y: run.y,
+        bandwidth,
+        of_peak,
+        bound: if of_peak >= MEMORY_BOUND { Bound::Memory } else { Bound::Compute },
+        bytes_per_cycle: cycles.map(|c| bytes as f64 / c as f64),
+        elements_per_cycle: cycles.zip(elements).map(|(c, e)| e as f64 / c as f64),
+    })
+}
+
+/// Measures peak read bandwidth in MB/s by summing a buffer much larger than
+/// the last level cache, returning the fastest of several repeats.
+pub fn probe_bandwidth() -> u64 {
+    let buffer = vec![1u64; PROBE_BYTES / std::mem::size_of::<u64>()];
+
+    let nanos = (0..PROBE_REPEATS)
+        .map(|_| {
+            let start = Instant::now();
+            let sum = hint::black_box(&buffer).iter()
+                .fold(0u64, |acc, &v| acc.wrapping_add(v));
+            hint::black_box(sum);
+            start.elapsed().as_nanos() as u64
+        })
+        .min()
+        .unwrap()
+        .max(1);
+
+    (PROBE_BYTES as f64 / nanos as f64 * 1000.0).round() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(runs: &str) -> HashMap<DatasetId, DatasetResults> {
+        crate::migrate::parse_results(&format!(r#"{{
+            "experiments": [],
+            "algorithm_sets": {{}},
+            "datasets": {{"d": {{
+                "info": {{"name": "d", "type": "real", "source": "webdocs",
+                    "gen_count": 2, "set_count_start": 2, "set_count_end": 2}},
+                "algos": {{{runs}}}
+            }}}}
+        }}"#)).unwrap().datasets
+    }
+
+    #[test]
+    fn test_summarise() {
+        let datasets = results(r#"
+            "b": [{"x": 2, "times": [500, 500], "input_bytes": [4000, 4000],
+                "cpu_cycles": [1000, 1000], "output_len": [100, 300],
+                "l1d": {}, "l1i": {}, "ll": {}}],
+            "a": [
+                {"x": 1, "times": [1000], "input_bytes": [2000],
+                    "l1d": {}, "l1i": {}, "ll": {}},
+                {"x": 2, "times": [1000], "input_bytes": [20000],
+                    "l1d": {}, "l1i": {}, "ll": {}}],
+            "no_bytes": [{"x": 2, "times": [1000], "l1d": {}, "l1i": {}, "ll": {}}]
+        "#);
+
+        let roofline = summarise(&datasets, 10000, false);
+        let names: Vec<&str> = roofline.algorithms.iter().map(|a| a.algorithm.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+
+        // 2000 bytes in 1000ns is 2GB/s, a fifth of the 10GB/s peak.
+        let a = &roofline.algorithms[0];
+        assert_eq!(a.points[0].bandwidth, 2000);
+        assert_eq!(a.points[0].of_peak, 200);
+        assert_eq!(a.points[0].bound, Bound::Compute);
+        assert_eq!(a.points[0].bytes_per_cycle, None);
+        assert_eq!(a.points[1].of_peak, 2000);
+        assert_eq!(a.points[1].bound, Bound::Memory);
+        assert_eq!(a.max_of_peak, 2000);
+
+        let b = &roofline.algorithms[1].points[0];
+        assert_eq!(b.bandwidth, 8000);
+        assert_eq!(b.bytes_per_cycle, Some(4.0));
+        assert_eq!(b.elements_per_cycle, Some(0.2));
+    }
+
+    #[test]
+    fn test_probe_bandwidth() {
+        assert!(probe_bandwidth() > 0);
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use serde::{Serialize, Deserialize};
+use crate::{placement::Backing, roofline::Roofline};
 
 pub type DatasetId = String;
 pub type AlgorithmId = String;
@@ -10,11 +11,275 @@ pub type AlgorithmVec = Vec<AlgorithmId>;
 pub const PERCENT: u32 = 1000;
 pub const PERCENT_F: f64 = PERCENT as f64;
 
+/// Version of the experiment and results schema written by this build. Bump it
+/// with a migration in `migrate.rs` when changing the layout of either.
+pub const SCHEMA_VERSION: u32 = 1;
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Experiment {
+    // Absent (0) in files from before versioning.
+    #[serde(default)]
+    pub version: u32,
     pub experiment: Vec<ExperimentEntry>,
     pub dataset: Vec<DatasetInfo>,
-    pub algorithm_sets: HashMap<String, AlgorithmVec>,
+    pub algorithm_sets: HashMap<String, AlgorithmSet>,
+    #[serde(default)]
+    pub on_unsupported: OnUnsupported,
+    #[serde(default)]
+    pub environment: EnvironmentConfig,
+}
+
+/// Key of the list used by machines without a list for their microarchitecture.
+pub const DEFAULT_MICROARCH: &str = "default";
+
+/// Either a single list of algorithms, or lists keyed by CPU microarchitecture
+/// (see `environment::MICROARCHS`) with an optional `default` list, so that one
+/// experiment file can choose suitable kernels on each machine of a cluster.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum AlgorithmSet {
+    Algorithms(AlgorithmVec),
+    ByMicroarch(HashMap<String, AlgorithmVec>),
+}
+
+impl AlgorithmSet {
+    /// The list for `microarch`, falling back to the default list.
+    pub fn select(&self, microarch: Option<&str>) -> Option<&AlgorithmVec> {
+        match self {
+            AlgorithmSet::Algorithms(v) => Some(v),
+            AlgorithmSet::ByMicroarch(lists) => microarch
+                .and_then(|m| lists.get(m))
+                .or_else(|| lists.get(DEFAULT_MICROARCH)),
+        }
+    }
+
+    /// Every algorithm in any list, without duplicates.
+    pub fn all(&self) -> AlgorithmVec {
+        match self {
+            AlgorithmSet::Algorithms(v) => v.clone(),
+            AlgorithmSet::ByMicroarch(lists) => {
+                let mut keys: Vec<&String> = lists.keys().collect();
+                keys.sort();
+
+                let mut all = AlgorithmVec::new();
+                for algorithm in keys.into_iter().flat_map(|k| &lists[k]) {
+                    if !all.contains(algorithm) {
+                        all.push(algorithm.clone());
+                    }
+                }
+                all
+            },
+        }
+    }
+}
+
+/// Controls reducing and detecting measurement noise, copied into results so
+/// that runs are self-describing.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct EnvironmentConfig {
+    /// Pin the measurement thread to this logical CPU.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pin_cpu: Option<usize>,
+    /// Time a fixed spin loop before the run and after each dataset to detect
+    /// frequency changes (e.g., turbo or thermal throttling) during a sweep.
+    #[serde(default)]
+    pub spin_baseline: bool,
+    /// Warn if a spin loop baseline differs from the first by more than this
+    /// (out of PERCENT). Defaults to 5%.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_baseline_drift: Option<u32>,
+    /// Place benchmark sets in 2MB huge pages. Requires the `placement`
+    /// feature.
+    #[serde(default)]
+    pub huge_pages: bool,
+    /// Place benchmark sets on this NUMA node. Requires the `placement`
+    /// feature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numa_node: Option<usize>,
+    /// Touch every page of the inputs once they are read, and write over
+    /// each output buffer before it is used, so page faults are left out of
+    /// timed runs.
+    #[serde(default)]
+    pub pretouch: bool,
+    /// Copy the sets of each datafile into fresh allocations before each
+    /// timed run, so every run finds its inputs just written to new addresses
+    /// whatever ran before it, rather than left in the caches by an earlier
+    /// run of another algorithm or pass.
+    #[serde(default)]
+    pub fresh_inputs: bool,
+    /// Time each algorithm at each point (one pass over the point's
+    /// datafiles) up to this many times. Defaults to a single pass.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_replications: Option<u32>,
+    /// Stop replicating once the relative standard error of the mean pass
+    /// time is at most this (out of PERCENT). Requires `max_replications`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_rse: Option<u32>,
+    /// Distance, in elements, that the `_prefetch` kernels prefetch ahead in
+    /// both sets. Defaults to `prefetch::DEFAULT_PREFETCH_DISTANCE`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefetch_distance: Option<usize>,
+    /// Growth factors `galloping_auto` chooses between for each dataset.
+    /// Defaults to 2, 4, 8 and 16.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gallop_factors: Option<Vec<usize>>,
+    /// Summarise the effective bandwidth of each run against peak read
+    /// bandwidth in the `roofline` object of the results.
+    #[serde(default)]
+    pub roofline: bool,
+    /// Peak read bandwidth in MB/s for the roofline summary. Measured with a
+    /// STREAM-like probe if absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_bandwidth: Option<u64>,
+    /// Measure the mean frequency of the measurement CPU while timing each
+    /// cell (an algorithm at a point), recording it in the results.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_monitor: Option<FrequencySource>,
+    /// Milliseconds between frequency samples with `frequency_monitor =
+    /// "sysfs"`. Defaults to 10.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_sample_ms: Option<u64>,
+    /// Annotate cells whose mean frequency is more than this (out of PERCENT)
+    /// below the highest mean of any cell before them. Defaults to 5%.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_frequency_drop: Option<u32>,
+    /// Sleep this many milliseconds after each cell of an algorithm using
+    /// AVX-512, so that a downclocked CPU recovers before the next cell.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avx512_cooldown_ms: Option<u64>,
+}
+
+impl EnvironmentConfig {
+    /// Whether benchmark sets must be allocated in a placement arena.
+    pub fn places_sets(&self) -> bool {
+        self.huge_pages || self.numa_node.is_some()
+    }
+}
+
+/// Where `frequency_monitor` reads the frequency of the measurement CPU.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FrequencySource {
+    /// Sample the current frequency from cpufreq, or from /proc/cpuinfo
+    /// without cpufreq, on another CPU.
+    Sysfs,
+    /// Read the APERF and MPERF counters before and after each cell, giving
+    /// the exact mean. Requires the `msr` feature and root.
+    Msr,
+}
+
+/// Frequency scaling state of a CPU, read from sysfs where available.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct CpuState {
+    pub cpu: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub governor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_khz: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turbo: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct EnvironmentResults {
+    pub config: EnvironmentConfig,
+    // Microarchitecture used to select algorithm lists, if detected or given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub microarch: Option<String>,
+    // How the memory holding benchmark sets was backed, if placed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backing: Option<Backing>,
+    pub start: CpuState,
+    pub end: CpuState,
+    // Nanoseconds per spin loop, measured before the run and after each
+    // dataset, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spin_baseline: Vec<u64>,
+}
+
+/// Visitor receiving each algorithm's result, to compare the overhead of
+/// handling output. Set for a whole run with `--output`, or per experiment
+/// with its `output` key.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum OutputMode {
+    /// `UnsafeWriter`: a vector preallocated to the smallest input's length,
+    /// written without bounds checks.
+    #[default]
+    Unsafe,
+    /// `Counter`: only the result's size, as with `--count-only`.
+    Count,
+    /// `VecWriter`: a vector starting empty, grown as needed.
+    Vec,
+    /// `SliceWriter`: a preallocated, fixed-size buffer with bounds checks.
+    Slice,
+    /// `ReserveWriter`: `UnsafeWriter`'s stores into a vector starting empty,
+    /// reserving one vector of space before each store.
+    Reserve,
+    /// `StreamingWriter`: non-temporal stores of whole cache lines, which
+    /// leave the cache to subsequent queries. Unavailable for svs, as the
+    /// output is only readable once the intersection is done.
+    Streaming,
+    /// `UnsafeLookupWriter`: `UnsafeWriter` storing vectors through the
+    /// shuffle lookup tables, even where AVX-512 compress stores exist.
+    UnsafeLookup,
+    /// `UnsafeCompressWriter`: `UnsafeWriter` storing vectors with AVX-512
+    /// compress stores. Requires a build with `avx512f`.
+    UnsafeCompress,
+    /// `MmapWriter`: a memory-mapped file, grown as needed, whose flush is
+    /// timed separately. Unix only, and unavailable for svs.
+    Mmap,
+    /// `HintedWriter`: hinted with the smallest input's length, so the whole
+    /// result is preallocated as for `UnsafeWriter`. Compare with `reserve`
+    /// and `vec`, which are unhinted.
+    Hinted,
+}
+
+impl OutputMode {
+    /// Name in experiment files and on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputMode::Unsafe => "unsafe",
+            OutputMode::Count => "count",
+            OutputMode::Vec => "vec",
+            OutputMode::Slice => "slice",
+            OutputMode::Reserve => "reserve",
+            OutputMode::Streaming => "streaming",
+            OutputMode::UnsafeLookup => "unsafe_lookup",
+            OutputMode::UnsafeCompress => "unsafe_compress",
+            OutputMode::Mmap => "mmap",
+            OutputMode::Hinted => "hinted",
+        }
+    }
+
+    /// Whether the result can be read back while intersecting, as svs needs
+    /// for each step. An `MmapWriter` could be, but each step would need its
+    /// own file.
+    pub fn readable(self) -> bool {
+        !matches!(self, OutputMode::Count | OutputMode::Streaming | OutputMode::Mmap)
+    }
+
+    /// Whether this build has the instructions (or platform) the writer needs.
+    pub fn built(self) -> bool {
+        match self {
+            OutputMode::UnsafeCompress => cfg!(target_feature = "avx512f"),
+            OutputMode::Mmap => cfg!(unix),
+            _ => true,
+        }
+    }
+}
+
+/// What to do with algorithms requiring CPU features unavailable on the
+/// machine running the benchmark (or not enabled when it was compiled).
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnUnsupported {
+    /// Skip the algorithm, recording it in the results' `unsupported` table.
+    #[default]
+    Skip,
+    /// Stop before running any experiments.
+    Fail,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -25,6 +290,10 @@ pub struct ExperimentEntry {
     #[serde(flatten)]
     pub algorithms: Algorithms,
     pub relative_to: Option<String>,
+    // Visitor for this experiment's runs, overriding `--output`. Its results
+    // are kept apart in the dataset's `outputs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<OutputMode>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -32,11 +301,65 @@ pub struct ExperimentEntry {
 pub enum Algorithms {
     Algorithms(Vec<String>),
     AlgorithmSet(String),
+    // Expanded into the other forms when parsed, see `pipeline`.
+    Pipeline(Pipeline),
+}
+
+/// Every combination of a set representation, a kernel and an output, rather
+/// than a list of algorithm names.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    pub representations: Vec<Representation>,
+    pub algorithms: Vec<AlgorithmId>,
+    // Runs with each output as with the experiment's `output`. Empty runs with
+    // the experiment's own output, or `--output`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<OutputMode>,
+}
+
+/// How the sets are prepared before a kernel intersects them.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Representation {
+    /// Sorted arrays, as generated.
+    Sorted,
+    /// Base and state pairs, with BSR kernels.
+    Bsr,
+    /// BSR, expanding the result into a sorted array.
+    BsrSorted,
+    /// Sorted arrays split into partitions by their high bits.
+    Partition,
+    /// Chunks stored as arrays or bitmaps by density.
+    Hybrid,
+    /// Blocks compressed with BP128, decoded before intersecting.
+    Bp128,
+    /// Blocks compressed with StreamVByte, decoded before intersecting.
+    Svb,
+}
+
+impl Representation {
+    /// Name of the algorithm intersecting this representation with `kernel`.
+    pub fn algorithm(self, kernel: &str) -> AlgorithmId {
+        match self {
+            Representation::Sorted => kernel.to_string(),
+            Representation::Bsr => format!("{kernel}_bsr"),
+            Representation::BsrSorted => format!("{kernel}_bsr_sorted"),
+            Representation::Partition => format!("partition_{kernel}"),
+            Representation::Hybrid => format!("hybrid_{kernel}"),
+            Representation::Bp128 => format!("bp128_{kernel}"),
+            Representation::Svb => format!("svb_{kernel}"),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct DatasetInfo {
     pub name: String,
+    // Independently generated instances of each point, each of `gen_count`
+    // datafiles in `<point>/<instance>`. Without it, a point has a single
+    // instance directly in its directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_replications: Option<u32>,
     #[serde(flatten)]
     pub dataset_type: DatasetType,
 }
@@ -53,11 +376,22 @@ pub struct SyntheticDataset {
     pub vary: Parameter,
     pub to: u32,
     pub step: u32,
+    // Optional second parameter, swept jointly with `vary` to produce a grid
+    // of (x, y) cells.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vary_y: Option<Sweep>,
     pub gen_count: usize,
     #[serde(flatten)]
     pub intersection: IntersectionInfo,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Sweep {
+    pub vary: Parameter,
+    pub to: u32,
+    pub step: u32,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct IntersectionInfo {
     pub set_count: u32,
@@ -65,6 +399,24 @@ pub struct IntersectionInfo {
     pub selectivity: u32,
     pub max_len: u32,
     pub skewness_factor: u32,
+    // Generate intersections of exactly the target size, failing rather than
+    // raising selectivity if the density leaves too few distinct values.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub exact: bool,
+    // Derive the elements by hashing their indices under this seed instead of
+    // sampling them, so the same datafiles can be regenerated anywhere from
+    // the dataset alone. Intersections are exact as with `exact`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    // Elements added to the length of every set, e.g., to leave a remainder
+    // after the last full vector of SIMD kernels.
+    #[serde(default)]
+    pub len_offset: u32,
+    // Draw elements from `0..universe` rather than from a range sized by
+    // `density` to the largest set, so the value range stays fixed as lengths
+    // vary. `density` is then ignored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub universe: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
@@ -75,6 +427,7 @@ pub enum Parameter {
     Size,
     Skew,
     SetCount,
+    LenOffset,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -83,15 +436,84 @@ pub struct RealDataset {
     pub gen_count: usize,
     pub set_count_start: u32,
     pub set_count_end: u32,
+    #[serde(default)]
+    pub query: RealQuery,
+    // Fraction (out of PERCENT) of sets, by size, which `stratified` and
+    // `top_k` queries draw from. Defaults to 10%.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stratum: Option<u32>,
+    // Subsample the sets of each query to a sweep of cardinalities, which
+    // become the points of the dataset in place of the set count.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subsample: Option<Subsample>,
+}
+
+impl RealDataset {
+    /// Number of sets intersected at point `x`.
+    pub fn set_count_at(&self, x: u32) -> u32 {
+        match self.subsample {
+            Some(_) => self.set_count_start,
+            None => x,
+        }
+    }
+}
+
+/// Cardinalities the sets of a real dataset are subsampled to, from `from` to
+/// `to` by `step`. A query has `set_count_start` sets, which must equal
+/// `set_count_end`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Subsample {
+    pub from: u32,
+    pub to: u32,
+    pub step: u32,
+    // Seed of the queries and of the elements kept, so that every point
+    // subsamples the same queries and regenerating gives the same datafiles.
+    #[serde(default)]
+    pub seed: u64,
+}
+
+/// How the sets of each query are chosen from a real dataset.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RealQuery {
+    /// Sets chosen uniformly at random.
+    #[default]
+    Random,
+    /// One set from the smallest `stratum` of sets and the rest from the
+    /// largest, i.e., small x large queries.
+    Stratified,
+    /// Sets chosen at random from the largest `stratum` of sets.
+    TopK,
+    /// Adjacency sets of a random k-clique, treating set `i` of the source
+    /// as the neighbours of vertex `i`. Intersecting these gives the vertices
+    /// extending the clique, as in clique listing.
+    Clique,
+    /// Adjacency sets of vertices found by snowball sampling from a random
+    /// vertex: each vertex is a neighbour of one chosen before it.
+    Snowball,
 }
 
 pub type SetPair = (Vec<i32>, Vec<i32>);
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Results {
+    #[serde(default)]
+    pub version: u32,
     pub experiments: Vec<ExperimentEntry>,
     pub datasets: HashMap<DatasetId, DatasetResults>,
     pub algorithm_sets: HashMap<String, AlgorithmVec>,
+    // Algorithms skipped as unsupported, mapped to the missing CPU features.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub unsupported: HashMap<AlgorithmId, Vec<String>>,
+    #[serde(default)]
+    pub environment: EnvironmentResults,
+    // Output used by experiments not setting their own, absent in older
+    // results when only counting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<OutputMode>,
+    // Effective bandwidth of each run against peak, with `roofline`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roofline: Option<Roofline>,
 }
 
 pub type AlgorithmResults = HashMap<AlgorithmId, Vec<ResultRun>>;
@@ -99,13 +521,41 @@ pub type AlgorithmResults = HashMap<AlgorithmId, Vec<ResultRun>>;
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DatasetResults {
     pub info: DatasetInfo,
-    pub algos: AlgorithmResults
+    pub algos: AlgorithmResults,
+    // Runs of experiments setting `output`, by that output.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub outputs: HashMap<OutputMode, AlgorithmResults>,
+    // Hash table shape of each FESIA algorithm's sets per point, present
+    // with --fesia-stats.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub fesia_stats: HashMap<AlgorithmId, Vec<FesiaStatsRun>>,
+}
+
+// Over every set of a point's datafiles.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FesiaStatsRun {
+    pub x: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<u32>,
+    pub sets: usize,
+    pub elements: usize,
+    pub segments: usize,
+    pub empty_segments: usize,
+    pub min_segment_size: usize,
+    pub max_segment_size: usize,
+    pub avg_segment_size: f64,
+    pub bitmap_density: f64,
+    // Number of segments of each size, from 0 elements.
+    pub histogram: Vec<usize>,
 }
 
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ResultRun {
     pub x: u32,
+    // Only present for datasets with `vary_y`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<u32>,
     // Nanoseconds
     pub times: Vec<u64>,
     pub l1d: CacheRun,
@@ -118,6 +568,103 @@ pub struct ResultRun {
     pub instructions: Option<Vec<u64>>,
     pub cpu_cycles: Option<Vec<u64>>,
     pub cpu_cycles_ref: Option<Vec<u64>>,
+    // Distribution of per-iteration times over all datafiles, only present
+    // when run with --latency.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<Latency>,
+    // Result cardinality and output buffer capacity per datafile, present
+    // when the algorithm writes to an output buffer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_len: Option<Vec<u64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_capacity: Option<Vec<u64>>,
+    // Nanoseconds to flush the output to its file per datafile, present with
+    // `--output mmap`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flush_times: Option<Vec<u64>>,
+    // Passes over the datafiles (each appending to the columns above) and the
+    // relative standard error of the mean pass time (out of PERCENT), present
+    // with `max_replications`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replications: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rse: Option<u32>,
+    // Instance of each sample of the columns above and the relative standard
+    // error of the mean time across instances (out of PERCENT), present for
+    // datasets with `data_replications`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instances: Option<Vec<u32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_rse: Option<u32>,
+    // Threads running queries at once and queries per second of each pass,
+    // present when run with --query-threads. `times` then holds the mean time
+    // per query of each pass rather than a time per datafile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_threads: Option<u32>,
+    // Queries per batch probe of one shared large set, present when run with
+    // --query-batch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_batch: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throughput: Option<Vec<u64>>,
+    // Bytes of the input sets of each datafile, not present with
+    // --query-threads.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_bytes: Option<Vec<u64>>,
+    // Cardinalities of each datafile's query: its smallest set |A|, its
+    // largest set |B| and the intersection of all its sets, present for real
+    // datasets, whose queries vary widely in size (see `process::stratify`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub len_a: Option<Vec<u64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub len_b: Option<Vec<u64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intersection_len: Option<Vec<u64>>,
+    // Work counters per datafile: element comparisons, vector loads of input
+    // elements and vectors of results visited, present when built with the
+    // `instrument` feature. Not present with --query-threads. Comparisons are
+    // only present for algorithms which count all of theirs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comparisons: Option<Vec<u64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vector_loads: Option<Vec<u64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emitted_vectors: Option<Vec<u64>>,
+    // Gallops of scalar galloping per datafile and their summed overshoot,
+    // so the mean overshoot is their ratio, present with `instrument` for
+    // algorithms that gallop.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gallops: Option<Vec<u64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gallop_overshoot: Option<Vec<u64>>,
+    // Growth factor chosen by `galloping_auto` for the dataset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gallop_factor: Option<usize>,
+    // Hash of the intersection per datafile (see `verify::hash_set`), present
+    // when run with --hash-outputs and the algorithm materialises its result.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_hashes: Option<Vec<u64>>,
+    // Mean frequency of the measurement CPU in kHz over the cell, present
+    // with `frequency_monitor`, and how far it dropped below the highest mean
+    // of any earlier cell (out of PERCENT), present if by more than
+    // `max_frequency_drop`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_khz: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_drop: Option<u32>,
+}
+
+// Nanoseconds
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Latency {
+    pub p50: u64,
+    pub p95: u64,
+    pub p99: u64,
+    pub max: u64,
+    // (lowest value in bucket, count) for each non-empty bucket.
+    pub histogram: Vec<(u64, u64)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub samples: Vec<u64>,
 }
 
 // Store columnar in JSON
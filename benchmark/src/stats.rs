@@ -0,0 +1,253 @@
+//! Summary statistics of the sets of a dataset, to check that a generator
+//! produces the intended properties before benchmarking it. Statistics are
+//! gathered over groups of sets, where a group is one datafile of a point (or
+//! all sets of a real source):
+//! - cardinality of each set,
+//! - density of each set, i.e., its length over the span of its values,
+//! - length of each run of consecutive integers within a set,
+//! - pairwise selectivity of sets in the same group, |A ∩ B| / min(|A|, |B|),
+//! - selectivity of each whole group, i.e., of its k-set intersection.
+
+use std::collections::BTreeMap;
+
+use rand::Rng;
+use serde::Serialize;
+use setops::{intersect, simple, visitor::Counter};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DatasetStats {
+    pub dataset: String,
+    pub points: Vec<PointStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PointStats {
+    /// Absent for the sets of a real source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<u32>,
+    #[serde(flatten)]
+    pub stats: SetsStats,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SetsStats {
+    pub groups: usize,
+    pub sets: usize,
+    pub cardinality: Option<Distribution>,
+    pub density: Option<Distribution>,
+    pub run_length: Option<Distribution>,
+    /// Number of runs of each length, by power of two: a run of length n is
+    /// counted in bucket floor(log2(n)).
+    pub run_length_histogram: Vec<u64>,
+    /// Number of pairs sampled for `pairwise_selectivity`.
+    pub pairs: usize,
+    pub pairwise_selectivity: Option<Distribution>,
+    pub group_selectivity: Option<Distribution>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Distribution {
+    pub count: u64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+}
+
+impl Distribution {
+    pub fn of(mut values: Vec<f64>) -> Option<Self> {
+        values.sort_by(f64::total_cmp);
+        let counts = values.into_iter().map(|v| (v, 1));
+        Self::of_sorted_counts(counts)
+    }
+
+    /// Distribution of values given the number of occurrences of each.
+    pub fn of_counts(counts: &BTreeMap<u64, u64>) -> Option<Self> {
+        Self::of_sorted_counts(counts.iter().map(|(&v, &c)| (v as f64, c)))
+    }
+
+    fn of_sorted_counts(counts: impl Iterator<Item = (f64, u64)> + Clone) -> Option<Self> {
+        let (count, sum) = counts.clone()
+            .fold((0, 0.0), |(count, sum), (v, c)| (count + c, sum + v * c as f64));
+        if count == 0 {
+            return None;
+        }
+
+        // Nearest-rank percentile.
+        let percentile = |p: u64| {
+            let rank = (p * count).div_ceil(100).max(1);
+            let mut seen = 0;
+            counts.clone()
+                .find(|&(_, c)| { seen += c; seen >= rank })
+                .map(|(v, _)| v)
+                .unwrap()
+        };
+
+        Some(Self {
+            count,
+            min: percentile(0),
+            max: percentile(100),
+            mean: sum / count as f64,
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+        })
+    }
+}
+
+/// Computes statistics of `groups` of sorted sets. If the groups have more
+/// than `max_pairs` pairs of sets in total, pairwise selectivity is estimated
+/// from `max_pairs` pairs sampled uniformly at random.
+pub fn sets_stats<S: AsRef<[i32]>>(
+    groups: &[Vec<S>],
+    max_pairs: usize,
+    rng: &mut impl Rng) -> SetsStats
+{
+    let sets = || groups.iter().flatten().map(AsRef::as_ref);
+
+    let mut cardinality = BTreeMap::new();
+    let mut run_length = BTreeMap::new();
+    let mut density = Vec::new();
+
+    for set in sets() {
+        *cardinality.entry(set.len() as u64).or_insert(0) += 1;
+
+        if let (Some(first), Some(last)) = (set.first(), set.last()) {
+            let span = *last as i64 - *first as i64 + 1;
+            density.push(set.len() as f64 / span as f64);
+        }
+        for run in runs(set) {
+            *run_length.entry(run as u64).or_insert(0) += 1;
+        }
+    }
+
+    let mut run_length_histogram = Vec::new();
+    for (&length, &count) in &run_length {
+        let bucket = length.ilog2() as usize;
+        if run_length_histogram.len() <= bucket {
+            run_length_histogram.resize(bucket + 1, 0);
+        }
+        run_length_histogram[bucket] += count;
+    }
+
+    let total_pairs: usize = groups.iter().map(|g| pair_count(g.len())).sum();
+    let pairs: Vec<(&[i32], &[i32])> = if total_pairs <= max_pairs {
+        groups.iter()
+            .flat_map(|g| g.iter().enumerate()
+                .flat_map(move |(i, a)| g[i+1..].iter().map(move |b| (a.as_ref(), b.as_ref()))))
+            .collect()
+    }
+    else {
+        let pairwise: Vec<&Vec<S>> = groups.iter().filter(|g| g.len() >= 2).collect();
+        (0..max_pairs)
+            .map(|_| {
+                let group = pairwise[rng.gen_range(0..pairwise.len())];
+                let i = rng.gen_range(0..group.len());
+                let j = (i + rng.gen_range(1..group.len())) % group.len();
+                (group[i].as_ref(), group[j].as_ref())
+            })
+            .collect()
+    };
+
+    let pairwise_selectivity = pairs.iter()
+        .map(|&(a, b)| selectivity(simple::count(a, b), &[a, b]))
+        .collect();
+
+    let group_selectivity = groups.iter()
+        .filter(|g| g.len() >= 2)
+        .map(|g| {
+            let mut counter = Counter::new();
+            intersect::svs(g, &mut counter);
+            selectivity(counter.count(), g)
+        })
+        .collect();
+
+    SetsStats {
+        groups: groups.len(),
+        sets: sets().count(),
+        cardinality: Distribution::of_counts(&cardinality),
+        density: Distribution::of(density),
+        run_length: Distribution::of_counts(&run_length),
+        run_length_histogram,
+        pairs: pairs.len(),
+        pairwise_selectivity: Distribution::of(pairwise_selectivity),
+        group_selectivity: Distribution::of(group_selectivity),
+    }
+}
+
+// Lengths of the maximal runs of consecutive integers in a sorted set.
+fn runs(set: &[i32]) -> impl Iterator<Item = usize> + '_ {
+    set.chunk_by(|a, b| *a as i64 + 1 == *b as i64).map(<[i32]>::len)
+}
+
+fn pair_count(sets: usize) -> usize {
+    sets * sets.saturating_sub(1) / 2
+}
+
+// Result length over the length of the smallest set, or 0 if it is empty.
+fn selectivity<S: AsRef<[i32]>>(result_len: usize, sets: &[S]) -> f64 {
+    match sets.iter().map(|s| s.as_ref().len()).min() {
+        Some(smallest) if smallest > 0 => result_len as f64 / smallest as f64,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn test_distribution() {
+        let d = Distribution::of((1..=100).rev().map(|v| v as f64).collect()).unwrap();
+        assert_eq!((d.count, d.min, d.max, d.mean), (100, 1.0, 100.0, 50.5));
+        assert_eq!((d.p50, d.p90, d.p99), (50.0, 90.0, 99.0));
+
+        let counts = BTreeMap::from([(1, 3), (4, 1)]);
+        let d = Distribution::of_counts(&counts).unwrap();
+        assert_eq!((d.count, d.mean, d.p50, d.max), (4, 1.75, 1.0, 4.0));
+
+        assert_eq!(Distribution::of(vec![]), None);
+    }
+
+    #[test]
+    fn test_sets_stats() {
+        let groups = vec![
+            vec![vec![0, 1, 2, 3], vec![2, 3, 4, 5, 6, 7, 8, 9]],
+            vec![vec![0, 10], vec![0, 5, 10], vec![10, 11]],
+        ];
+        let stats = sets_stats(&groups, usize::MAX, &mut StdRng::seed_from_u64(1));
+
+        assert_eq!((stats.groups, stats.sets, stats.pairs), (2, 5, 4));
+        assert_eq!(stats.cardinality.unwrap().max, 8.0);
+
+        let density = stats.density.unwrap();
+        assert_eq!((density.min, density.max), (2.0 / 11.0, 1.0));
+
+        // Runs: 4, 8 | 1, 1 | 1, 1, 1 | 2
+        assert_eq!(stats.run_length.unwrap().count, 8);
+        assert_eq!(stats.run_length_histogram, [5, 1, 1, 1]);
+
+        // Pairs: 2/4 | 2/2, 1/2, 1/2
+        let pairwise = stats.pairwise_selectivity.unwrap();
+        assert_eq!((pairwise.min, pairwise.max, pairwise.mean), (0.5, 1.0, 0.625));
+
+        // Groups: 2/4 | 1/2
+        let group = stats.group_selectivity.unwrap();
+        assert_eq!((group.count, group.mean), (2, 0.5));
+    }
+
+    #[test]
+    fn test_sampled_pairs() {
+        let groups = vec![(0..10).map(|i| vec![i, 100]).collect::<Vec<_>>()];
+        let stats = sets_stats(&groups, 20, &mut StdRng::seed_from_u64(1));
+
+        assert_eq!(stats.pairs, 20);
+        let pairwise = stats.pairwise_selectivity.unwrap();
+        assert_eq!((pairwise.min, pairwise.max), (0.5, 0.5));
+    }
+}
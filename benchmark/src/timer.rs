@@ -1,55 +1,268 @@
 pub mod harness;
+pub mod latency;
+pub mod concurrent;
 pub mod perf;
 
-use std::{simd::{*, cmp::*}, ops::BitAnd};
+use std::{cell::RefCell, sync::Arc};
 
 use setops::{
     intersect::{
-        self, Intersect2, Intersect2C, IntersectK,
-        fesia::{IntegerHash, FesiaTwoSetMethod, SimdType, HashScale, FesiaKSetMethod}
+        self, Intersect2, Intersect2C, IntersectK, SvsBuffers,
+        TwoSetAlgorithm, KSetAlgorithm, BatchProbe,
+        algorithm::{
+            Sorted2, SortedK, DecodeIntersect, Hybrid, UnionIntersect, FesiaAlgorithm,
+            FesiaHashAlgorithm,
+        },
+        probe_cache::CachedGalloping,
+        skip::SkipGalloping,
+        prefetch::{MergePrefetch, GallopingPrefetch},
+        GallopingFactor,
+        fesia::{
+            HashScale, HashType, IntegerHash, SetWithHashScale, FesiaIntersect, FesiaStats,
+            SegmentIntersect,
+        },
     },
     visitor::{
-        UnsafeWriter, Visitor, Counter,
-        SimdVisitor4, SimdVisitor8, SimdVisitor16
+        UnsafeWriter, UnsafeBsrWriter, Visitor, Counter, BsrExpandAdapter, VecWriter, ReserveWriter,
+        HintedWriter, StreamingWriter, UnsafeLookupWriter,
+        SimdVisitor4, SimdVisitor8, SimdVisitor16,
+        SimdBsrVisitor4, SimdBsrVisitor8, SimdBsrVisitor16,
     },
+    encoding::{Bp128Set, SvbSet},
+    hybrid, bloom,
 };
-use crate::{datafile::DatafileSet, timer::harness::time_fesia_kset};
-use harness::{Harness, HarnessVisitor, RunResult, UnsafeIntersectBsr};
+#[cfg(target_feature = "avx512f")]
+use setops::visitor::UnsafeCompressWriter;
+#[cfg(unix)]
+use setops::visitor::MmapWriter;
+use crate::{datafile::DatafileSet, schema::{OutputMode, PERCENT}};
+use harness::{Harness, HarnessVisitor, RunResult, IntersectBsr, EytzingerProbe, SliceOutput};
+use concurrent::{ConcurrentConfig, Throughput};
+#[cfg(feature = "instrument")]
+use setops::instrument::InstrumentVisitor;
+
+// Visitor the kernels of `Timer::new` write to, wrapped to count the vectors
+// they emit when built with `instrument`.
+#[cfg(feature = "instrument")]
+type Output<V> = InstrumentVisitor<V>;
+#[cfg(not(feature = "instrument"))]
+type Output<V> = V;
 
 type TwosetTimer = Box<dyn Fn(&mut Harness, &[i32], &[i32]) -> RunResult>;
 type KsetTimer = Box<dyn Fn(&mut Harness, &[DatafileSet]) -> RunResult>;
+type ConcurrentTimer = Box<dyn Fn(&[Vec<DatafileSet>], &ConcurrentConfig) -> Result<Throughput, String>>;
+type BatchTimer = Box<dyn Fn(&[Vec<DatafileSet>], &ConcurrentConfig, usize) -> Result<Throughput, String>>;
+type SetCheck = Box<dyn Fn(&[DatafileSet]) -> Result<(), String>>;
+type SetStats = Box<dyn Fn(&[DatafileSet]) -> FesiaStats>;
 
+#[derive(Default)]
 pub struct Timer {
     twoset: Option<TwosetTimer>,
     kset: Option<KsetTimer>,
+    // Only algorithms implementing `TwoSetAlgorithm` or `KSetAlgorithm` can
+    // share prepared sets between query threads.
+    concurrent_twoset: Option<ConcurrentTimer>,
+    concurrent_kset: Option<ConcurrentTimer>,
+    // Algorithms whose prepared sets implement `BatchProbe` can also be run
+    // with batches of queries.
+    concurrent_batch: Option<BatchTimer>,
+    // Rejects the sets of a datafile before they are timed.
+    check: Option<SetCheck>,
+    // Hash table shape of the sets the algorithm builds, for FESIA.
+    fesia_stats: Option<SetStats>,
+    // Whether every comparison of the algorithm is counted with `instrument`
+    // (see `TwoSetAlgo::counts_comparisons`).
+    counts_comparisons: bool,
 }
 
 impl Timer {
-    pub fn new(name: &str, count_only: bool) -> Option<Self>
+    fn of_twoset<A, V>(algorithm: A) -> Self
+    where
+        A: TwoSetAlgorithm<i32, V> + Send + Sync + 'static,
+        for<'s> A::Prepared<'s>: Send + Sync,
+        V: HarnessVisitor + 'static
     {
-        if count_only {
-            Self::make::<Counter>(name, count_only)
+        let algorithm = Arc::new(algorithm);
+        let shared = Arc::clone(&algorithm);
+        Timer {
+            twoset: Some(Box::new(move |warmup, a, b|
+                Ok(harness::time_twoset(warmup, a, b, &*algorithm)))),
+            concurrent_twoset: Some(Box::new(
+                move |queries, config| concurrent::time_concurrent_twoset(&*shared, queries, config))),
+            ..Default::default()
         }
-        else {
-            Self::make::<UnsafeWriter<i32>>(name, count_only)
+    }
+
+    /// Like `of_twoset`, sharing prepared sets with other algorithms of
+    /// `family`, which must prepare sets identically.
+    fn of_twoset_in<A, V>(algorithm: A, family: Option<String>) -> Self
+    where
+        A: TwoSetAlgorithm<i32, V> + Send + Sync + 'static,
+        for<'s> A::Prepared<'s>: Send + Sync + 'static,
+        V: HarnessVisitor + 'static
+    {
+        let algorithm = Arc::new(algorithm);
+        let shared = Arc::clone(&algorithm);
+        Timer {
+            twoset: Some(Box::new(move |warmup, a, b|
+                Ok(harness::time_twoset_in(warmup, a, b, &*algorithm, family.as_deref())))),
+            concurrent_twoset: Some(Box::new(
+                move |queries, config| concurrent::time_concurrent_twoset(&*shared, queries, config))),
+            ..Default::default()
+        }
+    }
+
+    fn of_kset<A, V>(algorithm: A) -> Self
+    where
+        A: KSetAlgorithm<i32, V> + Send + Sync + 'static,
+        for<'s> A::Prepared<'s>: Send + Sync,
+        V: HarnessVisitor + 'static
+    {
+        let algorithm = Arc::new(algorithm);
+        let shared = Arc::clone(&algorithm);
+        Timer {
+            kset: Some(Box::new(move |warmup, sets|
+                harness::time_kset(warmup, sets, &*algorithm))),
+            concurrent_kset: Some(Box::new(
+                move |queries, config| concurrent::time_concurrent_kset(&*shared, queries, config))),
+            ..Default::default()
+        }
+    }
+
+    /// Adds the k-set `algorithm`, sharing prepared sets as `of_twoset_in`.
+    fn with_kset_in<A, V>(self, algorithm: A, family: Option<String>) -> Self
+    where
+        A: KSetAlgorithm<i32, V> + Send + Sync + 'static,
+        for<'s> A::Prepared<'s>: Send + Sync + 'static,
+        V: HarnessVisitor + 'static
+    {
+        let algorithm = Arc::new(algorithm);
+        let shared = Arc::clone(&algorithm);
+        Timer {
+            kset: Some(Box::new(move |warmup, sets|
+                harness::time_kset_in(warmup, sets, &*algorithm, family.as_deref()))),
+            concurrent_kset: Some(Box::new(
+                move |queries, config| concurrent::time_concurrent_kset(&*shared, queries, config))),
+            ..self
+        }
+    }
+
+    /// Adds batch probes of the sets `algorithm` prepares.
+    fn with_batch<A, V>(self, algorithm: A) -> Self
+    where
+        A: TwoSetAlgorithm<i32, V> + Send + Sync + 'static,
+        for<'s> A::Prepared<'s>: BatchProbe<i32> + Send + Sync,
+        V: Visitor<i32> + HarnessVisitor + 'static
+    {
+        Timer {
+            concurrent_batch: Some(Box::new(move |queries, config, batch|
+                concurrent::time_concurrent_batch(&algorithm, queries, config, batch))),
+            ..self
+        }
+    }
+
+    fn with_counted_comparisons(self, counts_comparisons: bool) -> Self {
+        Timer { counts_comparisons, ..self }
+    }
+
+    fn with_check(self, check: impl Fn(&[DatafileSet]) -> Result<(), String> + 'static) -> Self {
+        Timer { check: Some(Box::new(check)), ..self }
+    }
+
+    fn with_fesia_stats<F>(self, hash_scale: HashScale) -> Self
+    where
+        F: SetWithHashScale + FesiaIntersect + 'static
+    {
+        let stats = move |sets: &[DatafileSet]| {
+            let mut stats = FesiaStats::default();
+            for set in sets {
+                stats.merge(&F::from_sorted(set, hash_scale).stats());
+            }
+            stats
+        };
+        Timer { fesia_stats: Some(Box::new(stats)), ..self }
+    }
+
+    /// `prefetch_distance` is used by the `_prefetch` kernels. Returns `None`
+    /// for unknown algorithms, and for outputs this build lacks.
+    pub fn new(name: &str, output: OutputMode, prefetch_distance: usize) -> Option<Self> {
+        let count_only = output == OutputMode::Count;
+        match output {
+            OutputMode::Unsafe =>
+                Self::make::<Output<UnsafeWriter<i32>>>(name, count_only, prefetch_distance),
+            OutputMode::Count =>
+                Self::make::<Output<Counter>>(name, count_only, prefetch_distance),
+            OutputMode::Vec =>
+                Self::make::<Output<VecWriter<i32>>>(name, count_only, prefetch_distance),
+            OutputMode::Slice =>
+                Self::make::<Output<SliceOutput>>(name, count_only, prefetch_distance),
+            OutputMode::Reserve =>
+                Self::make::<Output<ReserveWriter<i32>>>(name, count_only, prefetch_distance),
+            OutputMode::Hinted =>
+                Self::make::<Output<HintedWriter<i32>>>(name, count_only, prefetch_distance),
+            OutputMode::Streaming =>
+                Self::make::<Output<StreamingWriter>>(name, count_only, prefetch_distance),
+            OutputMode::UnsafeLookup =>
+                Self::make::<Output<UnsafeLookupWriter<i32>>>(name, count_only, prefetch_distance),
+            #[cfg(target_feature = "avx512f")]
+            OutputMode::UnsafeCompress =>
+                Self::make::<Output<UnsafeCompressWriter<i32>>>(name, count_only, prefetch_distance),
+            #[cfg(not(target_feature = "avx512f"))]
+            OutputMode::UnsafeCompress => None,
+            #[cfg(unix)]
+            OutputMode::Mmap =>
+                Self::make::<Output<MmapWriter>>(name, count_only, prefetch_distance),
+            #[cfg(not(unix))]
+            OutputMode::Mmap => None,
         }
     }
 
-    fn make<V>(name: &str, count_only: bool) -> Option<Self>
+    fn make<V>(name: &str, count_only: bool, prefetch_distance: usize) -> Option<Self>
     where
         V: Visitor<i32> + HarnessVisitor + TwosetTimingSpec<V>,
         V: SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + 'static
     {
         try_parse_twoset::<V>(name)
             .or_else(|| try_parse_twoset_c(name))
+            .or_else(|| try_parse_compressed::<V>(name))
+            .or_else(|| try_parse_partition::<V>(name))
+            .or_else(|| try_parse_union::<V>(name))
+            .or_else(|| try_parse_hybrid::<V>(name))
+            .or_else(|| try_parse_range::<V>(name))
+            .or_else(|| try_parse_galloping_cached::<V>(name))
+            .or_else(|| try_parse_galloping_skip::<V>(name))
+            .or_else(|| try_parse_prefetch::<V>(name, prefetch_distance))
+            .or_else(|| try_parse_galloping_factor::<V>(name))
+            .or_else(|| try_parse_eytzinger::<V>(name))
+            .or_else(|| try_parse_bloom::<V>(name))
             .or_else(|| try_parse_bsr(name))
             .or_else(|| try_parse_kset::<V>(name))
             .or_else(|| try_parse_roaring(name, count_only))
+            .or_else(|| try_parse_bitmap(name, count_only))
             .or_else(|| try_parse_fesia_hash::<V>(name))
             .or_else(|| try_parse_fesia::<V>(name))
     }
 
+    /// Whether the comparison counts of the work counters are complete for
+    /// this algorithm. Other algorithms do not record comparisons.
+    pub fn counts_comparisons(&self) -> bool {
+        self.counts_comparisons
+    }
+
+    /// Hash table shape of the sets of a datafile as this FESIA algorithm
+    /// builds them. `None` for other algorithms, and for sets it rejects.
+    pub fn fesia_stats(&self, sets: &[DatafileSet]) -> Option<FesiaStats> {
+        let stats = self.fesia_stats.as_ref()?;
+        if let Some(check) = &self.check {
+            check(sets).ok()?;
+        }
+        Some(stats(sets))
+    }
+
     pub fn run(&self, harness: &mut Harness, sets: &[DatafileSet]) -> RunResult {
+        if let Some(check) = &self.check {
+            check(sets)?;
+        }
         if sets.len() == 2 {
             if let Some(twoset) = &self.twoset {
                 twoset(harness, &sets[0], &sets[1])
@@ -70,6 +283,35 @@ impl Timer {
             }
         }
     }
+
+    /// Runs `queries`, each the sets of one datafile, from several threads
+    /// sharing prepared sets. Uses the 2-set algorithm if every query has 2
+    /// sets, as `run` does.
+    pub fn run_concurrent(&self, queries: &[Vec<DatafileSet>], config: &ConcurrentConfig)
+        -> Result<Throughput, String>
+    {
+        if let Some(check) = &self.check {
+            queries.iter().try_for_each(|sets| check(sets))?;
+        }
+        let twoset = queries.iter().all(|sets| sets.len() == 2);
+        let timer = match (&self.concurrent_twoset, &self.concurrent_kset) {
+            (Some(concurrent_twoset), _) if twoset => concurrent_twoset,
+            (_, Some(concurrent_kset)) => concurrent_kset,
+            _ => return Err(format!(
+                "concurrent {}intersection not supported", if twoset { "" } else { "k-set " })),
+        };
+        timer(queries, config)
+    }
+
+    /// Runs `queries` from several threads in batches of `batch`, probing
+    /// one shared large set (see `concurrent::time_concurrent_batch`).
+    pub fn run_batch(&self, queries: &[Vec<DatafileSet>], config: &ConcurrentConfig, batch: usize)
+        -> Result<Throughput, String>
+    {
+        let timer = self.concurrent_batch.as_ref()
+            .ok_or_else(|| "batch probes not supported".to_string())?;
+        timer(queries, config, batch)
+    }
 }
 
 fn try_parse_twoset<V>(name: &str) -> Option<Timer> 
@@ -77,95 +319,225 @@ where
     V: Visitor<i32> + HarnessVisitor + TwosetTimingSpec<V>,
     V: SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + 'static
 {
-    let maybe_intersect: Option<Intersect2<[i32], V>> = match name {
-        "naive_merge"      => Some(intersect::naive_merge),
-        "branchless_merge" => Some(intersect::branchless_merge),
-        "bmiss_scalar_3x"  => Some(intersect::bmiss_scalar_3x),
-        "bmiss_scalar_4x"  => Some(intersect::bmiss_scalar_4x),
-        "galloping"        => Some(intersect::galloping),
-        "binary_search"    => Some(intersect::binary_search_intersect),
-        "baezayates"       => Some(intersect::baezayates),
-        // SSE
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "shuffling_sse"    => Some(intersect::shuffling_sse),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "broadcast_sse"    => Some(intersect::broadcast_sse),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "bmiss"        => Some(intersect::bmiss),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "bmiss_sttni"  => Some(intersect::bmiss_sttni),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "qfilter"          => Some(intersect::qfilter),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "qfilter_v1"          => Some(intersect::qfilter_v1),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "lbk_v1x4_sse"    => Some(intersect::lbk_v1x4_sse),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "lbk_v1x8_sse"    => Some(intersect::lbk_v1x8_sse),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "lbk_v3_sse"    => Some(intersect::lbk_v3_sse),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "galloping_sse"    => Some(intersect::galloping_sse),
-        // AVX2
-        #[cfg(all(feature = "simd", target_feature = "avx2"))]
-        "shuffling_avx2"   => Some(intersect::shuffling_avx2),
-        #[cfg(all(feature = "simd", target_feature = "avx2"))]
-        "broadcast_avx2"   => Some(intersect::broadcast_avx2),
-        #[cfg(all(feature = "simd", target_feature = "avx2"))]
-        "lbk_v1x8_avx2"   => Some(intersect::lbk_v1x8_avx2),
-        #[cfg(all(feature = "simd", target_feature = "avx2"))]
-        "lbk_v1x16_avx2"   => Some(intersect::lbk_v1x16_avx2),
-        #[cfg(all(feature = "simd", target_feature = "avx2"))]
-        "lbk_v3_avx2"   => Some(intersect::lbk_v3_avx2),
-        #[cfg(all(feature = "simd", target_feature = "avx2"))]
-        "galloping_avx2"   => Some(intersect::galloping_avx2),
-        // AVX-512
-        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-        "shuffling_avx512"       => Some(intersect::shuffling_avx512),
-        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-        "broadcast_avx512"       => Some(intersect::broadcast_avx512),
-        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-        "vp2intersect_emulation" => Some(intersect::vp2intersect_emulation),
-        #[cfg(all(feature = "simd", target_feature = "avx512cd"))]
-        "conflict_intersect"     => Some(intersect::conflict_intersect),
-        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-        "lbk_v1x16_avx512"       => Some(intersect::lbk_v1x16_avx512),
-        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-        "lbk_v1x32_avx512"       => Some(intersect::lbk_v1x32_avx512),
-        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-        "lbk_v3_avx512"       => Some(intersect::lbk_v3_avx512),
-        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-        "galloping_avx512"       => Some(intersect::galloping_avx512),
-        // Branch
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "shuffling_sse_branch"    => Some(intersect::shuffling_sse_branch),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "broadcast_sse_branch"    => Some(intersect::broadcast_sse_branch),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "bmiss_branch"        => Some(intersect::bmiss_branch),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "bmiss_sttni_branch"  => Some(intersect::bmiss_sttni_branch),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "qfilter_branch"          => Some(intersect::qfilter_branch),
-        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "qfilter_v1_branch"       => Some(intersect::qfilter_v1_branch),
-        // AVX2
-        #[cfg(all(feature = "simd", target_feature = "avx2"))]
-        "shuffling_avx2_branch"   => Some(intersect::shuffling_avx2_branch),
+    let algo = intersect::dynamic::TwoSetAlgo::from_name(name)?;
+    Some(V::twoset_timer(algo.function()).with_counted_comparisons(algo.counts_comparisons()))
+}
+
+fn parse_twoset<V>(name: &str) -> Option<Intersect2<[i32], V>>
+where
+    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16
+{
+    intersect::dynamic::lookup(name)
+}
+
+// Intersection of compressed sets. "<encoding>_<algorithm>" decodes blocks of
+// both sets and intersects them with the named 2-set algorithm, e.g.,
+// "bp128_shuffling_sse". "svb_probe" only decodes blocks of the larger set
+// which may contain elements of the smaller set.
+fn try_parse_compressed<V>(name: &str) -> Option<Timer>
+where
+    V: Visitor<i32> + HarnessVisitor,
+    V: SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + 'static
+{
+    if name == "svb_probe" {
+        return Some(Timer {
+            twoset: Some(Box::new(
+                |warmup, a, b| Ok(harness::time_svb_probe::<V>(warmup, a, b)))),
+            ..Default::default()
+        });
+    }
+
+    let (encoding, kernel) = name.split_once('_')?;
+    let intersect = parse_twoset::<V>(kernel)?;
+
+    match encoding {
+        "bp128" => Some(Timer::of_twoset_in(
+            DecodeIntersect::<Bp128Set, V>::new(intersect), Some(encoding.to_string()))),
+        "svb"   => Some(Timer::of_twoset_in(
+            DecodeIntersect::<SvbSet, V>::new(intersect), Some(encoding.to_string()))),
+        _ => None,
+    }
+}
+
+// partition_<kernel>
+fn try_parse_partition<V>(name: &str) -> Option<Timer>
+where
+    V: Visitor<i32> + HarnessVisitor,
+    V: SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + 'static
+{
+    let kernel = name.strip_prefix("partition_")?;
+    let intersect = parse_twoset::<V>(kernel)?;
+
+    Some(Timer {
+        twoset: Some(Box::new(
+            move |warmup, a, b| Ok(harness::time_partition(warmup, a, b, intersect)))),
+        ..Default::default()
+    })
+}
+
+// union_<kernel>: the first set against the union of the rest.
+fn try_parse_union<V>(name: &str) -> Option<Timer>
+where
+    V: Visitor<i32> + HarnessVisitor,
+    V: SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + 'static
+{
+    let kernel = name.strip_prefix("union_")?;
+    let intersect = parse_twoset::<V>(kernel)?;
+
+    Some(Timer::of_kset(UnionIntersect { intersect }))
+}
+
+// hybrid[<bitmap threshold>]_<kernel>
+fn try_parse_hybrid<V>(name: &str) -> Option<Timer>
+where
+    V: Visitor<i32> + HarnessVisitor,
+    V: SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + 'static
+{
+    let (prefix, kernel) = name.split_once('_')?;
+    let bitmap_threshold = parse_hybrid_threshold(prefix)?;
+    let intersect = parse_twoset::<V>(kernel)?;
+
+    Some(Timer::of_twoset_in(
+        Hybrid { bitmap_threshold, intersect }, Some(format!("hybrid{bitmap_threshold}"))))
+}
+
+/// Parses `hybrid` or `hybrid<threshold>`, returning the bitmap threshold.
+pub fn parse_hybrid_threshold(prefix: &str) -> Option<usize> {
+    let threshold = prefix.strip_prefix("hybrid")?;
+    if threshold.is_empty() {
+        Some(hybrid::DEFAULT_BITMAP_THRESHOLD)
+    }
+    else {
+        threshold.parse().ok()
+    }
+}
+
+// range[<window>]_<kernel>
+fn try_parse_range<V>(name: &str) -> Option<Timer>
+where
+    V: Visitor<i32> + HarnessVisitor,
+    V: SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + 'static
+{
+    let (prefix, kernel) = name.split_once('_')?;
+    let window = parse_range_window(prefix)?;
+    let intersect = parse_twoset::<V>(kernel)?;
+
+    Some(Timer {
+        twoset: Some(Box::new(
+            move |warmup, a, b| Ok(harness::time_range(warmup, a, b, window, intersect)))),
+        ..Default::default()
+    })
+}
+
+/// Parses `range` or `range<window>`, returning the fraction (out of PERCENT)
+/// of the sets' values to intersect. Without a window the whole span is used,
+/// so only the cost of narrowing is added to the kernel.
+pub fn parse_range_window(prefix: &str) -> Option<u32> {
+    let window = prefix.strip_prefix("range")?;
+    if window.is_empty() {
+        Some(PERCENT)
+    }
+    else {
+        window.parse().ok().filter(|&w| w <= PERCENT)
+    }
+}
+
+// The rank index of each set is built when preparing it, so in the
+// concurrent mode every query probes the shared index of the larger set.
+fn try_parse_galloping_cached<V>(name: &str) -> Option<Timer>
+where
+    V: Visitor<i32> + HarnessVisitor + 'static
+{
+    (name == "galloping_cached").then(|| Timer::of_twoset::<_, V>(CachedGalloping::default())
+        .with_batch::<_, V>(CachedGalloping::default()))
+}
+
+// Skip pointers are likewise built when preparing each set.
+fn try_parse_galloping_skip<V>(name: &str) -> Option<Timer>
+where
+    V: Visitor<i32> + HarnessVisitor + 'static
+{
+    (name == "galloping_skip").then(|| Timer::of_twoset::<_, V>(SkipGalloping::default())
+        .with_batch::<_, V>(SkipGalloping::default()))
+}
+
+fn try_parse_prefetch<V>(name: &str, distance: usize) -> Option<Timer>
+where
+    V: Visitor<i32> + HarnessVisitor + 'static
+{
+    match name {
+        "merge_prefetch"     => Some(Timer::of_twoset::<_, V>(MergePrefetch { distance })),
+        "galloping_prefetch" => Some(Timer::of_twoset::<_, V>(GallopingPrefetch { distance })),
+        _ => None,
+    }
+}
+
+// galloping_factor<factor> and galloping_auto, which times the default factor
+// unless the runner has chosen one for the dataset.
+fn try_parse_galloping_factor<V>(name: &str) -> Option<Timer>
+where
+    V: Visitor<i32> + HarnessVisitor + 'static
+{
+    let factor = parse_gallop_factor(name)?;
+    Some(Timer::of_twoset::<_, V>(GallopingFactor { factor }).with_counted_comparisons(true))
+}
+
+/// Largest growth factor of `galloping_factor<factor>`.
+pub const MAX_GALLOP_FACTOR: usize = 64;
+
+/// Parses `galloping_factor<factor>` or `galloping_auto`, returning the growth
+/// factor, `DEFAULT_GALLOP_FACTOR` for `galloping_auto`.
+pub fn parse_gallop_factor(name: &str) -> Option<usize> {
+    if name == "galloping_auto" {
+        return Some(intersect::DEFAULT_GALLOP_FACTOR);
+    }
+    name.strip_prefix("galloping_factor")?
+        .parse().ok()
+        .filter(|f| (2..=MAX_GALLOP_FACTOR).contains(f))
+}
+
+fn try_parse_eytzinger<V>(name: &str) -> Option<Timer>
+where
+    V: Visitor<i32> + HarnessVisitor + 'static
+{
+    let probe: EytzingerProbe<V> = match name {
+        "eytzinger_probe" => intersect::eytzinger_probe,
         #[cfg(all(feature = "simd", target_feature = "avx2"))]
-        "broadcast_avx2_branch"   => Some(intersect::broadcast_avx2_branch),
-        // AVX-512
-        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-        "shuffling_avx512_branch"       => Some(intersect::shuffling_avx512_branch),
+        "eytzinger_probe_avx2" => intersect::eytzinger_probe_avx2,
         #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-        "broadcast_avx512_branch"       => Some(intersect::broadcast_avx512_branch),
-        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-        "vp2intersect_emulation_branch" => Some(intersect::vp2intersect_emulation_branch),
-        #[cfg(all(feature = "simd", target_feature = "avx512cd"))]
-        "conflict_intersect_branch"     => Some(intersect::conflict_intersect_branch),
-        _ => None,
+        "eytzinger_probe_avx512" => intersect::eytzinger_probe_avx512,
+        _ => return None,
     };
-    maybe_intersect.map(|intersect| V::twoset_timer(intersect))
+    Some(Timer {
+        twoset: Some(Box::new(
+            move |warmup, a, b| Ok(harness::time_eytzinger(warmup, a, b, probe)))),
+        ..Default::default()
+    })
+}
+
+// bloom[<bits per element>]_galloping
+fn try_parse_bloom<V>(name: &str) -> Option<Timer>
+where
+    V: Visitor<i32> + HarnessVisitor + 'static
+{
+    let bits_per_element = parse_bloom_bits(name.strip_suffix("_galloping")?)?;
+    Some(Timer {
+        twoset: Some(Box::new(
+            move |warmup, a, b| Ok(harness::time_bloom::<V>(warmup, a, b, bits_per_element)))),
+        ..Default::default()
+    })
+}
+
+/// Parses `bloom` or `bloom<bits>`, returning the filter bits per element.
+/// Without a count the default of `BlockedBloom::from_sorted` is used.
+pub fn parse_bloom_bits(prefix: &str) -> Option<usize> {
+    let bits = prefix.strip_prefix("bloom")?;
+    if bits.is_empty() {
+        Some(bloom::DEFAULT_BITS_PER_ELEMENT)
+    }
+    else {
+        bits.parse().ok().filter(|b| (1..=64).contains(b))
+    }
 }
 
 fn try_parse_twoset_c(name: &str) -> Option<Timer> {
@@ -180,6 +552,7 @@ fn try_parse_twoset_c(name: &str) -> Option<Timer> {
                 move |warmup, a, b| Ok(harness::time_twoset_c(warmup, a, b, i)))),
             kset: Some(Box::new(
                 move |warmup, sets| harness::time_svs_c(warmup, sets, i))),
+            ..Default::default()
         })
 }
 
@@ -187,29 +560,78 @@ pub trait TwosetTimingSpec<V> {
     fn twoset_timer(i: Intersect2<[i32], V>) -> Timer;
 }
 
-impl TwosetTimingSpec<UnsafeWriter<i32>> for UnsafeWriter<i32> {
-    fn twoset_timer(i: Intersect2<[i32], UnsafeWriter<i32>>) -> Timer {
-        Timer {
-            twoset: Some(Box::new(
-                move |warmup, a, b| Ok(harness::time_twoset(warmup, a, b, i)))),
-            kset: Some(Box::new(
-                move |warmup, sets| harness::time_svs::<UnsafeWriter<i32>>(warmup, sets, i))),
+macro_rules! twoset_timing_spec_svs {
+    ($($writer:ty),*) => {$(
+        impl TwosetTimingSpec<$writer> for $writer {
+            fn twoset_timer(i: Intersect2<[i32], $writer>) -> Timer {
+                // Reused by every k-set query.
+                let buffers = RefCell::new(SvsBuffers::<$writer>::default());
+                Timer {
+                    kset: Some(Box::new(
+                        move |warmup, sets| harness::time_svs(warmup, sets, &buffers, i))),
+                    ..Timer::of_twoset(Sorted2(i))
+                }
+            }
         }
-    }
+    )*};
 }
 
-impl TwosetTimingSpec<Counter> for Counter {
-    fn twoset_timer(i: Intersect2<[i32], Counter>) -> Timer {
-        Timer {
-            twoset: Some(Box::new(
-                move |warmup, a, b| Ok(harness::time_twoset(warmup, a, b, i)))),
-            kset: None,
+twoset_timing_spec_svs!(UnsafeWriter<i32>, VecWriter<i32>, SliceOutput, ReserveWriter<i32>,
+    UnsafeLookupWriter<i32>, HintedWriter<i32>);
+#[cfg(target_feature = "avx512f")]
+twoset_timing_spec_svs!(UnsafeCompressWriter<i32>);
+
+macro_rules! twoset_timing_spec_no_svs {
+    ($($writer:ty),*) => {$(
+        impl TwosetTimingSpec<$writer> for $writer {
+            fn twoset_timer(i: Intersect2<[i32], $writer>) -> Timer {
+                Timer::of_twoset(Sorted2(i))
+            }
         }
-    }
+    )*};
 }
 
+// Neither keeps a readable result for the next svs step.
+twoset_timing_spec_no_svs!(Counter, StreamingWriter);
+// Would need a file per svs step.
+#[cfg(unix)]
+twoset_timing_spec_no_svs!(MmapWriter);
+
+#[cfg(feature = "instrument")]
+twoset_timing_spec_svs!(InstrumentVisitor<UnsafeWriter<i32>>, InstrumentVisitor<VecWriter<i32>>,
+    InstrumentVisitor<SliceOutput>, InstrumentVisitor<ReserveWriter<i32>>,
+    InstrumentVisitor<UnsafeLookupWriter<i32>>, InstrumentVisitor<HintedWriter<i32>>);
+#[cfg(all(feature = "instrument", target_feature = "avx512f"))]
+twoset_timing_spec_svs!(InstrumentVisitor<UnsafeCompressWriter<i32>>);
+#[cfg(feature = "instrument")]
+twoset_timing_spec_no_svs!(InstrumentVisitor<Counter>, InstrumentVisitor<StreamingWriter>);
+#[cfg(all(feature = "instrument", unix))]
+twoset_timing_spec_no_svs!(InstrumentVisitor<MmapWriter>);
+
+// <BSR algorithm>[_sorted], where the _sorted suffix expands the BSR result
+// into a plain sorted array while intersecting.
 fn try_parse_bsr(name: &str) -> Option<Timer> {
-    let maybe_intersect: Option<UnsafeIntersectBsr> = match name {
+    if let Some(name) = name.strip_suffix("_sorted") {
+        let intersect = parse_bsr::<BsrExpandAdapter<UnsafeWriter<i32>>>(name)?;
+        return Some(Timer {
+            twoset: Some(Box::new(move |warmup, a, b|
+                Ok(harness::time_bsr_sorted(warmup, a, b, intersect)))),
+            ..Default::default()
+        });
+    }
+
+    let intersect = parse_bsr::<UnsafeBsrWriter>(name)?;
+    Some(Timer {
+        twoset: Some(Box::new(move |warmup, a, b| Ok(harness::time_bsr(warmup, a, b, intersect)))),
+        ..Default::default()
+    })
+}
+
+fn parse_bsr<W>(name: &str) -> Option<IntersectBsr<W>>
+where
+    W: SimdBsrVisitor4 + SimdBsrVisitor8 + SimdBsrVisitor16
+{
+    match name {
         "branchless_merge_bsr" => Some(intersect::branchless_merge_bsr),
         "galloping_bsr"        => Some(intersect::galloping_bsr),
         // SSE
@@ -251,11 +673,7 @@ fn try_parse_bsr(name: &str) -> Option<Timer> {
         #[cfg(all(feature = "simd", target_feature = "avx512f"))]
         "broadcast_avx512_bsr_branch"       => Some(intersect::broadcast_avx512_bsr_branch),
         _ => None,
-    };
-    maybe_intersect.map(|intersect: UnsafeIntersectBsr| Timer {
-        twoset: Some(Box::new(move |warmup, a, b| Ok(harness::time_bsr(warmup, a, b, intersect)))),
-        kset: None,
-    })
+    }
 }
 
 fn try_parse_kset<V>(name: &str) -> Option<Timer>
@@ -263,18 +681,17 @@ where
     V: Visitor<i32> + HarnessVisitor + TwosetTimingSpec<V>,
     V: SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + 'static
 {
-    let maybe_intersect: Option<IntersectK<DatafileSet, V>> = match name {
-        "baezayates_k"          => Some(intersect::baezayates_k),
-        "small_adaptive"        => Some(intersect::small_adaptive),
-        "small_adaptive_sorted" => Some(intersect::small_adaptive_sorted),
-        _ => None,
-    };
-    maybe_intersect.map(|intersect| Timer {
-        twoset: None,
-        kset: Some(Box::new(move |warmup, sets| harness::time_kset(warmup, sets, intersect))),
-    })
+    let algo = intersect::KSetAlgo::from_name(name)?;
+    let intersect: IntersectK<Vec<i32>, V> = algo.function();
+    Some(Timer::of_kset(SortedK(intersect)))
+}
+
+#[cfg(not(feature = "roaring"))]
+fn try_parse_roaring(_name: &str, _count_only: bool) -> Option<Timer> {
+    None
 }
 
+#[cfg(feature = "roaring")]
 fn try_parse_roaring(name: &str, count_only: bool) -> Option<Timer> { 
     match name {
         "croaring_opt" => Some(Timer {
@@ -284,6 +701,7 @@ fn try_parse_roaring(name: &str, count_only: bool) -> Option<Timer> {
                 if count_only { None } else {
                     Some(Box::new(|warmup, sets| Ok(harness::time_croaring_svs(warmup, sets, true))))
                 },
+            ..Default::default()
             }),
         "croaring" => Some(Timer {
             twoset: Some(Box::new(
@@ -292,6 +710,7 @@ fn try_parse_roaring(name: &str, count_only: bool) -> Option<Timer> {
                 if count_only { None } else {
                     Some(Box::new(|warmup, sets| Ok(harness::time_croaring_svs(warmup, sets, false))))
                 },
+            ..Default::default()
             }),
         // "roaringrs" => Some(Timer {
         //     twoset:
@@ -307,144 +726,164 @@ fn try_parse_roaring(name: &str, count_only: bool) -> Option<Timer> {
     }
 }
 
+fn try_parse_bitmap(name: &str, count_only: bool) -> Option<Timer> {
+    match name {
+        "bitmap_and" => Some(Timer {
+            twoset: Some(Box::new(
+                move |warmup, a, b| Ok(harness::time_bitmap_2set(warmup, a, b, count_only)))),
+            kset: Some(Box::new(|warmup, sets| Ok(harness::time_bitmap_kset(warmup, sets)))),
+            ..Default::default()
+        }),
+        "ewah_and" => Some(Timer {
+            twoset: Some(Box::new(
+                move |warmup, a, b| Ok(harness::time_ewah_2set(warmup, a, b, count_only)))),
+            kset: Some(Box::new(|warmup, sets| Ok(harness::time_ewah_kset(warmup, sets)))),
+            ..Default::default()
+        }),
+        _ => None,
+    }
+}
+
 fn try_parse_fesia<V>(name: &str) -> Option<Timer>
 where
-    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + HarnessVisitor
+    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + HarnessVisitor + 'static
 {
     use intersect::fesia::*;
 
-    let last_underscore = name.rfind("_")?;
-
-    let hash_scale = &name[last_underscore+1..];
-    if hash_scale.is_empty() {
-        return None;
-    }
-
-    let hash_scale: HashScale = hash_scale.parse().ok()?;
-    if hash_scale <= 0.0 {
-        return None;
+    let (prefix, hash_scale) = name.rsplit_once('_')?;
+    let hash_scale = parse_hash_scale(hash_scale)?;
+    let rest = prefix.strip_prefix("fesia")?;
+    // e.g., fesia32_avx2_prefilter_2, skipping pairs whose bitmaps are disjoint.
+    let (rest, prefilter) = match rest.strip_suffix("_prefilter") {
+        Some(rest) => (rest, true),
+        None => (rest, false),
+    };
+    let (layout, hash) = split_hash_type(rest);
+
+    match hash {
+        HashType::Identity => parse_fesia_layout::<IdentityHash, V>(layout, hash_scale, prefilter),
+        HashType::Mix => parse_fesia_layout::<MixHash, V>(layout, hash_scale, prefilter),
+        HashType::MultiplyShift => parse_fesia_layout::<MultiplyShiftHash, V>(layout, hash_scale, prefilter),
+        HashType::Crc32 => parse_fesia_layout::<Crc32Hash, V>(layout, hash_scale, prefilter),
+        HashType::XxHash => parse_fesia_layout::<XxHash, V>(layout, hash_scale, prefilter),
     }
+}
 
-    let prefix = &name[..last_underscore];
-
-    const FESIA: &str = "fesia";
+// <width>_<simd> with segments of `width` bits, hashed by `H`.
+fn parse_fesia_layout<H, V>(layout: &str, hash_scale: HashScale, prefilter: bool) -> Option<Timer>
+where
+    H: IntegerHash + Send + Sync + 'static,
+    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + HarnessVisitor + 'static
+{
+    use intersect::fesia::*;
 
-    use FesiaTwoSetMethod::*;
-    let (intersect, rest) =
-        if prefix.starts_with(FESIA) {
-            (SimilarSize, &prefix[FESIA.len()..])
-        }
-        else {
-            return None;
-        };
-    
-    use SimdType::*;
-    let simd_type =
-        if rest.ends_with("sse") { Sse }
-        else if rest.ends_with("avx2") { Avx2 }
-        else if rest.ends_with("avx512") { Avx512 }
-        else { return None; };
-
-    let maybe_timer: Option<Timer> =
-    match rest {
+    match layout {
         #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "8_sse" =>
-            Some(gen_fesia_timer::<MixHash, i8, 16, V>(hash_scale, intersect, simd_type)),
+        "8_sse" => Some(gen_fesia_timer::<Fesia<H, i8, 16>, SegmentIntersectSse, V>(hash_scale, prefilter)),
         #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "16_sse" =>
-            Some(gen_fesia_timer::<MixHash, i16, 8, V>(hash_scale, intersect, simd_type)),
+        "16_sse" => Some(gen_fesia_timer::<Fesia<H, i16, 8>, SegmentIntersectSse, V>(hash_scale, prefilter)),
         #[cfg(all(feature = "simd", target_feature = "ssse3"))]
-        "32_sse" =>
-            Some(gen_fesia_timer::<MixHash, i32, 4, V>(hash_scale, intersect, simd_type)),
+        "32_sse" => Some(gen_fesia_timer::<Fesia<H, i32, 4>, SegmentIntersectSse, V>(hash_scale, prefilter)),
         #[cfg(all(feature = "simd", target_feature = "avx2"))]
-        "8_avx2" =>
-            Some(gen_fesia_timer::<MixHash, i8, 32, V>(hash_scale, intersect, simd_type)),
+        "8_avx2" => Some(gen_fesia_timer::<Fesia<H, i8, 32>, SegmentIntersectAvx2, V>(hash_scale, prefilter)),
         #[cfg(all(feature = "simd", target_feature = "avx2"))]
-        "16_avx2" =>
-            Some(gen_fesia_timer::<MixHash, i16, 16, V>(hash_scale, intersect, simd_type)),
+        "16_avx2" => Some(gen_fesia_timer::<Fesia<H, i16, 16>, SegmentIntersectAvx2, V>(hash_scale, prefilter)),
         #[cfg(all(feature = "simd", target_feature = "avx2"))]
-        "32_avx2" =>
-            Some(gen_fesia_timer::<MixHash, i32, 8, V>(hash_scale, intersect, simd_type)),
+        "32_avx2" => Some(gen_fesia_timer::<Fesia<H, i32, 8>, SegmentIntersectAvx2, V>(hash_scale, prefilter)),
         #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-        "8_avx512" =>
-            Some(gen_fesia_timer::<MixHash, i8, 64, V>(hash_scale, intersect, simd_type)),
+        "8_avx512" => Some(gen_fesia_timer::<Fesia<H, i8, 64>, SegmentIntersectAvx512, V>(hash_scale, prefilter)),
         #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-        "16_avx512" =>
-            Some(gen_fesia_timer::<MixHash, i16, 32, V>(hash_scale, intersect, simd_type)),
+        "16_avx512" => Some(gen_fesia_timer::<Fesia<H, i16, 32>, SegmentIntersectAvx512, V>(hash_scale, prefilter)),
         #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-        "32_avx512" =>
-            Some(gen_fesia_timer::<MixHash, i32, 16, V>(hash_scale, intersect, simd_type)),
+        "32_avx512" => Some(gen_fesia_timer::<Fesia<H, i32, 16>, SegmentIntersectAvx512, V>(hash_scale, prefilter)),
         _ => None,
-    };
-
-    maybe_timer
+    }
 }
 
 fn try_parse_fesia_hash<V>(name: &str) -> Option<Timer>
 where
-    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + HarnessVisitor
+    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + HarnessVisitor + 'static
 {
     use intersect::fesia::*;
 
-    let last_underscore = name.rfind("_")?;
-
-    let hash_scale = &name[last_underscore+1..];
-    if hash_scale.is_empty() {
-        return None;
-    }
-
-    let hash_scale: HashScale = hash_scale.parse().ok()?;
-    if hash_scale <= 0.0 {
-        return None;
+    let (prefix, hash_scale) = name.rsplit_once('_')?;
+    let hash_scale = parse_hash_scale(hash_scale)?;
+    let rest = prefix.strip_prefix("fesia_hash")?;
+    let (width, hash) = split_hash_type(rest);
+
+    match hash {
+        HashType::Identity => parse_fesia_hash_width::<IdentityHash, V>(width, hash_scale),
+        HashType::Mix => parse_fesia_hash_width::<MixHash, V>(width, hash_scale),
+        HashType::MultiplyShift => parse_fesia_hash_width::<MultiplyShiftHash, V>(width, hash_scale),
+        HashType::Crc32 => parse_fesia_hash_width::<Crc32Hash, V>(width, hash_scale),
+        HashType::XxHash => parse_fesia_hash_width::<XxHash, V>(width, hash_scale),
     }
+}
 
-    let prefix = &name[..last_underscore];
+fn parse_fesia_hash_width<H, V>(width: &str, hash_scale: HashScale) -> Option<Timer>
+where
+    H: IntegerHash + Send + Sync + 'static,
+    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + HarnessVisitor + 'static
+{
+    use intersect::fesia::*;
 
-    const FESIA_HASH: &str = "fesia_hash";
+    match width {
+        "8" => Some(gen_fesia_hash_timer::<Fesia<H, i8, 16>, V>(hash_scale)),
+        "16" => Some(gen_fesia_hash_timer::<Fesia<H, i16, 8>, V>(hash_scale)),
+        "32" => Some(gen_fesia_hash_timer::<Fesia<H, i32, 4>, V>(hash_scale)),
+        _ => None,
+    }
+}
 
-    use FesiaTwoSetMethod::*;
-    let (intersect, rest) =
-        if prefix.starts_with(FESIA_HASH) {
-            (Skewed, &prefix[FESIA_HASH.len()..])
-        }
-        else {
-            return None;
-        };
+/// Splits an optional hash function suffix, e.g., `32_avx2_crc32`, off the
+/// rest of a FESIA name. Without one, sets are hashed with `MixHash`.
+pub fn split_hash_type(name: &str) -> (&str, HashType) {
+    match name.rsplit_once('_') {
+        Some((rest, hash)) => match HashType::from_name(hash) {
+            Some(hash) => (rest, hash),
+            None => (name, HashType::Mix),
+        },
+        None => (name, HashType::Mix),
+    }
+}
 
-    use SimdType::*;
-    let maybe_timer: Option<Timer> =
-    match rest {
-        "8" => Some(gen_fesia_timer::<MixHash, i8, 16, V>(hash_scale, intersect, Sse)),
-        "16" => Some(gen_fesia_timer::<MixHash, i16, 8, V>(hash_scale, intersect, Sse)),
-        "32" => Some(gen_fesia_timer::<MixHash, i32, 4, V>(hash_scale, intersect, Sse)),
-        _ => None,
-    };
+fn parse_hash_scale(hash_scale: &str) -> Option<HashScale> {
+    let hash_scale: HashScale = hash_scale.parse().ok()?;
+    (hash_scale > 0.0).then_some(hash_scale)
+}
 
-    maybe_timer
+fn gen_fesia_timer<F, I, V>(hash_scale: HashScale, prefilter: bool) -> Timer
+where
+    F: SetWithHashScale + FesiaIntersect + AsRef<F> + Send + Sync + 'static,
+    I: SegmentIntersect + Send + Sync + 'static,
+    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + HarnessVisitor + 'static
+{
+    // Preparing FESIA sets panics if they cannot be built, so check first.
+    let family = fesia_family::<F>(hash_scale);
+    Timer::of_twoset_in::<_, V>(
+            FesiaAlgorithm::<F, I>::new(hash_scale).with_prefilter(prefilter), Some(family.clone()))
+        .with_kset_in::<_, V>(FesiaAlgorithm::<F, I>::new(hash_scale), Some(family))
+        .with_check(move |sets| harness::check_fesia_sets(sets, hash_scale))
+        .with_fesia_stats::<F>(hash_scale)
 }
 
-fn gen_fesia_timer<H, S, const LANES: usize, V>(
-    hash_scale: HashScale,
-    intersect_method: FesiaTwoSetMethod,
-    simd_type: SimdType)
-    -> Timer
+fn gen_fesia_hash_timer<F, V>(hash_scale: HashScale) -> Timer
 where
-    H: IntegerHash,
-    S: SimdElement + MaskElement,
-    LaneCount<LANES>: SupportedLaneCount,
-    Simd<S, LANES>: BitAnd<Output=Simd<S, LANES>> + SimdPartialEq<Mask=Mask<S, LANES>>,
-    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + HarnessVisitor
+    F: SetWithHashScale + FesiaIntersect + AsRef<F> + Send + Sync + 'static,
+    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + HarnessVisitor + 'static
 {
     // TODO: k-set skewed intersect
-    let intersect_kset = FesiaKSetMethod::SimilarSize;
+    use intersect::fesia::SegmentIntersectSse;
 
-    use harness::time_fesia;
-
-    Timer {
-        twoset: Some(Box::new(move |warmup, a, b|
-            time_fesia::<H, S, LANES, V>(warmup, a, b, hash_scale, intersect_method, simd_type))),
-        kset: Some(Box::new(move |warmup, sets|
-            time_fesia_kset::<H, S, LANES, V>(warmup, sets, hash_scale, intersect_kset)))
-    }
+    let family = fesia_family::<F>(hash_scale);
+    Timer::of_twoset_in::<_, V>(FesiaHashAlgorithm::<F>::new(hash_scale), Some(family.clone()))
+        .with_kset_in::<_, V>(FesiaAlgorithm::<F, SegmentIntersectSse>::new(hash_scale), Some(family))
+        .with_check(move |sets| harness::check_fesia_sets(sets, hash_scale))
+        .with_fesia_stats::<F>(hash_scale)
 }
 
+// FESIA sets depend only on their type (hash, segment width and lanes) and
+// hash scale, whichever kernels intersect them.
+fn fesia_family<F>(hash_scale: HashScale) -> String {
+    format!("{}@{hash_scale}", std::any::type_name::<F>())
+}
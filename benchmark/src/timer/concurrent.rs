@@ -0,0 +1,228 @@
+//! Throughput of queries run by several threads at once on shared prepared
+//! sets, to see how an algorithm scales when cores compete for caches and
+//! memory bandwidth. Each query intersects the sets of one datafile. All
+//! queries of a point are prepared once and shared by scoped threads, then each
+//! thread runs queries round-robin, starting from a different query, until
+//! the configured duration has passed. Each query creates its visitor, so
+//! allocating an output buffer is included unless counting only.
+//!
+//! In batch mode, one large set is shared by every query instead, and each
+//! thread probes it with batches of small sets through `BatchProbe`.
+
+use std::{
+    hint,
+    sync::Barrier,
+    thread,
+    time::{Duration, Instant},
+};
+use setops::{intersect::{TwoSetAlgorithm, KSetAlgorithm, BatchProbe}, visitor::Visitor};
+use crate::{datafile::DatafileSet, timer::harness::HarnessVisitor};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ConcurrentConfig {
+    pub threads: usize,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Throughput {
+    pub queries: u64,
+    /// Longest time any thread ran for.
+    pub elapsed: Duration,
+}
+
+impl Throughput {
+    pub fn queries_per_second(&self) -> f64 {
+        self.queries as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// Mean time each of `threads` threads took per query.
+    pub fn mean_query_time(&self, threads: usize) -> Duration {
+        (self.elapsed * threads as u32).div_f64(self.queries.max(1) as f64)
+    }
+}
+
+pub fn time_concurrent_twoset<A, V>(
+    algorithm: &A,
+    queries: &[Vec<DatafileSet>],
+    config: &ConcurrentConfig) -> Result<Throughput, String>
+where
+    A: TwoSetAlgorithm<i32, V> + Sync,
+    for<'s> A::Prepared<'s>: Sync,
+    V: HarnessVisitor,
+{
+    let prepared = queries.iter()
+        .map(|sets| match sets.as_slice() {
+            [a, b] => Ok((algorithm.prepare(a), algorithm.prepare(b), a.len().min(b.len()))),
+            _ => Err("2-set intersection requires 2 sets per datafile".to_string()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    run_threads(&prepared, config, |(set_a, set_b, capacity)| {
+        let mut visitor = V::with_capacity(*capacity);
+        algorithm.intersect(set_a, set_b, &mut visitor);
+        hint::black_box(&visitor);
+    })
+}
+
+pub fn time_concurrent_kset<A, V>(
+    algorithm: &A,
+    queries: &[Vec<DatafileSet>],
+    config: &ConcurrentConfig) -> Result<Throughput, String>
+where
+    A: KSetAlgorithm<i32, V> + Sync,
+    for<'s> A::Prepared<'s>: Sync,
+    V: HarnessVisitor,
+{
+    let prepared = queries.iter()
+        .map(|sets| {
+            let capacity = sets.iter().map(|s| s.len()).min()
+                .ok_or_else(|| "cannot intersect 0 sets".to_string())?;
+            Ok((algorithm.prepare(sets), capacity))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    run_threads(&prepared, config, |(sets, capacity)| {
+        let mut visitor = V::with_capacity(*capacity);
+        algorithm.intersect(sets, &mut visitor);
+        hint::black_box(&visitor);
+    })
+}
+
+/// Probes the larger set of the first query with the smaller set of every
+/// query, `batch` queries at a time. Each batch counts as `batch` queries.
+pub fn time_concurrent_batch<A, V>(
+    algorithm: &A,
+    queries: &[Vec<DatafileSet>],
+    config: &ConcurrentConfig,
+    batch: usize) -> Result<Throughput, String>
+where
+    A: TwoSetAlgorithm<i32, V>,
+    for<'s> A::Prepared<'s>: BatchProbe<i32> + Sync,
+    V: Visitor<i32> + HarnessVisitor,
+{
+    if batch == 0 {
+        return Err("a batch needs at least 1 query".to_string());
+    }
+    let smalls = queries.iter()
+        .map(|sets| match sets.as_slice() {
+            [a, b] => Ok(if a.len() <= b.len() { a.as_slice() } else { b.as_slice() }),
+            _ => Err("batch probes require 2 sets per datafile".to_string()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let large = queries.first()
+        .and_then(|sets| sets.iter().max_by_key(|s| s.len()))
+        .ok_or_else(|| "no queries to run".to_string())?;
+    let large = algorithm.prepare(large);
+
+    // Batches of consecutive queries, the last wrapping around to the first.
+    let batches: Vec<Vec<usize>> = (0..smalls.len().div_ceil(batch))
+        .map(|b| (0..batch).map(|i| (b * batch + i) % smalls.len()).collect())
+        .collect();
+
+    let throughput = run_threads(&batches, config, |batch| {
+        let sets: Vec<&[i32]> = batch.iter().map(|&i| smalls[i]).collect();
+        let mut visitors: Vec<V> = sets.iter().map(|s| V::with_capacity(s.len())).collect();
+        large.intersect_batch(&sets, &mut visitors);
+        hint::black_box(&visitors);
+    })?;
+    Ok(Throughput { queries: throughput.queries * batch as u64, ..throughput })
+}
+
+fn run_threads<Q, F>(queries: &[Q], config: &ConcurrentConfig, query: F)
+    -> Result<Throughput, String>
+where
+    Q: Sync,
+    F: Fn(&Q) + Sync,
+{
+    if queries.is_empty() {
+        return Err("no queries to run".to_string());
+    }
+    if config.threads == 0 {
+        return Err("at least 1 query thread is required".to_string());
+    }
+
+    // Threads start together once all are spawned.
+    let barrier = Barrier::new(config.threads);
+    let (query, barrier) = (&query, &barrier);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..config.threads)
+            .map(|t| {
+                let duration = config.duration;
+
+                scope.spawn(move || {
+                    barrier.wait();
+                    let start = Instant::now();
+
+                    let mut count = 0;
+                    let mut i = t % queries.len();
+                    while count == 0 || start.elapsed() < duration {
+                        query(&queries[i]);
+                        count += 1;
+                        i = (i + 1) % queries.len();
+                    }
+                    (count, start.elapsed())
+                })
+            })
+            .collect();
+
+        let mut throughput = Throughput { queries: 0, elapsed: Duration::ZERO };
+        for handle in handles {
+            let (count, elapsed) = handle.join()
+                .map_err(|_| "query thread panicked".to_string())?;
+            throughput.queries += count;
+            throughput.elapsed = throughput.elapsed.max(elapsed);
+        }
+        Ok(throughput)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use setops::{
+        intersect::{self, algorithm::{Sorted2, SortedK}, skip::SkipGalloping},
+        visitor::Counter,
+    };
+
+    fn queries(k: usize) -> Vec<Vec<DatafileSet>> {
+        (1..=4).map(|step| (0..k).map(|i| (0..100).map(|v| v * (step + i as i32)).collect()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_concurrent_twoset() {
+        let config = ConcurrentConfig { threads: 3, duration: Duration::from_millis(10) };
+        let algorithm = Sorted2::<i32, Counter>(intersect::branchless_merge);
+
+        let throughput = time_concurrent_twoset(&algorithm, &queries(2), &config).unwrap();
+        assert!(throughput.queries >= 3);
+        assert!(throughput.elapsed >= config.duration);
+
+        assert!(time_concurrent_twoset(&algorithm, &queries(3), &config).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_batch() {
+        let config = ConcurrentConfig { threads: 2, duration: Duration::from_millis(10) };
+        let algorithm = SkipGalloping::default();
+
+        let throughput = time_concurrent_batch::<_, Counter>(
+            &algorithm, &queries(2), &config, 3).unwrap();
+        assert!(throughput.queries >= 6);
+        assert_eq!(throughput.queries % 3, 0);
+
+        assert!(time_concurrent_batch::<_, Counter>(&algorithm, &queries(2), &config, 0).is_err());
+        assert!(time_concurrent_batch::<_, Counter>(&algorithm, &queries(3), &config, 1).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_kset() {
+        let config = ConcurrentConfig { threads: 2, duration: Duration::from_millis(10) };
+        let algorithm = SortedK::<i32, Counter>(intersect::svs);
+
+        let throughput = time_concurrent_kset(&algorithm, &queries(3), &config).unwrap();
+        assert!(throughput.queries >= 2);
+    }
+}
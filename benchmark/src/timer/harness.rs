@@ -1,35 +1,163 @@
 use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
     time::{Duration, Instant},
-    hint, simd::{*, cmp::*}, ops::BitAnd,
+    hint, simd::*,
 };
+#[cfg(unix)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(unix)]
+use setops::visitor::MmapWriter;
 use setops::{
-    intersect::{Intersect2, Intersect2C, IntersectK, fesia::*, self},
+    intersect::{
+        Intersect2, Intersect2C, SvsBuffers, TwoSetAlgorithm, KSetAlgorithm, self,
+        fesia::{self, HashScale},
+        partition::{self, PartitionedSet},
+        range,
+    },
     visitor::{
         Visitor, SimdVisitor4, SimdVisitor8, SimdVisitor16,
-        UnsafeWriter, UnsafeBsrWriter, Counter
+        UnsafeWriter, UnsafeBsrWriter, Counter, BsrExpandAdapter,
+        VecWriter, ReserveWriter, HintedWriter, StreamingWriter, SliceWriter, Clearable, Reservable,
+        UnsafeLookupWriter, UnsafeCompressWriter,
     },
     bsr::{BsrVec, BsrRef},
+    encoding::SvbSet,
+    eytzinger::EytzingerSet,
+    bloom::BlockedBloom,
+    bitmap,
+    ewah::EwahBitmap,
+    instrument::{self, InstrumentVisitor, WorkCounters},
     Set,
 };
-use crate::{datafile::DatafileSet, util, timer::perf::*};
+use crate::{datafile::DatafileSet, util, verify, timer::perf::*, schema::PERCENT};
 
 pub type RunResult = Result<Run, String>;
-pub type UnsafeIntersectBsr = for<'a> fn(set_a: BsrRef<'a>, set_b: BsrRef<'a>, visitor: &mut UnsafeBsrWriter);
+pub type IntersectBsr<V> = for<'a> fn(set_a: BsrRef<'a>, set_b: BsrRef<'a>, visitor: &mut V);
+pub type EytzingerProbe<V> = fn(small: &[i32], large: &EytzingerSet<i32>, visitor: &mut V);
 
 pub struct Run {
     pub time: Duration,
     pub perf: PerfResults,
+    // Nanoseconds taken by each additional iteration, if requested with
+    // `Harness::with_latency`.
+    pub samples: Vec<u64>,
+    // Size of the result, if written to an output buffer.
+    pub output: Option<OutputStats>,
+    // Time taken to flush the output after the run, if written to a file.
+    pub flush: Option<Duration>,
+    // Work done in the counted run, if built with `instrument`.
+    pub work: Option<WorkCounters>,
+    // Hash of the result of the counted run (see `verify::hash_set`), if
+    // requested with `Harness::with_output_hashes`.
+    pub output_hash: Option<u64>,
+}
+
+/// Realised output cardinality against the capacity of the output buffer at
+/// the end of a run.
+#[derive(Debug, Clone, Copy)]
+pub struct OutputStats {
+    pub len: usize,
+    pub capacity: usize,
+}
+
+
+/// Prepared representations of the sets of one datafile, shared between the
+/// algorithms which prepare sets alike, e.g., FESIA algorithms of the same
+/// layout and hash scale. Entries are keyed by the address of the input, so
+/// the sets must not move or change while the cache is alive.
+#[derive(Default)]
+pub struct PreparedCache {
+    entries: RefCell<HashMap<PreparedKey, Rc<dyn Any>>>,
 }
 
+// Family, prepared type, and address and length of the input.
+type PreparedKey = (String, TypeId, usize, usize);
+
+impl PreparedCache {
+    /// Returns what `family` prepared from `input` earlier, or prepares it now.
+    pub fn get_or_prepare<T, P: 'static>(&self, family: &str, input: &[T], prepare: impl FnOnce() -> P)
+        -> Rc<P>
+    {
+        let key = (family.to_string(), TypeId::of::<P>(), input.as_ptr() as usize, input.len());
+        if let Some(prepared) = self.entries.borrow().get(&key) {
+            return Rc::clone(prepared).downcast().expect("keyed by type");
+        }
+        let prepared = Rc::new(prepare());
+        self.entries.borrow_mut().insert(key, prepared.clone());
+        prepared
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
 
 pub struct Harness<'a> {
     warmup: Duration,
     counters: &'a mut PerfCounters,
+    latency_iterations: usize,
+    pretouch: bool,
+    hash_outputs: bool,
+    prepared: Option<&'a PreparedCache>,
 }
 
 impl<'a> Harness<'a> {
     pub fn new(warmup: Duration, counters: &'a mut PerfCounters) -> Self {
-        Self { warmup, counters }
+        Self {
+            warmup,
+            counters,
+            latency_iterations: 0,
+            pretouch: false,
+            hash_outputs: false,
+            prepared: None,
+        }
+    }
+
+    /// Reuse sets prepared by earlier algorithms of the same family from
+    /// `cache`, rather than preparing them for every run.
+    pub fn with_prepared(mut self, cache: Option<&'a PreparedCache>) -> Self {
+        self.prepared = cache;
+        self
+    }
+
+    /// Prepares `input` with `prepare`, or takes it from the cache if the
+    /// harness shares prepared sets and the algorithm belongs to a `family`.
+    pub fn prepare<T, P: 'static>(&self, family: Option<&str>, input: &[T], prepare: impl FnOnce() -> P)
+        -> Rc<P>
+    {
+        match (self.prepared, family) {
+            (Some(cache), Some(family)) => cache.get_or_prepare(family, input, prepare),
+            _ => Rc::new(prepare()),
+        }
+    }
+
+    /// Write over the whole output buffer of each visitor prepared by
+    /// `time_visitor`, so first-touch page faults are not timed.
+    pub fn with_pretouch(mut self, pretouch: bool) -> Self {
+        self.pretouch = pretouch;
+        self
+    }
+
+    /// Hash the result of each run, so results can be checked against those
+    /// of another machine. Hashing is not timed, and results which are not
+    /// kept (e.g., when only counted) are not hashed.
+    pub fn with_output_hashes(mut self, hash_outputs: bool) -> Self {
+        self.hash_outputs = hash_outputs;
+        self
+    }
+
+    /// After the counted run, time `iterations` further runs individually to
+    /// record the distribution of run times.
+    pub fn with_latency(mut self, iterations: usize) -> Self {
+        self.latency_iterations = iterations;
+        self
     }
 
     pub fn time<D>(
@@ -45,6 +173,7 @@ impl<'a> Harness<'a> {
 
         let mut data = prepare();
 
+        instrument::reset();
         self.counters.enable();
 
         let start = Instant::now();
@@ -52,24 +181,260 @@ impl<'a> Harness<'a> {
         let elapsed = start.elapsed();
 
         self.counters.disable();
+        let work = instrument::ENABLED.then(instrument::take);
+
+        let mut samples = Vec::with_capacity(self.latency_iterations);
+        for _ in 0..self.latency_iterations {
+            let mut data = prepare();
+
+            let start = Instant::now();
+            run(&mut data);
+            samples.push(start.elapsed().as_nanos() as u64);
+            hint::black_box(&data);
+        }
 
         let run_result = Run {
             time: elapsed,
             perf: self.counters.results(),
+            samples,
+            output: None,
+            flush: None,
+            work,
+            output_hash: None,
         };
 
         (run_result, data)
     }
+
+    /// Like `time`, preparing a visitor for `cardinality` results before each
+    /// run.
+    pub fn time_visitor<V: HarnessVisitor>(
+        &mut self,
+        cardinality: usize,
+        run: impl Fn(&mut V)) -> (Run, V)
+    {
+        let pretouch = self.pretouch;
+        let prepare = || {
+            let mut visitor = V::with_capacity(cardinality);
+            if pretouch {
+                visitor.pretouch();
+            }
+            visitor
+        };
+        let (mut run, visitor) = self.time(prepare, run);
+        if self.hash_outputs {
+            run.output_hash = visitor.output_hash();
+        }
+        (run, visitor)
+    }
+
+    /// Records the hash of `result` in `run` if hashing outputs. `result` is
+    /// only called then, so it may redo an untimed run.
+    fn hash_output<I>(&self, mut run: Run, result: impl FnOnce() -> I) -> Run
+    where
+        I: IntoIterator<Item = u32>
+    {
+        if self.hash_outputs {
+            run.output_hash = Some(verify::hash_set(result()));
+        }
+        run
+    }
 }
 
 pub trait HarnessVisitor {
     fn with_capacity(cardinality: usize) -> Self;
+
+    fn output_stats(&self) -> Option<OutputStats> {
+        None
+    }
+
+    /// Writes every page of the output buffer, if the visitor has one.
+    fn pretouch(&mut self) {
+    }
+
+    /// Writes the output back to its file, returning the time taken. Only
+    /// called after the timed run, so I/O is kept out of its time.
+    fn flush(&mut self) -> Option<Duration> {
+        None
+    }
+
+    /// Hash of the result, if the visitor keeps it.
+    fn output_hash(&self) -> Option<u64> {
+        None
+    }
 }
 
-impl<T> HarnessVisitor for UnsafeWriter<T> {
+impl HarnessVisitor for UnsafeWriter<i32> {
     fn with_capacity(cardinality: usize) -> Self {
         UnsafeWriter::with_capacity(cardinality)
     }
+
+    fn output_stats(&self) -> Option<OutputStats> {
+        Some(OutputStats { len: self.as_ref().len(), capacity: self.capacity() })
+    }
+
+    fn output_hash(&self) -> Option<u64> {
+        hash_slice(self.as_ref())
+    }
+
+    fn pretouch(&mut self) {
+        touch(self, self.capacity());
+    }
+}
+
+impl HarnessVisitor for UnsafeLookupWriter<i32> {
+    fn with_capacity(cardinality: usize) -> Self {
+        UnsafeLookupWriter::with_capacity(cardinality)
+    }
+
+    fn output_stats(&self) -> Option<OutputStats> {
+        Some(OutputStats { len: self.as_ref().len(), capacity: self.capacity() })
+    }
+
+    fn output_hash(&self) -> Option<u64> {
+        hash_slice(self.as_ref())
+    }
+
+    fn pretouch(&mut self) {
+        touch(self, self.capacity());
+    }
+}
+
+impl HarnessVisitor for UnsafeCompressWriter<i32> {
+    fn with_capacity(cardinality: usize) -> Self {
+        UnsafeCompressWriter::with_capacity(cardinality)
+    }
+
+    fn output_stats(&self) -> Option<OutputStats> {
+        Some(OutputStats { len: self.as_ref().len(), capacity: self.capacity() })
+    }
+
+    fn output_hash(&self) -> Option<u64> {
+        hash_slice(self.as_ref())
+    }
+
+    fn pretouch(&mut self) {
+        touch(self, self.capacity());
+    }
+}
+
+// Starts empty so growing the vector is part of the measurement.
+impl HarnessVisitor for VecWriter<i32> {
+    fn with_capacity(_cardinality: usize) -> Self {
+        VecWriter::new()
+    }
+
+    fn output_stats(&self) -> Option<OutputStats> {
+        Some(OutputStats { len: self.as_ref().len(), capacity: self.capacity() })
+    }
+
+    fn output_hash(&self) -> Option<u64> {
+        hash_slice(self.as_ref())
+    }
+}
+
+// Like VecWriter, starts empty so reserving is part of the measurement.
+impl HarnessVisitor for ReserveWriter<i32> {
+    fn with_capacity(_cardinality: usize) -> Self {
+        ReserveWriter::new()
+    }
+
+    fn output_stats(&self) -> Option<OutputStats> {
+        Some(OutputStats { len: self.as_ref().len(), capacity: self.capacity() })
+    }
+
+    fn output_hash(&self) -> Option<u64> {
+        hash_slice(self.as_ref())
+    }
+}
+
+// Hinted with the bound, as a caller knowing min(|A|, |B|) would.
+impl HarnessVisitor for HintedWriter<i32> {
+    fn with_capacity(cardinality: usize) -> Self {
+        HintedWriter::new(Some(cardinality), cardinality)
+    }
+
+    fn output_stats(&self) -> Option<OutputStats> {
+        Some(OutputStats { len: self.as_ref().len(), capacity: self.capacity() })
+    }
+
+    fn output_hash(&self) -> Option<u64> {
+        hash_slice(self.as_ref())
+    }
+
+    fn pretouch(&mut self) {
+        touch(self, self.capacity());
+    }
+}
+
+impl HarnessVisitor for SliceOutput {
+    fn with_capacity(cardinality: usize) -> Self {
+        SliceOutput::with_capacity(cardinality)
+    }
+
+    fn output_stats(&self) -> Option<OutputStats> {
+        Some(OutputStats { len: self.position, capacity: self.buffer.len() })
+    }
+
+    fn output_hash(&self) -> Option<u64> {
+        hash_slice(self.as_ref())
+    }
+
+    fn pretouch(&mut self) {
+        touch(self, self.buffer.len());
+    }
+}
+
+impl HarnessVisitor for StreamingWriter {
+    fn with_capacity(cardinality: usize) -> Self {
+        StreamingWriter::with_capacity(cardinality)
+    }
+
+    fn output_stats(&self) -> Option<OutputStats> {
+        Some(OutputStats { len: self.len(), capacity: self.capacity() })
+    }
+
+    fn output_hash(&self) -> Option<u64> {
+        hash_slice(&self.to_vec())
+    }
+
+    fn pretouch(&mut self) {
+        touch(self, self.capacity());
+    }
+}
+
+// Each writer maps a fresh temporary file, unlinked at once so that it is
+// removed with the writer.
+#[cfg(unix)]
+impl HarnessVisitor for MmapWriter {
+    fn with_capacity(cardinality: usize) -> Self {
+        static FILES: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!("setops-output-{}-{}.bin",
+            std::process::id(), FILES.fetch_add(1, Ordering::Relaxed)));
+
+        let writer = MmapWriter::with_capacity(&path, cardinality)
+            .unwrap_or_else(|e| panic!("unable to map output file {}: {e}", path.display()));
+        let _ = std::fs::remove_file(&path);
+        writer
+    }
+
+    fn output_stats(&self) -> Option<OutputStats> {
+        Some(OutputStats { len: self.len(), capacity: self.capacity() })
+    }
+
+    fn output_hash(&self) -> Option<u64> {
+        hash_slice(self.as_ref())
+    }
+
+    fn pretouch(&mut self) {
+        touch(self, self.capacity());
+    }
+
+    fn flush(&mut self) -> Option<Duration> {
+        let start = Instant::now();
+        MmapWriter::flush(self).expect("unable to flush output file");
+        Some(start.elapsed())
+    }
 }
 
 impl HarnessVisitor for Counter {
@@ -78,22 +443,172 @@ impl HarnessVisitor for Counter {
     }
 }
 
-pub fn time_twoset<V>(
+impl<V: HarnessVisitor> HarnessVisitor for InstrumentVisitor<V> {
+    fn with_capacity(cardinality: usize) -> Self {
+        InstrumentVisitor::new(V::with_capacity(cardinality))
+    }
+
+    fn output_stats(&self) -> Option<OutputStats> {
+        self.inner().output_stats()
+    }
+
+    fn pretouch(&mut self) {
+        self.inner_mut().pretouch();
+    }
+
+    fn flush(&mut self) -> Option<Duration> {
+        self.inner_mut().flush()
+    }
+
+    fn output_hash(&self) -> Option<u64> {
+        self.inner().output_hash()
+    }
+}
+
+fn hash_slice(result: &[i32]) -> Option<u64> {
+    Some(verify::hash_set(util::slice_i32_to_u32(result).iter().copied()))
+}
+
+// Fills the buffer through the visitor, then empties it again.
+fn touch<V: Visitor<i32> + Clearable>(visitor: &mut V, capacity: usize) {
+    for _ in 0..capacity {
+        visitor.visit(0);
+    }
+    visitor.clear();
+}
+
+fn with_output<V: HarnessVisitor>((mut run, mut writer): (Run, V)) -> Run {
+    run.output = writer.output_stats();
+    run.flush = writer.flush();
+    run
+}
+
+/// A preallocated, fixed-size output buffer written through `SliceWriter`.
+/// The buffer has one vector of slack as vector stores write whole vectors.
+pub struct SliceOutput {
+    buffer: Box<[i32]>,
+    position: usize,
+}
+
+impl SliceOutput {
+    pub fn with_capacity(cardinality: usize) -> Self {
+        Self {
+            buffer: vec![0; cardinality + 16].into_boxed_slice(),
+            position: 0,
+        }
+    }
+
+    #[inline]
+    fn write(&mut self, f: impl FnOnce(&mut SliceWriter<i32>)) {
+        let mut writer = SliceWriter::from(&mut self.buffer[self.position..]);
+        f(&mut writer);
+        self.position += writer.position();
+    }
+}
+
+impl Visitor<i32> for SliceOutput {
+    #[inline]
+    fn visit(&mut self, value: i32) {
+        self.buffer[self.position] = value;
+        self.position += 1;
+    }
+}
+
+impl SimdVisitor4 for SliceOutput {
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        self.write(|w| w.visit_vector4(value, mask));
+    }
+}
+
+impl SimdVisitor8 for SliceOutput {
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        self.write(|w| w.visit_vector8(value, mask));
+    }
+}
+
+impl SimdVisitor16 for SliceOutput {
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        self.write(|w| w.visit_vector16(value, mask));
+    }
+}
+
+impl Clearable for SliceOutput {
+    fn clear(&mut self) {
+        self.position = 0;
+    }
+}
+
+impl AsRef<[i32]> for SliceOutput {
+    fn as_ref(&self) -> &[i32] {
+        &self.buffer[..self.position]
+    }
+}
+
+impl Reservable for SliceOutput {
+    fn reserve(&mut self, cardinality: usize) {
+        if self.buffer.len() < self.position + cardinality + 16 {
+            let mut buffer = vec![0; self.position + cardinality + 16].into_boxed_slice();
+            buffer[..self.position].copy_from_slice(&self.buffer[..self.position]);
+            self.buffer = buffer;
+        }
+    }
+}
+
+impl Default for SliceOutput {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+/// Times `algorithm` on two sets. Preparing the sets is not timed.
+pub fn time_twoset<A, V>(
     harness: &mut Harness,
     set_a: &[i32],
     set_b: &[i32],
-    intersect: Intersect2<[i32], V>) -> Run
+    algorithm: &A) -> Run
 where
-    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + HarnessVisitor
+    A: TwoSetAlgorithm<i32, V>,
+    V: HarnessVisitor
 {
-    let capacity = set_a.len().min(set_b.len());
+    let (prepared_a, prepared_b) = (algorithm.prepare(set_a), algorithm.prepare(set_b));
+    time_prepared_twoset(harness, &prepared_a, &prepared_b, set_a.len().min(set_b.len()), algorithm)
+}
 
-    let prepare = || V::with_capacity(capacity);
-    let run = |writer: &mut _| intersect(set_a, set_b, writer);
+/// Like `time_twoset`, sharing the prepared sets with other algorithms of
+/// `family` (see `Harness::prepare`). Only sets prepared without borrowing
+/// the input can be shared.
+pub fn time_twoset_in<A, V>(
+    harness: &mut Harness,
+    set_a: &[i32],
+    set_b: &[i32],
+    algorithm: &A,
+    family: Option<&str>) -> Run
+where
+    A: TwoSetAlgorithm<i32, V>,
+    for<'s> A::Prepared<'s>: 'static,
+    V: HarnessVisitor
+{
+    let prepared_a = harness.prepare(family, set_a, || algorithm.prepare(set_a));
+    let prepared_b = harness.prepare(family, set_b, || algorithm.prepare(set_b));
+    time_prepared_twoset(harness, &*prepared_a, &*prepared_b, set_a.len().min(set_b.len()), algorithm)
+}
 
-    let (elapsed, _writer) = harness.time(prepare, run);
+fn time_prepared_twoset<A, V>(
+    harness: &mut Harness,
+    prepared_a: &A::Prepared<'_>,
+    prepared_b: &A::Prepared<'_>,
+    capacity: usize,
+    algorithm: &A) -> Run
+where
+    A: TwoSetAlgorithm<i32, V>,
+    V: HarnessVisitor
+{
+    let run = |writer: &mut _| algorithm.intersect(prepared_a, prepared_b, writer);
 
-    elapsed
+    with_output(harness.time_visitor::<V>(capacity, run))
 }
 
 pub fn time_twoset_c(
@@ -109,66 +624,240 @@ pub fn time_twoset_c(
 
     let (elapsed, _writer) = harness.time(prepare, run);
 
-    elapsed
+    // The length of the result is only returned, so hashing runs it again.
+    harness.hash_output(elapsed, || {
+        let mut result = prepare();
+        let len = intersect(set_a, set_b, &mut result);
+        util::slice_i32_to_u32(&result[..len]).to_vec()
+    })
 }
 
 pub fn time_bsr(
     harness: &mut Harness,
     set_a: &[i32],
     set_b: &[i32],
-    intersect: UnsafeIntersectBsr) -> Run
+    intersect: IntersectBsr<UnsafeBsrWriter>) -> Run
 {
-    let bsr_a = BsrVec::from_sorted(util::slice_i32_to_u32(set_a));
-    let bsr_b = BsrVec::from_sorted(util::slice_i32_to_u32(set_b));
+    let bsr_a = prepare_bsr(harness, set_a);
+    let bsr_b = prepare_bsr(harness, set_b);
 
     let capacity = bsr_a.len().min(bsr_b.len());
 
     let prepare = || UnsafeBsrWriter::with_capacities(capacity);
     let run = |writer: &mut _| intersect(bsr_a.bsr_ref(), bsr_b.bsr_ref(), writer);
 
-    let (elapsed, _writer) = harness.time(prepare, run);
+    let (elapsed, writer) = harness.time(prepare, run);
+
+    harness.hash_output(elapsed, || {
+        let result: BsrVec = writer.into();
+        result.to_sorted_set()
+    })
+}
+
+// Converting to BSR is not timed; expanding the result back to elements is.
+pub fn time_bsr_sorted(
+    harness: &mut Harness,
+    set_a: &[i32],
+    set_b: &[i32],
+    intersect: IntersectBsr<BsrExpandAdapter<UnsafeWriter<i32>>>) -> Run
+{
+    let bsr_a = prepare_bsr(harness, set_a);
+    let bsr_b = prepare_bsr(harness, set_b);
+
+    let capacity = set_a.len().min(set_b.len());
+
+    let prepare = || BsrExpandAdapter::new(UnsafeWriter::with_capacity(capacity));
+    let run = |writer: &mut _| intersect(bsr_a.bsr_ref(), bsr_b.bsr_ref(), writer);
+
+    let (elapsed, writer) = harness.time(prepare, run);
+
+    harness.hash_output(elapsed, || util::slice_i32_to_u32(writer.into_inner().as_ref()).to_vec())
+}
+
+fn prepare_bsr(harness: &Harness, set: &[i32]) -> Rc<BsrVec> {
+    harness.prepare(Some("bsr"), set, || BsrVec::from_sorted(util::slice_i32_to_u32(set)))
+}
+
+// Partitioning is not timed.
+pub fn time_partition<V>(
+    harness: &mut Harness,
+    set_a: &[i32],
+    set_b: &[i32],
+    intersect: Intersect2<[i32], V>) -> Run
+where
+    V: Visitor<i32> + HarnessVisitor
+{
+    let (partitioned_a, partitioned_b) =
+        PartitionedSet::pair(set_a, set_b, partition::DEFAULT_BUCKET_LEN);
+
+    let capacity = set_a.len().min(set_b.len());
+
+    let run = |writer: &mut _|
+        partition::partition_intersect(&partitioned_a, &partitioned_b, intersect, writer);
+
+    with_output(harness.time_visitor::<V>(capacity, run))
+}
+
+/// Times `intersect_range` over the middle `window` (out of PERCENT) of the
+/// values spanned by both sets, including narrowing the sets to the range.
+pub fn time_range<V>(
+    harness: &mut Harness,
+    set_a: &[i32],
+    set_b: &[i32],
+    window: u32,
+    intersect: Intersect2<[i32], V>) -> Run
+where
+    V: Visitor<i32> + HarnessVisitor
+{
+    let range = middle_range(set_a, set_b, window);
+    let capacity = range::narrow(set_a, &range).len().min(range::narrow(set_b, &range).len());
+
+    let run = |writer: &mut _|
+        intersect::intersect_range(set_a, set_b, range.clone(), intersect, writer);
+
+    with_output(harness.time_visitor::<V>(capacity, run))
+}
+
+fn middle_range(set_a: &[i32], set_b: &[i32], window: u32) -> std::ops::Range<i32> {
+    let first = set_a.first().into_iter().chain(set_b.first()).min().copied().unwrap_or(0) as i64;
+    let last = set_a.last().max(set_b.last()).copied().unwrap_or(0) as i64;
+
+    let span = last - first + 1;
+    let width = span * window.min(PERCENT) as i64 / PERCENT as i64;
+    let start = first + (span - width) / 2;
+    (start as i32)..((start + width).min(i32::MAX as i64) as i32)
+}
+
+pub fn time_svb_probe<V>(
+    harness: &mut Harness,
+    set_a: &[i32],
+    set_b: &[i32]) -> Run
+where
+    V: Visitor<i32> + HarnessVisitor
+{
+    let (small, large) =
+        if set_a.len() <= set_b.len() { (set_a, set_b) } else { (set_b, set_a) };
+    let encoded_large = harness.prepare(Some("svb"), large, || SvbSet::from_sorted(large));
+
+    let run = |writer: &mut _| intersect::svb_probe(small, &encoded_large, writer);
 
-    elapsed
+    with_output(harness.time_visitor::<V>(small.len(), run))
 }
 
-pub fn time_kset<V>(
+/// Times probing each element of the smaller set into an `EytzingerSet` of
+/// the larger set. Building the layout is not timed.
+pub fn time_eytzinger<V>(
+    harness: &mut Harness,
+    set_a: &[i32],
+    set_b: &[i32],
+    probe: EytzingerProbe<V>) -> Run
+where
+    V: Visitor<i32> + HarnessVisitor
+{
+    let (small, large) =
+        if set_a.len() <= set_b.len() { (set_a, set_b) } else { (set_b, set_a) };
+    let layout = harness.prepare(Some("eytzinger"), large, || EytzingerSet::from_sorted(large));
+
+    let run = |writer: &mut _| probe(small, &layout, writer);
+
+    with_output(harness.time_visitor::<V>(small.len(), run))
+}
+
+/// Times `bloom_galloping` with a filter of the larger set using
+/// `bits_per_element`. Building the filter is not timed.
+pub fn time_bloom<V>(
+    harness: &mut Harness,
+    set_a: &[i32],
+    set_b: &[i32],
+    bits_per_element: usize) -> Run
+where
+    V: Visitor<i32> + HarnessVisitor
+{
+    let (small, large) =
+        if set_a.len() <= set_b.len() { (set_a, set_b) } else { (set_b, set_a) };
+    let filter = harness.prepare(Some(&format!("bloom{bits_per_element}")), large, ||
+        BlockedBloom::with_bits_per_element(large, bits_per_element));
+
+    let run = |writer: &mut _| intersect::bloom_galloping(small, large, &filter, writer);
+
+    with_output(harness.time_visitor::<V>(small.len(), run))
+}
+
+/// Times `algorithm` on k sets. Preparing the sets is not timed.
+pub fn time_kset<A, V>(
     harness: &mut Harness,
     sets: &[DatafileSet],
-    intersect: IntersectK<DatafileSet, V>) -> RunResult
+    algorithm: &A) -> RunResult
 where
-    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + HarnessVisitor
+    A: KSetAlgorithm<i32, V>,
+    V: HarnessVisitor
 {
-    let capacity = sets.iter().map(|s| s.len()).min()
-        .ok_or_else(|| "cannot intersect 0 sets".to_string())?;
+    let capacity = kset_capacity(sets)?;
+    Ok(time_prepared_kset(harness, &algorithm.prepare(sets), capacity, algorithm))
+}
 
-    let prepare = || V::with_capacity(capacity);
-    let run = |writer: &mut _| intersect(sets, writer);
+/// Like `time_kset`, sharing the prepared sets with other algorithms of
+/// `family`.
+pub fn time_kset_in<A, V>(
+    harness: &mut Harness,
+    sets: &[DatafileSet],
+    algorithm: &A,
+    family: Option<&str>) -> RunResult
+where
+    A: KSetAlgorithm<i32, V>,
+    for<'s> A::Prepared<'s>: 'static,
+    V: HarnessVisitor
+{
+    let capacity = kset_capacity(sets)?;
+    let prepared = harness.prepare(family, sets, || algorithm.prepare(sets));
+    Ok(time_prepared_kset(harness, &*prepared, capacity, algorithm))
+}
 
-    let (elapsed, _writer) = harness.time(prepare, run);
+fn kset_capacity(sets: &[DatafileSet]) -> Result<usize, String> {
+    sets.iter().map(|s| s.len()).min()
+        .ok_or_else(|| "cannot intersect 0 sets".to_string())
+}
+
+fn time_prepared_kset<A, V>(
+    harness: &mut Harness,
+    prepared: &A::Prepared<'_>,
+    capacity: usize,
+    algorithm: &A) -> Run
+where
+    A: KSetAlgorithm<i32, V>,
+    V: HarnessVisitor
+{
+    let run = |writer: &mut _| algorithm.intersect(prepared, writer);
 
-    Ok(elapsed)
+    with_output(harness.time_visitor::<V>(capacity, run))
 }
 
+/// Times svs with `buffers`, which are kept between queries (i.e., calls) so
+/// allocation is left out of the measurement.
 pub fn time_svs<V>(
     harness: &mut Harness,
     sets: &[DatafileSet],
-    intersect: Intersect2<[i32], UnsafeWriter<i32>>) -> RunResult
+    buffers: &RefCell<SvsBuffers<V>>,
+    intersect: Intersect2<[i32], V>) -> RunResult
+where
+    V: Visitor<i32> + Clearable + Reservable + AsRef<[i32]>
 {
-    // Note: max() required here
-    let capacity = sets.iter().map(|s| s.len()).max()
-        .ok_or_else(|| "cannot intersect 0 sets".to_string())?;
+    if sets.len() < 2 {
+        return Err("svs requires at least 2 sets".to_string());
+    }
 
-    let prepare = || (
-        UnsafeWriter::with_capacity(capacity),
-        UnsafeWriter::with_capacity(capacity)
-    );
-    let run = |(left, right): &mut _| {
-        intersect::svs_generic(sets, left, right, intersect);
+    let prepare = || ();
+    let run = |_: &mut ()| {
+        intersect::svs_double_buffered(sets, &mut buffers.borrow_mut(), intersect);
     };
 
     let (elapsed, _) = harness.time(prepare, run);
 
-    Ok(elapsed)
+    Ok(harness.hash_output(elapsed, || {
+        let mut buffers = buffers.borrow_mut();
+        let result = intersect::svs_double_buffered(sets, &mut buffers, intersect);
+        util::slice_i32_to_u32(result.as_ref()).to_vec()
+    }))
 }
 
 pub fn time_svs_c(
@@ -190,9 +879,14 @@ pub fn time_svs_c(
 
     let (elapsed, _) = harness.time(prepare, run);
 
-    Ok(elapsed)
+    Ok(harness.hash_output(elapsed, || {
+        let (mut left, mut right) = prepare();
+        let result = intersect::svs_generic_c(sets, &mut left, &mut right, intersect);
+        util::slice_i32_to_u32(result).to_vec()
+    }))
 }
 
+#[cfg(feature = "roaring")]
 pub fn time_croaring_2set(
     harness: &mut Harness,
     set_a: &[i32],
@@ -202,15 +896,11 @@ pub fn time_croaring_2set(
 {
     use croaring::Bitmap;
 
-    let prepare = || {
-        let mut bitmap_a = Bitmap::of(util::slice_i32_to_u32(&set_a));
-        let mut bitmap_b = Bitmap::of(util::slice_i32_to_u32(&set_b));
-        if optimise {
-            bitmap_a.run_optimize();
-            bitmap_b.run_optimize();
-        }
-        (bitmap_a, bitmap_b)
-    };
+    // Intersecting in place consumes the first bitmap, so each run clones
+    // bitmaps which are only built once.
+    let built_a = prepare_croaring(harness, set_a, optimise);
+    let built_b = prepare_croaring(harness, set_b, optimise);
+    let prepare = || ((*built_a).clone(), (*built_b).clone());
     let run = if count_only {
         |(bitmap_a, bitmap_b): &mut (Bitmap, Bitmap)| {
             bitmap_a.and_inplace(&bitmap_b);
@@ -222,38 +912,142 @@ pub fn time_croaring_2set(
     };
 
     let (elapsed, _) = harness.time(prepare, run);
-    elapsed
+    harness.hash_output(elapsed, || built_a.and(&built_b).to_vec())
 }
 
+#[cfg(feature = "roaring")]
 pub fn time_croaring_svs(harness: &mut Harness, sets: &[DatafileSet], optimise: bool)
     -> Run
 {
     use croaring::Bitmap;
     assert!(sets.len() > 2);
 
-    let prepare = || {
-        let mut victim = Bitmap::of(util::slice_i32_to_u32(&sets[0]));
-        victim.run_optimize();
+    // The first bitmap is always run-optimised.
+    let victim = prepare_croaring(harness, &sets[0], true);
+    let rest: Vec<Rc<Bitmap>> = sets[1..].iter()
+        .map(|s| prepare_croaring(harness, s, optimise))
+        .collect();
 
-        let rest: Vec<Bitmap> = (&sets[1..]).iter()
-            .map(|s| {
-                let mut bitmap = Bitmap::of(util::slice_i32_to_u32(&s));
-                if optimise {
-                    bitmap.run_optimize();
-                }
-                bitmap
-            }).collect();
+    let prepare = || ((*victim).clone(), rest.clone());
+    let run = |(victim, rest): &mut (Bitmap, Vec<Rc<Bitmap>>)| {
+        for bitmap in rest.iter() {
+            victim.and_inplace(bitmap);
+        }
+    };
 
-        (victim, rest)
+    let (elapsed, (victim, _)) = harness.time(prepare, run);
+    harness.hash_output(elapsed, || victim.to_vec())
+}
+
+#[cfg(feature = "roaring")]
+fn prepare_croaring(harness: &Harness, set: &[i32], optimise: bool) -> Rc<croaring::Bitmap> {
+    let family = if optimise { "croaring_opt" } else { "croaring" };
+    harness.prepare(Some(family), set, || {
+        let mut bitmap = croaring::Bitmap::of(util::slice_i32_to_u32(set));
+        if optimise {
+            bitmap.run_optimize();
+        }
+        bitmap
+    })
+}
+
+// Building bitmaps over the universe of the largest element is not timed. The
+// result is written as a bitmap, or only counted with `count_only`.
+pub fn time_bitmap_2set(
+    harness: &mut Harness,
+    set_a: &[i32],
+    set_b: &[i32],
+    count_only: bool) -> Run
+{
+    let universe = bitmap_universe(&[set_a, set_b]);
+    let bitmap_a = prepare_bitmap(harness, set_a, universe);
+    let bitmap_b = prepare_bitmap(harness, set_b, universe);
+
+    if count_only {
+        let run = |_: &mut ()| { hint::black_box(bitmap::and_len(&bitmap_a, &bitmap_b)); };
+        harness.time(|| (), run).0
+    }
+    else {
+        let prepare = || Vec::with_capacity(bitmap_a.len());
+        let (elapsed, out) = harness.time(prepare, |out| bitmap::and_into(&bitmap_a, &bitmap_b, out));
+        harness.hash_output(elapsed, || bitmap::to_sorted_set(&out))
+    }
+}
+
+pub fn time_bitmap_kset(harness: &mut Harness, sets: &[DatafileSet]) -> Run {
+    let universe = bitmap_universe(sets);
+    let bitmaps: Vec<Rc<Vec<u64>>> = sets.iter()
+        .map(|s| prepare_bitmap(harness, s, universe))
+        .collect();
+
+    let prepare = || Vec::with_capacity(bitmaps[0].len());
+    let run = |out: &mut Vec<u64>| {
+        bitmap::and_into(&bitmaps[0], &bitmaps[1], out);
+        for bitmap in &bitmaps[2..] {
+            bitmap::and_inplace(out, bitmap);
+        }
     };
-    let run = |(victim, rest): &mut (Bitmap, Vec<Bitmap>)| {
-        for bitmap in rest {
-            victim.and_inplace(bitmap);
+
+    let (elapsed, out) = harness.time(prepare, run);
+    harness.hash_output(elapsed, || bitmap::to_sorted_set(&out))
+}
+
+// Bitmaps are as long as the universe of the query, so it is part of the key.
+fn prepare_bitmap(harness: &Harness, set: &[i32], universe: usize) -> Rc<Vec<u64>> {
+    harness.prepare(Some(&format!("bitmap{universe}")), set, ||
+        bitmap::from_sorted(util::slice_i32_to_u32(set), universe))
+}
+
+fn bitmap_universe<S: AsRef<[i32]>>(sets: &[S]) -> usize {
+    sets.iter()
+        .filter_map(|s| s.as_ref().last())
+        .map(|&max| max as u32 as usize + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+// Compressing the sets is not timed. The result is written as an EWAH bitmap,
+// or only counted with `count_only`.
+pub fn time_ewah_2set(
+    harness: &mut Harness,
+    set_a: &[i32],
+    set_b: &[i32],
+    count_only: bool) -> Run
+{
+    let ewah_a = prepare_ewah(harness, set_a);
+    let ewah_b = prepare_ewah(harness, set_b);
+
+    if count_only {
+        let run = |_: &mut ()| { hint::black_box(ewah_a.and_len(&ewah_b)); };
+        harness.time(|| (), run).0
+    }
+    else {
+        let (elapsed, out) = harness.time(EwahBitmap::new, |out| ewah_a.and_into(&ewah_b, out));
+        harness.hash_output(elapsed, || out.to_sorted_set())
+    }
+}
+
+pub fn time_ewah_kset(harness: &mut Harness, sets: &[DatafileSet]) -> Run {
+    let bitmaps: Vec<Rc<EwahBitmap>> = sets.iter()
+        .map(|s| prepare_ewah(harness, s))
+        .collect();
+
+    // Results alternate between two bitmaps, as EWAH cannot AND in place.
+    let prepare = || (EwahBitmap::new(), EwahBitmap::new());
+    let run = |(out, scratch): &mut (EwahBitmap, EwahBitmap)| {
+        bitmaps[0].and_into(&bitmaps[1], out);
+        for bitmap in &bitmaps[2..] {
+            out.and_into(bitmap, scratch);
+            std::mem::swap(out, scratch);
         }
     };
 
-    let (elapsed, _) = harness.time(prepare, run);
-    elapsed
+    let (elapsed, (out, _)) = harness.time(prepare, run);
+    harness.hash_output(elapsed, || out.to_sorted_set())
+}
+
+fn prepare_ewah(harness: &Harness, set: &[i32]) -> Rc<EwahBitmap> {
+    harness.prepare(Some("ewah"), set, || EwahBitmap::from_sorted(util::slice_i32_to_u32(set)))
 }
 
 // pub fn time_roaringrs_2set(harness: &Harness, set_a: &[i32], set_b: &[i32])
@@ -305,86 +1099,67 @@ pub fn time_croaring_svs(harness: &mut Harness, sets: &[DatafileSet], optimise:
 //     elapsed
 // }
 
-pub fn time_fesia<H, S, const LANES: usize, V>(
-    harness: &mut Harness,
-    set_a: &[i32],
-    set_b: &[i32],
-    hash_scale: HashScale,
-    intersect_method: FesiaTwoSetMethod,
-    simd_type: SimdType)
-    -> RunResult
-where
-    H: IntegerHash,
-    S: SimdElement + MaskElement,
-    LaneCount<LANES>: SupportedLaneCount,
-    Simd<S, LANES>: BitAnd<Output=Simd<S, LANES>> + SimdPartialEq<Mask=Mask<S, LANES>>,
-    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + HarnessVisitor
-{
-    let capacity = set_a.len().min(set_b.len());
-    assert!(set_a.len() <= set_b.len());
-
-    let set_a: Fesia<H, S, LANES> = Fesia::from_sorted(set_a, hash_scale);
-    let set_b: Fesia<H, S, LANES> = Fesia::from_sorted(set_b, hash_scale);
-
-    let prepare = || V::with_capacity(capacity);
-
-    use FesiaTwoSetMethod::*;
-    use SimdType::*;
+/// Checks that FESIA sets can be built from each of `sets` at `hash_scale`,
+/// as preparing them panics otherwise.
+pub fn check_fesia_sets<S: AsRef<[i32]>>(sets: &[S], hash_scale: HashScale) -> Result<(), String> {
+    for set in sets {
+        fesia::hash_size(set.as_ref().len(), hash_scale).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
 
-    let (elapsed, _) = match (intersect_method, simd_type) {
-        #[cfg(target_feature = "ssse3")]
-        (SimilarSize, Sse) => {
-            let run = |writer: &mut _| set_a.intersect::<V, SegmentIntersectSse>(&set_b, writer);
-            harness.time(prepare, run)
-        }
-        #[cfg(target_feature = "avx2")]
-        (SimilarSize, Avx2) => {
-            let run = |writer: &mut _| set_a.intersect::<V, SegmentIntersectAvx2>(&set_b, writer);
-            harness.time(prepare, run)
-        }
-        #[cfg(target_feature = "avx512f")]
-        (SimilarSize, Avx512) => {
-            let run = |writer: &mut _| set_a.intersect::<V, SegmentIntersectAvx512>(&set_b, writer);
-            harness.time(prepare, run)
-        }
-        #[allow(unreachable_patterns)]
-        (SimilarSize, width) =>
-            return Err(format!("fesia SimilarSize does not support {:?}", width)),
-        (Skewed, _) =>
-            harness.time(prepare, |writer: &mut _| set_a.hash_intersect(&set_b, writer)),
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(elapsed)
-}
+    #[test]
+    fn test_slice_output() {
+        let set_a: Vec<i32> = (0..1000).filter(|i| i % 3 == 0).collect();
+        let set_b: Vec<i32> = (0..1000).filter(|i| i % 5 == 0).collect();
+        let expected: Vec<i32> = (0..1000).filter(|i| i % 15 == 0).collect();
 
-pub fn time_fesia_kset<H, S, const LANES: usize, V>(
-    harness: &mut Harness,
-    sets: &[DatafileSet],
-    hash_scale: HashScale,
-    intersect_method: FesiaKSetMethod)
-    -> RunResult
-where
-    H: IntegerHash,
-    S: SimdElement + MaskElement,
-    LaneCount<LANES>: SupportedLaneCount,
-    Simd<S, LANES>: BitAnd<Output=Simd<S, LANES>> + SimdPartialEq<Mask=Mask<S, LANES>>,
-    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16 + HarnessVisitor
-{
-    let capacity = sets.iter().map(|s| s.len()).min()
-        .ok_or_else(|| "cannot intersect 0 sets".to_string())?;
+        let mut output = SliceOutput::with_capacity(set_b.len());
+        intersect::shuffling_sse(&set_a, &set_b, &mut output);
+        assert_eq!(output.as_ref(), expected);
 
-    let fesia_sets: Vec<Fesia<H, S, LANES>> = sets.iter()
-        .map(|s| Fesia::from_sorted(s, hash_scale))
-        .collect();
+        let stats = output.output_stats().unwrap();
+        assert_eq!(stats.len, expected.len());
+        assert_eq!(stats.capacity, set_b.len() + 16);
 
-    let prepare = || V::with_capacity(capacity);
+        output.clear();
+        intersect::branchless_merge(&set_a, &set_b, &mut output);
+        assert_eq!(output.as_ref(), expected);
+    }
 
-    use FesiaKSetMethod::*;
+    #[test]
+    fn test_prepared_cache() {
+        let set: Vec<i32> = (0..100).collect();
+        let other = set.clone();
+        let cache = PreparedCache::default();
+        let builds = std::cell::Cell::new(0);
+        let build = || { builds.set(builds.get() + 1); set.len() };
+
+        let first = cache.get_or_prepare("a", &set, build);
+        let again = cache.get_or_prepare("a", &set, build);
+        assert!(Rc::ptr_eq(&first, &again));
+        assert_eq!(builds.get(), 1);
+
+        // Other families, inputs and prepared types are kept apart.
+        cache.get_or_prepare("b", &set, build);
+        cache.get_or_prepare("a", &other, build);
+        cache.get_or_prepare("a", &set, || set.len() as u32);
+        assert_eq!(builds.get(), 3);
+        assert_eq!(cache.len(), 4);
+    }
 
-    let (elapsed, _) = match intersect_method {
-        SimilarSize => harness.time(prepare,
-            |writer: &mut _| Fesia::<H, S, LANES>::intersect_k(&fesia_sets, writer)),
-    };
+    #[test]
+    fn test_middle_range() {
+        let set_a: Vec<i32> = (100..200).collect();
+        let set_b: Vec<i32> = (150..300).collect();
 
-    Ok(elapsed)
+        assert_eq!(middle_range(&set_a, &set_b, PERCENT), 100..300);
+        assert_eq!(middle_range(&set_a, &set_b, PERCENT / 2), 150..250);
+        assert!(middle_range(&set_a, &set_b, 0).is_empty());
+        assert!(middle_range(&[], &[], PERCENT / 2).is_empty());
+    }
 }
@@ -0,0 +1,141 @@
+//! Distribution of per-iteration run times. Values are counted in log-linear
+//! buckets in the style of HdrHistogram: values below `2 * HALF` have their
+//! own bucket, and each following power of two is split into `HALF` buckets,
+//! bounding the relative error of recorded values to `1 / HALF`.
+
+use crate::schema::Latency;
+
+const SUB_BUCKET_BITS: u32 = 7;
+const HALF: u64 = 1 << (SUB_BUCKET_BITS - 1);
+
+#[derive(Debug, Default, Clone)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    total: u64,
+    max: u64,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, value: u64) {
+        let index = bucket_index(value);
+        if index >= self.counts.len() {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        self.total += 1;
+        self.max = self.max.max(value);
+    }
+
+    pub fn len(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// Returns the largest value equivalent to the `q`th quantile (0.0 to 1.0)
+    /// of recorded values, or 0 if none were recorded.
+    pub fn quantile(&self, q: f64) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.total as f64).ceil() as u64).max(1);
+
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return bucket_upper(index).min(self.max);
+            }
+        }
+        self.max
+    }
+
+    /// Non-empty buckets as (lowest value in bucket, count) pairs.
+    pub fn buckets(&self) -> Vec<(u64, u64)> {
+        self.counts.iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(index, &count)| (bucket_lower(index), count))
+            .collect()
+    }
+
+    /// Summarises the distribution for the results file, including `samples`
+    /// only if individual samples were requested.
+    pub fn to_latency(&self, samples: Vec<u64>) -> Latency {
+        Latency {
+            p50: self.quantile(0.50),
+            p95: self.quantile(0.95),
+            p99: self.quantile(0.99),
+            max: self.max,
+            histogram: self.buckets(),
+            samples,
+        }
+    }
+}
+
+fn bucket_index(value: u64) -> usize {
+    if value < 2 * HALF {
+        return value as usize;
+    }
+    let shift = u64::BITS - value.leading_zeros() - SUB_BUCKET_BITS;
+    (shift as u64 * HALF + (value >> shift)) as usize
+}
+
+fn bucket_shift(index: usize) -> u32 {
+    let index = index as u64;
+    if index < 2 * HALF { 0 } else { (index / HALF - 1) as u32 }
+}
+
+fn bucket_lower(index: usize) -> u64 {
+    let shift = bucket_shift(index);
+    (index as u64 - shift as u64 * HALF) << shift
+}
+
+fn bucket_upper(index: usize) -> u64 {
+    bucket_lower(index) + ((1 << bucket_shift(index)) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_bounds() {
+        for value in (0..100_000).chain([u32::MAX as u64, u64::MAX / 3, u64::MAX]) {
+            let index = bucket_index(value);
+            assert!(bucket_lower(index) <= value && value <= bucket_upper(index));
+            if value >= 2 * HALF {
+                let width = bucket_upper(index) - bucket_lower(index) + 1;
+                assert!(width <= value / HALF);
+            }
+        }
+    }
+
+    #[test]
+    fn test_quantiles() {
+        let mut histogram = Histogram::new();
+        assert_eq!(histogram.quantile(0.5), 0);
+
+        for value in 1..=1000 {
+            histogram.record(value);
+        }
+        assert_eq!(histogram.len(), 1000);
+
+        for (q, exact) in [(0.5, 500), (0.95, 950), (0.99, 990)] {
+            let estimate = histogram.quantile(q);
+            assert!(estimate >= exact && estimate <= exact + exact / HALF, "{q}: {estimate}");
+        }
+        assert_eq!(histogram.quantile(1.0), 1000);
+        assert_eq!(histogram.quantile(0.0), 1);
+
+        let buckets = histogram.buckets();
+        assert_eq!(buckets.iter().map(|(_, c)| c).sum::<u64>(), 1000);
+        assert!(buckets.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+}
@@ -144,9 +144,10 @@ impl PerfCounters {
         }
     }
 
-    pub fn new_result_run(&self, x: u32) -> schema::ResultRun {
+    pub fn new_result_run(&self, x: u32, y: Option<u32>) -> schema::ResultRun {
         schema::ResultRun {
             x: x,
+            y,
             times: Vec::default(),
             l1d: Self::new_cache_run(&self.l1d),
             l1i: Self::new_cache_run(&self.l1i),
@@ -158,6 +159,30 @@ impl PerfCounters {
             instructions: self.instructions.as_ref().map(|_| Vec::new()),
             cpu_cycles: self.cpu_cycles.as_ref().map(|_| Vec::new()),
             cpu_cycles_ref: self.cpu_cycles_ref.as_ref().map(|_| Vec::new()),
+            latency: None,
+            output_len: None,
+            output_capacity: None,
+            flush_times: None,
+            replications: None,
+            rse: None,
+            instances: None,
+            data_rse: None,
+            query_threads: None,
+            query_batch: None,
+            throughput: None,
+            input_bytes: None,
+            len_a: None,
+            len_b: None,
+            intersection_len: None,
+            comparisons: None,
+            vector_loads: None,
+            emitted_vectors: None,
+            gallops: None,
+            gallop_overshoot: None,
+            gallop_factor: None,
+            output_hashes: None,
+            frequency_khz: None,
+            frequency_drop: None,
         }
     }
 
@@ -222,9 +247,10 @@ impl PerfCounters {
         }
     }
 
-    pub fn new_result_run(&self, x: u32) -> schema::ResultRun {
+    pub fn new_result_run(&self, x: u32, y: Option<u32>) -> schema::ResultRun {
         schema::ResultRun {
             x: x,
+            y,
             times: Vec::default(),
             l1d: Self::new_cache_run(),
             l1i: Self::new_cache_run(),
@@ -236,6 +262,30 @@ impl PerfCounters {
             instructions: None,
             cpu_cycles: None,
             cpu_cycles_ref: None,
+            latency: None,
+            output_len: None,
+            output_capacity: None,
+            flush_times: None,
+            replications: None,
+            rse: None,
+            instances: None,
+            data_rse: None,
+            query_threads: None,
+            query_batch: None,
+            throughput: None,
+            input_bytes: None,
+            len_a: None,
+            len_b: None,
+            intersection_len: None,
+            comparisons: None,
+            vector_loads: None,
+            emitted_vectors: None,
+            gallops: None,
+            gallop_overshoot: None,
+            gallop_factor: None,
+            output_hashes: None,
+            frequency_khz: None,
+            frequency_drop: None,
         }
     }
 
@@ -0,0 +1,242 @@
+//! Workload traces: timestamped 2-set queries over the sets of a real
+//! dataset, replayed to measure sustained throughput on realistic query mixes
+//! rather than on isolated pairs.
+//!
+//! Traces are text files with one query per line, `<time_us> <set> <set>`,
+//! where sets are indices into the dataset's sets and times are microseconds
+//! since the start of the trace, in non-decreasing order. Lines starting with
+//! `#` are comments.
+
+use std::io::{self, BufRead, Write};
+use rand::{Rng, seq::SliceRandom};
+use serde::{Serialize, Deserialize};
+use crate::schema::Latency;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceQuery {
+    pub time_us: u64,
+    pub set_a: u32,
+    pub set_b: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub queries: Vec<TraceQuery>,
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a query, e.g., when recording the queries of an application.
+    /// Queries must be recorded in time order.
+    pub fn record(&mut self, time_us: u64, set_a: u32, set_b: u32) {
+        assert!(self.queries.last().is_none_or(|q| q.time_us <= time_us),
+            "queries must be recorded in time order");
+        self.queries.push(TraceQuery { time_us, set_a, set_b });
+    }
+
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+
+    /// Time of the last query, in microseconds.
+    pub fn duration_us(&self) -> u64 {
+        self.queries.last().map_or(0, |q| q.time_us)
+    }
+
+    pub fn read(reader: impl BufRead) -> Result<Self, String> {
+        let mut trace = Trace::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<u64> = line.split_whitespace()
+                .map(|f| f.parse::<u64>())
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+
+            let [time_us, set_a, set_b] = fields[..] else {
+                return Err(format!("line {}: expected <time_us> <set> <set>", i + 1));
+            };
+            if set_a > u32::MAX as u64 || set_b > u32::MAX as u64 {
+                return Err(format!("line {}: set index out of range", i + 1));
+            }
+            if trace.duration_us() > time_us {
+                return Err(format!("line {}: time goes backwards", i + 1));
+            }
+            trace.record(time_us, set_a as u32, set_b as u32);
+        }
+        Ok(trace)
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "# time_us set set")?;
+        for q in &self.queries {
+            writeln!(writer, "{} {} {}", q.time_us, q.set_a, q.set_b)?;
+        }
+        Ok(())
+    }
+
+    /// Checks every query refers to one of `set_count` sets.
+    pub fn validate(&self, set_count: usize) -> Result<(), String> {
+        let bad = self.queries.iter()
+            .position(|q| q.set_a as usize >= set_count || q.set_b as usize >= set_count);
+        match bad {
+            Some(i) => Err(format!(
+                "query {i} refers to a set beyond the dataset's {set_count} sets")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Samples ranks `0..n` where rank `r` has probability proportional to
+/// `1 / (r + 1)^exponent`.
+pub struct Zipf {
+    cdf: Vec<f64>,
+}
+
+impl Zipf {
+    pub fn new(n: usize, exponent: f64) -> Self {
+        assert!(n > 0, "zipf distribution needs at least one rank");
+        assert!(exponent >= 0.0, "zipf exponent must not be negative");
+
+        let mut cdf: Vec<f64> = Vec::with_capacity(n);
+        let mut total = 0.0;
+        for rank in 0..n {
+            total += 1.0 / ((rank + 1) as f64).powf(exponent);
+            cdf.push(total);
+        }
+        for p in &mut cdf {
+            *p /= total;
+        }
+        Self { cdf }
+    }
+
+    pub fn sample(&self, rng: &mut impl Rng) -> usize {
+        let u: f64 = rng.gen();
+        self.cdf.partition_point(|&p| p < u).min(self.cdf.len() - 1)
+    }
+}
+
+/// Synthesises a trace of `query_count` queries over `set_count` sets, where
+/// the popularity of sets follows a Zipf distribution with `exponent` (ranks
+/// are assigned to sets at random) and queries arrive as a Poisson process
+/// at `rate` queries per second.
+pub fn zipf_trace(
+    set_count: usize,
+    query_count: usize,
+    exponent: f64,
+    rate: f64,
+    rng: &mut impl Rng) -> Trace
+{
+    assert!(set_count >= 2, "a trace needs at least two sets");
+    assert!(rate > 0.0, "query rate must be positive");
+
+    let zipf = Zipf::new(set_count, exponent);
+    let mut by_rank: Vec<u32> = (0..set_count as u32).collect();
+    by_rank.shuffle(rng);
+
+    let mut trace = Trace::new();
+    let mut time_s = 0.0;
+    for _ in 0..query_count {
+        let set_a = by_rank[zipf.sample(rng)];
+        let set_b = loop {
+            let set = by_rank[zipf.sample(rng)];
+            if set != set_a {
+                break set;
+            }
+        };
+        trace.record((time_s * 1e6) as u64, set_a, set_b);
+
+        // Exponentially distributed gaps between arrivals.
+        let u: f64 = rng.gen();
+        time_s += -(1.0 - u).ln() / rate;
+    }
+    trace
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReplayResults {
+    pub trace: String,
+    pub source: String,
+    /// Whether queries were issued at their trace times (open loop), rather
+    /// than back to back (closed loop).
+    pub paced: bool,
+    pub runs: Vec<ReplayRun>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReplayRun {
+    pub algorithm: String,
+    pub queries: usize,
+    pub elapsed_ns: u64,
+    pub queries_per_second: f64,
+    /// Total size of all query results.
+    pub result_len: u64,
+    /// Per-query latency. When paced, this is measured from the query's
+    /// trace time so it includes time spent queued behind earlier queries.
+    pub latency: Latency,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn test_trace_roundtrip() {
+        let mut trace = Trace::new();
+        trace.record(0, 1, 2);
+        trace.record(10, 3, 0);
+        trace.record(10, 2, 2);
+
+        let mut text = Vec::new();
+        trace.write(&mut text).unwrap();
+        assert_eq!(Trace::read(text.as_slice()).unwrap(), trace);
+
+        assert!(trace.validate(4).is_ok());
+        assert!(trace.validate(3).is_err());
+
+        assert!(Trace::read("5 1 2\n4 1 2\n".as_bytes()).unwrap_err().contains("backwards"));
+        assert!(Trace::read("5 1\n".as_bytes()).unwrap_err().contains("line 1"));
+        assert!(Trace::read("# comment\n\n5 x 2\n".as_bytes()).unwrap_err().contains("line 3"));
+    }
+
+    #[test]
+    fn test_zipf() {
+        let rng = &mut StdRng::seed_from_u64(1);
+        let zipf = Zipf::new(100, 1.0);
+
+        let mut counts = [0usize; 100];
+        for _ in 0..100_000 {
+            counts[zipf.sample(rng)] += 1;
+        }
+        // Rank 0 is about twice as popular as rank 1 and ten times rank 9.
+        assert!((1.8..2.2).contains(&(counts[0] as f64 / counts[1] as f64)));
+        assert!((8.0..12.0).contains(&(counts[0] as f64 / counts[9] as f64)));
+
+        let uniform = Zipf::new(4, 0.0);
+        assert!((0..1000).all(|_| uniform.sample(rng) < 4));
+    }
+
+    #[test]
+    fn test_zipf_trace() {
+        let rng = &mut StdRng::seed_from_u64(2);
+        let trace = zipf_trace(50, 10_000, 1.0, 1000.0, rng);
+
+        assert_eq!(trace.len(), 10_000);
+        assert!(trace.validate(50).is_ok());
+        assert!(trace.queries.iter().all(|q| q.set_a != q.set_b));
+        // 10000 queries at 1000 per second take about 10 seconds.
+        assert!((9_000_000..11_000_000).contains(&trace.duration_us()));
+    }
+}
@@ -0,0 +1,184 @@
+//! Checks that two results files (e.g., from machines with different ISAs)
+//! agree on the results of their algorithms, not only on their times. Run with
+//! --hash-outputs, the benchmark records a hash of each intersection per
+//! datafile (see `hash_set`), so a SIMD path which silently miscompares on one
+//! machine shows up as a hash differing from the other machine's.
+//!
+//! A cell, the runs of one algorithm at one (x, y) point of a dataset, is
+//! checked against the same algorithm in the baseline. An algorithm the
+//! baseline lacks, e.g., an AVX-512 kernel checked against a baseline from an
+//! AVX2 machine, is checked against the first baseline algorithm with hashes
+//! at that point instead, as every algorithm intersects the same datafiles.
+
+use crate::schema::{AlgorithmId, AlgorithmResults, DatasetId, DatasetResults, OutputMode, Results, ResultRun};
+
+/// Hash of a set which does not depend on the order of its elements, as FESIA
+/// and the hash kernels visit results out of order.
+pub fn hash_set(elements: impl IntoIterator<Item = u32>) -> u64 {
+    let (sum, len) = elements.into_iter()
+        .fold((0u64, 0u64), |(sum, len), item| (sum.wrapping_add(mix(item as u64)), len + 1));
+    mix(sum ^ mix(len))
+}
+
+// The finaliser of splitmix64, offset so that 0 does not hash to 0.
+fn mix(value: u64) -> u64 {
+    let mut x = value.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellVerification {
+    pub dataset: DatasetId,
+    /// Output of the experiment, if it set one.
+    pub output: Option<OutputMode>,
+    pub algorithm: AlgorithmId,
+    /// Baseline algorithm checked against: `algorithm`, unless the baseline
+    /// lacks it.
+    pub reference: AlgorithmId,
+    pub x: u32,
+    pub y: Option<u32>,
+    /// Samples (i.e., datafiles) hashed in both files.
+    pub compared: usize,
+    /// Samples whose hashes differ.
+    pub mismatches: Vec<usize>,
+}
+
+/// Checks each cell of `candidate` with hashes against `baseline`, in order of
+/// dataset, output, algorithm and point. Cells without a reference in the
+/// baseline are left out.
+pub fn verify(baseline: &Results, candidate: &Results) -> Vec<CellVerification> {
+    let mut cells = Vec::new();
+
+    let mut datasets: Vec<&DatasetId> = candidate.datasets.keys()
+        .filter(|d| baseline.datasets.contains_key(*d))
+        .collect();
+    datasets.sort();
+
+    for dataset in datasets {
+        let references = hashed_runs(&baseline.datasets[dataset]);
+
+        for (output, algos) in groups(&candidate.datasets[dataset]) {
+            let mut algorithms: Vec<&AlgorithmId> = algos.keys().collect();
+            algorithms.sort();
+
+            for algorithm in algorithms {
+                for run in &algos[algorithm] {
+                    let Some(hashes) = &run.output_hashes else {
+                        continue;
+                    };
+                    let at_point = |(_, r): &&(&AlgorithmId, &ResultRun)| r.x == run.x && r.y == run.y;
+                    let Some((reference, reference_run)) = references.iter()
+                        .filter(at_point)
+                        .find(|(a, _)| *a == algorithm)
+                        .or_else(|| references.iter().find(at_point))
+                    else {
+                        continue;
+                    };
+                    let reference_hashes = reference_run.output_hashes.as_ref().expect("hashed");
+
+                    let compared = hashes.len().min(reference_hashes.len());
+                    let mismatches = (0..compared)
+                        .filter(|&i| hashes[i] != reference_hashes[i])
+                        .collect();
+
+                    cells.push(CellVerification {
+                        dataset: dataset.clone(),
+                        output,
+                        algorithm: algorithm.clone(),
+                        reference: (*reference).clone(),
+                        x: run.x,
+                        y: run.y,
+                        compared,
+                        mismatches,
+                    });
+                }
+            }
+        }
+    }
+    cells
+}
+
+// Runs of experiments without an output, then those of each output.
+fn groups(results: &DatasetResults) -> Vec<(Option<OutputMode>, &AlgorithmResults)> {
+    let mut outputs: Vec<_> = results.outputs.iter()
+        .map(|(output, algos)| (Some(*output), algos))
+        .collect();
+    outputs.sort_by_key(|(output, _)| *output);
+    std::iter::once((None, &results.algos)).chain(outputs).collect()
+}
+
+// Every run with hashes, whatever its output as outputs do not change results.
+fn hashed_runs(results: &DatasetResults) -> Vec<(&AlgorithmId, &ResultRun)> {
+    let mut runs = Vec::new();
+    for (_, algos) in groups(results) {
+        let mut algorithms: Vec<&AlgorithmId> = algos.keys().collect();
+        algorithms.sort();
+        for algorithm in algorithms {
+            runs.extend(algos[algorithm].iter()
+                .filter(|r| r.output_hashes.is_some())
+                .map(|r| (algorithm, r)));
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(hashes: &[(&str, &[u64])]) -> Results {
+        let algos: Vec<String> = hashes.iter()
+            .map(|(algorithm, hashes)| format!(
+                r#""{algorithm}": [{{"x": 2, "times": [1, 1], "output_hashes": {hashes:?},
+                    "l1d": {{}}, "l1i": {{}}, "ll": {{}}}}]"#))
+            .collect();
+
+        crate::migrate::parse_results(&format!(r#"{{
+            "experiments": [],
+            "algorithm_sets": {{}},
+            "datasets": {{"d": {{
+                "info": {{"name": "d", "type": "real", "source": "webdocs",
+                    "gen_count": 2, "set_count_start": 2, "set_count_end": 2}},
+                "algos": {{{}}}
+            }}}}
+        }}"#, algos.join(","))).unwrap()
+    }
+
+    #[test]
+    fn test_hash_set() {
+        assert_eq!(hash_set([3, 1, 2]), hash_set([1, 2, 3]));
+        assert_ne!(hash_set([1, 2, 3]), hash_set([1, 2, 4]));
+        assert_ne!(hash_set([]), hash_set([0]));
+        assert_ne!(hash_set([0]), hash_set([0, 0]));
+    }
+
+    #[test]
+    fn test_verify() {
+        let baseline = results(&[
+            ("merge", &[10, 20]),
+            ("shuffling_sse", &[10, 20]),
+        ]);
+        let candidate = results(&[
+            ("merge", &[10, 20]),
+            ("shuffling_sse", &[10, 21]),
+            ("shuffling_avx512", &[11, 20]),
+        ]);
+
+        let cells = verify(&baseline, &candidate);
+        let names: Vec<(&str, &str)> = cells.iter()
+            .map(|c| (c.algorithm.as_str(), c.reference.as_str()))
+            .collect();
+        assert_eq!(names, [
+            ("merge", "merge"),
+            ("shuffling_avx512", "merge"),
+            ("shuffling_sse", "shuffling_sse"),
+        ]);
+
+        assert!(cells.iter().all(|c| c.compared == 2));
+        assert_eq!(cells[0].mismatches, Vec::<usize>::new());
+        assert_eq!(cells[1].mismatches, [0]);
+        assert_eq!(cells[2].mismatches, [1]);
+    }
+}
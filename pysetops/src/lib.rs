@@ -0,0 +1,140 @@
+//! Python bindings for the `setops` kernels over numpy arrays, so that scripts
+//! can check results against the exact kernels being benchmarked. Requires the
+//! `python` feature; build and install into the current environment with
+//! `maturin develop --release` in this directory.
+//!
+//! ```python
+//! import numpy as np, pysetops
+//! a = np.array([1, 3, 5, 7], dtype=np.int32)
+//! b = np.array([3, 4, 5], dtype=np.int32)
+//! pysetops.intersect(a, b)                     # array([3, 5])
+//! pysetops.count(a, b, "shuffling_avx2")       # 2
+//! pysetops.union(a, b)                         # array([1, 3, 4, 5, 7])
+//! ```
+//!
+//! Arrays must be one-dimensional, contiguous, `int32` or `uint32`, sorted
+//! and free of duplicates. Without an algorithm name the kernel is chosen as
+//! in `setops::simple`, i.e., the widest SIMD kernel of the build; named
+//! algorithms (see `algorithms()`) take `int32` arrays.
+
+#![cfg(feature = "python")]
+
+use numpy::{IntoPyArray, PyReadonlyArray1};
+use pyo3::{
+    prelude::*,
+    exceptions::{PyTypeError, PyValueError},
+};
+use setops::{
+    intersect::{dynamic, Intersect2Dyn},
+    simple,
+    util::check_strictly_sorted,
+};
+
+#[derive(FromPyObject)]
+enum Set<'py> {
+    I32(PyReadonlyArray1<'py, i32>),
+    U32(PyReadonlyArray1<'py, u32>),
+}
+
+/// Elements common to `a` and `b`, using the algorithm named `algorithm` if
+/// given.
+#[pyfunction]
+#[pyo3(signature = (a, b, algorithm=None))]
+fn intersect<'py>(
+    py: Python<'py>,
+    a: Set<'py>,
+    b: Set<'py>,
+    algorithm: Option<&str>) -> PyResult<Bound<'py, PyAny>>
+{
+    match (a, b) {
+        (Set::I32(a), Set::I32(b)) => {
+            let (a, b) = (as_set(&a)?, as_set(&b)?);
+            let result = match algorithm {
+                Some(name) => lookup(name)?.intersect(a, b),
+                None => simple::intersect(a, b),
+            };
+            Ok(result.into_pyarray(py).into_any())
+        },
+        (Set::U32(a), Set::U32(b)) => {
+            no_algorithm(algorithm)?;
+            Ok(simple::intersect(as_set(&a)?, as_set(&b)?).into_pyarray(py).into_any())
+        },
+        _ => Err(mixed_types()),
+    }
+}
+
+/// Number of elements common to `a` and `b`, using the algorithm named
+/// `algorithm` if given.
+#[pyfunction]
+#[pyo3(signature = (a, b, algorithm=None))]
+fn count(a: Set<'_>, b: Set<'_>, algorithm: Option<&str>) -> PyResult<usize> {
+    match (a, b) {
+        (Set::I32(a), Set::I32(b)) => {
+            let (a, b) = (as_set(&a)?, as_set(&b)?);
+            Ok(match algorithm {
+                Some(name) => lookup(name)?.count(a, b),
+                None => simple::count(a, b),
+            })
+        },
+        (Set::U32(a), Set::U32(b)) => {
+            no_algorithm(algorithm)?;
+            Ok(simple::count(as_set(&a)?, as_set(&b)?))
+        },
+        _ => Err(mixed_types()),
+    }
+}
+
+/// Elements of `a`, `b` or both.
+#[pyfunction]
+fn union<'py>(py: Python<'py>, a: Set<'py>, b: Set<'py>) -> PyResult<Bound<'py, PyAny>> {
+    match (a, b) {
+        (Set::I32(a), Set::I32(b)) =>
+            Ok(simple::union(as_set(&a)?, as_set(&b)?).into_pyarray(py).into_any()),
+        (Set::U32(a), Set::U32(b)) =>
+            Ok(simple::union(as_set(&a)?, as_set(&b)?).into_pyarray(py).into_any()),
+        _ => Err(mixed_types()),
+    }
+}
+
+/// Names of the algorithms available to `intersect` and `count` in this
+/// build.
+#[pyfunction]
+fn algorithms() -> Vec<&'static str> {
+    dynamic::names()
+}
+
+#[pymodule]
+fn pysetops(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(intersect, m)?)?;
+    m.add_function(wrap_pyfunction!(count, m)?)?;
+    m.add_function(wrap_pyfunction!(union, m)?)?;
+    m.add_function(wrap_pyfunction!(algorithms, m)?)?;
+    Ok(())
+}
+
+fn as_set<'a, T>(array: &'a PyReadonlyArray1<'_, T>) -> PyResult<&'a [T]>
+where
+    T: numpy::Element + Ord,
+{
+    let set = array.as_slice()
+        .map_err(|_| PyValueError::new_err("array must be contiguous"))?;
+    check_strictly_sorted(set)
+        .map_err(|e| PyValueError::new_err(format!("array must be sorted and free of duplicates: {e}")))?;
+    Ok(set)
+}
+
+fn lookup(name: &str) -> PyResult<Box<dyn Intersect2Dyn>> {
+    dynamic::by_name(name)
+        .ok_or_else(|| PyValueError::new_err(format!("unknown algorithm {name}")))
+}
+
+fn no_algorithm(algorithm: Option<&str>) -> PyResult<()> {
+    match algorithm {
+        Some(_) => Err(PyTypeError::new_err("named algorithms take int32 arrays")),
+        None => Ok(()),
+    }
+}
+
+fn mixed_types() -> PyErr {
+    PyTypeError::new_err("arrays must both be int32 or both be uint32")
+}
@@ -0,0 +1,85 @@
+# Checks the bindings against Python sets on random inputs. Run with
+# `pytest tests` after `maturin develop --release`.
+import random
+
+import numpy as np
+import pytest
+
+import pysetops
+
+SEEDS = range(20)
+DTYPES = [np.int32, np.uint32]
+
+
+def random_pair(seed, dtype):
+    rng = random.Random(seed)
+    span = rng.choice([64, 1024, 1 << 20])
+    # Ranges cross 0 for int32 and may cross 2^31 for uint32, where the
+    # signed and unsigned orders differ.
+    if dtype == np.int32:
+        low = -(span // 2)
+    else:
+        low = rng.choice([0, 2**31 - span // 2])
+
+    def sample():
+        return sorted(rng.sample(range(low, low + span), rng.randint(0, min(span, 2000))))
+    return sample(), sample()
+
+
+@pytest.mark.parametrize("dtype", DTYPES)
+@pytest.mark.parametrize("seed", SEEDS)
+def test_matches_python_sets(seed, dtype):
+    a, b = random_pair(seed, dtype)
+    array_a, array_b = np.array(a, dtype=dtype), np.array(b, dtype=dtype)
+
+    expected = sorted(set(a) & set(b))
+    result = pysetops.intersect(array_a, array_b)
+    assert result.dtype == dtype
+    assert result.tolist() == expected
+    assert pysetops.count(array_a, array_b) == len(expected)
+
+    union = pysetops.union(array_a, array_b)
+    assert union.dtype == dtype
+    assert union.tolist() == sorted(set(a) | set(b))
+
+
+@pytest.mark.parametrize("seed", SEEDS)
+def test_named_algorithms(seed):
+    a, b = random_pair(seed, np.int32)
+    array_a, array_b = np.array(a, dtype=np.int32), np.array(b, dtype=np.int32)
+    expected = sorted(set(a) & set(b))
+
+    for name in pysetops.algorithms():
+        assert pysetops.intersect(array_a, array_b, name).tolist() == expected, name
+        assert pysetops.count(array_a, array_b, name) == len(expected), name
+
+
+@pytest.mark.parametrize("dtype", DTYPES)
+@pytest.mark.parametrize("function", [pysetops.intersect, pysetops.count, pysetops.union])
+@pytest.mark.parametrize("invalid", [[3, 1, 2], [1, 2, 2, 3]])
+def test_rejects_unsorted(dtype, function, invalid):
+    valid = np.array([1, 2, 3], dtype=dtype)
+    invalid = np.array(invalid, dtype=dtype)
+
+    with pytest.raises(ValueError, match="sorted"):
+        function(invalid, valid)
+    with pytest.raises(ValueError, match="sorted"):
+        function(valid, invalid)
+
+
+def test_rejects_non_contiguous():
+    a = np.arange(10, dtype=np.int32)[::2]
+    with pytest.raises(ValueError, match="contiguous"):
+        pysetops.intersect(a, a)
+
+
+def test_rejects_mixed_and_unnamed_types():
+    signed = np.array([1, 2], dtype=np.int32)
+    unsigned = np.array([1, 2], dtype=np.uint32)
+
+    with pytest.raises(TypeError):
+        pysetops.intersect(signed, unsigned)
+    with pytest.raises(TypeError):
+        pysetops.count(unsigned, unsigned, "naive_merge")
+    with pytest.raises(ValueError, match="unknown algorithm"):
+        pysetops.count(signed, signed, "no_such_algorithm")
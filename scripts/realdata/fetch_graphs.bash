@@ -8,3 +8,5 @@ wget -nc "$TWITTER" -P "$DATASETS"
 wget -nc "$SKITTER" -P "$DATASETS"
 gunzip -dkcv "$DATASETS/twitter_combined.txt.gz" | $(dirname "$0")/process_graph.py > "$DATASETS/twitter.dat"
 gunzip -dkcv "$DATASETS/as-skitter.txt.gz" | $(dirname "$0")/process_graph.py > "$DATASETS/as-skitter.dat"
+gunzip -dkcv "$DATASETS/twitter_combined.txt.gz" | $(dirname "$0")/process_graph.py --by-vertex > "$DATASETS/twitter-graph.dat"
+gunzip -dkcv "$DATASETS/as-skitter.txt.gz" | $(dirname "$0")/process_graph.py --by-vertex --undirected > "$DATASETS/as-skitter-graph.dat"
@@ -1,10 +1,21 @@
 #!/usr/bin/env python3
+# Converts an edge list into one adjacency list per line.
+#
+# --by-vertex: relabel vertices 0..n-1 and write the adjacency list of vertex i
+#              on line i (possibly empty), as required by `query = "clique"`.
+#              Otherwise only non-empty lists are written, largest first.
+# --undirected: add each edge in both directions.
 import sys
 
-if len(sys.argv) <= 1:
+flags = [arg for arg in sys.argv[1:] if arg.startswith("--")]
+paths = [arg for arg in sys.argv[1:] if not arg.startswith("--")]
+by_vertex = "--by-vertex" in flags
+undirected = "--undirected" in flags
+
+if len(paths) == 0:
     file = sys.stdin
 else:
-    file = open(sys.argv[1], "r")
+    file = open(paths[0], "r")
 
 adj = {}
 
@@ -15,6 +26,17 @@ for line in file:
     start = int(segments[0])
     end = int(segments[1])
     adj.setdefault(start, []).append(end)
+    if undirected:
+        adj.setdefault(end, []).append(start)
+    elif by_vertex:
+        adj.setdefault(end, [])
+
+if by_vertex:
+    labels = {vertex: label for label, vertex in enumerate(sorted(adj.keys()))}
+    for vertex in sorted(adj.keys()):
+        adj_list = sorted(set(labels[v] for v in adj[vertex]))
+        print(' '.join(str(i) for i in adj_list))
+    sys.exit()
 
 adj_lists = []
 
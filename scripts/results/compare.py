@@ -31,11 +31,19 @@ def process_results(experiment, results):
         algorithms = experiment["algorithms"]
     dataset = results["datasets"][experiment["dataset"]]
     info = dataset["info"]
+    if "output" in experiment:
+        algos = dataset["outputs"][experiment["output"]]
+    else:
+        algos = dataset["algos"]
+
+    unsupported = results.get("unsupported", {})
 
     times = {}
     for algorithm in algorithms:
+        if algorithm in unsupported:
+            continue
         algorithm_times = []
-        for xrec in dataset["algos"][algorithm]:
+        for xrec in algos[algorithm]:
             if len(xrec["times"]) > 0:
                 algorithm_times.append(sum(xrec["times"]) / len(xrec["times"]))
         
@@ -30,6 +30,7 @@ COL_TITLES = {
     "size": "Size",
     "skewness_factor": "Skew",
     "set_count": "Set Count",
+    "len_offset": "Length Offset",
     "element_count": "Element Count",
     "element_bytes": "Total size of all sets (bytes)",
     "element_bytes_pow": "Total size of all sets (bytes)",
@@ -85,6 +86,7 @@ def main():
     parser.add_argument("out_path", type=str, help="Path to output file")
     parser.add_argument("--y_vs_x", action="store_true", help="Plot column y vs column x")
     parser.add_argument("--bars_per_alg", action="store_true", help="Plot bars for column per algorithm")
+    parser.add_argument("--heatmap", action="store_true", help="Plot column z over columns x and y per algorithm (2D sweeps)")
     parser.add_argument("--cols", type=str, nargs="+", help="Columns to plot")
     parser.add_argument("--xvalues", type=int, nargs="+", help="X values to plot")
     args = parser.parse_args()
@@ -106,6 +108,11 @@ def main():
         args.cols is not None and len(args.cols) >= 1 or fail("Expected at least one argument for cols")
         fig = plot_bars_per_algorithm(all_results, args.cols, args.xvalues)
 
+    elif args.heatmap:
+        args.cols is not None and len(args.cols) == 3 or fail("Expected three columns (x, y, z) for heatmap")
+        x_col, y_col, z_col = args.cols
+        fig = plot_heatmap(all_results, x_col, y_col, z_col)
+
     else:
         fail("No plot type specified")
 
@@ -137,6 +144,29 @@ def plot_y_vs_x(all_results, x_col, y_col):
     ax.legend()
     return fig
 
+def plot_heatmap(all_results, x_col, y_col, z_col):
+    algorithms = list(all_results.keys())
+    fig, axes = plt.subplots(1, len(algorithms), squeeze=False,
+                             figsize=(5 * len(algorithms), 4))
+
+    x_formatter = col_formatter(x_col)
+    y_formatter = col_formatter(y_col)
+
+    for ax, alg in zip(axes[0], algorithms):
+        grid = all_results[alg].pivot_table(index=y_col, columns=x_col, values=z_col)
+
+        image = ax.imshow(grid.values, origin="lower", aspect="auto")
+        fig.colorbar(image, ax=ax, label=col_title(z_col))
+
+        ax.set_title(ALGORITHMS.get(alg) or alg)
+        ax.set_xlabel(col_title(x_col))
+        ax.set_ylabel(col_title(y_col))
+        ax.set_xticks(range(len(grid.columns)), [x_formatter(x) for x in grid.columns])
+        ax.set_yticks(range(len(grid.index)), [y_formatter(y) for y in grid.index])
+
+    fig.tight_layout()
+    return fig
+
 def plot_bars_per_algorithm(all_results, columns, row_indices):
 
     def get_point_results(row_idx):
@@ -1,4 +1,6 @@
 #!/usr/bin/env python3
+# Superseded by `benchmark process`, which writes the same per-experiment,
+# per-algorithm summaries with confidence intervals from the results schema.
 import sys
 import json
 import os
@@ -35,38 +37,66 @@ def process_results(experiment, results):
     
     dataset_results = results["datasets"][experiment["dataset"]]
     info = dataset_results["info"]
+    # Experiments choosing their own output are kept apart from the rest.
+    if "output" in experiment:
+        algos = dataset_results["outputs"][experiment["output"]]
+    else:
+        algos = dataset_results["algos"]
+
+    unsupported = results.get("unsupported", {})
 
     results_per_alg = {}
     for algorithm in algorithms:
+        if algorithm in unsupported:
+            continue
         df = pd.DataFrame()
 
-        alg_results = dataset_results["algos"][algorithm]
+        alg_results = algos[algorithm]
         xvalues = [row["x"] for row in alg_results]
+        yvalues = [row.get("y") for row in alg_results]
+        vary_y = info.get("vary_y", {}).get("vary")
+
+        def parameter_values(parameter, key):
+            if info["vary"] == parameter:
+                return xvalues
+            if vary_y == parameter:
+                return yvalues
+            return [info.get(key, 0)] * len(xvalues)
+
+        df["x"] = xvalues
+        if vary_y is not None:
+            df["y"] = yvalues
 
         df["time_ns"] = [sum(row["times"]) / len(row["times"]) for row in alg_results]
         df["time_ns_std"] = [np.std(row["times"]) for row in alg_results]
 
-        df["selectivity"] = xvalues if info["vary"] == "selectivity" else [info["selectivity"]] * len(xvalues)
+        df["selectivity"] = parameter_values("selectivity", "selectivity")
         df["selectivity"] = df["selectivity"] / 1000
 
-        df["density"] = xvalues if info["vary"] == "density" else [info["density"]] * len(xvalues)
+        df["density"] = parameter_values("density", "density")
         df["density"] = df["density"] / 1000
 
-        df["skewness_factor"] = xvalues if info["vary"] == "skew" else [info["skewness_factor"]] * len(xvalues)
+        df["skewness_factor"] = parameter_values("skew", "skewness_factor")
         df["skewness_factor"] = df["skewness_factor"] / 1000
 
-        df["max_len_pow"] = xvalues if info["vary"] == "size" else [info["max_len"]] * len(xvalues)
+        df["max_len_pow"] = parameter_values("size", "max_len")
         df["max_len"] = 2 ** df["max_len_pow"]
-        df["set_count"] = xvalues if info["vary"] == "set_count" else [info["set_count"]] * len(xvalues)
+        df["set_count"] = parameter_values("set_count", "set_count")
+        df["len_offset"] = parameter_values("len_offset", "len_offset")
+
+        # With a fixed universe the density is that of the largest set in it.
+        if info.get("universe") is not None:
+            df["density"] = (df["max_len"] + df["len_offset"]) / info["universe"]
 
-        def elements_in(set_idx, skewness_factor, max_len):
-            return int(max_len / pow(set_idx+1, skewness_factor))
+        def elements_in(set_idx, skewness_factor, max_len, len_offset):
+            return int(max_len / pow(set_idx+1, skewness_factor)) + len_offset
         
         def element_count(row):
             skew = row["skewness_factor"]
             max_len = row["max_len"]
+            len_offset = int(row["len_offset"])
             set_count = int(row["set_count"])
-            return sum([elements_in(set_idx, skew, max_len) for set_idx in range(set_count)])
+            return sum([elements_in(set_idx, skew, max_len, len_offset) for set_idx in range(set_count)])
 
         df["element_count"] = df.apply(element_count, axis=1)
         df["element_bytes"] = df["element_count"] * ELEMENT_BYTES
@@ -91,6 +121,10 @@ def process_results(experiment, results):
                      "instructions", "cpu_cycles", "cpu_cycles_ref"]:
             df = add_cpu_stat(df, alg_results, f"{stat}", lambda row: row[stat])
 
+        if all("latency" in row for row in alg_results):
+            for stat in ["p50", "p95", "p99", "max"]:
+                df[f"latency_{stat}_ns"] = [row["latency"][stat] for row in alg_results]
+
         df["branch_miss_rate"] = df["branch_misses"] / df["branches"]
         df["ipc"] = df["instructions"] / df["cpu_cycles"]
         df["cpi"] = df["cpu_cycles"] / df["instructions"]
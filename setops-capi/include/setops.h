@@ -0,0 +1,81 @@
+/*
+ * C interface to the setops intersection kernels. Link against
+ * libsetops_capi (built by `cargo build --release -p setops-capi`).
+ *
+ * All sets are sorted, deduplicated arrays of int32_t. A pointer may be null
+ * only if its length is 0. Functions returning ptrdiff_t return a count, or a
+ * negative SETOPS_ERROR_* code.
+ */
+#ifndef SETOPS_H
+#define SETOPS_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SETOPS_ABI_VERSION 1
+
+#define SETOPS_ERROR_NULL              (-1)
+#define SETOPS_ERROR_UNKNOWN_ALGORITHM (-2)
+#define SETOPS_ERROR_CAPACITY          (-3)
+#define SETOPS_ERROR_TOO_FEW_SETS      (-4)
+
+#define SETOPS_FEATURE_SSSE3    (1u << 0)
+#define SETOPS_FEATURE_AVX2     (1u << 1)
+#define SETOPS_FEATURE_AVX512F  (1u << 2)
+#define SETOPS_FEATURE_AVX512CD (1u << 3)
+
+/* Called with `context` for each element of a result, in ascending order. */
+typedef void (*setops_visit_fn)(void *context, int32_t value);
+
+/* SETOPS_ABI_VERSION of the library, to check against this header. */
+uint32_t setops_abi_version(void);
+
+/* SETOPS_FEATURE_* flags the library was built with, which determine the
+ * available kernels, and those of the running CPU. */
+uint32_t setops_build_features(void);
+uint32_t setops_cpu_features(void);
+
+/* Whether the 2-set algorithm `name` (e.g. "shuffling_avx2") is available. */
+bool setops_has_algorithm(const char *name);
+
+/* Writes the intersection of `a` and `b` to `out` using the 2-set algorithm
+ * `name`. `out_capacity` must be at least the size of the result; the length
+ * of the smaller set always suffices. */
+ptrdiff_t setops_intersect(
+    const char *name,
+    const int32_t *a, size_t len_a,
+    const int32_t *b, size_t len_b,
+    int32_t *out, size_t out_capacity);
+
+/* Size of the intersection of `a` and `b` using the 2-set algorithm `name`. */
+ptrdiff_t setops_count(
+    const char *name,
+    const int32_t *a, size_t len_a,
+    const int32_t *b, size_t len_b);
+
+/* Calls `visit` for each element of the intersection of `a` and `b` using the
+ * 2-set algorithm `name`, returning the number of elements visited. */
+ptrdiff_t setops_intersect_visit(
+    const char *name,
+    const int32_t *a, size_t len_a,
+    const int32_t *b, size_t len_b,
+    setops_visit_fn visit, void *context);
+
+/* Writes the intersection of `count` (at least 2) sets to `out`. With a null
+ * `name` uses svs, otherwise applies the 2-set algorithm `name` to each set
+ * in turn. */
+ptrdiff_t setops_intersect_k(
+    const char *name,
+    const int32_t *const *sets, const size_t *lens, size_t count,
+    int32_t *out, size_t out_capacity);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SETOPS_H */
@@ -0,0 +1,342 @@
+//! C interface to the 2-set kernels (chosen by name, as in
+//! `setops::intersect::dynamic`) and k-set intersection, over raw pointers and
+//! lengths of sorted, deduplicated `int32_t` arrays. The declarations are in
+//! `include/setops.h`.
+//!
+//! The ABI is versioned by `SETOPS_ABI_VERSION`: functions are only added,
+//! and a change to an existing signature or error code bumps the version.
+//! Functions return a count, or a negative `SETOPS_ERROR_*` code; they never
+//! unwind into the caller.
+
+#![feature(portable_simd)]
+
+use std::{
+    ffi::{c_char, c_void, CStr},
+    simd::*,
+    slice,
+};
+use setops::{
+    intersect::{self, dynamic, Intersect2},
+    visitor::{Visitor, VecWriter, Counter, SimdVisitor4, SimdVisitor8, SimdVisitor16},
+};
+
+pub const SETOPS_ABI_VERSION: u32 = 1;
+
+pub const SETOPS_ERROR_NULL: isize = -1;
+pub const SETOPS_ERROR_UNKNOWN_ALGORITHM: isize = -2;
+pub const SETOPS_ERROR_CAPACITY: isize = -3;
+pub const SETOPS_ERROR_TOO_FEW_SETS: isize = -4;
+
+pub const SETOPS_FEATURE_SSSE3: u32 = 1 << 0;
+pub const SETOPS_FEATURE_AVX2: u32 = 1 << 1;
+pub const SETOPS_FEATURE_AVX512F: u32 = 1 << 2;
+pub const SETOPS_FEATURE_AVX512CD: u32 = 1 << 3;
+
+/// Called with `context` for each element of a result, in ascending order.
+pub type SetopsVisit = extern "C" fn(context: *mut c_void, value: i32);
+
+#[no_mangle]
+pub extern "C" fn setops_abi_version() -> u32 {
+    SETOPS_ABI_VERSION
+}
+
+/// Instruction set extensions the library was built with, and so which
+/// kernels are available.
+#[no_mangle]
+pub extern "C" fn setops_build_features() -> u32 {
+    let mut features = 0;
+    if cfg!(target_feature = "ssse3") { features |= SETOPS_FEATURE_SSSE3; }
+    if cfg!(target_feature = "avx2") { features |= SETOPS_FEATURE_AVX2; }
+    if cfg!(target_feature = "avx512f") { features |= SETOPS_FEATURE_AVX512F; }
+    if cfg!(target_feature = "avx512cd") { features |= SETOPS_FEATURE_AVX512CD; }
+    features
+}
+
+/// Instruction set extensions of the running CPU. A library built with
+/// features the CPU lacks must not be called.
+#[no_mangle]
+pub extern "C" fn setops_cpu_features() -> u32 {
+    let mut features = 0;
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("ssse3") { features |= SETOPS_FEATURE_SSSE3; }
+        if is_x86_feature_detected!("avx2") { features |= SETOPS_FEATURE_AVX2; }
+        if is_x86_feature_detected!("avx512f") { features |= SETOPS_FEATURE_AVX512F; }
+        if is_x86_feature_detected!("avx512cd") { features |= SETOPS_FEATURE_AVX512CD; }
+    }
+    features
+}
+
+/// Whether the 2-set algorithm `name` is available in this build.
+///
+/// # Safety
+/// `name` must be null or a nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn setops_has_algorithm(name: *const c_char) -> bool {
+    unsafe { algorithm::<Counter>(name) }.is_ok()
+}
+
+/// Writes the intersection of `a` and `b` to `out`, which must hold at least
+/// the length of the smaller set, using the 2-set algorithm `name` (see
+/// `setops_has_algorithm`). Returns the number of elements written.
+///
+/// # Safety
+/// `name` must be null or a nul-terminated string, and each pointer must be
+/// valid for its length (or null if the length is 0).
+#[no_mangle]
+pub unsafe extern "C" fn setops_intersect(
+    name: *const c_char,
+    a: *const i32, len_a: usize,
+    b: *const i32, len_b: usize,
+    out: *mut i32, out_capacity: usize) -> isize
+{
+    let run = || {
+        let intersect = unsafe { algorithm::<VecWriter<i32>>(name) }?;
+        let (a, b) = unsafe { (slice_of(a, len_a)?, slice_of(b, len_b)?) };
+
+        let mut writer = VecWriter::with_capacity(len_a.min(len_b));
+        intersect(a, b, &mut writer);
+        unsafe { write_out(writer.as_ref(), out, out_capacity) }
+    };
+    run().unwrap_or_else(|e| e)
+}
+
+/// Returns the size of the intersection of `a` and `b`, using the 2-set
+/// algorithm `name`.
+///
+/// # Safety
+/// As for `setops_intersect`.
+#[no_mangle]
+pub unsafe extern "C" fn setops_count(
+    name: *const c_char,
+    a: *const i32, len_a: usize,
+    b: *const i32, len_b: usize) -> isize
+{
+    let run = || {
+        let intersect = unsafe { algorithm::<Counter>(name) }?;
+        let (a, b) = unsafe { (slice_of(a, len_a)?, slice_of(b, len_b)?) };
+
+        let mut counter = Counter::new();
+        intersect(a, b, &mut counter);
+        Ok(counter.count() as isize)
+    };
+    run().unwrap_or_else(|e| e)
+}
+
+/// Calls `visit` with `context` for each element of the intersection of `a`
+/// and `b`, using the 2-set algorithm `name`. Returns the number of elements
+/// visited.
+///
+/// # Safety
+/// As for `setops_intersect`; `visit` must not unwind.
+#[no_mangle]
+pub unsafe extern "C" fn setops_intersect_visit(
+    name: *const c_char,
+    a: *const i32, len_a: usize,
+    b: *const i32, len_b: usize,
+    visit: SetopsVisit,
+    context: *mut c_void) -> isize
+{
+    let run = || {
+        let intersect = unsafe { algorithm::<CallbackVisitor>(name) }?;
+        let (a, b) = unsafe { (slice_of(a, len_a)?, slice_of(b, len_b)?) };
+
+        let mut visitor = CallbackVisitor { visit, context, count: 0 };
+        intersect(a, b, &mut visitor);
+        Ok(visitor.count as isize)
+    };
+    run().unwrap_or_else(|e| e)
+}
+
+/// Writes the intersection of the `count` sets `sets[i]` of length `lens[i]`
+/// to `out`, which must hold at least the length of the smallest set. With a
+/// null `name`, uses svs (`setops::intersect::svs`); otherwise applies the
+/// 2-set algorithm `name` to each set in turn. Returns the number of elements
+/// written.
+///
+/// # Safety
+/// `name` must be null or a nul-terminated string, `sets` and `lens` must be
+/// valid for `count` elements, and each set valid for its length.
+#[no_mangle]
+pub unsafe extern "C" fn setops_intersect_k(
+    name: *const c_char,
+    sets: *const *const i32,
+    lens: *const usize,
+    count: usize,
+    out: *mut i32, out_capacity: usize) -> isize
+{
+    let run = || {
+        if count < 2 {
+            return Err(SETOPS_ERROR_TOO_FEW_SETS);
+        }
+        let (sets, lens) = unsafe { (slice_of(sets, count)?, slice_of(lens, count)?) };
+        let sets = sets.iter().zip(lens)
+            .map(|(&set_ptr, &len)| unsafe { slice_of(set_ptr, len) })
+            .collect::<Result<Vec<&[i32]>, isize>>()?;
+
+        let result: Vec<i32> = if name.is_null() {
+            intersect::run_kset(&sets, intersect::svs)
+        }
+        else {
+            let intersect = unsafe { algorithm::<VecWriter<i32>>(name) }?;
+            let (mut left, mut right) = (VecWriter::new(), VecWriter::new());
+            intersect::svs_generic(&sets, &mut left, &mut right, intersect).as_ref().to_vec()
+        };
+        unsafe { write_out(&result, out, out_capacity) }
+    };
+    run().unwrap_or_else(|e| e)
+}
+
+unsafe fn algorithm<V>(name: *const c_char) -> Result<Intersect2<[i32], V>, isize>
+where
+    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16,
+{
+    if name.is_null() {
+        return Err(SETOPS_ERROR_NULL);
+    }
+    let name = unsafe { CStr::from_ptr(name) }.to_str()
+        .map_err(|_| SETOPS_ERROR_UNKNOWN_ALGORITHM)?;
+    dynamic::lookup(name).ok_or(SETOPS_ERROR_UNKNOWN_ALGORITHM)
+}
+
+unsafe fn slice_of<'a, T>(ptr: *const T, len: usize) -> Result<&'a [T], isize> {
+    match (ptr.is_null(), len) {
+        (true, 0) => Ok(&[]),
+        (true, _) => Err(SETOPS_ERROR_NULL),
+        (false, _) => Ok(unsafe { slice::from_raw_parts(ptr, len) }),
+    }
+}
+
+unsafe fn write_out(result: &[i32], out: *mut i32, out_capacity: usize) -> Result<isize, isize> {
+    if result.len() > out_capacity {
+        return Err(SETOPS_ERROR_CAPACITY);
+    }
+    if !result.is_empty() {
+        if out.is_null() {
+            return Err(SETOPS_ERROR_NULL);
+        }
+        unsafe { std::ptr::copy_nonoverlapping(result.as_ptr(), out, result.len()) };
+    }
+    Ok(result.len() as isize)
+}
+
+/// Passes each element of a result to a C callback.
+struct CallbackVisitor {
+    visit: SetopsVisit,
+    context: *mut c_void,
+    count: usize,
+}
+
+impl CallbackVisitor {
+    fn visit_lanes(&mut self, lanes: &[i32], mask: u64) {
+        for (i, &value) in lanes.iter().enumerate() {
+            if mask >> i & 1 == 1 {
+                self.visit(value);
+            }
+        }
+    }
+}
+
+impl Visitor<i32> for CallbackVisitor {
+    fn visit(&mut self, value: i32) {
+        (self.visit)(self.context, value);
+        self.count += 1;
+    }
+}
+
+impl SimdVisitor4 for CallbackVisitor {
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        self.visit_lanes(value.as_array(), mask);
+    }
+}
+
+impl SimdVisitor8 for CallbackVisitor {
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        self.visit_lanes(value.as_array(), mask);
+    }
+}
+
+impl SimdVisitor16 for CallbackVisitor {
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        self.visit_lanes(value.as_array(), mask);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: [i32; 7] = [1, 3, 5, 8, 9, 10, 14];
+    const B: [i32; 6] = [1, 2, 3, 9, 10, 11];
+    const C: [i32; 3] = [0, 3, 10];
+
+    #[test]
+    fn test_intersect() {
+        let name = c"branchless_merge".as_ptr();
+        let mut out = [0; 6];
+        let count = unsafe {
+            setops_intersect(name, A.as_ptr(), A.len(), B.as_ptr(), B.len(), out.as_mut_ptr(), 6)
+        };
+        assert_eq!(&out[..count as usize], [1, 3, 9, 10]);
+
+        let count = unsafe { setops_count(name, A.as_ptr(), A.len(), B.as_ptr(), B.len()) };
+        assert_eq!(count, 4);
+
+        let short = unsafe {
+            setops_intersect(name, A.as_ptr(), A.len(), B.as_ptr(), B.len(), out.as_mut_ptr(), 3)
+        };
+        assert_eq!(short, SETOPS_ERROR_CAPACITY);
+
+        let unknown = unsafe {
+            setops_count(c"none".as_ptr(), A.as_ptr(), A.len(), B.as_ptr(), B.len())
+        };
+        assert_eq!(unknown, SETOPS_ERROR_UNKNOWN_ALGORITHM);
+        assert!(unsafe { !setops_has_algorithm(std::ptr::null()) });
+        assert_eq!(unsafe { setops_count(name, std::ptr::null(), 0, B.as_ptr(), B.len()) }, 0);
+        assert_eq!(unsafe { setops_count(name, std::ptr::null(), 1, B.as_ptr(), B.len()) },
+            SETOPS_ERROR_NULL);
+    }
+
+    #[test]
+    fn test_intersect_visit() {
+        extern "C" fn push(context: *mut c_void, value: i32) {
+            unsafe { &mut *(context as *mut Vec<i32>) }.push(value);
+        }
+
+        for &name in &dynamic::names() {
+            let name = std::ffi::CString::new(name).unwrap();
+            let mut result: Vec<i32> = Vec::new();
+            let count = unsafe {
+                setops_intersect_visit(name.as_ptr(), A.as_ptr(), A.len(), B.as_ptr(), B.len(),
+                    push, &mut result as *mut Vec<i32> as *mut c_void)
+            };
+            assert_eq!(count, 4);
+            assert_eq!(result, [1, 3, 9, 10]);
+        }
+    }
+
+    #[test]
+    fn test_intersect_k() {
+        let sets = [A.as_ptr(), B.as_ptr(), C.as_ptr()];
+        let lens = [A.len(), B.len(), C.len()];
+        let mut out = [0; 3];
+
+        for name in [std::ptr::null(), c"branchless_merge".as_ptr()] {
+            let count = unsafe {
+                setops_intersect_k(name, sets.as_ptr(), lens.as_ptr(), 3, out.as_mut_ptr(), 3)
+            };
+            assert_eq!(&out[..count as usize], [3, 10]);
+        }
+        let too_few = unsafe {
+            setops_intersect_k(std::ptr::null(), sets.as_ptr(), lens.as_ptr(), 1, out.as_mut_ptr(), 3)
+        };
+        assert_eq!(too_few, SETOPS_ERROR_TOO_FEW_SETS);
+    }
+
+    #[test]
+    fn test_features() {
+        assert_eq!(setops_abi_version(), SETOPS_ABI_VERSION);
+        // This build runs, so the CPU has every feature it was built with.
+        assert_eq!(setops_build_features() & !setops_cpu_features(), 0);
+    }
+}
@@ -1,5 +1,5 @@
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn main() {
     if cfg!(target_os = "linux") {
@@ -23,4 +23,65 @@ fn main() {
     bindings.
         write_to_file(out_path.join("qfilter_c.rs"))
         .expect("Failed to write bindings");
+
+    println!("cargo::rustc-check-cfg=cfg(cpp_ref_fesia)");
+    if env::var_os("CARGO_FEATURE_CPP_REF").is_some() {
+        build_cpp_ref(&out_path);
+    }
+}
+
+// The reference implementations of the `cpp-ref` feature, compiled from
+// checkouts outside the repository: SIMDCompressionAndIntersection from
+// SIMDCOMPRESSION_DIR, and FESIA from the sources in FESIA_REF_DIR, if given,
+// through ffi/cpp_ref/fesia_adapter.cpp.
+fn build_cpp_ref(out_path: &Path) {
+    for var in ["SIMDCOMPRESSION_DIR", "FESIA_REF_DIR"] {
+        println!("cargo::rerun-if-env-changed={var}");
+    }
+    println!("cargo::rerun-if-changed=ffi/cpp_ref/cpp_ref.cpp");
+    println!("cargo::rerun-if-changed=ffi/cpp_ref/fesia_adapter.cpp");
+
+    let simdcomp = env::var("SIMDCOMPRESSION_DIR").unwrap_or_else(|_| panic!(
+        "the cpp-ref feature needs SIMDCOMPRESSION_DIR set to a checkout of \
+         https://github.com/lemire/SIMDCompressionAndIntersection"));
+    let simdcomp = Path::new(&simdcomp);
+
+    let mut build = cc::Build::new();
+    build.cpp(true)
+        .flag("-std=c++11")
+        .flag("-msse4.1")
+        .flag("-mavx2")
+        .opt_level(3)
+        .warnings(false)
+        .include("ffi/cpp_ref")
+        .include(simdcomp.join("include"))
+        .file(simdcomp.join("src/intersection.cpp"))
+        .file("ffi/cpp_ref/cpp_ref.cpp");
+
+    if let Ok(fesia) = env::var("FESIA_REF_DIR") {
+        let sources = std::fs::read_dir(&fesia)
+            .unwrap_or_else(|e| panic!("cannot read FESIA_REF_DIR {fesia}: {e}"))
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "cpp"));
+        for source in sources {
+            println!("cargo::rerun-if-changed={}", source.display());
+            build.file(source);
+        }
+        build.include(&fesia).file("ffi/cpp_ref/fesia_adapter.cpp");
+        println!("cargo::rustc-cfg=cpp_ref_fesia");
+    }
+    else {
+        println!("cargo::warning=FESIA_REF_DIR is not set, so the FESIA reference \
+                  is not built and fesia_matches_reference is ignored");
+    }
+    build.compile("cpp_ref");
+
+    bindgen::Builder::default()
+        .header("ffi/cpp_ref/cpp_ref.h")
+        .allowlist_function("ref_.*")
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+        .generate()
+        .expect("Unable to generate bindings")
+        .write_to_file(out_path.join("cpp_ref.rs"))
+        .expect("Failed to write bindings");
 }
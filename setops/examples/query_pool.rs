@@ -0,0 +1,59 @@
+//! Answers intersection queries from several threads at once over a shared
+//! pool of prepared sets. Sets are prepared once, wrapped in an `Arc` and
+//! shared without copying, as prepared types hold no thread-local state.
+//!
+//! cargo run --release --example query_pool -- [threads] [queries per thread]
+
+use std::{sync::Arc, thread, time::Instant};
+
+use setops::{
+    intersect::{
+        algorithm::{FesiaHashAlgorithm, TwoSetAlgorithm},
+        fesia::Fesia32Sse,
+    },
+    visitor::Counter,
+};
+
+const SETS: usize = 64;
+const SET_LEN: u32 = 10_000;
+const HASH_SCALE: f64 = 2.0;
+
+fn main() {
+    let mut args = std::env::args().skip(1).map(|a| a.parse::<usize>().expect("expected a number"));
+    let threads = args.next().unwrap_or(4);
+    let queries = args.next().unwrap_or(1000);
+
+    let algorithm = Arc::new(FesiaHashAlgorithm::<Fesia32Sse>::new(HASH_SCALE));
+
+    // Set i holds the multiples of i+1.
+    let pool: Arc<Vec<Fesia32Sse>> = Arc::new((1..=SETS as i32)
+        .map(|step| {
+            let set: Vec<i32> = (0..SET_LEN as i32).map(|i| i * step).collect();
+            TwoSetAlgorithm::<i32, Counter>::prepare(&*algorithm, &set)
+        })
+        .collect());
+
+    let start = Instant::now();
+    let workers: Vec<_> = (0..threads)
+        .map(|t| {
+            let (algorithm, pool) = (Arc::clone(&algorithm), Arc::clone(&pool));
+            thread::spawn(move || {
+                let mut total = 0;
+                for q in 0..queries {
+                    let (a, b) = ((t + q) % SETS, (t + 7 * q + 1) % SETS);
+                    let mut counter = Counter::new();
+                    algorithm.intersect(&pool[a], &pool[b], &mut counter);
+                    total += counter.count();
+                }
+                total
+            })
+        })
+        .collect();
+
+    let total: usize = workers.into_iter().map(|w| w.join().unwrap()).sum();
+    let elapsed = start.elapsed();
+
+    println!("{threads} threads answered {} queries in {elapsed:.2?} ({:.0} queries/s), {total} results",
+        threads * queries,
+        (threads * queries) as f64 / elapsed.as_secs_f64());
+}
@@ -0,0 +1,21 @@
+// C wrappers over the SIMDCompressionAndIntersection intersections, whose C++
+// functions in namespace SIMDCompressionLib cannot be called from Rust
+// directly.
+#include "cpp_ref.h"
+#include "intersection.h"
+
+#define REF_SIMDCOMP(name, function)                                    \
+    size_t name(const uint32_t *small, size_t small_len,                \
+                const uint32_t *large, size_t large_len,                \
+                uint32_t *out) {                                        \
+        return SIMDCompressionLib::function(                            \
+            small, small_len, large, large_len, out);                   \
+    }
+
+extern "C" {
+REF_SIMDCOMP(ref_simdcomp_galloping, onesidedgallopingintersection)
+REF_SIMDCOMP(ref_simdcomp_v1, v1)
+REF_SIMDCOMP(ref_simdcomp_v3, v3)
+REF_SIMDCOMP(ref_simdcomp_simd_galloping, SIMDgalloping)
+REF_SIMDCOMP(ref_simdcomp_simd_intersection, SIMDintersection)
+}
@@ -0,0 +1,52 @@
+#pragma once
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// SIMDCompressionAndIntersection (Lemire, Boytsov and Kurz). Each writes the
+// intersection of the smaller set `small` with `large` to `out` in ascending
+// order and returns its cardinality. `out` holds at least `small_len`
+// elements.
+size_t ref_simdcomp_galloping(
+            const uint32_t *small, size_t small_len,
+            const uint32_t *large, size_t large_len,
+            uint32_t *out);
+
+size_t ref_simdcomp_v1(
+            const uint32_t *small, size_t small_len,
+            const uint32_t *large, size_t large_len,
+            uint32_t *out);
+
+size_t ref_simdcomp_v3(
+            const uint32_t *small, size_t small_len,
+            const uint32_t *large, size_t large_len,
+            uint32_t *out);
+
+size_t ref_simdcomp_simd_galloping(
+            const uint32_t *small, size_t small_len,
+            const uint32_t *large, size_t large_len,
+            uint32_t *out);
+
+size_t ref_simdcomp_simd_intersection(
+            const uint32_t *small, size_t small_len,
+            const uint32_t *large, size_t large_len,
+            uint32_t *out);
+
+// FESIA (Zhang et al.), built by fesia_adapter.cpp only when the reference
+// sources are given in FESIA_REF_DIR (see the README). Builds both sets with
+// `hash_scale` bitmap bits per element, writes their intersection to `out`
+// in any order and returns its cardinality. `out` holds at least
+// min(len_a, len_b) elements.
+size_t ref_fesia_intersect(
+            const uint32_t *set_a, size_t len_a,
+            const uint32_t *set_b, size_t len_b,
+            double hash_scale,
+            uint32_t *out);
+
+#ifdef __cplusplus
+}
+#endif
@@ -0,0 +1,38 @@
+// C wrapper over the reference FESIA (Zhang et al.), compiled with the sources
+// in FESIA_REF_DIR. The reference is expected to declare in fesia.h:
+//
+//   namespace fesia {
+//   struct FesiaSet;
+//   FesiaSet *build_set(const uint32_t *set, size_t len, double hash_scale);
+//   size_t intersect(const FesiaSet *set_a, const FesiaSet *set_b, uint32_t *out);
+//   void free_set(FesiaSet *set);
+//   }
+//
+// `build_set` takes a strictly sorted set, and `intersect` writes the
+// intersection to `out` in any order and returns its cardinality.
+#include <memory>
+
+#include "cpp_ref.h"
+#include "fesia.h"
+
+namespace {
+
+using Set = std::unique_ptr<fesia::FesiaSet, void (*)(fesia::FesiaSet *)>;
+
+Set build(const uint32_t *set, size_t len, double hash_scale) {
+    return Set(fesia::build_set(set, len, hash_scale), fesia::free_set);
+}
+
+}
+
+extern "C" {
+size_t ref_fesia_intersect(
+            const uint32_t *set_a, size_t len_a,
+            const uint32_t *set_b, size_t len_b,
+            double hash_scale,
+            uint32_t *out) {
+    Set a = build(set_a, len_a, hash_scale);
+    Set b = build(set_b, len_b, hash_scale);
+    return fesia::intersect(a.get(), b.get(), out);
+}
+}
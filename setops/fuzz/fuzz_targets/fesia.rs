@@ -0,0 +1,70 @@
+#![no_main]
+//! Compares FESIA's segment and hash intersection against `naive_merge` for
+//! each SIMD width and segment size. The hash scale is derived from the input
+//! so that both sparse and crowded bitmaps are covered. The inputs, and the
+//! bitmaps and segments each set builds from them, are placed against guard
+//! pages, so reads past the end of any of them crash.
+
+use libfuzzer_sys::fuzz_target;
+use setops::{
+    intersect::{self, fesia::*},
+    visitor::VecWriter,
+};
+use setops_fuzz::{decode_set_pair, guarded, GuardedAlloc, GuardedSlice};
+
+#[global_allocator]
+static ALLOC: GuardedAlloc = GuardedAlloc;
+
+fn check<S, I>(left: &[i32], right: &[i32], hash_scale: HashScale, expected: &[i32])
+where
+    S: SetWithHashScale + FesiaIntersect,
+    I: SegmentIntersect,
+{
+    let (set_a, set_b) = guarded(|| (
+        S::from_sorted(left, hash_scale),
+        S::from_sorted(right, hash_scale)));
+
+    let mut visitor: VecWriter<i32> = VecWriter::new();
+    set_a.intersect::<VecWriter<i32>, I>(&set_b, &mut visitor);
+    let mut actual: Vec<i32> = visitor.into();
+    actual.sort_unstable();
+    assert_eq!(actual, expected, "segment intersect");
+
+    let mut visitor: VecWriter<i32> = VecWriter::new();
+    set_a.hash_intersect(&set_b, &mut visitor);
+    let mut actual: Vec<i32> = visitor.into();
+    actual.sort_unstable();
+    assert_eq!(actual, expected, "hash intersect");
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some(pair) = decode_set_pair(data) else {
+        return;
+    };
+    let hash_scale = (pair.shape % 32) as HashScale * 0.5 + 0.1;
+
+    let expected = intersect::run_2set(
+        pair.left.as_slice(), pair.right.as_slice(), intersect::naive_merge);
+
+    let (left, right) = (GuardedSlice::new(&pair.left), GuardedSlice::new(&pair.right));
+    let (left, right) = (left.as_slice(), right.as_slice());
+
+    #[cfg(target_feature = "ssse3")]
+    {
+        check::<Fesia8Sse, SegmentIntersectSse>(left, right, hash_scale, &expected);
+        check::<Fesia16Sse, SegmentIntersectSse>(left, right, hash_scale, &expected);
+        check::<Fesia32Sse, SegmentIntersectSse>(left, right, hash_scale, &expected);
+    }
+    #[cfg(target_feature = "avx2")]
+    {
+        check::<Fesia8Avx2, SegmentIntersectAvx2>(left, right, hash_scale, &expected);
+        check::<Fesia16Avx2, SegmentIntersectAvx2>(left, right, hash_scale, &expected);
+        check::<Fesia32Avx2, SegmentIntersectAvx2>(left, right, hash_scale, &expected);
+    }
+    #[cfg(target_feature = "avx512f")]
+    {
+        check::<Fesia8Avx512, SegmentIntersectAvx512>(left, right, hash_scale, &expected);
+        check::<Fesia16Avx512, SegmentIntersectAvx512>(left, right, hash_scale, &expected);
+        check::<Fesia32Avx512, SegmentIntersectAvx512>(left, right, hash_scale, &expected);
+    }
+});
@@ -0,0 +1,33 @@
+#![no_main]
+//! Compares every 2-set intersection algorithm operating on sorted arrays
+//! (`TwoSetAlgo::ALL`) against `naive_merge`. Inputs are placed against guard
+//! pages so kernels which read past the end of a set crash instead of silently
+//! passing.
+
+use libfuzzer_sys::fuzz_target;
+use setops::{
+    intersect::{self, TwoSetAlgo},
+    visitor::VecWriter,
+};
+use setops_fuzz::{decode_set_pair, GuardedSlice};
+
+fuzz_target!(|data: &[u8]| {
+    let Some(pair) = decode_set_pair(data) else {
+        return;
+    };
+
+    let expected = intersect::run_2set(
+        pair.left.as_slice(), pair.right.as_slice(), intersect::naive_merge);
+
+    let left = GuardedSlice::new(&pair.left);
+    let right = GuardedSlice::new(&pair.right);
+
+    for &algo in TwoSetAlgo::ALL {
+        let intersect = algo.function::<VecWriter<i32>>();
+        let actual = intersect::run_2set(left.as_slice(), right.as_slice(), intersect);
+        assert_eq!(actual, expected, "{algo} (left, right)");
+
+        let actual = intersect::run_2set(right.as_slice(), left.as_slice(), intersect);
+        assert_eq!(actual, expected, "{algo} (right, left)");
+    }
+});
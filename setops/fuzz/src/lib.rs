@@ -0,0 +1,203 @@
+//! Helpers shared by the fuzz targets: decoding fuzzer input into pairs of
+//! sorted sets and placing sets against guard pages so that any out-of-bounds
+//! read by a SIMD kernel faults immediately.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+pub struct SetPair {
+    pub left: Vec<i32>,
+    pub right: Vec<i32>,
+    pub shape: u8,
+}
+
+const HEADER_LEN: usize = 5;
+
+/// Decodes arbitrary bytes into two strictly increasing sets.
+///
+/// Layout: `shape: u8`, `start: i32` (little endian), followed by one byte per
+/// generated value. For each byte the upper six bits give the gap from the
+/// previous value and the lower two bits select whether the value is placed in
+/// the left set, the right set, or both.
+///
+/// `shape` additionally snaps both set lengths onto (or next to) multiples of
+/// 4, 8 or 16 so the fuzzer frequently exercises the boundary between the
+/// vectorised loop and the scalar tail.
+pub fn decode_set_pair(data: &[u8]) -> Option<SetPair> {
+    if data.len() < HEADER_LEN {
+        return None;
+    }
+    let shape = data[0];
+    let start = i32::from_le_bytes(data[1..HEADER_LEN].try_into().unwrap());
+
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+
+    let mut value = start;
+    for &byte in &data[HEADER_LEN..] {
+        match byte & 3 {
+            0 => left.push(value),
+            1 => right.push(value),
+            _ => {
+                left.push(value);
+                right.push(value);
+            }
+        }
+        let gap = (byte >> 2) as i32 + 1;
+        value = match value.checked_add(gap) {
+            Some(v) => v,
+            None => break,
+        };
+    }
+
+    left.truncate(snap_len(left.len(), shape));
+    right.truncate(snap_len(right.len(), shape >> 4));
+
+    Some(SetPair { left, right, shape })
+}
+
+fn snap_len(len: usize, shape: u8) -> usize {
+    let width = 4 << ((shape >> 2) % 3);
+    let base = len / width * width;
+    match shape & 3 {
+        0 => len,
+        1 => base,
+        2 => base.saturating_sub(1),
+        _ => (base + 1).min(len),
+    }
+}
+
+/// Copy of a set surrounded by inaccessible pages. The set ends exactly at
+/// the start of the trailing guard page, so reading even a single element past
+/// the end results in a segfault which libFuzzer reports as a crash.
+pub struct GuardedSlice {
+    map: *mut libc::c_void,
+    map_len: usize,
+    data: *const i32,
+    len: usize,
+}
+
+impl GuardedSlice {
+    pub fn new(items: &[i32]) -> Self {
+        let bytes = std::mem::size_of_val(items);
+        let (map, map_len, data) = map_guarded(bytes, std::mem::align_of::<i32>());
+        let data = data as *mut i32;
+        unsafe {
+            ptr::copy_nonoverlapping(items.as_ptr(), data, items.len());
+        }
+        Self { map, map_len, data, len: items.len() }
+    }
+
+    pub fn as_slice(&self) -> &[i32] {
+        unsafe { std::slice::from_raw_parts(self.data, self.len) }
+    }
+}
+
+impl Drop for GuardedSlice {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.map, self.map_len);
+        }
+    }
+}
+
+/// Global allocator which places each allocation made inside `guarded`
+/// against a trailing guard page, as `GuardedSlice` places a set, and serves
+/// all others from the system allocator. Fuzz targets install it to guard the
+/// arrays a set type builds internally, such as FESIA's bitmap and segments,
+/// which cannot be copied into a `GuardedSlice`.
+pub struct GuardedAlloc;
+
+thread_local! {
+    static GUARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+// Data pointers of the live guarded allocations, 0 for a free slot. Once all
+// slots are taken, allocations fall back to the system allocator.
+const MAX_GUARDED: usize = 1024;
+static GUARDED: [AtomicUsize; MAX_GUARDED] = [const { AtomicUsize::new(0) }; MAX_GUARDED];
+
+/// Runs `f`, placing its allocations on this thread against guard pages if
+/// `GuardedAlloc` is the global allocator. They may be freed after `f`
+/// returns.
+pub fn guarded<R>(f: impl FnOnce() -> R) -> R {
+    let outer = GUARDING.with(|guarding| guarding.replace(true));
+    let result = f();
+    GUARDING.with(|guarding| guarding.set(outer));
+    result
+}
+
+unsafe impl GlobalAlloc for GuardedAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if !GUARDING.with(Cell::get) || layout.align() > page_size() {
+            return System.alloc(layout);
+        }
+        let Some(slot) = GUARDED.iter().find(|slot|
+            slot.compare_exchange(0, usize::MAX, Ordering::AcqRel, Ordering::Relaxed).is_ok())
+        else {
+            return System.alloc(layout);
+        };
+        let (_, _, data) = map_guarded(layout.size(), layout.align());
+        slot.store(data as usize, Ordering::Release);
+        data
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let slot = GUARDED.iter().find(|slot| slot.load(Ordering::Acquire) == ptr as usize);
+        match slot {
+            Some(slot) => {
+                let (map, map_len) = guarded_mapping(ptr, layout.size());
+                libc::munmap(map, map_len);
+                slot.store(0, Ordering::Release);
+            },
+            None => System.dealloc(ptr, layout),
+        }
+    }
+}
+
+// Maps `bytes` aligned to `align` so that they end as close to the trailing
+// guard page as the alignment allows, exactly at it when `bytes` is a
+// multiple of `align`. Returns the mapping, its length and the data.
+fn map_guarded(bytes: usize, align: usize) -> (*mut libc::c_void, usize, *mut u8) {
+    let page = page_size();
+    let data_pages = bytes.div_ceil(page).max(1);
+    let map_len = (data_pages + 2) * page;
+
+    unsafe {
+        let map = libc::mmap(
+            ptr::null_mut(),
+            map_len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+            -1,
+            0);
+        assert!(map != libc::MAP_FAILED, "mmap failed");
+
+        let base = map as *mut u8;
+        let leading = base;
+        let trailing = base.add((data_pages + 1) * page);
+        assert!(libc::mprotect(leading as *mut _, page, libc::PROT_NONE) == 0);
+        assert!(libc::mprotect(trailing as *mut _, page, libc::PROT_NONE) == 0);
+
+        let data = trailing.sub(bytes);
+        let data = data.sub(data as usize % align);
+        (map, map_len, data)
+    }
+}
+
+// The mapping `map_guarded` made for `bytes` at `data`.
+fn guarded_mapping(data: *mut u8, bytes: usize) -> (*mut libc::c_void, usize) {
+    let page = page_size();
+    let data_pages = bytes.div_ceil(page).max(1);
+    let trailing = (data as usize + bytes).next_multiple_of(page);
+    let map = trailing - (data_pages + 1) * page;
+    (map as *mut libc::c_void, (data_pages + 2) * page)
+}
+
+fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
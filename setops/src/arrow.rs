@@ -0,0 +1,125 @@
+//! Conversions between Arrow primitive arrays (e.g., `UInt32Array` and
+//! `Int32Array`) and sorted sets, and intersection directly over Arrow
+//! buffers. Requires the `arrow` feature.
+//!
+//! An array is a set if it has no nulls and its values are strictly
+//! increasing. Its values buffer is then borrowed as a slice without copying,
+//! and a result is moved into the values buffer of a new array. A column of a
+//! record batch is used by downcasting it, e.g. with
+//! `column.as_primitive::<UInt32Type>()`.
+
+use std::fmt;
+use arrow_array::{Array, ArrowPrimitiveType, PrimitiveArray};
+use crate::{
+    intersect::{Intersect2, IntersectK},
+    visitor::VecWriter,
+    Set,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArrowSetError {
+    /// The array has this many null values.
+    Nulls(usize),
+    /// The value at `index` is not greater than the value before it.
+    Unsorted { index: usize },
+}
+
+impl fmt::Display for ArrowSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nulls(count) =>
+                write!(f, "array has {count} null values"),
+            Self::Unsorted { index } =>
+                write!(f, "array is not strictly increasing at index {index}"),
+        }
+    }
+}
+
+impl std::error::Error for ArrowSetError {}
+
+/// Borrows the values of `array` as a sorted set.
+pub fn as_set<T>(array: &PrimitiveArray<T>) -> Result<&[T::Native], ArrowSetError>
+where
+    T: ArrowPrimitiveType,
+    T::Native: Ord,
+{
+    if array.null_count() > 0 {
+        return Err(ArrowSetError::Nulls(array.null_count()));
+    }
+    let values: &[T::Native] = array.values();
+
+    match values.windows(2).position(|w| w[0] >= w[1]) {
+        Some(i) => Err(ArrowSetError::Unsorted { index: i + 1 }),
+        None => Ok(values),
+    }
+}
+
+/// Builds the set representation `S` (e.g., `BsrVec` or `HybridSet`) of a
+/// sorted array.
+pub fn to_set<T, S>(array: &PrimitiveArray<T>) -> Result<S, ArrowSetError>
+where
+    T: ArrowPrimitiveType,
+    T::Native: Ord,
+    S: Set<T::Native>,
+{
+    as_set(array).map(S::from_sorted)
+}
+
+/// Moves a sorted set into an array without copying.
+pub fn from_set<T>(set: Vec<T::Native>) -> PrimitiveArray<T>
+where
+    T: ArrowPrimitiveType,
+{
+    PrimitiveArray::new(set.into(), None)
+}
+
+/// Intersects two sorted arrays with a 2-set algorithm into a new array.
+pub fn intersect<T>(
+    array_a: &PrimitiveArray<T>,
+    array_b: &PrimitiveArray<T>,
+    intersect: Intersect2<[T::Native], VecWriter<T::Native>>)
+    -> Result<PrimitiveArray<T>, ArrowSetError>
+where
+    T: ArrowPrimitiveType,
+    T::Native: Ord,
+{
+    let mut writer = VecWriter::new();
+    intersect_with(array_a, array_b, intersect, &mut writer)?;
+    Ok(from_set(writer.into()))
+}
+
+/// Intersects two sorted arrays with a 2-set algorithm, passing the result to
+/// `visitor` (e.g., a `Counter` to only count it).
+pub fn intersect_with<T, V>(
+    array_a: &PrimitiveArray<T>,
+    array_b: &PrimitiveArray<T>,
+    intersect: Intersect2<[T::Native], V>,
+    visitor: &mut V) -> Result<(), ArrowSetError>
+where
+    T: ArrowPrimitiveType,
+    T::Native: Ord,
+{
+    intersect(as_set(array_a)?, as_set(array_b)?, visitor);
+    Ok(())
+}
+
+/// Intersects two or more sorted arrays with a k-set algorithm into a new
+/// array.
+pub fn intersect_k<'a, T>(
+    arrays: &[&'a PrimitiveArray<T>],
+    intersect: IntersectK<&'a [T::Native], VecWriter<T::Native>>)
+    -> Result<PrimitiveArray<T>, ArrowSetError>
+where
+    T: ArrowPrimitiveType,
+    T::Native: Ord,
+{
+    assert!(arrays.len() >= 2);
+
+    let sets = arrays.iter()
+        .map(|array| as_set(array))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut writer = VecWriter::new();
+    intersect(&sets, &mut writer);
+    Ok(from_set(writer.into()))
+}
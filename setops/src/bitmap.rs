@@ -0,0 +1,111 @@
+//! Plain fixed-size bitmaps over a universe `{0, 1, ..., universe-1}`, where
+//! bit `i % 64` of word `i / 64` is set if `i` is in the set. A bitmap takes
+//! `universe / 8` bytes regardless of the set's cardinality, so the bitwise AND
+//! of two bitmaps is only competitive with sorted-array intersection for dense
+//! sets. `BsrVec::from_bitmap` and `BsrVec::to_bitmap` convert to and from the
+//! BSR representation.
+//!
+//! Cardinalities are counted with `vpopcntq` if AVX-512 VPOPCNTDQ is enabled.
+
+/// Number of words in a bitmap over `universe` values.
+pub const fn words(universe: usize) -> usize {
+    universe.div_ceil(u64::BITS as usize)
+}
+
+/// Builds a bitmap over `universe` values from a sorted set.
+///
+/// Panics if an element is not less than `universe`.
+pub fn from_sorted(sorted: &[u32], universe: usize) -> Vec<u64> {
+    let mut bitmap = vec![0u64; words(universe)];
+    for &item in sorted {
+        assert!((item as usize) < universe,
+            "element {item} outside of universe of {universe} values");
+        bitmap[item as usize / 64] |= 1 << (item % 64);
+    }
+    bitmap
+}
+
+pub fn to_sorted_set(bitmap: &[u64]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(len(bitmap));
+    for (i, &word) in bitmap.iter().enumerate() {
+        let mut word = word;
+        while word != 0 {
+            result.push((i * 64) as u32 | word.trailing_zeros());
+            word &= word - 1;
+        }
+    }
+    result
+}
+
+/// Number of elements in `bitmap`.
+pub fn len(bitmap: &[u64]) -> usize {
+    #[cfg(all(feature = "simd", target_feature = "avx512vpopcntdq"))]
+    return unsafe { popcount_avx512(bitmap, bitmap, |a, _| a) };
+
+    #[cfg(not(all(feature = "simd", target_feature = "avx512vpopcntdq")))]
+    bitmap.iter().map(|w| w.count_ones() as usize).sum()
+}
+
+/// Number of elements in the intersection of `bitmap_a` and `bitmap_b`,
+/// without writing it. Words past the end of the shorter bitmap are empty.
+pub fn and_len(bitmap_a: &[u64], bitmap_b: &[u64]) -> usize {
+    let words = bitmap_a.len().min(bitmap_b.len());
+    let (bitmap_a, bitmap_b) = (&bitmap_a[..words], &bitmap_b[..words]);
+
+    #[cfg(all(feature = "simd", target_feature = "avx512vpopcntdq"))]
+    return unsafe {
+        popcount_avx512(bitmap_a, bitmap_b, |a, b| std::arch::x86_64::_mm512_and_si512(a, b))
+    };
+
+    #[cfg(not(all(feature = "simd", target_feature = "avx512vpopcntdq")))]
+    bitmap_a.iter().zip(bitmap_b)
+        .map(|(a, b)| (a & b).count_ones() as usize)
+        .sum()
+}
+
+/// Writes the intersection of `bitmap_a` and `bitmap_b` to `out`, which is
+/// resized to the shorter of the two.
+pub fn and_into(bitmap_a: &[u64], bitmap_b: &[u64], out: &mut Vec<u64>) {
+    let words = bitmap_a.len().min(bitmap_b.len());
+    out.clear();
+    out.extend(bitmap_a[..words].iter().zip(&bitmap_b[..words]).map(|(a, b)| a & b));
+}
+
+/// Intersects `out` with `bitmap` in place, e.g., for k-set intersection.
+pub fn and_inplace(out: &mut Vec<u64>, bitmap: &[u64]) {
+    out.truncate(bitmap.len());
+    for (word, other) in out.iter_mut().zip(bitmap) {
+        *word &= other;
+    }
+}
+
+// Sums the popcounts of `combine` applied to each pair of 512-bit blocks of
+// two bitmaps of equal length.
+#[cfg(all(feature = "simd", target_feature = "avx512vpopcntdq"))]
+#[inline]
+unsafe fn popcount_avx512<F>(bitmap_a: &[u64], bitmap_b: &[u64], combine: F) -> usize
+where
+    F: Fn(std::arch::x86_64::__m512i, std::arch::x86_64::__m512i) -> std::arch::x86_64::__m512i
+{
+    use std::arch::x86_64::*;
+
+    const W: usize = 8;
+    let st = bitmap_a.len() / W * W;
+
+    let mut total = _mm512_setzero_si512();
+    let mut i = 0;
+    while i < st {
+        let a = _mm512_loadu_si512(bitmap_a.as_ptr().add(i) as *const _);
+        let b = _mm512_loadu_si512(bitmap_b.as_ptr().add(i) as *const _);
+        total = _mm512_add_epi64(total, _mm512_popcnt_epi64(combine(a, b)));
+        i += W;
+    }
+
+    // Masked loads do not touch words past the end.
+    let mask = ((1u16 << (bitmap_a.len() - st)) - 1) as u8;
+    let a = _mm512_maskz_loadu_epi64(mask, bitmap_a.as_ptr().add(st) as *const _);
+    let b = _mm512_maskz_loadu_epi64(mask, bitmap_b.as_ptr().add(st) as *const _);
+    total = _mm512_add_epi64(total, _mm512_popcnt_epi64(combine(a, b)));
+
+    _mm512_reduce_add_epi64(total) as usize
+}
@@ -87,6 +87,40 @@ impl BsrVec {
         result
     }
 
+    /// Converts a bitmap (see `bitmap`) to BSR, where each word holds the
+    /// states of two consecutive bases.
+    pub fn from_bitmap(bitmap: &[u64]) -> Self {
+        // Each element needs at most one base, and each word at most two.
+        let capacity = crate::bitmap::len(bitmap).min(bitmap.len() * 2);
+        let mut bsr = Self::with_capacities(capacity);
+
+        for (i, &word) in bitmap.iter().enumerate() {
+            let base = (i * 2) as u32;
+            for (half, state) in [(0, word as u32), (1, (word >> 32) as u32)] {
+                if state != 0 {
+                    bsr.append(base + half, state);
+                }
+            }
+        }
+        bsr
+    }
+
+    /// Converts to a bitmap over `universe` values.
+    ///
+    /// Panics if an element is not less than `universe`.
+    pub fn to_bitmap(&self, universe: usize) -> Vec<u64> {
+        let mut bitmap = vec![0u64; crate::bitmap::words(universe)];
+        for (&base, &state) in self.iter() {
+            let last = ((base as usize) << BSR_SHIFT) + BSR_MASK as usize - state.leading_zeros() as usize;
+            assert!(last < universe,
+                "element {last} outside of universe of {universe} values");
+
+            let shift = (base % 2) * BSR_WIDTH;
+            bitmap[base as usize / 2] |= (state as u64) << shift;
+        }
+        bitmap
+    }
+
     pub fn iter(&self) -> Zip<slice::Iter<'_, u32>, slice::Iter<'_, u32>> {
         self.bases.iter().zip(self.states.iter())
     }
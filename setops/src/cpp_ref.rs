@@ -0,0 +1,91 @@
+//! The reference C++ implementations several kernels were ported from, built
+//! with the `cpp-ref` feature to cross-validate the ports on the same inputs
+//! (see `tests/cpp_ref_tests.rs`). The sources are not part of the repository:
+//! the build compiles them from checkouts named by environment variables (see
+//! the README).
+//!
+//! The references take sets of u32 in unsigned order, so they agree with the
+//! kernels on i32 only for elements below 2^31.
+
+// Generated from ffi/cpp_ref/cpp_ref.h. `ref_fesia_intersect` is only built
+// with the FESIA reference.
+#[allow(dead_code)]
+mod ffi {
+    include!(concat!(env!("OUT_DIR"), "/cpp_ref.rs"));
+}
+use ffi::*;
+
+type RefIntersect = unsafe extern "C" fn(*const u32, usize, *const u32, usize, *mut u32) -> usize;
+
+// Room past the result for the vector stores of the references.
+const OUT_PADDING: usize = 64;
+
+/// Intersections of SIMDCompressionAndIntersection (Lemire, Boytsov and
+/// Kurz), which the `lbk_*` and SIMD galloping kernels follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdComp {
+    /// `onesidedgallopingintersection`, scalar galloping.
+    Galloping,
+    /// `v1`, ported as the `lbk_v1x*` kernels.
+    V1,
+    /// `v3`, ported as the `lbk_v3_*` kernels.
+    V3,
+    /// `SIMDgalloping`, ported as `galloping_sse`.
+    SimdGalloping,
+    /// `SIMDintersection`, which picks one of the above by the ratio of the
+    /// set lengths.
+    SimdIntersection,
+}
+
+impl SimdComp {
+    pub const ALL: [SimdComp; 5] = [
+        SimdComp::Galloping, SimdComp::V1, SimdComp::V3,
+        SimdComp::SimdGalloping, SimdComp::SimdIntersection,
+    ];
+
+    fn function(self) -> RefIntersect {
+        match self {
+            SimdComp::Galloping        => ref_simdcomp_galloping,
+            SimdComp::V1               => ref_simdcomp_v1,
+            SimdComp::V3               => ref_simdcomp_v3,
+            SimdComp::SimdGalloping    => ref_simdcomp_simd_galloping,
+            SimdComp::SimdIntersection => ref_simdcomp_simd_intersection,
+        }
+    }
+
+    /// Intersects the strictly sorted `small` and `large`, where `small` is
+    /// not longer than `large`. Returns the result and the cardinality the
+    /// reference returned.
+    pub fn intersect(self, small: &[u32], large: &[u32]) -> (Vec<u32>, usize) {
+        assert!(small.len() <= large.len(), "the first set must be the smaller");
+
+        let mut out = vec![0; small.len() + OUT_PADDING];
+        let count = unsafe {
+            (self.function())(
+                small.as_ptr(), small.len(),
+                large.as_ptr(), large.len(),
+                out.as_mut_ptr())
+        };
+        out.truncate(count.min(small.len()));
+        (out, count)
+    }
+}
+
+/// Intersects the strictly sorted `set_a` and `set_b` with the reference FESIA
+/// at `hash_scale`. Returns the result in ascending order and the cardinality
+/// the reference returned.
+#[cfg(cpp_ref_fesia)]
+pub fn fesia_intersect(set_a: &[u32], set_b: &[u32], hash_scale: f64) -> (Vec<u32>, usize) {
+    let len = set_a.len().min(set_b.len());
+    let mut out = vec![0; len + OUT_PADDING];
+    let count = unsafe {
+        ref_fesia_intersect(
+            set_a.as_ptr(), set_a.len(),
+            set_b.as_ptr(), set_b.len(),
+            hash_scale,
+            out.as_mut_ptr())
+    };
+    out.truncate(count.min(len));
+    out.sort_unstable();
+    (out, count)
+}
@@ -0,0 +1,132 @@
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+#[cfg(feature = "simd")]
+use std::simd::{Simd, SimdElement, LaneCount, SupportedLaneCount};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Integer types which may be stored in a set.
+///
+/// SIMD kernels operate on signed lanes (`i32`, `i64`), so unsigned sets are
+/// reinterpreted bit-for-bit before being loaded into vectors. Equality is
+/// preserved by this reinterpretation but ordering is not: `u32` values of
+/// 2^31 and above become negative. Kernels which compare lanes by order must use
+/// `to_ordered_lane`, which flips the sign bit of unsigned values so that the
+/// signed order of lanes matches the order of the original values.
+///
+/// This trait is sealed and implemented for `i32`, `u32`, `i64` and `u64`.
+pub trait IntElement:
+    Copy + Ord + Default + Hash + Debug + Display + private::Sealed + 'static
+{
+    /// Signed integer of the same width used as a SIMD lane.
+    #[cfg(feature = "simd")]
+    type Lane: SimdElement + Copy + Ord + Debug;
+    #[cfg(not(feature = "simd"))]
+    type Lane: Copy + Ord + Debug;
+
+    const SIGNED: bool;
+    const BITS: u32;
+
+    /// Reinterprets the bits of `self` as a lane.
+    fn to_lane(self) -> Self::Lane;
+
+    /// Reinterprets the bits of `lane` as `Self`.
+    fn from_lane(lane: Self::Lane) -> Self;
+
+    /// Maps `self` to a lane such that signed lane order matches `Self` order.
+    fn to_ordered_lane(self) -> Self::Lane;
+
+    /// Inverse of `to_ordered_lane`.
+    fn from_ordered_lane(lane: Self::Lane) -> Self;
+
+    /// Widens the bits of `self` to 64 bits. Distinct values of `Self` map to
+    /// distinct results, which makes this suitable as input to a hash.
+    fn to_u64_bits(self) -> u64;
+
+    fn as_lanes(slice: &[Self]) -> &[Self::Lane] {
+        // Safety: Self and Lane are integers of the same width (checked
+        // by the implementations below).
+        unsafe { std::slice::from_raw_parts(slice.as_ptr() as *const Self::Lane, slice.len()) }
+    }
+
+    fn from_lanes(slice: &[Self::Lane]) -> &[Self] {
+        unsafe { std::slice::from_raw_parts(slice.as_ptr() as *const Self, slice.len()) }
+    }
+
+    #[cfg(feature = "simd")]
+    #[inline]
+    fn splat<const LANES: usize>(self) -> Simd<Self::Lane, LANES>
+    where
+        LaneCount<LANES>: SupportedLaneCount,
+    {
+        Simd::splat(self.to_lane())
+    }
+}
+
+macro_rules! impl_int_element {
+    ($t:ty, $lane:ty, $signed:expr) => {
+        impl private::Sealed for $t {}
+
+        impl IntElement for $t {
+            type Lane = $lane;
+
+            const SIGNED: bool = $signed;
+            const BITS: u32 = <$t>::BITS;
+
+            #[inline]
+            fn to_lane(self) -> $lane {
+                self as $lane
+            }
+
+            #[inline]
+            fn from_lane(lane: $lane) -> Self {
+                lane as $t
+            }
+
+            #[inline]
+            fn to_ordered_lane(self) -> $lane {
+                if $signed {
+                    self as $lane
+                } else {
+                    (self as $lane) ^ <$lane>::MIN
+                }
+            }
+
+            #[inline]
+            fn from_ordered_lane(lane: $lane) -> Self {
+                if $signed {
+                    lane as $t
+                } else {
+                    (lane ^ <$lane>::MIN) as $t
+                }
+            }
+
+            #[inline]
+            fn to_u64_bits(self) -> u64 {
+                self as $lane as i64 as u64
+            }
+        }
+
+        const _: () = assert!(std::mem::size_of::<$t>() == std::mem::size_of::<$lane>());
+    };
+}
+
+/// Applies `T::to_ordered_lane` to each lane of `lanes`, which hold elements
+/// reinterpreted by `to_lane`. For 32-bit elements the mapping flips the sign
+/// bit or nothing, so it also maps ordered lanes back.
+#[cfg(feature = "simd")]
+#[inline]
+pub(crate) fn to_ordered_lanes<T, const LANES: usize>(lanes: Simd<i32, LANES>) -> Simd<i32, LANES>
+where
+    T: IntElement<Lane = i32>,
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    lanes ^ Simd::splat(T::from_lane(0).to_ordered_lane())
+}
+
+impl_int_element!(i32, i32, true);
+impl_int_element!(u32, i32, false);
+impl_int_element!(i64, i64, true);
+impl_int_element!(u64, i64, false);
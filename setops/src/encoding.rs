@@ -0,0 +1,48 @@
+//! Compressed set representations, as used for posting lists in search
+//! engines. Sets are split into fixed size blocks which can be decoded
+//! independently, allowing intersections to decompress a block at a time into
+//! a small buffer and run an uncompressed intersection kernel on the result
+//! (see `intersect::compressed`).
+
+mod bp128;
+mod streamvbyte;
+
+pub use bp128::Bp128Set;
+pub use streamvbyte::SvbSet;
+
+/// Number of elements in each encoded block.
+pub const BLOCK_LEN: usize = 128;
+
+pub type Block = [i32; BLOCK_LEN];
+
+/// A sorted set stored as a sequence of independently decodable blocks.
+/// Every block except the last holds exactly `BLOCK_LEN` elements.
+pub trait BlockSet {
+    /// Number of elements in the set.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn block_count(&self) -> usize {
+        self.len().div_ceil(BLOCK_LEN)
+    }
+
+    /// Largest element of block `index`, available without decoding.
+    fn block_max(&self, index: usize) -> i32;
+
+    /// Decodes block `index` into `out`, returning the number of elements
+    /// written.
+    fn decode_block(&self, index: usize, out: &mut Block) -> usize;
+
+    fn decode(&self) -> Vec<i32> {
+        let mut result = Vec::with_capacity(self.len());
+        let mut block = [0; BLOCK_LEN];
+        for index in 0..self.block_count() {
+            let len = self.decode_block(index, &mut block);
+            result.extend_from_slice(&block[..len]);
+        }
+        result
+    }
+}
@@ -0,0 +1,167 @@
+//! Delta encoded, bit packed blocks in the layout of SIMD-BP128.
+//!
+//! Daniel Lemire and Leonid Boytsov. 2015. Decoding billions of integers per
+//! second through vectorization. Software: Practice and Experience 45, 1,
+//! 1–29. https://doi.org/10.1002/spe.2203
+//!
+//! Each block of 128 elements is stored as four interleaved lanes of 32
+//! values, where lane `j` holds elements `j, j+4, j+8, ...`. Deltas are taken
+//! between elements four positions apart (D4 coding), so decoding a block is a
+//! running sum over 4-wide vectors without any horizontal prefix sum. All
+//! deltas in a block are packed using the bit width of the largest delta.
+#[cfg(feature = "simd")]
+use std::simd::{*, num::SimdUint};
+
+use crate::Set;
+use super::{BlockSet, Block, BLOCK_LEN};
+
+const LANES: usize = 4;
+const VALUES_PER_LANE: usize = BLOCK_LEN / LANES;
+
+pub struct Bp128Set {
+    len: usize,
+    // Per block: the first element (which the first four deltas are relative
+    // to), the last element, the bit width and the offset into `words`.
+    bases: Vec<i32>,
+    maxes: Vec<i32>,
+    widths: Vec<u8>,
+    offsets: Vec<usize>,
+    words: Vec<u32>,
+}
+
+impl Bp128Set {
+    /// Size of the encoded set in bytes, excluding per-block metadata.
+    pub fn packed_bytes(&self) -> usize {
+        std::mem::size_of_val(self.words.as_slice())
+    }
+
+    fn encode_block(&mut self, block: &[i32]) {
+        debug_assert!(!block.is_empty() && block.len() <= BLOCK_LEN);
+
+        // Repeat the final element so padding encodes as zero deltas.
+        let last = *block.last().unwrap();
+        let mut padded = [last; BLOCK_LEN];
+        padded[..block.len()].copy_from_slice(block);
+
+        let base = padded[0];
+        let mut deltas = [0u32; BLOCK_LEN];
+        for i in 0..BLOCK_LEN {
+            let previous = if i < LANES { base } else { padded[i - LANES] };
+            deltas[i] = (padded[i] as u32).wrapping_sub(previous as u32);
+        }
+
+        let width = u32::BITS - deltas.iter().fold(0, |acc, &d| acc | d).leading_zeros();
+
+        let offset = self.words.len();
+        // A width of zero means every element equals `base`.
+        if width > 0 {
+            self.words.resize(offset + width as usize * LANES, 0);
+            pack(&deltas, width, &mut self.words[offset..]);
+        }
+
+        self.bases.push(base);
+        self.maxes.push(last);
+        self.widths.push(width as u8);
+        self.offsets.push(offset);
+    }
+}
+
+impl Set<i32> for Bp128Set {
+    fn from_sorted(sorted: &[i32]) -> Self {
+        let block_count = sorted.len().div_ceil(BLOCK_LEN);
+        let mut set = Self {
+            len: sorted.len(),
+            bases: Vec::with_capacity(block_count),
+            maxes: Vec::with_capacity(block_count),
+            widths: Vec::with_capacity(block_count),
+            offsets: Vec::with_capacity(block_count),
+            words: Vec::new(),
+        };
+
+        for block in sorted.chunks(BLOCK_LEN) {
+            set.encode_block(block);
+        }
+        set
+    }
+}
+
+impl BlockSet for Bp128Set {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn block_max(&self, index: usize) -> i32 {
+        self.maxes[index]
+    }
+
+    fn decode_block(&self, index: usize, out: &mut Block) -> usize {
+        let width = self.widths[index] as u32;
+        let base = self.bases[index];
+        let offset = self.offsets[index];
+        let words = &self.words[offset..offset + width as usize * LANES];
+
+        if width == 0 {
+            out.fill(base);
+        }
+        else {
+            unpack(words, width, base, out);
+        }
+
+        let remaining = self.len - index * BLOCK_LEN;
+        remaining.min(BLOCK_LEN)
+    }
+}
+
+fn pack(deltas: &[u32; BLOCK_LEN], width: u32, words: &mut [u32]) {
+    for (i, &delta) in deltas.iter().enumerate() {
+        let (lane, k) = (i % LANES, i / LANES);
+        let bit = k as u32 * width;
+        let (word, shift) = ((bit / u32::BITS) as usize, bit % u32::BITS);
+
+        words[word * LANES + lane] |= delta << shift;
+        if shift + width > u32::BITS {
+            words[(word + 1) * LANES + lane] |= delta >> (u32::BITS - shift);
+        }
+    }
+}
+
+#[cfg(feature = "simd")]
+#[inline]
+fn unpack(words: &[u32], width: u32, base: i32, out: &mut Block) {
+    let mask = u32x4::splat(if width == u32::BITS { !0 } else { (1 << width) - 1 });
+    let mut acc = u32x4::splat(base as u32);
+
+    for k in 0..VALUES_PER_LANE {
+        let bit = k as u32 * width;
+        let (word, shift) = ((bit / u32::BITS) as usize, bit % u32::BITS);
+
+        let mut value = u32x4::from_slice(&words[word * LANES..]) >> u32x4::splat(shift);
+        if shift + width > u32::BITS {
+            let high = u32x4::from_slice(&words[(word + 1) * LANES..]);
+            value |= high << u32x4::splat(u32::BITS - shift);
+        }
+        acc += value & mask;
+        acc.cast::<i32>().copy_to_slice(&mut out[k * LANES..]);
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+#[inline]
+fn unpack(words: &[u32], width: u32, base: i32, out: &mut Block) {
+    let mask = if width == u32::BITS { !0 } else { (1 << width) - 1 };
+    let mut acc = [base as u32; LANES];
+
+    for k in 0..VALUES_PER_LANE {
+        let bit = k as u32 * width;
+        let (word, shift) = ((bit / u32::BITS) as usize, bit % u32::BITS);
+
+        for lane in 0..LANES {
+            let mut value = words[word * LANES + lane] >> shift;
+            if shift + width > u32::BITS {
+                value |= words[(word + 1) * LANES + lane] << (u32::BITS - shift);
+            }
+            acc[lane] = acc[lane].wrapping_add(value & mask);
+            out[k * LANES + lane] = acc[lane] as i32;
+        }
+    }
+}
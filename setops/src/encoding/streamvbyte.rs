@@ -0,0 +1,211 @@
+//! Delta encoded blocks using Stream VByte.
+//!
+//! Daniel Lemire, Nathan Kurz and Christoph Rupp. 2018. Stream VByte: Faster
+//! byte-oriented integer compression. Information Processing Letters 130,
+//! 1–6. https://doi.org/10.1016/j.ipl.2017.09.011
+//!
+//! Each delta is stored using 1 to 4 bytes. The lengths of four consecutive
+//! deltas are packed into a control byte, and control bytes are stored
+//! separately from the data bytes so that a single shuffle expands the 4 deltas
+//! described by a control byte into a vector.
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+use {
+    std::simd::*,
+    crate::instructions::{shuffle_epi8, convert},
+};
+
+use crate::Set;
+use super::{BlockSet, Block, BLOCK_LEN};
+
+const GROUP: usize = 4;
+// Vectorised decoding always loads 16 data bytes.
+const DATA_PADDING: usize = 16;
+
+pub struct SvbSet {
+    len: usize,
+    // Per block: the first element (which the first delta is relative to), the
+    // last element and the offsets into `control` and `data`.
+    bases: Vec<i32>,
+    maxes: Vec<i32>,
+    control_offsets: Vec<usize>,
+    data_offsets: Vec<usize>,
+    control: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl SvbSet {
+    /// Size of the encoded set in bytes, excluding per-block metadata.
+    pub fn packed_bytes(&self) -> usize {
+        self.control.len() + self.data.len() - DATA_PADDING
+    }
+
+    fn encode_block(&mut self, block: &[i32]) {
+        debug_assert!(!block.is_empty() && block.len() <= BLOCK_LEN);
+
+        let base = block[0];
+        self.bases.push(base);
+        self.maxes.push(*block.last().unwrap());
+        self.control_offsets.push(self.control.len());
+        self.data_offsets.push(self.data.len());
+
+        let mut previous = base;
+        let mut deltas = block.iter().map(|&item| {
+            let delta = (item as u32).wrapping_sub(previous as u32);
+            previous = item;
+            delta
+        });
+
+        // Groups past the end of the block are padded with zero deltas.
+        for _ in 0..block.len().div_ceil(GROUP) {
+            let mut control = 0u8;
+            for i in 0..GROUP {
+                let delta = deltas.next().unwrap_or(0);
+                let bytes = byte_len(delta);
+                self.data.extend_from_slice(&delta.to_le_bytes()[..bytes]);
+                control |= ((bytes - 1) as u8) << (2 * i);
+            }
+            self.control.push(control);
+        }
+    }
+}
+
+impl Set<i32> for SvbSet {
+    fn from_sorted(sorted: &[i32]) -> Self {
+        let block_count = sorted.len().div_ceil(BLOCK_LEN);
+        let mut set = Self {
+            len: sorted.len(),
+            bases: Vec::with_capacity(block_count),
+            maxes: Vec::with_capacity(block_count),
+            control_offsets: Vec::with_capacity(block_count),
+            data_offsets: Vec::with_capacity(block_count),
+            control: Vec::new(),
+            data: Vec::new(),
+        };
+
+        for block in sorted.chunks(BLOCK_LEN) {
+            set.encode_block(block);
+        }
+        set.data.resize(set.data.len() + DATA_PADDING, 0);
+        set
+    }
+}
+
+impl BlockSet for SvbSet {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn block_max(&self, index: usize) -> i32 {
+        self.maxes[index]
+    }
+
+    fn decode_block(&self, index: usize, out: &mut Block) -> usize {
+        let len = (self.len - index * BLOCK_LEN).min(BLOCK_LEN);
+        let groups = len.div_ceil(GROUP);
+
+        let control_offset = self.control_offsets[index];
+        let control = &self.control[control_offset..control_offset + groups];
+        let data = &self.data[self.data_offsets[index]..];
+
+        decode_groups(control, data, self.bases[index], &mut out[..groups * GROUP]);
+        len
+    }
+}
+
+#[inline]
+fn byte_len(value: u32) -> usize {
+    match value {
+        0..=0xff => 1,
+        0x100..=0xffff => 2,
+        0x1_0000..=0xff_ffff => 3,
+        _ => 4,
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+#[inline]
+fn decode_groups(control: &[u8], data: &[u8], base: i32, out: &mut [i32]) {
+    let zero = u32x4::splat(0);
+    let mut previous = u32x4::splat(base as u32);
+    let mut position = 0;
+
+    for (&c, out) in control.iter().zip(out.chunks_exact_mut(GROUP)) {
+        let bytes = u8x16::from_slice(&data[position..position + 16]);
+        let deltas: u32x4 = convert(shuffle_epi8(bytes, SHUFFLE_MASKS[c as usize]));
+
+        // Prefix sum within the vector.
+        let sum = deltas + simd_swizzle!(zero, deltas, [0, 4, 5, 6]);
+        let sum = sum + simd_swizzle!(zero, sum, [0, 1, 4, 5]);
+        let values = sum + previous;
+
+        convert::<u32x4, i32x4>(values).copy_to_slice(out);
+        previous = simd_swizzle!(values, [3, 3, 3, 3]);
+        position += GROUP_BYTES[c as usize] as usize;
+    }
+}
+
+#[cfg(not(all(feature = "simd", target_feature = "ssse3")))]
+#[inline]
+fn decode_groups(control: &[u8], data: &[u8], base: i32, out: &mut [i32]) {
+    let mut previous = base as u32;
+    let mut position = 0;
+
+    for (&c, out) in control.iter().zip(out.chunks_exact_mut(GROUP)) {
+        for (i, item) in out.iter_mut().enumerate() {
+            let bytes = ((c >> (2 * i)) & 3) as usize + 1;
+            let mut delta = [0u8; 4];
+            delta[..bytes].copy_from_slice(&data[position..position + bytes]);
+            position += bytes;
+
+            previous = previous.wrapping_add(u32::from_le_bytes(delta));
+            *item = previous as i32;
+        }
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+const SHUFFLE_MASKS: [u8x16; 256] = gen_shuffle_masks();
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+const GROUP_BYTES: [u8; 256] = gen_group_bytes();
+
+// Moves the bytes of each delta into the low bytes of its 32-bit lane. Mask
+// bytes with the high bit set zero the destination byte.
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+const fn gen_shuffle_masks() -> [u8x16; 256] {
+    let mut result = [u8x16::from_array([0; 16]); 256];
+
+    let mut c = 0;
+    while c < 256 {
+        let mut mask = [0x80u8; 16];
+        let mut source = 0;
+        let mut i = 0;
+        while i < GROUP {
+            let bytes = ((c >> (2 * i)) & 3) + 1;
+            let mut b = 0;
+            while b < bytes {
+                mask[i * 4 + b] = source as u8;
+                source += 1;
+                b += 1;
+            }
+            i += 1;
+        }
+        result[c] = u8x16::from_array(mask);
+        c += 1;
+    }
+    result
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+const fn gen_group_bytes() -> [u8; 256] {
+    let mut result = [0u8; 256];
+    let mut c = 0;
+    while c < 256 {
+        let mut i = 0;
+        while i < GROUP {
+            result[c] += (((c >> (2 * i)) & 3) + 1) as u8;
+            i += 1;
+        }
+        c += 1;
+    }
+    result
+}
@@ -0,0 +1,159 @@
+//! Approximate intersection cardinality.
+//!
+//! These estimators trade accuracy for speed and are intended for choosing an
+//! intersection algorithm ahead of time, e.g., picking a merge for sets with
+//! a large output and galloping otherwise. Two approaches are provided:
+//! - `sample_intersection_card` probes a fixed number of evenly spaced
+//!   elements of the smaller set in the larger set.
+//! - `HyperLogLog` sketches estimate the size of the union, from which the
+//!   size of the intersection follows by inclusion-exclusion. Sketches may be
+//!   built once per set and reused across many pairs.
+use crate::element::IntElement;
+
+pub const MIN_PRECISION: u8 = 4;
+pub const MAX_PRECISION: u8 = 18;
+
+/// Estimates `|a ∩ b|` using HyperLogLog sketches with `2^precision`
+/// registers. The relative standard error of the union estimate is roughly
+/// `1.04 / sqrt(2^precision)`.
+///
+/// Since the inputs are slices, `|a|` and `|b|` are known exactly and only
+/// `|a ∪ b|` is estimated. As a result the error is relative to the union, so
+/// intersections which are small compared to their inputs are estimated
+/// poorly. `sample_intersection_card` is better suited to such cases.
+pub fn intersection_card<T: IntElement>(a: &[T], b: &[T], precision: u8) -> f64 {
+    let mut union = HyperLogLog::from_sorted(a, precision);
+    union.merge(&HyperLogLog::from_sorted(b, precision));
+    card_from_union(a.len(), b.len(), union.estimate())
+}
+
+/// Estimates `|a ∩ b|` given precomputed sketches of `a` and `b` containing
+/// `a_len` and `b_len` elements respectively.
+pub fn intersection_card_sketch(
+    a: &HyperLogLog, a_len: usize,
+    b: &HyperLogLog, b_len: usize) -> f64
+{
+    let mut union = a.clone();
+    union.merge(b);
+    card_from_union(a_len, b_len, union.estimate())
+}
+
+fn card_from_union(a_len: usize, b_len: usize, union: f64) -> f64 {
+    let max = a_len.min(b_len) as f64;
+    ((a_len + b_len) as f64 - union).clamp(0.0, max)
+}
+
+/// Estimates `|a ∩ b|` by searching for `samples` evenly spaced elements of the
+/// smaller set in the larger set and scaling the number of hits. The result is
+/// exact if `samples` is at least the length of the smaller set.
+///
+/// Sampled elements are increasing so each search only considers the part of
+/// the larger set after the previous match, giving a cost of
+/// `O(samples * log(large))`.
+pub fn sample_intersection_card<T: IntElement>(a: &[T], b: &[T], samples: usize) -> f64 {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+
+    let samples = samples.min(small.len());
+    if samples == 0 {
+        return 0.0;
+    }
+
+    let mut rest = large;
+    let mut hits = 0;
+    for i in 0..samples {
+        let target = small[i * small.len() / samples];
+        let position = rest.partition_point(|&item| item < target);
+        if position < rest.len() && rest[position] == target {
+            hits += 1;
+        }
+        rest = &rest[position..];
+    }
+
+    hits as f64 * small.len() as f64 / samples as f64
+}
+
+/// HyperLogLog cardinality sketch (Flajolet et al., 2007) using 64-bit hashes,
+/// so no large range correction is required.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    pub fn new(precision: u8) -> Self {
+        assert!(
+            (MIN_PRECISION..=MAX_PRECISION).contains(&precision),
+            "precision must be between {MIN_PRECISION} and {MAX_PRECISION}");
+
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    pub fn from_sorted<T: IntElement>(sorted: &[T], precision: u8) -> Self {
+        let mut sketch = Self::new(precision);
+        for &item in sorted {
+            sketch.insert(item);
+        }
+        sketch
+    }
+
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    #[inline]
+    pub fn insert<T: IntElement>(&mut self, item: T) {
+        let hash = splitmix64(item.to_u64_bits());
+        let index = (hash >> (64 - self.precision)) as usize;
+        // Set the lowest bit so the rank is bounded by 64 - precision + 1.
+        let rest = (hash << self.precision) | (1 << (self.precision - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    /// Combines `other` into `self` such that `self` sketches the union of
+    /// both inputs.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        assert!(self.precision == other.precision,
+            "cannot merge sketches of differing precision");
+
+        for (r, &o) in self.registers.iter_mut().zip(&other.registers) {
+            *r = (*r).max(o);
+        }
+    }
+
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+
+        let sum: f64 = self.registers.iter()
+            .map(|&r| (-(r as f64)).exp2())
+            .sum();
+        let raw = alpha * m * m / sum;
+
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        if raw <= 2.5 * m && zeros > 0 {
+            // Linear counting is more accurate for small cardinalities.
+            m * (m / zeros as f64).ln()
+        }
+        else {
+            raw
+        }
+    }
+}
+
+#[inline]
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,339 @@
+//! EWAH (Enhanced Word-Aligned Hybrid) compressed bitmaps, after Lemire et
+//! al., "Sorting improves word-aligned bitmap indexes". A bitmap is a stream
+//! of marker words, each followed by literal words. A marker encodes a run of
+//! clean words, all zeros or all ones, then the number of literal words copied
+//! as they are. Clustered sets thus take space in proportion to their runs
+//! rather than to their universe as plain bitmaps do, while AND still works a
+//! word at a time: clean runs of zeros skip the words of the other bitmap and
+//! runs of ones copy them, so only literals meeting literals are combined.
+//!
+//! Bit `i % 64` of uncompressed word `i / 64` is set if `i` is in the set, as
+//! in `bitmap`, and `from_bitmap` and `to_bitmap` convert between the two.
+
+use crate::visitor::{Visitor, VecWriter};
+
+// Marker layout: the run bit, then the run length and the number of literals.
+const RUN_LEN_BITS: u32 = 32;
+const MAX_RUN_LEN: u64 = (1 << RUN_LEN_BITS) - 1;
+const MAX_LITERALS: u64 = (1 << (u64::BITS - 1 - RUN_LEN_BITS)) - 1;
+
+#[inline]
+fn marker(run_bit: bool, run_len: u64, literals: u64) -> u64 {
+    run_bit as u64 | run_len << 1 | literals << (1 + RUN_LEN_BITS)
+}
+
+#[inline]
+fn run_bit(marker: u64) -> bool {
+    marker & 1 != 0
+}
+
+#[inline]
+fn run_len(marker: u64) -> u64 {
+    (marker >> 1) & MAX_RUN_LEN
+}
+
+#[inline]
+fn literal_count(marker: u64) -> u64 {
+    marker >> (1 + RUN_LEN_BITS)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EwahBitmap {
+    words: Vec<u64>,
+    // Uncompressed words encoded.
+    word_len: usize,
+}
+
+impl EwahBitmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds the bitmap of a sorted set, up to the word of its last element.
+    pub fn from_sorted(sorted: &[u32]) -> Self {
+        debug_assert!(crate::util::is_strictly_sorted(sorted));
+
+        let mut builder = Builder::default();
+        let mut i = 0;
+        while i < sorted.len() {
+            let word_index = sorted[i] as usize / 64;
+            builder.push_clean(false, (word_index - builder.bitmap.word_len) as u64);
+
+            let mut word = 0u64;
+            while i < sorted.len() && sorted[i] as usize / 64 == word_index {
+                word |= 1 << (sorted[i] % 64);
+                i += 1;
+            }
+            builder.push_literal(word);
+        }
+        builder.bitmap
+    }
+
+    pub fn from_bitmap(bitmap: &[u64]) -> Self {
+        let mut builder = Builder::default();
+        for &word in bitmap {
+            builder.push_literal(word);
+        }
+        builder.bitmap
+    }
+
+    /// The uncompressed bitmap, of `word_len` words.
+    pub fn to_bitmap(&self) -> Vec<u64> {
+        let mut bitmap = Vec::with_capacity(self.word_len);
+        self.for_each_word(|word| bitmap.push(word));
+        bitmap
+    }
+
+    pub fn to_sorted_set(&self) -> Vec<u32> {
+        let mut writer = VecWriter::with_capacity(self.len());
+        self.visit(&mut writer);
+        writer.into()
+    }
+
+    /// Visits the elements in ascending order.
+    pub fn visit<V: Visitor<u32>>(&self, visitor: &mut V) {
+        let mut base = 0u32;
+        self.for_each_word(|mut word| {
+            while word != 0 {
+                visitor.visit(base | word.trailing_zeros());
+                word &= word - 1;
+            }
+            base = base.wrapping_add(64);
+        });
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        let mut len = 0;
+        let mut cursor = Cursor::new(&self.words);
+        while !cursor.is_done() {
+            if cursor.run_bit {
+                len += cursor.run as usize * 64;
+            }
+            len += cursor.literals.iter().map(|w| w.count_ones() as usize).sum::<usize>();
+            cursor.skip(cursor.run + cursor.literals.len() as u64);
+        }
+        len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of uncompressed words, i.e., `bitmap::words` of the universe.
+    pub fn word_len(&self) -> usize {
+        self.word_len
+    }
+
+    /// Size of the compressed bitmap in bytes.
+    pub fn size_bytes(&self) -> usize {
+        std::mem::size_of_val(self.words.as_slice())
+    }
+
+    /// The intersection of `self` and `other`. Words past the end of the
+    /// shorter bitmap are empty.
+    pub fn and(&self, other: &Self) -> Self {
+        let mut builder = Builder::default();
+        and_words(&self.words, &other.words, &mut builder);
+        builder.bitmap
+    }
+
+    /// Like `and`, writing into `out` to reuse its allocation.
+    pub fn and_into(&self, other: &Self, out: &mut Self) {
+        let mut builder = Builder { bitmap: std::mem::take(out), marker: None };
+        builder.bitmap.words.clear();
+        builder.bitmap.word_len = 0;
+        and_words(&self.words, &other.words, &mut builder);
+        *out = builder.bitmap;
+    }
+
+    /// Number of elements in the intersection, without building it.
+    pub fn and_len(&self, other: &Self) -> usize {
+        let mut counter = WordCounter(0);
+        and_words(&self.words, &other.words, &mut counter);
+        counter.0
+    }
+
+    fn for_each_word(&self, mut f: impl FnMut(u64)) {
+        let mut cursor = Cursor::new(&self.words);
+        while !cursor.is_done() {
+            let clean = if cursor.run_bit { u64::MAX } else { 0 };
+            (0..cursor.run).for_each(|_| f(clean));
+            cursor.literals.iter().for_each(|&w| f(w));
+            cursor.skip(cursor.run + cursor.literals.len() as u64);
+        }
+    }
+}
+
+// Receives the words of a result in order.
+trait WordSink {
+    fn push_clean(&mut self, run_bit: bool, count: u64);
+    fn push_literal(&mut self, word: u64);
+}
+
+// Appends words to a bitmap, merging clean words into runs.
+#[derive(Default)]
+struct Builder {
+    bitmap: EwahBitmap,
+    // Index of the last marker.
+    marker: Option<usize>,
+}
+
+impl Builder {
+    fn new_marker(&mut self, run_bit: bool) -> usize {
+        self.bitmap.words.push(marker(run_bit, 0, 0));
+        let index = self.bitmap.words.len() - 1;
+        self.marker = Some(index);
+        index
+    }
+}
+
+impl WordSink for Builder {
+    fn push_clean(&mut self, bit: bool, mut count: u64) {
+        self.bitmap.word_len += count as usize;
+        while count > 0 {
+            // Extend the last marker if no literals follow it yet.
+            let index = match self.marker {
+                Some(i) if literal_count(self.bitmap.words[i]) == 0
+                    && (run_len(self.bitmap.words[i]) == 0
+                        || run_bit(self.bitmap.words[i]) == bit)
+                    && run_len(self.bitmap.words[i]) < MAX_RUN_LEN => i,
+                _ => self.new_marker(bit),
+            };
+            let current = run_len(self.bitmap.words[index]);
+            let added = count.min(MAX_RUN_LEN - current);
+            self.bitmap.words[index] = marker(bit, current + added, 0);
+            count -= added;
+        }
+    }
+
+    fn push_literal(&mut self, word: u64) {
+        if word == 0 || word == u64::MAX {
+            return self.push_clean(word != 0, 1);
+        }
+        let index = match self.marker {
+            Some(i) if literal_count(self.bitmap.words[i]) < MAX_LITERALS => i,
+            _ => self.new_marker(false),
+        };
+        self.bitmap.words[index] += 1 << (1 + RUN_LEN_BITS);
+        self.bitmap.words.push(word);
+        self.bitmap.word_len += 1;
+    }
+}
+
+// Counts the elements of the result.
+struct WordCounter(usize);
+
+impl WordSink for WordCounter {
+    fn push_clean(&mut self, run_bit: bool, count: u64) {
+        if run_bit {
+            self.0 += count as usize * 64;
+        }
+    }
+
+    fn push_literal(&mut self, word: u64) {
+        self.0 += word.count_ones() as usize;
+    }
+}
+
+// Position in a compressed bitmap: what is left of the current marker's run
+// and literals.
+struct Cursor<'a> {
+    words: &'a [u64],
+    // Index of the next marker.
+    next: usize,
+    run_bit: bool,
+    run: u64,
+    literals: &'a [u64],
+}
+
+impl<'a> Cursor<'a> {
+    fn new(words: &'a [u64]) -> Self {
+        let mut cursor = Self { words, next: 0, run_bit: false, run: 0, literals: &[] };
+        cursor.advance();
+        cursor
+    }
+
+    // Moves past exhausted markers.
+    fn advance(&mut self) {
+        while self.run == 0 && self.literals.is_empty() && self.next < self.words.len() {
+            let marker = self.words[self.next];
+            let literals = literal_count(marker) as usize;
+            self.run_bit = run_bit(marker);
+            self.run = run_len(marker);
+            self.literals = &self.words[self.next + 1..self.next + 1 + literals];
+            self.next += 1 + literals;
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.run == 0 && self.literals.is_empty()
+    }
+
+    // Skips up to `count` words, returning how many there were.
+    fn skip(&mut self, count: u64) -> u64 {
+        self.copy_into(count, &mut ())
+    }
+
+    // Writes up to `count` words to `sink`, returning how many there were.
+    fn copy_into(&mut self, count: u64, sink: &mut impl WordSink) -> u64 {
+        let mut left = count;
+        while left > 0 && !self.is_done() {
+            let run = left.min(self.run);
+            if run > 0 {
+                sink.push_clean(self.run_bit, run);
+                self.run -= run;
+                left -= run;
+            }
+            let literals = (left as usize).min(self.literals.len());
+            for &word in &self.literals[..literals] {
+                sink.push_literal(word);
+            }
+            self.literals = &self.literals[literals..];
+            left -= literals as u64;
+            self.advance();
+        }
+        count - left
+    }
+}
+
+// Discards words, for skipping.
+impl WordSink for () {
+    fn push_clean(&mut self, _run_bit: bool, _count: u64) {}
+    fn push_literal(&mut self, _word: u64) {}
+}
+
+// Word-aligned AND of two compressed bitmaps, up to the end of the shorter.
+fn and_words(words_a: &[u64], words_b: &[u64], sink: &mut impl WordSink) {
+    let mut a = Cursor::new(words_a);
+    let mut b = Cursor::new(words_b);
+
+    while !a.is_done() && !b.is_done() {
+        if a.run > 0 || b.run > 0 {
+            // The cursor in a run, preferring a run of zeros.
+            let (run, other) = match (a.run > 0, b.run > 0) {
+                (true, true) if !b.run_bit => (&mut b, &mut a),
+                (true, _) => (&mut a, &mut b),
+                _ => (&mut b, &mut a),
+            };
+            let count = run.run;
+            let done = if run.run_bit {
+                other.copy_into(count, sink)
+            }
+            else {
+                let skipped = other.skip(count);
+                sink.push_clean(false, skipped);
+                skipped
+            };
+            run.skip(done);
+        }
+        else {
+            let count = a.literals.len().min(b.literals.len());
+            for (&word_a, &word_b) in a.literals[..count].iter().zip(&b.literals[..count]) {
+                sink.push_literal(word_a & word_b);
+            }
+            a.skip(count as u64);
+            b.skip(count as u64);
+        }
+    }
+}
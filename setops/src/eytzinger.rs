@@ -0,0 +1,102 @@
+//! Eytzinger sets store a sorted set in the breadth-first order of a complete
+//! binary search tree: the root at index 1 and the children of node `k` at
+//! `2k` and `2k + 1`. Searching descends with `k = 2k + (node < target)`, a
+//! branchless step whose next few levels lie in neighbouring cache lines, and
+//! the steps of several searches can run in lockstep in SIMD lanes.
+//!
+//! Paul-Virak Khuong and Pat Morin. 2017. Array Layouts for Comparison-Based
+//! Searching. ACM J. Exp. Algorithmics 22. https://doi.org/10.1145/3053370
+
+use crate::Set;
+
+pub struct EytzingerSet<T> {
+    // Nodes in breadth-first order from index 1. Index 0 is unused.
+    layout: Vec<T>,
+}
+
+impl<T: Copy + Default> Set<T> for EytzingerSet<T> {
+    fn from_sorted(sorted: &[T]) -> Self {
+        Self { layout: eytzinger_layout(sorted) }
+    }
+}
+
+impl<T: Copy + Ord> EytzingerSet<T> {
+    pub fn len(&self) -> usize {
+        self.layout.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Nodes in breadth-first order, where node `k` is at index `k` and index
+    /// 0 is unused.
+    pub fn layout(&self) -> &[T] {
+        &self.layout
+    }
+
+    /// Returns the node holding the first element not less than `target`, or
+    /// 0 if every element is less.
+    #[inline]
+    pub fn lower_bound(&self, target: T) -> usize {
+        let mut k = 1;
+        while k < self.layout.len() {
+            k = 2 * k + (self.layout[k] < target) as usize;
+        }
+        lower_bound_node(k)
+    }
+
+    pub fn contains(&self, target: T) -> bool {
+        let k = self.lower_bound(target);
+        k != 0 && self.layout[k] == target
+    }
+
+    /// The elements in sorted order.
+    pub fn to_sorted(&self) -> Vec<T> {
+        let mut sorted = Vec::with_capacity(self.len());
+        // In-order traversal: descend left as far as possible, then step to
+        // the right child or back up past nodes reached from the right.
+        let mut k = 1;
+        while k < self.layout.len() {
+            k *= 2;
+        }
+        for _ in 0..self.len() {
+            k = lower_bound_node(k);
+            sorted.push(self.layout[k]);
+            k = 2 * k + 1;
+            while k < self.layout.len() {
+                k *= 2;
+            }
+        }
+        sorted
+    }
+}
+
+/// Converts the node reached once a descent leaves the tree into the node of
+/// the lower bound: the descent last went left (`k` even) at that node, so
+/// undo the trailing right steps and one left step.
+#[inline]
+pub fn lower_bound_node(k: usize) -> usize {
+    k >> (k.trailing_ones() + 1)
+}
+
+/// Arranges `sorted` in Eytzinger order, with an unused element at index 0.
+pub fn eytzinger_layout<T: Copy + Default>(sorted: &[T]) -> Vec<T> {
+    let mut layout = vec![T::default(); sorted.len() + 1];
+    let mut next = sorted.iter();
+    fill(&mut layout, &mut next, 1);
+    layout
+}
+
+// Visits nodes in order, handing each the next sorted element.
+fn fill<'a, T, I>(layout: &mut [T], sorted: &mut I, k: usize)
+where
+    T: Copy + 'a,
+    I: Iterator<Item = &'a T>,
+{
+    if k < layout.len() {
+        fill(layout, sorted, 2 * k);
+        layout[k] = *sorted.next().unwrap();
+        fill(layout, sorted, 2 * k + 1);
+    }
+}
@@ -0,0 +1,132 @@
+//! Hybrid sets store each chunk of 2^16 values (sharing the high 16 bits) as
+//! either a sorted array or a bitmap depending on how many elements the chunk
+//! holds, in the style of Roaring bitmaps.
+//!
+//! Daniel Lemire, Gregory Ssi-Yan-Kai and Owen Kaser. 2016. Consistently faster
+//! and smaller compressed bitmaps with Roaring. Software: Practice and
+//! Experience 46, 11, 1547–1569. https://doi.org/10.1002/spe.2402
+
+use crate::Set;
+
+pub const CHUNK_BITS: u32 = 16;
+pub const CHUNK_LEN: usize = 1 << CHUNK_BITS;
+pub const BITMAP_WORDS: usize = CHUNK_LEN / u64::BITS as usize;
+
+/// Chunks with more elements than this are stored as bitmaps by default. A
+/// bitmap takes 8KiB, the same as an array of 2048 i32s.
+pub const DEFAULT_BITMAP_THRESHOLD: usize = 2048;
+
+pub type Bitmap = [u64; BITMAP_WORDS];
+
+pub enum Container {
+    Array(Vec<i32>),
+    Bitmap(Box<Bitmap>),
+}
+
+pub struct HybridSet {
+    len: usize,
+    // High 16 bits of the elements in each chunk, in ascending order.
+    keys: Vec<i32>,
+    containers: Vec<Container>,
+}
+
+impl HybridSet {
+    /// Builds a set storing chunks with more than `bitmap_threshold` elements
+    /// as bitmaps. A threshold of 0 stores every chunk as a bitmap, and
+    /// `CHUNK_LEN` stores every chunk as an array.
+    pub fn from_sorted_with_threshold(sorted: &[i32], bitmap_threshold: usize) -> Self {
+        let mut set = Self {
+            len: sorted.len(),
+            keys: Vec::new(),
+            containers: Vec::new(),
+        };
+
+        let mut rest = sorted;
+        while let Some(&first) = rest.first() {
+            let key = chunk_key(first);
+            let len = rest.partition_point(|&item| chunk_key(item) == key);
+            let (chunk, remaining) = rest.split_at(len);
+
+            let container = if chunk.len() > bitmap_threshold {
+                let mut bitmap = Box::new([0u64; BITMAP_WORDS]);
+                for &item in chunk {
+                    let low = chunk_low(item);
+                    bitmap[low / 64] |= 1 << (low % 64);
+                }
+                Container::Bitmap(bitmap)
+            }
+            else {
+                Container::Array(chunk.to_vec())
+            };
+
+            set.keys.push(key);
+            set.containers.push(container);
+            rest = remaining;
+        }
+        set
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn keys(&self) -> &[i32] {
+        &self.keys
+    }
+
+    pub fn containers(&self) -> &[Container] {
+        &self.containers
+    }
+
+    /// Number of chunks stored as bitmaps.
+    pub fn bitmap_count(&self) -> usize {
+        self.containers.iter()
+            .filter(|c| matches!(c, Container::Bitmap(_)))
+            .count()
+    }
+
+    pub fn to_sorted_set(&self) -> Vec<i32> {
+        let mut result = Vec::with_capacity(self.len);
+        for (&key, container) in self.keys.iter().zip(&self.containers) {
+            match container {
+                Container::Array(array) => result.extend_from_slice(array),
+                Container::Bitmap(bitmap) =>
+                    for_each_bit(bitmap, key, |item| result.push(item)),
+            }
+        }
+        result
+    }
+}
+
+impl Set<i32> for HybridSet {
+    fn from_sorted(sorted: &[i32]) -> Self {
+        Self::from_sorted_with_threshold(sorted, DEFAULT_BITMAP_THRESHOLD)
+    }
+}
+
+#[inline]
+pub fn chunk_key(item: i32) -> i32 {
+    item >> CHUNK_BITS
+}
+
+#[inline]
+pub fn chunk_low(item: i32) -> usize {
+    item as usize & (CHUNK_LEN - 1)
+}
+
+/// Calls `f` with the element of each set bit of a chunk's bitmap in order.
+#[inline]
+pub fn for_each_bit(bitmap: &Bitmap, key: i32, mut f: impl FnMut(i32)) {
+    for (i, &word) in bitmap.iter().enumerate() {
+        let mut word = word;
+        while word != 0 {
+            let bit = word.trailing_zeros() as usize;
+            f((key << CHUNK_BITS) | (i * 64 + bit) as i32);
+            word &= word - 1;
+        }
+    }
+}
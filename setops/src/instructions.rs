@@ -29,6 +29,7 @@ where
     T: SimdElement + PartialOrd,
     LaneCount<LANES>: SupportedLaneCount,
 {
+    crate::instrument::count_vector_loads(1);
     unsafe { std::ptr::read_unaligned(src as *const _ as *const Simd<T, LANES>) }
 }
 
@@ -38,9 +39,23 @@ where
     T: SimdElement,
     LaneCount<LANES>: SupportedLaneCount,
 {
+    crate::instrument::count_vector_loads(1);
     unsafe { std::ptr::read_unaligned(src as *const _ as *const Simd<T, LANES>) }
 }
 
+/// Aligned counterpart of `load_unsafe`. `src` must be aligned to the size
+/// of the vector.
+#[inline]
+pub unsafe fn load_aligned_unsafe<T, const LANES: usize>(src: *const T) -> Simd<T, LANES>
+where
+    T: SimdElement,
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    debug_assert!(src as usize % std::mem::size_of::<Simd<T, LANES>>() == 0);
+    crate::instrument::count_vector_loads(1);
+    unsafe { std::ptr::read(src as *const Simd<T, LANES>) }
+}
+
 #[inline]
 pub fn store<T, const LANES: usize>(v: Simd<T, LANES>, out: &mut [T])
 where
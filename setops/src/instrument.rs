@@ -0,0 +1,180 @@
+//! Counts of the work done by kernels, so that algorithmic work (how many
+//! comparisons and loads an algorithm needs) can be told apart from
+//! microarchitectural effects (how fast the machine performs them).
+//!
+//! Kernels call the `count_*` hooks, which compile to nothing without the
+//! `instrument` feature. Comparisons are counted by the scalar merges,
+//! galloping and the shuffling, broadcast and all-pairs kernels; vector loads
+//! by the loads of `instructions`, so by every kernel using them.
+//! Emitted vectors are counted by wrapping the visitor in `InstrumentVisitor`.
+//! Scalar galloping also counts its gallops and how far each overshoots the
+//! target (see `count_gallop`).
+//! Counts are kept per thread: `reset` them before a run and `take` them
+//! after.
+
+#[cfg(feature = "instrument")]
+use std::cell::Cell;
+#[cfg(feature = "simd")]
+use std::simd::*;
+
+use crate::visitor::{Visitor, Clearable, Reservable};
+#[cfg(feature = "simd")]
+use crate::visitor::{SimdVisitor4, SimdVisitor8, SimdVisitor16};
+
+/// Whether the hooks count, i.e., whether built with `instrument`.
+pub const ENABLED: bool = cfg!(feature = "instrument");
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkCounters {
+    /// Element comparisons, counting each lane of a vector comparison.
+    pub comparisons: u64,
+    /// Vector loads of input elements.
+    pub vector_loads: u64,
+    /// Vectors of results passed to an `InstrumentVisitor`, whether or not
+    /// any of their lanes matched.
+    pub emitted_vectors: u64,
+    /// Gallops of scalar galloping, one per element searched for.
+    pub gallops: u64,
+    /// Sum over the gallops of the elements between the target's position
+    /// and the last step, i.e., the range left for the binary search after
+    /// the target.
+    pub gallop_overshoot: u64,
+}
+
+#[cfg(feature = "instrument")]
+thread_local! {
+    static COUNTERS: Cell<WorkCounters> = const {
+        Cell::new(WorkCounters {
+            comparisons: 0, vector_loads: 0, emitted_vectors: 0, gallops: 0, gallop_overshoot: 0,
+        })
+    };
+}
+
+/// Zeroes the calling thread's counters.
+pub fn reset() {
+    take();
+}
+
+/// Returns the calling thread's counters and zeroes them. Always zero
+/// without `instrument`.
+pub fn take() -> WorkCounters {
+    #[cfg(feature = "instrument")]
+    return COUNTERS.with(|counters| counters.take());
+    #[cfg(not(feature = "instrument"))]
+    WorkCounters::default()
+}
+
+#[inline(always)]
+pub fn count_comparisons(count: u64) {
+    update(|counters| counters.comparisons += count);
+}
+
+#[inline(always)]
+pub fn count_vector_loads(count: u64) {
+    update(|counters| counters.vector_loads += count);
+}
+
+#[inline(always)]
+pub fn count_emitted_vectors(count: u64) {
+    update(|counters| counters.emitted_vectors += count);
+}
+
+#[inline(always)]
+pub fn count_gallop(overshoot: u64) {
+    update(|counters| {
+        counters.gallops += 1;
+        counters.gallop_overshoot += overshoot;
+    });
+}
+
+#[cfg(feature = "instrument")]
+#[inline(always)]
+fn update(update: impl FnOnce(&mut WorkCounters)) {
+    COUNTERS.with(|cell| {
+        let mut counters = cell.get();
+        update(&mut counters);
+        cell.set(counters);
+    });
+}
+
+#[cfg(not(feature = "instrument"))]
+#[inline(always)]
+fn update(_update: impl FnOnce(&mut WorkCounters)) {
+}
+
+/// Wraps another visitor, counting the vectors of results visited. Without
+/// `instrument` it only forwards.
+#[derive(Debug, Default)]
+pub struct InstrumentVisitor<V> {
+    inner: V,
+}
+
+impl<V> InstrumentVisitor<V> {
+    pub fn new(inner: V) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &V {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut V {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+}
+
+impl<T, V: Visitor<T>> Visitor<T> for InstrumentVisitor<V> {
+    #[inline]
+    fn visit(&mut self, value: T) {
+        self.inner.visit(value);
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<V: SimdVisitor4> SimdVisitor4 for InstrumentVisitor<V> {
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        count_emitted_vectors(1);
+        self.inner.visit_vector4(value, mask);
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<V: SimdVisitor8> SimdVisitor8 for InstrumentVisitor<V> {
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        count_emitted_vectors(1);
+        self.inner.visit_vector8(value, mask);
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<V: SimdVisitor16> SimdVisitor16 for InstrumentVisitor<V> {
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        count_emitted_vectors(1);
+        self.inner.visit_vector16(value, mask);
+    }
+}
+
+impl<V: Clearable> Clearable for InstrumentVisitor<V> {
+    fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+impl<V: Reservable> Reservable for InstrumentVisitor<V> {
+    fn reserve(&mut self, cardinality: usize) {
+        self.inner.reserve(cardinality);
+    }
+}
+
+impl<T, V: AsRef<[T]>> AsRef<[T]> for InstrumentVisitor<V> {
+    fn as_ref(&self) -> &[T] {
+        self.inner.as_ref()
+    }
+}
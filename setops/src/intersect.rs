@@ -3,39 +3,114 @@ mod galloping;
 mod svs;
 mod adaptive;
 mod std_set;
+#[cfg(feature = "shuffling")]
 mod shuffling;
 mod broadcast;
 mod lbk;
+#[cfg(feature = "galloping")]
 mod simd_galloping;
 mod bmiss;
+mod sttni;
 mod qfilter;
 mod qfilter_c;
 mod avx512;
 pub mod mono;
+#[cfg(feature = "fesia")]
 pub mod fesia;
+pub mod compressed;
+pub mod partition;
+pub mod hybrid;
+pub mod limit;
+pub mod cursor;
+pub mod payload;
+pub mod range;
+pub mod probe_cache;
+pub mod skip;
+pub mod batch;
+pub mod inplace;
+pub mod prefetch;
+pub mod versioned;
+pub mod union;
+pub mod bloom;
+pub mod multiset;
+#[cfg(feature = "simd")]
+pub mod weighted;
+mod eytzinger;
+mod simd_merge;
+mod tail;
+pub mod tiny;
+pub mod algorithm;
+#[cfg(feature = "simd")]
+pub mod dynamic;
 
 pub use {
     merge::*,
-    galloping::{galloping, binary_search_intersect, galloping_inplace, galloping_bsr},
+    galloping::{
+        galloping, binary_search_intersect, galloping_inplace, galloping_factor, GallopingFactor,
+        tune_gallop_factor, DEFAULT_GALLOP_FACTOR,
+    },
     adaptive::*,
     std_set::*,
     svs::*,
     bmiss::*,
+    sttni::*,
+    compressed::svb_probe,
+    hybrid::hybrid_intersect,
+    limit::{limit_2set, limit_kset, limit_galloping},
+    cursor::IntersectCursor,
+    payload::{payload_merge, payload_galloping},
+    range::intersect_range,
+    probe_cache::{ProbeCache, galloping_cached},
+    skip::{SkipSet, galloping_skip},
+    batch::BatchProbe,
+    eytzinger::eytzinger_probe,
+    inplace::{merge_inplace, IntersectInplace},
+    prefetch::{merge_prefetch, galloping_prefetch},
+    versioned::{versioned_intersect, DeltaAdapter},
+    union::union_intersect,
+    bloom::bloom_galloping,
+    multiset::{multiset_merge, multiset_intersect, MultisetLayers},
+    algorithm::{TwoSetAlgorithm, KSetAlgorithm},
 };
+pub(crate) use galloping::gallop;
+#[cfg(feature = "bsr")]
+pub use galloping::galloping_bsr;
 
+#[cfg(all(feature = "shuffling", target_feature = "ssse3"))]
+pub use shuffling::*;
+#[cfg(all(feature = "galloping", target_feature = "ssse3"))]
+pub use simd_galloping::*;
+#[cfg(feature = "simd")]
+pub use dynamic::{by_name, Intersect2Dyn, TwoSetAlgo, KSetAlgo};
 #[cfg(all(feature = "simd", target_feature = "ssse3"))]
 pub use {
-    shuffling::*,
     broadcast::*,
-    simd_galloping::*,
     qfilter::*,
     qfilter_c::qfilter_c,
     lbk::*,
+    payload::payload_shuffling_sse,
+    weighted::{weighted_intersect, SumScore, ProductScore},
+    simd_merge::{simd_merge_sse, simd_merge_sse_tail},
+    tail::shuffling_sse_tail,
+    tiny::tiny_sse,
+    inplace::shuffling_sse_inplace,
+};
+#[cfg(all(feature = "simd", target_feature = "avx2"))]
+pub use {
+    payload::payload_shuffling_avx2, eytzinger::eytzinger_probe_avx2,
+    simd_merge::{simd_merge_avx2, simd_merge_avx2_tail}, tail::shuffling_avx2_tail,
+    tiny::tiny_avx2,
+    inplace::shuffling_avx2_inplace,
 };
 #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-pub use avx512::*;
+pub use {
+    avx512::*, payload::payload_shuffling_avx512, eytzinger::eytzinger_probe_avx512,
+    inplace::shuffling_avx512_inplace, tail::shuffling_avx512_tail, tiny::tiny_avx512,
+};
 
-use crate::{visitor::VecWriter, bsr::{BsrVec, BsrRef}};
+use crate::{visitor::{VecWriter, HintedWriter}, util::is_strictly_sorted};
+#[cfg(feature = "bsr")]
+use crate::bsr::{BsrVec, BsrRef};
 
 pub type Intersect2<I, V> = fn(a: &I, b: &I, visitor: &mut V);
 pub type Intersect2C<I> = fn(a: &I, b: &I, result: &mut I) -> usize;
@@ -45,19 +120,47 @@ pub fn run_2set<T>(
     set_a: &[T],
     set_b: &[T],
     intersect: Intersect2<[T], VecWriter<T>>) -> Vec<T>
+where
+    T: Ord
 {
+    debug_assert!(is_strictly_sorted(set_a) && is_strictly_sorted(set_b),
+        "input sets must be sorted and deduplicated");
+
     let mut writer: VecWriter<T> = VecWriter::new();
     intersect(set_a, set_b, &mut writer);
     writer.into()
 }
 
+/// As `run_2set`, sizing the result from `expected_len`, a hint of its
+/// cardinality. A hint of at least the smaller set's length pre-allocates the
+/// whole result, avoiding growth checks while writing.
+pub fn run_2set_hinted<T>(
+    set_a: &[T],
+    set_b: &[T],
+    intersect: Intersect2<[T], HintedWriter<T>>,
+    expected_len: Option<usize>) -> Vec<T>
+where
+    T: Ord
+{
+    debug_assert!(is_strictly_sorted(set_a) && is_strictly_sorted(set_b),
+        "input sets must be sorted and deduplicated");
+
+    let bound = set_a.len().min(set_b.len());
+    let mut writer = HintedWriter::new(expected_len, bound);
+    intersect(set_a, set_b, &mut writer);
+    writer.into()
+}
+
 pub fn run_2set_c<T>(
     set_a: &[T],
     set_b: &[T],
     intersect: Intersect2C<[T]>) -> Vec<T>
 where
-    T: Default + Clone + Copy
+    T: Ord + Default + Clone + Copy
 {
+    debug_assert!(is_strictly_sorted(set_a) && is_strictly_sorted(set_b),
+        "input sets must be sorted and deduplicated");
+
     let len = set_a.len().min(set_b.len());
     let mut result = vec![T::default();len];
 
@@ -73,12 +176,15 @@ where
     S: AsRef<[T]>,
 {
     assert!(sets.len() >= 2);
+    debug_assert!(sets.iter().all(|s| is_strictly_sorted(s.as_ref())),
+        "input sets must be sorted and deduplicated");
 
     let mut writer: VecWriter<T> = VecWriter::new();
     intersect(sets, &mut writer);
     writer.into()
 }
 
+#[cfg(feature = "bsr")]
 pub fn run_2set_bsr<'a>(
     set_a: BsrRef<'a>,
     set_b: BsrRef<'a>,
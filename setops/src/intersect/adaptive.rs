@@ -75,15 +75,17 @@ where
     uppers.push(&smallest[small_partition+1..]);
 
     let mut match_count = 0;
+    // Set if a set ends before target, so only the lower parts can intersect.
+    let mut exhausted = false;
 
     for large_set in &sets[1..] {
         let large_set = large_set.as_ref();
         let large_partition = binary_search(large_set, target, 0, large_set.len() as isize - 1);
 
         if large_partition >= large_set.len() {
-            return;
+            exhausted = true;
         }
-        if large_set[large_partition] == target {
+        else if large_set[large_partition] == target {
             match_count += 1;
         }
 
@@ -91,11 +93,15 @@ where
         uppers.push(&large_set[large_partition..]);
     }
 
+    baezayates_k(&lowers, visitor);
+    if exhausted {
+        return;
+    }
+
     if match_count == sets.len() - 1 {
         visitor.visit(target);
     }
 
-    baezayates_k(&lowers, visitor);
     baezayates_k(&uppers, visitor);
 }
 
@@ -0,0 +1,257 @@
+//! Intersection algorithms as values, so that algorithms which build their own
+//! representation of each set (e.g., FESIA or hybrid sets) or which carry
+//! parameters can be driven the same way as plain functions on sorted arrays.
+//! `prepare` builds the representation of one sorted set and is typically left
+//! out of measurements, while `intersect` intersects prepared sets. Algorithms
+//! on sorted arrays borrow the input rather than copy it, so they run on the
+//! sets as they were allocated (e.g., placed in huge pages).
+
+use std::marker::PhantomData;
+
+use crate::{
+    intersect::{self, Intersect2, IntersectK},
+    encoding::BlockSet,
+    hybrid::HybridSet,
+    visitor::{Visitor, VecWriter},
+    Set,
+};
+
+pub trait TwoSetAlgorithm<T, V> {
+    type Prepared<'a> where T: 'a;
+
+    fn prepare<'a>(&self, set: &'a [T]) -> Self::Prepared<'a>;
+
+    fn intersect(&self, set_a: &Self::Prepared<'_>, set_b: &Self::Prepared<'_>, visitor: &mut V);
+}
+
+/// Sets of a k-set query are prepared together, so that algorithms on sorted
+/// arrays can borrow the slice of sets as it is.
+pub trait KSetAlgorithm<T, V> {
+    type Prepared<'a> where T: 'a;
+
+    fn prepare<'a>(&self, sets: &'a [Vec<T>]) -> Self::Prepared<'a>;
+
+    fn intersect(&self, sets: &Self::Prepared<'_>, visitor: &mut V);
+}
+
+/// A 2-set algorithm on sorted arrays, e.g., `Sorted2(intersect::galloping)`.
+pub struct Sorted2<T, V>(pub Intersect2<[T], V>);
+
+impl<T, V> TwoSetAlgorithm<T, V> for Sorted2<T, V> {
+    type Prepared<'a> = &'a [T] where T: 'a;
+
+    fn prepare<'a>(&self, set: &'a [T]) -> &'a [T] {
+        set
+    }
+
+    fn intersect(&self, set_a: &&[T], set_b: &&[T], visitor: &mut V) {
+        (self.0)(set_a, set_b, visitor)
+    }
+}
+
+/// A k-set algorithm on sorted arrays, e.g., `SortedK(intersect::svs)`.
+pub struct SortedK<T, V>(pub IntersectK<Vec<T>, V>);
+
+impl<T, V> KSetAlgorithm<T, V> for SortedK<T, V> {
+    type Prepared<'a> = &'a [Vec<T>] where T: 'a;
+
+    fn prepare<'a>(&self, sets: &'a [Vec<T>]) -> &'a [Vec<T>] {
+        sets
+    }
+
+    fn intersect(&self, sets: &&[Vec<T>], visitor: &mut V) {
+        (self.0)(sets, visitor)
+    }
+}
+
+/// Intersects sets of encoding `S` block by block with `intersect` (see
+/// `intersect::compressed::decode_intersect`).
+pub struct DecodeIntersect<S, V> {
+    intersect: Intersect2<[i32], V>,
+    encoding: PhantomData<S>,
+}
+
+impl<S, V> DecodeIntersect<S, V> {
+    pub fn new(intersect: Intersect2<[i32], V>) -> Self {
+        Self { intersect, encoding: PhantomData }
+    }
+}
+
+impl<S, V> TwoSetAlgorithm<i32, V> for DecodeIntersect<S, V>
+where
+    S: BlockSet + Set<i32>,
+    V: Visitor<i32>,
+{
+    type Prepared<'a> = S;
+
+    fn prepare(&self, set: &[i32]) -> S {
+        S::from_sorted(set)
+    }
+
+    fn intersect(&self, set_a: &S, set_b: &S, visitor: &mut V) {
+        intersect::compressed::decode_intersect(set_a, set_b, self.intersect, visitor)
+    }
+}
+
+/// Intersects hybrid sets with bitmap chunks above `bitmap_threshold`
+/// elements, using `intersect` for pairs of array chunks.
+pub struct Hybrid<V> {
+    pub bitmap_threshold: usize,
+    pub intersect: Intersect2<[i32], V>,
+}
+
+impl<V: Visitor<i32>> TwoSetAlgorithm<i32, V> for Hybrid<V> {
+    type Prepared<'a> = HybridSet;
+
+    fn prepare(&self, set: &[i32]) -> HybridSet {
+        HybridSet::from_sorted_with_threshold(set, self.bitmap_threshold)
+    }
+
+    fn intersect(&self, set_a: &HybridSet, set_b: &HybridSet, visitor: &mut V) {
+        intersect::hybrid_intersect(set_a, set_b, self.intersect, visitor)
+    }
+}
+
+/// Intersects the first set with the union of the rest, `A ∩ (B1 ∪ ... ∪
+/// Bn)`, using `intersect` for blocks of the union (see
+/// `intersect::union_intersect`).
+pub struct UnionIntersect<V> {
+    pub intersect: Intersect2<[i32], V>,
+}
+
+impl<V: Visitor<i32>> KSetAlgorithm<i32, V> for UnionIntersect<V> {
+    type Prepared<'a> = &'a [Vec<i32>];
+
+    fn prepare<'a>(&self, sets: &'a [Vec<i32>]) -> &'a [Vec<i32>] {
+        sets
+    }
+
+    fn intersect(&self, sets: &&[Vec<i32>], visitor: &mut V) {
+        if let Some((set_a, lists)) = sets.split_first() {
+            intersect::union_intersect(set_a, lists, self.intersect, visitor)
+        }
+    }
+}
+
+#[cfg(feature = "fesia")]
+pub use self::fesia::*;
+
+#[cfg(feature = "fesia")]
+mod fesia {
+    use std::marker::PhantomData;
+
+    use super::{TwoSetAlgorithm, KSetAlgorithm};
+    use crate::{
+        intersect::fesia::{FesiaIntersect, HashScale, SegmentIntersect, SetWithHashScale},
+        visitor::{Visitor, SimdVisitor4, SimdVisitor8, SimdVisitor16},
+    };
+
+    /// FESIA sets of type `F` intersected segment by segment with the kernels
+    /// of `I`, or with `intersect_k` for k sets. Preparing a set panics if
+    /// `try_from_sorted` fails.
+    pub struct FesiaAlgorithm<F, I> {
+        pub hash_scale: HashScale,
+        /// Skip 2-set intersections whose bitmaps share no bit (see
+        /// `FesiaIntersect::maybe_intersects`).
+        pub prefilter: bool,
+        types: PhantomData<(F, I)>,
+    }
+
+    impl<F, I> FesiaAlgorithm<F, I> {
+        pub fn new(hash_scale: HashScale) -> Self {
+            Self { hash_scale, prefilter: false, types: PhantomData }
+        }
+
+        pub fn with_prefilter(self, prefilter: bool) -> Self {
+            Self { prefilter, ..self }
+        }
+    }
+
+    impl<F, I, V> TwoSetAlgorithm<i32, V> for FesiaAlgorithm<F, I>
+    where
+        F: SetWithHashScale + FesiaIntersect,
+        I: SegmentIntersect,
+        V: SimdVisitor4 + SimdVisitor8 + SimdVisitor16,
+    {
+        type Prepared<'a> = F;
+
+        fn prepare(&self, set: &[i32]) -> F {
+            F::from_sorted(set, self.hash_scale)
+        }
+
+        fn intersect(&self, set_a: &F, set_b: &F, visitor: &mut V) {
+            if !self.prefilter || set_a.maybe_intersects(set_b) {
+                set_a.intersect::<V, I>(set_b, visitor)
+            }
+        }
+    }
+
+    impl<F, I, V> KSetAlgorithm<i32, V> for FesiaAlgorithm<F, I>
+    where
+        F: SetWithHashScale + FesiaIntersect + AsRef<F>,
+        V: Visitor<i32>,
+    {
+        type Prepared<'a> = Vec<F>;
+
+        fn prepare(&self, sets: &[Vec<i32>]) -> Vec<F> {
+            sets.iter().map(|set| F::from_sorted(set, self.hash_scale)).collect()
+        }
+
+        fn intersect(&self, sets: &Vec<F>, visitor: &mut V) {
+            F::intersect_k(sets, visitor)
+        }
+    }
+
+    /// FESIA sets of type `F` intersected by probing the larger set's hash
+    /// table with each element of the smaller set.
+    pub struct FesiaHashAlgorithm<F> {
+        pub hash_scale: HashScale,
+        set: PhantomData<F>,
+    }
+
+    impl<F> FesiaHashAlgorithm<F> {
+        pub fn new(hash_scale: HashScale) -> Self {
+            Self { hash_scale, set: PhantomData }
+        }
+    }
+
+    impl<F, V> TwoSetAlgorithm<i32, V> for FesiaHashAlgorithm<F>
+    where
+        F: SetWithHashScale + FesiaIntersect,
+        V: Visitor<i32>,
+    {
+        type Prepared<'a> = F;
+
+        fn prepare(&self, set: &[i32]) -> F {
+            F::from_sorted(set, self.hash_scale)
+        }
+
+        fn intersect(&self, set_a: &F, set_b: &F, visitor: &mut V) {
+            set_a.hash_intersect(set_b, visitor)
+        }
+    }
+}
+
+/// Prepares and intersects two sorted sets with `algorithm`.
+pub fn run_2set<T, A>(set_a: &[T], set_b: &[T], algorithm: &A) -> Vec<T>
+where
+    A: TwoSetAlgorithm<T, VecWriter<T>>,
+{
+    let (prepared_a, prepared_b) = (algorithm.prepare(set_a), algorithm.prepare(set_b));
+
+    let mut writer = VecWriter::new();
+    algorithm.intersect(&prepared_a, &prepared_b, &mut writer);
+    writer.into()
+}
+
+/// Prepares and intersects sorted sets with `algorithm`.
+pub fn run_kset<T, A>(sets: &[Vec<T>], algorithm: &A) -> Vec<T>
+where
+    A: KSetAlgorithm<T, VecWriter<T>>,
+{
+    let prepared = algorithm.prepare(sets);
+
+    let mut writer = VecWriter::new();
+    algorithm.intersect(&prepared, &mut writer);
+    writer.into()
+}
@@ -5,6 +5,7 @@ use std::{
     cmp::Ordering,
 };
 use crate::{
+    element::IntElement,
     visitor::{Visitor, SimdVisitor16},
     intersect, instructions::load_unsafe,
 };
@@ -20,11 +21,10 @@ use std::arch::asm;
 pub fn vp2intersect_emulation<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor16,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 16;
 
@@ -97,11 +97,10 @@ unsafe fn emulate_mm512_2intersect_epi32_mask(a: __m512i, b: __m512i) -> u16 {
 pub fn conflict_intersect<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor16,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 8;
 
@@ -157,11 +156,10 @@ unsafe fn conflict_intersect_vector(a: __m256i, b: __m256i) -> (__m512i, u16) {
 pub fn vp2intersect_emulation_branch<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor16,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 16;
 
@@ -221,11 +219,10 @@ where
 pub fn conflict_intersect_branch<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor16,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 8;
 
@@ -0,0 +1,99 @@
+//! Batched probes of one prepared large set by many small sets. Probing
+//! queries one at a time stalls on a cache miss into the large set at every
+//! block a query reaches. `intersect_batch` instead interleaves the queries
+//! of a batch: each round, every query searches the block it located in the
+//! round before, then locates the block of its next target in the sampled top
+//! level and prefetches it. The other queries' probes then hide the latency of
+//! each prefetch, and the top level is traversed while it stays in cache.
+
+use crate::{
+    intersect::{gallop, prefetch::prefetch, probe_cache::CachedSet, skip::SkipSet},
+    visitor::Visitor,
+};
+
+/// A prepared large set which many small sets can probe at once.
+pub trait BatchProbe<T> {
+    /// Intersects each of `smalls` with `self`, visiting the result of
+    /// `smalls[i]` with `visitors[i]`. Panics unless there is one visitor per
+    /// small set.
+    fn intersect_batch<V: Visitor<T>>(&self, smalls: &[&[T]], visitors: &mut [V]);
+}
+
+impl<T: Ord + Copy> BatchProbe<T> for SkipSet<'_, T> {
+    fn intersect_batch<V: Visitor<T>>(&self, smalls: &[&[T]], visitors: &mut [V]) {
+        probe_pipelined(self.elements(), self.skips(), self.interval(), smalls, visitors)
+    }
+}
+
+impl<T: Ord + Copy> BatchProbe<T> for CachedSet<'_, T> {
+    fn intersect_batch<V: Visitor<T>>(&self, smalls: &[&[T]], visitors: &mut [V]) {
+        let interval = 1 << self.cache.shift();
+        probe_pipelined(self.elements, self.cache.samples(), interval, smalls, visitors)
+    }
+}
+
+// Progress of one query of a batch.
+struct Probe<'a, T> {
+    small: &'a [T],
+    // Index in `small` of the next target to locate.
+    next: usize,
+    block: usize,
+    // Position in the large set of the query's last search, within `block`.
+    pos: usize,
+    // Target located in `block` whose elements were prefetched last round.
+    pending: Option<T>,
+}
+
+// `samples[i]` must be element `i * interval` of `elements`.
+fn probe_pipelined<T, V>(
+    elements: &[T], samples: &[T], interval: usize, smalls: &[&[T]], visitors: &mut [V])
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    assert_eq!(smalls.len(), visitors.len(), "one visitor is needed per small set");
+    debug_assert!(samples.len() == elements.len().div_ceil(interval),
+        "samples were not taken from the large set");
+
+    let mut probes: Vec<Probe<T>> = smalls.iter()
+        .map(|&small| Probe { small, next: 0, block: 0, pos: 0, pending: None })
+        .collect();
+
+    let mut in_flight = true;
+    while in_flight {
+        in_flight = false;
+        for (probe, visitor) in probes.iter_mut().zip(visitors.iter_mut()) {
+            if let Some(target) = probe.pending.take() {
+                let end = ((probe.block + 1) * interval).min(elements.len());
+                probe.pos += gallop(&elements[probe.pos..end], target);
+                if probe.pos < end && elements[probe.pos] == target {
+                    visitor.visit(target);
+                }
+            }
+
+            // Targets found in or before the samples need no search.
+            while let Some(&target) = probe.small.get(probe.next) {
+                probe.next += 1;
+                let sample = probe.block + gallop(&samples[probe.block..], target);
+
+                if sample < samples.len() && samples[sample] == target {
+                    visitor.visit(target);
+                    probe.block = sample;
+                    probe.pos = sample * interval;
+                    continue;
+                }
+                if sample == 0 {
+                    continue;
+                }
+                if sample - 1 != probe.block {
+                    probe.block = sample - 1;
+                    probe.pos = probe.block * interval + 1;
+                }
+                prefetch(elements, probe.pos);
+                probe.pending = Some(target);
+                in_flight = true;
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,25 @@
+//! Intersection for extreme skew, where `small` is much smaller than `large`.
+//! Each element of `small` is first looked up in a `BlockedBloom` filter of
+//! `large`, prepared once, and only filter hits gallop into `large`. Most
+//! misses then cost one cache line rather than a search, and as galloping
+//! resumes from the last hit, its searches span the gaps between hits.
+
+use crate::{bloom::BlockedBloom, intersect::gallop, visitor::Visitor};
+
+/// `filter` must hold every element of `large`.
+pub fn bloom_galloping<V>(small: &[i32], mut large: &[i32], filter: &BlockedBloom, visitor: &mut V)
+where
+    V: Visitor<i32>,
+{
+    for &target in small {
+        if !filter.contains(target) {
+            continue;
+        }
+
+        let base = gallop(large, target);
+        if base < large.len() && large[base] == target {
+            visitor.visit(target);
+        }
+        large = &large[base..];
+    }
+}
@@ -6,8 +6,9 @@ use std::{
 };
 
 use crate::{
+    element::IntElement,
     visitor::Visitor,
-    intersect,
+    intersect::{self, galloping::gallop},
     instructions::{
         load_unsafe,
         BYTE_CHECK_GROUP_A,
@@ -90,11 +91,10 @@ const WORD_CHECK_SHUFFLE_B23: [usize; 4] = [2,3,2,3];
 pub fn bmiss<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T>,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     use crate::instructions::convert;
 
@@ -172,11 +172,10 @@ const BMISS_STTNI_BC_ARRAY: [u8x16; 2] = [
 pub fn bmiss_sttni<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T>,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
     use crate::instructions::shuffle_epi8;
     #[cfg(target_arch = "x86")]
     use std::arch::x86::*;
@@ -236,6 +235,65 @@ where
         visitor)
 }
 
+/// BMiss for skewed inputs: for each block of `small`, gallops over `large` to
+/// the first element not less than the block's minimum, then compares the
+/// block against blocks of `large` until one reaches the block's maximum.
+#[cfg(all(feature = "simd", target_feature = "sse"))]
+pub fn bmiss_galloping<T, V>(small: &[T], large: &[T], visitor: &mut V)
+where
+    V: Visitor<T>,
+    T: IntElement<Lane = i32>,
+{
+    let ptr_small = T::as_lanes(small).as_ptr();
+    let ptr_large = T::as_lanes(large).as_ptr();
+
+    const W: usize = 4;
+
+    let st_small = (small.len() / W) * W;
+
+    let mut i_a: usize = 0;
+    let mut i_b: usize = 0;
+
+    'outer: while i_a < st_small {
+        let first = small[i_a];
+        let last = small[i_a + W - 1];
+
+        i_b += gallop(&large[i_b..], first);
+
+        let v_a: i32x4 = unsafe { load_unsafe(ptr_small.add(i_a)) };
+        loop {
+            if i_b + W > large.len() {
+                break 'outer;
+            }
+            let v_b: i32x4 = unsafe { load_unsafe(ptr_large.add(i_b)) };
+
+            let mask =
+                v_a.simd_eq(v_b) |
+                v_a.simd_eq(v_b.rotate_elements_left::<1>()) |
+                v_a.simd_eq(v_b.rotate_elements_left::<2>()) |
+                v_a.simd_eq(v_b.rotate_elements_left::<3>());
+
+            let mut bits = mask.to_bitmask();
+            while bits != 0 {
+                let p = bits.trailing_zeros() as usize;
+                visitor.visit(unsafe { *small.get_unchecked(i_a + p) });
+                bits &= bits - 1;
+            }
+
+            if unsafe { *large.get_unchecked(i_b + W - 1) } >= last {
+                break;
+            }
+            i_b += W;
+        }
+        i_a += W;
+    }
+
+    intersect::branchless_merge(
+        unsafe { small.get_unchecked(i_a..) },
+        unsafe { large.get_unchecked(i_b..) },
+        visitor)
+}
+
 #[inline]
 unsafe fn bmiss_advance<T: Ord>(left: &mut &[T], right: &mut &[T], s: usize) {
     let l = left.get_unchecked(s-1);
@@ -251,11 +309,10 @@ unsafe fn bmiss_advance<T: Ord>(left: &mut &[T], right: &mut &[T], s: usize) {
 pub fn bmiss_branch<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T>,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     use crate::instructions::convert;
 
@@ -373,11 +430,10 @@ where
 pub fn bmiss_sttni_branch<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T>,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
     use crate::instructions::shuffle_epi8;
     #[cfg(target_arch = "x86")]
     use std::arch::x86::*;
@@ -7,25 +7,30 @@ use std::{
 };
 
 use crate::{
-    visitor::{Visitor, SimdVisitor4, SimdBsrVisitor4},
-    intersect, instructions::load_unsafe,
-    bsr::BsrRef,
+    element::IntElement,
+    visitor::{Visitor, SimdVisitor4},
+    intersect, instrument::count_comparisons, instructions::load_unsafe,
     util::*,
 };
+#[cfg(feature = "bsr")]
+use crate::{visitor::SimdBsrVisitor4, bsr::BsrRef};
 #[cfg(target_feature = "avx2")]
-use crate::visitor::{SimdVisitor8, SimdBsrVisitor8};
+use crate::visitor::SimdVisitor8;
+#[cfg(all(feature = "bsr", target_feature = "avx2"))]
+use crate::visitor::SimdBsrVisitor8;
 #[cfg(target_feature = "avx512f")]
-use crate::visitor::{SimdVisitor16, SimdBsrVisitor16};
+use crate::visitor::SimdVisitor16;
+#[cfg(all(feature = "bsr", target_feature = "avx512f"))]
+use crate::visitor::SimdBsrVisitor16;
 
 #[cfg(target_feature = "ssse3")]
 pub fn broadcast_sse<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor4,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 4;
 
@@ -44,6 +49,7 @@ where
             v_a.simd_eq(i32x4::splat(*ptr_b.add(i_b + 3))),
         ]};
         let mask = or_4(masks);
+        count_comparisons((W * W) as u64);
 
         visitor.visit_vector4(v_a, mask.to_bitmask());
 
@@ -63,11 +69,10 @@ where
 pub fn broadcast_avx2<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor8,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 8;
 
@@ -90,6 +95,7 @@ where
             v_a.simd_eq(i32x8::splat(*ptr_b.add(i_b + 7))),
         ]};
         let mask = or_8(masks);
+        count_comparisons((W * W) as u64);
 
         visitor.visit_vector8(v_a, mask.to_bitmask());
 
@@ -109,11 +115,10 @@ where
 pub fn broadcast_avx512<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor16,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 16;
 
@@ -144,6 +149,7 @@ where
             v_a.simd_eq(i32x16::splat(*ptr_b.add(i_b + 15))),
         ]};
         let mask = or_16(masks);
+        count_comparisons((W * W) as u64);
 
         visitor.visit_vector16(v_a, mask.to_bitmask());
 
@@ -159,7 +165,7 @@ where
         visitor)
 }
 
-#[cfg(target_feature = "ssse3")]
+#[cfg(all(feature = "bsr", target_feature = "ssse3"))]
 pub fn broadcast_sse_bsr<'a, V>(
     set_a: BsrRef<'a>,
     set_b: BsrRef<'a>,
@@ -212,7 +218,7 @@ where
         visitor)
 }
 
-#[cfg(target_feature = "avx2")]
+#[cfg(all(feature = "bsr", target_feature = "avx2"))]
 pub fn broadcast_avx2_bsr<'a, V>(
     set_a: BsrRef<'a>,
     set_b: BsrRef<'a>,
@@ -274,7 +280,7 @@ where
         visitor)
 }
 
-#[cfg(target_feature = "avx512f")]
+#[cfg(all(feature = "bsr", target_feature = "avx512f"))]
 pub fn broadcast_avx512_bsr<'a, V>(
     set_a: BsrRef<'a>,
     set_b: BsrRef<'a>,
@@ -359,11 +365,10 @@ where
 pub fn broadcast_sse_branch<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor4,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 4;
 
@@ -382,6 +387,7 @@ where
                 v_a.simd_eq(i32x4::splat(*ptr_b.add(i_b + 3))),
             ]};
             let mask = or_4(masks);
+            count_comparisons((W * W) as u64);
 
             visitor.visit_vector4(v_a, mask.to_bitmask());
 
@@ -422,11 +428,10 @@ where
 pub fn broadcast_avx2_branch<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor8,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 8;
 
@@ -449,6 +454,7 @@ where
                 v_a.simd_eq(i32x8::splat(*ptr_b.add(i_b + 7))),
             ]};
             let mask = or_8(masks);
+            count_comparisons((W * W) as u64);
 
             visitor.visit_vector8(v_a, mask.to_bitmask());
 
@@ -489,11 +495,10 @@ where
 pub fn broadcast_avx512_branch<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor16,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 16;
 
@@ -524,6 +529,7 @@ where
                 v_a.simd_eq(i32x16::splat(*ptr_b.add(i_b + 15))),
             ]};
             let mask = or_16(masks);
+            count_comparisons((W * W) as u64);
 
             visitor.visit_vector16(v_a, mask.to_bitmask());
 
@@ -560,7 +566,7 @@ where
         visitor)
 }
 
-#[cfg(target_feature = "ssse3")]
+#[cfg(all(feature = "bsr", target_feature = "ssse3"))]
 pub fn broadcast_sse_bsr_branch<'a, V>(
     set_a: BsrRef<'a>,
     set_b: BsrRef<'a>,
@@ -637,7 +643,7 @@ where
         visitor)
 }
 
-#[cfg(target_feature = "avx2")]
+#[cfg(all(feature = "bsr", target_feature = "avx2"))]
 pub fn broadcast_avx2_bsr_branch<'a, V>(
     set_a: BsrRef<'a>,
     set_b: BsrRef<'a>,
@@ -722,7 +728,7 @@ where
         visitor)
 }
 
-#[cfg(target_feature = "avx512f")]
+#[cfg(all(feature = "bsr", target_feature = "avx512f"))]
 pub fn broadcast_avx512_bsr_branch<'a, V>(
     set_a: BsrRef<'a>,
     set_b: BsrRef<'a>,
@@ -0,0 +1,108 @@
+use crate::{
+    encoding::{BlockSet, SvbSet, BLOCK_LEN},
+    intersect::Intersect2,
+    visitor::Visitor,
+};
+
+/// Intersects two compressed sets by decoding one block of each at a time into
+/// stack buffers and running `intersect` on the decoded blocks. The block with
+/// the smaller maximum is advanced after each step, so each pair of
+/// overlapping blocks is intersected exactly once. Blocks of `set_b` lying
+/// entirely below the current block of `set_a` are skipped using their maximum
+/// without being decoded.
+pub fn decode_intersect<S, V>(
+    set_a: &S,
+    set_b: &S,
+    intersect: Intersect2<[i32], V>,
+    visitor: &mut V)
+where
+    S: BlockSet,
+    V: Visitor<i32>,
+{
+    let mut block_a = [0; BLOCK_LEN];
+    let mut block_b = [0; BLOCK_LEN];
+
+    let (count_a, count_b) = (set_a.block_count(), set_b.block_count());
+    let (mut i_a, mut i_b) = (0, 0);
+
+    let mut len_a = 0;
+    let mut len_b = 0;
+    let (mut decoded_a, mut decoded_b) = (usize::MAX, usize::MAX);
+
+    while i_a < count_a && i_b < count_b {
+        let (max_a, max_b) = (set_a.block_max(i_a), set_b.block_max(i_b));
+
+        if decoded_a != i_a {
+            len_a = set_a.decode_block(i_a, &mut block_a);
+            decoded_a = i_a;
+        }
+        // All of block b lies below block a.
+        if max_b < block_a[0] {
+            i_b += 1;
+            continue;
+        }
+        if decoded_b != i_b {
+            len_b = set_b.decode_block(i_b, &mut block_b);
+            decoded_b = i_b;
+        }
+        if max_a < block_b[0] {
+            i_a += 1;
+            continue;
+        }
+
+        intersect(&block_a[..len_a], &block_b[..len_b], visitor);
+
+        if max_a <= max_b {
+            i_a += 1;
+        }
+        if max_b <= max_a {
+            i_b += 1;
+        }
+    }
+}
+
+/// Intersects a small uncompressed set with a large Stream VByte compressed
+/// set. Only the blocks of `large` whose range may contain an element of
+/// `small` are decoded, found using the maximum of each block, so the cost is
+/// bounded by `small.len()` block decodes regardless of the size of `large`.
+pub fn svb_probe<V>(small: &[i32], large: &SvbSet, visitor: &mut V)
+where
+    V: Visitor<i32>,
+{
+    block_probe(small, large, visitor)
+}
+
+/// `svb_probe` for any block encoded set.
+pub fn block_probe<S, V>(small: &[i32], large: &S, visitor: &mut V)
+where
+    S: BlockSet,
+    V: Visitor<i32>,
+{
+    let mut block = [0; BLOCK_LEN];
+    let block_count = large.block_count();
+
+    let mut i_block = 0;
+    let mut decoded = usize::MAX;
+    let mut len = 0;
+    let mut position = 0;
+
+    for &target in small {
+        while i_block < block_count && large.block_max(i_block) < target {
+            i_block += 1;
+        }
+        if i_block == block_count {
+            break;
+        }
+
+        if decoded != i_block {
+            len = large.decode_block(i_block, &mut block);
+            decoded = i_block;
+            position = 0;
+        }
+
+        position += block[position..len].partition_point(|&item| item < target);
+        if position < len && block[position] == target {
+            visitor.visit(target);
+        }
+    }
+}
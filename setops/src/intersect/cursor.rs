@@ -0,0 +1,113 @@
+//! Resumable intersection for interactive use. An `IntersectCursor` visits up
+//! to a given number of results, returns control, and later continues from the
+//! positions it saved in both sets, e.g., to page through a large result.
+
+use crate::{
+    intersect::galloping::gallop,
+    visitor::Visitor,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorMethod {
+    /// Branchless merge, for sets of similar size.
+    Merge,
+    /// Galloping from each element of `set_a` over `set_b`, for a small
+    /// `set_a` and a large `set_b`.
+    Galloping,
+}
+
+/// Saved state of a 2-set intersection of sorted arrays.
+#[derive(Debug, Clone)]
+pub struct IntersectCursor<'a, T> {
+    set_a: &'a [T],
+    set_b: &'a [T],
+    idx_a: usize,
+    idx_b: usize,
+    method: CursorMethod,
+}
+
+impl<'a, T> IntersectCursor<'a, T>
+where
+    T: Ord + Copy,
+{
+    pub fn new(set_a: &'a [T], set_b: &'a [T], method: CursorMethod) -> Self {
+        Self { set_a, set_b, idx_a: 0, idx_b: 0, method }
+    }
+
+    pub fn merge(set_a: &'a [T], set_b: &'a [T]) -> Self {
+        Self::new(set_a, set_b, CursorMethod::Merge)
+    }
+
+    pub fn galloping(small: &'a [T], large: &'a [T]) -> Self {
+        Self::new(small, large, CursorMethod::Galloping)
+    }
+
+    /// Visits up to `limit` further results in sorted order, returning how
+    /// many were visited. Fewer than `limit` means the cursor is done.
+    pub fn resume<V: Visitor<T>>(&mut self, limit: usize, visitor: &mut V) -> usize {
+        match self.method {
+            CursorMethod::Merge => self.resume_merge(limit, visitor),
+            CursorMethod::Galloping => self.resume_galloping(limit, visitor),
+        }
+    }
+
+    /// Visits all remaining results, returning how many were visited.
+    pub fn finish<V: Visitor<T>>(&mut self, visitor: &mut V) -> usize {
+        self.resume(usize::MAX, visitor)
+    }
+
+    /// Whether no results remain.
+    pub fn is_done(&self) -> bool {
+        self.idx_a >= self.set_a.len() || self.idx_b >= self.set_b.len()
+    }
+
+    /// Indices into `set_a` and `set_b` of the next elements to compare.
+    pub fn position(&self) -> (usize, usize) {
+        (self.idx_a, self.idx_b)
+    }
+
+    fn resume_merge<V: Visitor<T>>(&mut self, limit: usize, visitor: &mut V) -> usize {
+        let (set_a, set_b) = (self.set_a, self.set_b);
+        let (mut idx_a, mut idx_b) = (self.idx_a, self.idx_b);
+        let mut count = 0;
+
+        while count < limit && idx_a < set_a.len() && idx_b < set_b.len() {
+            let value_a = set_a[idx_a];
+            let value_b = set_b[idx_b];
+
+            if value_a == value_b {
+                visitor.visit(value_a);
+                count += 1;
+                idx_a += 1;
+                idx_b += 1;
+            } else {
+                idx_a += (value_a < value_b) as usize;
+                idx_b += (value_b < value_a) as usize;
+            }
+        }
+
+        (self.idx_a, self.idx_b) = (idx_a, idx_b);
+        count
+    }
+
+    fn resume_galloping<V: Visitor<T>>(&mut self, limit: usize, visitor: &mut V) -> usize {
+        let (small, large) = (self.set_a, self.set_b);
+        let (mut idx_a, mut idx_b) = (self.idx_a, self.idx_b);
+        let mut count = 0;
+
+        while count < limit && idx_a < small.len() && idx_b < large.len() {
+            let target = small[idx_a];
+            idx_b += gallop(&large[idx_b..], target);
+
+            if idx_b < large.len() && large[idx_b] == target {
+                visitor.visit(target);
+                count += 1;
+                idx_b += 1;
+            }
+            idx_a += 1;
+        }
+
+        (self.idx_a, self.idx_b) = (idx_a, idx_b);
+        count
+    }
+}
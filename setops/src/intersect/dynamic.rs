@@ -0,0 +1,345 @@
+//! Choosing 2-set algorithms on sorted arrays by name at runtime, e.g., from a
+//! configuration file. `lookup` returns the algorithm instantiated for a given
+//! visitor, and `by_name` returns a type-erased handle which needs no visitor.
+//! `TwoSetAlgo` and `KSetAlgo` enumerate the 2-set and k-set algorithms, so
+//! each can be run in turn. Only algorithms enabled by the target features of
+//! the build are available.
+
+use std::fmt;
+
+use crate::{
+    intersect::{self, Intersect2, IntersectK},
+    visitor::{Visitor, VecWriter, Counter, SimdVisitor4, SimdVisitor8, SimdVisitor16},
+};
+
+/// A 2-set intersection algorithm chosen at runtime.
+pub trait Intersect2Dyn: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Returns the elements common to both sorted sets.
+    fn intersect(&self, set_a: &[i32], set_b: &[i32]) -> Vec<i32>;
+
+    /// Returns the number of elements common to both sorted sets.
+    fn count(&self, set_a: &[i32], set_b: &[i32]) -> usize;
+}
+
+struct Handle {
+    name: &'static str,
+    write: Intersect2<[i32], VecWriter<i32>>,
+    count: Intersect2<[i32], Counter>,
+}
+
+impl Intersect2Dyn for Handle {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn intersect(&self, set_a: &[i32], set_b: &[i32]) -> Vec<i32> {
+        let mut writer = VecWriter::with_capacity(set_a.len().min(set_b.len()));
+        (self.write)(set_a, set_b, &mut writer);
+        writer.into()
+    }
+
+    fn count(&self, set_a: &[i32], set_b: &[i32]) -> usize {
+        let mut counter = Counter::new();
+        (self.count)(set_a, set_b, &mut counter);
+        counter.count()
+    }
+}
+
+/// Returns a handle to the algorithm called `name` (as listed by `names`), or
+/// `None` if there is no such algorithm in this build.
+pub fn by_name(name: &str) -> Option<Box<dyn Intersect2Dyn>> {
+    let name = names().into_iter().find(|&n| n == name)?;
+    Some(Box::new(Handle {
+        name,
+        write: lookup(name)?,
+        count: lookup(name)?,
+    }))
+}
+
+macro_rules! algorithms {
+    ($($(#[$cfg:meta])* $variant:ident $name:literal => $intersect:path,)*) => {
+        /// The 2-set algorithms on sorted arrays available in this build, e.g.,
+        /// to run each of them in turn through `ALL`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum TwoSetAlgo {
+            $($(#[$cfg])* $variant,)*
+        }
+
+        impl TwoSetAlgo {
+            pub const ALL: &'static [TwoSetAlgo] = &[$($(#[$cfg])* TwoSetAlgo::$variant,)*];
+
+            /// Name of the algorithm, as used by the benchmark.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($(#[$cfg])* TwoSetAlgo::$variant => $name,)*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $($(#[$cfg])* $name => Some(TwoSetAlgo::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// The algorithm instantiated for visitor `V`.
+            pub fn function<V>(self) -> Intersect2<[i32], V>
+            where
+                V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16
+            {
+                match self {
+                    $($(#[$cfg])* TwoSetAlgo::$variant => $intersect,)*
+                }
+            }
+        }
+    };
+}
+
+impl TwoSetAlgo {
+    /// Whether the algorithm counts every comparison it makes through the
+    /// `instrument` hooks. Other algorithms count none, or only those of a
+    /// scalar tail, so their comparison counts are not comparable.
+    pub fn counts_comparisons(self) -> bool {
+        use TwoSetAlgo::*;
+        #[allow(unreachable_patterns)]
+        match self {
+            NaiveMerge | BranchlessMerge | BranchlessMergeUnroll2 | BranchlessMergeUnroll4 |
+            BranchlessMergeCmov | Galloping | BinarySearch | Baezayates => true,
+            #[cfg(all(target_feature = "ssse3", feature = "shuffling"))]
+            ShufflingSse | ShufflingSseBranch | ShufflingSseAligned => true,
+            #[cfg(all(target_feature = "avx2", feature = "shuffling"))]
+            ShufflingAvx2 | ShufflingAvx2Branch | ShufflingAvx2Aligned => true,
+            #[cfg(all(target_feature = "avx512f", feature = "shuffling"))]
+            ShufflingAvx512 | ShufflingAvx512Branch | ShufflingAvx512Aligned => true,
+            #[cfg(target_feature = "ssse3")]
+            BroadcastSse | BroadcastSseBranch | TinySse => true,
+            #[cfg(target_feature = "avx2")]
+            BroadcastAvx2 | BroadcastAvx2Branch | TinyAvx2 => true,
+            #[cfg(target_feature = "avx512f")]
+            BroadcastAvx512 | BroadcastAvx512Branch | TinyAvx512 => true,
+            _ => false,
+        }
+    }
+
+    /// Intersects the sorted sets `set_a` and `set_b`, visiting the result.
+    pub fn run<V>(self, set_a: &[i32], set_b: &[i32], visitor: &mut V)
+    where
+        V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16
+    {
+        (self.function::<V>())(set_a, set_b, visitor)
+    }
+}
+
+impl fmt::Display for TwoSetAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Returns the algorithm called `name` for visitor `V`.
+pub fn lookup<V>(name: &str) -> Option<Intersect2<[i32], V>>
+where
+    V: Visitor<i32> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16
+{
+    TwoSetAlgo::from_name(name).map(TwoSetAlgo::function)
+}
+
+/// Names of all algorithms available in this build.
+pub fn names() -> Vec<&'static str> {
+    TwoSetAlgo::ALL.iter().map(|algo| algo.name()).collect()
+}
+
+/// The k-set algorithms on sorted arrays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KSetAlgo {
+    Svs,
+    BaezayatesK,
+    SmallAdaptive,
+    SmallAdaptiveSorted,
+}
+
+impl KSetAlgo {
+    pub const ALL: &'static [KSetAlgo] = &[
+        KSetAlgo::Svs,
+        KSetAlgo::BaezayatesK,
+        KSetAlgo::SmallAdaptive,
+        KSetAlgo::SmallAdaptiveSorted,
+    ];
+
+    /// Name of the algorithm, as used by the benchmark.
+    pub fn name(self) -> &'static str {
+        match self {
+            KSetAlgo::Svs                 => "svs",
+            KSetAlgo::BaezayatesK         => "baezayates_k",
+            KSetAlgo::SmallAdaptive       => "small_adaptive",
+            KSetAlgo::SmallAdaptiveSorted => "small_adaptive_sorted",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        KSetAlgo::ALL.iter().copied().find(|algo| algo.name() == name)
+    }
+
+    /// The algorithm instantiated for sets `S` and visitor `V`.
+    pub fn function<S, V>(self) -> IntersectK<S, V>
+    where
+        S: AsRef<[i32]>,
+        V: Visitor<i32>,
+    {
+        match self {
+            KSetAlgo::Svs                 => intersect::svs,
+            KSetAlgo::BaezayatesK         => intersect::baezayates_k,
+            KSetAlgo::SmallAdaptive       => intersect::small_adaptive,
+            KSetAlgo::SmallAdaptiveSorted => intersect::small_adaptive_sorted,
+        }
+    }
+
+    /// Intersects the sorted `sets`, visiting the result.
+    pub fn run<S, V>(self, sets: &[S], visitor: &mut V)
+    where
+        S: AsRef<[i32]>,
+        V: Visitor<i32>,
+    {
+        (self.function::<S, V>())(sets, visitor)
+    }
+}
+
+impl fmt::Display for KSetAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+algorithms! {
+    NaiveMerge "naive_merge"                          => intersect::naive_merge,
+    BranchlessMerge "branchless_merge"                => intersect::branchless_merge,
+    BranchlessMergeUnroll2 "branchless_merge_unroll2" => intersect::branchless_merge_unroll2,
+    BranchlessMergeUnroll4 "branchless_merge_unroll4" => intersect::branchless_merge_unroll4,
+    BranchlessMergeCmov "branchless_merge_cmov"       => intersect::branchless_merge_cmov,
+    BmissScalar3x "bmiss_scalar_3x"                   => intersect::bmiss_scalar_3x,
+    BmissScalar4x "bmiss_scalar_4x"                   => intersect::bmiss_scalar_4x,
+    Galloping "galloping"                             => intersect::galloping,
+    BinarySearch "binary_search"                      => intersect::binary_search_intersect,
+    Baezayates "baezayates"                           => intersect::baezayates,
+    // SSE
+    #[cfg(target_feature = "ssse3")]
+    #[cfg(feature = "shuffling")]
+    ShufflingSse "shuffling_sse"     => intersect::shuffling_sse,
+    #[cfg(target_feature = "ssse3")]
+    BroadcastSse "broadcast_sse"     => intersect::broadcast_sse,
+    #[cfg(target_feature = "ssse3")]
+    Bmiss "bmiss"                    => intersect::bmiss,
+    #[cfg(target_feature = "ssse3")]
+    BmissSttni "bmiss_sttni"         => intersect::bmiss_sttni,
+    #[cfg(target_feature = "ssse3")]
+    BmissGalloping "bmiss_galloping" => intersect::bmiss_galloping,
+    #[cfg(target_feature = "ssse3")]
+    Qfilter "qfilter"                => intersect::qfilter,
+    #[cfg(target_feature = "ssse3")]
+    QfilterV1 "qfilter_v1"           => intersect::qfilter_v1,
+    #[cfg(target_feature = "ssse3")]
+    LbkV1x4Sse "lbk_v1x4_sse"        => intersect::lbk_v1x4_sse,
+    #[cfg(target_feature = "ssse3")]
+    LbkV1x8Sse "lbk_v1x8_sse"        => intersect::lbk_v1x8_sse,
+    #[cfg(target_feature = "ssse3")]
+    LbkV3Sse "lbk_v3_sse"            => intersect::lbk_v3_sse,
+    #[cfg(target_feature = "ssse3")]
+    #[cfg(feature = "galloping")]
+    GallopingSse "galloping_sse"     => intersect::galloping_sse,
+    #[cfg(target_feature = "ssse3")]
+    SimdMergeSse "simd_merge_sse"    => intersect::simd_merge_sse,
+    // AVX2
+    #[cfg(target_feature = "avx2")]
+    #[cfg(feature = "shuffling")]
+    ShufflingAvx2 "shuffling_avx2"  => intersect::shuffling_avx2,
+    #[cfg(target_feature = "avx2")]
+    BroadcastAvx2 "broadcast_avx2"  => intersect::broadcast_avx2,
+    #[cfg(target_feature = "avx2")]
+    LbkV1x8Avx2 "lbk_v1x8_avx2"     => intersect::lbk_v1x8_avx2,
+    #[cfg(target_feature = "avx2")]
+    LbkV1x16Avx2 "lbk_v1x16_avx2"   => intersect::lbk_v1x16_avx2,
+    #[cfg(target_feature = "avx2")]
+    LbkV3Avx2 "lbk_v3_avx2"         => intersect::lbk_v3_avx2,
+    #[cfg(target_feature = "avx2")]
+    #[cfg(feature = "galloping")]
+    GallopingAvx2 "galloping_avx2"  => intersect::galloping_avx2,
+    #[cfg(target_feature = "avx2")]
+    SimdMergeAvx2 "simd_merge_avx2" => intersect::simd_merge_avx2,
+    // AVX-512
+    #[cfg(target_feature = "avx512f")]
+    #[cfg(feature = "shuffling")]
+    ShufflingAvx512 "shuffling_avx512"             => intersect::shuffling_avx512,
+    #[cfg(target_feature = "avx512f")]
+    BroadcastAvx512 "broadcast_avx512"             => intersect::broadcast_avx512,
+    #[cfg(target_feature = "avx512f")]
+    Vp2intersectEmulation "vp2intersect_emulation" => intersect::vp2intersect_emulation,
+    #[cfg(target_feature = "avx512cd")]
+    ConflictIntersect "conflict_intersect"         => intersect::conflict_intersect,
+    #[cfg(target_feature = "avx512f")]
+    LbkV1x16Avx512 "lbk_v1x16_avx512"              => intersect::lbk_v1x16_avx512,
+    #[cfg(target_feature = "avx512f")]
+    LbkV1x32Avx512 "lbk_v1x32_avx512"              => intersect::lbk_v1x32_avx512,
+    #[cfg(target_feature = "avx512f")]
+    LbkV3Avx512 "lbk_v3_avx512"                    => intersect::lbk_v3_avx512,
+    #[cfg(target_feature = "avx512f")]
+    #[cfg(feature = "galloping")]
+    GallopingAvx512 "galloping_avx512"             => intersect::galloping_avx512,
+    // Branch
+    #[cfg(target_feature = "ssse3")]
+    #[cfg(feature = "shuffling")]
+    ShufflingSseBranch "shuffling_sse_branch"                   => intersect::shuffling_sse_branch,
+    #[cfg(target_feature = "ssse3")]
+    BroadcastSseBranch "broadcast_sse_branch"                   => intersect::broadcast_sse_branch,
+    #[cfg(target_feature = "ssse3")]
+    BmissBranch "bmiss_branch"                                  => intersect::bmiss_branch,
+    #[cfg(target_feature = "ssse3")]
+    BmissSttniBranch "bmiss_sttni_branch"                       => intersect::bmiss_sttni_branch,
+    #[cfg(target_feature = "ssse3")]
+    QfilterBranch "qfilter_branch"                              => intersect::qfilter_branch,
+    #[cfg(target_feature = "ssse3")]
+    QfilterV1Branch "qfilter_v1_branch"                         => intersect::qfilter_v1_branch,
+    #[cfg(target_feature = "avx2")]
+    #[cfg(feature = "shuffling")]
+    ShufflingAvx2Branch "shuffling_avx2_branch"                 => intersect::shuffling_avx2_branch,
+    #[cfg(target_feature = "avx2")]
+    BroadcastAvx2Branch "broadcast_avx2_branch"                 => intersect::broadcast_avx2_branch,
+    #[cfg(target_feature = "avx512f")]
+    #[cfg(feature = "shuffling")]
+    ShufflingAvx512Branch "shuffling_avx512_branch"             => intersect::shuffling_avx512_branch,
+    #[cfg(target_feature = "avx512f")]
+    BroadcastAvx512Branch "broadcast_avx512_branch"             => intersect::broadcast_avx512_branch,
+    #[cfg(target_feature = "avx512f")]
+    Vp2intersectEmulationBranch "vp2intersect_emulation_branch" => intersect::vp2intersect_emulation_branch,
+    #[cfg(target_feature = "avx512cd")]
+    ConflictIntersectBranch "conflict_intersect_branch"         => intersect::conflict_intersect_branch,
+    // Aligned
+    #[cfg(target_feature = "ssse3")]
+    #[cfg(feature = "shuffling")]
+    ShufflingSseAligned "shuffling_sse_aligned"       => intersect::shuffling_sse_aligned,
+    #[cfg(target_feature = "avx2")]
+    #[cfg(feature = "shuffling")]
+    ShufflingAvx2Aligned "shuffling_avx2_aligned"     => intersect::shuffling_avx2_aligned,
+    #[cfg(target_feature = "avx512f")]
+    #[cfg(feature = "shuffling")]
+    ShufflingAvx512Aligned "shuffling_avx512_aligned" => intersect::shuffling_avx512_aligned,
+    // Masked-load tails
+    #[cfg(target_feature = "ssse3")]
+    ShufflingSseTail "shuffling_sse_tail"       => intersect::shuffling_sse_tail,
+    #[cfg(target_feature = "avx2")]
+    ShufflingAvx2Tail "shuffling_avx2_tail"     => intersect::shuffling_avx2_tail,
+    #[cfg(target_feature = "avx512f")]
+    ShufflingAvx512Tail "shuffling_avx512_tail" => intersect::shuffling_avx512_tail,
+    #[cfg(target_feature = "ssse3")]
+    SimdMergeSseTail "simd_merge_sse_tail"      => intersect::simd_merge_sse_tail,
+    #[cfg(target_feature = "avx2")]
+    SimdMergeAvx2Tail "simd_merge_avx2_tail"    => intersect::simd_merge_avx2_tail,
+    // All-pairs comparison for tiny sets
+    #[cfg(target_feature = "ssse3")]
+    TinySse "tiny_sse"       => intersect::tiny_sse,
+    #[cfg(target_feature = "avx2")]
+    TinyAvx2 "tiny_avx2"     => intersect::tiny_avx2,
+    #[cfg(target_feature = "avx512f")]
+    TinyAvx512 "tiny_avx512" => intersect::tiny_avx512,
+}
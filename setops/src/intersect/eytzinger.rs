@@ -0,0 +1,80 @@
+//! Intersection by searching for each element of a small sorted set in an
+//! `EytzingerSet`. The SIMD variants descend the tree for one vector of small
+//! elements at a time, gathering the node of each lane at every level.
+
+#[cfg(feature = "simd")]
+use std::simd::{*, cmp::*};
+
+use crate::{eytzinger::EytzingerSet, visitor::Visitor};
+#[cfg(feature = "simd")]
+use crate::{eytzinger::lower_bound_node, element::{IntElement, to_ordered_lanes}};
+
+pub fn eytzinger_probe<T, V>(small: &[T], large: &EytzingerSet<T>, visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    for &target in small {
+        if large.contains(target) {
+            visitor.visit(target);
+        }
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "avx2"))]
+pub fn eytzinger_probe_avx2<T, V>(small: &[T], large: &EytzingerSet<T>, visitor: &mut V)
+where
+    T: IntElement<Lane = i32>,
+    V: Visitor<T>,
+{
+    eytzinger_probe_simd::<8, T, V>(small, large, visitor)
+}
+
+#[cfg(all(feature = "simd", target_feature = "avx512f"))]
+pub fn eytzinger_probe_avx512<T, V>(small: &[T], large: &EytzingerSet<T>, visitor: &mut V)
+where
+    T: IntElement<Lane = i32>,
+    V: Visitor<T>,
+{
+    eytzinger_probe_simd::<16, T, V>(small, large, visitor)
+}
+
+/// Every descent leaves the tree after at most `depth` levels, so lanes step
+/// in lockstep for that many levels, each lane stopping once it leaves.
+#[cfg(feature = "simd")]
+#[inline]
+fn eytzinger_probe_simd<const W: usize, T, V>(
+    small: &[T],
+    large: &EytzingerSet<T>,
+    visitor: &mut V)
+where
+    LaneCount<W>: SupportedLaneCount,
+    T: IntElement<Lane = i32>,
+    V: Visitor<T>,
+{
+    let layout = T::as_lanes(large.layout());
+    let depth = usize::BITS - large.len().leading_zeros();
+    let node_count = Simd::<usize, W>::splat(layout.len());
+
+    let mut chunks = small.chunks_exact(W);
+    for chunk in &mut chunks {
+        let targets = Simd::<i32, W>::from_array(std::array::from_fn(|i| chunk[i].to_ordered_lane()));
+        let mut k = Simd::<usize, W>::splat(1);
+
+        for _ in 0..depth {
+            let inside = k.simd_lt(node_count);
+            let nodes = to_ordered_lanes::<T, W>(
+                Simd::gather_select(layout, inside, k, Simd::splat(0)));
+            let right = nodes.simd_lt(targets).cast::<isize>() & inside;
+            k = inside.select(k + k + right.select(Simd::splat(1), Simd::splat(0)), k);
+        }
+
+        for (lane, &target) in chunk.iter().enumerate() {
+            let node = lower_bound_node(k[lane]);
+            if node != 0 && layout[node] == target.to_lane() {
+                visitor.visit(target);
+            }
+        }
+    }
+    eytzinger_probe(chunks.remainder(), large, visitor)
+}
@@ -0,0 +1,158 @@
+//! FESIA for 64-bit keys, e.g., IDs drawn from a universe beyond `i32`. Each
+//! key is folded to a 32-bit residual and the distinct residuals are laid out
+//! as a regular `Fesia` set, so the 32-bit kernels and hash probe find the
+//! residuals both sets share. Keys folding to a shared residual are then
+//! compared exactly, as distinct keys may fold to the same residual.
+
+use std::simd::{*, cmp::*};
+use std::ops::BitAnd;
+
+use crate::{
+    element::IntElement,
+    visitor::{Visitor, VecWriter},
+};
+use super::{
+    Fesia, FesiaError, FesiaIntersect, HashScale, IntegerHash, MixHash, SegmentIntersect,
+    SetWithHashScale,
+};
+
+pub type Fesia64Sse    = Fesia64<MixHash, i32, 4>;
+pub type Fesia64Avx2   = Fesia64<MixHash, i32, 8>;
+pub type Fesia64Avx512 = Fesia64<MixHash, i32, 16>;
+
+pub struct Fesia64<H, S, const LANES: usize>
+where
+    H: IntegerHash,
+    S: SimdElement + MaskElement,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<S, LANES>: BitAnd<Output=Simd<S, LANES>> + SimdPartialEq<Mask=Mask<S, LANES>>,
+{
+    residuals: Fesia<H, S, LANES>,
+    // Distinct residuals in ascending order. The keys folding to
+    // `sorted_residuals[i]` are `keys[key_offsets[i]..key_offsets[i + 1]]`,
+    // in ascending order.
+    sorted_residuals: Vec<i32>,
+    key_offsets: Vec<u32>,
+    keys: Vec<i64>,
+}
+
+/// Folds `key` to 32 bits. Keys below 2^32 keep their low half, so only
+/// larger keys may collide, and the high half is scattered by a Fibonacci
+/// multiplier so that runs of keys differing in it do not collide.
+#[inline]
+pub fn residual(key: i64) -> i32 {
+    let bits = key as u64;
+    let high = (bits >> u32::BITS) as u32;
+    (bits as u32 ^ high.wrapping_mul(0x9E37_79B1)) as i32
+}
+
+impl<H, S, const LANES: usize> Fesia64<H, S, LANES>
+where
+    H: IntegerHash,
+    S: SimdElement + MaskElement,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<S, LANES>: BitAnd<Output=Simd<S, LANES>> + SimdPartialEq<Mask=Mask<S, LANES>>,
+{
+    /// Panics where `try_from_sorted` fails.
+    pub fn from_sorted(sorted: &[i64], hash_scale: HashScale) -> Self {
+        Self::try_from_sorted(sorted, hash_scale)
+            .unwrap_or_else(|e| panic!("cannot build FESIA set: {e}"))
+    }
+
+    /// The hash scale applies to the distinct residuals, which number the
+    /// keys unless keys collide.
+    pub fn try_from_sorted(sorted: &[i64], hash_scale: HashScale) -> Result<Self, FesiaError> {
+        if u32::try_from(sorted.len()).is_err() {
+            return Err(FesiaError::TooManyElements(sorted.len()));
+        }
+
+        let mut pairs: Vec<(i32, i64)> = sorted.iter()
+            .map(|&key| (residual(key), key))
+            .collect();
+        pairs.sort_unstable();
+
+        let mut sorted_residuals: Vec<i32> = Vec::new();
+        let mut key_offsets: Vec<u32> = Vec::new();
+        for (i, &(residual, _)) in pairs.iter().enumerate() {
+            if sorted_residuals.last() != Some(&residual) {
+                sorted_residuals.push(residual);
+                key_offsets.push(i as u32);
+            }
+        }
+        key_offsets.push(pairs.len() as u32);
+
+        Ok(Self {
+            residuals: Fesia::try_from_sorted(&sorted_residuals, hash_scale)?,
+            sorted_residuals,
+            key_offsets,
+            keys: pairs.into_iter().map(|(_, key)| key).collect(),
+        })
+    }
+
+    /// Builds from unsigned keys, whose results are visited reinterpreted as
+    /// `i64`.
+    pub fn from_sorted_elements<T>(sorted: &[T], hash_scale: HashScale) -> Self
+    where
+        T: IntElement<Lane = i64>,
+    {
+        Self::from_sorted(T::as_lanes(sorted), hash_scale)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Number of distinct residuals, i.e., the size of the 32-bit set.
+    pub fn residual_count(&self) -> usize {
+        self.sorted_residuals.len()
+    }
+
+    pub fn to_sorted_set(&self) -> Vec<i64> {
+        let mut result = self.keys.clone();
+        result.sort_unstable();
+        result
+    }
+
+    /// Intersects the residuals with the segment kernels `I`, then visits the
+    /// keys of each shared residual found in both sets.
+    pub fn intersect<I>(&self, other: &Self, visitor: &mut impl Visitor<i64>)
+    where
+        I: SegmentIntersect,
+    {
+        let mut shared: VecWriter<i32> = VecWriter::new();
+        self.residuals.intersect::<VecWriter<i32>, I>(&other.residuals, &mut shared);
+        self.verify(other, shared.as_ref(), visitor);
+    }
+
+    /// Intersects the residuals by probing, for skewed set sizes, then visits
+    /// the keys of each shared residual found in both sets.
+    pub fn hash_intersect(&self, other: &Self, visitor: &mut impl Visitor<i64>) {
+        let mut shared: VecWriter<i32> = VecWriter::new();
+        self.residuals.hash_intersect(&other.residuals, &mut shared);
+        self.verify(other, shared.as_ref(), visitor);
+    }
+
+    fn keys_of(&self, residual: i32) -> &[i64] {
+        match self.sorted_residuals.binary_search(&residual) {
+            Ok(i) => &self.keys[self.key_offsets[i] as usize..self.key_offsets[i + 1] as usize],
+            Err(_) => &[],
+        }
+    }
+
+    fn verify(&self, other: &Self, shared: &[i32], visitor: &mut impl Visitor<i64>) {
+        for &residual in shared {
+            let keys_b = other.keys_of(residual);
+            match self.keys_of(residual) {
+                // Without a collision in `self`, at most one key matches.
+                &[key] => if keys_b.contains(&key) {
+                    visitor.visit(key);
+                },
+                keys_a => crate::intersect::branchless_merge(keys_a, keys_b, visitor),
+            }
+        }
+    }
+}
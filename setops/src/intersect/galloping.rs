@@ -2,7 +2,13 @@ use std::cmp::Ordering;
 
 /// Search-based set intersection algorithms.
 
-use crate::{visitor::{Visitor, BsrVisitor}, bsr::BsrRef};
+use crate::{
+    intersect::TwoSetAlgorithm,
+    visitor::Visitor,
+    instrument::{count_comparisons, count_gallop},
+};
+#[cfg(feature = "bsr")]
+use crate::{visitor::BsrVisitor, bsr::BsrRef};
 
 pub fn galloping<T, V>(small: &[T], mut large: &[T], visitor: &mut V)
 where
@@ -10,17 +16,39 @@ where
     V: Visitor<T>,
 {
     for &target in small {
+        let base = gallop(large, target);
 
-        let mut offset = 1;
-
-        while offset < large.len() && large[offset] <= target {
-            offset *= 2;
+        if base < large.len() && large[base] == target {
+            visitor.visit(target);
         }
+        large = &large[base..];
+    }
+}
 
-        let lo: isize = (offset / 2) as isize;
-        let hi: isize = (large.len() as isize - 1).min(offset as isize);
+/// Returns the index of `target` in `large`, or of the first greater element,
+/// by doubling a step from the start of `large` and then binary searching.
+#[inline]
+pub(crate) fn gallop<T>(large: &[T], target: T) -> usize
+where
+    T: Ord + Copy,
+{
+    gallop_by(large, target, DEFAULT_GALLOP_FACTOR)
+}
 
-        let base = binary_search(large, target, lo, hi);
+/// Growth factor of the step of `galloping`.
+pub const DEFAULT_GALLOP_FACTOR: usize = 2;
+
+/// Galloping with the step multiplied by `factor` (at least 2) each time
+/// rather than doubled. A larger factor reaches distant targets in fewer
+/// steps but leaves a wider range to binary search, so suits more skewed sets.
+pub fn galloping_factor<T, V>(small: &[T], mut large: &[T], factor: usize, visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    debug_assert!(factor >= 2);
+    for &target in small {
+        let base = gallop_by(large, target, factor);
 
         if base < large.len() && large[base] == target {
             visitor.visit(target);
@@ -29,6 +57,116 @@ where
     }
 }
 
+#[inline]
+fn gallop_by<T>(large: &[T], target: T, factor: usize) -> usize
+where
+    T: Ord + Copy,
+{
+    let mut offset = 1;
+    let mut previous = 0;
+
+    while offset < large.len() && large[offset] <= target {
+        count_comparisons(1);
+        previous = offset;
+        offset *= factor;
+    }
+
+    let lo: isize = previous as isize;
+    let hi: isize = (large.len() as isize - 1).min(offset as isize);
+
+    let base = binary_search(large, target, lo, hi);
+    count_gallop((hi + 1 - base as isize).max(0) as u64);
+    base
+}
+
+/// `galloping_factor` as a `TwoSetAlgorithm` carrying its factor. The smaller
+/// set is searched for in the larger.
+pub struct GallopingFactor {
+    pub factor: usize,
+}
+
+impl Default for GallopingFactor {
+    fn default() -> Self {
+        Self { factor: DEFAULT_GALLOP_FACTOR }
+    }
+}
+
+impl<T, V> TwoSetAlgorithm<T, V> for GallopingFactor
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    type Prepared<'a> = &'a [T] where T: 'a;
+
+    fn prepare<'a>(&self, set: &'a [T]) -> &'a [T] {
+        set
+    }
+
+    fn intersect(&self, set_a: &&[T], set_b: &&[T], visitor: &mut V) {
+        let (small, large) = if set_a.len() <= set_b.len() {
+            (set_a, set_b)
+        }
+        else {
+            (set_b, set_a)
+        };
+        galloping_factor(small, large, self.factor, visitor)
+    }
+}
+
+/// The factor of `factors` for which galloping the smaller set of each pair
+/// into the larger compares the fewest elements, counting each step of the
+/// gallops and of the binary searches, or `None` without factors. Ties go to
+/// the earlier factor. Comparisons are counted directly, so tuning does not
+/// need the `instrument` feature.
+pub fn tune_gallop_factor<T>(pairs: &[(&[T], &[T])], factors: &[usize]) -> Option<usize>
+where
+    T: Ord + Copy,
+{
+    factors.iter()
+        .copied()
+        .min_by_key(|&factor| pairs.iter()
+            .map(|&(set_a, set_b)| {
+                let (small, large) = if set_a.len() <= set_b.len() {
+                    (set_a, set_b)
+                }
+                else {
+                    (set_b, set_a)
+                };
+                gallop_comparisons(small, large, factor)
+            })
+            .sum::<u64>())
+}
+
+// Comparisons of `galloping_factor`, as `count_comparisons` would count them.
+fn gallop_comparisons<T>(small: &[T], mut large: &[T], factor: usize) -> u64
+where
+    T: Ord + Copy,
+{
+    let mut comparisons = 0;
+    for &target in small {
+        let mut offset = 1;
+        let mut previous = 0;
+        while offset < large.len() && large[offset] <= target {
+            comparisons += 1;
+            previous = offset;
+            offset *= factor;
+        }
+
+        let (mut lo, mut hi) = (previous as isize, (large.len() as isize - 1).min(offset as isize));
+        while lo <= hi {
+            let mid = lo + (hi - lo) / 2;
+            comparisons += 1;
+            match large[mid as usize].cmp(&target) {
+                Ordering::Less    => lo = mid + 1,
+                Ordering::Greater => hi = mid - 1,
+                Ordering::Equal   => { lo = mid; break; },
+            }
+        }
+        large = &large[lo as usize..];
+    }
+    comparisons
+}
+
 pub fn binary_search_intersect<T, V>(small: &[T], mut large: &[T], visitor: &mut V)
 where
     T: Ord + Copy,
@@ -48,6 +186,10 @@ where
     }
 }
 
+/// Galloping over the Base and State Representation. Each base in `small` is
+/// searched for in `large`, and states of matching bases are intersected.
+/// See `galloping_[sse, avx2, avx512]_bsr` for SIMD probe variants.
+#[cfg(feature = "bsr")]
 pub fn galloping_bsr<'a, V>(small: BsrRef<'a>, mut large: BsrRef<'a>, visitor: &mut V)
 where
     V: BsrVisitor,
@@ -84,18 +226,7 @@ where
     for i in 0..small.len() {
 
         let target = unsafe { *small.get_unchecked(i) };
-        let mut offset = 1;
-
-        while offset < large.len() &&
-            large[offset] <= target
-        {
-            offset *= 2;
-        }
-
-        let lo: isize = (offset / 2) as isize;
-        let hi: isize = (large.len() as isize - 1).min(offset as isize);
-
-        let base = binary_search(large, target, lo, hi);
+        let base = gallop(large, target);
 
         if base < large.len() && large[base] == target {
             small[count] = target;
@@ -119,6 +250,7 @@ where
 
         let mid = lo + (hi - lo) / 2;
         let actual = set[mid as usize];
+        count_comparisons(1);
 
         match actual.cmp(&target) {
             Ordering::Less    => lo = mid + 1,
@@ -0,0 +1,85 @@
+#[cfg(feature = "simd")]
+use std::simd::{*, cmp::SimdPartialEq};
+
+use crate::{
+    hybrid::{HybridSet, Container, Bitmap, CHUNK_BITS, chunk_low},
+    intersect::Intersect2,
+    visitor::Visitor,
+};
+
+/// Intersects two hybrid sets chunk by chunk, choosing the method for each
+/// pair of chunks with equal keys from their representations: a bitmap AND
+/// for two bitmaps, probing the bitmap with each element of an array, or
+/// `intersect` for two arrays.
+pub fn hybrid_intersect<V>(
+    set_a: &HybridSet,
+    set_b: &HybridSet,
+    intersect: Intersect2<[i32], V>,
+    visitor: &mut V)
+where
+    V: Visitor<i32>,
+{
+    let (keys_a, keys_b) = (set_a.keys(), set_b.keys());
+    let (containers_a, containers_b) = (set_a.containers(), set_b.containers());
+
+    let (mut i_a, mut i_b) = (0, 0);
+    while i_a < keys_a.len() && i_b < keys_b.len() {
+        let (key_a, key_b) = (keys_a[i_a], keys_b[i_b]);
+
+        if key_a == key_b {
+            match (&containers_a[i_a], &containers_b[i_b]) {
+                (Container::Array(a), Container::Array(b)) =>
+                    intersect(a, b, visitor),
+                (Container::Array(array), Container::Bitmap(bitmap)) |
+                (Container::Bitmap(bitmap), Container::Array(array)) =>
+                    probe_bitmap(array, bitmap, visitor),
+                (Container::Bitmap(a), Container::Bitmap(b)) =>
+                    and_bitmaps(a, b, key_a, visitor),
+            }
+        }
+        i_a += (key_a <= key_b) as usize;
+        i_b += (key_b <= key_a) as usize;
+    }
+}
+
+fn probe_bitmap<V: Visitor<i32>>(array: &[i32], bitmap: &Bitmap, visitor: &mut V) {
+    for &item in array {
+        let low = chunk_low(item);
+        if bitmap[low / 64] & (1 << (low % 64)) != 0 {
+            visitor.visit(item);
+        }
+    }
+}
+
+#[cfg(feature = "simd")]
+fn and_bitmaps<V: Visitor<i32>>(a: &Bitmap, b: &Bitmap, key: i32, visitor: &mut V) {
+    const LANES: usize = 8;
+    let zero = u64x8::splat(0);
+
+    for (i, (a, b)) in a.chunks_exact(LANES).zip(b.chunks_exact(LANES)).enumerate() {
+        let and = u64x8::from_slice(a) & u64x8::from_slice(b);
+        // Skip runs of empty words without testing each one.
+        if and.simd_eq(zero).all() {
+            continue;
+        }
+        for (j, &word) in and.as_array().iter().enumerate() {
+            visit_word(word, key, i * LANES + j, visitor);
+        }
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+fn and_bitmaps<V: Visitor<i32>>(a: &Bitmap, b: &Bitmap, key: i32, visitor: &mut V) {
+    for (i, (&a, &b)) in a.iter().zip(b.iter()).enumerate() {
+        visit_word(a & b, key, i, visitor);
+    }
+}
+
+#[inline]
+fn visit_word<V: Visitor<i32>>(mut word: u64, key: i32, index: usize, visitor: &mut V) {
+    while word != 0 {
+        let bit = word.trailing_zeros() as usize;
+        visitor.visit((key << CHUNK_BITS) | (index * 64 + bit) as i32);
+        word &= word - 1;
+    }
+}
@@ -0,0 +1,265 @@
+//! In-place intersection, which compacts the elements of `set_a` also found in
+//! `set_b` into the front of `set_a` and truncates it, so no output buffer is
+//! allocated.
+//!
+//! The k-th match is an element of `set_a` at index `i >= k`, so writing it to
+//! index `k` never overwrites an element which has not yet been read. SIMD
+//! kernels rely on this by keeping the current block of `set_a` in a register
+//! until it is consumed and by writing only the matched lanes: a full vector
+//! store would spill unmatched lanes over elements which are still to be read.
+
+#[cfg(feature = "simd")]
+use std::simd::{*, cmp::*};
+
+#[cfg(feature = "simd")]
+use crate::{element::IntElement, instructions::load_unsafe, util::*};
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+use crate::instructions::{VEC_SHUFFLE_MASK4, shuffle_epi8};
+#[cfg(all(feature = "simd", target_feature = "avx2"))]
+use crate::instructions::{VEC_SHUFFLE_MASK8, permutevar8x32_epi32};
+
+pub type IntersectInplace<T> = fn(set_a: &mut Vec<T>, set_b: &[T]);
+
+/// Branchless merge writing the result into the front of `set_a`.
+pub fn merge_inplace<T>(set_a: &mut Vec<T>, set_b: &[T])
+where
+    T: Ord + Copy,
+{
+    let count = merge_tail(set_a, 0, &[], set_b, 0);
+    set_a.truncate(count);
+}
+
+/// `shuffling_sse` writing the result into the front of `set_a`.
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+pub fn shuffling_sse_inplace<T>(set_a: &mut Vec<T>, set_b: &[T])
+where
+    T: IntElement<Lane = i32>,
+{
+    const W: usize = 4;
+
+    let ptr_a = set_a.as_mut_ptr() as *mut i32;
+    let ptr_b = T::as_lanes(set_b).as_ptr();
+
+    let st_a = (set_a.len() / W) * W;
+    let st_b = (set_b.len() / W) * W;
+
+    let mut i_a: usize = 0;
+    let mut i_b: usize = 0;
+    let mut count: usize = 0;
+    let mut v_a: i32x4 = if st_a > 0 { unsafe { load_unsafe(ptr_a) } } else { i32x4::splat(0) };
+
+    while i_a < st_a && i_b < st_b {
+        let v_b: i32x4 = unsafe { load_unsafe(ptr_b.add(i_b)) };
+
+        let masks = [
+            v_a.simd_eq(v_b),
+            v_a.simd_eq(v_b.rotate_elements_left::<1>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<2>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<3>()),
+        ];
+        let mask = or_4(masks).to_bitmask();
+
+        let shuffled = shuffle_epi8(v_a, VEC_SHUFFLE_MASK4[mask as usize]);
+        unsafe { write_lanes(ptr_a.add(count), shuffled.as_array(), mask) };
+        count += mask.count_ones() as usize;
+
+        let a_max = T::from_lane(v_a[W - 1]);
+        let b_max = unsafe { *set_b.get_unchecked(i_b + W - 1) };
+
+        if a_max <= b_max {
+            i_a += W;
+            if i_a < st_a {
+                v_a = unsafe { load_unsafe(ptr_a.add(i_a)) };
+            }
+        }
+        i_b += W * (b_max <= a_max) as usize;
+    }
+
+    let block = if i_a < st_a { T::from_lanes(v_a.as_array()) } else { &[] };
+    let rest = i_a + block.len();
+
+    let count = merge_tail(set_a, rest, block, &set_b[i_b..], count);
+    set_a.truncate(count);
+}
+
+/// `shuffling_avx2` writing the result into the front of `set_a`.
+#[cfg(all(feature = "simd", target_feature = "avx2"))]
+pub fn shuffling_avx2_inplace<T>(set_a: &mut Vec<T>, set_b: &[T])
+where
+    T: IntElement<Lane = i32>,
+{
+    const W: usize = 8;
+
+    let ptr_a = set_a.as_mut_ptr() as *mut i32;
+    let ptr_b = T::as_lanes(set_b).as_ptr();
+
+    let st_a = (set_a.len() / W) * W;
+    let st_b = (set_b.len() / W) * W;
+
+    let mut i_a: usize = 0;
+    let mut i_b: usize = 0;
+    let mut count: usize = 0;
+    let mut v_a: i32x8 = if st_a > 0 { unsafe { load_unsafe(ptr_a) } } else { i32x8::splat(0) };
+
+    while i_a < st_a && i_b < st_b {
+        let v_b: i32x8 = unsafe { load_unsafe(ptr_b.add(i_b)) };
+
+        let masks = [
+            v_a.simd_eq(v_b),
+            v_a.simd_eq(v_b.rotate_elements_left::<1>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<2>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<3>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<4>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<5>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<6>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<7>()),
+        ];
+        let mask = or_8(masks).to_bitmask();
+
+        let shuffled = permutevar8x32_epi32(v_a, VEC_SHUFFLE_MASK8[mask as usize]);
+        unsafe { write_lanes(ptr_a.add(count), shuffled.as_array(), mask) };
+        count += mask.count_ones() as usize;
+
+        let a_max = T::from_lane(v_a[W - 1]);
+        let b_max = unsafe { *set_b.get_unchecked(i_b + W - 1) };
+
+        if a_max <= b_max {
+            i_a += W;
+            if i_a < st_a {
+                v_a = unsafe { load_unsafe(ptr_a.add(i_a)) };
+            }
+        }
+        i_b += W * (b_max <= a_max) as usize;
+    }
+
+    let block = if i_a < st_a { T::from_lanes(v_a.as_array()) } else { &[] };
+    let rest = i_a + block.len();
+
+    let count = merge_tail(set_a, rest, block, &set_b[i_b..], count);
+    set_a.truncate(count);
+}
+
+/// `shuffling_avx512` writing the result into the front of `set_a`. The
+/// compress store writes exactly the matched lanes.
+#[cfg(all(feature = "simd", target_feature = "avx512f"))]
+pub fn shuffling_avx512_inplace<T>(set_a: &mut Vec<T>, set_b: &[T])
+where
+    T: IntElement<Lane = i32>,
+{
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    const W: usize = 16;
+
+    let ptr_a = set_a.as_mut_ptr() as *mut i32;
+    let ptr_b = T::as_lanes(set_b).as_ptr();
+
+    let st_a = (set_a.len() / W) * W;
+    let st_b = (set_b.len() / W) * W;
+
+    let mut i_a: usize = 0;
+    let mut i_b: usize = 0;
+    let mut count: usize = 0;
+    let mut v_a: i32x16 = if st_a > 0 { unsafe { load_unsafe(ptr_a) } } else { i32x16::splat(0) };
+
+    while i_a < st_a && i_b < st_b {
+        let v_b: i32x16 = unsafe { load_unsafe(ptr_b.add(i_b)) };
+
+        let masks = [
+            v_a.simd_eq(v_b),
+            v_a.simd_eq(v_b.rotate_elements_left::<1>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<2>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<3>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<4>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<5>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<6>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<7>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<8>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<9>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<10>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<11>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<12>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<13>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<14>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<15>()),
+        ];
+        let mask = or_16(masks).to_bitmask();
+
+        unsafe {
+            _mm512_mask_compressstoreu_epi32(
+                ptr_a.add(count) as *mut u8,
+                mask as u16,
+                v_a.into(),
+            );
+        }
+        count += mask.count_ones() as usize;
+
+        let a_max = T::from_lane(v_a[W - 1]);
+        let b_max = unsafe { *set_b.get_unchecked(i_b + W - 1) };
+
+        if a_max <= b_max {
+            i_a += W;
+            if i_a < st_a {
+                v_a = unsafe { load_unsafe(ptr_a.add(i_a)) };
+            }
+        }
+        i_b += W * (b_max <= a_max) as usize;
+    }
+
+    let block = if i_a < st_a { T::from_lanes(v_a.as_array()) } else { &[] };
+    let rest = i_a + block.len();
+
+    let count = merge_tail(set_a, rest, block, &set_b[i_b..], count);
+    set_a.truncate(count);
+}
+
+/// Writes the first `mask.count_ones()` lanes of `shuffled` to `out`.
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+#[inline]
+unsafe fn write_lanes(out: *mut i32, shuffled: &[i32], mask: u64) {
+    unsafe {
+        std::ptr::copy_nonoverlapping(shuffled.as_ptr(), out, mask.count_ones() as usize);
+    }
+}
+
+/// Merges `block`, the last block loaded from `set_a` by a SIMD kernel,
+/// followed by `set_a[rest..]` with `set_b`, writing matches into `set_a`
+/// from `count` on. Returns the final count. Elements of `block` matched
+/// earlier are smaller than all of `set_b`, so they are not repeated.
+fn merge_tail<T>(
+    set_a: &mut [T],
+    rest: usize,
+    block: &[T],
+    set_b: &[T],
+    mut count: usize) -> usize
+where
+    T: Ord + Copy,
+{
+    let len_a = block.len() + set_a.len() - rest;
+
+    let mut idx_a = 0;
+    let mut idx_b = 0;
+
+    while idx_a < len_a && idx_b < set_b.len() {
+        let value_a = if idx_a < block.len() {
+            block[idx_a]
+        } else {
+            set_a[rest + idx_a - block.len()]
+        };
+        let value_b = set_b[idx_b];
+
+        if value_a == value_b {
+            set_a[count] = value_a;
+            count += 1;
+            idx_a += 1;
+            idx_b += 1;
+        } else {
+            idx_a += (value_a < value_b) as usize;
+            idx_b += (value_b < value_a) as usize;
+        }
+    }
+
+    count
+}
@@ -6,6 +6,7 @@ use std::{
 };
 
 use crate::{
+    element::IntElement,
     visitor::Visitor,
     intersect, instructions::load_unsafe,
 };
@@ -14,11 +15,10 @@ use crate::{
 pub fn lbk_v1x4_sse<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T>,
-    T: Ord + Copy + std::fmt::Display,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 4;
 
@@ -59,11 +59,10 @@ where
 pub fn lbk_v1x8_sse<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T>,
-    T: Ord + Copy + std::fmt::Display,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 4;
     const BOUND: usize = W*2;
@@ -110,11 +109,10 @@ where
 pub fn lbk_v1x8_avx2<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T>,
-    T: Ord + Copy + std::fmt::Display,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 8;
     const BOUND: usize = W;
@@ -157,11 +155,10 @@ where
 pub fn lbk_v1x16_avx2<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T>,
-    T: Ord + Copy + std::fmt::Display,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 8;
     const BOUND: usize = 2*W;
@@ -206,11 +203,10 @@ where
 pub fn lbk_v1x16_avx512<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T>,
-    T: Ord + Copy + std::fmt::Display,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 16;
     const BOUND: usize = W;
@@ -253,11 +249,10 @@ where
 pub fn lbk_v1x32_avx512<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T>,
-    T: Ord + Copy + std::fmt::Display,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 16;
     const BOUND: usize = 2*W;
@@ -305,11 +300,10 @@ const NUM_LANES_IN_BOUND: usize = 32;
 pub fn lbk_v3_sse<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T>,
-    T: Ord + Copy + std::fmt::Display,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 4;
     const BOUND: usize = W*NUM_LANES_IN_BOUND;
@@ -353,11 +347,10 @@ where
 pub fn lbk_v3_avx2<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T>,
-    T: Ord + Copy + std::fmt::Display,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 8;
     const BOUND: usize = W*NUM_LANES_IN_BOUND;
@@ -401,11 +394,10 @@ where
 pub fn lbk_v3_avx512<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T>,
-    T: Ord + Copy + std::fmt::Display,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 16;
     const BOUND: usize = W*NUM_LANES_IN_BOUND;
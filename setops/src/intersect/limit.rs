@@ -0,0 +1,147 @@
+//! Early stopping for `LIMIT k` queries. The sets are intersected a block at a
+//! time using any sorted-array algorithm, checking between blocks whether the
+//! visitor is saturated (e.g., a `LimitVisitor` which has seen k results).
+//! Blocks are taken from the smallest set, and each other set is cut at the
+//! last element of the block, so results are still visited in sorted order.
+
+use crate::{
+    intersect::{Intersect2, IntersectK, gallop},
+    visitor::{Visitor, Saturable},
+};
+
+/// Default number of elements of the smallest set intersected between checks.
+pub const DEFAULT_BLOCK_LEN: usize = 256;
+
+/// Intersects `set_a` and `set_b` with `intersect`, stopping once `visitor`
+/// is saturated.
+pub fn limit_2set<T, V>(
+    set_a: &[T],
+    set_b: &[T],
+    intersect: Intersect2<[T], V>,
+    visitor: &mut V)
+where
+    T: Ord,
+    V: Visitor<T> + Saturable,
+{
+    limit_2set_blocks(set_a, set_b, intersect, visitor, DEFAULT_BLOCK_LEN)
+}
+
+pub fn limit_2set_blocks<T, V>(
+    set_a: &[T],
+    set_b: &[T],
+    intersect: Intersect2<[T], V>,
+    visitor: &mut V,
+    block_len: usize)
+where
+    T: Ord,
+    V: Visitor<T> + Saturable,
+{
+    assert!(block_len > 0, "block_len must be positive");
+
+    let a_is_small = set_a.len() <= set_b.len();
+    let (small, large) = if a_is_small { (set_a, set_b) } else { (set_b, set_a) };
+
+    let mut rest = large;
+    for block in small.chunks(block_len) {
+        if visitor.is_saturated() || rest.is_empty() {
+            return;
+        }
+        let (large_block, remaining) = split_through(rest, block);
+        rest = remaining;
+
+        if !large_block.is_empty() {
+            // Keep the argument order of the caller for asymmetric algorithms.
+            if a_is_small {
+                intersect(block, large_block, visitor);
+            }
+            else {
+                intersect(large_block, block, visitor);
+            }
+        }
+    }
+}
+
+/// Galloping which checks after each result whether `visitor` is saturated,
+/// so no further elements of `small` are searched for.
+pub fn limit_galloping<T, V>(small: &[T], mut large: &[T], visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T> + Saturable,
+{
+    for &target in small {
+        if visitor.is_saturated() {
+            return;
+        }
+        let base = gallop(large, target);
+
+        if base < large.len() && large[base] == target {
+            visitor.visit(target);
+        }
+        large = &large[base..];
+    }
+}
+
+/// Intersects `sets` with the k-set algorithm `intersect`, stopping once
+/// `visitor` is saturated.
+pub fn limit_kset<'a, T, S, V>(
+    sets: &'a [S],
+    intersect: IntersectK<&'a [T], V>,
+    visitor: &mut V)
+where
+    T: Ord,
+    S: AsRef<[T]>,
+    V: Visitor<T> + Saturable,
+{
+    limit_kset_blocks(sets, intersect, visitor, DEFAULT_BLOCK_LEN)
+}
+
+pub fn limit_kset_blocks<'a, T, S, V>(
+    sets: &'a [S],
+    intersect: IntersectK<&'a [T], V>,
+    visitor: &mut V,
+    block_len: usize)
+where
+    T: Ord,
+    S: AsRef<[T]>,
+    V: Visitor<T> + Saturable,
+{
+    assert!(block_len > 0, "block_len must be positive");
+
+    let Some(smallest) = (0..sets.len()).min_by_key(|&i| sets[i].as_ref().len()) else {
+        return;
+    };
+
+    let mut rests: Vec<&'a [T]> = sets.iter().map(|s| s.as_ref()).collect();
+    let mut blocks: Vec<&'a [T]> = rests.clone();
+
+    for block in sets[smallest].as_ref().chunks(block_len) {
+        if visitor.is_saturated() {
+            return;
+        }
+
+        let mut empty = false;
+        for (i, rest) in rests.iter_mut().enumerate() {
+            if i == smallest {
+                blocks[i] = block;
+                continue;
+            }
+            let (set_block, remaining) = split_through(rest, block);
+            *rest = remaining;
+            blocks[i] = set_block;
+            empty |= set_block.is_empty();
+        }
+
+        if !empty {
+            intersect(&blocks, visitor);
+        }
+    }
+}
+
+// Splits `set` after the elements no greater than the last element of `block`.
+#[inline]
+fn split_through<'s, T: Ord>(set: &'s [T], block: &[T]) -> (&'s [T], &'s [T]) {
+    match block.last() {
+        Some(last) => set.split_at(set.partition_point(|item| item <= last)),
+        None => (&set[..0], set),
+    }
+}
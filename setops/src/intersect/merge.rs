@@ -1,6 +1,8 @@
 use std::cmp::Ordering;
 
-use crate::{visitor::{Visitor, BsrVisitor}, bsr::BsrRef};
+use crate::{visitor::Visitor, instrument::count_comparisons};
+#[cfg(feature = "bsr")]
+use crate::{visitor::BsrVisitor, bsr::BsrRef};
 
 /// Classical set intersection via merge. Original author unknown.
 // Inspired by https://highlyscalable.wordpress.com/2012/06/05/fast-intersection-sorted-lists-sse/
@@ -15,6 +17,7 @@ where
     while idx_a < set_a.len() && idx_b < set_b.len() {
         let value_a = set_a[idx_a];
         let value_b = set_b[idx_b];
+        count_comparisons(1);
 
         match value_a.cmp(&value_b) {
             Ordering::Less =>
@@ -47,6 +50,7 @@ where
     while idx_a < set_a.len() && idx_b < set_b.len() {
         let value_a = set_a[idx_a];
         let value_b = set_b[idx_b];
+        count_comparisons(1);
 
         if value_a == value_b {
             visitor.visit(value_a);
@@ -59,6 +63,92 @@ where
     }
 }
 
+/// `branchless_merge` unrolled twice. Each step advances each index by at
+/// most one, so both steps are in bounds while two elements remain in both
+/// sets.
+pub fn branchless_merge_unroll2<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    let mut idx_a = 0;
+    let mut idx_b = 0;
+
+    while idx_a + 2 <= set_a.len() && idx_b + 2 <= set_b.len() {
+        branchless_step(set_a, set_b, &mut idx_a, &mut idx_b, visitor);
+        branchless_step(set_a, set_b, &mut idx_a, &mut idx_b, visitor);
+    }
+    branchless_merge(&set_a[idx_a..], &set_b[idx_b..], visitor)
+}
+
+/// `branchless_merge` unrolled four times.
+pub fn branchless_merge_unroll4<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    let mut idx_a = 0;
+    let mut idx_b = 0;
+
+    while idx_a + 4 <= set_a.len() && idx_b + 4 <= set_b.len() {
+        branchless_step(set_a, set_b, &mut idx_a, &mut idx_b, visitor);
+        branchless_step(set_a, set_b, &mut idx_a, &mut idx_b, visitor);
+        branchless_step(set_a, set_b, &mut idx_a, &mut idx_b, visitor);
+        branchless_step(set_a, set_b, &mut idx_a, &mut idx_b, visitor);
+    }
+    branchless_merge(&set_a[idx_a..], &set_b[idx_b..], visitor)
+}
+
+/// Merge whose index updates are `select_unpredictable`, which asks the
+/// compiler for conditional moves rather than branches. Only the visit of a
+/// match is conditional.
+pub fn branchless_merge_cmov<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    use std::hint::select_unpredictable;
+
+    let mut idx_a = 0;
+    let mut idx_b = 0;
+
+    while idx_a < set_a.len() && idx_b < set_b.len() {
+        let value_a = unsafe { *set_a.get_unchecked(idx_a) };
+        let value_b = unsafe { *set_b.get_unchecked(idx_b) };
+        count_comparisons(1);
+
+        if value_a == value_b {
+            visitor.visit(value_a);
+        }
+        idx_a = select_unpredictable(value_a <= value_b, idx_a + 1, idx_a);
+        idx_b = select_unpredictable(value_b <= value_a, idx_b + 1, idx_b);
+    }
+}
+
+/// One step of `branchless_merge`. Callers ensure both indices are in bounds.
+#[inline(always)]
+fn branchless_step<T, V>(
+    set_a: &[T],
+    set_b: &[T],
+    idx_a: &mut usize,
+    idx_b: &mut usize,
+    visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    let value_a = unsafe { *set_a.get_unchecked(*idx_a) };
+    let value_b = unsafe { *set_b.get_unchecked(*idx_b) };
+    count_comparisons(1);
+
+    if value_a == value_b {
+        visitor.visit(value_a);
+    }
+    *idx_a += (value_a <= value_b) as usize;
+    *idx_b += (value_b <= value_a) as usize;
+}
+
+#[cfg(feature = "bsr")]
 pub fn branchless_merge_bsr<'a, V>(set_a: BsrRef<'a>, set_b: BsrRef<'a>, visitor: &mut V)
 where
     V: BsrVisitor,
@@ -12,37 +12,37 @@ pub fn branchless_merge_mono(set_a: &[i32], set_b: &[i32], visitor: &mut VecWrit
     branchless_merge(set_a, set_b, visitor);
 }
     
-#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+#[cfg(all(feature = "shuffling", target_feature = "ssse3"))]
 pub fn shuffling_sse_mono(set_a: &[i32], set_b: &[i32], visitor: &mut VecWriter<i32>)
 {
     shuffling_sse(set_a, set_b, visitor);
 }
     
-#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+#[cfg(all(feature = "shuffling", target_feature = "ssse3"))]
 pub fn shuffling_sse_branch_mono(set_a: &[i32], set_b: &[i32], visitor: &mut VecWriter<i32>)
 {
     shuffling_sse_branch(set_a, set_b, visitor);
 }
     
-#[cfg(all(feature = "simd", target_feature = "avx2"))]
+#[cfg(all(feature = "shuffling", target_feature = "avx2"))]
 pub fn shuffling_avx2_mono(set_a: &[i32], set_b: &[i32], visitor: &mut VecWriter<i32>)
 {
     shuffling_avx2(set_a, set_b, visitor);
 }
     
-#[cfg(all(feature = "simd", target_feature = "avx2"))]
+#[cfg(all(feature = "shuffling", target_feature = "avx2"))]
 pub fn shuffling_avx2_branch_mono(set_a: &[i32], set_b: &[i32], visitor: &mut VecWriter<i32>)
 {
     shuffling_avx2_branch(set_a, set_b, visitor);
 }
     
-#[cfg(all(feature = "simd", target_feature = "avx512f"))]
+#[cfg(all(feature = "shuffling", target_feature = "avx512f"))]
 pub fn shuffling_avx512_mono(set_a: &[i32], set_b: &[i32], visitor: &mut VecWriter<i32>)
 {
     shuffling_avx512(set_a, set_b, visitor);
 }
     
-#[cfg(all(feature = "simd", target_feature = "avx512f"))]
+#[cfg(all(feature = "shuffling", target_feature = "avx512f"))]
 pub fn shuffling_avx512_branch_mono(set_a: &[i32], set_b: &[i32], visitor: &mut VecWriter<i32>)
 {
     shuffling_avx512_branch(set_a, set_b, visitor);
@@ -96,6 +96,12 @@ pub fn bmiss_branch_mono(set_a: &[i32], set_b: &[i32], visitor: &mut VecWriter<i
     bmiss_branch(set_a, set_b, visitor);
 }
     
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+pub fn bmiss_galloping_mono(set_a: &[i32], set_b: &[i32], visitor: &mut VecWriter<i32>)
+{
+    bmiss_galloping(set_a, set_b, visitor);
+}
+    
 #[cfg(all(feature = "simd", target_feature = "ssse3"))]
 pub fn bmiss_sttni_mono(set_a: &[i32], set_b: &[i32], visitor: &mut VecWriter<i32>)
 {
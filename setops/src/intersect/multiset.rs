@@ -0,0 +1,157 @@
+//! Intersection of multisets, i.e., sorted arrays which may repeat elements,
+//! as for the terms of tokenised text. An element repeated `count_a` times in
+//! `A` and `count_b` times in `B` is visited `min(count_a, count_b)` times.
+//!
+//! `multiset_merge` works on the arrays directly. Other kernels assume strictly
+//! sorted sets, e.g., the SIMD kernels compare every pair of elements of two
+//! vectors and so would visit a repeated element once per pair. For them,
+//! `MultisetLayers` splits a multiset into strictly sorted layers, where layer
+//! `k` holds the elements repeated more than `k` times. An element is then in
+//! layer `k` of both inputs exactly when `k < min(count_a, count_b)`, so
+//! `multiset_intersect` intersects the layers pairwise with any kernel and
+//! visits each element once per layer it was found in.
+//!
+//! For set semantics instead, i.e., each common element visited once, wrap
+//! the visitor of any kernel in `DedupAdapter`.
+
+use crate::{
+    intersect::Intersect2,
+    visitor::{Visitor, VecWriter},
+};
+
+/// Merges the runs of equal elements of the sorted multisets `set_a` and
+/// `set_b`, visiting each element common to both as many times as the
+/// shorter of its runs.
+pub fn multiset_merge<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    let mut idx_a = 0;
+    let mut idx_b = 0;
+
+    while idx_a < set_a.len() && idx_b < set_b.len() {
+        let value_a = set_a[idx_a];
+        let value_b = set_b[idx_b];
+
+        if value_a == value_b {
+            let run_a = run_len(&set_a[idx_a..], value_a);
+            let run_b = run_len(&set_b[idx_b..], value_b);
+            for _ in 0..run_a.min(run_b) {
+                visitor.visit(value_a);
+            }
+            idx_a += run_a;
+            idx_b += run_b;
+        } else {
+            idx_a += (value_a < value_b) as usize;
+            idx_b += (value_b < value_a) as usize;
+        }
+    }
+}
+
+/// Number of leading elements of `set` equal to `value`.
+#[inline]
+fn run_len<T: Eq + Copy>(set: &[T], value: T) -> usize {
+    set.iter().take_while(|&&item| item == value).count()
+}
+
+/// A multiset split into strictly sorted layers, so that kernels for sets
+/// can intersect it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisetLayers<T> {
+    // Layer `k` holds the elements repeated more than `k` times, so each
+    // layer is a subset of the one before.
+    layers: Vec<Vec<T>>,
+}
+
+impl<T> MultisetLayers<T>
+where
+    T: Ord + Copy,
+{
+    /// Splits the sorted multiset `sorted`.
+    pub fn from_sorted(sorted: &[T]) -> Self {
+        debug_assert!(sorted.windows(2).all(|w| w[0] <= w[1]),
+            "multiset must be sorted");
+
+        let mut layers: Vec<Vec<T>> = Vec::new();
+        let mut i = 0;
+        while i < sorted.len() {
+            let run = run_len(&sorted[i..], sorted[i]);
+            if layers.len() < run {
+                layers.resize_with(run, Vec::new);
+            }
+            for layer in &mut layers[..run] {
+                layer.push(sorted[i]);
+            }
+            i += run;
+        }
+        Self { layers }
+    }
+
+    pub fn layers(&self) -> &[Vec<T>] {
+        &self.layers
+    }
+
+    /// Number of elements, counting repeats.
+    pub fn len(&self) -> usize {
+        self.layers.iter().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// The sorted multiset.
+    pub fn to_sorted(&self) -> Vec<T> {
+        let mut writer = VecWriter::with_capacity(self.len());
+        self.visit_counted(&mut writer);
+        writer.into()
+    }
+
+    /// Visits the elements of the first layer in order, each as many times as
+    /// the layers holding it.
+    fn visit_counted(&self, visitor: &mut impl Visitor<T>) {
+        let Some((first, rest)) = self.layers.split_first() else {
+            return;
+        };
+        let mut positions = vec![0; rest.len()];
+        for &item in first {
+            visitor.visit(item);
+            // Later layers are subsets, so the first missing `item` ends it.
+            for (layer, position) in rest.iter().zip(positions.iter_mut()) {
+                if layer.get(*position) != Some(&item) {
+                    break;
+                }
+                *position += 1;
+                visitor.visit(item);
+            }
+        }
+    }
+}
+
+/// Intersects the layers of `set_a` and `set_b` pairwise with `intersect`,
+/// which may be any kernel for strictly sorted sets, then visits each element
+/// common to both as many times as it is repeated in the input repeating it
+/// least, in ascending order.
+pub fn multiset_intersect<T, V>(
+    set_a: &MultisetLayers<T>,
+    set_b: &MultisetLayers<T>,
+    intersect: Intersect2<[T], VecWriter<T>>,
+    visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    let mut layers: Vec<Vec<T>> = Vec::new();
+    for (layer_a, layer_b) in set_a.layers.iter().zip(&set_b.layers) {
+        let mut writer: VecWriter<T> = VecWriter::new();
+        intersect(layer_a, layer_b, &mut writer);
+        let layer: Vec<T> = writer.into();
+        // Layers of the result are subsets of the one before too.
+        if layer.is_empty() {
+            break;
+        }
+        layers.push(layer);
+    }
+    MultisetLayers { layers }.visit_counted(visitor);
+}
@@ -0,0 +1,189 @@
+//! Key-value intersection (merge join). Each input is a sorted, deduplicated
+//! key array with a parallel array of payloads, where `payloads[i]` belongs to
+//! `keys[i]`. Matching keys are visited together with both payloads.
+//! The SIMD variants find matches with the shuffling kernels and gather the
+//! payloads of matched lanes from `payload_b` by the position of the match.
+
+use std::cmp::Ordering;
+#[cfg(feature = "simd")]
+use std::simd::{*, cmp::*, num::SimdInt};
+
+use crate::{intersect::gallop, visitor::PayloadVisitor};
+#[cfg(feature = "simd")]
+use crate::{element::IntElement, instructions::load_unsafe};
+
+/// Merge join of two key-payload inputs.
+pub fn payload_merge<K, A, B, V>(
+    keys_a: &[K], payload_a: &[A],
+    keys_b: &[K], payload_b: &[B],
+    visitor: &mut V)
+where
+    K: Ord + Copy,
+    A: Copy,
+    B: Copy,
+    V: PayloadVisitor<K, A, B>,
+{
+    check_lengths(keys_a, payload_a, keys_b, payload_b);
+
+    let mut idx_a = 0;
+    let mut idx_b = 0;
+
+    while idx_a < keys_a.len() && idx_b < keys_b.len() {
+        let key_a = keys_a[idx_a];
+        let key_b = keys_b[idx_b];
+
+        match key_a.cmp(&key_b) {
+            Ordering::Less =>
+                idx_a += 1,
+
+            Ordering::Greater =>
+                idx_b += 1,
+
+            Ordering::Equal => {
+                visitor.visit_payload(key_a, payload_a[idx_a], payload_b[idx_b]);
+                idx_a += 1;
+                idx_b += 1;
+            },
+        }
+    }
+}
+
+/// Galloping join for skewed inputs: each key of `keys_small` is searched for
+/// in `keys_large`.
+pub fn payload_galloping<K, A, B, V>(
+    keys_small: &[K], payload_small: &[A],
+    keys_large: &[K], payload_large: &[B],
+    visitor: &mut V)
+where
+    K: Ord + Copy,
+    A: Copy,
+    B: Copy,
+    V: PayloadVisitor<K, A, B>,
+{
+    check_lengths(keys_small, payload_small, keys_large, payload_large);
+
+    let mut base = 0;
+    for (&key, &payload) in keys_small.iter().zip(payload_small) {
+        base += gallop(&keys_large[base..], key);
+
+        if base < keys_large.len() && keys_large[base] == key {
+            visitor.visit_payload(key, payload, payload_large[base]);
+        }
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+pub fn payload_shuffling_sse<K, A, B, V>(
+    keys_a: &[K], payload_a: &[A],
+    keys_b: &[K], payload_b: &[B],
+    visitor: &mut V)
+where
+    K: IntElement<Lane = i32>,
+    A: SimdElement + Default,
+    B: SimdElement + Default,
+    V: PayloadVisitor<K, A, B>,
+{
+    payload_shuffling::<4, K, A, B, V>(keys_a, payload_a, keys_b, payload_b, visitor)
+}
+
+#[cfg(all(feature = "simd", target_feature = "avx2"))]
+pub fn payload_shuffling_avx2<K, A, B, V>(
+    keys_a: &[K], payload_a: &[A],
+    keys_b: &[K], payload_b: &[B],
+    visitor: &mut V)
+where
+    K: IntElement<Lane = i32>,
+    A: SimdElement + Default,
+    B: SimdElement + Default,
+    V: PayloadVisitor<K, A, B>,
+{
+    payload_shuffling::<8, K, A, B, V>(keys_a, payload_a, keys_b, payload_b, visitor)
+}
+
+#[cfg(all(feature = "simd", target_feature = "avx512f"))]
+pub fn payload_shuffling_avx512<K, A, B, V>(
+    keys_a: &[K], payload_a: &[A],
+    keys_b: &[K], payload_b: &[B],
+    visitor: &mut V)
+where
+    K: IntElement<Lane = i32>,
+    A: SimdElement + Default,
+    B: SimdElement + Default,
+    V: PayloadVisitor<K, A, B>,
+{
+    payload_shuffling::<16, K, A, B, V>(keys_a, payload_a, keys_b, payload_b, visitor)
+}
+
+/// Shuffling intersection of the keys. For each rotation `r` of the block of
+/// `keys_b`, lanes of `keys_a` matching under that rotation come from position
+/// `(lane + r) % W` of the block, which gives the indices of the payloads to
+/// gather from `payload_b`.
+#[cfg(feature = "simd")]
+#[inline]
+fn payload_shuffling<const W: usize, K, A, B, V>(
+    keys_a: &[K], payload_a: &[A],
+    keys_b: &[K], payload_b: &[B],
+    visitor: &mut V)
+where
+    LaneCount<W>: SupportedLaneCount,
+    K: IntElement<Lane = i32>,
+    A: SimdElement + Default,
+    B: SimdElement + Default,
+    V: PayloadVisitor<K, A, B>,
+{
+    check_lengths(keys_a, payload_a, keys_b, payload_b);
+
+    let ptr_a = K::as_lanes(keys_a).as_ptr();
+    let ptr_b = K::as_lanes(keys_b).as_ptr();
+
+    let lanes = Simd::<i32, W>::from_array(std::array::from_fn(|i| i as i32));
+    let wrap = Simd::<i32, W>::splat(W as i32 - 1);
+
+    let st_a = (keys_a.len() / W) * W;
+    let st_b = (keys_b.len() / W) * W;
+
+    let mut i_a: usize = 0;
+    let mut i_b: usize = 0;
+    while i_a < st_a && i_b < st_b {
+        let v_a: Simd<i32, W> = unsafe{ load_unsafe(ptr_a.add(i_a)) };
+        let mut v_b: Simd<i32, W> = unsafe{ load_unsafe(ptr_b.add(i_b)) };
+
+        let mut mask = Mask::<i32, W>::splat(false);
+        let mut rotation = Simd::<i32, W>::splat(0);
+        for r in 0..W {
+            let matched = v_a.simd_eq(v_b);
+            mask |= matched;
+            rotation = matched.select(Simd::splat(r as i32), rotation);
+            v_b = v_b.rotate_elements_left::<1>();
+        }
+
+        let bitmask = mask.to_bitmask();
+        if bitmask != 0 {
+            let offsets = ((lanes + rotation) & wrap).cast::<usize>();
+            let gathered_b = Simd::<B, W>::gather_select(
+                &payload_b[i_b..i_b + W],
+                Mask::from_bitmask(bitmask),
+                offsets,
+                Simd::splat(B::default()));
+            let block_a = Simd::<A, W>::from_slice(&payload_a[i_a..i_a + W]);
+
+            let keys = unsafe { keys_a.get_unchecked(i_a..i_a + W) };
+            visitor.visit_payload_lanes(keys, block_a, gathered_b, bitmask);
+        }
+
+        let a_max = unsafe { *keys_a.get_unchecked(i_a + W - 1) };
+        let b_max = unsafe { *keys_b.get_unchecked(i_b + W - 1) };
+
+        i_a += W * (a_max <= b_max) as usize;
+        i_b += W * (b_max <= a_max) as usize;
+    }
+    payload_merge(
+        &keys_a[i_a..], &payload_a[i_a..],
+        &keys_b[i_b..], &payload_b[i_b..],
+        visitor)
+}
+
+fn check_lengths<K, A, B>(keys_a: &[K], payload_a: &[A], keys_b: &[K], payload_b: &[B]) {
+    assert!(keys_a.len() == payload_a.len() && keys_b.len() == payload_b.len(),
+        "each key must have exactly one payload");
+}
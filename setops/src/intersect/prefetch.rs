@@ -0,0 +1,136 @@
+//! Merge and galloping with explicit software prefetches a fixed distance (in
+//! elements) ahead of the current position in both sets, to measure the
+//! benefit of prefetching on sets larger than the cache.
+
+use crate::{
+    intersect::{gallop, TwoSetAlgorithm},
+    visitor::Visitor,
+};
+
+/// Default prefetch distance, four cache lines of 32-bit elements.
+pub const DEFAULT_PREFETCH_DISTANCE: usize = 64;
+
+/// Hints that `set[index]` will be read soon. `index` may be out of bounds,
+/// as a prefetch never faults.
+#[inline]
+pub fn prefetch<T>(set: &[T], index: usize) {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::{_mm_prefetch, _MM_HINT_T0};
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    unsafe {
+        _mm_prefetch::<_MM_HINT_T0>(set.as_ptr().wrapping_add(index) as *const i8);
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    let _ = (set, index);
+}
+
+/// `branchless_merge` prefetching `distance` elements ahead in both sets.
+pub fn merge_prefetch<T, V>(set_a: &[T], set_b: &[T], distance: usize, visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    let mut idx_a = 0;
+    let mut idx_b = 0;
+
+    while idx_a < set_a.len() && idx_b < set_b.len() {
+        prefetch(set_a, idx_a + distance);
+        prefetch(set_b, idx_b + distance);
+
+        let value_a = set_a[idx_a];
+        let value_b = set_b[idx_b];
+
+        if value_a == value_b {
+            visitor.visit(value_a);
+            idx_a += 1;
+            idx_b += 1;
+        } else {
+            idx_a += (value_a < value_b) as usize;
+            idx_b += (value_b < value_a) as usize;
+        }
+    }
+}
+
+/// `galloping` prefetching `distance` elements ahead of the current target in
+/// `small` and of the current search base in `large`.
+pub fn galloping_prefetch<T, V>(small: &[T], mut large: &[T], distance: usize, visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    for (i, &target) in small.iter().enumerate() {
+        prefetch(small, i + distance);
+        prefetch(large, distance);
+
+        let base = gallop(large, target);
+
+        if base < large.len() && large[base] == target {
+            visitor.visit(target);
+        }
+        large = &large[base..];
+    }
+}
+
+/// `merge_prefetch` as a `TwoSetAlgorithm` carrying its prefetch distance.
+pub struct MergePrefetch {
+    pub distance: usize,
+}
+
+/// `galloping_prefetch` as a `TwoSetAlgorithm` carrying its prefetch
+/// distance. The smaller set is searched for in the larger.
+pub struct GallopingPrefetch {
+    pub distance: usize,
+}
+
+impl Default for MergePrefetch {
+    fn default() -> Self {
+        Self { distance: DEFAULT_PREFETCH_DISTANCE }
+    }
+}
+
+impl Default for GallopingPrefetch {
+    fn default() -> Self {
+        Self { distance: DEFAULT_PREFETCH_DISTANCE }
+    }
+}
+
+impl<T, V> TwoSetAlgorithm<T, V> for MergePrefetch
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    type Prepared<'a> = &'a [T] where T: 'a;
+
+    fn prepare<'a>(&self, set: &'a [T]) -> &'a [T] {
+        set
+    }
+
+    fn intersect(&self, set_a: &&[T], set_b: &&[T], visitor: &mut V) {
+        merge_prefetch(set_a, set_b, self.distance, visitor)
+    }
+}
+
+impl<T, V> TwoSetAlgorithm<T, V> for GallopingPrefetch
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    type Prepared<'a> = &'a [T] where T: 'a;
+
+    fn prepare<'a>(&self, set: &'a [T]) -> &'a [T] {
+        set
+    }
+
+    fn intersect(&self, set_a: &&[T], set_b: &&[T], visitor: &mut V) {
+        let (small, large) = if set_a.len() <= set_b.len() {
+            (set_a, set_b)
+        }
+        else {
+            (set_b, set_a)
+        };
+        galloping_prefetch(small, large, self.distance, visitor)
+    }
+}
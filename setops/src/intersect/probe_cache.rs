@@ -0,0 +1,120 @@
+//! Galloping against a large set shared by many queries. A `ProbeCache`
+//! samples every 2^k-th element of the large set into a rank index, built once
+//! and small enough to stay cached across queries. Each probe gallops over the
+//! samples, then searches a single block of 2^k elements of the large set,
+//! rather than repeating the search path through the large set for every
+//! query.
+
+use crate::{
+    intersect::{gallop, range::lower_bound, TwoSetAlgorithm},
+    visitor::Visitor,
+};
+
+/// Default log2 of the number of elements per sample, so that a block of
+/// 32-bit elements is one 64-byte cache line.
+pub const DEFAULT_SAMPLE_SHIFT: u32 = 4;
+
+/// Rank index of a sorted set: `samples[i]` is element `i << shift`.
+pub struct ProbeCache<T> {
+    samples: Vec<T>,
+    shift: u32,
+}
+
+impl<T: Copy> ProbeCache<T> {
+    pub fn new(set: &[T]) -> Self {
+        Self::with_shift(set, DEFAULT_SAMPLE_SHIFT)
+    }
+
+    pub fn with_shift(set: &[T], shift: u32) -> Self {
+        let samples = set.iter().step_by(1 << shift).copied().collect();
+        Self { samples, shift }
+    }
+
+    pub fn samples(&self) -> &[T] {
+        &self.samples
+    }
+
+    pub fn shift(&self) -> u32 {
+        self.shift
+    }
+}
+
+/// Gallops each element of `small` through the rank index of `large` in
+/// `cache`, which must have been built from `large`.
+pub fn galloping_cached<T, V>(small: &[T], large: &[T], cache: &ProbeCache<T>, visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    let samples = cache.samples.as_slice();
+    debug_assert!(samples.len() == large.len().div_ceil(1 << cache.shift),
+        "cache was not built from the large set");
+
+    let mut sample = 0;
+    for &target in small {
+        let pos = sample + gallop(&samples[sample..], target);
+
+        if pos < samples.len() && samples[pos] == target {
+            visitor.visit(target);
+            sample = pos;
+            continue;
+        }
+        if pos == 0 {
+            continue;
+        }
+        // samples[pos - 1] < target < samples[pos], so only the rest of
+        // block pos - 1 can hold the target.
+        sample = pos - 1;
+        let start = (sample << cache.shift) + 1;
+        let end = (pos << cache.shift).min(large.len());
+        let block = &large[start..end];
+
+        let i = lower_bound(block, target);
+        if i < block.len() && block[i] == target {
+            visitor.visit(target);
+        }
+    }
+}
+
+/// A sorted set with its rank index.
+pub struct CachedSet<'a, T> {
+    pub elements: &'a [T],
+    pub cache: ProbeCache<T>,
+}
+
+/// `galloping_cached` as a 2-set algorithm, probing the larger set's cache
+/// with the smaller set.
+pub struct CachedGalloping {
+    pub shift: u32,
+}
+
+impl Default for CachedGalloping {
+    fn default() -> Self {
+        Self { shift: DEFAULT_SAMPLE_SHIFT }
+    }
+}
+
+impl<T, V> TwoSetAlgorithm<T, V> for CachedGalloping
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    type Prepared<'a> = CachedSet<'a, T> where T: 'a;
+
+    fn prepare<'a>(&self, set: &'a [T]) -> CachedSet<'a, T> {
+        CachedSet {
+            elements: set,
+            cache: ProbeCache::with_shift(set, self.shift),
+        }
+    }
+
+    fn intersect(&self, set_a: &CachedSet<T>, set_b: &CachedSet<T>, visitor: &mut V) {
+        let (small, large) = if set_a.elements.len() <= set_b.elements.len() {
+            (set_a, set_b)
+        }
+        else {
+            (set_b, set_a)
+        };
+        galloping_cached(small.elements, large.elements, &large.cache, visitor)
+    }
+}
@@ -11,15 +11,18 @@
 /// https://github.com/pkumod/GraphSetIntersection (MIT License)
 
 use crate::{
-    visitor::{Visitor, SimdVisitor4, SimdBsrVisitor4},
+    element::IntElement,
+    visitor::{Visitor, SimdVisitor4},
     instructions::load_unsafe,
     intersect,
     instructions::{
         convert, shuffle_epi8,
         BYTE_CHECK_GROUP_A, BYTE_CHECK_GROUP_B,
         BYTE_CHECK_GROUP_A_VEC, BYTE_CHECK_GROUP_B_VEC
-    }, bsr::BsrRef,
+    },
 };
+#[cfg(feature = "bsr")]
+use crate::{visitor::SimdBsrVisitor4, bsr::BsrRef};
 use std::{
     simd::*,
     simd::cmp::*,
@@ -32,11 +35,10 @@ use std::{
 pub fn qfilter<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor4,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 4;
 
@@ -88,7 +90,7 @@ where
         visitor)
 }
 
-#[cfg(target_feature = "ssse3")]
+#[cfg(all(feature = "bsr", target_feature = "ssse3"))]
 pub fn qfilter_bsr<'a, V>(set_a: BsrRef<'a>, set_b: BsrRef<'a>, visitor: &mut V)
 where
     V: SimdBsrVisitor4,
@@ -184,11 +186,10 @@ where
 pub fn qfilter_v1<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor4,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 4;
 
@@ -362,11 +363,10 @@ const fn offsets_to_shuffle_mask4(offsets: usize) -> u8x16 {
 pub fn qfilter_branch<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor4,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 4;
 
@@ -446,7 +446,7 @@ where
         visitor)
 }
 
-#[cfg(target_feature = "ssse3")]
+#[cfg(all(feature = "bsr", target_feature = "ssse3"))]
 pub fn qfilter_bsr_branch<'a, V>(set_a: BsrRef<'a>, set_b: BsrRef<'a>, visitor: &mut V)
 where
     V: SimdBsrVisitor4,
@@ -570,11 +570,10 @@ where
 pub fn qfilter_v1_branch<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor4,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 4;
 
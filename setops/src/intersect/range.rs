@@ -0,0 +1,62 @@
+//! Intersection restricted to a range of values, e.g., the documents of a
+//! time window in posting lists ordered by time. Both sets are first narrowed
+//! to the range by binary search, then intersected with any sorted-array
+//! algorithm.
+
+use std::ops::Range;
+
+use crate::{intersect::Intersect2, visitor::Visitor};
+
+/// Intersects the elements of `set_a` and `set_b` within `range` with
+/// `intersect`.
+pub fn intersect_range<T, V>(
+    set_a: &[T],
+    set_b: &[T],
+    range: Range<T>,
+    intersect: Intersect2<[T], V>,
+    visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    let narrow_a = narrow(set_a, &range);
+    let narrow_b = narrow(set_b, &range);
+
+    if !narrow_a.is_empty() && !narrow_b.is_empty() {
+        intersect(narrow_a, narrow_b, visitor);
+    }
+}
+
+/// The part of `set` within `range`.
+pub fn narrow<'a, T>(set: &'a [T], range: &Range<T>) -> &'a [T]
+where
+    T: Ord + Copy,
+{
+    let start = lower_bound(set, range.start);
+    let end = lower_bound(set, range.end).max(start);
+    &set[start..end]
+}
+
+/// Returns the index of the first element of `set` not less than `target`.
+/// The search halves the candidate range unconditionally each step, so the
+/// comparison selects the next base (a conditional move) rather than a
+/// branch.
+#[inline]
+pub fn lower_bound<T>(set: &[T], target: T) -> usize
+where
+    T: Ord + Copy,
+{
+    if set.is_empty() {
+        return 0;
+    }
+
+    let mut base = 0;
+    let mut len = set.len();
+    while len > 1 {
+        let half = len / 2;
+        let mid = unsafe { *set.get_unchecked(base + half) };
+        base = if mid < target { base + half } else { base };
+        len -= half;
+    }
+    base + (unsafe { *set.get_unchecked(base) } < target) as usize
+}
@@ -1,4 +1,4 @@
-#![cfg(feature = "simd")]
+#![cfg(all(feature = "simd", feature = "shuffling"))]
 
 use std::{
     simd::*,
@@ -7,17 +7,21 @@ use std::{
 };
 
 use crate::{
-    visitor::{Visitor, SimdVisitor4,SimdBsrVisitor4},
-    intersect, instructions::load_unsafe,
-    bsr::BsrRef,
+    element::IntElement,
+    visitor::{Visitor, SimdVisitor4},
+    intersect, instrument::count_comparisons, instructions::{load_unsafe, load_aligned_unsafe},
     util::*,
 };
+#[cfg(feature = "bsr")]
+use crate::{visitor::SimdBsrVisitor4, bsr::BsrRef};
 #[cfg(target_feature = "avx2")]
-use crate::visitor::{
-    SimdVisitor8, SimdBsrVisitor8,
-};
+use crate::visitor::SimdVisitor8;
+#[cfg(all(feature = "bsr", target_feature = "avx2"))]
+use crate::visitor::SimdBsrVisitor8;
 #[cfg(target_feature = "avx512f")]
-use crate::visitor::{SimdVisitor16, SimdBsrVisitor16};
+use crate::visitor::SimdVisitor16;
+#[cfg(all(feature = "bsr", target_feature = "avx512f"))]
+use crate::visitor::SimdBsrVisitor16;
 
 /// SIMD Shuffling set intersection algorithm - Ilya Katsov 2012
 /// https://highlyscalable.wordpress.com/2012/06/05/fast-intersection-sorted-lists-sse/
@@ -26,11 +30,10 @@ use crate::visitor::{SimdVisitor16, SimdBsrVisitor16};
 pub fn shuffling_sse<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor4,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 4;
 
@@ -50,6 +53,7 @@ where
             v_a.simd_eq(v_b.rotate_elements_left::<3>()),
         ];
         let mask = or_4(masks);
+        count_comparisons((W * W) as u64);
 
         visitor.visit_vector4(v_a, mask.to_bitmask());
 
@@ -69,11 +73,10 @@ where
 pub fn shuffling_avx2<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor8,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 8;
 
@@ -96,6 +99,7 @@ where
                 v_a.simd_eq(v_b.rotate_elements_left::<7>()),
         ];
         let mask = or_8(masks);
+        count_comparisons((W * W) as u64);
 
         visitor.visit_vector8(v_a, mask.to_bitmask());
 
@@ -116,11 +120,10 @@ where
 pub fn shuffling_avx512<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor16,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 16;
 
@@ -152,6 +155,7 @@ where
                 v_a.simd_eq(v_b.rotate_elements_left::<15>()),
         ];
         let mask = or_16(masks);
+        count_comparisons((W * W) as u64);
 
         visitor.visit_vector16(v_a, mask.to_bitmask());
 
@@ -170,7 +174,7 @@ where
 
 // BSR implementations //
 
-#[cfg(target_feature = "ssse3")]
+#[cfg(all(feature = "bsr", target_feature = "ssse3"))]
 pub fn shuffling_sse_bsr<'a, V>(set_a: BsrRef<'a>, set_b: BsrRef<'a>, visitor: &mut V)
 where
     V: SimdBsrVisitor4,
@@ -220,7 +224,7 @@ where
         visitor)
 }
 
-#[cfg(target_feature = "avx2")]
+#[cfg(all(feature = "bsr", target_feature = "avx2"))]
 pub fn shuffling_avx2_bsr<'a, V>(set_a: BsrRef<'a>, set_b: BsrRef<'a>, visitor: &mut V)
 where
     V: SimdBsrVisitor8,
@@ -278,7 +282,7 @@ where
         visitor)
 }
 
-#[cfg(target_feature = "avx512f")]
+#[cfg(all(feature = "bsr", target_feature = "avx512f"))]
 pub fn shuffling_avx512_bsr<'a, V>(
     set_a: BsrRef<'a>,
     set_b: BsrRef<'a>,
@@ -363,11 +367,10 @@ where
 pub fn shuffling_sse_branch<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor4,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 4;
 
@@ -387,6 +390,7 @@ where
                 v_a.simd_eq(v_b.rotate_elements_left::<3>()),
             ];
             let mask = or_4(masks);
+            count_comparisons((W * W) as u64);
 
             visitor.visit_vector4(v_a, mask.to_bitmask());
 
@@ -429,11 +433,10 @@ where
 pub fn shuffling_avx2_branch<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor8,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 8;
 
@@ -457,6 +460,7 @@ where
                  v_a.simd_eq(v_b.rotate_elements_left::<7>()),
             ];
             let mask = or_8(masks);
+            count_comparisons((W * W) as u64);
 
             visitor.visit_vector8(v_a, mask.to_bitmask());
 
@@ -500,11 +504,10 @@ where
 pub fn shuffling_avx512_branch<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
 where
     V: Visitor<T> + SimdVisitor16,
-    T: Ord + Copy,
+    T: IntElement<Lane = i32>,
 {
-    assert!(std::mem::size_of::<T>() == std::mem::size_of::<i32>());
-    let ptr_a = set_a.as_ptr() as *const i32;
-    let ptr_b = set_b.as_ptr() as *const i32;
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
 
     const W: usize = 16;
 
@@ -536,6 +539,7 @@ where
                  v_a.simd_eq(v_b.rotate_elements_left::<15>()),
             ];
             let mask = or_16(masks);
+            count_comparisons((W * W) as u64);
 
             visitor.visit_vector16(v_a, mask.to_bitmask());
 
@@ -577,7 +581,7 @@ where
 
 // BSR implementations //
 
-#[cfg(target_feature = "ssse3")]
+#[cfg(all(feature = "bsr", target_feature = "ssse3"))]
 pub fn shuffling_sse_bsr_branch<'a, V>(set_a: BsrRef<'a>, set_b: BsrRef<'a>, visitor: &mut V)
 where
     V: SimdBsrVisitor4,
@@ -654,7 +658,7 @@ where
         visitor)
 }
 
-#[cfg(target_feature = "avx2")]
+#[cfg(all(feature = "bsr", target_feature = "avx2"))]
 pub fn shuffling_avx2_bsr_branch<'a, V>(set_a: BsrRef<'a>, set_b: BsrRef<'a>, visitor: &mut V)
 where
     V: SimdBsrVisitor8,
@@ -739,7 +743,7 @@ where
         visitor)
 }
 
-#[cfg(target_feature = "avx512f")]
+#[cfg(all(feature = "bsr", target_feature = "avx512f"))]
 pub fn shuffling_avx512_bsr_branch<'a, V>(
     set_a: BsrRef<'a>,
     set_b: BsrRef<'a>,
@@ -842,3 +846,240 @@ where
         unsafe { set_b.advanced_by_unchecked(i_b) },
         visitor)
 }
+
+
+// Aligned versions
+// A scalar merge consumes elements until either set is at an address aligned
+// to the vector size, which takes less than two vectors of steps. The main
+// loop keeps that set aligned by advancing whole vectors and loads it aligned,
+// and loads the other set unaligned.
+
+#[cfg(target_feature = "ssse3")]
+pub fn shuffling_sse_aligned<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor4,
+    T: IntElement<Lane = i32>,
+{
+    const W: usize = 4;
+
+    let (i_a, i_b) = merge_until_aligned(set_a, set_b, W, visitor);
+    if is_aligned(set_a, i_a, W) {
+        shuffling_sse_aligned_from(set_a, set_b, i_a, i_b, visitor)
+    }
+    else {
+        shuffling_sse_aligned_from(set_b, set_a, i_b, i_a, visitor)
+    }
+}
+
+// Main loop of `shuffling_sse_aligned` from `i_a` and `i_b`, with `set_a` aligned at `i_a`.
+#[cfg(target_feature = "ssse3")]
+#[inline]
+fn shuffling_sse_aligned_from<T, V>(set_a: &[T], set_b: &[T], mut i_a: usize, mut i_b: usize, visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor4,
+    T: IntElement<Lane = i32>,
+{
+    const W: usize = 4;
+
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
+
+    let st_a = i_a + ((set_a.len() - i_a) / W) * W;
+    let st_b = i_b + ((set_b.len() - i_b) / W) * W;
+
+    while i_a < st_a && i_b < st_b {
+        let v_a: i32x4 = unsafe{ load_aligned_unsafe(ptr_a.add(i_a)) };
+        let v_b: i32x4 = unsafe{ load_unsafe(ptr_b.add(i_b)) };
+
+        let masks = [
+            v_a.simd_eq(v_b),
+            v_a.simd_eq(v_b.rotate_elements_left::<1>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<2>()),
+            v_a.simd_eq(v_b.rotate_elements_left::<3>()),
+        ];
+        let mask = or_4(masks);
+        count_comparisons((W * W) as u64);
+
+        visitor.visit_vector4(v_a, mask.to_bitmask());
+
+        let a_max = unsafe { *set_a.get_unchecked(i_a + W - 1) };
+        let b_max = unsafe { *set_b.get_unchecked(i_b + W - 1) };
+
+        i_a += W * (a_max <= b_max) as usize;
+        i_b += W * (b_max <= a_max) as usize;
+    }
+    intersect::branchless_merge(
+        unsafe { set_a.get_unchecked(i_a..) },
+        unsafe { set_b.get_unchecked(i_b..) },
+        visitor)
+}
+
+#[cfg(target_feature = "avx2")]
+pub fn shuffling_avx2_aligned<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor8,
+    T: IntElement<Lane = i32>,
+{
+    const W: usize = 8;
+
+    let (i_a, i_b) = merge_until_aligned(set_a, set_b, W, visitor);
+    if is_aligned(set_a, i_a, W) {
+        shuffling_avx2_aligned_from(set_a, set_b, i_a, i_b, visitor)
+    }
+    else {
+        shuffling_avx2_aligned_from(set_b, set_a, i_b, i_a, visitor)
+    }
+}
+
+// Main loop of `shuffling_avx2_aligned` from `i_a` and `i_b`, with `set_a` aligned at `i_a`.
+#[cfg(target_feature = "avx2")]
+#[inline]
+fn shuffling_avx2_aligned_from<T, V>(set_a: &[T], set_b: &[T], mut i_a: usize, mut i_b: usize, visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor8,
+    T: IntElement<Lane = i32>,
+{
+    const W: usize = 8;
+
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
+
+    let st_a = i_a + ((set_a.len() - i_a) / W) * W;
+    let st_b = i_b + ((set_b.len() - i_b) / W) * W;
+
+    while i_a < st_a && i_b < st_b {
+        let v_a: i32x8 = unsafe{ load_aligned_unsafe(ptr_a.add(i_a)) };
+        let v_b: i32x8 = unsafe{ load_unsafe(ptr_b.add(i_b)) };
+        let masks = [
+                v_a.simd_eq(v_b),
+                v_a.simd_eq(v_b.rotate_elements_left::<1>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<2>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<3>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<4>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<5>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<6>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<7>()),
+        ];
+        let mask = or_8(masks);
+        count_comparisons((W * W) as u64);
+
+        visitor.visit_vector8(v_a, mask.to_bitmask());
+
+        let a_max = unsafe { *set_a.get_unchecked(i_a + W - 1) };
+        let b_max = unsafe { *set_b.get_unchecked(i_b + W - 1) };
+
+        i_a += W * (a_max <= b_max) as usize;
+        i_b += W * (b_max <= a_max) as usize;
+    }
+    intersect::branchless_merge(
+        unsafe { set_a.get_unchecked(i_a..) },
+        unsafe { set_b.get_unchecked(i_b..) },
+        visitor)
+}
+
+#[cfg(target_feature = "avx512f")]
+pub fn shuffling_avx512_aligned<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor16,
+    T: IntElement<Lane = i32>,
+{
+    const W: usize = 16;
+
+    let (i_a, i_b) = merge_until_aligned(set_a, set_b, W, visitor);
+    if is_aligned(set_a, i_a, W) {
+        shuffling_avx512_aligned_from(set_a, set_b, i_a, i_b, visitor)
+    }
+    else {
+        shuffling_avx512_aligned_from(set_b, set_a, i_b, i_a, visitor)
+    }
+}
+
+// Main loop of `shuffling_avx512_aligned` from `i_a` and `i_b`, with `set_a` aligned at `i_a`.
+#[cfg(target_feature = "avx512f")]
+#[inline]
+fn shuffling_avx512_aligned_from<T, V>(set_a: &[T], set_b: &[T], mut i_a: usize, mut i_b: usize, visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor16,
+    T: IntElement<Lane = i32>,
+{
+    const W: usize = 16;
+
+    let ptr_a = T::as_lanes(set_a).as_ptr();
+    let ptr_b = T::as_lanes(set_b).as_ptr();
+
+    let st_a = i_a + ((set_a.len() - i_a) / W) * W;
+    let st_b = i_b + ((set_b.len() - i_b) / W) * W;
+
+    while i_a < st_a && i_b < st_b {
+        let v_a: i32x16 = unsafe{ load_aligned_unsafe(ptr_a.add(i_a)) };
+        let v_b: i32x16 = unsafe{ load_unsafe(ptr_b.add(i_b)) };
+
+        let masks = [
+                v_a.simd_eq(v_b),
+                v_a.simd_eq(v_b.rotate_elements_left::<1>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<2>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<3>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<4>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<5>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<6>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<7>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<8>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<9>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<10>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<11>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<12>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<13>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<14>()),
+                v_a.simd_eq(v_b.rotate_elements_left::<15>()),
+        ];
+        let mask = or_16(masks);
+        count_comparisons((W * W) as u64);
+
+        visitor.visit_vector16(v_a, mask.to_bitmask());
+
+        let a_max = unsafe { *set_a.get_unchecked(i_a + W - 1) };
+        let b_max = unsafe { *set_b.get_unchecked(i_b + W - 1) };
+
+        i_a += W * (a_max <= b_max) as usize;
+        i_b += W * (b_max <= a_max) as usize;
+    }
+    intersect::branchless_merge(
+        unsafe { set_a.get_unchecked(i_a..) },
+        unsafe { set_b.get_unchecked(i_b..) },
+        visitor)
+}
+
+/// Merges `set_a` and `set_b` until either is at an address aligned to vectors
+/// of `lanes` elements, or one is exhausted. Returns the indices reached.
+#[inline]
+fn merge_until_aligned<T, V>(set_a: &[T], set_b: &[T], lanes: usize, visitor: &mut V) -> (usize, usize)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    let mut idx_a = 0;
+    let mut idx_b = 0;
+
+    while idx_a < set_a.len() && idx_b < set_b.len() &&
+        !is_aligned(set_a, idx_a, lanes) && !is_aligned(set_b, idx_b, lanes)
+    {
+        let value_a = set_a[idx_a];
+        let value_b = set_b[idx_b];
+        count_comparisons(1);
+
+        if value_a == value_b {
+            visitor.visit(value_a);
+            idx_a += 1;
+            idx_b += 1;
+        } else {
+            idx_a += (value_a < value_b) as usize;
+            idx_b += (value_b < value_a) as usize;
+        }
+    }
+    (idx_a, idx_b)
+}
+
+#[inline]
+fn is_aligned<T>(set: &[T], i: usize, lanes: usize) -> bool {
+    (set.as_ptr().wrapping_add(i) as usize).is_multiple_of(lanes * std::mem::size_of::<T>())
+}
@@ -1,4 +1,4 @@
-#![cfg(feature = "simd")]
+#![cfg(all(feature = "simd", feature = "galloping"))]
 /// SIMD Galloping algorithm by D. Lemire et al.
 ///
 /// Extends the classical galloping algorithm by performing comparisons of
@@ -11,7 +11,9 @@
 use std::simd::*;
 use std::simd::cmp::*;
 
-use crate::{visitor::{Visitor, BsrVisitor}, intersect, instructions::load_unsafe, bsr::BsrRef};
+use crate::{visitor::Visitor, intersect, instructions::load_unsafe};
+#[cfg(feature = "bsr")]
+use crate::{visitor::BsrVisitor, bsr::BsrRef};
 
 const NUM_LANES_IN_BOUND: usize = 32;
 
@@ -103,6 +105,7 @@ where
     intersect::branchless_merge(small, large, visitor)
 }
 
+#[cfg(feature = "bsr")]
 pub fn galloping_sse_bsr<'a, V>(
     small: BsrRef<'a>,
     large: BsrRef<'a>,
@@ -113,6 +116,7 @@ where
     simd_galloping_bsr_impl::<V, 4, u8>(small, large, visitor)
 }
 
+#[cfg(feature = "bsr")]
 pub fn galloping_avx2_bsr<'a, V>(
     small: BsrRef<'a>,
     large: BsrRef<'a>,
@@ -123,6 +127,7 @@ where
     simd_galloping_bsr_impl::<V, 8, u8>(small, large, visitor)
 }
 
+#[cfg(feature = "bsr")]
 pub fn galloping_avx512_bsr<'a, V>(
     small: BsrRef<'a>,
     large: BsrRef<'a>,
@@ -133,6 +138,7 @@ where
     simd_galloping_bsr_impl::<V, 16, u16>(small, large, visitor)
 }
 
+#[cfg(feature = "bsr")]
 pub fn simd_galloping_bsr_impl<'a, V, const LANES: usize, B>(
     mut small: BsrRef<'a>,
     mut large: BsrRef<'a>,
@@ -0,0 +1,196 @@
+#![cfg(feature = "simd")]
+
+//! Merge-based SIMD intersection. Both sets stream through a bitonic merge
+//! network one vector at a time, in the style of vectorised merge sort: the
+//! next vector is loaded from the set with the smaller next element and
+//! merged with the larger half of the previous merge, so the smaller half is
+//! final. As each set is strictly increasing, an element of the intersection
+//! appears as two equal neighbours in the merged order, which are found by
+//! comparing the final half with itself shifted by one lane.
+//!
+//! The merge network follows Chhugani et al. 2008. Efficient Implementation of
+//! Sorting on Multi-Core SIMD CPU Architecture. Proc. VLDB Endow. 1, 2.
+
+use std::simd::{*, cmp::*};
+
+use crate::{
+    element::{IntElement, to_ordered_lanes},
+    intersect,
+    visitor::{Visitor, SimdVisitor4},
+};
+#[cfg(target_feature = "avx2")]
+use crate::visitor::SimdVisitor8;
+
+type Merge<const W: usize> = fn(Simd<i32, W>, Simd<i32, W>) -> (Simd<i32, W>, Simd<i32, W>);
+/// Intersects what remains of both sets once either has less than a vector.
+type Tail<T, V> = fn(&[T], &[T], &mut V);
+
+#[cfg(target_feature = "ssse3")]
+pub fn simd_merge_sse<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor4,
+    T: IntElement<Lane = i32>,
+{
+    simd_merge::<4, T, V>(set_a, set_b, visitor,
+        merge4, |v, value, mask| v.visit_vector4(value, mask), intersect::branchless_merge)
+}
+
+/// `simd_merge_sse` with the rest of the sets intersected by
+/// `shuffling_sse_tail` rather than a scalar merge.
+#[cfg(target_feature = "ssse3")]
+pub fn simd_merge_sse_tail<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor4,
+    T: IntElement<Lane = i32>,
+{
+    simd_merge::<4, T, V>(set_a, set_b, visitor,
+        merge4, |v, value, mask| v.visit_vector4(value, mask), intersect::shuffling_sse_tail)
+}
+
+#[cfg(target_feature = "avx2")]
+pub fn simd_merge_avx2<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor8,
+    T: IntElement<Lane = i32>,
+{
+    simd_merge::<8, T, V>(set_a, set_b, visitor,
+        merge8, |v, value, mask| v.visit_vector8(value, mask), intersect::branchless_merge)
+}
+
+/// `simd_merge_avx2` with the rest of the sets intersected by
+/// `shuffling_avx2_tail` rather than a scalar merge.
+#[cfg(target_feature = "avx2")]
+pub fn simd_merge_avx2_tail<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor8,
+    T: IntElement<Lane = i32>,
+{
+    simd_merge::<8, T, V>(set_a, set_b, visitor,
+        merge8, |v, value, mask| v.visit_vector8(value, mask), intersect::shuffling_avx2_tail)
+}
+
+#[inline]
+fn simd_merge<const W: usize, T, V>(
+    set_a: &[T],
+    set_b: &[T],
+    visitor: &mut V,
+    merge: Merge<W>,
+    visit: fn(&mut V, Simd<i32, W>, u64),
+    tail: Tail<T, V>)
+where
+    LaneCount<W>: SupportedLaneCount,
+    T: IntElement<Lane = i32>,
+    V: Visitor<T>,
+{
+    if set_a.len() < W || set_b.len() < W {
+        return tail(set_a, set_b, visitor);
+    }
+
+    let lanes_a = T::as_lanes(set_a);
+    let lanes_b = T::as_lanes(set_b);
+    // Lanes are ordered as `T`, so the merge network sorts unsigned elements.
+    let load = |lanes: &[i32], i: usize| to_ordered_lanes::<T, W>(Simd::from_slice(&lanes[i..i + W]));
+
+    let (mut low, mut high) = merge(load(lanes_a, 0), load(lanes_b, 0));
+    let mut i_a = W;
+    let mut i_b = W;
+    loop {
+        let mut next = low.rotate_elements_left::<1>();
+        next[W - 1] = high[0];
+        visit(visitor, to_ordered_lanes::<T, W>(low), low.simd_eq(next).to_bitmask());
+
+        if i_a == set_a.len() || i_b == set_b.len() {
+            break;
+        }
+        let vector = if set_a[i_a] <= set_b[i_b] {
+            if i_a + W > set_a.len() {
+                break;
+            }
+            i_a += W;
+            load(lanes_a, i_a - W)
+        }
+        else {
+            if i_b + W > set_b.len() {
+                break;
+            }
+            i_b += W;
+            load(lanes_b, i_b - W)
+        };
+        (low, high) = merge(vector, high);
+    }
+
+    let carry = high.to_array().map(T::from_ordered_lane);
+    merge_tail(&carry, &set_a[i_a..], &set_b[i_b..], visitor, tail)
+}
+
+/// Intersects the rest of both sets, where `carry` holds the undecided larger
+/// half of the last merge, taken from either set.
+fn merge_tail<T, V>(carry: &[T], rest_a: &[T], rest_b: &[T], visitor: &mut V, tail: Tail<T, V>)
+where
+    T: IntElement,
+    V: Visitor<T>,
+{
+    let (mut i, mut i_a, mut i_b) = (0, 0, 0);
+    while i < carry.len() {
+        let value = [rest_a.get(i_a), rest_b.get(i_b)].into_iter()
+            .flatten()
+            .fold(carry[i], |min, &value| min.min(value));
+
+        let mut count = 0;
+        while i < carry.len() && carry[i] == value {
+            i += 1;
+            count += 1;
+        }
+        if rest_a.get(i_a) == Some(&value) {
+            i_a += 1;
+            count += 1;
+        }
+        if rest_b.get(i_b) == Some(&value) {
+            i_b += 1;
+            count += 1;
+        }
+        if count == 2 {
+            visitor.visit(value);
+        }
+    }
+    tail(&rest_a[i_a..], &rest_b[i_b..], visitor)
+}
+
+/// Merges two sorted vectors into the smaller and larger halves, both sorted.
+/// Reversing `b` makes the concatenation bitonic, so one min/max splits it
+/// into two bitonic halves which are then sorted by half-cleaners.
+#[inline]
+fn merge4(a: i32x4, b: i32x4) -> (i32x4, i32x4) {
+    let b = b.reverse();
+    (bitonic_sort4(a.simd_min(b)), bitonic_sort4(a.simd_max(b)))
+}
+
+#[cfg(target_feature = "avx2")]
+#[inline]
+fn merge8(a: i32x8, b: i32x8) -> (i32x8, i32x8) {
+    let b = b.reverse();
+    (bitonic_sort8(a.simd_min(b)), bitonic_sort8(a.simd_max(b)))
+}
+
+// Compares each lane with the lane `d` away, keeping the smaller in the lower
+// lane of each pair.
+macro_rules! half_clean {
+    ($v:expr, $partner:expr, $select:expr) => {{
+        let partner = simd_swizzle!($v, $partner);
+        simd_swizzle!($v.simd_min(partner), $v.simd_max(partner), $select)
+    }};
+}
+
+#[inline]
+fn bitonic_sort4(v: i32x4) -> i32x4 {
+    let v = half_clean!(v, [2, 3, 0, 1], [0, 1, 6, 7]);
+    half_clean!(v, [1, 0, 3, 2], [0, 5, 2, 7])
+}
+
+#[cfg(target_feature = "avx2")]
+#[inline]
+fn bitonic_sort8(v: i32x8) -> i32x8 {
+    let v = half_clean!(v, [4, 5, 6, 7, 0, 1, 2, 3], [0, 1, 2, 3, 12, 13, 14, 15]);
+    let v = half_clean!(v, [2, 3, 0, 1, 6, 7, 4, 5], [0, 1, 10, 11, 4, 5, 14, 15]);
+    half_clean!(v, [1, 0, 3, 2, 5, 4, 7, 6], [0, 9, 2, 11, 4, 13, 6, 15])
+}
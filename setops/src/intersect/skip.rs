@@ -0,0 +1,129 @@
+//! Two-level galloping for huge sets. A `SkipSet` keeps every 256th element
+//! of a sorted set in a top-level array of skip pointers, 1/256 the size of
+//! the set. Each probe first gallops over the skips to locate the block
+//! holding its target, touching the small top level rather than the huge
+//! set, then gallops within that one block from where the last probe of the
+//! block stopped. Unlike `galloping_cached`, which binary searches a whole
+//! block per probe, consecutive targets in a block continue from each other.
+
+use crate::{
+    intersect::{gallop, TwoSetAlgorithm},
+    visitor::Visitor,
+};
+
+/// Default number of elements per block, and so between skips.
+pub const DEFAULT_SKIP_INTERVAL: usize = 256;
+
+/// Skip pointers over a sorted set: `skips[i]` is element `i * interval`.
+pub struct SkipSet<'a, T> {
+    elements: &'a [T],
+    skips: Vec<T>,
+    interval: usize,
+}
+
+impl<'a, T: Copy> SkipSet<'a, T> {
+    pub fn from_sorted(set: &'a [T]) -> Self {
+        Self::with_interval(set, DEFAULT_SKIP_INTERVAL)
+    }
+
+    /// `interval` must be at least 2.
+    pub fn with_interval(set: &'a [T], interval: usize) -> Self {
+        assert!(interval >= 2, "skip interval {interval} is below 2");
+        let skips = set.iter().step_by(interval).copied().collect();
+        Self { elements: set, skips, interval }
+    }
+
+    pub fn elements(&self) -> &'a [T] {
+        self.elements
+    }
+
+    pub fn skips(&self) -> &[T] {
+        &self.skips
+    }
+
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+}
+
+/// Gallops each element of `small` through the skips of `large`, then
+/// within the block of `large` they locate.
+pub fn galloping_skip<T, V>(small: &[T], large: &SkipSet<'_, T>, visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    let (elements, skips) = (large.elements, large.skips.as_slice());
+    let interval = large.interval;
+
+    let mut block = 0;
+    // Position in `elements` of the last probe, within `block`.
+    let mut pos = 0;
+    for &target in small {
+        let skip = block + gallop(&skips[block..], target);
+
+        if skip < skips.len() && skips[skip] == target {
+            visitor.visit(target);
+            block = skip;
+            pos = skip * interval;
+            continue;
+        }
+        if skip == 0 {
+            continue;
+        }
+        // skips[skip - 1] < target < skips[skip], so only the rest of
+        // block skip - 1 can hold the target.
+        if skip - 1 != block {
+            block = skip - 1;
+            pos = block * interval + 1;
+        }
+        let end = ((block + 1) * interval).min(elements.len());
+
+        pos += gallop(&elements[pos..end], target);
+        if pos < end && elements[pos] == target {
+            visitor.visit(target);
+        }
+    }
+}
+
+/// `galloping_skip` as a 2-set algorithm, probing the larger set's skips
+/// with the smaller set.
+pub struct SkipGalloping {
+    pub interval: usize,
+}
+
+impl Default for SkipGalloping {
+    fn default() -> Self {
+        Self { interval: DEFAULT_SKIP_INTERVAL }
+    }
+}
+
+impl<T, V> TwoSetAlgorithm<T, V> for SkipGalloping
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    type Prepared<'a> = SkipSet<'a, T> where T: 'a;
+
+    fn prepare<'a>(&self, set: &'a [T]) -> SkipSet<'a, T> {
+        SkipSet::with_interval(set, self.interval)
+    }
+
+    fn intersect(&self, set_a: &SkipSet<T>, set_b: &SkipSet<T>, visitor: &mut V) {
+        let (small, large) = if set_a.len() <= set_b.len() {
+            (set_a, set_b)
+        }
+        else {
+            (set_b, set_a)
+        };
+        galloping_skip(small.elements, large, visitor)
+    }
+}
@@ -1,8 +1,64 @@
 use crate::{
-    intersect, 
-    visitor::{Visitor, VecWriter, SliceWriter, Clearable},
+    intersect::{self, Intersect2},
+    visitor::{Visitor, VecWriter, SliceWriter, Clearable, Reservable},
+    util::is_strictly_sorted,
+    simple::Element,
 };
 
+/// "Small vs. Small" k-set driver. Orders `sets` by length, intersects the two
+/// smallest with the 2-set algorithm `simple` would choose (galloping if
+/// skewed, otherwise the widest shuffling algorithm enabled), then gallops
+/// through each larger set with the running result, visiting the final
+/// result. Allocates the running result; see `svs_with_buffer` to reuse it.
+pub fn svs<T, S, V>(sets: &[S], visitor: &mut V)
+where
+    T: Element,
+    S: AsRef<[T]>,
+    V: Visitor<T>,
+{
+    svs_with_buffer(sets, &mut Vec::new(), visitor)
+}
+
+/// `svs` holding the running result in `buffer`, which is cleared first and
+/// left with its capacity for the next call.
+pub fn svs_with_buffer<T, S, V>(sets: &[S], buffer: &mut Vec<T>, visitor: &mut V)
+where
+    T: Element,
+    S: AsRef<[T]>,
+    V: Visitor<T>,
+{
+    let mut ordered: Vec<&[T]> = sets.iter().map(|s| s.as_ref()).collect();
+    ordered.sort_by_key(|s| s.len());
+
+    let (first, second, rest) = match ordered.as_slice() {
+        [] => return,
+        [only] => {
+            only.iter().for_each(|&v| visitor.visit(v));
+            return;
+        },
+        [first, second, rest @ ..] => (*first, *second, rest),
+    };
+
+    buffer.clear();
+    let mut writer = VecWriter::from(std::mem::take(buffer));
+    T::intersect_into(first, second, &mut writer);
+    let mut result: Vec<T> = writer.into();
+
+    match rest.split_last() {
+        None => result.iter().for_each(|&v| visitor.visit(v)),
+        Some((last, middle)) => {
+            let mut count = result.len();
+            for set in middle {
+                count = intersect::galloping_inplace(&mut result[..count], set);
+            }
+            intersect::galloping(&result[..count], last, visitor);
+        },
+    }
+
+    result.clear();
+    *buffer = result;
+}
+
 
 /// "Small vs. Small" adaptive set intersection algorithm.
 /// Assumes input sets are ordered from smallest to largest.
@@ -72,6 +128,69 @@ where
     left
 }
 
+/// Output buffers for `svs_double_buffered`, kept between calls so that
+/// intersecting many queries only allocates when a query's smallest set is
+/// larger than any before it.
+pub struct SvsBuffers<V> {
+    left: V,
+    right: V,
+    // Indices of the sets of the current query by length.
+    order: Vec<usize>,
+}
+
+impl<V> SvsBuffers<V> {
+    pub fn new(left: V, right: V) -> Self {
+        Self { left, right, order: Vec::new() }
+    }
+
+    pub fn into_inner(self) -> (V, V) {
+        (self.left, self.right)
+    }
+}
+
+impl<V: Default> Default for SvsBuffers<V> {
+    fn default() -> Self {
+        Self::new(V::default(), V::default())
+    }
+}
+
+/// Extends a 2-set intersection algorithm to k sets like `svs_generic`, but
+/// intersects the sets from smallest to largest so that both buffers only
+/// need room for the smallest set, ping-ponging between the buffers of
+/// `buffers`. Returns the buffer holding the result.
+pub fn svs_double_buffered<'b, T, S, V>(
+    sets: &[S],
+    buffers: &'b mut SvsBuffers<V>,
+    intersect: Intersect2<[T], V>) -> &'b V
+where
+    T: Ord + Copy,
+    S: AsRef<[T]>,
+    V: Visitor<T> + Clearable + Reservable + AsRef<[T]>,
+{
+    assert!(sets.len() >= 2);
+
+    let SvsBuffers { left, right, order } = buffers;
+    order.clear();
+    order.extend(0..sets.len());
+    order.sort_by_key(|&i| sets[i].as_ref().len());
+
+    let smallest = sets[order[0]].as_ref().len();
+    for buffer in [&mut *left, &mut *right] {
+        buffer.clear();
+        buffer.reserve(smallest);
+    }
+
+    let (mut out, mut input) = (left, right);
+    intersect(sets[order[0]].as_ref(), sets[order[1]].as_ref(), out);
+
+    for &i in &order[2..] {
+        std::mem::swap(&mut out, &mut input);
+        out.clear();
+        intersect(input.as_ref(), sets[i].as_ref(), out);
+    }
+    out
+}
+
 pub fn svs_generic_c<'a, T, S>(
     sets: &[S],
     mut left: &'a mut [T],
@@ -107,6 +226,9 @@ where
     T: Ord + Copy + Default,
     S: AsRef<[T]>,
 {
+    debug_assert!(sets.iter().all(|s| is_strictly_sorted(s.as_ref())),
+        "input sets must be sorted and deduplicated");
+
     let mut left: VecWriter<T> = VecWriter::new();
     let mut right: VecWriter<T> = VecWriter::new();
 
@@ -0,0 +1,147 @@
+#![cfg(feature = "simd")]
+
+//! Shuffling with explicit tail kernels. The plain SIMD kernels fall back to
+//! a scalar merge once fewer than a vector of elements remain in either set,
+//! which can dominate for small sets. These keep intersecting in SIMD
+//! registers until both sets are exhausted, loading the last, partial vector
+//! of a set with its missing lanes filled: by an AVX-512 masked load, or by
+//! reloading the last full vector of the set and blending out the lanes
+//! before the tail.
+
+#[cfg(all(target_arch = "x86", target_feature = "avx512f"))]
+use std::arch::x86::*;
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+use std::arch::x86_64::*;
+use std::simd::{*, cmp::*};
+
+use crate::{
+    element::IntElement,
+    instructions::load_unsafe,
+    visitor::{Visitor, SimdVisitor4},
+};
+#[cfg(target_feature = "avx2")]
+use crate::visitor::SimdVisitor8;
+#[cfg(target_feature = "avx512f")]
+use crate::visitor::SimdVisitor16;
+
+/// Loads up to `W` elements of `lanes` from `start`, returning the vector and
+/// the bitmask of the lanes holding them. Other lanes hold `fill`.
+pub(crate) type LoadTail<const W: usize> = fn(lanes: &[i32], start: usize, fill: i32) -> (Simd<i32, W>, u64);
+
+#[cfg(target_feature = "ssse3")]
+pub fn shuffling_sse_tail<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor4,
+    T: IntElement<Lane = i32>,
+{
+    shuffling_tail::<4, T, V>(set_a, set_b, visitor,
+        load_tail_blend, |v, value, mask| v.visit_vector4(value, mask))
+}
+
+#[cfg(target_feature = "avx2")]
+pub fn shuffling_avx2_tail<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor8,
+    T: IntElement<Lane = i32>,
+{
+    shuffling_tail::<8, T, V>(set_a, set_b, visitor,
+        load_tail_blend, |v, value, mask| v.visit_vector8(value, mask))
+}
+
+#[cfg(target_feature = "avx512f")]
+pub fn shuffling_avx512_tail<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor16,
+    T: IntElement<Lane = i32>,
+{
+    shuffling_tail::<16, T, V>(set_a, set_b, visitor,
+        load_tail_masked, |v, value, mask| v.visit_vector16(value, mask))
+}
+
+/// The shuffling algorithm, where the vector of either set may be partial.
+/// Lanes outside a partial vector of `set_b` repeat its first element, so
+/// they only match lanes of `set_a` which that element matches anyway, and
+/// lanes outside a partial vector of `set_a` are masked from the result.
+#[inline]
+fn shuffling_tail<const W: usize, T, V>(
+    set_a: &[T],
+    set_b: &[T],
+    visitor: &mut V,
+    load_tail: LoadTail<W>,
+    visit: fn(&mut V, Simd<i32, W>, u64))
+where
+    LaneCount<W>: SupportedLaneCount,
+    T: IntElement<Lane = i32>,
+    V: Visitor<T>,
+{
+    let lanes_a = T::as_lanes(set_a);
+    let lanes_b = T::as_lanes(set_b);
+
+    let mut i_a: usize = 0;
+    let mut i_b: usize = 0;
+    while i_a < set_a.len() && i_b < set_b.len() {
+        let count_a = (set_a.len() - i_a).min(W);
+        let count_b = (set_b.len() - i_b).min(W);
+
+        let (v_a, valid_a) = if count_a == W {
+            (unsafe { load_unsafe(lanes_a.as_ptr().add(i_a)) }, u64::MAX)
+        }
+        else {
+            load_tail(lanes_a, i_a, 0)
+        };
+        let mut v_b = if count_b == W {
+            unsafe { load_unsafe(lanes_b.as_ptr().add(i_b)) }
+        }
+        else {
+            load_tail(lanes_b, i_b, lanes_b[i_b]).0
+        };
+
+        let mut mask = Mask::<i32, W>::splat(false);
+        for _ in 0..W {
+            mask |= v_a.simd_eq(v_b);
+            v_b = v_b.rotate_elements_left::<1>();
+        }
+        visit(visitor, v_a, mask.to_bitmask() & valid_a);
+
+        let a_max = unsafe { *set_a.get_unchecked(i_a + count_a - 1) };
+        let b_max = unsafe { *set_b.get_unchecked(i_b + count_b - 1) };
+
+        i_a += count_a * (a_max <= b_max) as usize;
+        i_b += count_b * (b_max <= a_max) as usize;
+    }
+}
+
+/// Fewer than `W` elements remain from `start`. If the set holds a full
+/// vector, its last vector holds them in its top lanes and the lanes before
+/// are blended with `fill`. Otherwise the set is copied into a filled buffer.
+#[inline]
+pub(crate) fn load_tail_blend<const W: usize>(lanes: &[i32], start: usize, fill: i32) -> (Simd<i32, W>, u64)
+where
+    LaneCount<W>: SupportedLaneCount,
+{
+    let count = lanes.len() - start;
+    if lanes.len() >= W {
+        let last: Simd<i32, W> = unsafe { load_unsafe(lanes.as_ptr().add(lanes.len() - W)) };
+        let lane = Simd::<i32, W>::from_array(std::array::from_fn(|i| i as i32));
+        let tail = lane.simd_ge(Simd::splat((W - count) as i32));
+        (tail.select(last, Simd::splat(fill)), ((1 << count) - 1) << (W - count))
+    }
+    else {
+        let mut buffer = [fill; W];
+        buffer[..count].copy_from_slice(&lanes[start..]);
+        (Simd::from_array(buffer), (1 << count) - 1)
+    }
+}
+
+/// Masked-out lanes are neither read nor fault, so the load may run past the
+/// end of the set.
+#[cfg(target_feature = "avx512f")]
+#[inline]
+pub(crate) fn load_tail_masked(lanes: &[i32], start: usize, fill: i32) -> (i32x16, u64) {
+    let count = lanes.len() - start;
+    let mask = ((1u32 << count) - 1) as u16;
+    let loaded = unsafe {
+        _mm512_mask_loadu_epi32(_mm512_set1_epi32(fill), mask, lanes.as_ptr().add(start))
+    };
+    (loaded.into(), mask as u64)
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "simd")]
+
+//! Kernels for tiny sets, where both hold at most a few vectors of elements.
+//! Every element of the smaller set is broadcast and compared with every
+//! vector of the larger set, so no comparison depends on another and there is
+//! no merge to set up or finish. The work grows with the product of the
+//! lengths, which for tiny sets is less than the fixed overhead of the
+//! merging kernels. `simple` routes inputs of up to `TINY_MAX_LEN` elements
+//! here when AVX2 is enabled.
+
+use std::simd::{*, cmp::*};
+
+use crate::{
+    element::IntElement,
+    instructions::load_unsafe,
+    instrument::count_comparisons,
+    intersect::tail::{LoadTail, load_tail_blend},
+    visitor::{Visitor, SimdVisitor4},
+};
+#[cfg(target_feature = "avx2")]
+use crate::visitor::SimdVisitor8;
+#[cfg(target_feature = "avx512f")]
+use crate::{intersect::tail::load_tail_masked, visitor::SimdVisitor16};
+
+/// Longest sets `simple` routes to the 8- and 16-lane kernels rather than to
+/// the shuffling kernels of the same width. This is a default rather than a
+/// measured crossover; re-tune it on the target machine with
+/// `kernel-bench --filter small`. `simple` does not use the 4-lane kernel.
+pub const TINY_MAX_LEN: usize = 48;
+
+#[cfg(target_feature = "ssse3")]
+pub fn tiny_sse<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor4,
+    T: IntElement<Lane = i32>,
+{
+    all_pairs::<4, T, V>(set_a, set_b, visitor,
+        load_tail_blend, |v, value, mask| v.visit_vector4(value, mask))
+}
+
+#[cfg(target_feature = "avx2")]
+pub fn tiny_avx2<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor8,
+    T: IntElement<Lane = i32>,
+{
+    all_pairs::<8, T, V>(set_a, set_b, visitor,
+        load_tail_blend, |v, value, mask| v.visit_vector8(value, mask))
+}
+
+#[cfg(target_feature = "avx512f")]
+pub fn tiny_avx512<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    V: Visitor<T> + SimdVisitor16,
+    T: IntElement<Lane = i32>,
+{
+    all_pairs::<16, T, V>(set_a, set_b, visitor,
+        load_tail_masked, |v, value, mask| v.visit_vector16(value, mask))
+}
+
+/// The larger set is loaded in vectors, so there are fewer of them, and
+/// results are visited a vector at a time in ascending order.
+#[inline]
+fn all_pairs<const W: usize, T, V>(
+    set_a: &[T],
+    set_b: &[T],
+    visitor: &mut V,
+    load_tail: LoadTail<W>,
+    visit: fn(&mut V, Simd<i32, W>, u64))
+where
+    LaneCount<W>: SupportedLaneCount,
+    T: IntElement<Lane = i32>,
+    V: Visitor<T>,
+{
+    let (small, large) =
+        if set_a.len() <= set_b.len() { (set_a, set_b) } else { (set_b, set_a) };
+    if small.is_empty() {
+        return;
+    }
+    let lanes_small = T::as_lanes(small);
+    let lanes_large = T::as_lanes(large);
+
+    for start in (0..large.len()).step_by(W) {
+        let (vector, valid) = if start + W <= large.len() {
+            (unsafe { load_unsafe(lanes_large.as_ptr().add(start)) }, u64::MAX)
+        }
+        else {
+            load_tail(lanes_large, start, 0)
+        };
+
+        let mut mask = Mask::<i32, W>::splat(false);
+        for &item in lanes_small {
+            mask |= vector.simd_eq(Simd::splat(item));
+        }
+        count_comparisons((W * small.len()) as u64);
+        visit(visitor, vector, mask.to_bitmask() & valid);
+    }
+}
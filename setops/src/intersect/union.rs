@@ -0,0 +1,67 @@
+//! Intersection of one set with the union of many, `A ∩ (B1 ∪ ... ∪ Bn)`, as
+//! for search queries ANDing a term with an OR of terms. The union is never
+//! materialised: a k-way merge of the lists yields its elements in blocks of
+//! `UNION_BLOCK_LEN`, and each block is intersected with the part of `A` it
+//! spans by any 2-set kernel on sorted arrays, e.g., galloping when `A` is
+//! much larger than the union or a SIMD kernel otherwise.
+
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::{
+    intersect::{Intersect2, gallop},
+    visitor::Visitor,
+};
+
+/// Elements of the union intersected with `A` at a time. Large enough for
+/// SIMD kernels to run at full width, while small enough to stay in L1.
+pub const UNION_BLOCK_LEN: usize = 256;
+
+/// Visits the elements of the sorted set `set_a` found in any of the sorted
+/// `lists`, in ascending order, intersecting blocks of the union with
+/// `intersect`.
+pub fn union_intersect<T, S, V>(
+    set_a: &[T],
+    lists: &[S],
+    intersect: Intersect2<[T], V>,
+    visitor: &mut V)
+where
+    T: Ord + Copy,
+    S: AsRef<[T]>,
+    V: Visitor<T>,
+{
+    // Each list's next element and the list it is from.
+    let mut heap: BinaryHeap<Reverse<(T, usize)>> = lists.iter()
+        .enumerate()
+        .filter_map(|(i, list)| list.as_ref().first().map(|&item| Reverse((item, i))))
+        .collect();
+    let mut positions = vec![0; lists.len()];
+
+    let mut rest = set_a;
+    let mut block: Vec<T> = Vec::with_capacity(UNION_BLOCK_LEN);
+    while !rest.is_empty() && !heap.is_empty() {
+        block.clear();
+        while block.len() < UNION_BLOCK_LEN {
+            let Some(Reverse((item, i))) = heap.pop() else {
+                break;
+            };
+            if block.last() != Some(&item) {
+                block.push(item);
+            }
+
+            positions[i] += 1;
+            if let Some(&next) = lists[i].as_ref().get(positions[i]) {
+                heap.push(Reverse((next, i)));
+            }
+        }
+
+        // Elements of `A` up to the last of the block; the next block starts
+        // above it as duplicates across lists are merged.
+        let last = *block.last().unwrap();
+        let mut end = gallop(rest, last);
+        if rest.get(end) == Some(&last) {
+            end += 1;
+        }
+        intersect(&block, &rest[..end], visitor);
+        rest = &rest[end..];
+    }
+}
@@ -0,0 +1,141 @@
+//! Intersection of versioned sets without materialising their updated
+//! elements. The bases are intersected with any sorted-array algorithm through
+//! a `DeltaAdapter`, which drops results deleted from either set and merges in
+//! the results contributed by inserted elements, so the visitor still sees the
+//! updated intersection in ascending order.
+
+#[cfg(feature = "simd")]
+use std::simd::*;
+
+use crate::{
+    intersect::Intersect2,
+    versioned::VersionedSet,
+    visitor::Visitor,
+};
+#[cfg(feature = "simd")]
+use crate::visitor::{SimdVisitor4, SimdVisitor8, SimdVisitor16, visit_masked};
+
+/// Wraps the visitor of a base intersection, applying the deltas of both
+/// sets. Results must be visited in ascending order, as all sorted-array
+/// algorithms do.
+pub struct DeltaAdapter<T, V> {
+    inner: V,
+    /// Elements deleted from either set, ascending.
+    deletes: Vec<T>,
+    next_delete: usize,
+    /// Ascending results from the inserts, disjoint from the base results.
+    pending: Vec<T>,
+    next_pending: usize,
+}
+
+impl<T, V> DeltaAdapter<T, V>
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    fn new(set_a: &VersionedSet<T>, set_b: &VersionedSet<T>, inner: V) -> Self {
+        let mut deletes: Vec<T> = set_a.deletes().iter()
+            .chain(set_b.deletes())
+            .copied()
+            .collect();
+        deletes.sort_unstable();
+        deletes.dedup();
+
+        // An inserted element is absent from its own base, so these are
+        // disjoint from each other and from the base intersection.
+        let mut pending: Vec<T> = set_a.inserts().iter()
+            .copied()
+            .filter(|&item| set_b.contains(item))
+            .chain(set_b.inserts().iter()
+                .copied()
+                .filter(|&item| set_a.base().binary_search(&item).is_ok() && set_a.contains(item)))
+            .collect();
+        pending.sort_unstable();
+
+        Self { inner, deletes, next_delete: 0, pending, next_pending: 0 }
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+
+    fn flush_below(&mut self, value: T) {
+        while let Some(&item) = self.pending.get(self.next_pending) {
+            if item >= value {
+                break;
+            }
+            self.inner.visit(item);
+            self.next_pending += 1;
+        }
+    }
+
+    fn flush(&mut self) {
+        for &item in &self.pending[self.next_pending..] {
+            self.inner.visit(item);
+        }
+        self.next_pending = self.pending.len();
+    }
+}
+
+impl<T, V> Visitor<T> for DeltaAdapter<T, V>
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    fn visit(&mut self, value: T) {
+        // Results are ascending, so deletes below `value` are never needed again.
+        while self.deletes.get(self.next_delete).is_some_and(|&item| item < value) {
+            self.next_delete += 1;
+        }
+        if self.deletes.get(self.next_delete) == Some(&value) {
+            return;
+        }
+        self.flush_below(value);
+        self.inner.visit(value);
+    }
+}
+
+// Vector visits are split into scalar visits as any lane may be deleted or
+// need inserted results visited before it.
+#[cfg(feature = "simd")]
+impl<V: Visitor<i32>> SimdVisitor4 for DeltaAdapter<i32, V> {
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        visit_masked(self, value.as_array(), mask);
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<V: Visitor<i32>> SimdVisitor8 for DeltaAdapter<i32, V> {
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        visit_masked(self, value.as_array(), mask);
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<V: Visitor<i32>> SimdVisitor16 for DeltaAdapter<i32, V> {
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        visit_masked(self, value.as_array(), mask);
+    }
+}
+
+/// Intersects the updated elements of `set_a` and `set_b`, running
+/// `intersect` on their bases, and returns `visitor`. The inserts of each set
+/// are looked up in the other by binary search, so this is cheapest while the
+/// deltas are small relative to the bases.
+pub fn versioned_intersect<T, V>(
+    set_a: &VersionedSet<T>,
+    set_b: &VersionedSet<T>,
+    intersect: Intersect2<[T], DeltaAdapter<T, V>>,
+    visitor: V) -> V
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    let mut adapter = DeltaAdapter::new(set_a, set_b, visitor);
+    intersect(set_a.base(), set_b.base(), &mut adapter);
+    adapter.flush();
+    adapter.into_inner()
+}
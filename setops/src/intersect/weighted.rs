@@ -0,0 +1,205 @@
+//! Weighted intersection for ranked retrieval. Each input is a sorted key
+//! array with a parallel array of weights, as for `intersect::payload`, and
+//! each matching key is visited with a score combining its two weights. Any
+//! payload kernel finds the matches; with the shuffling kernels the weights of
+//! `B` are gathered and the scores of a whole vector are combined at once.
+
+use std::{marker::PhantomData, simd::*};
+
+use crate::visitor::{PayloadVisitor, ScoreVisitor};
+
+/// A payload kernel, e.g., `payload_merge` or `payload_shuffling_avx2`.
+pub type PayloadJoin<K, A, B, V> = fn(&[K], &[A], &[K], &[B], &mut V);
+
+/// A weight type, combined with wrapping arithmetic for integers.
+pub trait Weight: SimdElement + Default {
+    fn add(self, other: Self) -> Self;
+    fn mul(self, other: Self) -> Self;
+
+    fn add_lanes<const LANES: usize>(a: Simd<Self, LANES>, b: Simd<Self, LANES>) -> Simd<Self, LANES>
+    where
+        LaneCount<LANES>: SupportedLaneCount;
+
+    fn mul_lanes<const LANES: usize>(a: Simd<Self, LANES>, b: Simd<Self, LANES>) -> Simd<Self, LANES>
+    where
+        LaneCount<LANES>: SupportedLaneCount;
+}
+
+macro_rules! int_weight {
+    ($($t:ty),*) => {$(
+        impl Weight for $t {
+            #[inline]
+            fn add(self, other: Self) -> Self {
+                self.wrapping_add(other)
+            }
+
+            #[inline]
+            fn mul(self, other: Self) -> Self {
+                self.wrapping_mul(other)
+            }
+
+            #[inline]
+            fn add_lanes<const LANES: usize>(a: Simd<Self, LANES>, b: Simd<Self, LANES>) -> Simd<Self, LANES>
+            where
+                LaneCount<LANES>: SupportedLaneCount,
+            {
+                a + b
+            }
+
+            #[inline]
+            fn mul_lanes<const LANES: usize>(a: Simd<Self, LANES>, b: Simd<Self, LANES>) -> Simd<Self, LANES>
+            where
+                LaneCount<LANES>: SupportedLaneCount,
+            {
+                a * b
+            }
+        }
+    )*};
+}
+
+macro_rules! float_weight {
+    ($($t:ty),*) => {$(
+        impl Weight for $t {
+            #[inline]
+            fn add(self, other: Self) -> Self {
+                self + other
+            }
+
+            #[inline]
+            fn mul(self, other: Self) -> Self {
+                self * other
+            }
+
+            #[inline]
+            fn add_lanes<const LANES: usize>(a: Simd<Self, LANES>, b: Simd<Self, LANES>) -> Simd<Self, LANES>
+            where
+                LaneCount<LANES>: SupportedLaneCount,
+            {
+                a + b
+            }
+
+            #[inline]
+            fn mul_lanes<const LANES: usize>(a: Simd<Self, LANES>, b: Simd<Self, LANES>) -> Simd<Self, LANES>
+            where
+                LaneCount<LANES>: SupportedLaneCount,
+            {
+                a * b
+            }
+        }
+    )*};
+}
+
+int_weight!(i32, u32, i64, u64);
+float_weight!(f32, f64);
+
+/// How the two weights of a matching key combine into its score.
+pub trait Combine {
+    fn combine<W: Weight>(a: W, b: W) -> W;
+
+    fn combine_lanes<W: Weight, const LANES: usize>(a: Simd<W, LANES>, b: Simd<W, LANES>) -> Simd<W, LANES>
+    where
+        LaneCount<LANES>: SupportedLaneCount;
+}
+
+/// Scores a key by the sum of its weights, e.g., for term frequencies.
+pub struct SumScore;
+
+/// Scores a key by the product of its weights, e.g., for probabilities.
+pub struct ProductScore;
+
+impl Combine for SumScore {
+    #[inline]
+    fn combine<W: Weight>(a: W, b: W) -> W {
+        a.add(b)
+    }
+
+    #[inline]
+    fn combine_lanes<W: Weight, const LANES: usize>(a: Simd<W, LANES>, b: Simd<W, LANES>) -> Simd<W, LANES>
+    where
+        LaneCount<LANES>: SupportedLaneCount,
+    {
+        W::add_lanes(a, b)
+    }
+}
+
+impl Combine for ProductScore {
+    #[inline]
+    fn combine<W: Weight>(a: W, b: W) -> W {
+        a.mul(b)
+    }
+
+    #[inline]
+    fn combine_lanes<W: Weight, const LANES: usize>(a: Simd<W, LANES>, b: Simd<W, LANES>) -> Simd<W, LANES>
+    where
+        LaneCount<LANES>: SupportedLaneCount,
+    {
+        W::mul_lanes(a, b)
+    }
+}
+
+/// Wraps a `ScoreVisitor` as the `PayloadVisitor` of a payload kernel,
+/// combining the weights of each match with `C`.
+pub struct ScoreAdapter<C, V> {
+    inner: V,
+    combine: PhantomData<C>,
+}
+
+impl<C, V> ScoreAdapter<C, V> {
+    pub fn new(inner: V) -> Self {
+        Self { inner, combine: PhantomData }
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+}
+
+impl<C, K, W, V> PayloadVisitor<K, W, W> for ScoreAdapter<C, V>
+where
+    C: Combine,
+    W: Weight,
+    V: ScoreVisitor<K, W>,
+{
+    #[inline]
+    fn visit_payload(&mut self, key: K, payload_a: W, payload_b: W) {
+        self.inner.visit_score(key, C::combine(payload_a, payload_b));
+    }
+
+    #[inline]
+    fn visit_payload_lanes<const LANES: usize>(
+        &mut self,
+        keys: &[K],
+        payload_a: Simd<W, LANES>,
+        payload_b: Simd<W, LANES>,
+        mask: u64)
+    where
+        K: Copy,
+        W: SimdElement,
+        LaneCount<LANES>: SupportedLaneCount,
+    {
+        let scores = C::combine_lanes(payload_a, payload_b);
+        let mut bits = mask;
+        while bits != 0 {
+            let lane = bits.trailing_zeros() as usize;
+            self.inner.visit_score(keys[lane], scores[lane]);
+            bits &= bits - 1;
+        }
+    }
+}
+
+/// Visits each key of both `keys_a` and `keys_b` with the score `C` combines
+/// from its two weights, finding matches with `join`, and returns `visitor`.
+pub fn weighted_intersect<C, K, W, V>(
+    keys_a: &[K], weights_a: &[W],
+    keys_b: &[K], weights_b: &[W],
+    join: PayloadJoin<K, W, W, ScoreAdapter<C, V>>,
+    visitor: V) -> V
+where
+    C: Combine,
+    W: Weight,
+    V: ScoreVisitor<K, W>,
+{
+    let mut adapter = ScoreAdapter::new(visitor);
+    join(keys_a, weights_a, keys_b, weights_b, &mut adapter);
+    adapter.into_inner()
+}
@@ -5,11 +5,55 @@ pub mod intersect;
 pub mod visitor;
 pub mod instructions;
 pub mod bsr;
-mod util;
+pub mod bitmap;
+pub mod ewah;
+pub mod encoding;
+pub mod hybrid;
+pub mod eytzinger;
+pub mod bloom;
+pub mod element;
+pub mod estimate;
+pub mod simple;
+pub mod versioned;
+pub mod util;
+pub mod instrument;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "cpp-ref")]
+pub mod cpp_ref;
 
-pub trait Set<T>
+/// A set representation built from a strictly sorted array.
+pub trait Set<T>: Sized
 where
     T: Clone
 {
     fn from_sorted(sorted: &[T]) -> Self;
+
+    /// Like `from_sorted`, first checking that `sorted` is strictly sorted,
+    /// e.g., for input from outside the application.
+    fn try_from_sorted(sorted: &[T]) -> Result<Self, util::SortError>
+    where
+        T: Ord,
+    {
+        util::check_strictly_sorted(sorted)?;
+        Ok(Self::from_sorted(sorted))
+    }
+
+    /// Sorts `items` and removes duplicates before building the set.
+    fn from_unsorted(items: &[T]) -> Self
+    where
+        T: Ord,
+    {
+        let mut sorted = items.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        Self::from_sorted(&sorted)
+    }
+}
+
+/// A sorted array is its own set representation.
+impl<T: Clone> Set<T> for Vec<T> {
+    fn from_sorted(sorted: &[T]) -> Self {
+        sorted.to_vec()
+    }
 }
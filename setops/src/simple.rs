@@ -0,0 +1,412 @@
+//! A simple interface for set operations on sorted slices, for users who do
+//! not need to choose an algorithm or visitor. An algorithm is picked from the
+//! lengths of the inputs and the SIMD extensions enabled at compile time:
+//! an all-pairs comparison when both 32-bit inputs are tiny and AVX2 is
+//! enabled, galloping for skewed inputs, otherwise the widest shuffling
+//! algorithm available for 32-bit elements (if the `shuffling` feature is
+//! enabled) or a branchless merge otherwise.
+//!
+//! ```
+//! use setops::simple;
+//!
+//! let a = [1, 3, 5, 7, 9];
+//! let b = [3, 4, 5, 6, 7];
+//! assert_eq!(simple::intersect(&a, &b), [3, 5, 7]);
+//! assert_eq!(simple::count(&a, &b), 3);
+//! assert_eq!(simple::union(&a, &b), [1, 3, 4, 5, 6, 7, 9]);
+//! assert!(simple::intersects(&a, &b));
+//! assert!(simple::is_subset(&[3, 7], &a));
+//!
+//! let mut first = Vec::new();
+//! assert_eq!(simple::intersect_first_k(&a, &b, 2, &mut first), 2);
+//! assert_eq!(first, [3, 5]);
+//! ```
+//!
+//! All inputs must be sorted in ascending order without duplicates. This is
+//! checked in debug builds only.
+
+use crate::{
+    element::IntElement,
+    intersect::{self, gallop, Intersect2, limit},
+    util::is_strictly_sorted,
+    visitor::{Visitor, VecWriter, Counter, LimitVisitor},
+};
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+use crate::visitor::{SimdVisitor4, SimdVisitor8, SimdVisitor16};
+
+// Smallest block of the smaller input intersected by `intersect_first_k`
+// between checks for saturation.
+const FIRST_K_MIN_BLOCK: usize = 64;
+
+/// Inputs whose lengths differ by at least this factor are intersected by
+/// galloping through the larger input.
+pub const GALLOPING_SKEW: usize = 32;
+
+/// Returns the elements common to `set_a` and `set_b`.
+pub fn intersect<T: Element>(set_a: &[T], set_b: &[T]) -> Vec<T> {
+    debug_assert_sorted(set_a, set_b);
+
+    let mut writer = VecWriter::with_capacity(set_a.len().min(set_b.len()));
+    T::intersect_into(set_a, set_b, &mut writer);
+    writer.into()
+}
+
+/// Returns the number of elements common to `set_a` and `set_b`.
+pub fn count<T: Element>(set_a: &[T], set_b: &[T]) -> usize {
+    debug_assert_sorted(set_a, set_b);
+
+    let mut counter = Counter::new();
+    T::count_into(set_a, set_b, &mut counter);
+    counter.count()
+}
+
+/// Appends the `k` smallest elements common to `set_a` and `set_b` (or all of
+/// them if there are fewer) to `out`, returning how many were appended.
+/// Intersection stops once `k` results have been found.
+pub fn intersect_first_k<T: Element>(
+    set_a: &[T],
+    set_b: &[T],
+    k: usize,
+    out: &mut Vec<T>) -> usize
+{
+    debug_assert_sorted(set_a, set_b);
+    if k == 0 {
+        return 0;
+    }
+
+    let capacity = k.min(set_a.len()).min(set_b.len());
+    let mut visitor = LimitVisitor::new(VecWriter::with_capacity(capacity), k);
+    T::first_k_into(set_a, set_b, &mut visitor);
+
+    let found: Vec<T> = visitor.into_inner().into();
+    out.extend_from_slice(&found);
+    found.len()
+}
+
+/// Returns whether `set_a` and `set_b` have an element in common, stopping at
+/// the first one found.
+pub fn intersects<T: Element>(set_a: &[T], set_b: &[T]) -> bool {
+    debug_assert_sorted(set_a, set_b);
+    T::intersects(set_a, set_b)
+}
+
+/// Returns whether every element of `set_a` is in `set_b`, stopping at the
+/// first element missing from `set_b`.
+pub fn is_subset<T: Element>(set_a: &[T], set_b: &[T]) -> bool {
+    debug_assert_sorted(set_a, set_b);
+
+    match (set_a.first(), set_a.last(), set_b.first(), set_b.last()) {
+        (None, _, _, _) => true,
+        (Some(&first_a), Some(&last_a), Some(&first_b), Some(&last_b)) =>
+            set_a.len() <= set_b.len() && first_b <= first_a && last_a <= last_b &&
+            T::is_subset(set_a, set_b),
+        _ => false,
+    }
+}
+
+/// Returns the elements in either `set_a` or `set_b`.
+pub fn union<T: Element>(set_a: &[T], set_b: &[T]) -> Vec<T> {
+    debug_assert_sorted(set_a, set_b);
+
+    let mut result = Vec::with_capacity(set_a.len() + set_b.len());
+    let (mut i_a, mut i_b) = (0, 0);
+    while i_a < set_a.len() && i_b < set_b.len() {
+        let (a, b) = (set_a[i_a], set_b[i_b]);
+        result.push(a.min(b));
+        i_a += (a <= b) as usize;
+        i_b += (b <= a) as usize;
+    }
+    result.extend_from_slice(&set_a[i_a..]);
+    result.extend_from_slice(&set_b[i_b..]);
+    result
+}
+
+/// Element types supported by the simple interface: `i32`, `u32`, `i64` and
+/// `u64`.
+pub trait Element: IntElement {
+    #[doc(hidden)]
+    fn intersect_into(set_a: &[Self], set_b: &[Self], writer: &mut VecWriter<Self>);
+    #[doc(hidden)]
+    fn count_into(set_a: &[Self], set_b: &[Self], counter: &mut Counter);
+    #[doc(hidden)]
+    fn intersects(set_a: &[Self], set_b: &[Self]) -> bool;
+    #[doc(hidden)]
+    fn is_subset(set_a: &[Self], set_b: &[Self]) -> bool;
+    #[doc(hidden)]
+    fn first_k_into(set_a: &[Self], set_b: &[Self], visitor: &mut LimitVisitor<VecWriter<Self>>);
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+macro_rules! impl_element_32 {
+    ($t:ty) => {
+        impl Element for $t {
+            fn intersect_into(set_a: &[Self], set_b: &[Self], writer: &mut VecWriter<Self>) {
+                vector_32(set_a, set_b, writer)
+            }
+            fn count_into(set_a: &[Self], set_b: &[Self], counter: &mut Counter) {
+                vector_32(set_a, set_b, counter)
+            }
+            fn intersects(set_a: &[Self], set_b: &[Self]) -> bool {
+                intersects_vector_32(set_a, set_b)
+            }
+            fn is_subset(set_a: &[Self], set_b: &[Self]) -> bool {
+                is_subset_vector_32(set_a, set_b)
+            }
+            fn first_k_into(set_a: &[Self], set_b: &[Self], visitor: &mut LimitVisitor<VecWriter<Self>>) {
+                first_k_vector_32(set_a, set_b, visitor)
+            }
+        }
+    };
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl_element_32!(i32);
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl_element_32!(u32);
+
+macro_rules! impl_element_scalar {
+    ($($t:ty),*) => {$(
+        impl Element for $t {
+            fn intersect_into(set_a: &[Self], set_b: &[Self], writer: &mut VecWriter<Self>) {
+                scalar(set_a, set_b, writer)
+            }
+            fn count_into(set_a: &[Self], set_b: &[Self], counter: &mut Counter) {
+                scalar(set_a, set_b, counter)
+            }
+            fn intersects(set_a: &[Self], set_b: &[Self]) -> bool {
+                intersects_scalar(set_a, set_b)
+            }
+            fn is_subset(set_a: &[Self], set_b: &[Self]) -> bool {
+                is_subset_scalar(set_a, set_b)
+            }
+            fn first_k_into(set_a: &[Self], set_b: &[Self], visitor: &mut LimitVisitor<VecWriter<Self>>) {
+                first_k(set_a, set_b, intersect::branchless_merge, visitor)
+            }
+        }
+    )*};
+}
+
+#[cfg(not(all(feature = "simd", target_feature = "ssse3")))]
+impl_element_scalar!(i32, u32);
+impl_element_scalar!(i64, u64);
+
+fn scalar<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    if let Some((small, large)) = skewed(set_a, set_b) {
+        intersect::galloping(small, large, visitor);
+    }
+    else {
+        intersect::branchless_merge(set_a, set_b, visitor);
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+fn vector_32<T, V>(set_a: &[T], set_b: &[T], visitor: &mut V)
+where
+    T: IntElement<Lane = i32>,
+    V: Visitor<T> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16,
+{
+    #[cfg(target_feature = "avx2")]
+    if set_a.len().max(set_b.len()) <= intersect::tiny::TINY_MAX_LEN {
+        #[cfg(target_feature = "avx512f")]
+        intersect::tiny_avx512(set_a, set_b, visitor);
+        #[cfg(not(target_feature = "avx512f"))]
+        intersect::tiny_avx2(set_a, set_b, visitor);
+        return;
+    }
+
+    if let Some((small, large)) = skewed(set_a, set_b) {
+        intersect::galloping(small, large, visitor);
+        return;
+    }
+
+    #[cfg(all(feature = "shuffling", target_feature = "avx512f"))]
+    intersect::shuffling_avx512(set_a, set_b, visitor);
+    #[cfg(all(feature = "shuffling", target_feature = "avx2", not(target_feature = "avx512f")))]
+    intersect::shuffling_avx2(set_a, set_b, visitor);
+    #[cfg(all(feature = "shuffling", not(target_feature = "avx2")))]
+    intersect::shuffling_sse(set_a, set_b, visitor);
+    #[cfg(not(feature = "shuffling"))]
+    intersect::branchless_merge(set_a, set_b, visitor);
+}
+
+// Gallops with a check for saturation after each result if skewed, otherwise
+// intersects blocks of the smaller set with `intersect` until saturated.
+fn first_k<T, V>(
+    set_a: &[T],
+    set_b: &[T],
+    intersect: Intersect2<[T], LimitVisitor<V>>,
+    visitor: &mut LimitVisitor<V>)
+where
+    T: Ord + Copy,
+    V: Visitor<T>,
+{
+    if let Some((small, large)) = skewed(set_a, set_b) {
+        limit::limit_galloping(small, large, visitor);
+        return;
+    }
+
+    // Blocks a little larger than the number of results wanted, so that a
+    // high selectivity needs few blocks and a low one wastes little work.
+    let block_len = visitor.remaining().saturating_mul(2)
+        .clamp(FIRST_K_MIN_BLOCK, limit::DEFAULT_BLOCK_LEN);
+    limit::limit_2set_blocks(set_a, set_b, intersect, visitor, block_len);
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+fn first_k_vector_32<T, V>(set_a: &[T], set_b: &[T], visitor: &mut LimitVisitor<V>)
+where
+    T: IntElement<Lane = i32>,
+    V: Visitor<T> + SimdVisitor4 + SimdVisitor8 + SimdVisitor16,
+{
+    #[cfg(all(feature = "shuffling", target_feature = "avx512f"))]
+    let intersect: Intersect2<[T], LimitVisitor<V>> = intersect::shuffling_avx512;
+    #[cfg(all(feature = "shuffling", target_feature = "avx2", not(target_feature = "avx512f")))]
+    let intersect: Intersect2<[T], LimitVisitor<V>> = intersect::shuffling_avx2;
+    #[cfg(all(feature = "shuffling", not(target_feature = "avx2")))]
+    let intersect: Intersect2<[T], LimitVisitor<V>> = intersect::shuffling_sse;
+    #[cfg(not(feature = "shuffling"))]
+    let intersect: Intersect2<[T], LimitVisitor<V>> = intersect::branchless_merge;
+
+    first_k(set_a, set_b, intersect, visitor);
+}
+
+fn intersects_scalar<T: Ord + Copy>(set_a: &[T], set_b: &[T]) -> bool {
+    if let Some((small, mut large)) = skewed(set_a, set_b) {
+        for &target in small {
+            let base = gallop(large, target);
+            if base < large.len() && large[base] == target {
+                return true;
+            }
+            large = &large[base..];
+        }
+        return false;
+    }
+
+    let (mut i_a, mut i_b) = (0, 0);
+    while i_a < set_a.len() && i_b < set_b.len() {
+        let (a, b) = (set_a[i_a], set_b[i_b]);
+        if a == b {
+            return true;
+        }
+        i_a += (a < b) as usize;
+        i_b += (b < a) as usize;
+    }
+    false
+}
+
+fn is_subset_scalar<T: Ord + Copy>(set_a: &[T], set_b: &[T]) -> bool {
+    if set_a.len().saturating_mul(GALLOPING_SKEW) <= set_b.len() {
+        let mut large = set_b;
+        for &target in set_a {
+            let base = gallop(large, target);
+            if base == large.len() || large[base] != target {
+                return false;
+            }
+            large = &large[base + 1..];
+        }
+        return true;
+    }
+
+    let mut i_b = 0;
+    for &a in set_a {
+        while i_b < set_b.len() && set_b[i_b] < a {
+            i_b += 1;
+        }
+        if i_b == set_b.len() || set_b[i_b] != a {
+            return false;
+        }
+        i_b += 1;
+    }
+    true
+}
+
+// Vectors of the widest width enabled, scanned for each element of the
+// smaller set (as in the broadcast algorithms).
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+const LANES: usize =
+    if cfg!(target_feature = "avx512f") { 16 }
+    else if cfg!(target_feature = "avx2") { 8 }
+    else { 4 };
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+fn intersects_vector_32<T>(set_a: &[T], set_b: &[T]) -> bool
+where
+    T: IntElement<Lane = i32>,
+{
+    if skewed(set_a, set_b).is_some() {
+        return intersects_scalar(set_a, set_b);
+    }
+    let (small, large) = if set_a.len() <= set_b.len() { (set_a, set_b) } else { (set_b, set_a) };
+
+    let mut i_b = 0;
+    for (i_a, &target) in small.iter().enumerate() {
+        match probe_block(large, &mut i_b, target) {
+            Some(true) => return true,
+            Some(false) => (),
+            None => return intersects_scalar(&small[i_a..], &large[i_b..]),
+        }
+    }
+    false
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+fn is_subset_vector_32<T>(set_a: &[T], set_b: &[T]) -> bool
+where
+    T: IntElement<Lane = i32>,
+{
+    if set_a.len().saturating_mul(GALLOPING_SKEW) <= set_b.len() {
+        return is_subset_scalar(set_a, set_b);
+    }
+
+    let mut i_b = 0;
+    for (i_a, &target) in set_a.iter().enumerate() {
+        match probe_block(set_b, &mut i_b, target) {
+            Some(true) => (),
+            Some(false) => return false,
+            None => return is_subset_scalar(&set_a[i_a..], &set_b[i_b..]),
+        }
+    }
+    true
+}
+
+// Skips whole vectors of `set` before `target` from `i`, then returns whether
+// the vector at `i` contains `target`, or `None` if fewer than LANES elements
+// remain.
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+#[inline]
+fn probe_block<T>(set: &[T], i: &mut usize, target: T) -> Option<bool>
+where
+    T: IntElement<Lane = i32>,
+{
+    use std::simd::{Simd, cmp::SimdPartialEq};
+
+    while *i + LANES <= set.len() && set[*i + LANES - 1] < target {
+        *i += LANES;
+    }
+    if *i + LANES > set.len() {
+        return None;
+    }
+    let block = Simd::<i32, LANES>::from_slice(&T::as_lanes(set)[*i..*i + LANES]);
+    Some(block.simd_eq(target.splat()).any())
+}
+
+// Returns the smaller and larger set if their lengths differ enough for
+// galloping to be faster.
+fn skewed<'a, T>(set_a: &'a [T], set_b: &'a [T]) -> Option<(&'a [T], &'a [T])> {
+    let (small, large) = if set_a.len() <= set_b.len() {
+        (set_a, set_b)
+    }
+    else {
+        (set_b, set_a)
+    };
+    (small.len().saturating_mul(GALLOPING_SKEW) <= large.len()).then_some((small, large))
+}
+
+fn debug_assert_sorted<T: Ord>(set_a: &[T], set_b: &[T]) {
+    debug_assert!(is_strictly_sorted(set_a) && is_strictly_sorted(set_b),
+        "input sets must be sorted and deduplicated");
+}
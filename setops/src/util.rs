@@ -1,13 +1,102 @@
-use std::ops::BitOr;
+use std::{fmt, ops::BitOr};
+#[cfg(feature = "simd")]
+use std::simd::{prelude::*, LaneCount, SupportedLaneCount};
+use crate::element::IntElement;
+#[cfg(feature = "simd")]
+use crate::element::to_ordered_lanes;
 
+/// Returns true if `set` is sorted in ascending order and contains no
+/// duplicates, i.e., is a valid input for the intersection algorithms.
+#[inline]
+pub fn is_strictly_sorted<T: Ord>(set: &[T]) -> bool {
+    set.windows(2).all(|w| w[0] < w[1])
+}
+
+/// Why a set is not strictly sorted, at the first offending pair of elements
+/// `index` and `index + 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortError {
+    /// Element `index` is greater than the next.
+    Unsorted { index: usize },
+    /// Element `index` equals the next.
+    Duplicate { index: usize },
+}
+
+impl fmt::Display for SortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsorted { index } =>
+                write!(f, "set is not sorted: element {index} is greater than element {}",
+                    index + 1),
+            Self::Duplicate { index } =>
+                write!(f, "set has duplicates: element {index} equals element {}", index + 1),
+        }
+    }
+}
+
+impl std::error::Error for SortError {}
+
+/// Like `is_strictly_sorted`, reporting where `set` is not.
+pub fn check_strictly_sorted<T: Ord>(set: &[T]) -> Result<(), SortError> {
+    match find_unsorted(set) {
+        None => Ok(()),
+        Some(index) => Err(sort_error(set, index)),
+    }
+}
+
+/// Like `check_strictly_sorted`, comparing `LANES` pairs of elements at a
+/// time.
+#[cfg(feature = "simd")]
+pub fn check_strictly_sorted_simd<T, const LANES: usize>(set: &[T]) -> Result<(), SortError>
+where
+    T: IntElement<Lane = i32>,
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    match find_unsorted_simd::<T, LANES>(set) {
+        None => Ok(()),
+        Some(index) => Err(sort_error(set, index)),
+    }
+}
+
+// Index of the first element not less than the next.
+fn find_unsorted<T: Ord>(set: &[T]) -> Option<usize> {
+    set.windows(2).position(|w| w[0] >= w[1])
+}
+
+#[cfg(feature = "simd")]
+fn find_unsorted_simd<T, const LANES: usize>(set: &[T]) -> Option<usize>
+where
+    T: IntElement<Lane = i32>,
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    let lanes = T::as_lanes(set);
+    let load = |i: usize| to_ordered_lanes::<T, LANES>(Simd::from_slice(&lanes[i..]));
+
+    let mut i = 0;
+    while i + LANES < lanes.len() {
+        let lo = load(i);
+        let hi = load(i + 1);
+        let unsorted = lo.simd_ge(hi);
+        if unsorted.any() {
+            return Some(i + unsorted.to_bitmask().trailing_zeros() as usize);
+        }
+        i += LANES;
+    }
+    find_unsorted(&set[i..]).map(|offset| i + offset)
+}
+
+fn sort_error<T: Ord>(set: &[T], index: usize) -> SortError {
+    if set[index] == set[index + 1] {
+        SortError::Duplicate { index }
+    }
+    else {
+        SortError::Unsorted { index }
+    }
+}
 
 #[inline]
 pub fn slice_i32_to_u32(slice_i32: &[i32]) -> &[u32] {
-    unsafe {
-        std::slice::from_raw_parts(
-            slice_i32.as_ptr() as *const u32, slice_i32.len()
-        )
-    }
+    u32::from_lanes(slice_i32)
 }
 
 #[inline]
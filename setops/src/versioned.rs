@@ -0,0 +1,139 @@
+//! Versioned sets hold a sorted base snapshot together with sorted deltas of
+//! the elements inserted and deleted since, as for posting lists which are
+//! updated incrementally and only occasionally rebuilt. Intersections (see
+//! `intersect::versioned_intersect`) run on the bases and apply the deltas on
+//! the fly, so the updated sets are never materialised.
+//!
+//! Deltas are kept minimal: inserted elements are never in the base, deleted
+//! elements always are, and no element is both inserted and deleted.
+
+use crate::Set;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionedSet<T> {
+    base: Vec<T>,
+    inserts: Vec<T>,
+    deletes: Vec<T>,
+}
+
+impl<T> VersionedSet<T>
+where
+    T: Ord + Copy,
+{
+    pub fn new(base: Vec<T>) -> Self {
+        debug_assert!(crate::util::is_strictly_sorted(&base));
+        Self { base, inserts: Vec::new(), deletes: Vec::new() }
+    }
+
+    /// Adds `value`, returning whether it was absent.
+    pub fn insert(&mut self, value: T) -> bool {
+        if self.base.binary_search(&value).is_ok() {
+            // Undeleting restores the base element.
+            return remove_sorted(&mut self.deletes, value);
+        }
+        insert_sorted(&mut self.inserts, value)
+    }
+
+    /// Removes `value`, returning whether it was present.
+    pub fn remove(&mut self, value: T) -> bool {
+        if self.base.binary_search(&value).is_ok() {
+            return insert_sorted(&mut self.deletes, value);
+        }
+        remove_sorted(&mut self.inserts, value)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        if self.base.binary_search(&value).is_ok() {
+            self.deletes.binary_search(&value).is_err()
+        }
+        else {
+            self.inserts.binary_search(&value).is_ok()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.base.len() + self.inserts.len() - self.deletes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn base(&self) -> &[T] {
+        &self.base
+    }
+
+    pub fn inserts(&self) -> &[T] {
+        &self.inserts
+    }
+
+    pub fn deletes(&self) -> &[T] {
+        &self.deletes
+    }
+
+    /// Number of elements in the deltas, e.g., to decide when to `compact`.
+    pub fn delta_len(&self) -> usize {
+        self.inserts.len() + self.deletes.len()
+    }
+
+    /// Elements of the updated set, in ascending order.
+    pub fn to_sorted_set(&self) -> Vec<T> {
+        let mut result = Vec::with_capacity(self.len());
+        let mut inserts = self.inserts.iter().peekable();
+        let mut deletes = self.deletes.iter().peekable();
+
+        for &item in &self.base {
+            while let Some(&&insert) = inserts.peek() {
+                if insert > item {
+                    break;
+                }
+                result.push(insert);
+                inserts.next();
+            }
+            if deletes.peek() == Some(&&item) {
+                deletes.next();
+            }
+            else {
+                result.push(item);
+            }
+        }
+        result.extend(inserts);
+        result
+    }
+
+    /// Folds the deltas into a new base snapshot.
+    pub fn compact(&mut self) {
+        self.base = self.to_sorted_set();
+        self.inserts.clear();
+        self.deletes.clear();
+    }
+}
+
+impl<T> Set<T> for VersionedSet<T>
+where
+    T: Ord + Copy,
+{
+    fn from_sorted(sorted: &[T]) -> Self {
+        Self::new(sorted.to_vec())
+    }
+}
+
+fn insert_sorted<T: Ord>(items: &mut Vec<T>, value: T) -> bool {
+    match items.binary_search(&value) {
+        Ok(_) => false,
+        Err(i) => {
+            items.insert(i, value);
+            true
+        },
+    }
+}
+
+fn remove_sorted<T: Ord>(items: &mut Vec<T>, value: T) -> bool {
+    match items.binary_search(&value) {
+        Ok(i) => {
+            items.remove(i);
+            true
+        },
+        Err(_) => false,
+    }
+}
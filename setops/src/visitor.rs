@@ -1,7 +1,7 @@
-use crate::{bsr::{BsrVec, BsrRef}, instructions};
+use crate::{bsr::{BsrVec, BsrRef, BSR_SHIFT}, instructions};
 #[cfg(feature = "simd")]
 use {
-    std::simd::*,
+    std::simd::{*, num::SimdInt},
     crate::util::slice_i32_to_u32
 };
 #[cfg(all(feature = "simd", target_feature = "ssse3"))]
@@ -10,6 +10,11 @@ use crate::instructions::{ VEC_SHUFFLE_MASK4, shuffle_epi8 };
 #[cfg(all(feature = "simd", target_feature = "avx2"))]
 use crate::instructions::{VEC_SHUFFLE_MASK8, permutevar8x32_epi32};
 
+#[cfg(unix)]
+pub mod mmap;
+#[cfg(unix)]
+pub use mmap::MmapWriter;
+
 /// Used to receive set intersection results in a generic way. Inspired by
 /// roaring-rs.
 pub trait Visitor<T> {
@@ -20,6 +25,13 @@ pub trait Clearable {
     fn clear(&mut self);
 }
 
+/// Output buffers which can make room for a number of further results, so a
+/// buffer kept between intersections only grows when a larger result may
+/// follow.
+pub trait Reservable {
+    fn reserve(&mut self, cardinality: usize);
+}
+
 /// Counts intersection size without storing result.
 pub struct Counter {
     count: usize,
@@ -47,6 +59,12 @@ impl Default for Counter {
     }
 }
 
+impl Clearable for Counter {
+    fn clear(&mut self) {
+        self.count = 0;
+    }
+}
+
 /// Stores intersection result in a vector.
 pub struct VecWriter<T> {
     items: Vec<T>,
@@ -64,6 +82,10 @@ impl<T> VecWriter<T> {
             items: Vec::with_capacity(cardinality),
         }
     }
+
+    pub fn capacity(&self) -> usize {
+        self.items.capacity()
+    }
 }
 
 impl<T> AsRef<[T]> for VecWriter<T> {
@@ -78,6 +100,13 @@ impl<T> From<VecWriter<T>> for Vec<T> {
     }
 }
 
+/// Appends to `value`, reusing its allocation.
+impl<T> From<Vec<T>> for VecWriter<T> {
+    fn from(value: Vec<T>) -> Self {
+        Self { items: value }
+    }
+}
+
 impl<T> Default for VecWriter<T> {
     fn default() -> Self {
         Self { items: Vec::default() }
@@ -96,6 +125,12 @@ impl<T> Clearable for VecWriter<T> {
     }
 }
 
+impl<T> Reservable for VecWriter<T> {
+    fn reserve(&mut self, cardinality: usize) {
+        self.items.reserve(cardinality);
+    }
+}
+
 /// Writes intersection result to provided array slice.
 pub struct SliceWriter<'a, T> {
     data: &'a mut[T],
@@ -130,6 +165,431 @@ impl<'a, T> Clearable for SliceWriter<'a, T> {
     }
 }
 
+/// Wraps another visitor, dropping consecutive repeated values. Algorithms
+/// assume deduplicated inputs and will repeat a result item for each
+/// duplicate, so this allows sets with repeated elements (multisets) to be
+/// intersected with set semantics.
+pub struct DedupAdapter<V, T> {
+    inner: V,
+    last: Option<T>,
+}
+
+impl<V, T> DedupAdapter<V, T> {
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            last: None,
+        }
+    }
+
+    pub fn inner(&self) -> &V {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+}
+
+impl<V, T> Visitor<T> for DedupAdapter<V, T>
+where
+    V: Visitor<T>,
+    T: PartialEq + Copy,
+{
+    fn visit(&mut self, value: T) {
+        if self.last != Some(value) {
+            self.last = Some(value);
+            self.inner.visit(value);
+        }
+    }
+}
+
+impl<V, T> Clearable for DedupAdapter<V, T>
+where
+    V: Clearable,
+{
+    fn clear(&mut self) {
+        self.last = None;
+        self.inner.clear();
+    }
+}
+
+impl<V, T> AsRef<[T]> for DedupAdapter<V, T>
+where
+    V: AsRef<[T]>,
+{
+    fn as_ref(&self) -> &[T] {
+        self.inner.as_ref()
+    }
+}
+
+/// Wraps a visitor of plain elements, expanding each BSR (base, state) pair
+/// visited into the elements it represents. This allows BSR intersections to
+/// be used where sorted output is expected. Elements are visited as i32 so
+/// should be reinterpreted as u32, e.g., by using `VecWriter<u32>`.
+pub struct BsrExpandAdapter<V> {
+    inner: V,
+}
+
+impl<V> BsrExpandAdapter<V> {
+    pub fn new(inner: V) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &V {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+}
+
+impl<V> Clearable for BsrExpandAdapter<V>
+where
+    V: Clearable,
+{
+    fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+impl<V, T> AsRef<[T]> for BsrExpandAdapter<V>
+where
+    V: AsRef<[T]>,
+{
+    fn as_ref(&self) -> &[T] {
+        self.inner.as_ref()
+    }
+}
+
+/// Visitors which can signal that they accept no further results, allowing
+/// intersections run through `intersect::limit` to stop early.
+pub trait Saturable {
+    fn is_saturated(&self) -> bool;
+}
+
+/// Wraps another visitor, passing on at most `limit` results and dropping the
+/// rest. Used for `LIMIT k` queries, where it reports saturation once `limit`
+/// results have been visited.
+pub struct LimitVisitor<V> {
+    inner: V,
+    remaining: usize,
+}
+
+impl<V> LimitVisitor<V> {
+    pub fn new(inner: V, limit: usize) -> Self {
+        Self {
+            inner,
+            remaining: limit,
+        }
+    }
+
+    /// Number of results which can still be visited.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    pub fn inner(&self) -> &V {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+}
+
+impl<V, T> Visitor<T> for LimitVisitor<V>
+where
+    V: Visitor<T>,
+{
+    #[inline]
+    fn visit(&mut self, value: T) {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            self.inner.visit(value);
+        }
+    }
+}
+
+impl<V> Saturable for LimitVisitor<V> {
+    #[inline]
+    fn is_saturated(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+impl<V, T> AsRef<[T]> for LimitVisitor<V>
+where
+    V: AsRef<[T]>,
+{
+    fn as_ref(&self) -> &[T] {
+        self.inner.as_ref()
+    }
+}
+
+/// Reduction of intersection results into one value, applied by `FoldVisitor`.
+pub trait Fold {
+    type Acc: Copy;
+    /// Result of folding no elements.
+    const IDENTITY: Self::Acc;
+
+    fn fold(acc: Self::Acc, value: i32) -> Self::Acc;
+
+    /// Folds the lanes of `value` selected by `mask` at once.
+    #[cfg(feature = "simd")]
+    fn fold_lanes<const LANES: usize>(acc: Self::Acc, value: Simd<i32, LANES>, mask: Mask<i32, LANES>)
+        -> Self::Acc
+    where
+        LaneCount<LANES>: SupportedLaneCount;
+}
+
+/// XOR of all results, e.g., a checksum of matching ids.
+pub struct Xor;
+/// Bitwise AND of all results, or -1 (all bits set) if there are none.
+pub struct And;
+/// Sum of all results, widened to i64 so it cannot overflow.
+pub struct Sum;
+/// Smallest result, or `i32::MAX` if there are none.
+pub struct Min;
+/// Largest result, or `i32::MIN` if there are none.
+pub struct Max;
+
+// Unselected lanes are replaced with the identity before reducing the vector.
+macro_rules! fold_i32 {
+    ($($fold:ty => $identity:expr, |$acc:ident, $value:ident| $scalar:expr, $reduce:ident;)*) => {$(
+        impl Fold for $fold {
+            type Acc = i32;
+            const IDENTITY: i32 = $identity;
+
+            #[inline]
+            fn fold($acc: i32, $value: i32) -> i32 {
+                $scalar
+            }
+
+            #[cfg(feature = "simd")]
+            #[inline]
+            fn fold_lanes<const LANES: usize>(acc: i32, value: Simd<i32, LANES>, mask: Mask<i32, LANES>)
+                -> i32
+            where
+                LaneCount<LANES>: SupportedLaneCount
+            {
+                let reduced = mask.select(value, Simd::splat($identity)).$reduce();
+                Self::fold(acc, reduced)
+            }
+        }
+    )*};
+}
+
+fold_i32! {
+    Xor => 0,        |acc, value| acc ^ value,      reduce_xor;
+    And => -1,       |acc, value| acc & value,      reduce_and;
+    Min => i32::MAX, |acc, value| acc.min(value),   reduce_min;
+    Max => i32::MIN, |acc, value| acc.max(value),   reduce_max;
+}
+
+impl Fold for Sum {
+    type Acc = i64;
+    const IDENTITY: i64 = 0;
+
+    #[inline]
+    fn fold(acc: i64, value: i32) -> i64 {
+        acc + value as i64
+    }
+
+    #[cfg(feature = "simd")]
+    #[inline]
+    fn fold_lanes<const LANES: usize>(acc: i64, value: Simd<i32, LANES>, mask: Mask<i32, LANES>)
+        -> i64
+    where
+        LaneCount<LANES>: SupportedLaneCount
+    {
+        acc + mask.select(value, Simd::splat(0)).cast::<i64>().reduce_sum()
+    }
+}
+
+/// Aggregates intersection results with `F` (e.g., `FoldVisitor<Xor>`) without
+/// storing them. Vector visits reduce the selected lanes directly.
+pub struct FoldVisitor<F: Fold> {
+    acc: F::Acc,
+}
+
+impl<F: Fold> FoldVisitor<F> {
+    pub fn new() -> Self {
+        Self { acc: F::IDENTITY }
+    }
+
+    pub fn value(&self) -> F::Acc {
+        self.acc
+    }
+}
+
+impl<F: Fold> Default for FoldVisitor<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Fold> Visitor<i32> for FoldVisitor<F> {
+    #[inline]
+    fn visit(&mut self, value: i32) {
+        self.acc = F::fold(self.acc, value);
+    }
+}
+
+impl<F: Fold> Clearable for FoldVisitor<F> {
+    fn clear(&mut self) {
+        self.acc = F::IDENTITY;
+    }
+}
+
+/// Receives matching keys of a key-value intersection together with the
+/// payloads attached to the key in each input.
+pub trait PayloadVisitor<K, A, B> {
+    fn visit_payload(&mut self, key: K, payload_a: A, payload_b: B);
+
+    /// Visits the lanes selected by `mask` of a block of matching `keys`, as
+    /// found by the SIMD kernels, whose payloads were loaded as vectors.
+    #[cfg(feature = "simd")]
+    #[inline]
+    fn visit_payload_lanes<const LANES: usize>(
+        &mut self,
+        keys: &[K],
+        payload_a: Simd<A, LANES>,
+        payload_b: Simd<B, LANES>,
+        mask: u64)
+    where
+        K: Copy,
+        A: SimdElement,
+        B: SimdElement,
+        LaneCount<LANES>: SupportedLaneCount,
+    {
+        let mut bits = mask;
+        while bits != 0 {
+            let lane = bits.trailing_zeros() as usize;
+            self.visit_payload(keys[lane], payload_a[lane], payload_b[lane]);
+            bits &= bits - 1;
+        }
+    }
+}
+
+impl<K, A, B> PayloadVisitor<K, A, B> for Counter {
+    fn visit_payload(&mut self, _key: K, _payload_a: A, _payload_b: B) {
+        self.count += 1;
+    }
+}
+
+/// Stores `(key, payload_a, payload_b)` triples of a key-value intersection.
+pub struct PayloadWriter<K, A, B> {
+    items: Vec<(K, A, B)>,
+}
+
+impl<K, A, B> PayloadWriter<K, A, B> {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+        }
+    }
+
+    pub fn with_capacity(cardinality: usize) -> Self {
+        Self {
+            items: Vec::with_capacity(cardinality),
+        }
+    }
+}
+
+impl<K, A, B> AsRef<[(K, A, B)]> for PayloadWriter<K, A, B> {
+    fn as_ref(&self) -> &[(K, A, B)] {
+        &self.items
+    }
+}
+
+impl<K, A, B> From<PayloadWriter<K, A, B>> for Vec<(K, A, B)> {
+    fn from(value: PayloadWriter<K, A, B>) -> Self {
+        value.items
+    }
+}
+
+impl<K, A, B> Default for PayloadWriter<K, A, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, A, B> PayloadVisitor<K, A, B> for PayloadWriter<K, A, B> {
+    fn visit_payload(&mut self, key: K, payload_a: A, payload_b: B) {
+        self.items.push((key, payload_a, payload_b));
+    }
+}
+
+impl<K, A, B> Clearable for PayloadWriter<K, A, B> {
+    fn clear(&mut self) {
+        self.items.clear();
+    }
+}
+
+/// Receives matching keys of a weighted intersection with their combined
+/// score (see `intersect::weighted`).
+pub trait ScoreVisitor<K, W> {
+    fn visit_score(&mut self, key: K, score: W);
+}
+
+impl<K, W> ScoreVisitor<K, W> for Counter {
+    fn visit_score(&mut self, _key: K, _score: W) {
+        self.count += 1;
+    }
+}
+
+/// Stores `(key, score)` pairs of a weighted intersection, e.g., to be ranked
+/// by score afterwards.
+pub struct ScoreWriter<K, W> {
+    items: Vec<(K, W)>,
+}
+
+impl<K, W> ScoreWriter<K, W> {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+        }
+    }
+
+    pub fn with_capacity(cardinality: usize) -> Self {
+        Self {
+            items: Vec::with_capacity(cardinality),
+        }
+    }
+}
+
+impl<K, W> AsRef<[(K, W)]> for ScoreWriter<K, W> {
+    fn as_ref(&self) -> &[(K, W)] {
+        &self.items
+    }
+}
+
+impl<K, W> From<ScoreWriter<K, W>> for Vec<(K, W)> {
+    fn from(value: ScoreWriter<K, W>) -> Self {
+        value.items
+    }
+}
+
+impl<K, W> Default for ScoreWriter<K, W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, W> ScoreVisitor<K, W> for ScoreWriter<K, W> {
+    fn visit_score(&mut self, key: K, score: W) {
+        self.items.push((key, score));
+    }
+}
+
+impl<K, W> Clearable for ScoreWriter<K, W> {
+    fn clear(&mut self) {
+        self.items.clear();
+    }
+}
+
 /*-------- SIMD --------*/
 /// Allows visiting of multiple elements
 #[cfg(feature = "simd")]
@@ -229,6 +689,28 @@ impl SimdVisitor16 for VecWriter<i32> {
     }
 }
 
+// Without SSSE3 there is no shuffle to compress the masked lanes, so they are
+// pushed one by one. Only scalar kernels are built then, but `dynamic` still
+// instantiates them for any SIMD visitor.
+#[cfg(all(feature = "simd", not(target_feature = "ssse3")))]
+impl SimdVisitor4 for VecWriter<i32> {
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        visit_masked(self, value.as_array(), mask);
+    }
+}
+#[cfg(all(feature = "simd", not(target_feature = "ssse3")))]
+impl SimdVisitor8 for VecWriter<i32> {
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        visit_masked(self, value.as_array(), mask);
+    }
+}
+#[cfg(all(feature = "simd", not(target_feature = "ssse3")))]
+impl SimdVisitor16 for VecWriter<i32> {
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        visit_masked(self, value.as_array(), mask);
+    }
+}
+
 impl Visitor<i32> for VecWriter<u32> {
     fn visit(&mut self, value: i32) {
         self.items.push(value as u32);
@@ -373,30 +855,143 @@ impl<'a> SimdVisitor16 for SliceWriter<'a, i32> {
     }
 }
 
-/// Allows visiting of single entries in Base and State Representation
-pub trait BsrVisitor {
-    fn visit_bsr(&mut self, base: u32, state: u32);
-}
-
-/// Allows visiting of multiple entries in Base and State Representation
+// DEDUP ADAPTER
+// Vector visits are split into scalar visits as duplicates may occur both
+// within and across vectors.
 #[cfg(feature = "simd")]
-pub trait SimdBsrVisitor4 : BsrVisitor {
-    fn visit_bsr_vector4(&mut self, base: i32x4, state: i32x4, mask: u64);
-}
-pub trait SimdBsrVisitor8 : BsrVisitor {
-    fn visit_bsr_vector8(&mut self, base: i32x8, state: i32x8, mask: u64);
-}
-pub trait SimdBsrVisitor16 : BsrVisitor {
-    fn visit_bsr_vector16(&mut self, base: i32x16, state: i32x16, mask: u64);
+impl<V: Visitor<i32>> SimdVisitor4 for DedupAdapter<V, i32> {
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        visit_masked(self, value.as_array(), mask);
+    }
 }
 
-impl BsrVisitor for BsrVec {
-    fn visit_bsr(&mut self, base: u32, state: u32) {
-        self.append(base, state)
+#[cfg(feature = "simd")]
+impl<V: Visitor<i32>> SimdVisitor8 for DedupAdapter<V, i32> {
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        visit_masked(self, value.as_array(), mask);
     }
 }
 
-impl BsrVisitor for Counter {
+#[cfg(feature = "simd")]
+impl<V: Visitor<i32>> SimdVisitor16 for DedupAdapter<V, i32> {
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        visit_masked(self, value.as_array(), mask);
+    }
+}
+
+// LIMIT VISITOR
+// Masks are trimmed to their lowest set bits so the inner visitor can still
+// write whole vectors.
+#[cfg(feature = "simd")]
+impl<V: SimdVisitor4> SimdVisitor4 for LimitVisitor<V> {
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        let mask = self.take_mask(mask);
+        if mask != 0 {
+            self.inner.visit_vector4(value, mask);
+        }
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<V: SimdVisitor8> SimdVisitor8 for LimitVisitor<V> {
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        let mask = self.take_mask(mask);
+        if mask != 0 {
+            self.inner.visit_vector8(value, mask);
+        }
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<V: SimdVisitor16> SimdVisitor16 for LimitVisitor<V> {
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        let mask = self.take_mask(mask);
+        if mask != 0 {
+            self.inner.visit_vector16(value, mask);
+        }
+    }
+}
+
+// FOLD VISITOR
+#[cfg(feature = "simd")]
+impl<F: Fold> SimdVisitor4 for FoldVisitor<F> {
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        self.acc = F::fold_lanes(self.acc, value, mask32x4::from_bitmask(mask));
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<F: Fold> SimdVisitor8 for FoldVisitor<F> {
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        self.acc = F::fold_lanes(self.acc, value, mask32x8::from_bitmask(mask));
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<F: Fold> SimdVisitor16 for FoldVisitor<F> {
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        self.acc = F::fold_lanes(self.acc, value, mask32x16::from_bitmask(mask));
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<V> LimitVisitor<V> {
+    #[inline]
+    fn take_mask(&mut self, mut mask: u64) -> u64 {
+        let count = mask.count_ones() as usize;
+        if count > self.remaining {
+            for _ in self.remaining..count {
+                mask &= !(1 << (63 - mask.leading_zeros()));
+            }
+        }
+        self.remaining -= mask.count_ones() as usize;
+        mask
+    }
+}
+
+#[cfg(feature = "simd")]
+#[inline]
+pub(crate) fn visit_masked<V: Visitor<i32>>(visitor: &mut V, values: &[i32], mut mask: u64) {
+    while mask != 0 {
+        let i = mask.trailing_zeros() as usize;
+        visitor.visit(values[i]);
+        mask &= mask - 1;
+    }
+}
+
+/// Allows visiting of single entries in Base and State Representation
+pub trait BsrVisitor {
+    fn visit_bsr(&mut self, base: u32, state: u32);
+}
+
+/// Allows visiting of multiple entries in Base and State Representation
+#[cfg(feature = "simd")]
+pub trait SimdBsrVisitor4 : BsrVisitor {
+    fn visit_bsr_vector4(&mut self, base: i32x4, state: i32x4, mask: u64);
+}
+pub trait SimdBsrVisitor8 : BsrVisitor {
+    fn visit_bsr_vector8(&mut self, base: i32x8, state: i32x8, mask: u64);
+}
+pub trait SimdBsrVisitor16 : BsrVisitor {
+    fn visit_bsr_vector16(&mut self, base: i32x16, state: i32x16, mask: u64);
+}
+
+impl BsrVisitor for BsrVec {
+    fn visit_bsr(&mut self, base: u32, state: u32) {
+        self.append(base, state)
+    }
+}
+
+impl BsrVisitor for Counter {
     fn visit_bsr(&mut self, _base: u32, state: u32) {
         self.count += state.count_ones() as usize;
     }
@@ -438,6 +1033,68 @@ impl SimdBsrVisitor4 for Counter {
 }
 
 
+// Adds each bit index of the state to the shifted base and visits the lanes of
+// set bits, using the inner visitor's masked vector writes.
+#[cfg(feature = "simd")]
+impl<V: SimdVisitor16> BsrVisitor for BsrExpandAdapter<V> {
+    #[inline]
+    fn visit_bsr(&mut self, base: u32, state: u32) {
+        const LOW: i32x16 = i32x16::from_array(
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+        const HIGH: i32x16 = i32x16::from_array(
+            [16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31]);
+
+        let high = i32x16::splat((base << BSR_SHIFT) as i32);
+        let (low_mask, high_mask) = (state & 0xffff, state >> 16);
+        if low_mask != 0 {
+            self.inner.visit_vector16(high + LOW, low_mask as u64);
+        }
+        if high_mask != 0 {
+            self.inner.visit_vector16(high + HIGH, high_mask as u64);
+        }
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+impl<V: Visitor<i32>> BsrVisitor for BsrExpandAdapter<V> {
+    fn visit_bsr(&mut self, base: u32, mut state: u32) {
+        while state != 0 {
+            let bit = state.trailing_zeros();
+            self.inner.visit(((base << BSR_SHIFT) | bit) as i32);
+            state &= state - 1;
+        }
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<V: SimdVisitor16> SimdBsrVisitor4 for BsrExpandAdapter<V> {
+    fn visit_bsr_vector4(&mut self, base: i32x4, state: i32x4, mask: u64) {
+        visit_bsr_masked(self, base.as_array(), state.as_array(), mask);
+    }
+}
+#[cfg(feature = "simd")]
+impl<V: SimdVisitor16> SimdBsrVisitor8 for BsrExpandAdapter<V> {
+    fn visit_bsr_vector8(&mut self, base: i32x8, state: i32x8, mask: u64) {
+        visit_bsr_masked(self, base.as_array(), state.as_array(), mask);
+    }
+}
+#[cfg(feature = "simd")]
+impl<V: SimdVisitor16> SimdBsrVisitor16 for BsrExpandAdapter<V> {
+    fn visit_bsr_vector16(&mut self, base: i32x16, state: i32x16, mask: u64) {
+        visit_bsr_masked(self, base.as_array(), state.as_array(), mask);
+    }
+}
+
+#[cfg(feature = "simd")]
+#[inline]
+fn visit_bsr_masked<V: BsrVisitor>(visitor: &mut V, bases: &[i32], states: &[i32], mut mask: u64) {
+    while mask != 0 {
+        let i = mask.trailing_zeros() as usize;
+        visitor.visit_bsr(bases[i] as u32, states[i] as u32);
+        mask &= mask - 1;
+    }
+}
+
 /// Ensures all visits match expected output.
 /// Used for testing algorithm correctness.
 pub struct EnsureVisitor<'a, T>
@@ -776,6 +1433,10 @@ impl<T> UnsafeWriter<T> {
             items: Vec::with_capacity(cardinality + 16),
         }
     }
+
+    pub fn capacity(&self) -> usize {
+        self.items.capacity()
+    }
 }
 
 impl<T> AsRef<[T]> for UnsafeWriter<T> {
@@ -811,6 +1472,13 @@ impl<T> Clearable for UnsafeWriter<T> {
     }
 }
 
+impl<T> Reservable for UnsafeWriter<T> {
+    fn reserve(&mut self, cardinality: usize) {
+        // With the same slack for vector stores as `with_capacity`.
+        self.items.reserve(cardinality + 16);
+    }
+}
+
 #[cfg(all(feature = "simd", target_feature = "ssse3"))]
 impl SimdVisitor4 for UnsafeWriter<i32> {
     #[inline]
@@ -928,16 +1596,11 @@ impl SimdVisitor16 for UnsafeWriter<i32> {
             (mask >> 12 & 0xF) as u8,
         ];
 
-        extend_i32vec_x4(&mut self.items, i32x4::from_slice(&arr[..4]),   masks[0]);
-        extend_i32vec_x4(&mut self.items, i32x4::from_slice(&arr[4..8]),  masks[1]);
-        extend_i32vec_x4(&mut self.items, i32x4::from_slice(&arr[8..12]), masks[2]);
-        extend_i32vec_x4(&mut self.items, i32x4::from_slice(&arr[12..]),  masks[3]);
-
         let shuffled = [
             shuffle_epi8(i32x4::from_slice(&arr[..4]),  VEC_SHUFFLE_MASK4[masks[0] as usize]),
             shuffle_epi8(i32x4::from_slice(&arr[4..8]), VEC_SHUFFLE_MASK4[masks[1] as usize]),
-            shuffle_epi8(i32x4::from_slice(&arr[8..12]), VEC_SHUFFLE_MASK4[masks[1] as usize]),
-            shuffle_epi8(i32x4::from_slice(&arr[12..]), VEC_SHUFFLE_MASK4[masks[1] as usize]),
+            shuffle_epi8(i32x4::from_slice(&arr[8..12]), VEC_SHUFFLE_MASK4[masks[2] as usize]),
+            shuffle_epi8(i32x4::from_slice(&arr[12..]), VEC_SHUFFLE_MASK4[masks[3] as usize]),
         ];
 
         unsafe { unsafe_vec_extend(shuffled[0], masks[0], &mut self.items) };
@@ -947,6 +1610,619 @@ impl SimdVisitor16 for UnsafeWriter<i32> {
     }
 }
 
+/// Safe counterpart of `UnsafeWriter` for use outside benchmarks. Each vector
+/// visit first reserves space for a whole vector (amortised by `Vec`), then
+/// takes the same compress-store or shuffle-store path.
+pub struct ReserveWriter<T>(UnsafeWriter<T>);
+
+impl<T> ReserveWriter<T> {
+    pub fn new() -> Self {
+        Self(UnsafeWriter::new())
+    }
+
+    pub fn with_capacity(cardinality: usize) -> Self {
+        Self(UnsafeWriter::with_capacity(cardinality))
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+}
+
+impl<T> AsRef<[T]> for ReserveWriter<T> {
+    fn as_ref(&self) -> &[T] {
+        self.0.as_ref()
+    }
+}
+
+impl<T> From<ReserveWriter<T>> for Vec<T> {
+    fn from(value: ReserveWriter<T>) -> Self {
+        value.0.into()
+    }
+}
+
+impl<T> Default for ReserveWriter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Visitor<T> for ReserveWriter<T> {
+    fn visit(&mut self, value: T) {
+        self.0.items.push(value);
+    }
+}
+
+impl<T> Clearable for ReserveWriter<T> {
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+impl<T> Reservable for ReserveWriter<T> {
+    fn reserve(&mut self, cardinality: usize) {
+        self.0.reserve(cardinality);
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor4 for ReserveWriter<i32> {
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        self.0.items.reserve(4);
+        self.0.visit_vector4(value, mask);
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor8 for ReserveWriter<i32> {
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        self.0.items.reserve(8);
+        self.0.visit_vector8(value, mask);
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor16 for ReserveWriter<i32> {
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        self.0.items.reserve(16);
+        self.0.visit_vector16(value, mask);
+    }
+}
+
+/// Writer sized from a hint of the result's cardinality, for callers which
+/// know roughly how large a result will be. When the hint reaches a bound the
+/// result cannot exceed (e.g., the size of the smaller input), the bound is
+/// allocated up front and results are stored as by `UnsafeWriter`, with no
+/// growth checks. Otherwise the hint is only reserved and the writer grows as
+/// needed, as `ReserveWriter` does.
+pub struct HintedWriter<T> {
+    inner: UnsafeWriter<T>,
+    bounded: bool,
+}
+
+impl<T> HintedWriter<T> {
+    /// `expected_len` estimates the result's cardinality, which is at most
+    /// `bound`.
+    pub fn new(expected_len: Option<usize>, bound: usize) -> Self {
+        match expected_len {
+            Some(len) if len >= bound => Self {
+                inner: UnsafeWriter::with_capacity(bound),
+                bounded: true,
+            },
+            Some(len) => Self { inner: UnsafeWriter::with_capacity(len), bounded: false },
+            None => Self { inner: UnsafeWriter::new(), bounded: false },
+        }
+    }
+
+    /// Whether the whole result fits without growing.
+    pub fn is_bounded(&self) -> bool {
+        self.bounded
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    #[inline]
+    fn make_room(&mut self, lanes: usize) {
+        if !self.bounded {
+            self.inner.items.reserve(lanes);
+        }
+    }
+}
+
+impl<T> AsRef<[T]> for HintedWriter<T> {
+    fn as_ref(&self) -> &[T] {
+        self.inner.as_ref()
+    }
+}
+
+impl<T> From<HintedWriter<T>> for Vec<T> {
+    fn from(value: HintedWriter<T>) -> Self {
+        value.inner.into()
+    }
+}
+
+impl<T> Default for HintedWriter<T> {
+    fn default() -> Self {
+        Self::new(None, 0)
+    }
+}
+
+impl<T> Visitor<T> for HintedWriter<T> {
+    #[inline]
+    fn visit(&mut self, value: T) {
+        self.make_room(1);
+        self.inner.visit(value);
+    }
+}
+
+// A bounded writer stays bounded, so later results must also fit within the
+// bound, as for a cleared `UnsafeWriter`.
+impl<T> Clearable for HintedWriter<T> {
+    fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+// `Reservable` callers pass a bound on the next result, as `UnsafeWriter`
+// relies on.
+impl<T> Reservable for HintedWriter<T> {
+    fn reserve(&mut self, cardinality: usize) {
+        self.inner.reserve(cardinality);
+        self.bounded = true;
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor4 for HintedWriter<i32> {
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        self.make_room(4);
+        self.inner.visit_vector4(value, mask);
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor8 for HintedWriter<i32> {
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        self.make_room(8);
+        self.inner.visit_vector8(value, mask);
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor16 for HintedWriter<i32> {
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        self.make_room(16);
+        self.inner.visit_vector16(value, mask);
+    }
+}
+
+/// `UnsafeWriter` which always writes vectors through the shuffle lookup
+/// tables, even when built with AVX-512 and its compress stores. Only for
+/// benchmarking, to measure one store path against the other on the same
+/// machine.
+pub struct UnsafeLookupWriter<T>(UnsafeWriter<T>);
+
+impl<T> UnsafeLookupWriter<T> {
+    pub fn new() -> Self {
+        Self(UnsafeWriter::new())
+    }
+
+    pub fn with_capacity(cardinality: usize) -> Self {
+        Self(UnsafeWriter::with_capacity(cardinality))
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+}
+
+impl<T> AsRef<[T]> for UnsafeLookupWriter<T> {
+    fn as_ref(&self) -> &[T] {
+        self.0.as_ref()
+    }
+}
+
+impl<T> From<UnsafeLookupWriter<T>> for Vec<T> {
+    fn from(value: UnsafeLookupWriter<T>) -> Self {
+        value.0.into()
+    }
+}
+
+impl<T> Default for UnsafeLookupWriter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Visitor<T> for UnsafeLookupWriter<T> {
+    fn visit(&mut self, value: T) {
+        self.0.visit(value);
+    }
+}
+
+impl<T> Clearable for UnsafeLookupWriter<T> {
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+impl<T> Reservable for UnsafeLookupWriter<T> {
+    fn reserve(&mut self, cardinality: usize) {
+        self.0.reserve(cardinality);
+    }
+}
+
+// Without AVX-512, `UnsafeWriter` already uses the lookup tables.
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor4 for UnsafeLookupWriter<i32> {
+    #[cfg(not(target_feature = "avx512f"))]
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        self.0.visit_vector4(value, mask);
+    }
+
+    #[cfg(target_feature = "avx512f")]
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        let shuffled = shuffle_epi8(value, VEC_SHUFFLE_MASK4[mask as usize]);
+        unsafe { unsafe_vec_extend(shuffled, mask, &mut self.0.items) };
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor8 for UnsafeLookupWriter<i32> {
+    #[cfg(not(target_feature = "avx512f"))]
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        self.0.visit_vector8(value, mask);
+    }
+
+    #[cfg(target_feature = "avx512f")]
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        let shuffled = permutevar8x32_epi32(value, VEC_SHUFFLE_MASK8[mask as usize]);
+        unsafe { unsafe_vec_extend(shuffled, mask, &mut self.0.items) };
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor16 for UnsafeLookupWriter<i32> {
+    #[cfg(not(target_feature = "avx512f"))]
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        self.0.visit_vector16(value, mask);
+    }
+
+    #[cfg(target_feature = "avx512f")]
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        let arr = value.as_array();
+        let left = mask & 0xFF;
+        let right = (mask >> 8) & 0xFF;
+
+        let shuffled1 = permutevar8x32_epi32(i32x8::from_slice(&arr[..8]), VEC_SHUFFLE_MASK8[left as usize]);
+        let shuffled2 = permutevar8x32_epi32(i32x8::from_slice(&arr[8..]), VEC_SHUFFLE_MASK8[right as usize]);
+
+        unsafe { unsafe_vec_extend(shuffled1, left,  &mut self.0.items) };
+        unsafe { unsafe_vec_extend(shuffled2, right, &mut self.0.items) };
+    }
+}
+
+/// `UnsafeWriter` restricted to AVX-512 compress stores: its vector visits
+/// only exist in builds with `avx512f`. Only for benchmarking, alongside
+/// `UnsafeLookupWriter`.
+pub struct UnsafeCompressWriter<T>(UnsafeWriter<T>);
+
+impl<T> UnsafeCompressWriter<T> {
+    pub fn new() -> Self {
+        Self(UnsafeWriter::new())
+    }
+
+    pub fn with_capacity(cardinality: usize) -> Self {
+        Self(UnsafeWriter::with_capacity(cardinality))
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+}
+
+impl<T> AsRef<[T]> for UnsafeCompressWriter<T> {
+    fn as_ref(&self) -> &[T] {
+        self.0.as_ref()
+    }
+}
+
+impl<T> From<UnsafeCompressWriter<T>> for Vec<T> {
+    fn from(value: UnsafeCompressWriter<T>) -> Self {
+        value.0.into()
+    }
+}
+
+impl<T> Default for UnsafeCompressWriter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Visitor<T> for UnsafeCompressWriter<T> {
+    fn visit(&mut self, value: T) {
+        self.0.visit(value);
+    }
+}
+
+impl<T> Clearable for UnsafeCompressWriter<T> {
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+impl<T> Reservable for UnsafeCompressWriter<T> {
+    fn reserve(&mut self, cardinality: usize) {
+        self.0.reserve(cardinality);
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "avx512f"))]
+impl SimdVisitor4 for UnsafeCompressWriter<i32> {
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        self.0.visit_vector4(value, mask);
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "avx512f"))]
+impl SimdVisitor8 for UnsafeCompressWriter<i32> {
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        self.0.visit_vector8(value, mask);
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "avx512f"))]
+impl SimdVisitor16 for UnsafeCompressWriter<i32> {
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        self.0.visit_vector16(value, mask);
+    }
+}
+
+/// Elements of `i32` per 64-byte cache line.
+const LINE: usize = 16;
+
+#[repr(C, align(64))]
+#[derive(Clone, Copy)]
+struct Line([i32; LINE]);
+
+// Room for a partial line plus a whole vector of results.
+#[repr(C, align(64))]
+struct Staging([i32; 2 * LINE]);
+
+/// Writes results with non-temporal stores, which bypass the cache so that a
+/// large output does not evict the data of subsequent queries. Streaming
+/// stores must write whole aligned vectors, so results are staged until they
+/// fill a cache line, which is then streamed to the output. Staged results
+/// are only copied out by `to_vec`, so the output cannot be read while
+/// intersecting (e.g., by `svs`).
+pub struct StreamingWriter {
+    lines: Vec<Line>,
+    staging: Staging,
+    staged: usize,
+}
+
+impl StreamingWriter {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(cardinality: usize) -> Self {
+        Self {
+            lines: Vec::with_capacity(cardinality.div_ceil(LINE) + 1),
+            staging: Staging([0; 2 * LINE]),
+            staged: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len() * LINE + self.staged
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.lines.capacity() * LINE
+    }
+
+    /// Waits for streamed lines to reach memory and returns every result,
+    /// including those still staged.
+    pub fn to_vec(&self) -> Vec<i32> {
+        store_fence();
+        let streamed = unsafe {
+            std::slice::from_raw_parts(self.lines.as_ptr() as *const i32, self.lines.len() * LINE)
+        };
+        let mut result = Vec::with_capacity(self.len());
+        result.extend_from_slice(streamed);
+        result.extend_from_slice(&self.staging.0[..self.staged]);
+        result
+    }
+
+    /// Streams the first staged line once it is full, moving the rest of the
+    /// staged results to its place.
+    #[inline]
+    fn stream_full_line(&mut self) {
+        if self.staged < LINE {
+            return;
+        }
+        self.lines.reserve(1);
+        unsafe {
+            let dst = self.lines.as_mut_ptr().add(self.lines.len()) as *mut i32;
+            stream_line(dst, self.staging.0.as_ptr());
+            self.lines.set_len(self.lines.len() + 1);
+        }
+        self.staging.0.copy_within(LINE.., 0);
+        self.staged -= LINE;
+    }
+
+    /// Stages a vector whose first `count` lanes are results.
+    #[cfg(feature = "simd")]
+    #[inline]
+    fn stage<const LANES: usize>(&mut self, shuffled: Simd<i32, LANES>, count: usize)
+    where
+        LaneCount<LANES>: SupportedLaneCount,
+    {
+        debug_assert!(self.staged + LANES <= 2 * LINE);
+        unsafe {
+            let dst = self.staging.0.as_mut_ptr().add(self.staged) as *mut Simd<i32, LANES>;
+            dst.write_unaligned(shuffled);
+        }
+        self.staged += count;
+        self.stream_full_line();
+    }
+}
+
+impl Default for StreamingWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<StreamingWriter> for Vec<i32> {
+    fn from(value: StreamingWriter) -> Self {
+        value.to_vec()
+    }
+}
+
+impl Visitor<i32> for StreamingWriter {
+    #[inline]
+    fn visit(&mut self, value: i32) {
+        self.staging.0[self.staged] = value;
+        self.staged += 1;
+        self.stream_full_line();
+    }
+}
+
+impl Clearable for StreamingWriter {
+    fn clear(&mut self) {
+        self.lines.clear();
+        self.staged = 0;
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor4 for StreamingWriter {
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        let shuffled = shuffle_epi8(value, VEC_SHUFFLE_MASK4[mask as usize]);
+        self.stage(shuffled, mask.count_ones() as usize);
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor8 for StreamingWriter {
+    #[cfg(target_feature = "avx2")]
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        let shuffled = permutevar8x32_epi32(value, VEC_SHUFFLE_MASK8[mask as usize]);
+        self.stage(shuffled, mask.count_ones() as usize);
+    }
+
+    #[cfg(all(target_feature = "ssse3", not(target_feature = "avx2")))]
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        let arr = value.as_array();
+        self.visit_vector4(i32x4::from_slice(&arr[..4]), mask & 0xF);
+        self.visit_vector4(i32x4::from_slice(&arr[4..]), mask >> 4 & 0xF);
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor16 for StreamingWriter {
+    #[cfg(target_feature = "avx512f")]
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        #[cfg(target_arch = "x86")]
+        use std::arch::x86::*;
+        #[cfg(target_arch = "x86_64")]
+        use std::arch::x86_64::*;
+
+        unsafe {
+            _mm512_mask_compressstoreu_epi32(
+                self.staging.0.as_mut_ptr().add(self.staged) as *mut u8,
+                mask as u16,
+                value.into(),
+            );
+        }
+        self.staged += mask.count_ones() as usize;
+        self.stream_full_line();
+    }
+
+    #[cfg(not(target_feature = "avx512f"))]
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        let arr = value.as_array();
+        self.visit_vector8(i32x8::from_slice(&arr[..8]), mask & 0xFF);
+        self.visit_vector8(i32x8::from_slice(&arr[8..]), mask >> 8 & 0xFF);
+    }
+}
+
+/// Copies a cache line from `src` to `dst` with non-temporal stores. Both
+/// must be 64-byte aligned.
+#[inline]
+unsafe fn stream_line(dst: *mut i32, src: *const i32) {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "avx512f"))]
+    unsafe {
+        _mm512_stream_si512(dst as *mut _, std::ptr::read(src as *const __m512i));
+    }
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "avx2", not(target_feature = "avx512f")))]
+    unsafe {
+        for i in (0..LINE).step_by(8) {
+            _mm256_stream_si256(dst.add(i) as *mut _, std::ptr::read(src.add(i) as *const __m256i));
+        }
+    }
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "sse2", not(target_feature = "avx2")))]
+    unsafe {
+        for i in (0..LINE).step_by(4) {
+            _mm_stream_si128(dst.add(i) as *mut _, std::ptr::read(src.add(i) as *const __m128i));
+        }
+    }
+    #[cfg(not(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "sse2")))]
+    unsafe {
+        std::ptr::copy_nonoverlapping(src, dst, LINE);
+    }
+}
+
+/// Orders non-temporal stores before any later store, so that streamed
+/// results are visible once it returns.
+#[inline]
+fn store_fence() {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::_mm_sfence;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::_mm_sfence;
+
+    #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "sse"))]
+    unsafe { _mm_sfence() };
+}
+
 unsafe fn unsafe_vec_extend<T, V, const LANES: usize>(
     value: Simd<T, LANES>,
     mask: u64,
@@ -0,0 +1,292 @@
+//! Writes results into a memory-mapped file, so the output is bounded by disk
+//! rather than RAM and is only paged in when read back. The file starts with
+//! the number of results as a little-endian `u64`, followed by the results as
+//! native-endian `i32`s. Results reach the file when the writer is flushed,
+//! which is kept out of visiting so that I/O can be timed on its own.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read},
+    os::unix::io::AsRawFd,
+    path::Path,
+    ptr,
+};
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+use std::simd::*;
+#[cfg(all(feature = "simd", target_feature = "ssse3", not(target_feature = "avx512f")))]
+use crate::instructions::{VEC_SHUFFLE_MASK4, shuffle_epi8};
+#[cfg(all(feature = "simd", target_feature = "avx2", not(target_feature = "avx512f")))]
+use crate::instructions::{VEC_SHUFFLE_MASK8, permutevar8x32_epi32};
+
+use super::{Visitor, Clearable, Reservable};
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+use super::{SimdVisitor4, SimdVisitor8, SimdVisitor16};
+
+/// Bytes of the length prefix.
+pub const HEADER_BYTES: usize = 8;
+const ELEMENT_BYTES: usize = std::mem::size_of::<i32>();
+// Vector stores write whole vectors, so `with_capacity` maps room for one
+// more than asked.
+const SLACK: usize = 16;
+
+pub struct MmapWriter {
+    file: File,
+    map: *mut u8,
+    map_bytes: usize,
+    len: usize,
+}
+
+// The mapping is owned by the writer like a heap allocation.
+unsafe impl Send for MmapWriter {}
+
+impl MmapWriter {
+    /// Creates (or truncates) the file at `path` to hold results.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::with_capacity(path, 0)
+    }
+
+    /// Creates the file at `path` sized for `cardinality` results, so no
+    /// remapping is needed while they are written.
+    pub fn with_capacity(path: impl AsRef<Path>, cardinality: usize) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true).write(true).create(true).truncate(true)
+            .open(path)?;
+        let mut writer = Self { file, map: ptr::null_mut(), map_bytes: 0, len: 0 };
+        writer.remap(cardinality + SLACK)?;
+        Ok(writer)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Results which fit in the current mapping.
+    pub fn capacity(&self) -> usize {
+        (self.map_bytes - HEADER_BYTES) / ELEMENT_BYTES
+    }
+
+    /// Writes the length prefix and waits for the mapped results to reach
+    /// the file.
+    pub fn flush(&mut self) -> io::Result<()> {
+        unsafe {
+            ptr::write_unaligned(self.map as *mut u64, (self.len as u64).to_le());
+            if libc::msync(self.map as *mut libc::c_void, self.map_bytes, libc::MS_SYNC) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Flushes, unmaps and trims the file to the results written, returning
+    /// the file and the number of results.
+    pub fn finish(mut self) -> io::Result<(File, usize)> {
+        self.flush()?;
+        self.unmap();
+        let len = self.len;
+        let file = self.file.try_clone()?;
+        file.set_len((HEADER_BYTES + len * ELEMENT_BYTES) as u64)?;
+        Ok((file, len))
+    }
+
+    /// Reads the results of a file written by `MmapWriter`.
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<i32>> {
+        let mut file = File::open(path)?;
+        let mut header = [0u8; HEADER_BYTES];
+        file.read_exact(&mut header)?;
+        let len = u64::from_le_bytes(header) as usize;
+
+        let mut bytes = vec![0u8; len * ELEMENT_BYTES];
+        file.read_exact(&mut bytes)?;
+        Ok(bytes.chunks_exact(ELEMENT_BYTES)
+            .map(|b| i32::from_ne_bytes(b.try_into().unwrap()))
+            .collect())
+    }
+
+    #[inline]
+    fn data(&self) -> *mut i32 {
+        unsafe { self.map.add(HEADER_BYTES) as *mut i32 }
+    }
+
+    /// Makes room for `additional` results beyond those written, doubling the
+    /// mapping to amortise remapping.
+    #[inline]
+    fn ensure(&mut self, additional: usize) {
+        if self.len + additional > self.capacity() {
+            let wanted = (self.len + additional).max(2 * self.capacity());
+            self.remap(wanted).expect("failed to grow mapped output file");
+        }
+    }
+
+    fn remap(&mut self, elements: usize) -> io::Result<()> {
+        self.unmap();
+        let bytes = HEADER_BYTES + elements * ELEMENT_BYTES;
+        self.file.set_len(bytes as u64)?;
+        let map = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                bytes,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                self.file.as_raw_fd(),
+                0)
+        };
+        if map == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        self.map = map as *mut u8;
+        self.map_bytes = bytes;
+        Ok(())
+    }
+
+    fn unmap(&mut self) {
+        if !self.map.is_null() {
+            unsafe { libc::munmap(self.map as *mut libc::c_void, self.map_bytes) };
+            self.map = ptr::null_mut();
+        }
+    }
+
+    /// Stores a vector whose first `count` lanes are results.
+    #[cfg(all(feature = "simd", target_feature = "ssse3", not(target_feature = "avx512f")))]
+    #[inline]
+    fn store<const LANES: usize>(&mut self, shuffled: Simd<i32, LANES>, count: usize)
+    where
+        LaneCount<LANES>: SupportedLaneCount,
+    {
+        self.ensure(LANES);
+        unsafe {
+            let dst = self.data().add(self.len) as *mut Simd<i32, LANES>;
+            dst.write_unaligned(shuffled);
+        }
+        self.len += count;
+    }
+}
+
+impl Drop for MmapWriter {
+    fn drop(&mut self) {
+        if !self.map.is_null() {
+            // Leave a readable file behind without waiting for write-back,
+            // which `flush` and `finish` do.
+            unsafe { ptr::write_unaligned(self.map as *mut u64, (self.len as u64).to_le()) };
+            self.unmap();
+        }
+    }
+}
+
+impl AsRef<[i32]> for MmapWriter {
+    /// Reads the results back through the mapping.
+    fn as_ref(&self) -> &[i32] {
+        unsafe { std::slice::from_raw_parts(self.data(), self.len) }
+    }
+}
+
+impl Visitor<i32> for MmapWriter {
+    #[inline]
+    fn visit(&mut self, value: i32) {
+        self.ensure(1);
+        unsafe { self.data().add(self.len).write_unaligned(value) };
+        self.len += 1;
+    }
+}
+
+impl Clearable for MmapWriter {
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl Reservable for MmapWriter {
+    fn reserve(&mut self, cardinality: usize) {
+        self.ensure(cardinality);
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor4 for MmapWriter {
+    #[cfg(not(target_feature = "avx512f"))]
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        let shuffled = shuffle_epi8(value, VEC_SHUFFLE_MASK4[mask as usize]);
+        self.store(shuffled, mask.count_ones() as usize);
+    }
+
+    #[cfg(target_feature = "avx512f")]
+    #[inline]
+    fn visit_vector4(&mut self, value: i32x4, mask: u64) {
+        #[cfg(target_arch = "x86")]
+        use std::arch::x86::*;
+        #[cfg(target_arch = "x86_64")]
+        use std::arch::x86_64::*;
+
+        self.ensure(4);
+        unsafe {
+            _mm_mask_compressstoreu_epi32(
+                self.data().add(self.len) as *mut u8, mask as u8, value.into());
+        }
+        self.len += mask.count_ones() as usize;
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor8 for MmapWriter {
+    #[cfg(all(target_feature = "avx2", not(target_feature = "avx512f")))]
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        let shuffled = permutevar8x32_epi32(value, VEC_SHUFFLE_MASK8[mask as usize]);
+        self.store(shuffled, mask.count_ones() as usize);
+    }
+
+    #[cfg(target_feature = "avx512f")]
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        #[cfg(target_arch = "x86")]
+        use std::arch::x86::*;
+        #[cfg(target_arch = "x86_64")]
+        use std::arch::x86_64::*;
+
+        self.ensure(8);
+        unsafe {
+            _mm256_mask_compressstoreu_epi32(
+                self.data().add(self.len) as *mut u8, mask as u8, value.into());
+        }
+        self.len += mask.count_ones() as usize;
+    }
+
+    #[cfg(all(target_feature = "ssse3", not(target_feature = "avx2")))]
+    #[inline]
+    fn visit_vector8(&mut self, value: i32x8, mask: u64) {
+        let arr = value.as_array();
+        self.visit_vector4(i32x4::from_slice(&arr[..4]), mask & 0xF);
+        self.visit_vector4(i32x4::from_slice(&arr[4..]), mask >> 4 & 0xF);
+    }
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+impl SimdVisitor16 for MmapWriter {
+    #[cfg(target_feature = "avx512f")]
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        #[cfg(target_arch = "x86")]
+        use std::arch::x86::*;
+        #[cfg(target_arch = "x86_64")]
+        use std::arch::x86_64::*;
+
+        self.ensure(16);
+        unsafe {
+            _mm512_mask_compressstoreu_epi32(
+                self.data().add(self.len) as *mut u8, mask as u16, value.into());
+        }
+        self.len += mask.count_ones() as usize;
+    }
+
+    #[cfg(not(target_feature = "avx512f"))]
+    #[inline]
+    fn visit_vector16(&mut self, value: i32x16, mask: u64) {
+        let arr = value.as_array();
+        self.visit_vector8(i32x8::from_slice(&arr[..8]), mask & 0xFF);
+        self.visit_vector8(i32x8::from_slice(&arr[8..]), mask >> 8 & 0xFF);
+    }
+}
@@ -0,0 +1,369 @@
+//! Differential tests checking every algorithm against `BTreeSet::intersection`
+//! on seeded random cases and on the corpora in `tests/data/differential`.
+//!
+//! Random cases vary the number of sets, their density and skew, how many
+//! elements all sets share and where in the u32 range their elements lie,
+//! including both ends and either side of 2^31. Elements are generated as u32
+//! and reinterpreted as i32 for algorithms on i32, so the ends of the u32
+//! range fall in the middle of i32 sets and 2^31 at their ends.
+//!
+//! `SETOPS_DIFFERENTIAL_CASES` and `SETOPS_DIFFERENTIAL_SEED` override the
+//! number of random cases and the seed, e.g., for a longer soak run. A failing
+//! case is printed in corpus format so that it can be added to a corpus file.
+
+#[allow(dead_code)]
+mod testlib;
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    env, fmt, fs,
+    path::Path,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng, seq::SliceRandom};
+use setops::{
+    intersect::{
+        self, Intersect2,
+        algorithm::{self, DecodeIntersect, Hybrid},
+        partition::{self, PartitionedSet},
+        probe_cache::CachedGalloping,
+    },
+    bsr::BsrVec,
+    encoding::{Bp128Set, SvbSet},
+    eytzinger::EytzingerSet,
+    visitor::VecWriter,
+    Set,
+};
+use testlib::bsr::BSR_INTERSECTS;
+
+const DEFAULT_CASES: usize = 1000;
+const DEFAULT_SEED: u64 = 0;
+const MAX_LEN: usize = 1024;
+
+type TwoSetFn = Box<dyn Fn(&[i32], &[i32]) -> Vec<i32>>;
+type KSetFn = Box<dyn Fn(&[Vec<i32>]) -> Vec<i32>>;
+type TwoSetU32Fn = Box<dyn Fn(&[u32], &[u32]) -> Vec<u32>>;
+type EytzingerProbe = fn(&[i32], &EytzingerSet<i32>, &mut VecWriter<i32>);
+
+/// Sets of one query, as u32 values in any order.
+#[derive(Clone)]
+struct Case(Vec<Vec<u32>>);
+
+// Corpus format: sets separated by '|', elements separated by whitespace.
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sets: Vec<String> = self.0.iter()
+            .map(|set| set.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(" "))
+            .collect();
+        f.write_str(&sets.join(" | "))
+    }
+}
+
+impl Case {
+    fn parse(line: &str) -> Result<Self, String> {
+        line.split('|')
+            .map(|set| set.split_whitespace()
+                .map(|v| v.parse::<u32>().map_err(|e| format!("invalid element {v}: {e}")))
+                .collect())
+            .collect::<Result<_, _>>()
+            .map(Case)
+    }
+
+    /// Sorted sets in order of increasing length.
+    fn sorted<T: Ord + Copy>(&self, convert: impl Fn(u32) -> T) -> Vec<Vec<T>> {
+        let mut sets: Vec<Vec<T>> = self.0.iter()
+            .map(|set| {
+                let mut set: Vec<T> = set.iter().map(|&v| convert(v)).collect();
+                set.sort_unstable();
+                set.dedup();
+                set
+            })
+            .collect();
+        sets.sort_by_key(|s| s.len());
+        sets
+    }
+}
+
+fn random_case(rng: &mut StdRng) -> Case {
+    let set_count = if rng.gen_bool(0.7) { 2 } else { rng.gen_range(3..=5) };
+
+    // Lengths are log-uniform so that short sets around vector widths are
+    // common, and the other sets are up to 64 times longer.
+    let log_uniform = |rng: &mut StdRng, max: usize| -> usize {
+        (rng.gen_range(0.0..(max as f64 + 1.0).log2()).exp2() as usize - 1).min(max)
+    };
+    let small_len = log_uniform(rng, MAX_LEN);
+    let lens: Vec<usize> = (0..set_count)
+        .map(|i| if i == 0 { small_len } else {
+            (small_len.max(1) << rng.gen_range(0..=6)).min(MAX_LEN * 4)
+        })
+        .collect();
+    let max_len = *lens.iter().max().unwrap() as u64;
+
+    let density = *[1.0, 0.5, 0.1, 0.01, 1e-4, 0.0].choose(rng).unwrap();
+    let span = if density == 0.0 {
+        u32::MAX as u64 + 1
+    } else {
+        ((max_len as f64 / density) as u64).clamp(1, u32::MAX as u64 + 1)
+    };
+    let start = match rng.gen_range(0..4) {
+        0 => 0,
+        1 => u32::MAX as u64 + 1 - span,
+        2 => (1u64 << 31).saturating_sub(span / 2).min(u32::MAX as u64 + 1 - span),
+        _ => rng.gen_range(0..=u32::MAX as u64 + 1 - span),
+    };
+    let element = |rng: &mut StdRng| (start + rng.gen_range(0..span)) as u32;
+
+    let shared: Vec<u32> = (0..rng.gen_range(0..=small_len)).map(|_| element(rng)).collect();
+    let sets = lens.iter()
+        .map(|&len| {
+            let mut set = shared.clone();
+            set.truncate(len);
+            set.extend((set.len()..len).map(|_| element(rng)));
+            set
+        })
+        .collect();
+    Case(sets)
+}
+
+fn btree_intersect<T: Ord + Copy>(sets: &[Vec<T>]) -> Vec<T> {
+    let mut result: BTreeSet<T> = sets[0].iter().copied().collect();
+    for set in &sets[1..] {
+        let set: BTreeSet<T> = set.iter().copied().collect();
+        result = result.intersection(&set).copied().collect();
+    }
+    result.into_iter().collect()
+}
+
+fn hash_count(sets: &[Vec<i32>]) -> usize {
+    let sets: Vec<HashSet<i32>> = sets.iter().map(|s| s.iter().copied().collect()).collect();
+    sets[0].iter().filter(|v| sets[1..].iter().all(|s| s.contains(v))).count()
+}
+
+struct Algorithms {
+    twoset: Vec<(String, TwoSetFn)>,
+    kset: Vec<(String, KSetFn)>,
+    twoset_u32: Vec<(String, TwoSetU32Fn)>,
+}
+
+fn algorithms() -> Algorithms {
+    let mut twoset: Vec<(String, TwoSetFn)> = Vec::new();
+    let mut kset: Vec<(String, KSetFn)> = Vec::new();
+
+    for name in intersect::dynamic::names() {
+        let handle = intersect::by_name(name).unwrap();
+        twoset.push((name.to_string(), Box::new(move |a, b| {
+            let result = handle.intersect(a, b);
+            assert_eq!(handle.count(a, b), result.len(), "{} count", handle.name());
+            result
+        })));
+    }
+
+    let kernel: Intersect2<[i32], VecWriter<i32>> = intersect::branchless_merge;
+    twoset.push(("partition_branchless_merge".to_string(), Box::new(move |a, b| {
+        let (a, b) = PartitionedSet::pair(a, b, 16);
+        let mut writer = VecWriter::new();
+        partition::partition_intersect(&a, &b, kernel, &mut writer);
+        writer.into()
+    })));
+    let hybrid = Hybrid { bitmap_threshold: 64, intersect: kernel };
+    twoset.push(("hybrid64_branchless_merge".to_string(),
+        Box::new(move |a, b| algorithm::run_2set(a, b, &hybrid))));
+    let bp128 = DecodeIntersect::<Bp128Set, _>::new(kernel);
+    twoset.push(("bp128_branchless_merge".to_string(),
+        Box::new(move |a, b| algorithm::run_2set(a, b, &bp128))));
+    let svb = DecodeIntersect::<SvbSet, _>::new(kernel);
+    twoset.push(("svb_branchless_merge".to_string(),
+        Box::new(move |a, b| algorithm::run_2set(a, b, &svb))));
+    twoset.push(("galloping_cached".to_string(), Box::new(|a, b| {
+        algorithm::run_2set(a, b, &CachedGalloping { shift: 2 })
+    })));
+    let probes: &[(&str, EytzingerProbe)] = &[
+        ("eytzinger_probe", intersect::eytzinger_probe),
+        #[cfg(all(feature = "simd", target_feature = "avx2"))]
+        ("eytzinger_probe_avx2", intersect::eytzinger_probe_avx2),
+        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+        ("eytzinger_probe_avx512", intersect::eytzinger_probe_avx512),
+    ];
+    for &(name, probe) in probes {
+        twoset.push((name.to_string(), Box::new(move |a, b| {
+            let mut writer = VecWriter::new();
+            probe(a, &EytzingerSet::from_sorted(b), &mut writer);
+            writer.into()
+        })));
+    }
+
+    kset.push(("svs".to_string(), Box::new(|sets| intersect::run_kset(sets, intersect::svs))));
+    kset.push(("baezayates_k".to_string(),
+        Box::new(|sets| intersect::run_kset(sets, intersect::baezayates_k))));
+    kset.push(("small_adaptive".to_string(),
+        Box::new(|sets| intersect::run_kset(sets, intersect::small_adaptive))));
+    kset.push(("small_adaptive_sorted".to_string(),
+        Box::new(|sets| intersect::run_kset(sets, intersect::small_adaptive_sorted))));
+
+    #[cfg(feature = "simd")]
+    fesia::push_algorithms(&mut twoset, &mut kset);
+
+    let mut twoset_u32: Vec<(String, TwoSetU32Fn)> = vec![
+        ("naive_merge".to_string(),
+            Box::new(|a, b| intersect::run_2set(a, b, intersect::naive_merge))),
+        ("branchless_merge".to_string(),
+            Box::new(|a, b| intersect::run_2set(a, b, intersect::branchless_merge))),
+        ("galloping".to_string(),
+            Box::new(|a, b| intersect::run_2set(a, b, intersect::galloping))),
+        ("binary_search".to_string(),
+            Box::new(|a, b| intersect::run_2set(a, b, intersect::binary_search_intersect))),
+        ("baezayates".to_string(),
+            Box::new(|a, b| intersect::run_2set(a, b, intersect::baezayates))),
+    ];
+    for bsr in BSR_INTERSECTS {
+        let i = bsr.1;
+        twoset_u32.push((bsr.0.to_string(), Box::new(move |a, b| {
+            let (a, b) = (BsrVec::from_sorted(a), BsrVec::from_sorted(b));
+            intersect::run_2set_bsr(a.bsr_ref(), b.bsr_ref(), i).to_sorted_set()
+        })));
+    }
+
+    Algorithms { twoset, kset, twoset_u32 }
+}
+
+#[cfg(feature = "simd")]
+mod fesia {
+    use super::*;
+    use setops::intersect::{
+        algorithm::{FesiaAlgorithm, FesiaHashAlgorithm},
+        fesia::*,
+    };
+
+    const HASH_SCALES: &[HashScale] = &[0.5, 2.0];
+
+    // FESIA writes its result out of order.
+    fn sorted(mut result: Vec<i32>) -> Vec<i32> {
+        result.sort_unstable();
+        result
+    }
+
+    fn push<F, I>(name: &str, twoset: &mut Vec<(String, TwoSetFn)>, kset: &mut Vec<(String, KSetFn)>)
+    where
+        F: SetWithHashScale + FesiaIntersect + AsRef<F> + 'static,
+        I: SegmentIntersect + 'static,
+    {
+        for &hash_scale in HASH_SCALES {
+            let fesia = FesiaAlgorithm::<F, I>::new(hash_scale);
+            twoset.push((format!("{name}_{hash_scale}"),
+                Box::new(move |a, b| sorted(algorithm::run_2set(a, b, &fesia)))));
+            let fesia = FesiaAlgorithm::<F, I>::new(hash_scale);
+            kset.push((format!("{name}_{hash_scale}"),
+                Box::new(move |sets| sorted(algorithm::run_kset(sets, &fesia)))));
+        }
+    }
+
+    fn push_hash<F>(name: &str, twoset: &mut Vec<(String, TwoSetFn)>)
+    where
+        F: SetWithHashScale + FesiaIntersect + 'static,
+    {
+        for &hash_scale in HASH_SCALES {
+            let fesia = FesiaHashAlgorithm::<F>::new(hash_scale);
+            twoset.push((format!("{name}_{hash_scale}"),
+                Box::new(move |a, b| sorted(algorithm::run_2set(a, b, &fesia)))));
+        }
+    }
+
+    pub fn push_algorithms(twoset: &mut Vec<(String, TwoSetFn)>, kset: &mut Vec<(String, KSetFn)>) {
+        push_hash::<Fesia8Sse>("fesia_hash8", twoset);
+        push_hash::<Fesia16Sse>("fesia_hash16", twoset);
+        push_hash::<Fesia32Sse>("fesia_hash32", twoset);
+        #[cfg(target_feature = "ssse3")]
+        {
+            push::<Fesia8Sse, SegmentIntersectSse>("fesia8_sse", twoset, kset);
+            push::<Fesia16Sse, SegmentIntersectSse>("fesia16_sse", twoset, kset);
+            push::<Fesia32Sse, SegmentIntersectSse>("fesia32_sse", twoset, kset);
+        }
+        #[cfg(target_feature = "avx2")]
+        {
+            push::<Fesia8Avx2, SegmentIntersectAvx2>("fesia8_avx2", twoset, kset);
+            push::<Fesia16Avx2, SegmentIntersectAvx2>("fesia16_avx2", twoset, kset);
+            push::<Fesia32Avx2, SegmentIntersectAvx2>("fesia32_avx2", twoset, kset);
+        }
+        #[cfg(target_feature = "avx512f")]
+        {
+            push::<Fesia8Avx512, SegmentIntersectAvx512>("fesia8_avx512", twoset, kset);
+            push::<Fesia16Avx512, SegmentIntersectAvx512>("fesia16_avx512", twoset, kset);
+            push::<Fesia32Avx512, SegmentIntersectAvx512>("fesia32_avx512", twoset, kset);
+        }
+    }
+}
+
+/// Runs every algorithm applicable to `case`, returning the names of those
+/// which differ from `BTreeSet`.
+fn check_case(algorithms: &Algorithms, case: &Case) -> Vec<String> {
+    let mut failed = Vec::new();
+    if case.0.len() < 2 {
+        return failed;
+    }
+
+    let sets = case.sorted(|v| v as i32);
+    let expected = btree_intersect(&sets);
+    assert_eq!(hash_count(&sets), expected.len(), "HashSet and BTreeSet differ");
+
+    if let [a, b] = sets.as_slice() {
+        failed.extend(algorithms.twoset.iter()
+            .filter(|(_, intersect)| intersect(a, b) != expected)
+            .map(|(name, _)| name.clone()));
+
+        let sets_u32 = case.sorted(|v| v);
+        let expected_u32 = btree_intersect(&sets_u32);
+        failed.extend(algorithms.twoset_u32.iter()
+            .filter(|(_, intersect)| intersect(&sets_u32[0], &sets_u32[1]) != expected_u32)
+            .map(|(name, _)| format!("{name} (u32)")));
+    }
+    failed.extend(algorithms.kset.iter()
+        .filter(|(_, intersect)| intersect(&sets) != expected)
+        .map(|(name, _)| name.clone()));
+    failed
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+#[test]
+fn differential_random() {
+    let cases = env_or("SETOPS_DIFFERENTIAL_CASES", DEFAULT_CASES);
+    let seed = env_or("SETOPS_DIFFERENTIAL_SEED", DEFAULT_SEED);
+
+    let algorithms = algorithms();
+    let mut rng = StdRng::seed_from_u64(seed);
+    for i in 0..cases {
+        let case = random_case(&mut rng);
+        let failed = check_case(&algorithms, &case);
+        assert!(failed.is_empty(),
+            "case {i} of seed {seed} differs from BTreeSet for {}:\n{case}", failed.join(", "));
+    }
+}
+
+#[test]
+fn differential_corpus() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/differential");
+    let algorithms = algorithms();
+
+    let mut paths: Vec<_> = fs::read_dir(&dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "txt"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no corpus files in {}", dir.display());
+
+    for path in paths {
+        let corpus = fs::read_to_string(&path).unwrap();
+        let lines = corpus.lines().enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+
+        for (number, line) in lines {
+            let case = Case::parse(line)
+                .unwrap_or_else(|e| panic!("{}:{}: {e}", path.display(), number + 1));
+            let failed = check_case(&algorithms, &case);
+            assert!(failed.is_empty(), "{}:{} differs from BTreeSet for {}",
+                path.display(), number + 1, failed.join(", "));
+        }
+    }
+}
@@ -6,13 +6,36 @@ mod testlib;
 use testlib::{
     DualIntersectFn, SortedSet, SetCollection,
     properties::prop_intersection_correct,
-    SimilarSetPair, SkewedSetPair,
+    SimilarSetPair, SkewedSetPair, SignBoundaryPair,
+    bsr::{BsrIntersectFn, BsrIntersect, BsrSet, BsrSetPair, SkewedBsrPair},
 };
 use setops::{
-    intersect::{self, fesia::*, Intersect2}, bsr::BsrVec, Set,
-    visitor::{VecWriter, UnsafeWriter, EnsureVisitor, EnsureVisitorBsr, Counter},
+    intersect::{
+        self, fesia::*, Intersect2, IntersectInplace, IntersectCursor, SvsBuffers, partition::{self, PartitionedSet},
+        ProbeCache, probe_cache::CachedGalloping, prefetch::GallopingPrefetch, GallopingFactor,
+        SkipSet, skip::SkipGalloping, BatchProbe, probe_cache::CachedSet,
+        algorithm::{self, Sorted2, SortedK, DecodeIntersect, Hybrid}, DeltaAdapter, MultisetLayers,
+        weighted::{self, Combine, ScoreAdapter, SumScore, ProductScore}, tiny::TINY_MAX_LEN,
+    },
+    bsr::{BsrVec, BsrRef}, bitmap, ewah::EwahBitmap, Set,
+    visitor::{
+        VecWriter, UnsafeWriter, ReserveWriter, StreamingWriter, UnsafeLookupWriter, EnsureVisitor, EnsureVisitorBsr, Counter, DedupAdapter,
+        BsrExpandAdapter, LimitVisitor, Fold, FoldVisitor, Xor, And, Sum, Min, Max, PayloadWriter,
+        MmapWriter, HintedWriter, ScoreWriter,
+    },
+    util::{is_strictly_sorted, check_strictly_sorted, check_strictly_sorted_simd, SortError},
+    element::IntElement,
+    estimate, simple,
+    encoding::{Bp128Set, SvbSet, BlockSet},
+    hybrid::HybridSet,
+    eytzinger::EytzingerSet,
+    bloom::BlockedBloom,
+    versioned::VersionedSet,
+    instrument::{self, InstrumentVisitor},
 };
 
+use std::collections::BTreeMap;
+
 use FesiaTwoSetMethod::*;
 use SimdType::*;
 
@@ -104,6 +127,49 @@ quickcheck! {
         prop_intersection_correct(result, sets.as_slice())
     }
 
+    fn svs_double_buffered_correct(
+        intersect: DualIntersectFn,
+        sets: SetCollection<i32>,
+        other: SetCollection<i32>) -> bool
+    {
+        // Buffers reused by a second query.
+        let mut buffers = SvsBuffers::default();
+        let result = intersect::svs_double_buffered(sets.as_slice(), &mut buffers, intersect.1)
+            .as_ref().to_vec();
+        let other_result = intersect::svs_double_buffered(other.as_slice(), &mut buffers, intersect.1)
+            .as_ref().to_vec();
+
+        prop_intersection_correct(result, sets.as_slice()) &&
+        prop_intersection_correct(other_result, other.as_slice())
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn svs_double_buffered_unsafe_correct(sets: SetCollection<i32>, other: SetCollection<i32>) -> bool {
+        let mut buffers: SvsBuffers<UnsafeWriter<i32>> = SvsBuffers::default();
+        let result = intersect::svs_double_buffered(
+            sets.as_slice(), &mut buffers, intersect::shuffling_avx2).as_ref().to_vec();
+        let other_result = intersect::svs_double_buffered(
+            other.as_slice(), &mut buffers, intersect::shuffling_avx2).as_ref().to_vec();
+
+        prop_intersection_correct(result, sets.as_slice()) &&
+        prop_intersection_correct(other_result, other.as_slice())
+    }
+
+    fn svs_driver_correct(sets: SetCollection<i32>, other: SetCollection<u64>) -> bool {
+        let result = intersect::run_kset(sets.as_slice(), intersect::svs);
+        let other_result = intersect::run_kset(other.as_slice(), intersect::svs);
+
+        // The buffer is cleared before reuse.
+        let mut buffer = vec![0, 1, 2];
+        let mut writer = VecWriter::new();
+        intersect::svs_with_buffer(sets.as_slice(), &mut buffer, &mut writer);
+        let reused: Vec<i32> = writer.into();
+
+        prop_intersection_correct(reused, sets.as_slice()) &&
+        prop_intersection_correct(result, sets.as_slice()) &&
+        prop_intersection_correct(other_result, other.as_slice())
+    }
+
     fn small_adaptive_correct(sets: SetCollection<i32>) -> bool {
         let result = intersect::run_kset(sets.as_slice(), intersect::small_adaptive);
         prop_intersection_correct(result, sets.as_slice())
@@ -114,6 +180,23 @@ quickcheck! {
         prop_intersection_correct(result, sets.as_slice())
     }
 
+    // The first set against the union of the rest.
+    fn union_intersect_correct(sets: SetCollection<i32>) -> bool {
+        let Some((set_a, lists)) = sets.as_slice().split_first() else {
+            return true;
+        };
+        union_intersect_matches(set_a.as_slice(), lists, intersect::galloping) &&
+        union_intersect_matches(set_a.as_slice(), lists, intersect::branchless_merge)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn union_intersect_sse_correct(sets: SetCollection<i32>) -> bool {
+        let Some((set_a, lists)) = sets.as_slice().split_first() else {
+            return true;
+        };
+        union_intersect_matches(set_a.as_slice(), lists, intersect::shuffling_sse)
+    }
+
     // SIMD Shuffling
     #[cfg(feature = "simd")]
     fn shuffling_sse_correct(set_a: SortedSet<i32>, set_b: SortedSet<i32>) -> bool {
@@ -144,6 +227,63 @@ quickcheck! {
         actual == expected
     }
 
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn shuffling_sse_aligned_correct(sets: SimilarSetPair<i32>) -> bool {
+        aligned_matches_merge(&sets, intersect::shuffling_sse_aligned)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn shuffling_avx2_aligned_correct(sets: SimilarSetPair<i32>) -> bool {
+        aligned_matches_merge(&sets, intersect::shuffling_avx2_aligned)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    fn shuffling_avx512_aligned_correct(sets: SimilarSetPair<i32>) -> bool {
+        aligned_matches_merge(&sets, intersect::shuffling_avx512_aligned)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn shuffling_sse_tail_correct(sets: SimilarSetPair<i32>) -> bool {
+        tail_matches_merge(sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_sse_tail) &&
+        tail_matches_merge(sets.0.as_slice(), sets.1.as_slice(), intersect::simd_merge_sse_tail)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn shuffling_avx2_tail_correct(sets: SimilarSetPair<i32>) -> bool {
+        tail_matches_merge(sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_avx2_tail) &&
+        tail_matches_merge(sets.0.as_slice(), sets.1.as_slice(), intersect::simd_merge_avx2_tail)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    fn shuffling_avx512_tail_correct(sets: SimilarSetPair<i32>) -> bool {
+        tail_matches_merge(sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_avx512_tail)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn shuffling_tail_skewed_correct(sets: SkewedSetPair<i32>) -> bool {
+        let (small, large) = (sets.small.as_slice(), sets.large.as_slice());
+        tail_matches_merge(small, large, intersect::shuffling_sse_tail) &&
+        tail_matches_merge(large, small, intersect::shuffling_avx2_tail) &&
+        tail_matches_merge(small, large, intersect::simd_merge_avx2_tail)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn tiny_correct(sets: SimilarSetPair<i32>) -> bool {
+        let (set_a, set_b) = (sets.0.as_slice(), sets.1.as_slice());
+        tiny_matches_merge(set_a, set_b, intersect::tiny_sse) &&
+        tiny_prefixes(set_a, set_b).all(|(a, b)| simple_matches(a, b))
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn tiny_avx2_correct(sets: SimilarSetPair<i32>) -> bool {
+        tiny_matches_merge(sets.0.as_slice(), sets.1.as_slice(), intersect::tiny_avx2)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    fn tiny_avx512_correct(sets: SimilarSetPair<i32>) -> bool {
+        tiny_matches_merge(sets.0.as_slice(), sets.1.as_slice(), intersect::tiny_avx512)
+    }
+
     #[cfg(feature = "simd")]
     fn shuffling_sse_bsr_correct(sets: SimilarSetPair<u32>) -> bool {
         let left = BsrVec::from_sorted(sets.0.as_ref());
@@ -396,6 +536,49 @@ quickcheck! {
         ensurer.position() == expected.len()
     }
 
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn galloping_avx2_bsr_correct(sets: SkewedSetPair<u32>) -> bool {
+        let small = BsrVec::from_sorted(sets.small.as_ref());
+        let large = BsrVec::from_sorted(sets.large.as_ref());
+
+        let expected = intersect::run_2set_bsr(
+            small.bsr_ref(), large.bsr_ref(), intersect::branchless_merge_bsr);
+
+        let mut ensurer = EnsureVisitorBsr::from(expected.bsr_ref());
+
+        intersect::galloping_avx2_bsr(small.bsr_ref(), large.bsr_ref(), &mut ensurer);
+        ensurer.position() == expected.len()
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    fn galloping_avx512_bsr_correct(sets: SkewedSetPair<u32>) -> bool {
+        let small = BsrVec::from_sorted(sets.small.as_ref());
+        let large = BsrVec::from_sorted(sets.large.as_ref());
+
+        let expected = intersect::run_2set_bsr(
+            small.bsr_ref(), large.bsr_ref(), intersect::branchless_merge_bsr);
+
+        let mut ensurer = EnsureVisitorBsr::from(expected.bsr_ref());
+
+        intersect::galloping_avx512_bsr(small.bsr_ref(), large.bsr_ref(), &mut ensurer);
+        ensurer.position() == expected.len()
+    }
+
+    // SIMD galloping swaps its inputs so the smaller set is always probed.
+    #[cfg(feature = "simd")]
+    fn galloping_sse_bsr_swapped_correct(sets: SkewedSetPair<u32>) -> bool {
+        let small = BsrVec::from_sorted(sets.small.as_ref());
+        let large = BsrVec::from_sorted(sets.large.as_ref());
+
+        let expected = intersect::run_2set_bsr(
+            small.bsr_ref(), large.bsr_ref(), intersect::branchless_merge_bsr);
+
+        let actual = intersect::run_2set_bsr(
+            large.bsr_ref(), small.bsr_ref(), intersect::galloping_sse_bsr);
+
+        actual.bases == expected.bases && actual.states == expected.states
+    }
+
     fn galloping_bsr_correct(sets: SkewedSetPair<u32>) -> bool {
         let small = BsrVec::from_sorted(sets.small.as_ref());
         let large = BsrVec::from_sorted(sets.large.as_ref());
@@ -463,6 +646,92 @@ quickcheck! {
         actual == expected
     }
 
+    #[cfg(feature = "simd")]
+    fn bmiss_galloping_correct(sets: SkewedSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.small.as_slice(),
+            sets.large.as_slice(),
+            intersect::naive_merge);
+
+        let actual = intersect::run_2set(
+            sets.small.as_slice(),
+            sets.large.as_slice(),
+            intersect::bmiss_galloping);
+
+        actual == expected
+    }
+
+    #[cfg(feature = "simd")]
+    fn bmiss_galloping_similar_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(),
+            sets.1.as_slice(),
+            intersect::naive_merge);
+
+        let actual = intersect::run_2set(
+            sets.0.as_slice(),
+            sets.1.as_slice(),
+            intersect::bmiss_galloping);
+
+        actual == expected
+    }
+
+    // STTNI
+    #[cfg(all(feature = "simd", target_feature = "sse4.2"))]
+    fn sttni_u16_correct(sets: SimilarSetPair<u16>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(),
+            sets.1.as_slice(),
+            intersect::naive_merge);
+
+        let actual = intersect::run_2set(
+            sets.0.as_slice(),
+            sets.1.as_slice(),
+            intersect::sttni_u16);
+
+        actual == expected
+    }
+
+    // SIMD merge
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn simd_merge_sse_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(),
+            sets.1.as_slice(),
+            intersect::naive_merge);
+
+        let actual = intersect::run_2set(
+            sets.0.as_slice(),
+            sets.1.as_slice(),
+            intersect::simd_merge_sse);
+
+        actual == expected
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn simd_merge_avx2_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(),
+            sets.1.as_slice(),
+            intersect::naive_merge);
+
+        let actual = intersect::run_2set(
+            sets.0.as_slice(),
+            sets.1.as_slice(),
+            intersect::simd_merge_avx2);
+
+        actual == expected
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn simd_merge_skewed_correct(sets: SkewedSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.small.as_slice(), sets.large.as_slice(), intersect::naive_merge);
+
+        intersect::run_2set(sets.small.as_slice(), sets.large.as_slice(), intersect::simd_merge_sse) == expected &&
+        intersect::run_2set(sets.large.as_slice(), sets.small.as_slice(), intersect::simd_merge_avx2) == expected
+    }
+
     // QFilter
     #[cfg(feature = "simd")]
     fn qfilter_correct(sets: SimilarSetPair<i32>) -> bool {
@@ -619,6 +888,29 @@ quickcheck! {
         })
     }
 
+    #[cfg(feature = "simd")]
+    fn fesia_hash_batch_correct(items: Vec<i32>) -> bool {
+        hash_batch_matches::<IdentityHash>(&items) &&
+        hash_batch_matches::<MixHash>(&items) &&
+        hash_batch_matches::<MultiplyShiftHash>(&items) &&
+        hash_batch_matches::<Crc32Hash>(&items) &&
+        hash_batch_matches::<XxHash>(&items)
+    }
+
+    // Every hash function, with both the kernels and hash probing.
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn fesia_hashes_correct(sets: SkewedSetPair<i32>) -> bool {
+        let small = sets.small.as_slice();
+        let large = sets.large.as_slice();
+        (1..5).map(|h| h as f64 * 4.0).all(|hash_scale| {
+            fesia_hash_matches::<IdentityHash>(small, large, hash_scale) &&
+            fesia_hash_matches::<MixHash>(small, large, hash_scale) &&
+            fesia_hash_matches::<MultiplyShiftHash>(small, large, hash_scale) &&
+            fesia_hash_matches::<Crc32Hash>(small, large, hash_scale) &&
+            fesia_hash_matches::<XxHash>(small, large, hash_scale)
+        })
+    }
+
     #[cfg(all(feature = "simd", target_feature = "avx2"))]
     fn fesia8_avx2_correct(sets: SimilarSetPair<i32>) -> bool {
         let set_a = sets.0.as_slice();
@@ -682,6 +974,74 @@ quickcheck! {
         })
     }
 
+    #[cfg(feature = "simd")]
+    fn fesia_hash_probe_correct(sets: SkewedSetPair<i32>) -> bool {
+        let small = sets.small.as_slice();
+        let large = sets.large.as_slice();
+        (0..10).map(|h| h as f64 * 2.0).all(|hash_scale| {
+            fesia_hash_probe_matches::<Fesia8Avx2>(small, large, hash_scale) &&
+            fesia_hash_probe_matches::<Fesia16Avx2>(small, large, hash_scale) &&
+            fesia_hash_probe_matches::<Fesia<IdentityHash, i16, 16>>(small, large, hash_scale) &&
+            fesia_hash_probe_matches::<Fesia<XxHash, i32, 8>>(small, large, hash_scale)
+        })
+    }
+
+    #[cfg(feature = "simd")]
+    fn fesia_stats_correct(sets: SkewedSetPair<i32>) -> bool {
+        let small = sets.small.as_slice();
+        let large = sets.large.as_slice();
+        (1..10).map(|h| h as f64 * 2.0).all(|hash_scale| {
+            let set_small = Fesia16Avx2::from_sorted(small, hash_scale);
+            let set_large = Fesia16Avx2::from_sorted(large, hash_scale);
+
+            let mut stats = set_small.stats();
+            let small_ok = stats.elements == small.len() &&
+                stats.histogram.iter().sum::<usize>() == set_small.segment_count() &&
+                stats.histogram.iter().enumerate().map(|(n, &c)| n * c).sum::<usize>() == small.len() &&
+                stats.bitmap_ones <= small.len();
+
+            stats.merge(&set_large.stats());
+            small_ok &&
+                stats.sets == 2 &&
+                stats.elements == small.len() + large.len() &&
+                stats.segments == set_small.segment_count() + set_large.segment_count() &&
+                stats.histogram.iter().sum::<usize>() == stats.segments
+        })
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn fesia_updates_correct(sets: SimilarSetPair<i32>) -> bool {
+        let set_a = sets.0.as_slice();
+        let set_b = sets.1.as_slice();
+        (1..10).map(|h| h as f64 * 2.0).all(|hash_scale| {
+            fesia_updates_match::<Fesia8Sse>(set_a, set_b, hash_scale) &&
+            fesia_updates_match::<Fesia16Sse>(set_a, set_b, hash_scale) &&
+            fesia_updates_match::<Fesia32Sse>(set_a, set_b, hash_scale)
+        })
+    }
+
+    // Keys beyond 32 bits, several of which fold to each residual.
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn fesia64_correct(sets: SkewedSetPair<i32>) -> bool {
+        let small = colliding_keys(sets.small.as_slice(), 0);
+        let large = colliding_keys(sets.large.as_slice(), 1);
+        (0..10).map(|h| h as f64 * 2.0).all(|hash_scale| {
+            fesia64_matches(&small, &large, hash_scale)
+        })
+    }
+
+    // The bitmap pre-test never rejects sets sharing an element.
+    #[cfg(feature = "simd")]
+    fn fesia_maybe_intersects_correct(sets: SkewedSetPair<i32>) -> bool {
+        let small = sets.small.as_slice();
+        let large = sets.large.as_slice();
+        (1..10).map(|h| h as f64 * 2.0).all(|hash_scale| {
+            fesia_maybe_intersects_matches::<Fesia8Avx512>(small, large, hash_scale) &&
+            fesia_maybe_intersects_matches::<Fesia16Avx2>(small, large, hash_scale) &&
+            fesia_maybe_intersects_matches::<Fesia32Sse>(small, large, hash_scale)
+        })
+    }
+
     #[cfg(all(feature = "simd", target_feature = "ssse3"))]
     fn fesia_kset_sse_correct(sets: SetCollection<i32>) -> bool {
         let mut sets: Vec<SortedSet<i32>> = sets.into();
@@ -729,48 +1089,1811 @@ quickcheck! {
         set.as_ref() == BsrVec::from_sorted(set.as_ref()).to_sorted_set()
     }
 
-    // Unsafe writer
+    fn sorted_set_strictly_sorted(set: SortedSet<i32>) -> bool {
+        is_strictly_sorted(set.as_slice())
+    }
+
+    // Element
+    fn ordered_lane_preserves_order_u32(a: u32, b: u32) -> bool {
+        a.cmp(&b) == a.to_ordered_lane().cmp(&b.to_ordered_lane()) &&
+        u32::from_ordered_lane(a.to_ordered_lane()) == a &&
+        u32::from_lane(a.to_lane()) == a
+    }
+
+    fn ordered_lane_preserves_order_u64(a: u64, b: u64) -> bool {
+        a.cmp(&b) == a.to_ordered_lane().cmp(&b.to_ordered_lane()) &&
+        u64::from_ordered_lane(a.to_ordered_lane()) == a
+    }
+
+    fn ordered_lane_identity_i32(a: i32) -> bool {
+        a.to_ordered_lane() == a && a.to_lane() == a
+    }
+
     #[cfg(feature = "simd")]
-    fn unsafe_writer_sse_correct(set_a: SortedSet<i32>, set_b: SortedSet<i32>) -> bool {
-        let result = run_unsafe_writer(
-            set_a.as_slice(), set_b.as_slice(), intersect::shuffling_sse);
-        prop_intersection_correct(result, &[set_a.as_slice(), set_b.as_slice()])
+    fn shuffling_sse_u32_correct(sets: SimilarSetPair<u32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(),
+            sets.1.as_slice(),
+            intersect::naive_merge);
+
+        let mut visitor: VecWriter<u32> = VecWriter::new();
+        intersect::shuffling_sse(sets.0.as_slice(), sets.1.as_slice(), &mut visitor);
+
+        let actual: Vec<u32> = visitor.into();
+        actual == expected
+    }
+
+    // Values straddling 2^31
+    #[cfg(feature = "simd")]
+    fn sign_boundary_sse_correct(sets: SignBoundaryPair) -> bool {
+        [
+            intersect::shuffling_sse,
+            intersect::shuffling_sse_branch,
+            intersect::broadcast_sse,
+            intersect::bmiss,
+            intersect::qfilter,
+            intersect::lbk_v1x4_sse,
+            intersect::lbk_v3_sse,
+            intersect::simd_merge_sse,
+        ].into_iter().all(|i| sign_boundary_correct(&sets, i))
     }
 
     #[cfg(all(feature = "simd", target_feature = "avx2"))]
-    fn unsafe_writer_avx2_correct(set_a: SortedSet<i32>, set_b: SortedSet<i32>) -> bool {
-        let result = run_unsafe_writer(
-            set_a.as_slice(), set_b.as_slice(), intersect::shuffling_avx2);
-        prop_intersection_correct(result, &[set_a.as_slice(), set_b.as_slice()])
+    fn sign_boundary_avx2_correct(sets: SignBoundaryPair) -> bool {
+        [
+            intersect::shuffling_avx2,
+            intersect::shuffling_avx2_branch,
+            intersect::broadcast_avx2,
+            intersect::lbk_v1x8_avx2,
+            intersect::lbk_v3_avx2,
+            intersect::simd_merge_avx2,
+        ].into_iter().all(|i| sign_boundary_correct(&sets, i))
     }
 
     #[cfg(all(feature = "simd", target_feature = "avx512f"))]
-    fn unsafe_writer_avx512_correct(sets: SimilarSetPair<i32>) -> bool {
-        let expected = run_unsafe_writer(
+    fn sign_boundary_avx512_correct(sets: SignBoundaryPair) -> bool {
+        [
+            intersect::shuffling_avx512,
+            intersect::shuffling_avx512_branch,
+            intersect::broadcast_avx512,
+            intersect::vp2intersect_emulation,
+            intersect::lbk_v1x16_avx512,
+            intersect::lbk_v3_avx512,
+        ].into_iter().all(|i| sign_boundary_correct(&sets, i))
+    }
+
+    #[cfg(feature = "simd")]
+    fn sign_boundary_fesia_correct(sets: SignBoundaryPair) -> bool {
+        (1..5).map(|h| h as f64).all(|hash_scale| {
+            fesia_u32_correct::<Fesia8Sse, SegmentIntersectSse>(&sets, hash_scale) &&
+            fesia_u32_correct::<Fesia16Sse, SegmentIntersectSse>(&sets, hash_scale) &&
+            fesia_u32_correct::<Fesia32Sse, SegmentIntersectSse>(&sets, hash_scale)
+        })
+    }
+
+    // Dedup adapter
+    fn dedup_adapter_multiset_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
             sets.0.as_slice(),
             sets.1.as_slice(),
             intersect::naive_merge);
 
-        let actual = run_unsafe_writer(
+        let left = repeat_items(sets.0.as_slice());
+        let right = repeat_items(sets.1.as_slice());
+
+        let mut visitor = DedupAdapter::new(VecWriter::new());
+        intersect::branchless_merge(&left, &right, &mut visitor);
+
+        let actual: Vec<i32> = visitor.into_inner().into();
+        actual == expected
+    }
+
+    #[cfg(feature = "simd")]
+    fn dedup_adapter_simd_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
             sets.0.as_slice(),
             sets.1.as_slice(),
-            intersect::shuffling_avx512);
+            intersect::naive_merge);
 
+        let mut visitor = DedupAdapter::new(VecWriter::new());
+        intersect::shuffling_sse(sets.0.as_slice(), sets.1.as_slice(), &mut visitor);
+
+        let actual: Vec<i32> = visitor.into_inner().into();
         actual == expected
     }
-}
 
-fn run_unsafe_writer<T>(
-    set_a: &[T],
-    set_b: &[T],
-    intersect: Intersect2<[T], UnsafeWriter<T>>) -> Vec<T>
-{
-    let mut writer: UnsafeWriter<T> = UnsafeWriter::with_capacity(set_a.len().min(set_b.len()));
-    intersect(set_a, set_b, &mut writer);
-    writer.into()
-}
+    // Versioned sets
+    fn versioned_updates_correct(set: SortedSet<i32>, updates: Vec<u16>) -> bool {
+        let mut model: std::collections::BTreeSet<i32> = set.as_slice().iter().copied().collect();
+        let mut versioned = VersionedSet::new(set.as_slice().to_vec());
+        let toggled = toggle_items(&mut versioned, updates, set.as_slice(), |v, present|
+            present == model.contains(&v) && if present { model.remove(&v) } else { model.insert(v) });
+
+        toggled &&
+        versioned.to_sorted_set() == model.iter().copied().collect::<Vec<i32>>() &&
+        versioned.len() == model.len()
+    }
+
+    fn versioned_intersect_correct(
+        sets: SimilarSetPair<i32>,
+        updates_a: Vec<u16>,
+        updates_b: Vec<u16>) -> bool
+    {
+        versioned_matches(sets.0.as_slice(), sets.1.as_slice(), updates_a, updates_b,
+            intersect::branchless_merge, intersect::galloping)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn versioned_intersect_simd_correct(
+        sets: SimilarSetPair<i32>,
+        updates_a: Vec<u16>,
+        updates_b: Vec<u16>) -> bool
+    {
+        versioned_matches(sets.0.as_slice(), sets.1.as_slice(), updates_a, updates_b,
+            intersect::shuffling_sse, intersect::galloping_sse)
+    }
+
+    // Multisets
+    fn multiset_merge_correct(items_a: Vec<u16>, items_b: Vec<u16>) -> bool {
+        let (set_a, set_b) = (multiset(&items_a), multiset(&items_b));
+        let mut writer: VecWriter<i32> = VecWriter::new();
+        intersect::multiset_merge(&set_a, &set_b, &mut writer);
+        writer.as_ref() == multiset_reference(&set_a, &set_b)
+    }
+
+    fn multiset_layers_correct(items: Vec<u16>) -> bool {
+        let set = multiset(&items);
+        let layers = MultisetLayers::from_sorted(&set);
+        layers.to_sorted() == set &&
+        layers.len() == set.len() &&
+        layers.layers().iter().all(|layer| is_strictly_sorted(layer))
+    }
+
+    fn multiset_intersect_correct(items_a: Vec<u16>, items_b: Vec<u16>) -> bool {
+        let (set_a, set_b) = (multiset(&items_a), multiset(&items_b));
+        let expected = multiset_reference(&set_a, &set_b);
+        let (layers_a, layers_b) = (MultisetLayers::from_sorted(&set_a), MultisetLayers::from_sorted(&set_b));
+
+        let intersects: &[Intersect2<[i32], VecWriter<i32>>] = &[
+            intersect::branchless_merge,
+            intersect::galloping,
+            #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+            intersect::shuffling_sse,
+            #[cfg(all(feature = "simd", target_feature = "avx2"))]
+            intersect::shuffling_avx2,
+            #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+            intersect::shuffling_avx512,
+        ];
+        intersects.iter().all(|&intersect| {
+            let mut writer: VecWriter<i32> = VecWriter::new();
+            intersect::multiset_intersect(&layers_a, &layers_b, intersect, &mut writer);
+            writer.as_ref() == expected
+        })
+    }
+
+    // Instrumentation
+    fn instrument_visitor_correct(sets: SimilarSetPair<i32>) -> bool {
+        let (set_a, set_b) = (sets.0.as_slice(), sets.1.as_slice());
+        let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+
+        let intersects: &[Intersect2<[i32], InstrumentVisitor<VecWriter<i32>>>] = &[
+            intersect::naive_merge,
+            intersect::galloping,
+            #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+            intersect::shuffling_sse,
+            #[cfg(all(feature = "simd", target_feature = "avx2"))]
+            intersect::broadcast_avx2,
+            #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+            intersect::shuffling_avx512,
+        ];
+        intersects.iter().all(|&intersect| {
+            let mut visitor = InstrumentVisitor::new(VecWriter::new());
+            instrument::reset();
+            intersect(set_a, set_b, &mut visitor);
+            let work = instrument::take();
+
+            let counted = if instrument::ENABLED {
+                // Every element of the result is compared at least once.
+                work.comparisons >= expected.len() as u64
+            }
+            else {
+                work == Default::default()
+            };
+            visitor.as_ref() == expected && counted
+        })
+    }
+
+    // Fold visitor
+    fn fold_visitor_correct(sets: SimilarSetPair<i32>) -> bool {
+        let (set_a, set_b) = (sets.0.as_slice(), sets.1.as_slice());
+        fold_matches::<Xor>(set_a, set_b) &&
+        fold_matches::<And>(set_a, set_b) &&
+        fold_matches::<Sum>(set_a, set_b) &&
+        fold_matches::<Min>(set_a, set_b) &&
+        fold_matches::<Max>(set_a, set_b)
+    }
+
+    // Key-value intersection
+    fn payload_intersect_correct(sets: SimilarSetPair<i32>) -> bool {
+        payload_matches(sets.0.as_slice(), sets.1.as_slice())
+    }
+
+    fn payload_intersect_correct_skewed(sets: SkewedSetPair<i32>) -> bool {
+        payload_matches(sets.small.as_slice(), sets.large.as_slice())
+    }
+
+    fn weighted_intersect_correct(sets: SimilarSetPair<i32>) -> bool {
+        let (set_a, set_b) = (sets.0.as_slice(), sets.1.as_slice());
+        weighted_matches::<SumScore>(set_a, set_b, u32::wrapping_add) &&
+        weighted_matches::<ProductScore>(set_a, set_b, u32::wrapping_mul)
+    }
+
+    fn weighted_intersect_correct_skewed(sets: SkewedSetPair<i32>) -> bool {
+        let (set_a, set_b) = (sets.small.as_slice(), sets.large.as_slice());
+        weighted_matches::<SumScore>(set_a, set_b, u32::wrapping_add) &&
+        weighted_matches::<ProductScore>(set_a, set_b, u32::wrapping_mul)
+    }
+
+    // Estimation
+    fn sample_card_exact_when_saturated(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge).len();
+        let samples = sets.0.as_slice().len().max(sets.1.as_slice().len());
+        let actual = estimate::sample_intersection_card(
+            sets.0.as_slice(), sets.1.as_slice(), samples);
+        actual == expected as f64
+    }
+
+    fn hll_card_bounded(sets: SimilarSetPair<i32>) -> bool {
+        let estimate = estimate::intersection_card(
+            sets.0.as_slice(), sets.1.as_slice(), 10);
+        estimate >= 0.0 && estimate <= sets.0.as_slice().len().min(sets.1.as_slice().len()) as f64
+    }
+
+    // Compressed sets
+    fn bp128_roundtrip(sets: SkewedSetPair<i32>) -> bool {
+        let encoded = Bp128Set::from_sorted(sets.large.as_slice());
+        encoded.decode() == sets.large.as_slice()
+    }
+
+    fn bp128_dense_roundtrip(sets: SkewedSetPair<i16>) -> bool {
+        let large = widen(sets.large.as_slice());
+        let encoded = Bp128Set::from_sorted(&large);
+        encoded.decode() == large
+    }
+
+    fn bp128_decode_intersect_correct(
+        intersect: DualIntersectFn,
+        sets: SkewedSetPair<i16>) -> bool
+    {
+        let (small, large) = (widen(sets.small.as_slice()), widen(sets.large.as_slice()));
+        decode_intersect_correct::<Bp128Set>(&small, &large, intersect.1)
+    }
+
+    fn bp128_decode_intersect_similar_correct(
+        intersect: DualIntersectFn,
+        sets: SimilarSetPair<i32>) -> bool
+    {
+        decode_intersect_correct::<Bp128Set>(sets.0.as_slice(), sets.1.as_slice(), intersect.1)
+    }
+
+    fn svb_roundtrip(sets: SkewedSetPair<i32>) -> bool {
+        let encoded = SvbSet::from_sorted(sets.large.as_slice());
+        encoded.decode() == sets.large.as_slice()
+    }
+
+    fn svb_dense_roundtrip(sets: SkewedSetPair<i16>) -> bool {
+        let large = widen(sets.large.as_slice());
+        let encoded = SvbSet::from_sorted(&large);
+        encoded.decode() == large
+    }
+
+    fn svb_decode_intersect_correct(
+        intersect: DualIntersectFn,
+        sets: SkewedSetPair<i16>) -> bool
+    {
+        let (small, large) = (widen(sets.small.as_slice()), widen(sets.large.as_slice()));
+        decode_intersect_correct::<SvbSet>(&small, &large, intersect.1)
+    }
+
+    fn svb_probe_correct(sets: SkewedSetPair<i32>) -> bool {
+        svb_probe_matches(sets.small.as_slice(), sets.large.as_slice())
+    }
+
+    fn svb_probe_dense_correct(sets: SkewedSetPair<i16>) -> bool {
+        svb_probe_matches(&widen(sets.small.as_slice()), &widen(sets.large.as_slice()))
+    }
+
+    // Partitioned sets
+    fn partition_correct(
+        intersect: DualIntersectFn,
+        sets: SimilarSetPair<i32>,
+        bucket_len: usize) -> bool
+    {
+        partition_matches(sets.0.as_slice(), sets.1.as_slice(), bucket_len % 256, intersect.1)
+    }
+
+    fn partition_skewed_correct(
+        intersect: DualIntersectFn,
+        sets: SkewedSetPair<i16>,
+        shift: u8) -> bool
+    {
+        let (small, large) = (widen(sets.small.as_slice()), widen(sets.large.as_slice()));
+        let shift = shift as u32 % 17;
+
+        let expected = intersect::run_2set(&small, &large, intersect::naive_merge);
+        let partitioned_small = PartitionedSet::from_sorted(&small, shift);
+        let partitioned_large = PartitionedSet::from_sorted(&large, shift);
+
+        let mut writer = VecWriter::new();
+        partition::partition_intersect(
+            &partitioned_small, &partitioned_large, intersect.1, &mut writer);
+        let actual: Vec<i32> = writer.into();
+
+        partitioned_large.as_slice() == large && actual == expected
+    }
+
+    // Flipping the sign bit keeps order and gives i32 sets either side of 0.
+    fn partition_sign_boundary_correct(sets: SignBoundaryPair) -> bool {
+        let flip = |set: &[u32]| -> Vec<i32> {
+            set.iter().map(|&v| (v ^ (1 << 31)) as i32).collect()
+        };
+        partition_matches(
+            &flip(sets.0.as_slice()), &flip(sets.1.as_slice()), 4, intersect::branchless_merge)
+    }
+
+    // BSR algorithms
+    fn bsr_roundtrip(set: BsrSet) -> bool {
+        set.to_bsr().to_sorted_set() == set.0.as_slice()
+    }
+
+    fn bsr_matches_merge(intersect: BsrIntersectFn, sets: BsrSetPair) -> bool {
+        bsr_matches_sorted(&sets.0, &sets.1, intersect.1)
+    }
+
+    fn bsr_skewed_matches_merge(intersect: BsrIntersectFn, sets: SkewedBsrPair) -> bool {
+        bsr_matches_sorted(&sets.small, &sets.large, intersect.1) &&
+        bsr_matches_sorted(&sets.large, &sets.small, intersect.1)
+    }
+
+    fn bsr_self_intersection(intersect: BsrIntersectFn, set: BsrSet) -> bool {
+        bsr_matches_sorted(&set, &set, intersect.1)
+    }
+
+    // BSR expansion
+    fn bsr_expand_merge_correct(sets: SimilarSetPair<u32>) -> bool {
+        bsr_expand_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::branchless_merge_bsr)
+    }
+
+    fn bsr_expand_galloping_correct(sets: SkewedSetPair<u32>) -> bool {
+        bsr_expand_matches(sets.small.as_slice(), sets.large.as_slice(), intersect::galloping_bsr)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn bsr_expand_shuffling_sse_correct(sets: SimilarSetPair<u32>) -> bool {
+        bsr_expand_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_sse_bsr)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn bsr_expand_broadcast_avx2_correct(sets: SimilarSetPair<u32>) -> bool {
+        bsr_expand_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::broadcast_avx2_bsr)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    fn bsr_expand_shuffling_avx512_correct(sets: SimilarSetPair<u32>) -> bool {
+        bsr_expand_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_avx512_bsr)
+    }
+
+    fn bsr_expand_sign_boundary_correct(sets: SignBoundaryPair) -> bool {
+        bsr_expand_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::branchless_merge_bsr)
+    }
+
+    // Hybrid sets
+    fn hybrid_roundtrip(set: SortedSet<i32>, threshold: u16) -> bool {
+        let hybrid = HybridSet::from_sorted_with_threshold(set.as_slice(), threshold as usize);
+        hybrid.to_sorted_set() == set.as_slice() && hybrid.len() == set.as_slice().len()
+    }
+
+    fn hybrid_correct(
+        intersect: DualIntersectFn,
+        sets: SimilarSetPair<i32>,
+        threshold: u8) -> bool
+    {
+        hybrid_matches(sets.0.as_slice(), sets.1.as_slice(), threshold as usize, intersect.1)
+    }
+
+    // Dense sets span few chunks, mixing bitmaps and arrays.
+    fn hybrid_dense_correct(
+        intersect: DualIntersectFn,
+        sets: SkewedSetPair<i16>,
+        threshold: u8) -> bool
+    {
+        let (small, large) = (widen(sets.small.as_slice()), widen(sets.large.as_slice()));
+        hybrid_matches(&small, &large, threshold as usize, intersect.1)
+    }
+
+    // Algorithm traits
+    fn algorithm_2set_correct(sets: SimilarSetPair<i32>, threshold: u8) -> bool {
+        let (set_a, set_b) = (sets.0.as_slice(), sets.1.as_slice());
+        let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+
+        let hybrid = Hybrid {
+            bitmap_threshold: threshold as usize,
+            intersect: intersect::branchless_merge,
+        };
+        let decode = DecodeIntersect::<SvbSet, _>::new(intersect::branchless_merge);
+
+        algorithm::run_2set(set_a, set_b, &Sorted2(intersect::galloping)) == expected &&
+        algorithm::run_2set(set_a, set_b, &hybrid) == expected &&
+        algorithm::run_2set(set_a, set_b, &decode) == expected
+    }
+
+    fn algorithm_kset_correct(sets: SetCollection<i32>) -> bool {
+        let vecs: Vec<Vec<i32>> = sets.as_slice().iter().map(|s| s.as_slice().to_vec()).collect();
+        let result = algorithm::run_kset(&vecs, &SortedK(intersect::svs));
+        prop_intersection_correct(result, sets.as_slice())
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn algorithm_fesia_correct(sets: SimilarSetPair<i32>, others: SetCollection<i32>) -> bool {
+        let (set_a, set_b) = (sets.0.as_slice(), sets.1.as_slice());
+        let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+
+        let fesia = algorithm::FesiaAlgorithm::<Fesia32Sse, SegmentIntersectSse>::new(2.0);
+        let mut actual = algorithm::run_2set(set_a, set_b, &fesia);
+        actual.sort();
+        let fesia_hash = algorithm::FesiaHashAlgorithm::<Fesia32Sse>::new(2.0);
+        let mut actual_hash = algorithm::run_2set(set_a, set_b, &fesia_hash);
+        actual_hash.sort();
+        let others: Vec<Vec<i32>> = others.as_slice().iter().map(|s| s.as_slice().to_vec()).collect();
+        let mut actual_k = algorithm::run_kset(&others, &fesia);
+        actual_k.sort();
+
+        actual == expected && actual_hash == expected &&
+        prop_intersection_correct(actual_k, others.as_slice())
+    }
+
+    // Bitmaps
+    fn bitmap_roundtrip(set: SortedSet<u16>, extra: u16) -> bool {
+        let set: Vec<u32> = set.as_slice().iter().map(|&i| i as u32).collect();
+        let universe = set.last().map_or(0, |&i| i as usize + 1) + extra as usize;
+
+        let bitmap = bitmap::from_sorted(&set, universe);
+        let bsr = BsrVec::from_sorted(&set);
+
+        bitmap.len() == bitmap::words(universe) &&
+        bitmap::to_sorted_set(&bitmap) == set &&
+        bitmap::len(&bitmap) == set.len() &&
+        bsr.to_bitmap(universe) == bitmap &&
+        BsrVec::from_bitmap(&bitmap) == bsr
+    }
+
+    fn bitmap_and_correct(sets: SimilarSetPair<u16>) -> bool {
+        let widen = |set: &[u16]| -> Vec<u32> { set.iter().map(|&i| i as u32).collect() };
+        let (set_a, set_b) = (widen(sets.0.as_slice()), widen(sets.1.as_slice()));
+        let universe = |set: &[u32]| set.last().map_or(0, |&i| i as usize + 1);
+
+        // Bitmaps of different lengths.
+        let bitmap_a = bitmap::from_sorted(&set_a, universe(&set_a));
+        let bitmap_b = bitmap::from_sorted(&set_b, universe(&set_b));
+        let expected = simple::intersect(&set_a, &set_b);
+
+        let mut result = Vec::new();
+        bitmap::and_into(&bitmap_a, &bitmap_b, &mut result);
+
+        let mut inplace = bitmap_a.clone();
+        bitmap::and_inplace(&mut inplace, &bitmap_b);
+
+        bitmap::to_sorted_set(&result) == expected &&
+        inplace == result &&
+        bitmap::and_len(&bitmap_a, &bitmap_b) == expected.len()
+    }
+
+    // EWAH bitmaps
+    fn ewah_roundtrip(set: SortedSet<u16>, runs: Vec<(u16, u8)>) -> bool {
+        let set = clustered(set.as_slice(), &runs);
+        let ewah = EwahBitmap::from_sorted(&set);
+        let universe = set.last().map_or(0, |&i| i as usize + 1);
+        let bitmap = bitmap::from_sorted(&set, universe);
+
+        ewah.to_sorted_set() == set &&
+        ewah.len() == set.len() &&
+        ewah.word_len() == bitmap.len() &&
+        ewah.to_bitmap() == bitmap &&
+        EwahBitmap::from_bitmap(&bitmap) == ewah
+    }
+
+    fn ewah_and_correct(set_a: SortedSet<u16>, set_b: SortedSet<u16>, runs_a: Vec<(u16, u8)>, runs_b: Vec<(u16, u8)>) -> bool {
+        let set_a = clustered(set_a.as_slice(), &runs_a);
+        let set_b = clustered(set_b.as_slice(), &runs_b);
+        let (ewah_a, ewah_b) = (EwahBitmap::from_sorted(&set_a), EwahBitmap::from_sorted(&set_b));
+        let expected = simple::intersect(&set_a, &set_b);
+
+        let result = ewah_a.and(&ewah_b);
+        let mut into = ewah_b.clone();
+        ewah_b.and_into(&ewah_a, &mut into);
+
+        result.to_sorted_set() == expected &&
+        into.to_sorted_set() == expected &&
+        ewah_a.and_len(&ewah_b) == expected.len()
+    }
+
+    // Validating set construction
+    fn check_strictly_sorted_correct(items: Vec<i32>, sort: bool) -> bool {
+        let mut items = items;
+        if sort {
+            // Sorted, but likely with duplicates.
+            items.iter_mut().for_each(|i| *i %= 64);
+            items.sort();
+        }
+        let expected = check_strictly_sorted(&items);
+
+        let located = match expected {
+            Ok(()) => is_strictly_sorted(&items),
+            Err(SortError::Unsorted { index }) =>
+                is_strictly_sorted(&items[..=index]) && items[index] > items[index + 1],
+            Err(SortError::Duplicate { index }) =>
+                is_strictly_sorted(&items[..=index]) && items[index] == items[index + 1],
+        };
+        located && simd_checks_match(&items, expected)
+    }
+
+    fn check_strictly_sorted_u32_correct(sets: SignBoundaryPair) -> bool {
+        let mut items = sets.0.as_slice().to_vec();
+        items.extend_from_slice(sets.1.as_slice());
+        let expected = check_strictly_sorted(&items);
+        simd_checks_match(&items, expected) &&
+        simd_checks_match(sets.0.as_slice(), Ok(()))
+    }
+
+    fn from_unsorted_correct(items: Vec<u16>) -> bool {
+        let items: Vec<u32> = items.iter().map(|&i| i as u32).collect();
+        let mut sorted = items.clone();
+        sorted.sort();
+        sorted.dedup();
+
+        Vec::from_unsorted(&items) == sorted &&
+        BsrVec::from_unsorted(&items) == BsrVec::from_sorted(&sorted) &&
+        Vec::try_from_sorted(&sorted).as_ref() == Ok(&sorted) &&
+        BsrVec::try_from_sorted(&items).is_ok() == is_strictly_sorted(&items)
+    }
+
+    // Simple interface
+    fn simple_similar_correct(sets: SimilarSetPair<i32>) -> bool {
+        simple_matches(sets.0.as_slice(), sets.1.as_slice())
+    }
+
+    fn simple_skewed_correct(sets: SkewedSetPair<i32>) -> bool {
+        simple_matches(sets.small.as_slice(), sets.large.as_slice()) &&
+        simple_matches(sets.large.as_slice(), sets.small.as_slice())
+    }
+
+    fn simple_u32_correct(sets: SignBoundaryPair) -> bool {
+        simple_matches(sets.0.as_slice(), sets.1.as_slice())
+    }
+
+    fn simple_64_bit_correct(sets: SimilarSetPair<i64>, skewed: SkewedSetPair<u64>) -> bool {
+        simple_matches(sets.0.as_slice(), sets.1.as_slice()) &&
+        simple_matches(skewed.small.as_slice(), skewed.large.as_slice())
+    }
+
+    fn simple_first_k_correct(sets: SimilarSetPair<i32>, skewed: SkewedSetPair<u32>, k: u8) -> bool {
+        first_k_matches(sets.0.as_slice(), sets.1.as_slice(), k as usize) &&
+        first_k_matches(skewed.small.as_slice(), skewed.large.as_slice(), k as usize) &&
+        first_k_matches(skewed.large.as_slice(), skewed.small.as_slice(), k as usize)
+    }
+
+    fn simple_first_k_64_bit_correct(sets: SimilarSetPair<i64>, k: u8) -> bool {
+        first_k_matches(sets.0.as_slice(), sets.1.as_slice(), k as usize)
+    }
+
+    fn simple_subset_correct(sets: SimilarSetPair<i32>, skewed: SkewedSetPair<u64>) -> bool {
+        simple_subset_matches(sets.0.as_slice()) &&
+        simple_subset_matches(skewed.large.as_slice())
+    }
+
+    // Algorithms by name
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn by_name_correct(sets: SimilarSetPair<i32>) -> bool {
+        let (set_a, set_b) = (sets.0.as_slice(), sets.1.as_slice());
+        let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+
+        intersect::dynamic::names().into_iter().all(|name| {
+            let algorithm = intersect::by_name(name).unwrap();
+            algorithm.name() == name &&
+            algorithm.intersect(set_a, set_b) == expected &&
+            algorithm.count(set_a, set_b) == expected.len()
+        })
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn two_set_algo_correct(sets: SimilarSetPair<i32>) -> bool {
+        let (set_a, set_b) = (sets.0.as_slice(), sets.1.as_slice());
+        let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+
+        intersect::TwoSetAlgo::ALL.iter().all(|&algo| {
+            let mut visitor: VecWriter<i32> = VecWriter::new();
+            algo.run(set_a, set_b, &mut visitor);
+            intersect::TwoSetAlgo::from_name(algo.name()) == Some(algo) &&
+            Vec::from(visitor) == expected
+        })
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn kset_algo_correct(sets: SetCollection<i32>) -> bool {
+        intersect::KSetAlgo::ALL.iter().all(|&algo| {
+            let mut visitor: VecWriter<i32> = VecWriter::new();
+            algo.run(sets.as_slice(), &mut visitor);
+            intersect::KSetAlgo::from_name(algo.name()) == Some(algo) &&
+            prop_intersection_correct(visitor.into(), sets.as_slice())
+        })
+    }
+
+    // Limit
+    fn limit_merge_correct(sets: SimilarSetPair<i32>, limit: u8, block_len: u8) -> bool {
+        limit_matches(sets.0.as_slice(), sets.1.as_slice(), limit, block_len,
+            intersect::branchless_merge)
+    }
+
+    fn limit_galloping_correct(sets: SkewedSetPair<i32>, limit: u8, block_len: u8) -> bool {
+        limit_matches(sets.small.as_slice(), sets.large.as_slice(), limit, block_len,
+            intersect::galloping)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn limit_shuffling_sse_correct(sets: SimilarSetPair<i32>, limit: u8, block_len: u8) -> bool {
+        limit_matches(sets.0.as_slice(), sets.1.as_slice(), limit, block_len,
+            intersect::shuffling_sse)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn limit_broadcast_avx2_correct(sets: SimilarSetPair<i32>, limit: u8, block_len: u8) -> bool {
+        limit_matches(sets.0.as_slice(), sets.1.as_slice(), limit, block_len,
+            intersect::broadcast_avx2)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    fn limit_shuffling_avx512_correct(
+        sets: SimilarSetPair<i32>, limit: u8, block_len: u8) -> bool
+    {
+        limit_matches(sets.0.as_slice(), sets.1.as_slice(), limit, block_len,
+            intersect::shuffling_avx512)
+    }
+
+    fn limit_kset_correct(sets: SetCollection<i32>, limit: u8, block_len: u8) -> bool {
+        let expected = intersect::run_kset(sets.as_slice(), intersect::small_adaptive);
+
+        let mut visitor = LimitVisitor::new(VecWriter::new(), limit as usize);
+        intersect::limit::limit_kset_blocks(
+            sets.as_slice(), intersect::small_adaptive, &mut visitor, block_len as usize + 1);
+        let actual: Vec<i32> = visitor.into_inner().into();
+
+        actual == expected[..expected.len().min(limit as usize)]
+    }
+
+    // Range
+    fn range_merge_correct(sets: SimilarSetPair<i32>, start: i32, end: i32) -> bool {
+        range_matches(sets.0.as_slice(), sets.1.as_slice(), start, end, intersect::branchless_merge)
+    }
+
+    fn range_galloping_correct(sets: SkewedSetPair<i32>, start: i32, end: i32) -> bool {
+        range_matches(sets.small.as_slice(), sets.large.as_slice(), start, end, intersect::galloping)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn range_shuffling_avx2_correct(sets: SimilarSetPair<i32>, start: i32, end: i32) -> bool {
+        range_matches(sets.0.as_slice(), sets.1.as_slice(), start, end, intersect::shuffling_avx2)
+    }
+
+    fn lower_bound_correct(set: SortedSet<i32>, target: i32) -> bool {
+        let set = set.as_slice();
+        let expected = set.partition_point(|&value| value < target);
+        intersect::range::lower_bound(set, target) == expected &&
+        set.iter().enumerate().all(|(i, &value)| intersect::range::lower_bound(set, value) == i)
+    }
+
+    // In-place
+    fn merge_inplace_correct(sets: SimilarSetPair<i32>) -> bool {
+        inplace_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::merge_inplace)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn shuffling_sse_inplace_correct(sets: SimilarSetPair<u32>) -> bool {
+        inplace_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_sse_inplace)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn shuffling_avx2_inplace_correct(sets: SimilarSetPair<i32>) -> bool {
+        inplace_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_avx2_inplace)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    fn shuffling_avx512_inplace_correct(sets: SimilarSetPair<i32>) -> bool {
+        inplace_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_avx512_inplace)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn shuffling_inplace_skewed_correct(sets: SkewedSetPair<i32>) -> bool {
+        inplace_matches(sets.small.as_slice(), sets.large.as_slice(), intersect::shuffling_sse_inplace) &&
+        inplace_matches(sets.large.as_slice(), sets.small.as_slice(), intersect::shuffling_avx2_inplace)
+    }
+
+    // Prefetch
+    fn merge_prefetch_correct(sets: SimilarSetPair<i32>, distance: u16) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+
+        let mut writer = VecWriter::new();
+        intersect::merge_prefetch(sets.0.as_slice(), sets.1.as_slice(), distance as usize, &mut writer);
+        let actual: Vec<i32> = writer.into();
+
+        actual == expected
+    }
+
+    fn galloping_prefetch_correct(sets: SkewedSetPair<i32>, distance: u16) -> bool {
+        let expected = intersect::run_2set(
+            sets.small.as_slice(), sets.large.as_slice(), intersect::naive_merge);
+
+        let algorithm = GallopingPrefetch { distance: distance as usize };
+        let actual = algorithm::run_2set(sets.large.as_slice(), sets.small.as_slice(), &algorithm);
+
+        actual == expected
+    }
+
+    // Galloping growth factor
+    fn galloping_factor_correct(sets: SkewedSetPair<i32>, factor: u8) -> bool {
+        let expected = intersect::run_2set(
+            sets.small.as_slice(), sets.large.as_slice(), intersect::naive_merge);
+
+        let algorithm = GallopingFactor { factor: factor as usize % 31 + 2 };
+        let actual = algorithm::run_2set(sets.large.as_slice(), sets.small.as_slice(), &algorithm);
+
+        actual == expected
+    }
+
+    // Cursor
+    fn cursor_merge_correct(sets: SimilarSetPair<i32>, limit: u8) -> bool {
+        let cursor = IntersectCursor::merge(sets.0.as_slice(), sets.1.as_slice());
+        cursor_correct(cursor, limit as usize + 1, sets.0.as_slice(), sets.1.as_slice())
+    }
+
+    fn cursor_galloping_correct(sets: SkewedSetPair<i32>, limit: u8) -> bool {
+        let cursor = IntersectCursor::galloping(sets.small.as_slice(), sets.large.as_slice());
+        cursor_correct(cursor, limit as usize + 1, sets.small.as_slice(), sets.large.as_slice())
+    }
+
+    // Probe cache
+    fn galloping_cached_correct(sets: SkewedSetPair<i32>, shift: u8) -> bool {
+        let (small, large) = (sets.small.as_slice(), sets.large.as_slice());
+        let expected = intersect::run_2set(small, large, intersect::naive_merge);
+
+        let cache = ProbeCache::with_shift(large, shift as u32 % 8);
+        let mut writer = VecWriter::new();
+        intersect::galloping_cached(small, large, &cache, &mut writer);
+        let actual: Vec<i32> = writer.into();
+
+        let algorithm = CachedGalloping { shift: shift as u32 % 8 };
+        actual == expected &&
+        algorithm::run_2set(small, large, &algorithm) == expected &&
+        algorithm::run_2set(large, small, &algorithm) == expected
+    }
+
+    fn galloping_cached_shared(sets: SetCollection<i32>) -> bool {
+        let (large, queries) = sets.as_slice().split_first().unwrap();
+        let cache = ProbeCache::new(large.as_slice());
+        queries.iter().all(|query| {
+            let mut writer = VecWriter::new();
+            intersect::galloping_cached(query.as_slice(), large.as_slice(), &cache, &mut writer);
+            let actual: Vec<i32> = writer.into();
+            actual == intersect::run_2set(query.as_slice(), large.as_slice(), intersect::naive_merge)
+        })
+    }
+
+    // Skip pointers
+    fn galloping_skip_correct(sets: SkewedSetPair<i32>, interval: u8) -> bool {
+        let (small, large) = (sets.small.as_slice(), sets.large.as_slice());
+        let expected = intersect::run_2set(small, large, intersect::naive_merge);
+
+        let interval = interval as usize % 300 + 2;
+        let skip_set = SkipSet::with_interval(large, interval);
+        let mut writer = VecWriter::new();
+        intersect::galloping_skip(small, &skip_set, &mut writer);
+        let actual: Vec<i32> = writer.into();
+
+        let algorithm = SkipGalloping { interval };
+        actual == expected &&
+        algorithm::run_2set(small, large, &algorithm) == expected &&
+        algorithm::run_2set(large, small, &algorithm) == expected
+    }
+
+    // Batch probes
+    fn intersect_batch_correct(sets: SetCollection<i32>, interval: u8) -> bool {
+        let (large, queries) = sets.as_slice().split_first().unwrap();
+        let large = large.as_slice();
+        let smalls: Vec<&[i32]> = queries.iter().map(|q| q.as_slice()).collect();
+        let expected: Vec<Vec<i32>> = smalls.iter()
+            .map(|&small| intersect::run_2set(small, large, intersect::naive_merge))
+            .collect();
+
+        let batch = |prepared: &dyn Fn(&mut [VecWriter<i32>])| {
+            let mut writers: Vec<VecWriter<i32>> = smalls.iter().map(|_| VecWriter::new()).collect();
+            prepared(&mut writers);
+            writers.into_iter().map(Vec::from).collect::<Vec<Vec<i32>>>()
+        };
+
+        let skip_set = SkipSet::with_interval(large, interval as usize % 300 + 2);
+        let cached = CachedSet {
+            elements: large,
+            cache: ProbeCache::with_shift(large, interval as u32 % 8),
+        };
+        batch(&|writers| skip_set.intersect_batch(&smalls, writers)) == expected &&
+        batch(&|writers| cached.intersect_batch(&smalls, writers)) == expected
+    }
+
+    // Blocked Bloom filter
+    fn bloom_no_false_negatives(set: SortedSet<i32>, bits_per_element: u8) -> bool {
+        let set = set.as_slice();
+        let filter = BlockedBloom::with_bits_per_element(set, bits_per_element as usize % 32);
+        set.iter().all(|&value| filter.contains(value))
+    }
+
+    fn bloom_galloping_correct(sets: SkewedSetPair<i32>, bits_per_element: u8) -> bool {
+        let (small, large) = (sets.small.as_slice(), sets.large.as_slice());
+        let filter = BlockedBloom::with_bits_per_element(large, bits_per_element as usize % 32);
+
+        let expected = intersect::run_2set(small, large, intersect::naive_merge);
+        let mut writer: VecWriter<i32> = VecWriter::new();
+        intersect::bloom_galloping(small, large, &filter, &mut writer);
+        writer.as_ref() == expected.as_slice()
+    }
+
+    // Eytzinger layout
+    fn eytzinger_layout_correct(set: SortedSet<i32>, target: i32) -> bool {
+        let set = set.as_slice();
+        let layout = EytzingerSet::from_sorted(set);
+
+        let rank = set.partition_point(|&value| value < target);
+        let node = layout.lower_bound(target);
+        let lower_bound_correct = match set.get(rank) {
+            Some(&value) => node != 0 && layout.layout()[node] == value,
+            None => node == 0,
+        };
+
+        layout.to_sorted() == set &&
+        set.iter().all(|&value| layout.contains(value)) &&
+        layout.contains(target) == set.contains(&target) &&
+        lower_bound_correct
+    }
+
+    fn eytzinger_probe_correct(sets: SkewedSetPair<i32>) -> bool {
+        eytzinger_matches(sets.small.as_slice(), sets.large.as_slice())
+    }
+
+    fn eytzinger_probe_similar_correct(sets: SimilarSetPair<i32>) -> bool {
+        eytzinger_matches(sets.0.as_slice(), sets.1.as_slice())
+    }
+
+    fn eytzinger_probe_sign_boundary_correct(sets: SignBoundaryPair) -> bool {
+        eytzinger_matches(sets.0.as_slice(), sets.1.as_slice())
+    }
+
+    // Unsafe writer
+    #[cfg(feature = "simd")]
+    fn unsafe_writer_sse_correct(set_a: SortedSet<i32>, set_b: SortedSet<i32>) -> bool {
+        let result = run_unsafe_writer(
+            set_a.as_slice(), set_b.as_slice(), intersect::shuffling_sse);
+        prop_intersection_correct(result, &[set_a.as_slice(), set_b.as_slice()])
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn unsafe_writer_avx2_correct(set_a: SortedSet<i32>, set_b: SortedSet<i32>) -> bool {
+        let result = run_unsafe_writer(
+            set_a.as_slice(), set_b.as_slice(), intersect::shuffling_avx2);
+        prop_intersection_correct(result, &[set_a.as_slice(), set_b.as_slice()])
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    fn unsafe_writer_avx512_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = run_unsafe_writer(
+            sets.0.as_slice(),
+            sets.1.as_slice(),
+            intersect::naive_merge);
+
+        let actual = run_unsafe_writer(
+            sets.0.as_slice(),
+            sets.1.as_slice(),
+            intersect::shuffling_avx512);
+
+        actual == expected
+    }
+
+    // Reserve writer, starting empty so every vector store must grow it.
+    #[cfg(feature = "simd")]
+    fn reserve_writer_sse_correct(set_a: SortedSet<i32>, set_b: SortedSet<i32>) -> bool {
+        let result = run_reserve_writer(
+            set_a.as_slice(), set_b.as_slice(), intersect::shuffling_sse);
+        prop_intersection_correct(result, &[set_a.as_slice(), set_b.as_slice()])
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn reserve_writer_avx2_correct(set_a: SortedSet<i32>, set_b: SortedSet<i32>) -> bool {
+        let result = run_reserve_writer(
+            set_a.as_slice(), set_b.as_slice(), intersect::shuffling_avx2);
+        prop_intersection_correct(result, &[set_a.as_slice(), set_b.as_slice()])
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    fn reserve_writer_avx512_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+        let actual = run_reserve_writer(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_avx512);
+
+        actual == expected
+    }
+
+    // Streaming writer, staging results into cache lines streamed on filling.
+    fn streaming_writer_scalar_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+        let actual = run_streaming_writer(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::branchless_merge);
+
+        actual == expected
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn streaming_writer_sse_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+        let actual = run_streaming_writer(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_sse);
+
+        actual == expected
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    fn streaming_writer_avx2_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+        let actual = run_streaming_writer(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_avx2);
+
+        actual == expected
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    fn streaming_writer_avx512_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+        let actual = run_streaming_writer(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_avx512);
+
+        actual == expected
+    }
+    // Lookup writer, taking the shuffle table path even with AVX-512.
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn unsafe_lookup_writer_sse_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+        let actual = run_lookup_writer(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_sse);
+
+        actual == expected
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    fn unsafe_lookup_writer_avx512_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+        let actual = run_lookup_writer(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_avx512);
+
+        actual == expected
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    fn unsafe_compress_writer_avx512_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+
+        let mut writer = setops::visitor::UnsafeCompressWriter::with_capacity(
+            sets.0.as_slice().len().min(sets.1.as_slice().len()));
+        intersect::shuffling_avx512(sets.0.as_slice(), sets.1.as_slice(), &mut writer);
+        let actual: Vec<i32> = writer.into();
+
+        actual == expected
+    }
+
+    // Mmap writer, starting from a small mapping so it is remapped.
+    fn mmap_writer_scalar_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+        mmap_writer_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::branchless_merge, &expected)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn mmap_writer_simd_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+        mmap_writer_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_sse, &expected) &&
+        mmap_writer_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::broadcast_sse, &expected)
+    }
+
+    // Hinted writer, unhinted, under-hinted and hinted with the bound.
+    fn hinted_writer_scalar_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+        hinted_writer_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::branchless_merge, &expected)
+    }
+
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    fn hinted_writer_simd_correct(sets: SimilarSetPair<i32>) -> bool {
+        let expected = intersect::run_2set(
+            sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+        hinted_writer_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::shuffling_sse, &expected) &&
+        hinted_writer_matches(sets.0.as_slice(), sets.1.as_slice(), intersect::galloping_sse, &expected)
+    }
+}
+
+// Scalar and vector visits of every width give the fold of the sorted result.
+fn fold_matches<F>(set_a: &[i32], set_b: &[i32]) -> bool
+where
+    F: Fold,
+    F::Acc: PartialEq,
+{
+    let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge)
+        .into_iter()
+        .fold(F::IDENTITY, F::fold);
+
+    let algorithms: &[Intersect2<[i32], FoldVisitor<F>>] = &[
+        intersect::branchless_merge,
+        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+        intersect::shuffling_sse,
+        #[cfg(all(feature = "simd", target_feature = "avx2"))]
+        intersect::shuffling_avx2,
+        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+        intersect::shuffling_avx512,
+    ];
+    algorithms.iter().all(|intersect| {
+        let mut visitor = FoldVisitor::<F>::new();
+        intersect(set_a, set_b, &mut visitor);
+        visitor.value() == expected
+    })
+}
+
+type PayloadIntersect = fn(&[i32], &[u64], &[i32], &[u16], &mut PayloadWriter<i32, u64, u16>);
+
+// Payloads of different widths, derived from the key so they can be checked.
+fn payload_matches(set_a: &[i32], set_b: &[i32]) -> bool {
+    let payload_a: Vec<u64> = set_a.iter().map(|&k| k as u64 ^ 0xdead_beef_0000).collect();
+    let payload_b: Vec<u16> = set_b.iter().map(|&k| k as u16 ^ 0x5a5a).collect();
+
+    let expected: Vec<(i32, u64, u16)> = intersect::run_2set(set_a, set_b, intersect::naive_merge)
+        .into_iter()
+        .map(|k| (k, k as u64 ^ 0xdead_beef_0000, k as u16 ^ 0x5a5a))
+        .collect();
+
+    let algorithms: &[PayloadIntersect] = &[
+        intersect::payload_merge,
+        intersect::payload_galloping,
+        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+        intersect::payload_shuffling_sse,
+        #[cfg(all(feature = "simd", target_feature = "avx2"))]
+        intersect::payload_shuffling_avx2,
+        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+        intersect::payload_shuffling_avx512,
+    ];
+    algorithms.iter().all(|intersect| {
+        let mut writer = PayloadWriter::new();
+        intersect(set_a, &payload_a, set_b, &payload_b, &mut writer);
+        writer.as_ref() == expected.as_slice()
+    })
+}
+
+type WeightedJoin<C> = weighted::PayloadJoin<i32, u32, u32, ScoreAdapter<C, ScoreWriter<i32, u32>>>;
+
+// Weights derived from the key, so large products wrap.
+fn weighted_matches<C: Combine>(set_a: &[i32], set_b: &[i32], combine: fn(u32, u32) -> u32) -> bool {
+    let weight_a = |k: i32| (k as u32).wrapping_mul(0x9E37_79B1);
+    let weight_b = |k: i32| k as u32 ^ 0x5a5a;
+    let weights_a: Vec<u32> = set_a.iter().map(|&k| weight_a(k)).collect();
+    let weights_b: Vec<u32> = set_b.iter().map(|&k| weight_b(k)).collect();
+
+    let expected: Vec<(i32, u32)> = intersect::run_2set(set_a, set_b, intersect::naive_merge)
+        .into_iter()
+        .map(|k| (k, combine(weight_a(k), weight_b(k))))
+        .collect();
+
+    let joins: &[WeightedJoin<C>] = &[
+        intersect::payload_merge,
+        intersect::payload_galloping,
+        #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+        intersect::payload_shuffling_sse,
+        #[cfg(all(feature = "simd", target_feature = "avx2"))]
+        intersect::payload_shuffling_avx2,
+        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+        intersect::payload_shuffling_avx512,
+    ];
+    joins.iter().all(|&join| {
+        let writer = intersect::weighted_intersect(
+            set_a, &weights_a, set_b, &weights_b, join, ScoreWriter::new());
+        writer.as_ref() == expected.as_slice()
+    })
+}
+
+fn sign_boundary_correct(
+    sets: &SignBoundaryPair,
+    intersect: Intersect2<[u32], VecWriter<u32>>) -> bool
+{
+    let expected = intersect::run_2set(
+        sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+    let actual = intersect::run_2set(
+        sets.0.as_slice(), sets.1.as_slice(), intersect);
+    actual == expected
+}
+
+#[cfg(feature = "simd")]
+fn fesia_u32_correct<S, I>(sets: &SignBoundaryPair, hash_scale: HashScale) -> bool
+where
+    S: SetWithHashScale + FesiaIntersect,
+    I: SegmentIntersect,
+{
+    let expected = intersect::run_2set(
+        sets.0.as_slice(), sets.1.as_slice(), intersect::naive_merge);
+
+    let set_a = S::from_sorted_elements(sets.0.as_slice(), hash_scale);
+    let set_b = S::from_sorted_elements(sets.1.as_slice(), hash_scale);
+
+    let mut visitor: VecWriter<u32> = VecWriter::new();
+    set_a.intersect::<VecWriter<u32>, I>(&set_b, &mut visitor);
+    let mut actual: Vec<u32> = visitor.into();
+    actual.sort();
+
+    let mut visitor: VecWriter<u32> = VecWriter::new();
+    set_a.hash_intersect(&set_b, &mut visitor);
+    let mut actual_hash: Vec<u32> = visitor.into();
+    actual_hash.sort();
+
+    actual == expected && actual_hash == expected
+}
+
+fn partition_matches(
+    set_a: &[i32],
+    set_b: &[i32],
+    bucket_len: usize,
+    intersect: Intersect2<[i32], VecWriter<i32>>) -> bool
+{
+    let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+
+    let (partitioned_a, partitioned_b) = PartitionedSet::pair(set_a, set_b, bucket_len);
+
+    let mut writer = VecWriter::new();
+    partition::partition_intersect(&partitioned_a, &partitioned_b, intersect, &mut writer);
+    let actual: Vec<i32> = writer.into();
+
+    actual == expected
+}
+
+// Intersecting in BSR gives the BSR encoding of the sorted intersection.
+fn bsr_matches_sorted(set_a: &BsrSet, set_b: &BsrSet, intersect: BsrIntersect) -> bool {
+    let expected = intersect::run_2set(
+        set_a.0.as_slice(), set_b.0.as_slice(), intersect::branchless_merge);
+
+    let (bsr_a, bsr_b) = (set_a.to_bsr(), set_b.to_bsr());
+    let actual = intersect::run_2set_bsr(bsr_a.bsr_ref(), bsr_b.bsr_ref(), intersect);
+
+    actual.to_sorted_set() == expected && actual == BsrVec::from_sorted(&expected)
+}
+
+fn simple_matches<T: simple::Element>(set_a: &[T], set_b: &[T]) -> bool {
+    let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+
+    let mut union: Vec<T> = set_a.iter().chain(set_b).copied().collect();
+    union.sort();
+    union.dedup();
+
+    simple::intersect(set_a, set_b) == expected &&
+    simple::count(set_a, set_b) == expected.len() &&
+    simple::union(set_a, set_b) == union &&
+    simple::intersects(set_a, set_b) != expected.is_empty() &&
+    simple::is_subset(set_a, set_b) == (expected.len() == set_a.len()) &&
+    simple::is_subset(set_b, set_a) == (expected.len() == set_b.len())
+}
+
+fn first_k_matches<T: simple::Element>(set_a: &[T], set_b: &[T], k: usize) -> bool {
+    let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+    let expected = &expected[..k.min(expected.len())];
+
+    // Results are appended after existing elements.
+    let mut out: Vec<T> = set_a.iter().take(1).copied().collect();
+    let prefix_len = out.len();
+    let count = simple::intersect_first_k(set_a, set_b, k, &mut out);
+
+    count == expected.len() && out[prefix_len..] == *expected
+}
+
+// Checks predicates on a subset of `set` (every other element), with and
+// without an element missing from `set`.
+fn simple_subset_matches<T>(set: &[T]) -> bool
+where
+    T: simple::Element + std::ops::Add<Output = T> + From<u8>,
+{
+    let subset: Vec<T> = set.iter().step_by(2).copied().collect();
+    let mut extra = subset.clone();
+    if let Some(missing) = set.windows(2)
+        .find(|w| w[0] + T::from(1) < w[1])
+        .map(|w| w[0] + T::from(1))
+    {
+        let at = extra.partition_point(|&x| x < missing);
+        extra.insert(at, missing);
+    }
+
+    simple::is_subset(&subset, set) &&
+    simple::intersects(&subset, set) != subset.is_empty() &&
+    simple::is_subset(&extra, set) == (extra.len() == subset.len()) &&
+    simple::is_subset(&[], set)
+}
+
+fn limit_matches(
+    set_a: &[i32],
+    set_b: &[i32],
+    limit: u8,
+    block_len: u8,
+    intersect: Intersect2<[i32], LimitVisitor<VecWriter<i32>>>) -> bool
+{
+    let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+    let expected = &expected[..expected.len().min(limit as usize)];
+
+    let mut visitor = LimitVisitor::new(VecWriter::new(), limit as usize);
+    intersect::limit::limit_2set_blocks(
+        set_a, set_b, intersect, &mut visitor, block_len as usize + 1);
+    let saturated = visitor.remaining() == limit as usize - expected.len();
+    let actual: Vec<i32> = visitor.into_inner().into();
+
+    saturated && actual == expected
+}
+
+// Bounds are taken from the sets where possible so that ranges usually cut
+// them, rather than lying outside the generated values.
+fn range_matches(
+    set_a: &[i32],
+    set_b: &[i32],
+    start: i32,
+    end: i32,
+    intersect: Intersect2<[i32], VecWriter<i32>>) -> bool
+{
+    let pick = |set: &[i32], i: i32| set.get(i as usize % set.len().max(1)).copied().unwrap_or(i);
+    let range = pick(set_a, start)..pick(set_b, end);
+
+    let expected: Vec<i32> = intersect::run_2set(set_a, set_b, intersect::naive_merge)
+        .into_iter()
+        .filter(|value| range.contains(value))
+        .collect();
+
+    let mut writer = VecWriter::new();
+    intersect::intersect_range(set_a, set_b, range, intersect, &mut writer);
+    let actual: Vec<i32> = writer.into();
+
+    actual == expected
+}
+
+fn inplace_matches<T>(set_a: &[T], set_b: &[T], intersect: IntersectInplace<T>) -> bool
+where
+    T: Ord + Copy,
+{
+    let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+
+    let mut actual = set_a.to_vec();
+    intersect(&mut actual, set_b);
+
+    actual == expected
+}
+
+/// Checks an aligned kernel from every pair of start offsets within a 64-byte
+/// line, so the scalar prologue runs for each alignment of either set and the
+/// main loop runs with the sets misaligned differently.
+fn aligned_matches_merge(
+    sets: &SimilarSetPair<i32>,
+    intersect: Intersect2<[i32], VecWriter<i32>>) -> bool
+{
+    let offsets = |set: &[i32]| 0..16.min(set.len() + 1);
+    let (set_a, set_b) = (sets.0.as_slice(), sets.1.as_slice());
+
+    offsets(set_a).flat_map(|a| offsets(set_b).map(move |b| (a, b))).all(|(offset_a, offset_b)| {
+        let set_a = &set_a[offset_a..];
+        let set_b = &set_b[offset_b..];
+
+        let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+        let actual = intersect::run_2set(set_a, set_b, intersect);
+
+        actual == expected
+    })
+}
+
+// Trims up to a vector of the largest width from the end of each set, so
+// every tail length is covered, including sets shorter than a vector.
+fn tail_matches_merge(
+    set_a: &[i32],
+    set_b: &[i32],
+    intersect: Intersect2<[i32], VecWriter<i32>>) -> bool
+{
+    (0..16.min(set_a.len() + 1)).all(|trim| {
+        let set_a = &set_a[..set_a.len() - trim];
+        let set_b = &set_b[..set_b.len() - trim.min(set_b.len())];
+
+        let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+        let actual = intersect::run_2set(set_a, set_b, intersect);
+
+        actual == expected
+    })
+}
+
+// Prefixes of both sets up to twice the longest routed to the all-pairs
+// kernels, in steps not dividing any vector width.
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+fn tiny_prefixes<'a>(set_a: &'a [i32], set_b: &'a [i32])
+    -> impl Iterator<Item = (&'a [i32], &'a [i32])>
+{
+    let lens = |len: usize| (0..=len.min(2 * TINY_MAX_LEN)).step_by(5);
+    let lens_b: Vec<usize> = lens(set_b.len()).collect();
+    lens(set_a.len()).flat_map(move |len_a| lens_b.clone().into_iter()
+        .map(move |len_b| (&set_a[..len_a], &set_b[..len_b])))
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+fn tiny_matches_merge(
+    set_a: &[i32],
+    set_b: &[i32],
+    intersect: Intersect2<[i32], VecWriter<i32>>) -> bool
+{
+    tiny_prefixes(set_a, set_b).all(|(set_a, set_b)|
+        intersect::run_2set(set_a, set_b, intersect) ==
+        intersect::run_2set(set_a, set_b, intersect::naive_merge))
+}
+
+type EytzingerProbe<T> = fn(&[T], &EytzingerSet<T>, &mut VecWriter<T>);
+
+fn eytzinger_matches<T>(small: &[T], large: &[T]) -> bool
+where
+    T: IntElement<Lane = i32>,
+{
+    let expected = intersect::run_2set(small, large, intersect::naive_merge);
+    let layout = EytzingerSet::from_sorted(large);
+
+    let probes: &[EytzingerProbe<T>] = &[
+        intersect::eytzinger_probe,
+        #[cfg(all(feature = "simd", target_feature = "avx2"))]
+        intersect::eytzinger_probe_avx2,
+        #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+        intersect::eytzinger_probe_avx512,
+    ];
+    probes.iter().all(|probe| {
+        let mut writer = VecWriter::new();
+        probe(small, &layout, &mut writer);
+        let actual: Vec<T> = writer.into();
+        actual == expected
+    })
+}
+
+fn bsr_expand_matches(
+    set_a: &[u32],
+    set_b: &[u32],
+    intersect: for<'a> fn(BsrRef<'a>, BsrRef<'a>, &mut BsrExpandAdapter<VecWriter<u32>>)) -> bool
+{
+    let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+
+    let bsr_a = BsrVec::from_sorted(set_a);
+    let bsr_b = BsrVec::from_sorted(set_b);
+
+    let mut adapter = BsrExpandAdapter::new(VecWriter::<u32>::new());
+    intersect(bsr_a.bsr_ref(), bsr_b.bsr_ref(), &mut adapter);
+    let actual: Vec<u32> = adapter.into_inner().into();
+
+    actual == expected
+}
+
+fn hybrid_matches(
+    set_a: &[i32],
+    set_b: &[i32],
+    threshold: usize,
+    intersect: Intersect2<[i32], VecWriter<i32>>) -> bool
+{
+    let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+
+    let hybrid_a = HybridSet::from_sorted_with_threshold(set_a, threshold);
+    let hybrid_b = HybridSet::from_sorted_with_threshold(set_b, threshold);
+
+    let mut writer = VecWriter::new();
+    intersect::hybrid_intersect(&hybrid_a, &hybrid_b, intersect, &mut writer);
+    let actual: Vec<i32> = writer.into();
+
+    actual == expected
+}
+
+type VersionedIntersect = Intersect2<[i32], DeltaAdapter<i32, VecWriter<i32>>>;
+
+fn union_intersect_matches(
+    set_a: &[i32],
+    lists: &[SortedSet<i32>],
+    intersect: Intersect2<[i32], VecWriter<i32>>) -> bool
+{
+    let expected: Vec<i32> = set_a.iter()
+        .copied()
+        .filter(|item| lists.iter().any(|list| list.as_slice().binary_search(item).is_ok()))
+        .collect();
+
+    let mut visitor: VecWriter<i32> = VecWriter::new();
+    intersect::union_intersect(set_a, lists, intersect, &mut visitor);
+    Vec::from(visitor) == expected
+}
+
+// Whether the SIMD sortedness check of every width returns `expected`.
+fn simd_checks_match<T>(items: &[T], expected: Result<(), SortError>) -> bool
+where
+    T: IntElement<Lane = i32>,
+{
+    check_strictly_sorted_simd::<T, 4>(items) == expected &&
+    check_strictly_sorted_simd::<T, 8>(items) == expected &&
+    check_strictly_sorted_simd::<T, 16>(items) == expected
+}
+
+// A sorted multiset of the items folded into a small range, so most repeat.
+fn multiset(items: &[u16]) -> Vec<i32> {
+    let mut set: Vec<i32> = items.iter().map(|&item| (item % 64) as i32).collect();
+    set.sort_unstable();
+    set
+}
+
+fn multiset_reference(set_a: &[i32], set_b: &[i32]) -> Vec<i32> {
+    let counts = |set: &[i32]| set.iter().fold(BTreeMap::new(), |mut counts, &item| {
+        *counts.entry(item).or_insert(0) += 1;
+        counts
+    });
+    let counts_b = counts(set_b);
+    counts(set_a).into_iter()
+        .flat_map(|(item, count_a)| {
+            let count = counts_b.get(&item).map_or(0, |&count_b: &usize| count_a.min(count_b));
+            std::iter::repeat_n(item, count)
+        })
+        .collect()
+}
+
+// `set` with the runs `(start, len)` added, so bitmaps have words of ones.
+fn clustered(set: &[u16], runs: &[(u16, u8)]) -> Vec<u32> {
+    let runs = runs.iter()
+        .flat_map(|&(start, len)| start as u32..start as u32 + len as u32 * 4);
+    let mut result: Vec<u32> = set.iter().map(|&i| i as u32).chain(runs).collect();
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+fn versioned_matches(
+    set_a: &[i32],
+    set_b: &[i32],
+    updates_a: Vec<u16>,
+    updates_b: Vec<u16>,
+    first: VersionedIntersect,
+    second: VersionedIntersect) -> bool
+{
+    // Toggle elements of both sets so inserts and deletes overlap the bases.
+    let pool: Vec<i32> = set_a.iter().chain(set_b).copied().collect();
+    let mut versioned_a = VersionedSet::new(set_a.to_vec());
+    let mut versioned_b = VersionedSet::new(set_b.to_vec());
+    let toggled = toggle_items(&mut versioned_a, updates_a, &pool, |_, _| true) &
+        toggle_items(&mut versioned_b, updates_b, &pool, |_, _| true);
+
+    let expected = intersect::run_2set(
+        &versioned_a.to_sorted_set(),
+        &versioned_b.to_sorted_set(),
+        intersect::naive_merge);
+
+    toggled && [first, second].into_iter().all(|intersect| {
+        let actual: Vec<i32> = intersect::versioned_intersect(
+            &versioned_a, &versioned_b, intersect, VecWriter::new()).into();
+        actual == expected
+    })
+}
+
+/// Inserts or removes an element for each update, checking the returned
+/// presence with `check`. Updates pick from `pool` or are used as elements.
+fn toggle_items(
+    set: &mut VersionedSet<i32>,
+    updates: Vec<u16>,
+    pool: &[i32],
+    mut check: impl FnMut(i32, bool) -> bool) -> bool
+{
+    updates.into_iter().all(|update| {
+        let value = if pool.is_empty() || update % 3 == 0 {
+            update as i32
+        }
+        else {
+            pool[update as usize % pool.len()]
+        };
+        let present = set.contains(value);
+        let changed = if present { set.remove(value) } else { set.insert(value) };
+        changed && check(value, present)
+    })
+}
+
+fn widen(set: &[i16]) -> Vec<i32> {
+    set.iter().map(|&item| item as i32).collect()
+}
+
+fn decode_intersect_correct<S: BlockSet + Set<i32>>(
+    set_a: &[i32],
+    set_b: &[i32],
+    intersect: Intersect2<[i32], VecWriter<i32>>) -> bool
+{
+    let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+
+    let encoded_a = S::from_sorted(set_a);
+    let encoded_b = S::from_sorted(set_b);
+
+    let mut writer = VecWriter::new();
+    intersect::compressed::decode_intersect(&encoded_a, &encoded_b, intersect, &mut writer);
+    let forward: Vec<i32> = writer.into();
+
+    let mut writer = VecWriter::new();
+    intersect::compressed::decode_intersect(&encoded_b, &encoded_a, intersect, &mut writer);
+    let backward: Vec<i32> = writer.into();
+
+    forward == expected && backward == expected
+}
+
+fn svb_probe_matches(small: &[i32], large: &[i32]) -> bool {
+    let expected = intersect::run_2set(small, large, intersect::naive_merge);
+
+    let mut writer = VecWriter::new();
+    intersect::svb_probe(small, &SvbSet::from_sorted(large), &mut writer);
+    let actual: Vec<i32> = writer.into();
+
+    actual == expected
+}
+
+// Repeats each item between one and three times.
+fn repeat_items(set: &[i32]) -> Vec<i32> {
+    set.iter()
+        .flat_map(|&item| std::iter::repeat_n(item, item.rem_euclid(3) as usize + 1))
+        .collect()
+}
+
+// Resumes `cursor` `limit` results at a time, checking each call visits
+// exactly `limit` results until the last.
+fn cursor_correct(
+    mut cursor: IntersectCursor<i32>,
+    limit: usize,
+    set_a: &[i32],
+    set_b: &[i32]) -> bool
+{
+    let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+
+    let mut writer: VecWriter<i32> = VecWriter::new();
+    loop {
+        let count = cursor.resume(limit, &mut writer);
+        if count < limit {
+            break;
+        }
+        if writer.as_ref().len() > expected.len() {
+            return false;
+        }
+    }
+    let actual: Vec<i32> = writer.into();
+
+    actual == expected && cursor.is_done() && cursor.finish(&mut Counter::new()) == 0
+}
+
+fn run_unsafe_writer<T>(
+    set_a: &[T],
+    set_b: &[T],
+    intersect: Intersect2<[T], UnsafeWriter<T>>) -> Vec<T>
+{
+    let mut writer: UnsafeWriter<T> = UnsafeWriter::with_capacity(set_a.len().min(set_b.len()));
+    intersect(set_a, set_b, &mut writer);
+    writer.into()
+}
+
+#[cfg(feature = "simd")]
+fn run_reserve_writer(
+    set_a: &[i32],
+    set_b: &[i32],
+    intersect: Intersect2<[i32], ReserveWriter<i32>>) -> Vec<i32>
+{
+    let mut writer = ReserveWriter::new();
+    intersect(set_a, set_b, &mut writer);
+    writer.into()
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+fn run_lookup_writer(
+    set_a: &[i32],
+    set_b: &[i32],
+    intersect: Intersect2<[i32], UnsafeLookupWriter<i32>>) -> Vec<i32>
+{
+    let mut writer = UnsafeLookupWriter::with_capacity(set_a.len().min(set_b.len()));
+    intersect(set_a, set_b, &mut writer);
+    writer.into()
+}
+
+fn hinted_writer_matches(
+    set_a: &[i32],
+    set_b: &[i32],
+    intersect: Intersect2<[i32], HintedWriter<i32>>,
+    expected: &[i32]) -> bool
+{
+    let bound = set_a.len().min(set_b.len());
+    [None, Some(bound / 4), Some(bound)].into_iter().all(|hint|
+        intersect::run_2set_hinted(set_a, set_b, intersect, hint) == expected)
+}
+
+/// Checks the results both through the mapping and read back from the file.
+fn mmap_writer_matches(
+    set_a: &[i32],
+    set_b: &[i32],
+    intersect: Intersect2<[i32], MmapWriter>,
+    expected: &[i32]) -> bool
+{
+    static FILES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!("setops-mmap-{}-{}.bin",
+        std::process::id(), FILES.fetch_add(1, std::sync::atomic::Ordering::Relaxed)));
+
+    let mut writer = MmapWriter::create(&path).unwrap();
+    intersect(set_a, set_b, &mut writer);
+    let mapped = writer.as_ref() == expected;
+    let (_, len) = writer.finish().unwrap();
+    let read = MmapWriter::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    mapped && len == expected.len() && read == expected
+}
+
+fn run_streaming_writer(
+    set_a: &[i32],
+    set_b: &[i32],
+    intersect: Intersect2<[i32], StreamingWriter>) -> Vec<i32>
+{
+    let mut writer = StreamingWriter::new();
+    intersect(set_a, set_b, &mut writer);
+    writer.into()
+}
+
+#[cfg(feature = "simd")]
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+fn fesia_hash_matches<H: IntegerHash>(small: &[i32], large: &[i32], hash_scale: HashScale) -> bool {
+    fesia_correct::<Fesia<H, i16, 8>>(small, large, hash_scale, SimilarSize, Sse) &&
+    fesia_correct::<Fesia<H, i32, 4>>(small, large, hash_scale, Skewed, Sse)
+}
+
+fn hash_batch_matches<H: IntegerHash>(items: &[i32]) -> bool {
+    let mut hashes = vec![0; items.len()];
+    hash_batch::<H>(items, &mut hashes);
+    items.iter().zip(&hashes).all(|(&item, &hash)| H::hash(item) == hash)
+}
+
+// Maps each element `x` to keys folding to residual `x`, with high halves
+// from `first` to `x mod 3`, so sets built with a different `first` share
+// some of the keys of a shared residual.
+#[cfg(target_feature = "ssse3")]
+fn colliding_keys(set: &[i32], first: u32) -> Vec<i64> {
+    let mut keys: Vec<i64> = set.iter()
+        .flat_map(|&x| (first..=x.rem_euclid(3) as u32).map(move |high| {
+            let low = x as u32 ^ high.wrapping_mul(0x9E37_79B1);
+            ((high as u64) << 32 | low as u64) as i64
+        }))
+        .collect();
+    keys.sort_unstable();
+    keys
+}
+
+#[cfg(target_feature = "ssse3")]
+fn fesia64_matches(set_a: &[i64], set_b: &[i64], hash_scale: HashScale) -> bool {
+    let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+
+    let set1 = Fesia64Sse::from_sorted(set_a, hash_scale);
+    let set2 = Fesia64Sse::from_sorted(set_b, hash_scale);
+
+    let mut visitor: VecWriter<i64> = VecWriter::new();
+    set1.intersect::<SegmentIntersectSse>(&set2, &mut visitor);
+    let mut actual: Vec<i64> = visitor.into();
+    actual.sort();
+
+    let mut visitor: VecWriter<i64> = VecWriter::new();
+    set2.hash_intersect(&set1, &mut visitor);
+    let mut actual_hash: Vec<i64> = visitor.into();
+    actual_hash.sort();
+
+    actual == expected && actual_hash == expected &&
+    set1.to_sorted_set() == set_a &&
+    set_a.iter()
+        .filter(|&&key| (0..1 << 32).contains(&key))
+        .all(|&key| residual(key) == key as i32)
+}
+
+// The vectorised probe must agree with the scalar one, in both directions.
+fn fesia_hash_probe_matches<S>(small: &[i32], large: &[i32], hash_scale: HashScale) -> bool
+where
+    S: SetWithHashScale + FesiaIntersect
+{
+    let expected = intersect::run_2set(small, large, intersect::naive_merge);
+
+    let set_small = S::from_sorted(small, hash_scale);
+    let set_large = S::from_sorted(large, hash_scale);
+
+    let run = |set1: &S, set2: &S, scalar: bool| {
+        let mut visitor: VecWriter<i32> = VecWriter::new();
+        if scalar {
+            set1.hash_intersect_scalar(set2, &mut visitor);
+        }
+        else {
+            set1.hash_intersect(set2, &mut visitor);
+        }
+        let mut actual: Vec<i32> = visitor.into();
+        actual.sort();
+        actual
+    };
+
+    run(&set_small, &set_large, false) == expected &&
+    run(&set_large, &set_small, false) == expected &&
+    run(&set_small, &set_large, true) == expected
+}
+
+fn fesia_maybe_intersects_matches<S>(small: &[i32], large: &[i32], hash_scale: HashScale) -> bool
+where
+    S: SetWithHashScale + FesiaIntersect
+{
+    let intersects = !intersect::run_2set(small, large, intersect::naive_merge).is_empty();
+
+    let set_small = S::from_sorted(small, hash_scale);
+    let set_large = S::from_sorted(large, hash_scale);
+    let empty = S::from_sorted(&[], hash_scale);
+
+    (!intersects || set_small.maybe_intersects(&set_large)) &&
+    set_small.maybe_intersects(&set_large) == set_large.maybe_intersects(&set_small) &&
+    !set_large.maybe_intersects(&empty)
+}
+
+// Builds `set_a` by inserting half its elements, and inserting then removing
+// those of `set_b`, which leaves both slack and sentinels in the layout.
+#[cfg(target_feature = "ssse3")]
+fn fesia_updates_match<S>(set_a: &[i32], set_b: &[i32], hash_scale: HashScale) -> bool
+where
+    S: SetWithHashScale + FesiaIntersect + FesiaUpdate
+{
+    let expected = intersect::run_2set(set_a, set_b, intersect::naive_merge);
+    let extra: Vec<i32> = set_b.iter().copied()
+        .filter(|item| set_a.binary_search(item).is_err())
+        .collect();
+
+    let initial: Vec<i32> = set_a.iter().copied().step_by(2).collect();
+    let mut set1 = S::from_sorted(&initial, hash_scale);
+    let set2 = S::from_sorted(set_b, hash_scale);
+
+    let mut updated = set_a.iter().skip(1).step_by(2).all(|&item| set1.insert(item)) &&
+        initial.iter().all(|&item| !set1.insert(item)) &&
+        extra.iter().all(|&item| set1.insert(item)) &&
+        extra.iter().all(|&item| set1.remove(item)) &&
+        extra.iter().all(|&item| !set1.remove(item));
+
+    let matches = |set1: &S| {
+        let mut visitor: VecWriter<i32> = VecWriter::new();
+        set1.intersect::<VecWriter<i32>, SegmentIntersectSse>(&set2, &mut visitor);
+        let mut actual: Vec<i32> = visitor.into();
+        actual.sort();
+
+        let mut visitor: VecWriter<i32> = VecWriter::new();
+        set1.hash_intersect(&set2, &mut visitor);
+        let mut actual_hash: Vec<i32> = visitor.into();
+        actual_hash.sort();
+
+        actual == expected && actual_hash == expected &&
+            set1.stats().elements == set_a.len()
+    };
+    updated &= matches(&set1);
+    set1.compact();
+    updated && matches(&set1)
+}
 
-#[cfg(feature = "simd")]
 fn fesia_correct<S>(
     set_a: &[i32],
     set_b: &[i32],
@@ -0,0 +1,179 @@
+// Arbitrary sets and algorithms for testing BSR intersections //
+use std::fmt;
+use quickcheck::{Arbitrary, Gen};
+use setops::{
+    intersect,
+    bsr::{BsrVec, BsrRef, BSR_WIDTH},
+    Set,
+};
+use crate::testlib::SortedSet;
+
+pub type BsrIntersect = for<'a> fn(BsrRef<'a>, BsrRef<'a>, &mut BsrVec);
+
+#[derive(Clone)]
+pub struct BsrIntersectFn(pub &'static str, pub BsrIntersect);
+
+impl fmt::Debug for BsrIntersectFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// Every BSR intersection algorithm enabled in this build.
+pub const BSR_INTERSECTS: &[BsrIntersectFn] = &[
+    BsrIntersectFn("branchless_merge_bsr", intersect::branchless_merge_bsr),
+    BsrIntersectFn("galloping_bsr", intersect::galloping_bsr),
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    BsrIntersectFn("shuffling_sse_bsr", intersect::shuffling_sse_bsr),
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    BsrIntersectFn("shuffling_sse_bsr_branch", intersect::shuffling_sse_bsr_branch),
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    BsrIntersectFn("broadcast_sse_bsr", intersect::broadcast_sse_bsr),
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    BsrIntersectFn("broadcast_sse_bsr_branch", intersect::broadcast_sse_bsr_branch),
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    BsrIntersectFn("qfilter_bsr", intersect::qfilter_bsr),
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    BsrIntersectFn("qfilter_bsr_branch", intersect::qfilter_bsr_branch),
+    #[cfg(all(feature = "simd", target_feature = "ssse3"))]
+    BsrIntersectFn("galloping_sse_bsr", intersect::galloping_sse_bsr),
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    BsrIntersectFn("shuffling_avx2_bsr", intersect::shuffling_avx2_bsr),
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    BsrIntersectFn("shuffling_avx2_bsr_branch", intersect::shuffling_avx2_bsr_branch),
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    BsrIntersectFn("broadcast_avx2_bsr", intersect::broadcast_avx2_bsr),
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    BsrIntersectFn("broadcast_avx2_bsr_branch", intersect::broadcast_avx2_bsr_branch),
+    #[cfg(all(feature = "simd", target_feature = "avx2"))]
+    BsrIntersectFn("galloping_avx2_bsr", intersect::galloping_avx2_bsr),
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    BsrIntersectFn("shuffling_avx512_bsr", intersect::shuffling_avx512_bsr),
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    BsrIntersectFn("shuffling_avx512_bsr_branch", intersect::shuffling_avx512_bsr_branch),
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    BsrIntersectFn("broadcast_avx512_bsr", intersect::broadcast_avx512_bsr),
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    BsrIntersectFn("broadcast_avx512_bsr_branch", intersect::broadcast_avx512_bsr_branch),
+    #[cfg(all(feature = "simd", target_feature = "avx512f"))]
+    BsrIntersectFn("galloping_avx512_bsr", intersect::galloping_avx512_bsr),
+];
+
+impl Arbitrary for BsrIntersectFn {
+    fn arbitrary(g: &mut Gen) -> Self {
+        g.choose(BSR_INTERSECTS).unwrap().clone()
+    }
+}
+
+// Arbitrary BSR sets //
+// Elements are generated as a mix of sparse values, runs of consecutive values
+// and words of random bits within a universe, so BSR states range from single
+// bits to full words, and small universes give sets sharing many bases.
+
+#[derive(Debug, Clone)]
+pub struct BsrSet(pub SortedSet<u32>);
+
+impl BsrSet {
+    pub fn to_bsr(&self) -> BsrVec {
+        BsrVec::from_sorted(self.0.as_slice())
+    }
+}
+
+impl Arbitrary for BsrSet {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let universe = arbitrary_universe(g);
+        BsrSet(arbitrary_elements(g, universe, 8).into())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BsrSetPair(pub BsrSet, pub BsrSet);
+
+impl Arbitrary for BsrSetPair {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let universe = arbitrary_universe(g);
+        let shared = arbitrary_elements(g, universe, 4);
+
+        let mut left = arbitrary_elements(g, universe, 8);
+        let mut right = arbitrary_elements(g, universe, 8);
+        left.extend(&shared);
+        right.extend(&shared);
+
+        BsrSetPair(BsrSet(left.into()), BsrSet(right.into()))
+    }
+}
+
+// A small set of sparse values against a large set made mostly of dense runs.
+#[derive(Debug, Clone)]
+pub struct SkewedBsrPair {
+    pub small: BsrSet,
+    pub large: BsrSet,
+}
+
+impl Arbitrary for SkewedBsrPair {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let universe = arbitrary_universe(g);
+
+        let mut large = Vec::new();
+        for _ in 0..usize::arbitrary(g) % 32 + 1 {
+            large.extend(arbitrary_run(g, universe));
+        }
+
+        let mut small: Vec<u32> = (0..usize::arbitrary(g) % 64 + 1)
+            .map(|_| u32::arbitrary(g) % universe)
+            .collect();
+        if !large.is_empty() {
+            // Include some elements of the large set.
+            for _ in 0..usize::arbitrary(g) % 16 {
+                small.push(large[usize::arbitrary(g) % large.len()]);
+            }
+        }
+
+        SkewedBsrPair {
+            small: BsrSet(small.into()),
+            large: BsrSet(large.into()),
+        }
+    }
+}
+
+fn arbitrary_universe(g: &mut Gen) -> u32 {
+    *g.choose(&[1 << 10, 1 << 16, 1 << 24, u32::MAX]).unwrap()
+}
+
+fn arbitrary_elements(g: &mut Gen, universe: u32, max_segments: usize) -> Vec<u32> {
+    let mut elements = Vec::new();
+    for _ in 0..usize::arbitrary(g) % (max_segments + 1) {
+        match u8::arbitrary(g) % 3 {
+            0 => elements.extend(
+                Vec::<u32>::arbitrary(g).into_iter().map(|v| v % universe)),
+            1 => elements.extend(arbitrary_run(g, universe)),
+            _ => elements.extend(arbitrary_words(g, universe)),
+        }
+    }
+    elements
+}
+
+// Consecutive values, possibly spanning several full BSR words.
+fn arbitrary_run(g: &mut Gen, universe: u32) -> impl Iterator<Item = u32> {
+    let start = u32::arbitrary(g) % universe;
+    let len = u32::arbitrary(g) % (8 * BSR_WIDTH) + 1;
+    start..start.saturating_add(len).min(universe)
+}
+
+// Consecutive BSR words with random states.
+fn arbitrary_words(g: &mut Gen, universe: u32) -> Vec<u32> {
+    let first_base = (u32::arbitrary(g) % universe) / BSR_WIDTH;
+    let word_count = u32::arbitrary(g) % 16 + 1;
+
+    let mut elements = Vec::new();
+    for base in first_base..first_base.saturating_add(word_count) {
+        let state = u32::arbitrary(g);
+        for bit in 0..BSR_WIDTH {
+            let value = base as u64 * BSR_WIDTH as u64 + bit as u64;
+            if state & (1 << bit) != 0 && value < universe as u64 {
+                elements.push(value as u32);
+            }
+        }
+    }
+    elements
+}
@@ -1,4 +1,5 @@
 pub mod properties;
+pub mod bsr;
 
 use quickcheck::Arbitrary;
 use setops::{
@@ -84,6 +85,9 @@ impl quickcheck::Arbitrary for DualIntersectFn {
     fn arbitrary(g: &mut quickcheck::Gen) -> Self {
         g.choose([
             DualIntersectFn("branchless_merge", intersect::branchless_merge),
+            DualIntersectFn("branchless_merge_unroll2", intersect::branchless_merge_unroll2),
+            DualIntersectFn("branchless_merge_unroll4", intersect::branchless_merge_unroll4),
+            DualIntersectFn("branchless_merge_cmov", intersect::branchless_merge_cmov),
             DualIntersectFn("galloping", intersect::galloping),
             DualIntersectFn("baezayates", intersect::baezayates),
             #[cfg(feature = "simd")]
@@ -152,6 +156,25 @@ where
     }
 }
 
+// Arbitrary pair of u32 sets with values on both sides of 2^31, i.e., which
+// would be out of order if reinterpreted as i32.
+#[derive(Debug, Clone)]
+pub struct SignBoundaryPair(pub SortedSet<u32>, pub SortedSet<u32>);
+
+impl quickcheck::Arbitrary for SignBoundaryPair {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        const BASE: u32 = (1 << 31) - (1 << 15);
+        let pair = SimilarSetPair::<u16>::arbitrary(g);
+        let shift = |set: SortedSet<u16>| -> SortedSet<u32> {
+            set.into_inner().into_iter()
+                .map(|v| BASE + v as u32)
+                .collect::<Vec<u32>>()
+                .into()
+        };
+        SignBoundaryPair(shift(pair.0), shift(pair.1))
+    }
+}
+
 fn vec_of_len<T: Arbitrary>(len: usize, g: &mut quickcheck::Gen) -> Vec<T> {
     let mut result: Vec<T> = Vec::with_capacity(len);
     while result.len() < len {
@@ -1,4 +1,8 @@
-use setops::{visitor::VecWriter, intersect};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use setops::{
+    visitor::{VecWriter, LimitVisitor, Visitor}, intersect::{self, partition::*}, estimate,
+    hybrid::{HybridSet, CHUNK_LEN},
+};
 
 
 // Sanity check
@@ -57,3 +61,262 @@ fn test_simd_galloping() {
 
     assert!(actual == expected);
 }
+
+#[test]
+fn test_tune_gallop_factor() {
+    let large: Vec<i32> = (0..1 << 16).collect();
+    let factors = [2, 4, 8, 16];
+
+    // Far-apart targets favour a faster-growing gallop.
+    let sparse: Vec<i32> = vec![1 << 15, (1 << 16) - 1];
+    let tuned = intersect::tune_gallop_factor(&[(&sparse, &large)], &factors);
+    assert!(tuned.is_some_and(|factor| factor > 2), "tuned {tuned:?}");
+
+    // Adjacent targets never gallop, so every factor ties and the first wins.
+    let dense: Vec<i32> = (0..64).collect();
+    assert_eq!(intersect::tune_gallop_factor(&[(&large, &dense)], &factors), Some(2));
+
+    assert_eq!(intersect::tune_gallop_factor::<i32>(&[], &[]), None);
+}
+
+#[test]
+fn test_estimate_intersection_card() {
+    // |a| = |b| = 100000, |a ∩ b| = 50000, |a ∪ b| = 150000
+    let a: Vec<u32> = (0..100000).map(|i| i * 2).collect();
+    let b: Vec<u32> = (0..100000).map(|i| i * 2 + 100000).collect();
+
+    let hll = estimate::intersection_card(&a, &b, 14);
+    assert!((hll - 50000.0).abs() < 150000.0 * 0.03, "hll estimate {hll}");
+
+    let sampled = estimate::sample_intersection_card(&a, &b, 1000);
+    assert!((sampled - 50000.0).abs() < 50000.0 * 0.05, "sampled estimate {sampled}");
+}
+
+#[test]
+fn test_partition_intersect() {
+    let set_a = [-70, -3, 0, 5, 6, 40, 41, 300, 301, 1 << 20];
+    let set_b = [-70, -4, 5, 7, 41, 299, 301, 1 << 20, 1 << 21];
+
+    let partitioned_a = PartitionedSet::from_sorted(&set_a, 4);
+    let partitioned_b = PartitionedSet::from_sorted(&set_b, 4);
+    assert_eq!(partitioned_a.as_slice(), &set_a);
+
+    let mut writer = VecWriter::new();
+    partition_intersect(&partitioned_a, &partitioned_b, intersect::naive_merge, &mut writer);
+    let result: Vec<i32> = writer.into();
+    assert_eq!(result, [-70, 5, 41, 301, 1 << 20]);
+
+    assert_eq!(PartitionedSet::shift_for(&[], 32), 0);
+    assert_eq!(PartitionedSet::shift_for(&[0, 1023], 1), 9);
+    assert_eq!(PartitionedSet::shift_for(&[i32::MIN, i32::MAX], 32), 31);
+
+    // A small set spanning every i32 needs no table entry per bucket of the
+    // dense larger set.
+    let large: Vec<i32> = (0..1000).collect();
+    let (small, large) = PartitionedSet::pair(&[i32::MIN, 5, i32::MAX], &large, 32);
+    assert!(small.bucket_count() <= 4);
+    let mut writer: VecWriter<i32> = VecWriter::new();
+    partition_intersect(&small, &large, intersect::naive_merge, &mut writer);
+    assert_eq!(writer.as_ref(), [5]);
+}
+
+#[test]
+fn test_hybrid_intersect() {
+    // Chunk -1 is a bitmap in a only, chunk 0 is a bitmap in both and chunk 2
+    // is an array in both.
+    let mut set_a: Vec<i32> = (-300..-100).collect();
+    set_a.extend((0..600).map(|i| i * 3));
+    set_a.extend([2 * CHUNK_LEN as i32 + 1, 2 * CHUNK_LEN as i32 + 7]);
+
+    let mut set_b: Vec<i32> = (-150..-140).collect();
+    set_b.extend((0..400).map(|i| i * 4));
+    set_b.extend([2 * CHUNK_LEN as i32 + 7, 3 * CHUNK_LEN as i32]);
+
+    let hybrid_a = HybridSet::from_sorted_with_threshold(&set_a, 100);
+    let hybrid_b = HybridSet::from_sorted_with_threshold(&set_b, 100);
+    assert_eq!(hybrid_a.bitmap_count(), 2);
+    assert_eq!(hybrid_b.bitmap_count(), 1);
+
+    let expected = intersect::run_2set(&set_a, &set_b, intersect::naive_merge);
+    let mut writer = VecWriter::new();
+    intersect::hybrid_intersect(&hybrid_a, &hybrid_b, intersect::branchless_merge, &mut writer);
+    let result: Vec<i32> = writer.into();
+    assert_eq!(result, expected);
+    assert_eq!(result.len(), 10 + 134 + 1);
+}
+
+static LIMIT_BLOCKS: AtomicUsize = AtomicUsize::new(0);
+
+fn counting_merge<V: Visitor<i32>>(set_a: &[i32], set_b: &[i32], visitor: &mut V) {
+    LIMIT_BLOCKS.fetch_add(1, Ordering::Relaxed);
+    intersect::branchless_merge(set_a, set_b, visitor);
+}
+
+#[test]
+fn test_limit_stops_early() {
+    let set_a: Vec<i32> = (0..1000).collect();
+    let set_b: Vec<i32> = (0..2000).map(|i| i * 2).collect();
+
+    let mut visitor = LimitVisitor::new(VecWriter::new(), 25);
+    intersect::limit::limit_2set_blocks(&set_a, &set_b, counting_merge, &mut visitor, 10);
+
+    // Each block of a holds 5 even elements.
+    assert_eq!(LIMIT_BLOCKS.load(Ordering::Relaxed), 5);
+    assert_eq!(visitor.remaining(), 0);
+    let result: Vec<i32> = visitor.into_inner().into();
+    assert_eq!(result, (0..25).map(|i| i * 2).collect::<Vec<_>>());
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+#[test]
+fn test_by_name() {
+    let algorithm = intersect::by_name("shuffling_sse").unwrap();
+    assert_eq!(algorithm.name(), "shuffling_sse");
+    assert_eq!(algorithm.intersect(&[1, 2, 3, 5, 8], &[2, 3, 4, 8]), [2, 3, 8]);
+    assert_eq!(algorithm.count(&[1, 2, 3, 5, 8], &[2, 3, 4, 8]), 3);
+
+    assert!(intersect::by_name("shuffling_sse_bsr").is_none());
+    assert!(intersect::by_name("unknown").is_none());
+    assert!(intersect::dynamic::names().contains(&"galloping"));
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+#[test]
+fn test_algo_enums() {
+    use intersect::{TwoSetAlgo, KSetAlgo};
+
+    assert_eq!(TwoSetAlgo::from_name("shuffling_sse"), Some(TwoSetAlgo::ShufflingSse));
+    assert_eq!(TwoSetAlgo::Galloping.to_string(), "galloping");
+    assert_eq!(TwoSetAlgo::ALL.len(), intersect::dynamic::names().len());
+    assert!(TwoSetAlgo::from_name("unknown").is_none());
+    assert!(TwoSetAlgo::Galloping.counts_comparisons());
+    assert!(!TwoSetAlgo::Qfilter.counts_comparisons());
+
+    let mut writer: VecWriter<i32> = VecWriter::new();
+    TwoSetAlgo::BranchlessMerge.run(&[1, 2, 3, 5, 8], &[2, 3, 4, 8], &mut writer);
+    assert_eq!(writer.as_ref(), [2, 3, 8]);
+
+    let sets = vec![vec![1, 2, 3, 5, 8], vec![2, 3, 4, 8], vec![0, 3, 8]];
+    for &algo in KSetAlgo::ALL {
+        let mut writer: VecWriter<i32> = VecWriter::new();
+        algo.run(&sets, &mut writer);
+        assert_eq!(writer.as_ref(), [3, 8], "{algo}");
+    }
+    assert_eq!(KSetAlgo::from_name("svs"), Some(KSetAlgo::Svs));
+}
+
+#[test]
+fn test_union_intersect_blocks() {
+    use intersect::union::UNION_BLOCK_LEN;
+
+    // Lists overlapping each other and spanning several blocks of the union.
+    let len = 3 * UNION_BLOCK_LEN as i32;
+    let lists: Vec<Vec<i32>> = (2..5).map(|step| (0..len).map(|i| i * step).collect()).collect();
+    let set_a: Vec<i32> = (0..4 * len).filter(|i| i % 7 != 0).collect();
+
+    let expected: Vec<i32> = set_a.iter()
+        .copied()
+        .filter(|i| lists.iter().any(|list| list.binary_search(i).is_ok()))
+        .collect();
+
+    let mut writer: VecWriter<i32> = VecWriter::new();
+    intersect::union_intersect(&set_a, &lists, intersect::galloping, &mut writer);
+    assert_eq!(writer.as_ref(), expected);
+
+    let mut writer: VecWriter<i32> = VecWriter::new();
+    intersect::union_intersect(&set_a, &lists[..0], intersect::galloping, &mut writer);
+    assert!(writer.as_ref().is_empty());
+}
+
+#[test]
+fn test_fesia_hash_size_bounds() {
+    use intersect::fesia::{hash_size, FesiaError, MAX_HASH_SIZE};
+
+    assert_eq!(hash_size(0, 1.0), Ok(16 * 32));
+    assert_eq!(hash_size(1000, 1.0), Ok(1024));
+    assert_eq!(hash_size(1 << 28, 16.0), Ok(MAX_HASH_SIZE as usize));
+    assert_eq!(hash_size((1 << 28) + 1, 16.0),
+        Err(FesiaError::HashSizeTooLarge { len: (1 << 28) + 1, hash_scale: 16.0 }));
+    assert_eq!(hash_size(1 << 28, 1e12),
+        Err(FesiaError::HashSizeTooLarge { len: 1 << 28, hash_scale: 1e12 }));
+
+    assert_eq!(hash_size(u32::MAX as usize, 0.5), Ok(1 << 31));
+    assert_eq!(hash_size(u32::MAX as usize + 1, 0.5),
+        Err(FesiaError::TooManyElements(u32::MAX as usize + 1)));
+
+    for hash_scale in [-1.0, f64::INFINITY] {
+        assert_eq!(hash_size(10, hash_scale), Err(FesiaError::InvalidHashScale(hash_scale)));
+    }
+    assert!(matches!(hash_size(10, f64::NAN), Err(FesiaError::InvalidHashScale(_))));
+}
+
+#[cfg(all(feature = "simd", target_feature = "ssse3"))]
+#[test]
+fn test_fesia_try_from_sorted() {
+    use intersect::fesia::{Fesia32Sse, FesiaError, SetWithHashScale};
+
+    let set: Vec<i32> = (0..1000).map(|i| i * 3).collect();
+    assert!(Fesia32Sse::try_from_sorted(&set, 4.0).is_ok());
+
+    let error = Fesia32Sse::try_from_sorted(&set, f64::MAX).err().unwrap();
+    assert_eq!(error, FesiaError::HashSizeTooLarge { len: 1000, hash_scale: f64::MAX });
+    assert!(error.to_string().contains("exceeds the maximum"));
+}
+
+#[test]
+fn test_try_from_sorted() {
+    use setops::{Set, bsr::BsrVec, util::SortError};
+
+    assert!(BsrVec::try_from_sorted(&[1, 5, 9]).is_ok());
+
+    let error = BsrVec::try_from_sorted(&[1, 9, 5]).unwrap_err();
+    assert_eq!(error, SortError::Unsorted { index: 1 });
+    assert_eq!(error.to_string(), "set is not sorted: element 1 is greater than element 2");
+
+    let error = Vec::<i32>::try_from_sorted(&[1, 1, 5]).unwrap_err();
+    assert_eq!(error, SortError::Duplicate { index: 0 });
+    assert_eq!(error.to_string(), "set has duplicates: element 0 equals element 1");
+
+    assert_eq!(BsrVec::from_unsorted(&[9, 1, 5, 1]), BsrVec::from_sorted(&[1, 5, 9]));
+}
+
+// Prepared sets are shared between query threads.
+#[test]
+fn test_prepared_sets_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<intersect::fesia::Fesia8Sse>();
+    assert_send_sync::<intersect::fesia::Fesia32Avx512>();
+    assert_send_sync::<setops::bsr::BsrVec>();
+    assert_send_sync::<HybridSet>();
+    assert_send_sync::<PartitionedSet>();
+    assert_send_sync::<setops::encoding::Bp128Set>();
+    assert_send_sync::<setops::encoding::SvbSet>();
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn test_arrow_intersect() {
+    use arrow_array::{UInt32Array, Int32Array};
+    use setops::{arrow::*, bsr::BsrVec};
+
+    let array_a = UInt32Array::from(vec![1, 3, 5, 8, 9, 10, 14]);
+    let array_b = UInt32Array::from(vec![1, 2, 3, 9, 10, 11]);
+
+    let result = intersect(&array_a, &array_b, intersect::branchless_merge).unwrap();
+    assert_eq!(result.values().as_ref(), [1, 3, 9, 10]);
+
+    let array_c = UInt32Array::from(vec![3, 10]);
+    let result = intersect_k(&[&array_a, &array_b, &array_c], intersect::svs).unwrap();
+    assert_eq!(result.values().as_ref(), [3, 10]);
+
+    // Values are borrowed from the array's buffer.
+    assert_eq!(as_set(&array_a).unwrap().as_ptr(), array_a.values().as_ptr());
+    let bsr: BsrVec = to_set(&array_a).unwrap();
+    assert_eq!(bsr.to_sorted_set(), array_a.values().to_vec());
+
+    let unsorted = Int32Array::from(vec![1, 3, 3]);
+    assert_eq!(as_set(&unsorted), Err(ArrowSetError::Unsorted { index: 2 }));
+    let nulls = Int32Array::from(vec![Some(1), None]);
+    assert_eq!(as_set(&nulls), Err(ArrowSetError::Nulls(1)));
+}